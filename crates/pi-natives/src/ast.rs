@@ -5,32 +5,69 @@ use std::{
 	path::{Path, PathBuf},
 };
 
-use ast_grep_core::{
-	Language, MatchStrictness, matcher::Pattern, source::Edit, tree_sitter::LanguageExt,
-};
+use ast_grep_core::{Language, MatchStrictness, matcher::Pattern, source::Edit};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
-use crate::{fs_cache, glob_util, language::SupportLang, task};
+use crate::{
+	ast_cache,
+	ast_rule::{self, Rule},
+	changeset, diff, fs_cache, git, glob_util,
+	grep::ContextLine,
+	injection, journal,
+	language::SupportLang,
+	prof::{self, CallTrace, TraceSpan},
+	readonly, task,
+};
 
-const DEFAULT_FIND_LIMIT: u32 = 50;
+pub(crate) const DEFAULT_FIND_LIMIT: u32 = 50;
 
 #[napi(object)]
 pub struct AstFindOptions<'env> {
-	pub patterns:     Option<Vec<String>>,
-	pub lang:         Option<String>,
-	pub path:         Option<String>,
-	pub glob:         Option<String>,
-	pub selector:     Option<String>,
-	pub strictness:   Option<String>,
-	pub limit:        Option<u32>,
-	pub offset:       Option<u32>,
+	pub patterns:            Option<Vec<String>>,
+	/// ast-grep rule objects as JSON text (`kind`, `regex`, `inside`, `has`,
+	/// `all`/`any`/`not`, ...), searched alongside `patterns`.
+	pub rules:               Option<Vec<String>>,
+	pub lang:                Option<String>,
+	pub path:                Option<String>,
+	pub glob:                Option<String>,
+	/// Match `glob` case-sensitively; by default, matching ignores case unless
+	/// `glob` contains an uppercase letter.
+	#[napi(js_name = "globCaseSensitive")]
+	pub glob_case_sensitive: Option<bool>,
+	/// Search only files changed in git: "working", "staged", or
+	/// "branch:<ref>" (see `grep`).
+	#[napi(js_name = "changedOnly", ts_type = "\"working\" | \"staged\" | `branch:${string}`")]
+	pub changed_only:        Option<String>,
+	#[napi(js_name = "ignoreSources")]
+	pub ignore_sources:      Option<Vec<String>>,
+	#[napi(js_name = "ignoreFiles")]
+	pub ignore_files:        Option<Vec<String>>,
+	pub selector:            Option<String>,
+	pub strictness:          Option<String>,
+	pub limit:               Option<u32>,
+	pub offset:              Option<u32>,
 	#[napi(js_name = "includeMeta")]
-	pub include_meta: Option<bool>,
-	pub context:      Option<u32>,
-	pub signal:       Option<Unknown<'env>>,
+	pub include_meta:        Option<bool>,
+	/// Source lines to include before and after each match.
+	pub context:             Option<u32>,
+	/// Return matches grouped by file in `files`; `limit` and `offset` then
+	/// count files instead of matches.
+	#[napi(js_name = "groupByFile")]
+	pub group_by_file:       Option<bool>,
+	/// Also search embedded code: `<script>`/`<style>` blocks in HTML, Vue,
+	/// and Svelte files and fenced code blocks in Markdown. With `lang`, only
+	/// blocks in that language are searched.
+	pub injections:          Option<bool>,
+	/// Annotate each match with the last commit that touched its first line,
+	/// using one `git blame` per matched file.
+	pub blame:               Option<bool>,
+	/// Attach per-phase timings to the result as `trace`.
+	pub trace:               Option<bool>,
+	pub signal:              Option<Unknown<'env>>,
 	#[napi(js_name = "timeoutMs")]
-	pub timeout_ms:   Option<u32>,
+	pub timeout_ms:          Option<u32>,
+	pub scope:               Option<ClassInstance<'env, task::CancellationScope>>,
 }
 
 #[napi(object)]
@@ -51,6 +88,17 @@ pub struct AstFindMatch {
 	pub end_column:     u32,
 	#[napi(js_name = "metaVariables")]
 	pub meta_variables: Option<HashMap<String, String>>,
+	/// Language of the embedded block the match is in (`injections` only).
+	#[napi(js_name = "embeddedLang")]
+	pub embedded_lang:  Option<String>,
+	/// Last commit that touched the match's first line (`blame: true` only).
+	pub blame:          Option<git::BlameInfo>,
+	/// Lines before the match (`context` only).
+	#[napi(js_name = "contextBefore")]
+	pub context_before: Option<Vec<ContextLine>>,
+	/// Lines after the match (`context` only).
+	#[napi(js_name = "contextAfter")]
+	pub context_after:  Option<Vec<ContextLine>>,
 }
 
 #[napi(object)]
@@ -66,6 +114,19 @@ pub struct AstFindResult {
 	pub limit_reached:      bool,
 	#[napi(js_name = "parseErrors")]
 	pub parse_errors:       Option<Vec<String>>,
+	/// Matches grouped by file (`groupByFile` only; `matches` is then empty).
+	pub files:              Option<Vec<AstFileMatches>>,
+	/// Per-phase timings, when requested with `trace`.
+	pub trace:              Option<TraceSpan>,
+}
+
+/// Matches in one file (`groupByFile`).
+#[napi(object)]
+pub struct AstFileMatches {
+	pub path:    String,
+	/// Matches in the file.
+	pub count:   u32,
+	pub matches: Vec<AstFindMatch>,
 }
 
 #[napi(object)]
@@ -74,6 +135,14 @@ pub struct AstReplaceOptions<'env> {
 	pub lang:                Option<String>,
 	pub path:                Option<String>,
 	pub glob:                Option<String>,
+	/// Match `glob` case-sensitively (default: only when it contains an
+	/// uppercase letter).
+	#[napi(js_name = "globCaseSensitive")]
+	pub glob_case_sensitive: Option<bool>,
+	#[napi(js_name = "ignoreSources")]
+	pub ignore_sources:      Option<Vec<String>>,
+	#[napi(js_name = "ignoreFiles")]
+	pub ignore_files:        Option<Vec<String>>,
 	pub selector:            Option<String>,
 	pub strictness:          Option<String>,
 	#[napi(js_name = "dryRun")]
@@ -84,9 +153,13 @@ pub struct AstReplaceOptions<'env> {
 	pub max_files:           Option<u32>,
 	#[napi(js_name = "failOnParseError")]
 	pub fail_on_parse_error: Option<bool>,
+	/// Return a unified diff per touched file.
+	#[napi(js_name = "returnDiff")]
+	pub return_diff:         Option<bool>,
 	pub signal:              Option<Unknown<'env>>,
 	#[napi(js_name = "timeoutMs")]
 	pub timeout_ms:          Option<u32>,
+	pub scope:               Option<ClassInstance<'env, task::CancellationScope>>,
 }
 
 #[napi(object)]
@@ -132,6 +205,11 @@ pub struct AstReplaceResult {
 	pub limit_reached:      bool,
 	#[napi(js_name = "parseErrors")]
 	pub parse_errors:       Option<Vec<String>>,
+	/// Unified diff per touched file, in path order (with `returnDiff`).
+	pub diffs:              Option<Vec<diff::FileDiff>>,
+	/// Previewed changes for `applyChangeSet()` (dry runs only).
+	#[napi(js_name = "changeSet")]
+	pub change_set:         Option<changeset::ChangeSet>,
 }
 
 struct FileCandidate {
@@ -234,7 +312,7 @@ fn supported_lang_list() -> String {
 	keys.join(", ")
 }
 
-fn resolve_supported_lang(value: &str) -> Result<SupportLang> {
+pub(crate) fn resolve_supported_lang(value: &str) -> Result<SupportLang> {
 	let lower = value.to_ascii_lowercase();
 	LANG_ALIASES.get(lower.as_str()).copied().ok_or_else(|| {
 		Error::from_reason(format!(
@@ -244,7 +322,7 @@ fn resolve_supported_lang(value: &str) -> Result<SupportLang> {
 	})
 }
 
-fn resolve_language(lang: Option<&str>, file_path: &Path) -> Result<SupportLang> {
+pub(crate) fn resolve_language(lang: Option<&str>, file_path: &Path) -> Result<SupportLang> {
 	if let Some(lang) = lang.map(str::trim).filter(|lang| !lang.is_empty()) {
 		return resolve_supported_lang(lang);
 	}
@@ -260,7 +338,7 @@ fn resolve_language(lang: Option<&str>, file_path: &Path) -> Result<SupportLang>
 /// When `lang` is explicitly provided, all files are considered candidates
 /// (the user chose to treat them as that language). When `lang` is None,
 /// only files with recognizable code extensions are included.
-fn is_supported_file(file_path: &Path, explicit_lang: Option<&str>) -> bool {
+pub(crate) fn is_supported_file(file_path: &Path, explicit_lang: Option<&str>) -> bool {
 	if explicit_lang.is_some() {
 		return true;
 	}
@@ -354,9 +432,20 @@ fn collect_from_entries(
 	Ok(files)
 }
 
+fn scan_policy(
+	ignore_sources: Option<Vec<String>>,
+	ignore_files: Option<Vec<String>>,
+) -> Result<fs_cache::ScanPolicy> {
+	Ok(fs_cache::ScanPolicy::new(true, true)
+		.with_ignore_sources(ignore_sources)?
+		.with_ignore_files(ignore_files))
+}
+
 fn collect_candidates(
 	path: Option<String>,
 	glob: Option<&str>,
+	glob_case_sensitive: Option<bool>,
+	policy: &fs_cache::ScanPolicy,
 	ct: &task::CancelToken,
 ) -> Result<Vec<FileCandidate>> {
 	let search_path = normalize_search_path(path)?;
@@ -379,9 +468,9 @@ fn collect_candidates(
 		)));
 	}
 
-	let glob_set = glob_util::try_compile_glob(glob, false)?;
+	let glob_set = glob_util::try_compile_glob(glob, false, glob_case_sensitive)?;
 	let mentions_node_modules = glob.is_some_and(|value| value.contains("node_modules"));
-	let scan = fs_cache::get_or_scan(&search_path, true, true, ct)?;
+	let scan = fs_cache::get_or_scan(&search_path, policy, ct)?;
 	let mut files = collect_from_entries(
 		&search_path,
 		&scan.entries,
@@ -391,7 +480,7 @@ fn collect_candidates(
 	)?;
 
 	if files.is_empty() && scan.cache_age_ms >= fs_cache::empty_recheck_ms() {
-		let fresh = fs_cache::force_rescan(&search_path, true, true, true, ct)?;
+		let fresh = fs_cache::force_rescan(&search_path, policy, true, ct)?;
 		files =
 			collect_from_entries(&search_path, &fresh, glob_set.as_ref(), mentions_node_modules, ct)?;
 	}
@@ -400,7 +489,28 @@ fn collect_candidates(
 	Ok(files)
 }
 
-fn compile_pattern(
+/// Keep only candidates git reports as changed under `changed_only`.
+fn retain_changed(
+	candidates: &mut Vec<FileCandidate>,
+	changed_only: Option<&str>,
+	ct: &task::CancelToken,
+) -> Result<()> {
+	let Some(scope) = changed_only else {
+		return Ok(());
+	};
+	let scope = git::ChangedScope::parse(scope)?;
+	let Some(dir) = candidates
+		.first()
+		.and_then(|candidate| candidate.absolute_path.parent())
+	else {
+		return Ok(());
+	};
+	let changed = git::changed_files(dir, &scope, ct)?;
+	candidates.retain(|candidate| changed.contains(&candidate.absolute_path));
+	Ok(())
+}
+
+pub(crate) fn compile_pattern(
 	pattern: &str,
 	selector: Option<&str>,
 	strictness: &MatchStrictness,
@@ -445,7 +555,7 @@ fn apply_edits(content: &str, edits: &[Edit<String>]) -> Result<String> {
 	Ok(output)
 }
 
-fn normalize_pattern_list(patterns: Option<Vec<String>>) -> Result<Vec<String>> {
+fn normalize_pattern_list(patterns: Option<Vec<String>>) -> Vec<String> {
 	let mut normalized = Vec::new();
 	let mut seen = BTreeSet::new();
 	for raw in patterns.unwrap_or_default() {
@@ -457,12 +567,22 @@ fn normalize_pattern_list(patterns: Option<Vec<String>>) -> Result<Vec<String>>
 			normalized.push(pattern.to_string());
 		}
 	}
-	if normalized.is_empty() {
-		return Err(Error::from_reason(
-			"`patterns` is required and must include at least one non-empty pattern".to_string(),
-		));
-	}
-	Ok(normalized)
+	normalized
+}
+
+/// Parse `rules`, labelling each `rules[i]` for error messages.
+fn parse_rule_list(rules: Option<Vec<String>>) -> Result<Vec<(String, serde_json::Value)>> {
+	rules
+		.unwrap_or_default()
+		.iter()
+		.enumerate()
+		.map(|(index, rule)| {
+			let label = format!("rules[{index}]");
+			ast_rule::parse_rule(rule)
+				.map(|value| (label.clone(), value))
+				.map_err(|err| Error::from_reason(format!("{label}: {err}")))
+		})
+		.collect()
 }
 
 fn normalize_rewrite_map(
@@ -488,7 +608,9 @@ fn normalize_rewrite_map(
 }
 struct CompiledFindPattern {
 	pattern:                String,
-	compiled_by_lang:       HashMap<String, Pattern>,
+	/// Rule source for `rules` entries; `None` for `patterns`.
+	rule:                   Option<serde_json::Value>,
+	compiled_by_lang:       HashMap<String, Rule>,
 	compile_errors_by_lang: HashMap<String, String>,
 }
 
@@ -531,87 +653,271 @@ fn resolve_candidates_for_find(
 	Ok((resolved, languages))
 }
 
+/// Up to `context` lines before line `start` and after line `end` (0-indexed).
+fn context_lines(
+	lines: &[&str],
+	start: usize,
+	end: usize,
+	context: usize,
+) -> (Vec<ContextLine>, Vec<ContextLine>) {
+	let line = |index: usize| ContextLine {
+		line_number: to_u32(index + 1),
+		line:        lines[index].to_string(),
+	};
+	let before = (start.saturating_sub(context)..start.min(lines.len()))
+		.map(line)
+		.collect();
+	let after = (end + 1
+		..lines
+			.len()
+			.min(end.saturating_add(context).saturating_add(1)))
+		.map(line)
+		.collect();
+	(before, after)
+}
+
 fn compile_find_patterns(
 	patterns: &[String],
+	rules: &[(String, serde_json::Value)],
 	languages: &HashMap<String, SupportLang>,
 	selector: Option<&str>,
 	strictness: &MatchStrictness,
 	ct: &task::CancelToken,
 ) -> Result<Vec<CompiledFindPattern>> {
-	let mut compiled = Vec::with_capacity(patterns.len());
+	let mut compiled = Vec::with_capacity(patterns.len() + rules.len());
+	let sources = patterns
+		.iter()
+		.map(|pattern| (pattern, None))
+		.chain(rules.iter().map(|(label, rule)| (label, Some(rule))));
 
-	for pattern in patterns {
+	for (pattern, rule) in sources {
 		ct.heartbeat()?;
-		let mut compiled_by_lang = HashMap::with_capacity(languages.len());
-		let mut compile_errors_by_lang = HashMap::new();
-
-		for (lang_key, &language) in languages {
+		let mut find_pattern = CompiledFindPattern {
+			pattern:                pattern.clone(),
+			rule:                   rule.cloned(),
+			compiled_by_lang:       HashMap::with_capacity(languages.len()),
+			compile_errors_by_lang: HashMap::new(),
+		};
+		for &language in languages.values() {
 			ct.heartbeat()?;
-			match compile_pattern(pattern, selector, strictness, language) {
-				Ok(compiled_pattern) => {
-					compiled_by_lang.insert(lang_key.clone(), compiled_pattern);
-				},
-				Err(err) => {
-					compile_errors_by_lang.insert(lang_key.clone(), err.to_string());
-				},
-			}
+			find_pattern.compile_for(language, selector, strictness);
 		}
-
-		compiled.push(CompiledFindPattern {
-			pattern: pattern.clone(),
-			compiled_by_lang,
-			compile_errors_by_lang,
-		});
+		compiled.push(find_pattern);
 	}
 
 	Ok(compiled)
 }
+
+impl CompiledFindPattern {
+	/// Compile for `language` unless already attempted; embedded code can
+	/// need languages no candidate file resolved to.
+	fn compile_for(
+		&mut self,
+		language: SupportLang,
+		selector: Option<&str>,
+		strictness: &MatchStrictness,
+	) {
+		let lang_key = language.canonical_name();
+		if self.compiled_by_lang.contains_key(lang_key)
+			|| self.compile_errors_by_lang.contains_key(lang_key)
+		{
+			return;
+		}
+		let result = match &self.rule {
+			Some(rule) => {
+				ast_rule::compile_rule(rule, language, strictness).map_err(Error::from_reason)
+			},
+			None => compile_pattern(&self.pattern, selector, strictness, language).map(Rule::Pattern),
+		};
+		match result {
+			Ok(compiled_pattern) => {
+				self
+					.compiled_by_lang
+					.insert(lang_key.to_string(), compiled_pattern);
+			},
+			Err(err) => {
+				self
+					.compile_errors_by_lang
+					.insert(lang_key.to_string(), err.to_string());
+			},
+		}
+	}
+}
+
+/// A syntax tree to search in a candidate file: the whole file, or an
+/// embedded block starting at `region`.
+struct SearchTree {
+	parsed: std::sync::Arc<ast_cache::ParsedFile>,
+	lang:   SupportLang,
+	region: Option<injection::Region>,
+}
+
+/// Every match of `rule` in a whole parsed file, reported under `path`
+/// without meta variables, context, or blame.
+pub(crate) fn tree_matches(
+	parsed: &ast_cache::ParsedFile,
+	rule: &Rule,
+	path: &str,
+) -> Vec<AstFindMatch> {
+	parsed
+		.ast
+		.root()
+		.find_all(rule)
+		.map(|matched| {
+			let range = matched.range();
+			let (start, end) = (matched.start_pos(), matched.end_pos());
+			AstFindMatch {
+				path:           path.to_string(),
+				text:           matched.text().into_owned(),
+				byte_start:     to_u32(range.start),
+				byte_end:       to_u32(range.end),
+				start_line:     to_u32(start.line().saturating_add(1)),
+				start_column:   to_u32(start.column(matched.get_node()).saturating_add(1)),
+				end_line:       to_u32(end.line().saturating_add(1)),
+				end_column:     to_u32(end.column(matched.get_node()).saturating_add(1)),
+				meta_variables: None,
+				embedded_lang:  None,
+				blame:          None,
+				context_before: None,
+				context_after:  None,
+			}
+		})
+		.collect()
+}
+
+/// Every match of the files from `offset` up to `limit` files, in order
+/// (`matches` is sorted by path), and whether more files follow.
+fn page_files(
+	matches: Vec<AstFindMatch>,
+	offset: usize,
+	limit: usize,
+) -> (Vec<AstFindMatch>, bool) {
+	let mut files = 0;
+	let mut previous: Option<String> = None;
+	let mut page = Vec::new();
+	for matched in matches {
+		if previous.as_deref() != Some(matched.path.as_str()) {
+			files += 1;
+			previous = Some(matched.path.clone());
+			if files > offset + limit {
+				return (page, true);
+			}
+		}
+		if files > offset {
+			page.push(matched);
+		}
+	}
+	(page, false)
+}
+
+/// Group path-sorted matches into per-file buckets.
+fn group_files(matches: Vec<AstFindMatch>) -> Vec<AstFileMatches> {
+	let mut files: Vec<AstFileMatches> = Vec::new();
+	for matched in matches {
+		match files.last_mut() {
+			Some(file) if file.path == matched.path => file.matches.push(matched),
+			_ => files.push(AstFileMatches {
+				path:    matched.path.clone(),
+				count:   0,
+				matches: vec![matched],
+			}),
+		}
+	}
+	for file in &mut files {
+		file.count = to_u32(file.matches.len());
+	}
+	files
+}
+
 #[napi(js_name = "astGrep")]
 pub fn ast_grep(options: AstFindOptions<'_>) -> task::Async<AstFindResult> {
 	let AstFindOptions {
 		patterns,
+		rules,
 		lang,
 		path,
 		glob,
+		glob_case_sensitive,
+		changed_only,
+		ignore_sources,
+		ignore_files,
 		selector,
 		strictness,
 		limit,
 		offset,
 		include_meta,
-		context: _,
+		context,
+		group_by_file,
+		injections,
+		blame,
+		trace,
 		signal,
 		timeout_ms,
+		scope,
 	} = options;
 
-	let ct = task::CancelToken::new(timeout_ms, signal);
+	let ct = task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref());
 	let normalized_limit = limit.unwrap_or(DEFAULT_FIND_LIMIT).max(1);
 	let normalized_offset = offset.unwrap_or(0);
 
 	task::blocking("ast_grep", ct, move |ct| {
-		let patterns = normalize_pattern_list(patterns)?;
+		let patterns = normalize_pattern_list(patterns);
+		let rules = parse_rule_list(rules)?;
+		if patterns.is_empty() && rules.is_empty() {
+			return Err(Error::from_reason(
+				"`patterns` or `rules` is required and must include at least one non-empty pattern or \
+				 rule"
+					.to_string(),
+			));
+		}
 		let strictness = parse_strictness(strictness.as_deref())?;
 		let include_meta = include_meta.unwrap_or(false);
+		let context = context.unwrap_or(0);
+		let injections = injections.unwrap_or(false);
+		let group_by_file = group_by_file.unwrap_or(false);
 		let lang_str = lang.as_deref().map(str::trim).filter(|v| !v.is_empty());
-		let candidates: Vec<_> = collect_candidates(path, glob.as_deref(), &ct)?
-			.into_iter()
-			.filter(|candidate| is_supported_file(&candidate.absolute_path, lang_str))
-			.collect();
-
+		let trace = CallTrace::start("astGrep", trace.unwrap_or(false));
+		let policy = scan_policy(ignore_sources, ignore_files)?;
+		let scan_span = prof::trace_span(trace.as_ref(), "scan");
+		let mut candidates: Vec<_> =
+			collect_candidates(path, glob.as_deref(), glob_case_sensitive, &policy, &ct)?
+				.into_iter()
+				.filter(|candidate| {
+					is_supported_file(&candidate.absolute_path, lang_str)
+						|| (injections && injection::is_host(&candidate.absolute_path))
+				})
+				.collect();
+		retain_changed(&mut candidates, changed_only.as_deref(), &ct)?;
+		drop(scan_span);
+
+		let compile_span = prof::trace_span(trace.as_ref(), "compile");
 		let (resolved_candidates, languages) =
 			resolve_candidates_for_find(candidates, lang_str, &ct)?;
-		let compiled_patterns =
-			compile_find_patterns(&patterns, &languages, selector.as_deref(), &strictness, &ct)?;
+		let mut compiled_patterns = compile_find_patterns(
+			&patterns,
+			&rules,
+			&languages,
+			selector.as_deref(),
+			&strictness,
+			&ct,
+		)?;
+		drop(compile_span);
 		let files_searched = to_u32(resolved_candidates.len());
 
+		let match_span = prof::trace_span(trace.as_ref(), "match");
 		let mut all_matches = Vec::new();
 		let mut parse_errors = Vec::new();
 		let mut total_matches = 0u32;
 		let mut files_with_matches = BTreeSet::new();
+		let mut absolute_paths = HashMap::new();
 		for resolved in resolved_candidates {
 			ct.heartbeat()?;
 			let ResolvedCandidate { candidate, language, language_error } = resolved;
+			let embedded = injections && injection::is_host(&candidate.absolute_path);
 
-			if let Some(error) = language_error.as_deref() {
+			if let Some(error) = language_error.as_deref()
+				&& !embedded
+			{
 				for compiled in &compiled_patterns {
 					parse_errors
 						.push(format!("{}: {}: {error}", compiled.pattern, candidate.display_path));
@@ -619,74 +925,185 @@ pub fn ast_grep(options: AstFindOptions<'_>) -> task::Async<AstFindResult> {
 				continue;
 			}
 
-			let Some(language) = language else {
-				continue;
-			};
-			let lang_key = language.canonical_name();
-			let source = match std::fs::read_to_string(&candidate.absolute_path) {
-				Ok(source) => source,
-				Err(err) => {
+			// With `lang`, an embedding document is searched only through its
+			// blocks in that language.
+			let host_language = language.filter(|_| !(embedded && lang_str.is_some()));
+			let mut trees = Vec::new();
+			if let Some(language) = host_language {
+				let lang_key = language.canonical_name();
+				if compiled_patterns
+					.iter()
+					.any(|compiled| compiled.compiled_by_lang.contains_key(lang_key))
+				{
+					match ast_cache::get_or_parse(&candidate.absolute_path, language) {
+						Ok(parsed) => trees.push(SearchTree { parsed, lang: language, region: None }),
+						Err(err) => {
+							for compiled in &compiled_patterns {
+								parse_errors.push(format!(
+									"{}: {}: {err}",
+									compiled.pattern, candidate.display_path
+								));
+							}
+							continue;
+						},
+					}
+				} else {
 					for compiled in &compiled_patterns {
-						parse_errors
-							.push(format!("{}: {}: {err}", compiled.pattern, candidate.display_path));
+						if let Some(error) = compiled.compile_errors_by_lang.get(lang_key) {
+							parse_errors
+								.push(format!("{}: {}: {error}", compiled.pattern, candidate.display_path));
+						}
 					}
-					continue;
-				},
-			};
-
-			let mut runnable_patterns: Vec<(&str, &Pattern)> = Vec::new();
-			for compiled in &compiled_patterns {
-				ct.heartbeat()?;
-				if let Some(error) = compiled.compile_errors_by_lang.get(lang_key) {
-					parse_errors
-						.push(format!("{}: {}: {error}", compiled.pattern, candidate.display_path));
-					continue;
-				}
-				if let Some(pattern) = compiled.compiled_by_lang.get(lang_key) {
-					runnable_patterns.push((compiled.pattern.as_str(), pattern));
 				}
 			}
-			if runnable_patterns.is_empty() {
+			if trees.is_empty() && !embedded {
 				continue;
 			}
-
-			let ast = language.ast_grep(source);
-			if ast.root().dfs().any(|node| node.is_error()) {
-				parse_errors.push(format!(
-					"{}: parse error (syntax tree contains error nodes)",
-					candidate.display_path
-				));
+			let host_parsed = trees.first().map(|tree| tree.parsed.clone());
+			let read_source;
+			let source = if let Some(parsed) = &host_parsed {
+				parsed.source()
+			} else {
+				match std::fs::read_to_string(&candidate.absolute_path) {
+					Ok(text) => {
+						read_source = text;
+						read_source.as_str()
+					},
+					Err(err) => {
+						parse_errors.push(format!("{}: {err}", candidate.display_path));
+						continue;
+					},
+				}
+			};
+			if embedded {
+				let only = lang_str.map(resolve_supported_lang).transpose()?;
+				for region in injection::find_regions(&candidate.absolute_path, source) {
+					ct.heartbeat()?;
+					if only.is_some_and(|only| only != region.lang) {
+						continue;
+					}
+					for compiled in &mut compiled_patterns {
+						compiled.compile_for(region.lang, selector.as_deref(), &strictness);
+					}
+					let parsed = ast_cache::ParsedFile::new(
+						source[region.start..region.end].to_string(),
+						region.lang,
+					);
+					trees.push(SearchTree {
+						parsed: std::sync::Arc::new(parsed),
+						lang:   region.lang,
+						region: Some(region),
+					});
+				}
 			}
+			let lines: Vec<&str> = if context > 0 {
+				source.lines().collect()
+			} else {
+				Vec::new()
+			};
 
-			for (_, pattern) in runnable_patterns {
-				ct.heartbeat()?;
-				for matched in ast.root().find_all(pattern.clone()) {
+			// Compile errors are reported once per file and language, however
+			// many blocks share the language.
+			let mut reported_langs = BTreeSet::new();
+			for tree in &trees {
+				let lang_key = tree.lang.canonical_name();
+				let report = reported_langs.insert(lang_key);
+				let mut runnable_patterns: Vec<(&str, &Rule)> = Vec::new();
+				for compiled in &compiled_patterns {
 					ct.heartbeat()?;
-					total_matches = total_matches.saturating_add(1);
-					let range = matched.range();
-					let start = matched.start_pos();
-					let end = matched.end_pos();
-					let meta_variables = if include_meta {
-						Some(HashMap::<String, String>::from(matched.get_env().clone()))
-					} else {
-						None
-					};
-					all_matches.push(AstFindMatch {
-						path: candidate.display_path.clone(),
-						text: matched.text().into_owned(),
-						byte_start: to_u32(range.start),
-						byte_end: to_u32(range.end),
-						start_line: to_u32(start.line().saturating_add(1)),
-						start_column: to_u32(start.column(matched.get_node()).saturating_add(1)),
-						end_line: to_u32(end.line().saturating_add(1)),
-						end_column: to_u32(end.column(matched.get_node()).saturating_add(1)),
-						meta_variables,
+					if let Some(error) = compiled.compile_errors_by_lang.get(lang_key) {
+						if report {
+							parse_errors
+								.push(format!("{}: {}: {error}", compiled.pattern, candidate.display_path));
+						}
+						continue;
+					}
+					if let Some(pattern) = compiled.compiled_by_lang.get(lang_key) {
+						runnable_patterns.push((compiled.pattern.as_str(), pattern));
+					}
+				}
+				if runnable_patterns.is_empty() {
+					continue;
+				}
+
+				if tree.parsed.has_error {
+					parse_errors.push(match &tree.region {
+						Some(region) => format!(
+							"{}:{}: parse error in embedded {lang_key} block",
+							candidate.display_path,
+							region.line + 1
+						),
+						None => format!(
+							"{}: parse error (syntax tree contains error nodes)",
+							candidate.display_path
+						),
 					});
-					files_with_matches.insert(candidate.display_path.clone());
+				}
+				absolute_paths.insert(candidate.display_path.clone(), candidate.absolute_path.clone());
+				// Offsets that move block-relative positions into the host file.
+				let (byte_offset, line_offset, column_offset) = tree
+					.region
+					.as_ref()
+					.map_or((0, 0, 0), |region| (region.start, region.line, region.column));
+				let column = |line: usize, column: usize| {
+					if line == 0 {
+						column + column_offset
+					} else {
+						column
+					}
+				};
+
+				for (_, pattern) in runnable_patterns {
+					ct.heartbeat()?;
+					for matched in tree.parsed.ast.root().find_all(pattern) {
+						ct.heartbeat()?;
+						total_matches = total_matches.saturating_add(1);
+						let range = matched.range();
+						let start = matched.start_pos();
+						let end = matched.end_pos();
+						let meta_variables = if include_meta {
+							Some(HashMap::<String, String>::from(matched.get_env().clone()))
+						} else {
+							None
+						};
+						let start_line = start.line() + line_offset;
+						let end_line = end.line() + line_offset;
+						let (context_before, context_after) = if context > 0 {
+							let (before, after) =
+								context_lines(&lines, start_line, end_line, context as usize);
+							(Some(before), Some(after))
+						} else {
+							(None, None)
+						};
+						let start_column = column(start.line(), start.column(matched.get_node()));
+						let end_column = column(end.line(), end.column(matched.get_node()));
+						all_matches.push(AstFindMatch {
+							path: candidate.display_path.clone(),
+							text: matched.text().into_owned(),
+							byte_start: to_u32(range.start + byte_offset),
+							byte_end: to_u32(range.end + byte_offset),
+							start_line: to_u32(start_line.saturating_add(1)),
+							start_column: to_u32(start_column.saturating_add(1)),
+							end_line: to_u32(end_line.saturating_add(1)),
+							end_column: to_u32(end_column.saturating_add(1)),
+							meta_variables,
+							embedded_lang: tree
+								.region
+								.as_ref()
+								.map(|region| region.lang.canonical_name().to_string()),
+							blame: None,
+							context_before,
+							context_after,
+						});
+						files_with_matches.insert(candidate.display_path.clone());
+					}
 				}
 			}
 		}
 
+		drop(match_span);
+
+		let serialize_span = prof::trace_span(trace.as_ref(), "serialize");
 		all_matches.sort_by(|left, right| {
 			left
 				.path
@@ -699,23 +1116,50 @@ pub fn ast_grep(options: AstFindOptions<'_>) -> task::Async<AstFindResult> {
 				.then(left.byte_end.cmp(&right.byte_end))
 		});
 
-		let visible_matches = all_matches
-			.into_iter()
-			.skip(normalized_offset as usize)
-			.collect::<Vec<_>>();
-		let limit_reached = visible_matches.len() > normalized_limit as usize;
-		let matches = visible_matches
-			.into_iter()
-			.take(normalized_limit as usize)
-			.collect::<Vec<_>>();
+		let (mut matches, limit_reached) = if group_by_file {
+			page_files(all_matches, normalized_offset as usize, normalized_limit as usize)
+		} else {
+			let visible_matches = all_matches
+				.into_iter()
+				.skip(normalized_offset as usize)
+				.collect::<Vec<_>>();
+			let limit_reached = visible_matches.len() > normalized_limit as usize;
+			let matches = visible_matches
+				.into_iter()
+				.take(normalized_limit as usize)
+				.collect::<Vec<_>>();
+			(matches, limit_reached)
+		};
+		drop(serialize_span);
+
+		if blame.unwrap_or(false) && !matches.is_empty() {
+			let _span = prof::trace_span(trace.as_ref(), "blame");
+			if let Some(dir) = absolute_paths
+				.values()
+				.next()
+				.and_then(|path| path.parent())
+			{
+				git::work_tree_root(dir, &ct)?;
+			}
+			git::annotate_blame(
+				&mut matches,
+				|matched| absolute_paths[&matched.path].clone(),
+				|matched| matched.start_line,
+				|matched, info| matched.blame = Some(info),
+				&ct,
+			)?;
+		}
+		let files = group_by_file.then(|| group_files(std::mem::take(&mut matches)));
 
 		Ok(AstFindResult {
 			matches,
+			files,
 			total_matches,
 			files_with_matches: to_u32(files_with_matches.len()),
 			files_searched,
 			limit_reached,
 			parse_errors: (!parse_errors.is_empty()).then_some(parse_errors),
+			trace: trace.map(CallTrace::finish),
 		})
 	})
 }
@@ -727,30 +1171,41 @@ pub fn ast_edit(options: AstReplaceOptions<'_>) -> task::Async<AstReplaceResult>
 		lang,
 		path,
 		glob,
+		glob_case_sensitive,
+		ignore_sources,
+		ignore_files,
 		selector,
 		strictness,
 		dry_run,
 		max_replacements,
 		max_files,
 		fail_on_parse_error,
+		return_diff,
 		signal,
 		timeout_ms,
+		scope,
 	} = options;
 
-	let ct = task::CancelToken::new(timeout_ms, signal);
+	let ct = task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref());
 	task::blocking("ast_edit", ct, move |ct| {
 		let rewrite_rules = normalize_rewrite_map(rewrites)?;
 		let strictness = parse_strictness(strictness.as_deref())?;
 		let dry_run = dry_run.unwrap_or(true);
+		if !dry_run {
+			readonly::ensure_writable("astEdit")?;
+		}
 		let max_replacements = max_replacements.unwrap_or(u32::MAX).max(1);
 		let max_files = max_files.unwrap_or(u32::MAX).max(1);
 		let fail_on_parse_error = fail_on_parse_error.unwrap_or(false);
+		let return_diff = return_diff.unwrap_or(false);
 
 		let lang_str = lang.as_deref().map(str::trim).filter(|v| !v.is_empty());
-		let candidates: Vec<_> = collect_candidates(path, glob.as_deref(), &ct)?
-			.into_iter()
-			.filter(|candidate| is_supported_file(&candidate.absolute_path, lang_str))
-			.collect();
+		let policy = scan_policy(ignore_sources, ignore_files)?;
+		let candidates: Vec<_> =
+			collect_candidates(path, glob.as_deref(), glob_case_sensitive, &policy, &ct)?
+				.into_iter()
+				.filter(|candidate| is_supported_file(&candidate.absolute_path, lang_str))
+				.collect();
 		let effective_lang = if let Some(lang) = lang_str {
 			lang.to_string()
 		} else {
@@ -782,18 +1237,30 @@ pub fn ast_edit(options: AstReplaceOptions<'_>) -> task::Async<AstReplaceResult>
 				limit_reached:      false,
 				parse_errors:       (!parse_errors.is_empty()).then_some(parse_errors),
 				changes:            vec![],
+				diffs:              return_diff.then(Vec::new),
+				change_set:         None,
 			});
 		}
 
 		let mut changes = Vec::new();
+		let mut pending_writes = Vec::new();
+		let mut diffs = Vec::new();
+		let mut change_set = changeset::ChangeSetBuilder::default();
 		let mut file_counts: BTreeMap<String, u32> = BTreeMap::new();
 		let mut files_touched = 0u32;
 		let mut limit_reached = false;
 
 		for candidate in &candidates {
 			ct.heartbeat()?;
-			let source = match std::fs::read_to_string(&candidate.absolute_path) {
-				Ok(source) => source,
+			// Writes are computed from the tree, so they must not trust a
+			// stat-only cache hit.
+			let parsed = if dry_run {
+				ast_cache::get_or_parse(&candidate.absolute_path, language)
+			} else {
+				ast_cache::get_or_parse_verified(&candidate.absolute_path, language)
+			};
+			let parsed = match parsed {
+				Ok(parsed) => parsed,
 				Err(err) => {
 					if fail_on_parse_error {
 						return Err(Error::from_reason(format!("{}: {err}", candidate.display_path)));
@@ -802,9 +1269,8 @@ pub fn ast_edit(options: AstReplaceOptions<'_>) -> task::Async<AstReplaceResult>
 					continue;
 				},
 			};
-
-			let ast = language.ast_grep(&source);
-			if ast.root().dfs().any(|node| node.is_error()) {
+			let (ast, source) = (&parsed.ast, parsed.source());
+			if parsed.has_error {
 				let parse_issue = format!(
 					"{}: parse error (syntax tree contains error nodes)",
 					candidate.display_path
@@ -867,21 +1333,44 @@ pub fn ast_edit(options: AstReplaceOptions<'_>) -> task::Async<AstReplaceResult>
 			files_touched = files_touched.saturating_add(1);
 			file_counts.insert(candidate.display_path.clone(), to_u32(file_changes.len()));
 
-			if !dry_run {
-				let edits: Vec<Edit<String>> = file_changes
-					.iter()
-					.map(|entry| Edit {
-						position:       entry.edit.position,
-						deleted_length: entry.edit.deleted_length,
-						inserted_text:  entry.edit.inserted_text.clone(),
-					})
-					.collect();
-				let output = apply_edits(&source, &edits)?;
-				if output != source {
-					std::fs::write(&candidate.absolute_path, output).map_err(|err| {
-						Error::from_reason(format!("Failed to write {}: {err}", candidate.display_path))
-					})?;
-				}
+			let edits: Vec<Edit<String>> = file_changes
+				.iter()
+				.map(|entry| Edit {
+					position:       entry.edit.position,
+					deleted_length: entry.edit.deleted_length,
+					inserted_text:  entry.edit.inserted_text.clone(),
+				})
+				.collect();
+			match apply_edits(source, &edits) {
+				Ok(output) => {
+					if return_diff {
+						diffs.push(diff::unified_diff(
+							&candidate.display_path,
+							source,
+							&output,
+							diff::DEFAULT_CONTEXT,
+						));
+					}
+					if output == source {
+						// Rewrites that reproduce the matched text change nothing.
+					} else if dry_run {
+						change_set.add(
+							&candidate.display_path,
+							&candidate.absolute_path,
+							Some(source),
+							Some(&output),
+						);
+					} else {
+						pending_writes.push(journal::JournalEntry {
+							path:   candidate.absolute_path.clone(),
+							before: Some(source.as_bytes().to_vec()),
+							after:  Some(output.into_bytes()),
+						});
+					}
+				},
+				// A preview reports the file instead of failing the whole run.
+				Err(err) if dry_run => change_set.conflict(&candidate.display_path, err.reason.clone()),
+				Err(err) => return Err(err),
 			}
 
 			changes.extend(file_changes.into_iter().map(|entry| entry.change));
@@ -890,6 +1379,13 @@ pub fn ast_edit(options: AstReplaceOptions<'_>) -> task::Async<AstReplaceResult>
 			}
 		}
 
+		// All files are written together under a journal so a crash mid-apply
+		// can be rolled forward or back with `recoverJournal()`.
+		journal::apply(&pending_writes)?;
+		for write in &pending_writes {
+			ast_cache::invalidate_path(&write.path);
+		}
+
 		let file_changes = file_counts
 			.into_iter()
 			.map(|(path, count)| AstReplaceFileChange { path, count })
@@ -904,6 +1400,11 @@ pub fn ast_edit(options: AstReplaceOptions<'_>) -> task::Async<AstReplaceResult>
 			limit_reached,
 			parse_errors: (!parse_errors.is_empty()).then_some(parse_errors),
 			changes,
+			diffs: return_diff.then(|| {
+				diffs.sort_by(|left, right| left.path.cmp(&right.path));
+				diffs
+			}),
+			change_set: dry_run.then(|| change_set.finish()),
 		})
 	})
 }
@@ -945,9 +1446,15 @@ mod tests {
 	fn glob_star_matches_only_direct_children() {
 		let tree = make_temp_tree();
 		let ct = task::CancelToken::default();
-		let candidates =
-			collect_candidates(Some(tree.root.to_string_lossy().into_owned()), Some("*.ts"), &ct)
-				.expect("candidate collection should succeed");
+		let policy = fs_cache::ScanPolicy::new(true, true);
+		let candidates = collect_candidates(
+			Some(tree.root.to_string_lossy().into_owned()),
+			Some("*.ts"),
+			None,
+			&policy,
+			&ct,
+		)
+		.expect("candidate collection should succeed");
 		let paths = candidates
 			.into_iter()
 			.map(|file| file.display_path)
@@ -959,9 +1466,15 @@ mod tests {
 	fn glob_double_star_matches_recursively() {
 		let tree = make_temp_tree();
 		let ct = task::CancelToken::default();
-		let candidates =
-			collect_candidates(Some(tree.root.to_string_lossy().into_owned()), Some("**/*.ts"), &ct)
-				.expect("candidate collection should succeed");
+		let policy = fs_cache::ScanPolicy::new(true, true);
+		let candidates = collect_candidates(
+			Some(tree.root.to_string_lossy().into_owned()),
+			Some("**/*.ts"),
+			None,
+			&policy,
+			&ct,
+		)
+		.expect("candidate collection should succeed");
 		let paths = candidates
 			.into_iter()
 			.map(|file| file.display_path)
@@ -984,9 +1497,15 @@ mod tests {
 	fn infers_single_replace_lang_for_uniform_candidates() {
 		let tree = make_temp_tree();
 		let ct = task::CancelToken::default();
-		let candidates =
-			collect_candidates(Some(tree.root.to_string_lossy().into_owned()), Some("**/*.ts"), &ct)
-				.expect("candidate collection should succeed");
+		let policy = fs_cache::ScanPolicy::new(true, true);
+		let candidates = collect_candidates(
+			Some(tree.root.to_string_lossy().into_owned()),
+			Some("**/*.ts"),
+			None,
+			&policy,
+			&ct,
+		)
+		.expect("candidate collection should succeed");
 		let inferred =
 			infer_single_replace_lang(&candidates, &ct).expect("language should be inferred");
 		assert_eq!(inferred, "typescript");
@@ -996,9 +1515,15 @@ mod tests {
 	fn rejects_mixed_replace_lang_inference() {
 		let tree = make_mixed_temp_tree();
 		let ct = task::CancelToken::default();
-		let candidates =
-			collect_candidates(Some(tree.root.to_string_lossy().into_owned()), None, &ct)
-				.expect("candidate collection should succeed");
+		let policy = fs_cache::ScanPolicy::new(true, true);
+		let candidates = collect_candidates(
+			Some(tree.root.to_string_lossy().into_owned()),
+			None,
+			None,
+			&policy,
+			&ct,
+		)
+		.expect("candidate collection should succeed");
 		let err = infer_single_replace_lang(&candidates, &ct)
 			.expect_err("mixed language inference should fail");
 		assert!(err.to_string().contains("multiple languages"));
@@ -1015,6 +1540,22 @@ mod tests {
 		assert!(resolve_supported_lang("brainfuck").is_err());
 	}
 
+	#[test]
+	fn collects_context_lines() {
+		let lines = ["a", "b", "c", "d", "e"];
+		let numbers = |context: Vec<ContextLine>| {
+			context
+				.into_iter()
+				.map(|line| (line.line_number, line.line))
+				.collect::<Vec<_>>()
+		};
+		let (before, after) = context_lines(&lines, 1, 2, 2);
+		assert_eq!(numbers(before), [(1, "a".to_string())]);
+		assert_eq!(numbers(after), [(4, "d".to_string()), (5, "e".to_string())]);
+		let (before, after) = context_lines(&lines, 4, 4, 1);
+		assert_eq!((numbers(before), numbers(after).len()), (vec![(4, "d".to_string())], 0));
+	}
+
 	#[test]
 	fn applies_non_overlapping_edits() {
 		let source = "const answer = 41;";
@@ -1035,4 +1576,48 @@ mod tests {
 		];
 		assert!(apply_edits(source, &edits).is_err());
 	}
+
+	fn find_match(path: &str, line: u32) -> AstFindMatch {
+		AstFindMatch {
+			path:           path.to_string(),
+			text:           String::new(),
+			byte_start:     0,
+			byte_end:       0,
+			start_line:     line,
+			start_column:   1,
+			end_line:       line,
+			end_column:     1,
+			meta_variables: None,
+			embedded_lang:  None,
+			blame:          None,
+			context_before: None,
+			context_after:  None,
+		}
+	}
+
+	#[test]
+	fn pages_and_groups_matches_by_file() {
+		let matches = || {
+			vec![
+				find_match("a.rs", 1),
+				find_match("a.rs", 4),
+				find_match("b.rs", 2),
+				find_match("c.rs", 3),
+				find_match("c.rs", 9),
+			]
+		};
+		let (page, more) = page_files(matches(), 1, 1);
+		assert!(more);
+		let files = group_files(page);
+		assert_eq!(files.len(), 1);
+		assert_eq!((files[0].path.as_str(), files[0].count), ("b.rs", 1));
+
+		let (page, more) = page_files(matches(), 1, 5);
+		assert!(!more);
+		let counts: Vec<_> = group_files(page)
+			.iter()
+			.map(|file| (file.path.clone(), file.count))
+			.collect();
+		assert_eq!(counts, [("b.rs".to_string(), 1), ("c.rs".to_string(), 2)]);
+	}
 }
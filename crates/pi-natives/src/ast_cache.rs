@@ -0,0 +1,325 @@
+//! Shared cache of parsed syntax trees for structural search and rewrite.
+//!
+//! `astGrep` and `astEdit` used to read and parse every candidate on each
+//! call. Parsed files are now kept in an LRU keyed by path and language and
+//! validated against the file's modification time and size, so repeated
+//! queries over the same tree only stat unchanged files. Each entry also keeps
+//! an xxh3 hash of its source: callers about to write a file derived from a
+//! cached tree use [`get_or_parse_verified`], which re-reads the bytes and
+//! reparses when an edit kept both the size and the modification time.
+//!
+//! # Policy Configuration (environment overrides)
+//! - `AST_CACHE_MAX_ENTRIES` – default `512` (`0` disables the cache)
+//! - `AST_CACHE_MAX_BYTES`   – default `67108864` (64 MiB of source text)
+
+use std::{
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	sync::{
+		Arc, LazyLock,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::SystemTime,
+};
+
+use ast_grep_core::{AstGrep, tree_sitter::StrDoc};
+use napi_derive::napi;
+use parking_lot::Mutex;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::language::SupportLang;
+
+const DEFAULT_MAX_ENTRIES: usize = 512;
+const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+fn env_usize(name: &str, default: usize) -> usize {
+	std::env::var(name)
+		.ok()
+		.and_then(|v| v.parse().ok())
+		.unwrap_or(default)
+}
+
+pub(crate) fn max_entries() -> usize {
+	env_usize("AST_CACHE_MAX_ENTRIES", DEFAULT_MAX_ENTRIES)
+}
+
+pub(crate) fn max_bytes() -> usize {
+	env_usize("AST_CACHE_MAX_BYTES", DEFAULT_MAX_BYTES)
+}
+
+/// A parsed source file.
+pub struct ParsedFile {
+	pub ast:       AstGrep<StrDoc<SupportLang>>,
+	/// Whether the tree contains error nodes.
+	pub has_error: bool,
+	/// xxh3 hash of the source text.
+	hash:          u64,
+}
+
+impl ParsedFile {
+	pub fn new(source: String, lang: SupportLang) -> Self {
+		let hash = xxh3_64(source.as_bytes());
+		let ast = AstGrep::new(source, lang);
+		let has_error = ast.root().dfs().any(|node| node.is_error());
+		Self { ast, has_error, hash }
+	}
+
+	/// Source text the tree was parsed from.
+	pub fn source(&self) -> &str {
+		self.ast.get_text()
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+	path: PathBuf,
+	lang: SupportLang,
+}
+
+struct CacheEntry {
+	mtime:     Option<SystemTime>,
+	size:      u64,
+	last_used: u64,
+	parsed:    Arc<ParsedFile>,
+}
+
+#[derive(Default)]
+struct AstCache {
+	entries: HashMap<CacheKey, CacheEntry>,
+	bytes:   usize,
+	clock:   u64,
+}
+
+impl AstCache {
+	fn remove(&mut self, key: &CacheKey) -> bool {
+		let Some(entry) = self.entries.remove(key) else {
+			return false;
+		};
+		self.bytes -= entry.parsed.source().len();
+		true
+	}
+
+	/// Drop least recently used entries until both limits hold.
+	fn evict(&mut self, max_entries: usize, max_bytes: usize) {
+		while self.entries.len() > max_entries || self.bytes > max_bytes {
+			let Some(oldest) = self
+				.entries
+				.iter()
+				.min_by_key(|(_, entry)| entry.last_used)
+				.map(|(key, _)| key.clone())
+			else {
+				break;
+			};
+			self.remove(&oldest);
+		}
+	}
+}
+
+static AST_CACHE: LazyLock<Mutex<AstCache>> = LazyLock::new(Mutex::default);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Parse `path` as `lang`, reusing the cached tree while the file's
+/// modification time and size are unchanged.
+pub fn get_or_parse(path: &Path, lang: SupportLang) -> io::Result<Arc<ParsedFile>> {
+	let metadata = fs::metadata(path)?;
+	let mtime = metadata.modified().ok();
+	let size = metadata.len();
+	let key = CacheKey { path: path.to_path_buf(), lang };
+	{
+		let mut cache = AST_CACHE.lock();
+		cache.clock += 1;
+		let clock = cache.clock;
+		if let Some(entry) = cache.entries.get_mut(&key)
+			&& entry.mtime.is_some()
+			&& entry.mtime == mtime
+			&& entry.size == size
+		{
+			entry.last_used = clock;
+			HITS.fetch_add(1, Ordering::Relaxed);
+			return Ok(entry.parsed.clone());
+		}
+	}
+
+	MISSES.fetch_add(1, Ordering::Relaxed);
+	let parsed = Arc::new(ParsedFile::new(fs::read_to_string(path)?, lang));
+	insert(key, mtime, size, &parsed);
+	Ok(parsed)
+}
+
+/// Parse `path` as `lang`, reusing the cached tree only when its hash matches
+/// the bytes currently on disk.
+///
+/// Unlike [`get_or_parse`], this always reads the file, so it also catches
+/// edits that keep the size and land within the modification time
+/// granularity. Use it before writing output derived from the tree.
+pub fn get_or_parse_verified(path: &Path, lang: SupportLang) -> io::Result<Arc<ParsedFile>> {
+	let metadata = fs::metadata(path)?;
+	let source = fs::read_to_string(path)?;
+	let hash = xxh3_64(source.as_bytes());
+	let key = CacheKey { path: path.to_path_buf(), lang };
+	{
+		let mut cache = AST_CACHE.lock();
+		cache.clock += 1;
+		let clock = cache.clock;
+		if let Some(entry) = cache.entries.get_mut(&key)
+			&& entry.parsed.hash == hash
+			&& entry.parsed.source() == source
+		{
+			entry.last_used = clock;
+			HITS.fetch_add(1, Ordering::Relaxed);
+			return Ok(entry.parsed.clone());
+		}
+	}
+
+	MISSES.fetch_add(1, Ordering::Relaxed);
+	let parsed = Arc::new(ParsedFile::new(source, lang));
+	insert(key, metadata.modified().ok(), metadata.len(), &parsed);
+	Ok(parsed)
+}
+
+fn insert(key: CacheKey, mtime: Option<SystemTime>, size: u64, parsed: &Arc<ParsedFile>) {
+	let (max_entries, max_bytes) = (max_entries(), max_bytes());
+	if max_entries > 0 && parsed.source().len() <= max_bytes {
+		let mut cache = AST_CACHE.lock();
+		cache.remove(&key);
+		cache.bytes += parsed.source().len();
+		let last_used = cache.clock;
+		cache
+			.entries
+			.insert(key, CacheEntry { mtime, size, last_used, parsed: parsed.clone() });
+		cache.evict(max_entries, max_bytes);
+	}
+}
+
+/// Drop cached trees for `target` and every file under it.
+pub fn invalidate_path(target: &Path) -> usize {
+	let mut cache = AST_CACHE.lock();
+	let keys: Vec<CacheKey> = cache
+		.entries
+		.keys()
+		.filter(|key| key.path.starts_with(target))
+		.cloned()
+		.collect();
+	keys.iter().filter(|key| cache.remove(key)).count()
+}
+
+/// Drop cached trees for files under `path`, or every cached tree when
+/// omitted, and return how many were dropped.
+///
+/// Changed files are detected by modification time and size, so this is only
+/// needed to release memory or after edits that preserve both. Writes from
+/// `astEdit` re-verify content hashes and never depend on it.
+#[napi(js_name = "astCacheInvalidate")]
+pub fn ast_cache_invalidate(path: Option<String>) -> u32 {
+	let removed = if let Some(path) = path {
+		let path = PathBuf::from(path);
+		invalidate_path(&fs::canonicalize(&path).unwrap_or(path))
+	} else {
+		let mut cache = AST_CACHE.lock();
+		let removed = cache.entries.len();
+		*cache = AstCache { clock: cache.clock, ..AstCache::default() };
+		removed
+	};
+	crate::utils::clamp_u32(removed as u64)
+}
+
+/// Snapshot of the parsed-tree cache.
+#[napi(object)]
+pub struct AstCacheStats {
+	/// Cached files.
+	pub entries:      u32,
+	/// Source bytes held by cached files.
+	#[napi(js_name = "sourceBytes")]
+	pub source_bytes: f64,
+	/// Lookups served from the cache since startup.
+	pub hits:         f64,
+	/// Lookups that read and parsed the file since startup.
+	pub misses:       f64,
+	/// Maximum cached files (`AST_CACHE_MAX_ENTRIES`).
+	#[napi(js_name = "maxEntries")]
+	pub max_entries:  u32,
+	/// Maximum cached source bytes (`AST_CACHE_MAX_BYTES`).
+	#[napi(js_name = "maxBytes")]
+	pub max_bytes:    f64,
+}
+
+/// Report what the parsed-tree cache holds and how often it was hit.
+#[napi(js_name = "astCacheStats")]
+pub fn ast_cache_stats() -> AstCacheStats {
+	let cache = AST_CACHE.lock();
+	AstCacheStats {
+		entries:      crate::utils::clamp_u32(cache.entries.len() as u64),
+		source_bytes: cache.bytes as f64,
+		hits:         HITS.load(Ordering::Relaxed) as f64,
+		misses:       MISSES.load(Ordering::Relaxed) as f64,
+		max_entries:  crate::utils::clamp_u32(max_entries() as u64),
+		max_bytes:    max_bytes() as f64,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::UNIX_EPOCH;
+
+	use super::*;
+
+	#[test]
+	fn reuses_trees_until_the_file_changes() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let dir = std::env::temp_dir().join(format!("pi-ast-cache-test-{unique}"));
+		fs::create_dir_all(&dir).unwrap();
+		let file = dir.join("main.rs");
+		fs::write(&file, "fn main() {}\n").unwrap();
+
+		let first = get_or_parse(&file, SupportLang::Rust).unwrap();
+		let second = get_or_parse(&file, SupportLang::Rust).unwrap();
+		assert!(Arc::ptr_eq(&first, &second));
+		assert!(!first.has_error);
+
+		fs::write(&file, "fn main() { broken\n").unwrap();
+		let changed = get_or_parse(&file, SupportLang::Rust).unwrap();
+		assert_eq!(changed.source(), "fn main() { broken\n");
+		assert!(changed.has_error);
+
+		assert_eq!(invalidate_path(&dir), 1);
+		let reparsed = get_or_parse(&file, SupportLang::Rust).unwrap();
+		assert!(!Arc::ptr_eq(&changed, &reparsed));
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn verified_lookups_catch_edits_that_keep_mtime_and_size() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let dir = std::env::temp_dir().join(format!("pi-ast-cache-verify-test-{unique}"));
+		fs::create_dir_all(&dir).unwrap();
+		let file = dir.join("main.rs");
+		fs::write(&file, "fn a() {}\n").unwrap();
+		let mtime = fs::metadata(&file).unwrap().modified().unwrap();
+
+		let cached = get_or_parse(&file, SupportLang::Rust).unwrap();
+		fs::write(&file, "fn b() {}\n").unwrap();
+		fs::File::options()
+			.write(true)
+			.open(&file)
+			.unwrap()
+			.set_modified(mtime)
+			.unwrap();
+
+		// The stat-only lookup cannot tell the files apart.
+		assert!(Arc::ptr_eq(&cached, &get_or_parse(&file, SupportLang::Rust).unwrap()));
+		let verified = get_or_parse_verified(&file, SupportLang::Rust).unwrap();
+		assert_eq!(verified.source(), "fn b() {}\n");
+		assert!(Arc::ptr_eq(&verified, &get_or_parse_verified(&file, SupportLang::Rust).unwrap()));
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+}
@@ -0,0 +1,396 @@
+//! ast-grep rule objects for `astGrep`.
+//!
+//! A rule is the JSON form of an ast-grep rule: atomic keys (`pattern`,
+//! `kind`, `regex`), relational keys (`inside`, `has`, `follows`, `precedes`)
+//! and combinators (`all`, `any`, `not`). Every key in one object must hold.
+//! The ast-grep config crate is not bundled, so rules are compiled here onto
+//! the `ast-grep-core` matchers.
+
+use std::borrow::Cow;
+
+use ast_grep_core::{
+	MatchStrictness, Matcher, Node,
+	matcher::{KindMatcher, MatcherExt, Pattern, RegexMatcher},
+	meta_var::MetaVarEnv,
+	source::Doc,
+};
+use serde_json::{Map, Value};
+
+use crate::language::SupportLang;
+
+/// A compiled rule for one language.
+pub enum Rule {
+	Pattern(Pattern),
+	Kind(KindMatcher),
+	Regex(RegexMatcher),
+	Relation(Box<Relation>),
+	All(Vec<Self>),
+	Any(Vec<Self>),
+	Not(Box<Self>),
+}
+
+#[derive(Clone, Copy)]
+enum RelationKind {
+	Inside,
+	Has,
+	Follows,
+	Precedes,
+}
+
+/// How far a relational rule searches.
+enum StopBy {
+	/// Only the parent, direct children, or adjacent sibling.
+	Neighbor,
+	/// All ancestors, descendants, or siblings in that direction.
+	End,
+	/// Until (and including) a node matching the rule.
+	Rule(Rule),
+}
+
+pub struct Relation {
+	kind:    RelationKind,
+	rule:    Rule,
+	stop_by: StopBy,
+	field:   Option<String>,
+}
+
+/// Parse a rule from its JSON text.
+pub fn parse_rule(text: &str) -> Result<Value, String> {
+	let value: Value =
+		serde_json::from_str(text).map_err(|err| format!("Invalid rule JSON: {err}"))?;
+	if !value.is_object() {
+		return Err("Invalid rule: expected a JSON object".to_string());
+	}
+	Ok(value)
+}
+
+/// Compile a parsed rule for `lang`. Patterns inherit `strictness` unless
+/// they set their own.
+pub fn compile_rule(
+	value: &Value,
+	lang: SupportLang,
+	strictness: &MatchStrictness,
+) -> Result<Rule, String> {
+	let object = value
+		.as_object()
+		.ok_or_else(|| format!("Invalid rule: expected an object, got {value}"))?;
+	compile_object(object, lang, strictness)
+}
+
+fn compile_object(
+	object: &Map<String, Value>,
+	lang: SupportLang,
+	strictness: &MatchStrictness,
+) -> Result<Rule, String> {
+	// Cheap atomic checks run before relational searches and combinators.
+	let mut atomic = Vec::new();
+	let mut rest = Vec::new();
+	for (key, value) in object {
+		match key.as_str() {
+			"pattern" => atomic.push(Rule::Pattern(compile_pattern(value, lang, strictness)?)),
+			"kind" => {
+				let kind = expect_str(key, value)?;
+				let matcher = KindMatcher::try_new(kind, lang)
+					.map_err(|err| format!("Invalid rule kind '{kind}': {err}"))?;
+				atomic.push(Rule::Kind(matcher));
+			},
+			"regex" => {
+				let regex = expect_str(key, value)?;
+				let matcher = RegexMatcher::try_new(regex)
+					.map_err(|err| format!("Invalid rule regex '{regex}': {err}"))?;
+				atomic.push(Rule::Regex(matcher));
+			},
+			"inside" | "has" | "follows" | "precedes" => {
+				rest.push(Rule::Relation(Box::new(compile_relation(key, value, lang, strictness)?)));
+			},
+			"all" | "any" => {
+				let rules = value
+					.as_array()
+					.filter(|rules| !rules.is_empty())
+					.ok_or_else(|| format!("Invalid rule: `{key}` must be a non-empty array"))?
+					.iter()
+					.map(|rule| compile_rule(rule, lang, strictness))
+					.collect::<Result<Vec<_>, _>>()?;
+				rest.push(if key == "all" {
+					Rule::All(rules)
+				} else {
+					Rule::Any(rules)
+				});
+			},
+			"not" => rest.push(Rule::Not(Box::new(compile_rule(value, lang, strictness)?))),
+			"stopBy" | "field" => {
+				return Err(format!("Invalid rule: `{key}` is only valid inside a relational rule"));
+			},
+			_ => return Err(format!("Invalid rule: unknown key `{key}`")),
+		}
+	}
+	atomic.append(&mut rest);
+	if atomic.iter().all(|rule| matches!(rule, Rule::Not(_))) {
+		return Err("Invalid rule: needs at least one positive condition".to_string());
+	}
+	Ok(if atomic.len() == 1 {
+		atomic.pop().expect("one rule")
+	} else {
+		Rule::All(atomic)
+	})
+}
+
+fn expect_str<'a>(key: &str, value: &'a Value) -> Result<&'a str, String> {
+	value
+		.as_str()
+		.ok_or_else(|| format!("Invalid rule: `{key}` must be a string"))
+}
+
+/// `pattern` is a string, or `{ context, selector, strictness? }`.
+fn compile_pattern(
+	value: &Value,
+	lang: SupportLang,
+	strictness: &MatchStrictness,
+) -> Result<Pattern, String> {
+	let (compiled, strictness) = if let Some(pattern) = value.as_str() {
+		(Pattern::try_new(pattern, lang), strictness.clone())
+	} else {
+		let object = value
+			.as_object()
+			.ok_or("Invalid rule: `pattern` must be a string or an object")?;
+		let context = object
+			.get("context")
+			.and_then(Value::as_str)
+			.ok_or("Invalid rule: pattern object needs a `context` string")?;
+		let strictness = match object.get("strictness") {
+			Some(value) => expect_str("strictness", value)?
+				.parse::<MatchStrictness>()
+				.map_err(|err| format!("Invalid strictness: {err}"))?,
+			None => strictness.clone(),
+		};
+		let compiled = match object.get("selector") {
+			Some(selector) => Pattern::contextual(context, expect_str("selector", selector)?, lang),
+			None => Pattern::try_new(context, lang),
+		};
+		(compiled, strictness)
+	};
+	let mut compiled = compiled.map_err(|err| format!("Invalid pattern: {err}"))?;
+	compiled.strictness = strictness;
+	Ok(compiled)
+}
+
+fn compile_relation(
+	key: &str,
+	value: &Value,
+	lang: SupportLang,
+	strictness: &MatchStrictness,
+) -> Result<Relation, String> {
+	let kind = match key {
+		"inside" => RelationKind::Inside,
+		"has" => RelationKind::Has,
+		"follows" => RelationKind::Follows,
+		_ => RelationKind::Precedes,
+	};
+	let mut object = value
+		.as_object()
+		.ok_or_else(|| format!("Invalid rule: `{key}` must be an object"))?
+		.clone();
+	let stop_by = match object.remove("stopBy") {
+		None => StopBy::Neighbor,
+		Some(Value::String(stop)) if stop == "neighbor" => StopBy::Neighbor,
+		Some(Value::String(stop)) if stop == "end" => StopBy::End,
+		Some(rule @ Value::Object(_)) => StopBy::Rule(compile_rule(&rule, lang, strictness)?),
+		Some(other) => {
+			return Err(format!(
+				"Invalid rule: `stopBy` must be \"neighbor\", \"end\", or a rule, got {other}"
+			));
+		},
+	};
+	let field = match object.remove("field") {
+		None => None,
+		Some(_) if matches!(kind, RelationKind::Follows | RelationKind::Precedes) => {
+			return Err(format!("Invalid rule: `field` is not supported by `{key}`"));
+		},
+		Some(field) => Some(expect_str("field", &field)?.to_string()),
+	};
+	let rule = compile_object(&object, lang, strictness)?;
+	Ok(Relation { kind, rule, stop_by, field })
+}
+
+impl Relation {
+	/// Whether the search ends after visiting `node`.
+	fn stops<D: Doc>(&self, node: &Node<'_, D>) -> bool {
+		match &self.stop_by {
+			StopBy::Neighbor => true,
+			StopBy::End => false,
+			StopBy::Rule(rule) => rule.match_node(node.clone()).is_some(),
+		}
+	}
+
+	/// Match `candidate`, keeping its captures only on success.
+	fn try_match<'t, D: Doc>(
+		&self,
+		candidate: Node<'t, D>,
+		env: &mut Cow<'_, MetaVarEnv<'t, D>>,
+	) -> bool {
+		let mut scratch = env.clone();
+		if self
+			.rule
+			.match_node_with_env(candidate, &mut scratch)
+			.is_some()
+		{
+			*env = Cow::Owned(scratch.into_owned());
+			true
+		} else {
+			false
+		}
+	}
+
+	fn holds<'t, D: Doc>(&self, node: &Node<'t, D>, env: &mut Cow<'_, MetaVarEnv<'t, D>>) -> bool {
+		match self.kind {
+			RelationKind::Inside => {
+				let mut child = node.clone();
+				for ancestor in node.ancestors() {
+					let in_field = self.field.as_deref().is_none_or(|field| {
+						ancestor
+							.field_children(field)
+							.any(|node| node.node_id() == child.node_id())
+					});
+					if in_field && self.try_match(ancestor.clone(), env) {
+						return true;
+					}
+					if self.stops(&ancestor) {
+						return false;
+					}
+					child = ancestor;
+				}
+				false
+			},
+			RelationKind::Has => {
+				let mut stack: Vec<Node<'t, D>> = match self.field.as_deref() {
+					Some(field) => node.field_children(field).collect(),
+					None => node.children().collect(),
+				};
+				if matches!(self.stop_by, StopBy::Neighbor) {
+					return stack.into_iter().any(|child| self.try_match(child, env));
+				}
+				stack.reverse();
+				while let Some(candidate) = stack.pop() {
+					if self.try_match(candidate.clone(), env) {
+						return true;
+					}
+					if !self.stops(&candidate) {
+						let start = stack.len();
+						stack.extend(candidate.children());
+						stack[start..].reverse();
+					}
+				}
+				false
+			},
+			RelationKind::Follows | RelationKind::Precedes => {
+				let siblings: Vec<Node<'t, D>> = if matches!(self.kind, RelationKind::Follows) {
+					node.prev_all().collect()
+				} else {
+					node.next_all().collect()
+				};
+				for sibling in siblings {
+					if self.try_match(sibling.clone(), env) {
+						return true;
+					}
+					if self.stops(&sibling) {
+						return false;
+					}
+				}
+				false
+			},
+		}
+	}
+}
+
+impl Matcher for Rule {
+	fn match_node_with_env<'tree, D: Doc>(
+		&self,
+		node: Node<'tree, D>,
+		env: &mut Cow<MetaVarEnv<'tree, D>>,
+	) -> Option<Node<'tree, D>> {
+		match self {
+			Self::Pattern(pattern) => pattern.match_node_with_env(node, env),
+			Self::Kind(kind) => kind.match_node_with_env(node, env),
+			Self::Regex(regex) => regex.match_node_with_env(node, env),
+			Self::Relation(relation) => relation.holds(&node, env).then_some(node),
+			Self::All(rules) => {
+				let mut scratch = env.clone();
+				for rule in rules {
+					rule.match_node_with_env(node.clone(), &mut scratch)?;
+				}
+				*env = scratch;
+				Some(node)
+			},
+			Self::Any(rules) => rules.iter().find_map(|rule| {
+				let mut scratch = env.clone();
+				rule.match_node_with_env(node.clone(), &mut scratch)?;
+				*env = scratch;
+				Some(node.clone())
+			}),
+			Self::Not(rule) => {
+				let mut scratch = env.clone();
+				rule
+					.match_node_with_env(node.clone(), &mut scratch)
+					.is_none()
+					.then_some(node)
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ast_grep_core::tree_sitter::LanguageExt;
+
+	use super::*;
+
+	fn find(source: &str, rule: &str) -> Vec<String> {
+		let lang = SupportLang::TypeScript;
+		let rule = compile_rule(&parse_rule(rule).unwrap(), lang, &MatchStrictness::Smart).unwrap();
+		let ast = lang.ast_grep(source);
+		ast.root()
+			.find_all(&rule)
+			.map(|found| found.text().into_owned())
+			.collect()
+	}
+
+	#[test]
+	fn matches_relational_rules() {
+		let source = "function a() { await load(); }\nasync function b() { await save(); }\nlet x = \
+		              await fetch();\n";
+		// `await` inside a function that is not async.
+		assert_eq!(
+			find(
+				source,
+				r#"{"pattern": "await $X", "inside": {"kind": "function_declaration", "stopBy": "end", "not": {"regex": "^async"}}}"#
+			),
+			["await load()"]
+		);
+		assert_eq!(
+			find(
+				source,
+				r#"{"kind": "function_declaration", "has": {"regex": "save", "stopBy": "end"}}"#
+			)
+			.len(),
+			1
+		);
+		assert_eq!(find(source, r#"{"any": [{"pattern": "load()"}, {"pattern": "fetch()"}]}"#), [
+			"load()", "fetch()"
+		]);
+		assert_eq!(
+			find(
+				source,
+				r#"{"kind": "lexical_declaration", "follows": {"kind": "function_declaration"}}"#
+			),
+			["let x = await fetch();"]
+		);
+		let lang = SupportLang::TypeScript;
+		let compile =
+			|rule: &str| compile_rule(&parse_rule(rule).unwrap(), lang, &MatchStrictness::Smart).err();
+		assert!(compile(r#"{"not": {"kind": "identifier"}}"#).is_some());
+		assert!(compile(r#"{"kind": "no_such_kind"}"#).is_some());
+		assert!(compile(r#"{"kind": "identifier", "stopBy": "end"}"#).is_some());
+		assert!(compile(r#"{"follows": {"kind": "identifier", "field": "name"}}"#).is_some());
+		assert!(parse_rule("[1]").is_err());
+	}
+}
@@ -0,0 +1,634 @@
+//! Several searches over one tree in a single pass.
+//!
+//! `batchQuery()` runs grep, glob, and astFind queries against the same root
+//! with one filesystem scan, and reads each file at most once: its bytes go to
+//! every grep matcher and astFind pattern whose filters accept the file, and
+//! astFind queries share one parse per file and language. Results come back
+//! per query, in query order, shaped like those of `grep()`, `glob()`, and
+//! `astGrep()`.
+
+use std::{
+	collections::{HashMap, HashSet},
+	fs::File,
+	io::Read,
+	path::Path,
+};
+
+use ast_grep_core::MatchStrictness;
+use globset::GlobSet;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+use crate::{
+	ast::{self, AstFindMatch, AstFindResult},
+	ast_cache::ParsedFile,
+	ast_rule::Rule,
+	fs_cache::{self, FileType, GlobMatch},
+	glob::GlobResult,
+	glob_util,
+	grep::{self, GrepMatch, GrepResult},
+	language::SupportLang,
+	regex_engine::{PatternMatcher, RegexEngine},
+	task,
+};
+
+/// One search of a `batchQuery()`.
+#[napi(object)]
+pub struct QuerySpec {
+	/// Kind of search: "grep", "glob", or "astFind".
+	#[napi(ts_type = "\"grep\" | \"glob\" | \"astFind\"")]
+	pub kind:        String,
+	/// Regex (grep) or glob pattern (glob).
+	pub pattern:     Option<String>,
+	/// ast-grep patterns (astFind).
+	pub patterns:    Option<Vec<String>>,
+	/// Language of astFind patterns; inferred from each file's extension by
+	/// default.
+	pub lang:        Option<String>,
+	/// Glob filter on the paths of searched files (grep, astFind).
+	pub glob:        Option<String>,
+	/// Case-insensitive search (grep).
+	#[napi(js_name = "ignoreCase")]
+	pub ignore_case: Option<bool>,
+	/// Let matches span lines (grep).
+	pub multiline:   Option<bool>,
+	/// Lines of context before and after each match (grep).
+	pub context:     Option<u32>,
+	/// Maximum matches to return (default: all for grep and glob, 50 for
+	/// astFind).
+	pub limit:       Option<u32>,
+}
+
+/// Options for `batchQuery()`, shared by all of its queries.
+#[napi(object)]
+pub struct BatchQueryOptions<'env> {
+	/// Include hidden files (default: false).
+	pub hidden:     Option<bool>,
+	/// Respect .gitignore files (default: true).
+	pub gitignore:  Option<bool>,
+	/// Enable shared filesystem scan cache (default: false).
+	pub cache:      Option<bool>,
+	/// Abort signal for cancelling the operation.
+	pub signal:     Option<Unknown<'env>>,
+	/// Timeout in milliseconds for the operation.
+	#[napi(js_name = "timeoutMs")]
+	pub timeout_ms: Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:      Option<ClassInstance<'env, task::CancellationScope>>,
+}
+
+/// Result of one query; only the field of its kind is set.
+#[napi(object)]
+pub struct QueryResult {
+	pub grep:     Option<GrepResult>,
+	pub glob:     Option<GlobResult>,
+	#[napi(js_name = "astFind")]
+	pub ast_find: Option<AstFindResult>,
+}
+
+/// Result of `batchQuery()`.
+#[napi(object)]
+pub struct BatchQueryResult {
+	/// Results in query order.
+	pub results:    Vec<QueryResult>,
+	/// Files read from disk, each at most once for all queries.
+	#[napi(js_name = "filesRead")]
+	pub files_read: u32,
+}
+
+/// Which files a content query searches.
+struct FileFilter {
+	glob:         Option<GlobSet>,
+	node_modules: bool,
+}
+
+impl FileFilter {
+	fn new(glob: Option<&str>) -> Result<Self> {
+		Ok(Self {
+			glob:         glob_util::try_compile_glob(glob, true, None)?,
+			node_modules: glob.is_some_and(|glob| glob.contains("node_modules")),
+		})
+	}
+
+	fn accepts(&self, path: &str) -> bool {
+		!fs_cache::should_skip_path(Path::new(path), self.node_modules)
+			&& self.glob.as_ref().is_none_or(|glob| glob.is_match(path))
+	}
+}
+
+/// How a content query searches a file.
+#[derive(Clone, Copy)]
+enum Target {
+	/// As bytes, with a regex.
+	Text,
+	/// As a syntax tree in a language.
+	Syntax(SupportLang),
+}
+
+/// A validated query.
+enum Query {
+	Grep {
+		matcher: PatternMatcher,
+		files:   FileFilter,
+		context: u32,
+		limit:   Option<u64>,
+	},
+	Glob {
+		glob:         GlobSet,
+		node_modules: bool,
+		limit:        usize,
+	},
+	Ast {
+		patterns: Vec<String>,
+		lang:     Option<String>,
+		files:    FileFilter,
+		limit:    usize,
+		/// Each pattern compiled per language key, or why it did not compile.
+		rules:    HashMap<&'static str, Vec<std::result::Result<Rule, String>>>,
+	},
+}
+
+impl Query {
+	fn compile(spec: QuerySpec) -> Result<Self> {
+		let pattern = || {
+			spec
+				.pattern
+				.as_deref()
+				.map(str::trim)
+				.filter(|pattern| !pattern.is_empty())
+				.ok_or_else(|| Error::from_reason("`pattern` is required".to_string()))
+		};
+		match spec.kind.as_str() {
+			"grep" => Ok(Self::Grep {
+				matcher: grep::build_matcher(
+					pattern()?,
+					RegexEngine::Default,
+					spec.ignore_case.unwrap_or(false),
+					spec.multiline.unwrap_or(false),
+				)?,
+				files:   FileFilter::new(spec.glob.as_deref())?,
+				context: spec.context.unwrap_or(0),
+				limit:   spec.limit.map(u64::from),
+			}),
+			"glob" => {
+				let pattern = pattern()?;
+				Ok(Self::Glob {
+					glob:         glob_util::compile_glob(pattern, true, None)?,
+					node_modules: pattern.contains("node_modules"),
+					limit:        spec.limit.map_or(usize::MAX, |limit| limit as usize),
+				})
+			},
+			"astFind" => {
+				let patterns: Vec<String> = spec
+					.patterns
+					.unwrap_or_default()
+					.iter()
+					.map(|pattern| pattern.trim().to_string())
+					.filter(|pattern| !pattern.is_empty())
+					.collect();
+				if patterns.is_empty() {
+					return Err(Error::from_reason("`patterns` is required".to_string()));
+				}
+				let lang = spec.lang.filter(|lang| !lang.trim().is_empty());
+				if let Some(lang) = lang.as_deref() {
+					ast::resolve_supported_lang(lang)?;
+				}
+				Ok(Self::Ast {
+					patterns,
+					lang,
+					files: FileFilter::new(spec.glob.as_deref())?,
+					limit: spec.limit.unwrap_or(ast::DEFAULT_FIND_LIMIT).max(1) as usize,
+					rules: HashMap::new(),
+				})
+			},
+			kind => Err(Error::from_reason(format!(
+				"Unknown query kind '{kind}' (expected grep, glob, or astFind)"
+			))),
+		}
+	}
+
+	/// How this query searches the file at `path`, if it does.
+	fn searches(&self, path: &str) -> Option<Target> {
+		match self {
+			Self::Grep { files, .. } => files.accepts(path).then_some(Target::Text),
+			Self::Ast { lang, files, .. } => {
+				if !files.accepts(path) || !ast::is_supported_file(Path::new(path), lang.as_deref()) {
+					return None;
+				}
+				ast::resolve_language(lang.as_deref(), Path::new(path))
+					.ok()
+					.map(Target::Syntax)
+			},
+			Self::Glob { .. } => None,
+		}
+	}
+}
+
+/// What one content query found in one file.
+enum Found {
+	Grep { matches: Vec<GrepMatch>, count: u64 },
+	Ast { matches: Vec<AstFindMatch>, errors: Vec<String> },
+}
+
+/// A file some content query searches, with the queries by index.
+struct Planned<'a> {
+	path:    &'a str,
+	queries: Vec<(usize, Target)>,
+}
+
+/// Read up to `max_bytes` of `path`.
+fn read_capped(path: &Path, max_bytes: u64) -> Option<Vec<u8>> {
+	let mut content = Vec::new();
+	File::open(path)
+		.ok()?
+		.take(max_bytes)
+		.read_to_end(&mut content)
+		.ok()?;
+	Some(content)
+}
+
+/// Search one file's content for every query planned on it.
+fn search_file(
+	root: &Path,
+	planned: &Planned<'_>,
+	queries: &[Query],
+	max_bytes: u64,
+	ct: &task::CancelToken,
+) -> Option<Vec<(usize, Found)>> {
+	if ct.aborted() {
+		return None;
+	}
+	let content = read_capped(&root.join(planned.path), max_bytes)?;
+	let mut trees: HashMap<&'static str, ParsedFile> = HashMap::new();
+	let mut found = Vec::with_capacity(planned.queries.len());
+	for &(index, target) in &planned.queries {
+		match (&queries[index], target) {
+			(Query::Grep { matcher, context, limit, .. }, _) => {
+				if let Some((matches, count)) =
+					grep::search_file_bytes(matcher, planned.path, &content, *context, *limit, ct)
+				{
+					found.push((index, Found::Grep { matches, count }));
+				}
+			},
+			(Query::Ast { patterns, rules, .. }, Target::Syntax(language)) => {
+				let key = language.canonical_name();
+				let mut errors = Vec::new();
+				let parsed = match trees.entry(key) {
+					std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+					std::collections::hash_map::Entry::Vacant(entry) => {
+						let Ok(source) = std::str::from_utf8(&content) else {
+							errors.push(format!("{}: file is not valid UTF-8", planned.path));
+							found.push((index, Found::Ast { matches: Vec::new(), errors }));
+							continue;
+						};
+						entry.insert(ParsedFile::new(source.to_string(), language))
+					},
+				};
+				if parsed.has_error {
+					errors.push(format!(
+						"{}: parse error (syntax tree contains error nodes)",
+						planned.path
+					));
+				}
+				let mut matches = Vec::new();
+				for (pattern, rule) in patterns.iter().zip(&rules[key]) {
+					match rule {
+						Ok(rule) => matches.extend(ast::tree_matches(parsed, rule, planned.path)),
+						Err(error) => errors.push(format!("{pattern}: {}: {error}", planned.path)),
+					}
+				}
+				found.push((index, Found::Ast { matches, errors }));
+			},
+			_ => {},
+		}
+	}
+	Some(found)
+}
+
+fn glob_result(
+	entries: &[&GlobMatch],
+	glob: &GlobSet,
+	node_modules: bool,
+	limit: usize,
+) -> GlobResult {
+	let matches: Vec<GlobMatch> = entries
+		.iter()
+		.filter(|entry| {
+			!fs_cache::should_skip_path(Path::new(&entry.path), node_modules)
+				&& glob.is_match(&entry.path)
+		})
+		.take(limit)
+		.map(|&entry| entry.clone())
+		.collect();
+	GlobResult {
+		total_matches: crate::utils::clamp_u32(matches.len() as u64),
+		matches,
+		cursor: None,
+		trace: None,
+	}
+}
+
+/// Fold per-file grep results, in path order, into a `grep()` result.
+fn grep_result(
+	files: Vec<(Vec<GrepMatch>, u64)>,
+	searched: usize,
+	limit: Option<u64>,
+) -> GrepResult {
+	let mut matches = Vec::new();
+	let mut total_matches = 0u64;
+	let mut files_with_matches = 0u32;
+	let mut limit_reached = false;
+	let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+	for (file_matches, count) in files {
+		if count == 0 {
+			continue;
+		}
+		files_with_matches = files_with_matches.saturating_add(1);
+		total_matches = total_matches.saturating_add(count);
+		let room = limit - matches.len();
+		limit_reached |= file_matches.len() > room || (room == 0 && count > 0);
+		matches.extend(file_matches.into_iter().take(room));
+	}
+	GrepResult {
+		matches,
+		total_matches: crate::utils::clamp_u32(total_matches),
+		files_with_matches,
+		files_searched: crate::utils::clamp_u32(searched as u64),
+		limit_reached: limit_reached.then_some(true),
+		truncated_files: None,
+		directories: None,
+		reused_files: None,
+		heatmaps: None,
+		blocks: None,
+		cursor: None,
+		budget_exhausted: None,
+		trace: None,
+	}
+}
+
+/// Fold per-file astFind results, in path order, into an `astGrep()` result.
+fn ast_result(
+	files: Vec<(Vec<AstFindMatch>, Vec<String>)>,
+	searched: usize,
+	limit: usize,
+) -> AstFindResult {
+	let mut matches = Vec::new();
+	let mut parse_errors = Vec::new();
+	let mut total_matches = 0usize;
+	let mut files_with_matches = 0usize;
+	for (mut file_matches, errors) in files {
+		parse_errors.extend(errors);
+		if file_matches.is_empty() {
+			continue;
+		}
+		file_matches.sort_by_key(|matched| (matched.byte_start, matched.byte_end));
+		files_with_matches += 1;
+		total_matches += file_matches.len();
+		matches.extend(file_matches);
+	}
+	let limit_reached = matches.len() > limit;
+	matches.truncate(limit);
+	AstFindResult {
+		matches,
+		total_matches: crate::utils::clamp_u32(total_matches as u64),
+		files_with_matches: crate::utils::clamp_u32(files_with_matches as u64),
+		files_searched: crate::utils::clamp_u32(searched as u64),
+		limit_reached,
+		parse_errors: (!parse_errors.is_empty()).then_some(parse_errors),
+		files: None,
+		trace: None,
+	}
+}
+
+fn batch_query_sync(
+	root: &Path,
+	specs: Vec<QuerySpec>,
+	policy: &fs_cache::ScanPolicy,
+	use_cache: bool,
+	ct: &task::CancelToken,
+) -> Result<BatchQueryResult> {
+	let mut queries = specs
+		.into_iter()
+		.enumerate()
+		.map(|(index, spec)| {
+			Query::compile(spec)
+				.map_err(|err| Error::from_reason(format!("queries[{index}]: {}", err.reason)))
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	let scanned = if use_cache {
+		fs_cache::get_or_scan(root, policy, ct)?.entries
+	} else {
+		fs_cache::force_rescan(root, policy, false, ct)?
+	};
+	let mut entries: Vec<&GlobMatch> = scanned.iter().collect();
+	entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+	let plan: Vec<Planned<'_>> = entries
+		.iter()
+		.filter(|entry| entry.file_type == FileType::File)
+		.filter_map(|entry| {
+			let queries: Vec<_> = queries
+				.iter()
+				.enumerate()
+				.filter_map(|(index, query)| Some((index, query.searches(&entry.path)?)))
+				.collect();
+			(!queries.is_empty()).then_some(Planned { path: &entry.path, queries })
+		})
+		.collect();
+	ct.heartbeat()?;
+
+	// Compile astFind patterns for every language they will meet.
+	let mut languages: Vec<HashSet<SupportLang>> = vec![HashSet::new(); queries.len()];
+	for planned in &plan {
+		for &(index, target) in &planned.queries {
+			if let Target::Syntax(language) = target {
+				languages[index].insert(language);
+			}
+		}
+	}
+	for (query, languages) in queries.iter_mut().zip(languages) {
+		if let Query::Ast { patterns, rules, .. } = query {
+			for language in languages {
+				let compiled = patterns
+					.iter()
+					.map(|pattern| {
+						ast::compile_pattern(pattern, None, &MatchStrictness::Smart, language)
+							.map(Rule::Pattern)
+							.map_err(|err| err.reason.clone())
+					})
+					.collect();
+				rules.insert(language.canonical_name(), compiled);
+			}
+		}
+	}
+
+	let max_bytes = grep::resolve_max_file_bytes(None);
+	let found: Vec<Option<Vec<(usize, Found)>>> = plan
+		.par_iter()
+		.map(|planned| search_file(root, planned, &queries, max_bytes, ct))
+		.collect();
+	ct.heartbeat()?;
+	let files_read = found.iter().flatten().count();
+
+	let mut grep_files: Vec<Vec<(Vec<GrepMatch>, u64)>> =
+		queries.iter().map(|_| Vec::new()).collect();
+	let mut ast_files: Vec<Vec<(Vec<AstFindMatch>, Vec<String>)>> =
+		queries.iter().map(|_| Vec::new()).collect();
+	let mut searched = vec![0usize; queries.len()];
+	for (index, result) in found.into_iter().flatten().flatten() {
+		searched[index] += 1;
+		match result {
+			Found::Grep { matches, count } => grep_files[index].push((matches, count)),
+			Found::Ast { matches, errors } => ast_files[index].push((matches, errors)),
+		}
+	}
+
+	let results = queries
+		.iter()
+		.zip(grep_files.into_iter().zip(ast_files))
+		.zip(searched)
+		.map(|((query, (grep_files, ast_files)), searched)| match query {
+			Query::Grep { limit, .. } => QueryResult {
+				grep:     Some(grep_result(grep_files, searched, *limit)),
+				glob:     None,
+				ast_find: None,
+			},
+			Query::Glob { glob, node_modules, limit } => QueryResult {
+				grep:     None,
+				glob:     Some(glob_result(&entries, glob, *node_modules, *limit)),
+				ast_find: None,
+			},
+			Query::Ast { limit, .. } => QueryResult {
+				grep:     None,
+				glob:     None,
+				ast_find: Some(ast_result(ast_files, searched, *limit)),
+			},
+		})
+		.collect();
+	Ok(BatchQueryResult { results, files_read: crate::utils::clamp_u32(files_read as u64) })
+}
+
+/// Run several grep, glob, and astFind queries over `root` in one pass.
+///
+/// The tree is scanned once and each file read at most once, however many
+/// queries search it; results are returned per query, in query order.
+///
+/// # Errors
+/// Returns an error when `root` is not a directory, a query is invalid, or
+/// cancellation/timeout is triggered.
+#[napi(js_name = "batchQuery")]
+pub fn batch_query(
+	root: String,
+	queries: Vec<QuerySpec>,
+	options: Option<BatchQueryOptions<'_>>,
+) -> task::Async<BatchQueryResult> {
+	let (hidden, gitignore, cache, ct) = match options {
+		Some(BatchQueryOptions { hidden, gitignore, cache, signal, timeout_ms, scope }) => (
+			hidden,
+			gitignore,
+			cache,
+			task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref()),
+		),
+		None => (None, None, None, task::CancelToken::default()),
+	};
+	task::blocking("batch_query", ct, move |ct| {
+		let root = fs_cache::resolve_search_path(&root)?;
+		let policy = fs_cache::ScanPolicy::new(hidden.unwrap_or(false), gitignore.unwrap_or(true));
+		batch_query_sync(&root, queries, &policy, cache.unwrap_or(false), &ct)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	use super::*;
+
+	fn spec(kind: &str) -> QuerySpec {
+		QuerySpec {
+			kind:        kind.to_string(),
+			pattern:     None,
+			patterns:    None,
+			lang:        None,
+			glob:        None,
+			ignore_case: None,
+			multiline:   None,
+			context:     None,
+			limit:       None,
+		}
+	}
+
+	#[test]
+	fn runs_queries_in_one_pass() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-batch-query-test-{unique}"));
+		std::fs::create_dir_all(root.join("src")).expect("temp dir should be created");
+		std::fs::write(root.join("src/a.rs"), "fn alpha() {}\nfn beta() { alpha(); }\n")
+			.expect("fixture should be written");
+		std::fs::write(root.join("notes.txt"), "alpha\n").expect("fixture should be written");
+
+		let queries = vec![
+			QuerySpec { pattern: Some("alpha".to_string()), ..spec("grep") },
+			QuerySpec { pattern: Some("*.rs".to_string()), ..spec("glob") },
+			QuerySpec { patterns: Some(vec!["alpha()".to_string()]), ..spec("astFind") },
+			QuerySpec {
+				pattern: Some("alpha".to_string()),
+				glob: Some("*.txt".to_string()),
+				limit: Some(0),
+				..spec("grep")
+			},
+		];
+		let result = batch_query_sync(
+			&root,
+			queries,
+			&fs_cache::ScanPolicy::new(false, true),
+			false,
+			&task::CancelToken::default(),
+		)
+		.expect("batch should succeed");
+		assert_eq!(result.files_read, 2);
+
+		let grep = result.results[0].grep.as_ref().unwrap();
+		let lines: Vec<_> = grep
+			.matches
+			.iter()
+			.map(|m| format!("{}:{}", m.path, m.line_number))
+			.collect();
+		assert_eq!(lines, ["notes.txt:1", "src/a.rs:1", "src/a.rs:2"]);
+		assert_eq!((grep.files_searched, grep.files_with_matches), (2, 2));
+
+		let glob = result.results[1].glob.as_ref().unwrap();
+		assert_eq!(
+			glob
+				.matches
+				.iter()
+				.map(|m| m.path.as_str())
+				.collect::<Vec<_>>(),
+			["src/a.rs"]
+		);
+
+		let ast = result.results[2].ast_find.as_ref().unwrap();
+		assert_eq!((ast.files_searched, ast.total_matches), (1, 1));
+		assert_eq!((ast.matches[0].start_line, ast.matches[0].text.as_str()), (2, "alpha()"));
+
+		let limited = result.results[3].grep.as_ref().unwrap();
+		assert!(limited.matches.is_empty());
+		assert_eq!((limited.files_searched, limited.limit_reached), (1, Some(true)));
+
+		let invalid = batch_query_sync(
+			&root,
+			vec![spec("grep")],
+			&fs_cache::ScanPolicy::new(false, true),
+			false,
+			&task::CancelToken::default(),
+		);
+		assert!(invalid.is_err_and(|err| err.reason == "queries[0]: `pattern` is required"));
+
+		let _ = std::fs::remove_dir_all(&root);
+	}
+}
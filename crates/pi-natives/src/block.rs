@@ -0,0 +1,190 @@
+//! Exact multi-line block search for "replace this block" edits.
+//!
+//! `findBlock()` compares a block against every run of whole lines in a file,
+//! optionally ignoring indentation or collapsing whitespace, and returns the
+//! byte ranges of the runs that match. Each file line is normalized once, so
+//! checking many candidate blocks against a large file stays cheap.
+
+use std::borrow::Cow;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Options for `findBlock()`.
+#[napi(object)]
+#[derive(Default)]
+pub struct FindBlockOptions {
+	/// Ignore leading whitespace on each line (default: false).
+	#[napi(js_name = "ignoreIndentation")]
+	pub ignore_indentation:   Option<bool>,
+	/// Collapse runs of spaces and tabs to one space and ignore trailing
+	/// whitespace on each line (default: false).
+	#[napi(js_name = "normalizeWhitespace")]
+	pub normalize_whitespace: Option<bool>,
+}
+
+/// A run of lines matching the block.
+#[napi(object)]
+pub struct BlockMatch {
+	/// Byte offset of the first matched line's start.
+	pub start:      u32,
+	/// Byte offset of the last matched line's end, before its line break.
+	pub end:        u32,
+	/// First matched line (1-indexed).
+	#[napi(js_name = "startLine")]
+	pub start_line: u32,
+	/// Last matched line (1-indexed, inclusive).
+	#[napi(js_name = "endLine")]
+	pub end_line:   u32,
+}
+
+#[derive(Clone, Copy)]
+struct Normalize {
+	indentation: bool,
+	whitespace:  bool,
+}
+
+impl Normalize {
+	fn apply(self, line: &str) -> Cow<'_, str> {
+		let line = if self.indentation {
+			line.trim_start_matches([' ', '\t'])
+		} else {
+			line
+		};
+		if !self.whitespace {
+			return Cow::Borrowed(line);
+		}
+		let line = line.trim_end_matches([' ', '\t']);
+		if !line.contains("  ") && !line.contains('\t') {
+			return Cow::Borrowed(line);
+		}
+		let mut out = String::with_capacity(line.len());
+		let mut in_space = false;
+		for ch in line.chars() {
+			if ch == ' ' || ch == '\t' {
+				if !in_space {
+					out.push(' ');
+				}
+				in_space = true;
+			} else {
+				out.push(ch);
+				in_space = false;
+			}
+		}
+		Cow::Owned(out)
+	}
+}
+
+/// Byte spans of each line in `text`, excluding `\n` and `\r\n` terminators.
+fn line_spans(text: &str) -> Vec<(usize, usize)> {
+	let mut spans = Vec::new();
+	let mut start = 0;
+	for (index, byte) in text.bytes().enumerate() {
+		if byte == b'\n' {
+			let end = if index > start && text.as_bytes()[index - 1] == b'\r' {
+				index - 1
+			} else {
+				index
+			};
+			spans.push((start, end));
+			start = index + 1;
+		}
+	}
+	if start < text.len() {
+		spans.push((start, text.len()));
+	}
+	spans
+}
+
+fn find_block_sync(text: &str, block: &str, normalize: Normalize) -> Result<Vec<BlockMatch>> {
+	let block = block.strip_suffix('\n').unwrap_or(block);
+	let block = block.strip_suffix('\r').unwrap_or(block);
+	if block.is_empty() {
+		return Err(Error::from_reason("findBlock: block is empty"));
+	}
+	let needle: Vec<Cow<'_, str>> = line_spans(block)
+		.into_iter()
+		.map(|(start, end)| normalize.apply(&block[start..end]))
+		.collect();
+	let spans = line_spans(text);
+	let lines: Vec<Cow<'_, str>> = spans
+		.iter()
+		.map(|(start, end)| normalize.apply(&text[*start..*end]))
+		.collect();
+
+	let mut matches = Vec::new();
+	if needle.len() > lines.len() {
+		return Ok(matches);
+	}
+	for first in 0..=lines.len() - needle.len() {
+		if lines[first..first + needle.len()] == needle[..] {
+			let last = first + needle.len() - 1;
+			matches.push(BlockMatch {
+				start:      crate::utils::clamp_u32(spans[first].0 as u64),
+				end:        crate::utils::clamp_u32(spans[last].1 as u64),
+				start_line: crate::utils::clamp_u32(first as u64 + 1),
+				end_line:   crate::utils::clamp_u32(last as u64 + 1),
+			});
+		}
+	}
+	Ok(matches)
+}
+
+/// Find every run of whole lines in `fileText` that matches `block`.
+///
+/// Lines compare exactly unless `ignoreIndentation` or `normalizeWhitespace`
+/// is set; line endings (`\n` or `\r\n`) never matter, and a trailing line
+/// break on `block` is ignored. Overlapping runs are all reported.
+///
+/// # Errors
+/// Returns an error if `block` is empty.
+#[napi(js_name = "findBlock")]
+pub fn find_block(
+	file_text: String,
+	block: String,
+	options: Option<FindBlockOptions>,
+) -> Result<Vec<BlockMatch>> {
+	let options = options.unwrap_or_default();
+	let normalize = Normalize {
+		indentation: options.ignore_indentation.unwrap_or(false),
+		whitespace:  options.normalize_whitespace.unwrap_or(false),
+	};
+	find_block_sync(&file_text, &block, normalize)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn matches_blocks_modulo_whitespace() {
+		let text = "fn a() {\r\n\tlet x =  1;\r\n\treturn x;\r\n}\nfn b() {\n    let x = 1;   \n    \
+		            return x;\n}\n";
+		let block = "let x = 1;\nreturn x;\n";
+		let find = |indentation, whitespace| {
+			find_block_sync(text, block, Normalize { indentation, whitespace })
+				.unwrap()
+				.iter()
+				.map(|found| {
+					(found.start_line, found.end_line, &text[found.start as usize..found.end as usize])
+				})
+				.map(|(start, end, source)| (start, end, source.to_string()))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(find(false, false).len(), 0);
+		assert_eq!(find(true, false).len(), 0);
+		assert_eq!(find(true, true), [
+			(2, 3, "\tlet x =  1;\r\n\treturn x;".to_string()),
+			(6, 7, "    let x = 1;   \n    return x;".to_string()),
+		]);
+		assert_eq!(
+			find_block_sync(text, "}", Normalize { indentation: false, whitespace: false })
+				.unwrap()
+				.len(),
+			2
+		);
+		assert!(
+			find_block_sync(text, "\n", Normalize { indentation: true, whitespace: true }).is_err()
+		);
+	}
+}
@@ -0,0 +1,306 @@
+//! Previewed change sets shared by the mutating APIs.
+//!
+//! A dry run of a writer (e.g. `astEdit`) records every file it would change
+//! in a [`ChangeSet`]: per-file unified diffs, content hashes, and conflicts
+//! (edits that could not be computed). The set is kept by id, so an approval
+//! UI can show one shape for every writer and later call `applyChangeSet(id)`
+//! to write exactly what was previewed. Applying re-hashes every file first
+//! and refuses the whole set if any changed since the preview, then writes
+//! through the journal so the set lands atomically.
+//!
+//! Only the most recent [`MAX_PENDING`] sets are kept.
+
+use std::{
+	collections::VecDeque,
+	path::{Path, PathBuf},
+	sync::{
+		LazyLock,
+		atomic::{AtomicU64, Ordering},
+	},
+};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use parking_lot::Mutex;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::{ast_cache, diff, fs_cache, journal, readonly, task};
+
+/// Pending change sets kept for `applyChangeSet()`; older ones are dropped.
+const MAX_PENDING: usize = 32;
+
+/// One file in a change set.
+#[napi(object)]
+pub struct ChangeSetFile {
+	/// File path as reported by the writer (usually relative to its root).
+	pub path:        String,
+	/// "modified", "created", or "deleted".
+	#[napi(ts_type = "\"modified\" | \"created\" | \"deleted\"")]
+	pub status:      String,
+	/// xxh3 hash (16 hex digits) of the content the change was computed from.
+	#[napi(js_name = "beforeHash")]
+	pub before_hash: Option<String>,
+	/// xxh3 hash of the content after the change.
+	#[napi(js_name = "afterHash")]
+	pub after_hash:  Option<String>,
+	/// Unified diff of the change.
+	pub diff:        diff::FileDiff,
+}
+
+/// A file a change set could not cover.
+#[napi(object)]
+pub struct ChangeSetConflict {
+	/// File path as reported by the writer.
+	pub path:   String,
+	/// Why the file was left out or could not be applied.
+	pub reason: String,
+}
+
+/// Previewed file changes that can be applied later with `applyChangeSet()`.
+#[napi(object)]
+pub struct ChangeSet {
+	/// Id to pass to `applyChangeSet()` or `discardChangeSet()`.
+	pub id:        String,
+	/// Changed files, in path order.
+	pub files:     Vec<ChangeSetFile>,
+	/// Lines added across all files.
+	pub additions: u32,
+	/// Lines removed across all files.
+	pub deletions: u32,
+	/// Files left out of the set.
+	pub conflicts: Vec<ChangeSetConflict>,
+}
+
+struct PendingFile {
+	path:        String,
+	absolute:    PathBuf,
+	before_hash: Option<u64>,
+	after:       Option<Vec<u8>>,
+}
+
+struct PendingChangeSet {
+	id:    String,
+	files: Vec<PendingFile>,
+}
+
+static PENDING: LazyLock<Mutex<VecDeque<PendingChangeSet>>> =
+	LazyLock::new(|| Mutex::new(VecDeque::with_capacity(MAX_PENDING)));
+
+fn hash_hex(hash: u64) -> String {
+	format!("{hash:016x}")
+}
+
+fn next_id() -> String {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+	format!("cs-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed) + 1)
+}
+
+/// Collects file changes during a writer's dry run.
+#[derive(Default)]
+pub struct ChangeSetBuilder {
+	files:     Vec<(ChangeSetFile, PendingFile)>,
+	conflicts: Vec<ChangeSetConflict>,
+}
+
+impl ChangeSetBuilder {
+	/// Record a change of `absolute` (shown as `path`); `None` content means
+	/// the file is absent before (created) or after (deleted) the change.
+	pub fn add(&mut self, path: &str, absolute: &Path, before: Option<&str>, after: Option<&str>) {
+		let status = match (before, after) {
+			(None, _) => "created",
+			(_, None) => "deleted",
+			_ => "modified",
+		};
+		let before_hash = before.map(|text| xxh3_64(text.as_bytes()));
+		let after_hash = after.map(|text| xxh3_64(text.as_bytes()));
+		let diff = diff::unified_diff(
+			path,
+			before.unwrap_or_default(),
+			after.unwrap_or_default(),
+			diff::DEFAULT_CONTEXT,
+		);
+		self.files.push((
+			ChangeSetFile {
+				path: path.to_string(),
+				status: status.to_string(),
+				before_hash: before_hash.map(hash_hex),
+				after_hash: after_hash.map(hash_hex),
+				diff,
+			},
+			PendingFile {
+				path: path.to_string(),
+				absolute: absolute.to_path_buf(),
+				before_hash,
+				after: after.map(|text| text.as_bytes().to_vec()),
+			},
+		));
+	}
+
+	/// Record a file the writer could not change.
+	pub fn conflict(&mut self, path: &str, reason: impl Into<String>) {
+		self
+			.conflicts
+			.push(ChangeSetConflict { path: path.to_string(), reason: reason.into() });
+	}
+
+	/// Register the set for `applyChangeSet()` and return its preview.
+	pub fn finish(mut self) -> ChangeSet {
+		self
+			.files
+			.sort_by(|left, right| left.0.path.cmp(&right.0.path));
+		let id = next_id();
+		let (files, pending): (Vec<_>, Vec<_>) = self.files.into_iter().unzip();
+		let additions = files.iter().map(|file| file.diff.additions).sum();
+		let deletions = files.iter().map(|file| file.diff.deletions).sum();
+
+		let mut registry = PENDING.lock();
+		registry.truncate(MAX_PENDING - 1);
+		registry.push_front(PendingChangeSet { id: id.clone(), files: pending });
+		ChangeSet { id, files, additions, deletions, conflicts: self.conflicts }
+	}
+}
+
+/// Outcome of `applyChangeSet()`.
+#[napi(object)]
+pub struct ApplyChangeSetResult {
+	/// Whether the files were written.
+	pub applied:       bool,
+	/// Number of files written or deleted.
+	#[napi(js_name = "filesWritten")]
+	pub files_written: u32,
+	/// Files that changed since the preview; nothing is written when any
+	/// exist, and the set stays pending.
+	pub conflicts:     Vec<ChangeSetConflict>,
+}
+
+fn apply_sync(id: &str) -> Result<ApplyChangeSetResult> {
+	let Some(set) = ({
+		let mut registry = PENDING.lock();
+		let position = registry.iter().position(|set| set.id == id);
+		position.and_then(|position| registry.remove(position))
+	}) else {
+		return Err(Error::from_reason(format!("Unknown or expired change set: {id}")));
+	};
+
+	let mut entries = Vec::with_capacity(set.files.len());
+	let mut conflicts = Vec::new();
+	for file in &set.files {
+		let current = match std::fs::read(&file.absolute) {
+			Ok(bytes) => Some(bytes),
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+			Err(err) => {
+				conflicts
+					.push(ChangeSetConflict { path: file.path.clone(), reason: err.to_string() });
+				continue;
+			},
+		};
+		let current_hash = current.as_deref().map(xxh3_64);
+		if current_hash != file.before_hash {
+			let reason = match (file.before_hash, current_hash) {
+				(Some(_), None) => "file was deleted since the preview",
+				(None, Some(_)) => "file was created since the preview",
+				_ => "file changed since the preview",
+			};
+			conflicts
+				.push(ChangeSetConflict { path: file.path.clone(), reason: reason.to_string() });
+			continue;
+		}
+		entries.push(journal::JournalEntry {
+			path:   file.absolute.clone(),
+			before: current,
+			after:  file.after.clone(),
+		});
+	}
+
+	if !conflicts.is_empty() {
+		// Keep the set so the caller can inspect it or re-run the preview.
+		PENDING.lock().push_front(set);
+		return Ok(ApplyChangeSetResult { applied: false, files_written: 0, conflicts });
+	}
+	journal::apply(&entries)?;
+	for entry in &entries {
+		ast_cache::invalidate_path(&entry.path);
+		if let Some(parent) = entry.path.parent() {
+			fs_cache::invalidate_path(parent);
+		}
+	}
+	Ok(ApplyChangeSetResult {
+		applied: true,
+		files_written: crate::utils::clamp_u32(entries.len() as u64),
+		conflicts,
+	})
+}
+
+/// Write a previewed change set atomically.
+///
+/// Every file is re-hashed first; if any changed since the preview, nothing
+/// is written and the conflicts are returned.
+///
+/// # Errors
+/// Fails when the id is unknown or expired, when read-only mode is on, or
+/// when a write fails (earlier writes are rolled back).
+#[napi(js_name = "applyChangeSet")]
+pub fn apply_change_set(id: String) -> Result<task::Async<ApplyChangeSetResult>> {
+	readonly::ensure_writable("applyChangeSet")?;
+	Ok(task::blocking("changeset.apply", (), move |_| apply_sync(&id)))
+}
+
+/// Forget a previewed change set; returns whether it was pending.
+#[napi(js_name = "discardChangeSet")]
+pub fn discard_change_set(id: String) -> bool {
+	let mut registry = PENDING.lock();
+	let before = registry.len();
+	registry.retain(|set| set.id != id);
+	registry.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+
+	#[test]
+	fn applies_previewed_changes_unless_stale() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let dir = std::env::temp_dir().join(format!("pi-changeset-test-{unique}"));
+		fs::create_dir_all(&dir).unwrap();
+		let edited = dir.join("a.txt");
+		let created = dir.join("b.txt");
+		fs::write(&edited, "one\n").unwrap();
+
+		let mut builder = ChangeSetBuilder::default();
+		builder.add("b.txt", &created, None, Some("new\n"));
+		builder.add("a.txt", &edited, Some("one\n"), Some("two\n"));
+		builder.conflict("c.txt", "overlapping edits");
+		let set = builder.finish();
+		let paths: Vec<_> = set
+			.files
+			.iter()
+			.map(|file| (file.path.as_str(), file.status.as_str()))
+			.collect();
+		assert_eq!(paths, [("a.txt", "modified"), ("b.txt", "created")]);
+		assert_eq!((set.additions, set.deletions, set.conflicts.len()), (2, 1, 1));
+
+		fs::write(&edited, "changed\n").unwrap();
+		let stale = apply_sync(&set.id).unwrap();
+		assert!(!stale.applied);
+		assert_eq!(stale.conflicts[0].path, "a.txt");
+		assert!(!created.exists());
+
+		fs::write(&edited, "one\n").unwrap();
+		let applied = apply_sync(&set.id).unwrap();
+		assert_eq!((applied.applied, applied.files_written), (true, 2));
+		assert_eq!(fs::read_to_string(&edited).unwrap(), "two\n");
+		assert_eq!(fs::read_to_string(&created).unwrap(), "new\n");
+		assert!(apply_sync(&set.id).is_err());
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+}
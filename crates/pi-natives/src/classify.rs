@@ -0,0 +1,269 @@
+//! Path classification: source, test, fixture, config, or docs.
+//!
+//! `classifyPath()` applies per-ecosystem naming conventions (`*.test.ts`,
+//! `test_*.py`, `*_test.go`, `src/test/java`, `__fixtures__`, `testdata`, ...)
+//! so every caller agrees on what counts as a test. `grep`'s `type` option
+//! uses the same rules for `type: "test"` and `type: "!test"`.
+
+use std::path::Path;
+
+use ast_grep_core::Language;
+use napi_derive::napi;
+
+use crate::language::SupportLang;
+
+/// Role of a file in its project.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathClass {
+	Source,
+	Test,
+	Fixture,
+	Config,
+	Docs,
+}
+
+impl PathClass {
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Source => "source",
+			Self::Test => "test",
+			Self::Fixture => "fixture",
+			Self::Config => "config",
+			Self::Docs => "docs",
+		}
+	}
+
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"source" | "src" => Self::Source,
+			"test" | "tests" => Self::Test,
+			"fixture" | "fixtures" => Self::Fixture,
+			"config" => Self::Config,
+			"docs" | "doc" => Self::Docs,
+			_ => return None,
+		})
+	}
+}
+
+/// Directories holding test data rather than tests.
+const FIXTURE_DIRS: &[&str] = &[
+	"fixtures",
+	"fixture",
+	"__fixtures__",
+	"__snapshots__",
+	"testdata",
+	"test-data",
+	"test_data",
+	"snapshots",
+];
+
+/// Directories holding tests (`src/test/java`, `spec/`, `__tests__/`, ...).
+const TEST_DIRS: &[&str] = &["test", "tests", "__tests__", "__mocks__", "spec", "specs", "e2e"];
+
+const DOC_DIRS: &[&str] = &["docs", "doc", "documentation"];
+
+const DOC_EXTENSIONS: &[&str] = &["md", "mdx", "markdown", "rst", "adoc", "asciidoc", "org"];
+
+/// File stems that are docs whatever their extension.
+const DOC_STEMS: &[&str] = &[
+	"readme",
+	"changelog",
+	"changes",
+	"license",
+	"licence",
+	"contributing",
+	"authors",
+	"notice",
+	"code_of_conduct",
+];
+
+const CONFIG_NAMES: &[&str] = &[
+	"package.json",
+	"package-lock.json",
+	"yarn.lock",
+	"pnpm-lock.yaml",
+	"pnpm-workspace.yaml",
+	"bun.lock",
+	"bun.lockb",
+	"deno.json",
+	"deno.jsonc",
+	"cargo.toml",
+	"cargo.lock",
+	"rust-toolchain",
+	"rust-toolchain.toml",
+	"go.mod",
+	"go.sum",
+	"go.work",
+	"pyproject.toml",
+	"setup.py",
+	"setup.cfg",
+	"pipfile",
+	"pipfile.lock",
+	"poetry.lock",
+	"gemfile",
+	"gemfile.lock",
+	"pom.xml",
+	"build.gradle",
+	"build.gradle.kts",
+	"settings.gradle",
+	"settings.gradle.kts",
+	"composer.json",
+	"makefile",
+	"dockerfile",
+	"justfile",
+	"biome.json",
+	"biome.jsonc",
+];
+
+const CONFIG_EXTENSIONS: &[&str] =
+	&["toml", "yaml", "yml", "ini", "cfg", "conf", "properties", "editorconfig", "lock"];
+
+/// Whether `name` (lowercase) follows a test file naming convention.
+fn is_test_file(name: &str, original: &str) -> bool {
+	let (stem, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+	// JS/TS and friends: `x.test.ts`, `x.spec.tsx`, `x.e2e.js`.
+	if stem
+		.split('.')
+		.skip(1)
+		.any(|part| matches!(part, "test" | "spec" | "e2e"))
+	{
+		return true;
+	}
+	match ext {
+		"py" => stem.starts_with("test_") || stem.ends_with("_test") || stem == "conftest",
+		"go" => stem.ends_with("_test"),
+		"rb" => stem.ends_with("_spec") || stem.ends_with("_test"),
+		"exs" => stem.ends_with("_test"),
+		// JVM, .NET, PHP, and Swift: `FooTest.java`, `FooTests.cs`, `FooIT.kt`.
+		"java" | "kt" | "scala" | "groovy" | "cs" | "fs" | "php" | "swift" => {
+			let stem = &original[..stem.len()];
+			stem.ends_with("Test")
+				|| stem.ends_with("Tests")
+				|| stem.ends_with("IT")
+				|| stem.ends_with("Spec")
+		},
+		_ => false,
+	}
+}
+
+/// Classify a path by naming conventions alone; the file is not read.
+///
+/// Fixture directories win over test directories (`tests/fixtures/x.ts` is a
+/// fixture), tests win over docs and config, and anything else is source.
+pub fn classify(path: &Path) -> PathClass {
+	let normalized = path.to_string_lossy().replace('\\', "/");
+	let mut components: Vec<&str> = normalized
+		.split('/')
+		.filter(|part| !part.is_empty() && *part != ".")
+		.collect();
+	let Some(original) = components.pop() else {
+		return PathClass::Source;
+	};
+	let name = original.to_ascii_lowercase();
+	let dirs: Vec<String> = components
+		.iter()
+		.map(|dir| dir.to_ascii_lowercase())
+		.collect();
+	let in_dir = |names: &[&str]| dirs.iter().any(|dir| names.contains(&dir.as_str()));
+
+	let ext = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+	if in_dir(FIXTURE_DIRS) || ext == "snap" {
+		return PathClass::Fixture;
+	}
+	// `Foo.Tests` projects in .NET and `FooTests` targets in Swift packages.
+	let test_project = components.iter().any(|dir| dir.ends_with("Tests"));
+	if in_dir(TEST_DIRS) || test_project || is_test_file(&name, original) {
+		return PathClass::Test;
+	}
+
+	let stem = name.split('.').next().unwrap_or_default();
+	if DOC_EXTENSIONS.contains(&ext) || DOC_STEMS.contains(&stem) {
+		return PathClass::Docs;
+	}
+	if in_dir(DOC_DIRS) && SupportLang::from_path(Path::new(&name)).is_none() {
+		return PathClass::Docs;
+	}
+
+	// Dotfiles such as `.eslintrc`, `.prettierrc.json`, `.gitignore`.
+	let dotfile = name
+		.strip_prefix('.')
+		.and_then(|rest| rest.split('.').next())
+		.is_some_and(|base| base.ends_with("rc") || base.ends_with("ignore") || base == "env");
+	let is_config = CONFIG_NAMES.contains(&name.as_str())
+		|| CONFIG_EXTENSIONS.contains(&ext)
+		|| dotfile
+		// `vite.config.ts`, `jest.config.js`, `tsconfig.build.json`.
+		|| name.split('.').nth(1) == Some("config")
+		|| name.starts_with("tsconfig")
+		|| name.starts_with("jsconfig")
+		// JSON at the root configures tools; elsewhere it is usually data.
+		|| (ext == "json" && dirs.is_empty())
+		|| dirs.first().is_some_and(|dir| dir == ".github" || dir == ".circleci");
+	if is_config {
+		PathClass::Config
+	} else {
+		PathClass::Source
+	}
+}
+
+/// Classify a path as "source", "test", "fixture", "config", or "docs" by
+/// ecosystem naming conventions.
+#[napi(
+	js_name = "classifyPath",
+	ts_return_type = "\"source\" | \"test\" | \"fixture\" | \"config\" | \"docs\""
+)]
+pub fn classify_path(path: String) -> &'static str {
+	classify(Path::new(&path)).as_str()
+}
+
+/// Classify many paths in one call; see `classifyPath`.
+#[napi(
+	js_name = "classifyPaths",
+	ts_return_type = "Array<\"source\" | \"test\" | \"fixture\" | \"config\" | \"docs\">"
+)]
+pub fn classify_paths(paths: Vec<String>) -> Vec<&'static str> {
+	paths
+		.iter()
+		.map(|path| classify(Path::new(path)).as_str())
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn classifies_by_convention() {
+		let cases = [
+			("src/lib.rs", PathClass::Source),
+			("src/app.test.ts", PathClass::Test),
+			("src/Button.spec.tsx", PathClass::Test),
+			("pkg/server_test.go", PathClass::Test),
+			("tests/test_api.py", PathClass::Test),
+			("app/conftest.py", PathClass::Test),
+			("src/test/java/com/acme/FooTest.java", PathClass::Test),
+			("MyLib.Tests/ParserTests.cs", PathClass::Test),
+			("spec/models/user_spec.rb", PathClass::Test),
+			("src/__tests__/fixtures/input.ts", PathClass::Fixture),
+			("pkg/parser/testdata/case1.go", PathClass::Fixture),
+			("src/__snapshots__/view.test.ts.snap", PathClass::Fixture),
+			("README.md", PathClass::Docs),
+			("docs/guide/setup.txt", PathClass::Docs),
+			("docs/examples/demo.ts", PathClass::Source),
+			("LICENSE", PathClass::Docs),
+			(".env.local", PathClass::Config),
+			("package.json", PathClass::Config),
+			("tsconfig.build.json", PathClass::Config),
+			("vite.config.ts", PathClass::Config),
+			(".eslintrc.json", PathClass::Config),
+			(".github/workflows/ci.yml", PathClass::Config),
+			("crates/core/Cargo.toml", PathClass::Config),
+			("src/data/countries.json", PathClass::Source),
+			("src/contest.py", PathClass::Source),
+			("src/latest.ts", PathClass::Source),
+		];
+		for (path, expected) in cases {
+			assert_eq!(classify(Path::new(path)), expected, "{path}");
+		}
+	}
+}
@@ -0,0 +1,265 @@
+//! Per-extension comment syntax registry.
+//!
+//! Maps file extensions to line/block comment markers for features that need
+//! to recognize comments without a full parse (comment extraction, TODO
+//! scanning, code statistics). Built-in defaults cover common languages;
+//! `registerCommentSyntax()` adds or overrides entries so in-house DSL files
+//! participate too.
+
+use std::{path::Path, sync::LazyLock};
+
+use dashmap::DashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Comment markers for one file type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[napi(object)]
+pub struct CommentSyntax {
+	/// Line comment marker (e.g. "//").
+	pub line:        Option<String>,
+	/// Block comment opener (e.g. "/*").
+	#[napi(js_name = "blockStart")]
+	pub block_start: Option<String>,
+	/// Block comment closer (e.g. "*/").
+	#[napi(js_name = "blockEnd")]
+	pub block_end:   Option<String>,
+}
+
+/// A registry entry, as returned by `listCommentSyntaxes()`.
+#[napi(object)]
+pub struct CommentSyntaxEntry {
+	/// Normalized extension (lowercase, no leading dot).
+	pub ext:    String,
+	pub syntax: CommentSyntax,
+	/// Whether the entry was registered at runtime (vs. built in).
+	pub custom: bool,
+}
+
+type Markers = (Option<&'static str>, Option<(&'static str, &'static str)>);
+
+const C_STYLE: Markers = (Some("//"), Some(("/*", "*/")));
+const HASH: Markers = (Some("#"), None);
+const DASH_DASH: Markers = (Some("--"), None);
+const MARKUP: Markers = (None, Some(("<!--", "-->")));
+
+/// Built-in comment markers by extension.
+const DEFAULTS: &[(&[&str], Markers)] = &[
+	(
+		&[
+			"c", "h", "cc", "cpp", "cxx", "hpp", "hxx", "hh", "cs", "java", "kt", "kts", "scala",
+			"go", "rs", "js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "swift", "dart", "php",
+			"zig", "sol", "proto", "groovy", "gradle", "json5", "jsonc", "scss", "less", "m", "mm",
+			"v", "sv", "odin",
+		],
+		C_STYLE,
+	),
+	(&["css"], (None, Some(("/*", "*/")))),
+	(
+		&[
+			"py", "pyi", "sh", "bash", "zsh", "fish", "rb", "pl", "pm", "r", "yaml", "yml", "toml",
+			"ini", "conf", "cfg", "mk", "make", "cmake", "tf", "hcl", "nix", "ex", "exs", "jl", "bzl",
+			"star", "ps1",
+		],
+		HASH,
+	),
+	(&["dockerfile", "makefile"], HASH),
+	(&["sql"], (Some("--"), Some(("/*", "*/")))),
+	(&["lua"], (Some("--"), Some(("--[[", "]]")))),
+	(&["hs"], (Some("--"), Some(("{-", "-}")))),
+	(&["elm", "ada", "vhd", "vhdl"], DASH_DASH),
+	(&["html", "htm", "xml", "svg", "xhtml", "vue", "svelte", "md", "markdown"], MARKUP),
+	(&["clj", "cljs", "el", "lisp", "scm", "ss", "rkt", "asm", "s"], (Some(";"), None)),
+	(&["erl", "hrl", "tex", "sty", "cls"], (Some("%"), None)),
+	(&["vim"], (Some("\""), None)),
+	(&["bat", "cmd"], (Some("REM"), None)),
+	(&["ml", "mli"], (None, Some(("(*", "*)")))),
+];
+
+static CUSTOM: LazyLock<DashMap<String, CommentSyntax>> = LazyLock::new(DashMap::new);
+
+fn normalize_ext(ext: &str) -> String {
+	ext.trim().trim_start_matches('.').to_ascii_lowercase()
+}
+
+fn to_syntax((line, block): Markers) -> CommentSyntax {
+	CommentSyntax {
+		line:        line.map(str::to_string),
+		block_start: block.map(|(start, _)| start.to_string()),
+		block_end:   block.map(|(_, end)| end.to_string()),
+	}
+}
+
+fn builtin(ext: &str) -> Option<CommentSyntax> {
+	DEFAULTS
+		.iter()
+		.find(|(exts, _)| exts.contains(&ext))
+		.map(|(_, markers)| to_syntax(*markers))
+}
+
+/// Resolve comment markers for an extension (with or without leading dot).
+///
+/// Runtime registrations take precedence over built-in defaults.
+pub fn lookup(ext: &str) -> Option<CommentSyntax> {
+	let ext = normalize_ext(ext);
+	if let Some(custom) = CUSTOM.get(&ext) {
+		return Some(custom.clone());
+	}
+	builtin(&ext)
+}
+
+/// Resolve comment markers for a path by its extension, falling back to the
+/// lowercase file name for extensionless files like `Dockerfile`.
+pub fn lookup_path(path: &Path) -> Option<CommentSyntax> {
+	if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+		return lookup(ext);
+	}
+	path
+		.file_name()
+		.and_then(|name| name.to_str())
+		.and_then(lookup)
+}
+
+/// Comments of `source` under `syntax`, markers included, in source order.
+///
+/// This is a lexical scan: markers inside string literals are taken for
+/// comments too. An unterminated block comment runs to the end of `source`.
+pub fn find_comments<'a>(source: &'a str, syntax: &CommentSyntax) -> Vec<&'a str> {
+	let block = syntax
+		.block_start
+		.as_deref()
+		.zip(syntax.block_end.as_deref());
+	let mut comments = Vec::new();
+	let mut rest = source;
+	while let Some(ch) = rest.chars().next() {
+		// Block openers win over line markers they start with, like Lua's `--[[`.
+		if let Some((start, end)) = block.filter(|(start, _)| rest.starts_with(start)) {
+			let body = &rest[start.len()..];
+			let len = start.len() + body.find(end).map_or(body.len(), |at| at + end.len());
+			comments.push(&rest[..len]);
+			rest = &rest[len..];
+		} else if syntax
+			.line
+			.as_deref()
+			.is_some_and(|line| rest.starts_with(line))
+		{
+			let len = rest.find('\n').unwrap_or(rest.len());
+			comments.push(&rest[..len]);
+			rest = &rest[len..];
+		} else {
+			rest = &rest[ch.len_utf8()..];
+		}
+	}
+	comments
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+	value.filter(|value| !value.trim().is_empty())
+}
+
+/// Register (or override) comment markers for a file extension.
+///
+/// # Errors
+/// Fails when the extension is empty, no marker is given, or only one of
+/// `blockStart`/`blockEnd` is set.
+#[napi(js_name = "registerCommentSyntax")]
+pub fn register_comment_syntax(ext: String, syntax: CommentSyntax) -> Result<()> {
+	let ext = normalize_ext(&ext);
+	if ext.is_empty() {
+		return Err(Error::from_reason("Comment syntax extension must not be empty"));
+	}
+	let syntax = CommentSyntax {
+		line:        non_empty(syntax.line),
+		block_start: non_empty(syntax.block_start),
+		block_end:   non_empty(syntax.block_end),
+	};
+	if syntax.block_start.is_some() != syntax.block_end.is_some() {
+		return Err(Error::from_reason(format!(
+			"Comment syntax for .{ext}: blockStart and blockEnd must be set together"
+		)));
+	}
+	if syntax.line.is_none() && syntax.block_start.is_none() {
+		return Err(Error::from_reason(format!(
+			"Comment syntax for .{ext}: at least one of line or blockStart/blockEnd is required"
+		)));
+	}
+	CUSTOM.insert(ext, syntax);
+	Ok(())
+}
+
+/// Get the effective comment markers for an extension, if known.
+#[napi(js_name = "getCommentSyntax")]
+pub fn get_comment_syntax(ext: String) -> Option<CommentSyntax> {
+	lookup(&ext)
+}
+
+/// List every known extension with its effective comment markers.
+///
+/// Sorted by extension; custom registrations replace built-in entries.
+#[napi(js_name = "listCommentSyntaxes")]
+pub fn list_comment_syntaxes() -> Vec<CommentSyntaxEntry> {
+	let mut entries: Vec<CommentSyntaxEntry> = DEFAULTS
+		.iter()
+		.flat_map(|(exts, markers)| exts.iter().map(move |ext| (*ext, *markers)))
+		.filter(|(ext, _)| !CUSTOM.contains_key(*ext))
+		.map(|(ext, markers)| CommentSyntaxEntry {
+			ext:    ext.to_string(),
+			syntax: to_syntax(markers),
+			custom: false,
+		})
+		.collect();
+	entries.extend(CUSTOM.iter().map(|entry| CommentSyntaxEntry {
+		ext:    entry.key().clone(),
+		syntax: entry.value().clone(),
+		custom: true,
+	}));
+	entries.sort_by(|a, b| a.ext.cmp(&b.ext));
+	entries
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn custom_syntax_overrides_defaults_and_normalizes_ext() {
+		assert_eq!(lookup(".RS").and_then(|s| s.line).as_deref(), Some("//"));
+		assert_eq!(
+			lookup_path(Path::new("build/Dockerfile"))
+				.and_then(|s| s.line)
+				.as_deref(),
+			Some("#")
+		);
+
+		register_comment_syntax(".PiDsl".to_string(), CommentSyntax {
+			line:        Some(";;".to_string()),
+			block_start: None,
+			block_end:   None,
+		})
+		.unwrap();
+		assert_eq!(lookup("pidsl").and_then(|s| s.line).as_deref(), Some(";;"));
+		assert!(
+			list_comment_syntaxes()
+				.iter()
+				.any(|entry| entry.ext == "pidsl" && entry.custom)
+		);
+
+		let unbalanced = CommentSyntax {
+			line:        None,
+			block_start: Some("<<".to_string()),
+			block_end:   None,
+		};
+		assert!(register_comment_syntax("pidsl2".to_string(), unbalanced).is_err());
+	}
+
+	#[test]
+	fn finds_line_and_block_comments() {
+		let lua = lookup("lua").unwrap();
+		assert_eq!(find_comments("x = 1 -- one\n--[[ two\nlines ]] y = 2\n", &lua), [
+			"-- one",
+			"--[[ two\nlines ]]"
+		]);
+		let c = lookup("c").unwrap();
+		assert_eq!(find_comments("a /* é */ b // c\n/* open", &c), ["/* é */", "// c", "/* open"]);
+	}
+}
@@ -0,0 +1,223 @@
+//! Process-wide settings with per-call snapshots.
+//!
+//! Settings JS can change at any time (read-only mode, the concurrency and text
+//! width policies, the workspace root) live in one [`Config`] behind a lock, so
+//! a reader never sees
+//! half of an update. Every [`task::CancelToken`] captures a snapshot when the
+//! call starts, and [`task::blocking`] work runs with that snapshot installed
+//! for its thread: a long-running operation keeps the settings it started with
+//! even if JS changes them mid-call. Code on that thread reads settings with
+//! [`current()`]; work fanned out to other threads reads them from its token
+//! with [`task::CancelToken::config`].
+//!
+//! `getEffectiveConfig()` reports the live settings together with the
+//! environment-derived policies of other modules, for debugging.
+
+use std::{
+	cell::Cell,
+	fs, io,
+	path::{Path, PathBuf},
+};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use parking_lot::{Mutex, RwLock};
+
+use crate::{ast_cache, fs_cache, journal, scratch, task, text};
+
+/// Settings shared by all native calls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+	/// Mutating exports fail (see `readonly`).
+	pub read_only:   bool,
+	/// Background work throttling.
+	pub concurrency: task::Policy,
+	/// Cell widths of ambiguous characters and emoji.
+	pub width:       text::WidthPolicy,
+	/// Canonical workspace root, or `None` for the current directory.
+	pub workspace:   Option<&'static Path>,
+}
+
+static GLOBAL: RwLock<Config> = RwLock::new(Config {
+	read_only:   false,
+	concurrency: task::Policy::DEFAULT,
+	width:       text::WidthPolicy::DEFAULT,
+	workspace:   None,
+});
+
+/// Workspace roots set so far. Each distinct root is kept for the life of the
+/// process, which keeps [`Config`] `Copy`.
+static WORKSPACES: Mutex<Vec<&'static Path>> = Mutex::new(Vec::new());
+
+thread_local! {
+	/// Snapshot of the call running on this thread.
+	static CALL: Cell<Option<Config>> = const { Cell::new(None) };
+}
+
+/// The live settings.
+pub fn snapshot() -> Config {
+	*GLOBAL.read()
+}
+
+/// Settings of the call running on this thread, or the live settings
+/// outside a call.
+pub fn current() -> Config {
+	CALL.get().unwrap_or_else(snapshot)
+}
+
+/// Change the live settings; calls already running keep their snapshot.
+pub fn update(change: impl FnOnce(&mut Config)) {
+	change(&mut GLOBAL.write());
+}
+
+/// Run `work` with `config` as this thread's call snapshot.
+///
+/// The previous snapshot is restored when `work` returns or unwinds.
+pub fn with_snapshot<R>(config: Config, work: impl FnOnce() -> R) -> R {
+	struct Restore(Option<Config>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			CALL.set(self.0);
+		}
+	}
+
+	let _restore = Restore(CALL.replace(Some(config)));
+	work()
+}
+
+/// Workspace root of the current call: the one set with
+/// `setWorkspaceRoot()`, or the current directory.
+pub fn workspace_root() -> io::Result<PathBuf> {
+	current()
+		.workspace
+		.map_or_else(std::env::current_dir, |root| Ok(root.to_path_buf()))
+}
+
+/// Leak `root` once per distinct path.
+fn intern(root: PathBuf) -> &'static Path {
+	let mut workspaces = WORKSPACES.lock();
+	if let Some(known) = workspaces.iter().find(|known| **known == root) {
+		return known;
+	}
+	let leaked: &'static Path = Box::leak(root.into_boxed_path());
+	workspaces.push(leaked);
+	leaked
+}
+
+/// Set the workspace root that workspace-relative exports (`removePaths`,
+/// `movePath`, `statPaths`, the write journal) default to; `null` restores
+/// the current directory.
+///
+/// # Errors
+/// Fails when `path` is not an existing directory.
+#[napi(js_name = "setWorkspaceRoot")]
+pub fn set_workspace_root(path: Option<String>) -> Result<()> {
+	let workspace = match path {
+		Some(path) => {
+			let root = fs::canonicalize(&path)
+				.map_err(|err| Error::from_reason(format!("Invalid workspace {path}: {err}")))?;
+			if !root.is_dir() {
+				return Err(Error::from_reason(format!("Invalid workspace {path}: not a directory")));
+			}
+			Some(intern(root))
+		},
+		None => None,
+	};
+	update(|config| config.workspace = workspace);
+	Ok(())
+}
+
+/// Live settings, from `getEffectiveConfig()`.
+#[napi(object)]
+pub struct EffectiveConfig {
+	/// Whether read-only mode is enabled.
+	#[napi(js_name = "readOnly")]
+	pub read_only:                 bool,
+	/// Background work throttling policy.
+	pub concurrency:               task::ConcurrencyPolicy,
+	/// Cell widths of ambiguous characters and emoji.
+	pub width:                     text::TextWidthPolicy,
+	/// Workspace root, from `setWorkspaceRoot()` or the current directory.
+	#[napi(js_name = "workspaceRoot")]
+	pub workspace_root:            String,
+	/// Root of scratch directories (`FS_SCRATCH_DIR`).
+	#[napi(js_name = "scratchDir")]
+	pub scratch_dir:               String,
+	/// Directory of pending write journals (`FS_JOURNAL_DIR`).
+	#[napi(js_name = "journalDir")]
+	pub journal_dir:               String,
+	/// Filesystem scan lifetime in milliseconds (`FS_SCAN_CACHE_TTL_MS`).
+	#[napi(js_name = "fsScanCacheTtlMs")]
+	pub fs_scan_cache_ttl_ms:      f64,
+	/// Age after which an empty cached scan is redone
+	/// (`FS_SCAN_EMPTY_RECHECK_MS`).
+	#[napi(js_name = "fsScanEmptyRecheckMs")]
+	pub fs_scan_empty_recheck_ms:  f64,
+	/// Maximum cached filesystem scans (`FS_SCAN_CACHE_MAX_ENTRIES`).
+	#[napi(js_name = "fsScanCacheMaxEntries")]
+	pub fs_scan_cache_max_entries: u32,
+	/// Maximum cached syntax trees (`AST_CACHE_MAX_ENTRIES`).
+	#[napi(js_name = "astCacheMaxEntries")]
+	pub ast_cache_max_entries:     u32,
+	/// Maximum source bytes of cached syntax trees (`AST_CACHE_MAX_BYTES`).
+	#[napi(js_name = "astCacheMaxBytes")]
+	pub ast_cache_max_bytes:       f64,
+}
+
+/// Report the settings a call started now would use.
+#[napi(js_name = "getEffectiveConfig")]
+pub fn get_effective_config() -> EffectiveConfig {
+	let config = snapshot();
+	EffectiveConfig {
+		read_only:                 config.read_only,
+		concurrency:               config.concurrency.into(),
+		width:                     config.width.into(),
+		workspace_root:            workspace_root()
+			.map_or_else(|_| String::new(), |root| root.to_string_lossy().into_owned()),
+		scratch_dir:               scratch::scratch_root().to_string_lossy().into_owned(),
+		journal_dir:               journal::journal_dir().to_string_lossy().into_owned(),
+		fs_scan_cache_ttl_ms:      fs_cache::cache_ttl_ms() as f64,
+		fs_scan_empty_recheck_ms:  fs_cache::empty_recheck_ms() as f64,
+		fs_scan_cache_max_entries: crate::utils::clamp_u32(fs_cache::max_cache_entries() as u64),
+		ast_cache_max_entries:     crate::utils::clamp_u32(ast_cache::max_entries() as u64),
+		ast_cache_max_bytes:       ast_cache::max_bytes() as f64,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn calls_keep_their_snapshot() {
+		let live = snapshot();
+		let pinned = Config { read_only: !live.read_only, ..live };
+		let seen = with_snapshot(pinned, || {
+			let nested = with_snapshot(live, current);
+			(current(), nested)
+		});
+		assert_eq!(seen, (pinned, live));
+		assert_eq!(CALL.get(), None);
+
+		let token = task::CancelToken::default();
+		assert_eq!(token.config().concurrency, snapshot().concurrency);
+	}
+
+	#[test]
+	fn restores_the_snapshot_after_a_panic() {
+		let pinned = Config { read_only: !snapshot().read_only, ..snapshot() };
+		let outer = with_snapshot(pinned, || {
+			let panicked =
+				std::panic::catch_unwind(|| with_snapshot(snapshot(), || panic!("call failed")));
+			assert!(panicked.is_err());
+			current()
+		});
+		assert_eq!(outer, pinned);
+		assert_eq!(CALL.get(), None);
+
+		let root = std::env::temp_dir();
+		let workspace = Config { workspace: Some(intern(root.clone())), ..snapshot() };
+		assert_eq!(with_snapshot(workspace, workspace_root).unwrap(), root);
+	}
+}
@@ -0,0 +1,325 @@
+//! Line diffs and unified patches.
+//!
+//! [`diff_lines`] computes a shortest edit script with Myers' O(ND)
+//! algorithm after trimming the common prefix and suffix, and
+//! [`unified_diff`] renders it as a `diff -u` / `git diff` style patch with
+//! `@@` hunks. Mutating APIs use it to show reviewable changes in dry runs.
+
+use napi_derive::napi;
+
+/// Context lines around each change in unified patches.
+pub const DEFAULT_CONTEXT: usize = 3;
+
+/// One step of a line edit script, indexing into the old and new lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineOp {
+	Equal { old: usize, new: usize },
+	Delete { old: usize },
+	Insert { new: usize },
+}
+
+/// Split `text` into lines that keep their terminators, so a missing final
+/// newline survives the round trip.
+pub fn split_lines(text: &str) -> Vec<&str> {
+	text.split_inclusive('\n').collect()
+}
+
+/// Shortest edit script turning `old` into `new`.
+pub fn diff_lines(old: &[&str], new: &[&str]) -> Vec<LineOp> {
+	let prefix = old
+		.iter()
+		.zip(new)
+		.take_while(|(left, right)| left == right)
+		.count();
+	let suffix = old[prefix..]
+		.iter()
+		.rev()
+		.zip(new[prefix..].iter().rev())
+		.take_while(|(left, right)| left == right)
+		.count();
+
+	let mut ops: Vec<LineOp> = (0..prefix)
+		.map(|index| LineOp::Equal { old: index, new: index })
+		.collect();
+	let old_middle = &old[prefix..old.len() - suffix];
+	let new_middle = &new[prefix..new.len() - suffix];
+	ops.extend(
+		myers(old_middle, new_middle)
+			.into_iter()
+			.map(|op| match op {
+				LineOp::Equal { old, new } => LineOp::Equal { old: old + prefix, new: new + prefix },
+				LineOp::Delete { old } => LineOp::Delete { old: old + prefix },
+				LineOp::Insert { new } => LineOp::Insert { new: new + prefix },
+			}),
+	);
+	ops.extend((0..suffix).map(|index| LineOp::Equal {
+		old: old.len() - suffix + index,
+		new: new.len() - suffix + index,
+	}));
+	ops
+}
+
+/// Myers' greedy forward search, keeping only the live diagonals of each
+/// round so memory grows with the square of the edit distance, not the input.
+fn myers(old: &[&str], new: &[&str]) -> Vec<LineOp> {
+	let (old_len, new_len) = (old.len() as isize, new.len() as isize);
+	let max = old_len + new_len;
+	let same = |x: isize, y: isize| old[x as usize] == new[y as usize];
+	let offset = max + 1;
+	let index = |k: isize| (k + offset) as usize;
+	let mut frontier = vec![0isize; (2 * max + 3) as usize];
+	// trace[d][k + d] is the furthest x reached on diagonal k after round d.
+	let mut trace: Vec<Vec<isize>> = Vec::new();
+
+	'search: for d in 0..=max {
+		for k in (-d..=d).step_by(2) {
+			let down = k == -d || (k != d && frontier[index(k - 1)] < frontier[index(k + 1)]);
+			let mut x = if down {
+				frontier[index(k + 1)]
+			} else {
+				frontier[index(k - 1)] + 1
+			};
+			let mut y = x - k;
+			while x < old_len && y < new_len && same(x, y) {
+				x += 1;
+				y += 1;
+			}
+			frontier[index(k)] = x;
+			if x >= old_len && y >= new_len {
+				trace.push(frontier[index(-d)..=index(d)].to_vec());
+				break 'search;
+			}
+		}
+		trace.push(frontier[index(-d)..=index(d)].to_vec());
+	}
+
+	let mut ops = Vec::new();
+	let (mut x, mut y) = (old_len, new_len);
+	for d in (1..trace.len() as isize).rev() {
+		let previous = &trace[(d - 1) as usize];
+		let reached = |k: isize| previous[(k + d - 1) as usize];
+		let k = x - y;
+		let down = k == -d || (k != d && reached(k - 1) < reached(k + 1));
+		let previous_k = if down { k + 1 } else { k - 1 };
+		let previous_x = reached(previous_k);
+		let previous_y = previous_x - previous_k;
+		while x > previous_x && y > previous_y {
+			x -= 1;
+			y -= 1;
+			ops.push(LineOp::Equal { old: x as usize, new: y as usize });
+		}
+		if down {
+			y -= 1;
+			ops.push(LineOp::Insert { new: y as usize });
+		} else {
+			x -= 1;
+			ops.push(LineOp::Delete { old: x as usize });
+		}
+	}
+	while x > 0 && y > 0 {
+		x -= 1;
+		y -= 1;
+		ops.push(LineOp::Equal { old: x as usize, new: y as usize });
+	}
+	ops.reverse();
+	ops
+}
+
+/// A hunk of a unified patch.
+#[napi(object)]
+pub struct DiffHunk {
+	/// First old line covered (1-based; the line before the hunk when it
+	/// covers no old lines).
+	#[napi(js_name = "oldStart")]
+	pub old_start: u32,
+	/// Number of old lines covered.
+	#[napi(js_name = "oldLines")]
+	pub old_lines: u32,
+	/// First new line covered, numbered like `oldStart`.
+	#[napi(js_name = "newStart")]
+	pub new_start: u32,
+	/// Number of new lines covered.
+	#[napi(js_name = "newLines")]
+	pub new_lines: u32,
+	/// Hunk text: the `@@` header followed by ` `, `-`, and `+` lines.
+	pub text:      String,
+}
+
+/// Unified patch for one file.
+#[napi(object)]
+pub struct FileDiff {
+	/// File path, as shown in the `---`/`+++` headers.
+	pub path:      String,
+	/// Complete patch: `--- a/path`, `+++ b/path`, then every hunk.
+	pub patch:     String,
+	/// Hunks in file order.
+	pub hunks:     Vec<DiffHunk>,
+	/// Lines added.
+	pub additions: u32,
+	/// Lines removed.
+	pub deletions: u32,
+}
+
+fn push_line(out: &mut String, marker: char, line: &str) {
+	out.push(marker);
+	out.push_str(line);
+	if !line.ends_with('\n') {
+		out.push_str("\n\\ No newline at end of file\n");
+	}
+}
+
+/// 1-based first line of a hunk range that follows `before` lines; a range
+/// covering no lines is anchored at the line before it.
+const fn range_start(before: usize, len: usize) -> usize {
+	if len == 0 { before } else { before + 1 }
+}
+
+fn range_header(start: usize, len: usize) -> String {
+	if len == 1 {
+		start.to_string()
+	} else {
+		format!("{start},{len}")
+	}
+}
+
+/// Group an edit script into hunks with `context` unchanged lines around
+/// each change, merging changes whose context would overlap.
+fn render_hunks(old: &[&str], new: &[&str], ops: &[LineOp], context: usize) -> Vec<DiffHunk> {
+	let changes: Vec<usize> = ops
+		.iter()
+		.enumerate()
+		.filter(|(_, op)| !matches!(op, LineOp::Equal { .. }))
+		.map(|(position, _)| position)
+		.collect();
+	let mut hunks = Vec::new();
+	let mut cursor = 0;
+	while cursor < changes.len() {
+		let mut last = cursor;
+		while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * context + 1 {
+			last += 1;
+		}
+		let start = changes[cursor].saturating_sub(context);
+		let end = (changes[last] + context + 1).min(ops.len());
+		cursor = last + 1;
+
+		// Lines on each side before the first op of the hunk.
+		let (old_start, new_start) =
+			ops[..start]
+				.iter()
+				.fold((0, 0), |(old_count, new_count), op| match op {
+					LineOp::Equal { .. } => (old_count + 1, new_count + 1),
+					LineOp::Delete { .. } => (old_count + 1, new_count),
+					LineOp::Insert { .. } => (old_count, new_count + 1),
+				});
+		let mut body = String::new();
+		let (mut old_len, mut new_len) = (0, 0);
+		for op in &ops[start..end] {
+			match *op {
+				LineOp::Equal { old: index, .. } => {
+					push_line(&mut body, ' ', old[index]);
+					old_len += 1;
+					new_len += 1;
+				},
+				LineOp::Delete { old: index } => {
+					push_line(&mut body, '-', old[index]);
+					old_len += 1;
+				},
+				LineOp::Insert { new: index } => {
+					push_line(&mut body, '+', new[index]);
+					new_len += 1;
+				},
+			}
+		}
+		let old_start = range_start(old_start, old_len);
+		let new_start = range_start(new_start, new_len);
+		let text = format!(
+			"@@ -{} +{} @@\n{body}",
+			range_header(old_start, old_len),
+			range_header(new_start, new_len)
+		);
+		hunks.push(DiffHunk {
+			old_start: crate::utils::clamp_u32(old_start as u64),
+			old_lines: crate::utils::clamp_u32(old_len as u64),
+			new_start: crate::utils::clamp_u32(new_start as u64),
+			new_lines: crate::utils::clamp_u32(new_len as u64),
+			text,
+		});
+	}
+	hunks
+}
+
+/// Unified patch turning `old` into `new`, with `context` lines around each
+/// change. Identical inputs produce no hunks and an empty patch.
+pub fn unified_diff(path: &str, old: &str, new: &str, context: usize) -> FileDiff {
+	let old_lines = split_lines(old);
+	let new_lines = split_lines(new);
+	let ops = diff_lines(&old_lines, &new_lines);
+	let hunks = render_hunks(&old_lines, &new_lines, &ops, context);
+	let count = |pick: fn(&LineOp) -> bool| {
+		crate::utils::clamp_u32(ops.iter().filter(|op| pick(op)).count() as u64)
+	};
+	let mut patch = String::new();
+	if !hunks.is_empty() {
+		patch = format!("--- a/{path}\n+++ b/{path}\n");
+		for hunk in &hunks {
+			patch.push_str(&hunk.text);
+		}
+	}
+	FileDiff {
+		path: path.to_string(),
+		patch,
+		hunks,
+		additions: count(|op| matches!(op, LineOp::Insert { .. })),
+		deletions: count(|op| matches!(op, LineOp::Delete { .. })),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn apply(old: &[&str], new: &[&str], ops: &[LineOp]) -> Vec<String> {
+		ops.iter()
+			.filter_map(|op| match *op {
+				LineOp::Equal { old: index, .. } => Some(old[index].to_string()),
+				LineOp::Insert { new: index } => Some(new[index].to_string()),
+				LineOp::Delete { .. } => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn diffs_lines_minimally() {
+		let old = ["a", "b", "c", "a", "b", "b", "a"];
+		let new = ["c", "b", "a", "b", "a", "c"];
+		let ops = diff_lines(&old, &new);
+		assert_eq!(apply(&old, &new, &ops), new);
+		let edits = ops
+			.iter()
+			.filter(|op| !matches!(op, LineOp::Equal { .. }))
+			.count();
+		assert_eq!(edits, 5);
+		assert_eq!(diff_lines(&[], &["x"]), [LineOp::Insert { new: 0 }]);
+		assert_eq!(diff_lines(&["x"], &[]), [LineOp::Delete { old: 0 }]);
+	}
+
+	#[test]
+	fn renders_unified_patch() {
+		let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+		let new = "one\nTWO\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\neleven";
+		let diff = unified_diff("src/x.txt", old, new, 1);
+		assert_eq!((diff.additions, diff.deletions), (2, 1));
+		assert_eq!(
+			diff.patch,
+			"--- a/src/x.txt\n+++ b/src/x.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n@@ -10 \
+			 +10,2 @@\n ten\n+eleven\n\\ No newline at end of file\n"
+		);
+		assert_eq!((diff.hunks[1].old_start, diff.hunks[1].new_lines), (10, 2));
+
+		let wide = unified_diff("x", old, new, 4);
+		assert_eq!(wide.hunks.len(), 1);
+		let empty = unified_diff("x", "", "a\n", 3);
+		assert_eq!(empty.hunks[0].text, "@@ -0,0 +1 @@\n+a\n");
+		assert_eq!(unified_diff("x", old, old, 3).patch, "");
+	}
+}
@@ -0,0 +1,232 @@
+//! Structured exit information for spawned commands.
+//!
+//! PTY and shell results report how a command ended as an [`ExitInfo`]: the
+//! exit code, the terminating signal by name, whether it dumped core, and a
+//! classification that reads the same on Unix and Windows. Shell exit codes
+//! above 128 follow the POSIX convention of `128 + signal`.
+
+use napi_derive::napi;
+
+/// How a command ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitKind {
+	/// Exited with code 0.
+	Success,
+	/// Exited with a non-zero code.
+	Failure,
+	/// Terminated by a signal, a crash, or a kill request.
+	Killed,
+	/// Terminated because the call's timeout elapsed.
+	Timeout,
+	/// Stopped by job control (e.g. `SIGTSTP`).
+	Stopped,
+}
+
+impl ExitKind {
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Success => "success",
+			Self::Failure => "failure",
+			Self::Killed => "killed",
+			Self::Timeout => "timeout",
+			Self::Stopped => "stopped",
+		}
+	}
+}
+
+/// Structured exit status of a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[napi(object)]
+pub struct ExitInfo {
+	/// Exit code, when the command exited on its own.
+	pub code:        Option<i32>,
+	/// Terminating or stopping signal name (e.g. "SIGKILL"), when known.
+	pub signal:      Option<String>,
+	/// Whether the command dumped core (Unix only).
+	#[napi(js_name = "coreDumped")]
+	pub core_dumped: bool,
+	/// "success", "failure", "killed", "timeout", or "stopped".
+	#[napi(ts_type = "\"success\" | \"failure\" | \"killed\" | \"timeout\" | \"stopped\"")]
+	pub kind:        String,
+}
+
+#[cfg(unix)]
+const SIGNALS: &[(i32, &str)] = &[
+	(libc::SIGHUP, "SIGHUP"),
+	(libc::SIGINT, "SIGINT"),
+	(libc::SIGQUIT, "SIGQUIT"),
+	(libc::SIGILL, "SIGILL"),
+	(libc::SIGTRAP, "SIGTRAP"),
+	(libc::SIGABRT, "SIGABRT"),
+	(libc::SIGBUS, "SIGBUS"),
+	(libc::SIGFPE, "SIGFPE"),
+	(libc::SIGKILL, "SIGKILL"),
+	(libc::SIGUSR1, "SIGUSR1"),
+	(libc::SIGSEGV, "SIGSEGV"),
+	(libc::SIGUSR2, "SIGUSR2"),
+	(libc::SIGPIPE, "SIGPIPE"),
+	(libc::SIGALRM, "SIGALRM"),
+	(libc::SIGTERM, "SIGTERM"),
+	(libc::SIGCHLD, "SIGCHLD"),
+	(libc::SIGCONT, "SIGCONT"),
+	(libc::SIGSTOP, "SIGSTOP"),
+	(libc::SIGTSTP, "SIGTSTP"),
+	(libc::SIGTTIN, "SIGTTIN"),
+	(libc::SIGTTOU, "SIGTTOU"),
+	(libc::SIGURG, "SIGURG"),
+	(libc::SIGXCPU, "SIGXCPU"),
+	(libc::SIGXFSZ, "SIGXFSZ"),
+	(libc::SIGVTALRM, "SIGVTALRM"),
+	(libc::SIGPROF, "SIGPROF"),
+	(libc::SIGWINCH, "SIGWINCH"),
+	(libc::SIGSYS, "SIGSYS"),
+];
+
+/// Signals with the same number on every POSIX system, plus the `SIGTSTP`
+/// number brush reports for stopped jobs.
+#[cfg(not(unix))]
+const SIGNALS: &[(i32, &str)] = &[
+	(1, "SIGHUP"),
+	(2, "SIGINT"),
+	(3, "SIGQUIT"),
+	(4, "SIGILL"),
+	(6, "SIGABRT"),
+	(8, "SIGFPE"),
+	(9, "SIGKILL"),
+	(11, "SIGSEGV"),
+	(13, "SIGPIPE"),
+	(14, "SIGALRM"),
+	(15, "SIGTERM"),
+	(20, "SIGTSTP"),
+];
+
+const STOP_SIGNALS: &[&str] = &["SIGSTOP", "SIGTSTP", "SIGTTIN", "SIGTTOU"];
+
+/// Name of signal `number` (e.g. "SIGTERM"), if it is a known signal.
+pub fn signal_name(number: i32) -> Option<&'static str> {
+	SIGNALS
+		.iter()
+		.find(|(signal, _)| *signal == number)
+		.map(|(_, name)| *name)
+}
+
+fn signal_number(name: &str) -> Option<i32> {
+	SIGNALS
+		.iter()
+		.find(|(_, signal)| *signal == name)
+		.map(|(number, _)| *number)
+}
+
+impl ExitInfo {
+	fn new(code: Option<i32>, signal: Option<String>, core_dumped: bool, kind: ExitKind) -> Self {
+		Self { code, signal, core_dumped, kind: kind.as_str().to_string() }
+	}
+
+	/// A command that exited with `code`.
+	///
+	/// On Windows, NTSTATUS failure codes (`0xC0000000` and up, e.g. access
+	/// violations or Ctrl+C) mean the process was terminated, not that it
+	/// reported failure.
+	pub fn exited(code: i32) -> Self {
+		let kind = match code {
+			0 => ExitKind::Success,
+			_ if cfg!(windows) && code as u32 >= 0xc000_0000 => ExitKind::Killed,
+			_ => ExitKind::Failure,
+		};
+		Self::new(Some(code), None, false, kind)
+	}
+
+	/// A command terminated (or stopped) by signal `number`.
+	pub fn signaled(number: i32, core_dumped: bool) -> Self {
+		let name = signal_name(number).map_or_else(|| format!("SIG{number}"), str::to_string);
+		let kind = if STOP_SIGNALS.contains(&name.as_str()) {
+			ExitKind::Stopped
+		} else {
+			ExitKind::Killed
+		};
+		Self::new(None, Some(name), core_dumped, kind)
+	}
+
+	/// Interpret a shell exit code, where `128 + N` means signal N.
+	pub fn from_shell_code(code: i32) -> Self {
+		// brush reports stopped jobs as `128 + 20` on every platform.
+		if code == 148 {
+			return Self::new(None, Some("SIGTSTP".to_string()), false, ExitKind::Stopped);
+		}
+		match code - 128 {
+			signal @ 1..=64 if signal_name(signal).is_some() => Self::signaled(signal, false),
+			_ => Self::exited(code),
+		}
+	}
+
+	/// Decode a raw `waitpid` status.
+	#[cfg(unix)]
+	pub fn from_wait_status(status: libc::c_int) -> Self {
+		if libc::WIFSIGNALED(status) {
+			Self::signaled(libc::WTERMSIG(status), libc::WCOREDUMP(status))
+		} else if libc::WIFSTOPPED(status) {
+			Self::signaled(libc::WSTOPSIG(status), false)
+		} else {
+			Self::exited(libc::WEXITSTATUS(status))
+		}
+	}
+
+	/// A command whose status is unknown because the call was aborted.
+	pub fn aborted(timed_out: bool) -> Self {
+		Self::new(None, None, false, ExitKind::Killed).with_abort(timed_out)
+	}
+
+	/// Reclassify a command the caller killed, keeping its code and signal.
+	pub fn with_abort(mut self, timed_out: bool) -> Self {
+		let kind = if timed_out {
+			ExitKind::Timeout
+		} else {
+			ExitKind::Killed
+		};
+		kind.as_str().clone_into(&mut self.kind);
+		self
+	}
+
+	/// Shell-style exit code: the code itself, or `128 + N` for signal N.
+	pub fn shell_code(&self) -> Option<i32> {
+		self.code.or_else(|| {
+			self
+				.signal
+				.as_deref()
+				.and_then(signal_number)
+				.map(|number| 128 + number)
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn classifies_codes_and_signals() {
+		assert_eq!(ExitInfo::exited(0).kind, "success");
+		assert_eq!(ExitInfo::exited(2).kind, "failure");
+
+		let interrupted = ExitInfo::from_shell_code(130);
+		assert_eq!(
+			(interrupted.signal.as_deref(), interrupted.kind.as_str()),
+			(Some("SIGINT"), "killed")
+		);
+		assert_eq!(interrupted.shell_code(), Some(130));
+		assert_eq!(ExitInfo::from_shell_code(148).kind, "stopped");
+		assert_eq!(ExitInfo::from_shell_code(255).kind, "failure");
+
+		assert_eq!(ExitInfo::exited(1).with_abort(true).kind, "timeout");
+		assert_eq!(ExitInfo::aborted(false).code, None);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn decodes_wait_status() {
+		let crashed = ExitInfo::from_wait_status(libc::SIGSEGV | 0x80);
+		assert_eq!(crashed.signal.as_deref(), Some("SIGSEGV"));
+		assert!(crashed.core_dumped);
+		assert_eq!(ExitInfo::from_wait_status(3 << 8).code, Some(3));
+	}
+}
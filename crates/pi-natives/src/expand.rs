@@ -0,0 +1,274 @@
+//! Shell-style expansion for user-supplied paths.
+//!
+//! `expandPath()` is a small standalone expander, not the embedded shell's. It
+//! supports:
+//! - `~` and `~/...` (other `~` prefixes are kept as written);
+//! - `$VAR` and `${VAR}`, plus the `-`, `:-`, `+`, and `:+` operators;
+//! - single quotes, double quotes, and backslash escapes, with quote removal.
+//!
+//! Positional parameters expand to nothing. Other `${...}` forms are errors,
+//! and field splitting, globbing, and command substitution are not performed.
+
+use std::collections::HashMap;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Options for `expandPath()`.
+#[napi(object)]
+pub struct ExpandPathOptions {
+	/// Variables to expand from; defaults to the process environment.
+	pub env:  Option<HashMap<String, String>>,
+	/// Home directory for `~`; defaults to `HOME` (or the OS home directory).
+	pub home: Option<String>,
+}
+
+struct Expander<'a> {
+	input: &'a str,
+	pos:   usize,
+	env:   &'a dyn Fn(&str) -> Option<String>,
+	home:  &'a dyn Fn() -> Option<String>,
+}
+
+const fn is_name_start(byte: u8) -> bool {
+	byte.is_ascii_alphabetic() || byte == b'_'
+}
+
+const fn is_name_char(byte: u8) -> bool {
+	byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+impl Expander<'_> {
+	fn peek(&self) -> Option<u8> {
+		self.input.as_bytes().get(self.pos).copied()
+	}
+
+	/// Slice between positions that sit on ASCII delimiters.
+	fn text(&self, start: usize, end: usize) -> &str {
+		&self.input[start..end]
+	}
+
+	/// Expand until `stop` (unquoted) or end of input.
+	fn expand_until(&mut self, stop: Option<u8>, out: &mut String) -> Result<()> {
+		let word_start = self.pos;
+		while let Some(byte) = self.peek() {
+			if Some(byte) == stop {
+				return Ok(());
+			}
+			match byte {
+				b'~' if self.pos == word_start => self.tilde(stop, out),
+				b'\\' => {
+					self.pos += 1;
+					if let Some(escaped) = self.next_char() {
+						out.push_str(escaped);
+					}
+				},
+				b'\'' => {
+					self.pos += 1;
+					let start = self.pos;
+					while self.peek().is_some_and(|byte| byte != b'\'') {
+						self.pos += 1;
+					}
+					if self.peek().is_none() {
+						return Err(Error::from_reason("Unterminated single quote"));
+					}
+					out.push_str(self.text(start, self.pos));
+					self.pos += 1;
+				},
+				b'"' => {
+					self.pos += 1;
+					self.double_quoted(out)?;
+				},
+				b'$' => self.dollar(out)?,
+				_ => {
+					if let Some(ch) = self.next_char() {
+						out.push_str(ch);
+					}
+				},
+			}
+		}
+		if stop.is_some() {
+			return Err(Error::from_reason("Unterminated ${ expansion"));
+		}
+		Ok(())
+	}
+
+	fn next_char(&mut self) -> Option<&str> {
+		let ch = self.input[self.pos..].chars().next()?;
+		let start = self.pos;
+		self.pos += ch.len_utf8();
+		Some(self.text(start, self.pos))
+	}
+
+	/// `~` or `~/...`; `~user` and other prefixes are left as written.
+	fn tilde(&mut self, stop: Option<u8>, out: &mut String) {
+		self.pos += 1;
+		let at_boundary = self
+			.peek()
+			.is_none_or(|byte| byte == b'/' || Some(byte) == stop);
+		match (at_boundary, (self.home)()) {
+			(true, Some(home)) => out.push_str(&home),
+			_ => out.push('~'),
+		}
+	}
+
+	fn double_quoted(&mut self, out: &mut String) -> Result<()> {
+		loop {
+			match self.peek() {
+				None => return Err(Error::from_reason("Unterminated double quote")),
+				Some(b'"') => {
+					self.pos += 1;
+					return Ok(());
+				},
+				Some(b'\\') => {
+					self.pos += 1;
+					// In double quotes, backslash escapes only $ ` " \ and newline.
+					match self.peek() {
+						Some(b'$' | b'`' | b'"' | b'\\') => {
+							out.push(self.input.as_bytes()[self.pos] as char);
+							self.pos += 1;
+						},
+						Some(b'\n') => self.pos += 1,
+						_ => out.push('\\'),
+					}
+				},
+				Some(b'$') => self.dollar(out)?,
+				Some(_) => {
+					if let Some(ch) = self.next_char() {
+						out.push_str(ch);
+					}
+				},
+			}
+		}
+	}
+
+	fn name(&mut self) -> &str {
+		let start = self.pos;
+		while self.peek().is_some_and(is_name_char) {
+			self.pos += 1;
+		}
+		self.text(start, self.pos)
+	}
+
+	fn dollar(&mut self, out: &mut String) -> Result<()> {
+		self.pos += 1;
+		match self.peek() {
+			Some(byte) if is_name_start(byte) => {
+				let name = self.name().to_string();
+				out.push_str(&(self.env)(&name).unwrap_or_default());
+			},
+			Some(b'{') => {
+				self.pos += 1;
+				self.braced(out)?;
+			},
+			// Positional parameters are never set outside a script.
+			Some(byte) if byte.is_ascii_digit() => self.pos += 1,
+			_ => out.push('$'),
+		}
+		Ok(())
+	}
+
+	/// `${NAME}`, `${NAME-word}`, `${NAME:-word}`, `${NAME+word}`,
+	/// `${NAME:+word}`, after the opening brace.
+	fn braced(&mut self, out: &mut String) -> Result<()> {
+		if !self.peek().is_some_and(is_name_start) {
+			return Err(Error::from_reason("Bad substitution: expected a variable name after ${"));
+		}
+		let name = self.name().to_string();
+		let value = (self.env)(&name);
+		let colon = self.peek() == Some(b':');
+		if colon {
+			self.pos += 1;
+		}
+		let op = self.peek();
+		if !colon && op == Some(b'}') {
+			self.pos += 1;
+			out.push_str(&value.unwrap_or_default());
+			return Ok(());
+		}
+		if !matches!(op, Some(b'-' | b'+')) {
+			return Err(Error::from_reason(format!(
+				"Bad substitution: unsupported operator in ${{{name}...}}"
+			)));
+		}
+		self.pos += 1;
+		// With `:`, an empty value counts as unset.
+		let set = value
+			.as_deref()
+			.is_some_and(|value| !colon || !value.is_empty());
+		let use_word = (op == Some(b'-')) != set;
+		let mut word = String::new();
+		self.expand_until(Some(b'}'), &mut word)?;
+		self.pos += 1;
+		if use_word {
+			out.push_str(&word);
+		} else if op == Some(b'-') {
+			out.push_str(&value.unwrap_or_default());
+		}
+		Ok(())
+	}
+}
+
+/// Expand `input` with lookups supplied by the caller.
+pub fn expand_with(
+	input: &str,
+	env: &dyn Fn(&str) -> Option<String>,
+	home: &dyn Fn() -> Option<String>,
+) -> Result<String> {
+	let mut expander = Expander { input, pos: 0, env, home };
+	let mut out = String::with_capacity(input.len());
+	expander.expand_until(None, &mut out)?;
+	Ok(out)
+}
+
+/// Expand `~`, `$VAR`, and `${VAR:-default}` in a path, including quote
+/// removal.
+///
+/// # Errors
+/// Fails on unterminated quotes and malformed or unsupported `${...}`.
+#[napi(js_name = "expandPath")]
+pub fn expand_path(input: String, options: Option<ExpandPathOptions>) -> Result<String> {
+	let (env, home) = options.map_or((None, None), |options| (options.env, options.home));
+	let lookup = |name: &str| match &env {
+		Some(env) => env.get(name).cloned(),
+		None => std::env::var(name).ok(),
+	};
+	let home_dir = || {
+		home
+			.clone()
+			.or_else(|| lookup("HOME"))
+			.or_else(|| std::env::home_dir().map(|path| path.to_string_lossy().into_owned()))
+	};
+	expand_with(&input, &lookup, &home_dir)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn expand(input: &str) -> Result<String> {
+		let env = HashMap::from([
+			("USER".to_string(), "pi".to_string()),
+			("EMPTY".to_string(), String::new()),
+		]);
+		expand_path(
+			input.to_string(),
+			Some(ExpandPathOptions { env: Some(env), home: Some("/home/pi".to_string()) }),
+		)
+	}
+
+	#[test]
+	fn expands_like_the_shell() {
+		assert_eq!(expand("~/src/$USER").unwrap(), "/home/pi/src/pi");
+		assert_eq!(expand("~other/x").unwrap(), "~other/x");
+		assert_eq!(expand("a/~/b").unwrap(), "a/~/b");
+		assert_eq!(expand("${MISSING:-~/cache}").unwrap(), "/home/pi/cache");
+		assert_eq!(expand("${EMPTY-set}|${EMPTY:-unset}").unwrap(), "|unset");
+		assert_eq!(expand("${USER:+has-user}${MISSING+x}").unwrap(), "has-user");
+		assert_eq!(expand(r#"'$USER' "dir $USER" \$HOME"#).unwrap(), "$USER dir pi $HOME");
+		assert_eq!(expand("cost$ 5").unwrap(), "cost$ 5");
+		assert!(expand("${USER").is_err());
+		assert!(expand("'open").is_err());
+		assert!(expand("${#USER}").is_err());
+	}
+}
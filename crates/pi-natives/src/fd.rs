@@ -1,12 +1,20 @@
 //! Fuzzy file path discovery for autocomplete and @-mention resolution.
 //!
 //! Searches for files and directories whose paths match a query string via
-//! subsequence scoring. Uses the shared [`fs_cache`] for directory scanning.
+//! fzf-style subsequence scoring, and reports which characters matched. Uses
+//! the shared [`fs_cache`] for directory scanning. With `searchContent`, the
+//! best path candidates are also checked for the query in their contents, and
+//! files that contain it rank higher. Callers can pass recently opened paths
+//! and frecency weights so the files a user works with rank above lexically
+//! similar ones.
 
-use std::path::Path;
+use std::{collections::HashMap, io::Read, path::Path};
 
+use grep_matcher::Matcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
+use rayon::prelude::*;
 
 use crate::{fs_cache, task};
 
@@ -18,35 +26,96 @@ use crate::{fs_cache, task};
 #[napi(object)]
 pub struct FuzzyFindOptions<'env> {
 	/// Fuzzy query to match against file paths (case-insensitive).
-	pub query:       String,
+	pub query:              String,
 	/// Directory to search.
-	pub path:        String,
+	pub path:               String,
 	/// Include hidden files (default: false).
-	pub hidden:      Option<bool>,
+	pub hidden:             Option<bool>,
 	/// Respect .gitignore (default: true).
-	pub gitignore:   Option<bool>,
+	pub gitignore:          Option<bool>,
+	/// Standard ignore sources to apply ("gitignore", "ignore", "rgignore");
+	/// overrides `gitignore` when set (default: all three).
+	#[napi(js_name = "ignoreSources")]
+	pub ignore_sources:     Option<Vec<String>>,
+	/// Additional ignore file names to honor in each directory.
+	#[napi(js_name = "ignoreFiles")]
+	pub ignore_files:       Option<Vec<String>>,
 	/// Enable shared filesystem scan cache (default: false).
-	pub cache:       Option<bool>,
+	pub cache:              Option<bool>,
 	/// Maximum number of matches to return (default: 100).
 	#[napi(js_name = "maxResults")]
-	pub max_results: Option<u32>,
+	pub max_results:        Option<u32>,
+	/// Also look for the query in the contents of the best path candidates
+	/// and boost files that contain it (default: false).
+	#[napi(js_name = "searchContent")]
+	pub search_content:     Option<bool>,
+	/// How many top-scoring files `searchContent` checks (default: 100).
+	#[napi(js_name = "contentCandidates")]
+	pub content_candidates: Option<u32>,
+	/// Recently opened paths, most recent first (relative to `path` or
+	/// absolute); matching entries get a boost that fades with position.
+	pub recent:             Option<Vec<String>>,
+	/// Frecency weights by path (relative to `path` or absolute); matching
+	/// entries get a boost that grows logarithmically with the weight.
+	pub frecency:           Option<HashMap<String, f64>>,
+	/// Ranking weights; omitted fields keep their defaults.
+	pub weights:            Option<FuzzyFindWeights>,
 	/// Abort signal for cancelling the operation.
-	pub signal:      Option<Unknown<'env>>,
+	pub signal:             Option<Unknown<'env>>,
 	/// Timeout in milliseconds for the operation.
 	#[napi(js_name = "timeoutMs")]
-	pub timeout_ms:  Option<u32>,
+	pub timeout_ms:         Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:              Option<ClassInstance<'env, task::CancellationScope>>,
+}
+
+/// Ranking weights for `fuzzyFind`. Each is validated (finite, not
+/// negative) and clamped to [`MAX_WEIGHT`]; `gapPenalty` is clamped to the
+/// score of one matched character.
+#[napi(object)]
+#[derive(Default)]
+pub struct FuzzyFindWeights {
+	/// Score of a file name equal to the query (default: 120).
+	pub exact:            Option<f64>,
+	/// Score of a file name starting with the query (default: 100).
+	pub prefix:           Option<f64>,
+	/// Score of a file name containing the query (default: 80).
+	pub contains:         Option<f64>,
+	/// Score of a path containing the query (default: 60).
+	#[napi(js_name = "pathContains")]
+	pub path_contains:    Option<f64>,
+	/// Base score of a file name matching the query as a subsequence; up to
+	/// 40 is added for match quality (default: 50).
+	pub subsequence:      Option<f64>,
+	/// Base score of a path matching the query as a subsequence (default:
+	/// 30).
+	#[napi(js_name = "pathSubsequence")]
+	pub path_subsequence: Option<f64>,
+	/// Penalty for each gap between matched characters of a subsequence
+	/// (default: 3, at most 16).
+	#[napi(js_name = "gapPenalty")]
+	pub gap_penalty:      Option<f64>,
+	/// Added to the score of directories (default: 10).
+	#[napi(js_name = "directoryBonus")]
+	pub directory_bonus:  Option<f64>,
 }
 
 /// A single match in fuzzy find results.
 #[napi(object)]
 pub struct FuzzyFindMatch {
 	/// Relative path from the search root (uses `/` separators).
-	pub path:         String,
+	pub path:          String,
 	/// Whether this entry is a directory.
 	#[napi(js_name = "isDirectory")]
-	pub is_directory: bool,
+	pub is_directory:  bool,
 	/// Match quality score (higher is better).
-	pub score:        u32,
+	pub score:         u32,
+	/// Whether the file contains the query (`searchContent` only).
+	#[napi(js_name = "contentMatch")]
+	pub content_match: Option<bool>,
+	/// Offsets (UTF-16 code units) in `path` of the characters that matched
+	/// the query, for highlighting; empty for an empty query.
+	pub positions:     Vec<u32>,
 }
 
 /// Result of fuzzy file path search.
@@ -73,45 +142,353 @@ fn normalize_fuzzy_text(value: &str) -> String {
 		.collect()
 }
 
-/// Scores a query as a subsequence of `target`. Returns 0 if not a subsequence.
-fn fuzzy_subsequence_score(query_chars: &[char], target: &str) -> u32 {
-	if query_chars.is_empty() {
-		return 1;
+// fzf v2 scoring constants: a matched character is worth `SCORE_MATCH`, gaps
+// cost a start penalty plus a smaller per-character extension, and matches at
+// word boundaries, camelCase humps, and digits earn bonuses.
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+const BONUS_BOUNDARY_WHITE: i32 = BONUS_BOUNDARY + 2;
+const BONUS_BOUNDARY_DELIMITER: i32 = BONUS_BOUNDARY + 1;
+const BONUS_NON_WORD: i32 = SCORE_MATCH / 2;
+const BONUS_CAMEL_123: i32 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// Upper bound of user-supplied path scoring weights.
+const MAX_WEIGHT: f64 = 1000.0;
+
+/// Path scoring weights, resolved from [`FuzzyFindWeights`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ScoreWeights {
+	exact:            u32,
+	prefix:           u32,
+	contains:         u32,
+	path_contains:    u32,
+	subsequence:      u32,
+	path_subsequence: u32,
+	/// Gap start penalty of subsequence alignment (negative).
+	gap_start:        i32,
+	directory_bonus:  u32,
+}
+
+impl ScoreWeights {
+	const DEFAULT: Self = Self {
+		exact:            120,
+		prefix:           100,
+		contains:         80,
+		path_contains:    60,
+		subsequence:      50,
+		path_subsequence: 30,
+		gap_start:        SCORE_GAP_START,
+		directory_bonus:  10,
+	};
+
+	fn resolve(weights: Option<&FuzzyFindWeights>) -> Result<Self> {
+		let Some(weights) = weights else {
+			return Ok(Self::DEFAULT);
+		};
+		let weight = |name: &str, value: Option<f64>, default: u32, max: f64| -> Result<u32> {
+			match value {
+				None => Ok(default),
+				Some(value) if value.is_finite() && value >= 0.0 => Ok(value.min(max).round() as u32),
+				Some(value) => {
+					Err(Error::from_reason(format!("Invalid fuzzyFind weight {name}: {value}")))
+				},
+			}
+		};
+		let default = Self::DEFAULT;
+		let gap_penalty = weight(
+			"gapPenalty",
+			weights.gap_penalty,
+			default.gap_start.unsigned_abs(),
+			f64::from(SCORE_MATCH),
+		)?;
+		Ok(Self {
+			exact:            weight("exact", weights.exact, default.exact, MAX_WEIGHT)?,
+			prefix:           weight("prefix", weights.prefix, default.prefix, MAX_WEIGHT)?,
+			contains:         weight("contains", weights.contains, default.contains, MAX_WEIGHT)?,
+			path_contains:    weight(
+				"pathContains",
+				weights.path_contains,
+				default.path_contains,
+				MAX_WEIGHT,
+			)?,
+			subsequence:      weight(
+				"subsequence",
+				weights.subsequence,
+				default.subsequence,
+				MAX_WEIGHT,
+			)?,
+			path_subsequence: weight(
+				"pathSubsequence",
+				weights.path_subsequence,
+				default.path_subsequence,
+				MAX_WEIGHT,
+			)?,
+			gap_start:        -(gap_penalty as i32),
+			directory_bonus:  weight(
+				"directoryBonus",
+				weights.directory_bonus,
+				default.directory_bonus,
+				MAX_WEIGHT,
+			)?,
+		})
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+	White,
+	NonWord,
+	Delimiter,
+	Lower,
+	Upper,
+	Letter,
+	Number,
+}
+
+fn char_class(ch: char) -> CharClass {
+	match ch {
+		'a'..='z' => CharClass::Lower,
+		'A'..='Z' => CharClass::Upper,
+		'0'..='9' => CharClass::Number,
+		'/' | '\\' | ',' | ':' | ';' | '|' => CharClass::Delimiter,
+		ch if ch.is_whitespace() => CharClass::White,
+		ch if ch.is_lowercase() => CharClass::Lower,
+		ch if ch.is_uppercase() => CharClass::Upper,
+		ch if ch.is_numeric() => CharClass::Number,
+		ch if ch.is_alphabetic() => CharClass::Letter,
+		_ => CharClass::NonWord,
+	}
+}
+
+/// Bonus for matching a character of class `class` that follows `prev`.
+const fn boundary_bonus(prev: CharClass, class: CharClass) -> i32 {
+	let word =
+		matches!(class, CharClass::Lower | CharClass::Upper | CharClass::Letter | CharClass::Number);
+	match (prev, class) {
+		(CharClass::White, _) if word => BONUS_BOUNDARY_WHITE,
+		(CharClass::Delimiter, _) if word => BONUS_BOUNDARY_DELIMITER,
+		(CharClass::NonWord, _) if word => BONUS_BOUNDARY,
+		(CharClass::Lower, CharClass::Upper) => BONUS_CAMEL_123,
+		(CharClass::Number, CharClass::Number) => 0,
+		(_, CharClass::Number) => BONUS_CAMEL_123,
+		(_, CharClass::NonWord | CharClass::Delimiter) => BONUS_NON_WORD,
+		(_, CharClass::White) => BONUS_BOUNDARY_WHITE,
+		_ => 0,
+	}
+}
+
+/// Best alignment of a query within a target.
+pub(crate) struct FuzzyAlignment {
+	pub score:     i32,
+	/// Character indices in the target, one per query character.
+	pub positions: Vec<usize>,
+}
+
+/// Aligns lowercase `query` against `target` case-insensitively, fzf v2
+/// style: a Smith-Waterman pass that maximizes match and bonus points minus
+/// gap penalties, with consecutive runs inheriting the bonus of their first
+/// character. Returns `None` if `query` is not a subsequence of `target`.
+pub(crate) fn fuzzy_align(query: &[char], target: &str) -> Option<FuzzyAlignment> {
+	align(query, target, SCORE_GAP_START)
+}
+
+/// [`fuzzy_align`] with `gap_start` as the gap start penalty.
+fn align(query: &[char], target: &str, gap_start: i32) -> Option<FuzzyAlignment> {
+	const UNSET: i32 = i32::MIN / 2;
+	if query.is_empty() {
+		return Some(FuzzyAlignment { score: 0, positions: Vec::new() });
 	}
-	let mut query_index = 0usize;
-	let mut gaps = 0u32;
-	let mut last_match_index: Option<usize> = None;
-	for (target_index, target_ch) in target.chars().enumerate() {
-		if query_index >= query_chars.len() {
-			break;
+	let chars: Vec<char> = target.chars().collect();
+	let lower: Vec<char> = chars
+		.iter()
+		.map(|ch| ch.to_lowercase().next().unwrap_or(*ch))
+		.collect();
+
+	// Cheap subsequence check, which also bounds the window worth scoring.
+	let mut matched = 0usize;
+	let mut first = 0usize;
+	for (index, ch) in lower.iter().enumerate() {
+		if *ch == query[matched] {
+			if matched == 0 {
+				first = index;
+			}
+			matched += 1;
+			if matched == query.len() {
+				break;
+			}
 		}
-		if query_chars[query_index] == target_ch {
-			if let Some(last_index) = last_match_index
-				&& target_index > last_index + 1
+	}
+	if matched < query.len() {
+		return None;
+	}
+	let last = lower.iter().rposition(|ch| *ch == query[query.len() - 1])?;
+	let text = &lower[first..=last];
+	let width = text.len();
+	let mut prev_class = if first == 0 {
+		CharClass::Delimiter
+	} else {
+		char_class(chars[first - 1])
+	};
+	let bonus: Vec<i32> = chars[first..=last]
+		.iter()
+		.map(|ch| {
+			let class = char_class(*ch);
+			let bonus = boundary_bonus(prev_class, class);
+			prev_class = class;
+			bonus
+		})
+		.collect();
+
+	// Row `i` holds the best score with query[i] matched at each column, the
+	// bonus of the run that match extends, and the column query[i - 1] used.
+	let mut score = vec![UNSET; query.len() * width];
+	let mut run = vec![0i32; query.len() * width];
+	let mut from = vec![0usize; query.len() * width];
+	for (i, query_ch) in query.iter().enumerate() {
+		let row = i * width;
+		// Best previous-row match followed by at least one skipped column.
+		let mut gap: Option<(i32, usize)> = None;
+		for j in 0..width {
+			if i > 0 && j >= 2 {
+				let extended = gap.map(|(value, col)| (value + SCORE_GAP_EXTENSION, col));
+				let above = score[row - width + j - 2];
+				let opened = (above != UNSET).then_some((above + gap_start, j - 2));
+				gap = match (extended, opened) {
+					(Some(extended), Some(opened)) if opened.0 >= extended.0 => Some(opened),
+					(None, opened) => opened,
+					(extended, _) => extended,
+				};
+			}
+			if text[j] != *query_ch {
+				continue;
+			}
+			let cell = row + j;
+			if i == 0 {
+				score[cell] = SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+				run[cell] = bonus[j];
+				continue;
+			}
+			if j > 0 && score[cell - width - 1] != UNSET {
+				let run_bonus = run[cell - width - 1];
+				let consecutive = bonus[j].max(run_bonus).max(BONUS_CONSECUTIVE);
+				score[cell] = score[cell - width - 1] + SCORE_MATCH + consecutive;
+				run[cell] = if bonus[j] >= BONUS_BOUNDARY && bonus[j] > run_bonus {
+					bonus[j]
+				} else {
+					run_bonus
+				};
+				from[cell] = j - 1;
+			}
+			if let Some((value, col)) = gap
+				&& value + SCORE_MATCH + bonus[j] > score[cell]
 			{
-				gaps = gaps.saturating_add(1);
+				score[cell] = value + SCORE_MATCH + bonus[j];
+				run[cell] = bonus[j];
+				from[cell] = col;
 			}
-			last_match_index = Some(target_index);
-			query_index += 1;
 		}
 	}
-	if query_index != query_chars.len() {
+
+	let last_row = (query.len() - 1) * width;
+	let (mut col, best) = (0..width)
+		.map(|j| (j, score[last_row + j]))
+		.filter(|(_, value)| *value != UNSET)
+		.fold(None, |best: Option<(usize, i32)>, (j, value)| match best {
+			Some((_, top)) if top >= value => best,
+			_ => Some((j, value)),
+		})?;
+	let mut positions = vec![0usize; query.len()];
+	for i in (0..query.len()).rev() {
+		positions[i] = first + col;
+		col = from[i * width + col];
+	}
+	Some(FuzzyAlignment { score: best, positions })
+}
+
+/// Scores a query as a subsequence of `target` with [`fuzzy_align`], scaled
+/// to 1..=40 against a perfect boundary-anchored run. Returns 0 if not a
+/// subsequence.
+fn fuzzy_subsequence_score(query_chars: &[char], target: &str, gap_start: i32) -> u32 {
+	if query_chars.is_empty() {
+		return 1;
+	}
+	let Some(alignment) = align(query_chars, target, gap_start) else {
 		return 0;
+	};
+	let len = query_chars.len() as i32;
+	let perfect = SCORE_MATCH * len + BONUS_BOUNDARY_WHITE * (BONUS_FIRST_CHAR_MULTIPLIER + len - 1);
+	(alignment.score.max(0) * 40 / perfect).clamp(1, 40) as u32
+}
+
+/// Positions (UTF-16 offsets into `path`) of the characters matching the
+/// query, from the better of the file-name and whole-path alignments. The
+/// literal query is tried first so separators it contains are highlighted.
+fn match_positions(
+	path: &str,
+	query_lower: &str,
+	query_chars: &[char],
+	weights: &ScoreWeights,
+) -> Vec<u32> {
+	let trimmed = path.trim_end_matches('/');
+	let file_name = Path::new(trimmed)
+		.file_name()
+		.and_then(|name| name.to_str())
+		.unwrap_or(trimmed);
+	let name_offset = trimmed[..trimmed.len() - file_name.len()].chars().count();
+	let literal: Vec<char> = query_lower
+		.chars()
+		.filter(|ch| !ch.is_whitespace())
+		.collect();
+	let alignment = [literal.as_slice(), query_chars]
+		.into_iter()
+		.find_map(|query| {
+			let in_name = align(query, file_name, weights.gap_start).map(|mut alignment| {
+				alignment
+					.positions
+					.iter_mut()
+					.for_each(|pos| *pos += name_offset);
+				alignment
+			});
+			let in_path = align(query, trimmed, weights.gap_start);
+			match (in_name, in_path) {
+				(Some(name), Some(path)) if path.score > name.score => Some(path),
+				(Some(name), _) => Some(name),
+				(None, path) => path,
+			}
+		});
+	let Some(alignment) = alignment else {
+		return Vec::new();
+	};
+	let mut utf16 = Vec::with_capacity(trimmed.len());
+	let mut offset = 0u32;
+	for ch in trimmed.chars() {
+		utf16.push(offset);
+		offset += ch.len_utf16() as u32;
 	}
-	let gap_penalty = gaps.saturating_mul(5);
-	40u32.saturating_sub(gap_penalty).max(1)
+	alignment.positions.iter().map(|pos| utf16[*pos]).collect()
 }
 
-/// Composite path scoring: exact > starts-with > contains > fuzzy subsequence.
+/// Composite path scoring: exact > starts-with > contains > fuzzy subsequence
+/// with the default `weights`. Returns 0 when the path does not match.
 fn score_fuzzy_path(
 	path: &str,
 	is_directory: bool,
 	query_lower: &str,
 	normalized_query: &str,
 	query_chars: &[char],
+	weights: &ScoreWeights,
 ) -> u32 {
+	let directory_bonus = if is_directory {
+		weights.directory_bonus
+	} else {
+		0
+	};
 	if query_lower.is_empty() {
-		return if is_directory { 11 } else { 1 };
+		return 1 + directory_bonus;
 	}
 
 	let file_name = Path::new(path)
@@ -120,38 +497,37 @@ fn score_fuzzy_path(
 		.unwrap_or(path);
 	let lower_file_name = file_name.to_lowercase();
 
-	let mut score = if lower_file_name == query_lower {
-		120
+	let score = if lower_file_name == query_lower {
+		weights.exact
 	} else if lower_file_name.starts_with(query_lower) {
-		100
+		weights.prefix
 	} else if lower_file_name.contains(query_lower) {
-		80
+		weights.contains
 	} else {
 		let lower_path = path.to_lowercase();
 		if lower_path.contains(query_lower) {
-			60
+			weights.path_contains
 		} else {
-			let normalized_file_name = normalize_fuzzy_text(file_name);
-			let file_name_fuzzy = fuzzy_subsequence_score(query_chars, &normalized_file_name);
+			let file_name_fuzzy = fuzzy_subsequence_score(query_chars, file_name, weights.gap_start);
 			if file_name_fuzzy > 0 {
-				50 + file_name_fuzzy
+				weights.subsequence + file_name_fuzzy
 			} else {
 				let normalized_path = normalize_fuzzy_text(path);
 				let path_fuzzy = if normalized_path == normalized_query {
 					40
 				} else {
-					fuzzy_subsequence_score(query_chars, &normalized_path)
+					fuzzy_subsequence_score(query_chars, path, weights.gap_start)
 				};
-				if path_fuzzy > 0 { 30 + path_fuzzy } else { 0 }
+				if path_fuzzy == 0 {
+					return 0;
+				}
+				weights.path_subsequence + path_fuzzy
 			}
 		}
 	};
 
-	if is_directory && score > 0 {
-		score += 10;
-	}
-
-	score
+	// A zero weight must not turn a match into a miss.
+	score.max(1) + directory_bonus
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -160,16 +536,24 @@ fn score_fuzzy_path(
 
 /// Internal configuration for fuzzy find, extracted from options.
 struct FuzzyFindConfig {
-	query:       String,
-	path:        String,
-	hidden:      Option<bool>,
-	gitignore:   Option<bool>,
-	max_results: Option<u32>,
-	cache:       Option<bool>,
+	query:              String,
+	path:               String,
+	hidden:             Option<bool>,
+	gitignore:          Option<bool>,
+	ignore_sources:     Option<Vec<String>>,
+	ignore_files:       Option<Vec<String>>,
+	max_results:        Option<u32>,
+	cache:              Option<bool>,
+	search_content:     Option<bool>,
+	content_candidates: Option<u32>,
+	recent:             Option<Vec<String>>,
+	frecency:           Option<HashMap<String, f64>>,
+	weights:            Option<FuzzyFindWeights>,
 }
 
 fn fuzzy_find_sync(config: FuzzyFindConfig, ct: task::CancelToken) -> Result<FuzzyFindResult> {
 	let root = fs_cache::resolve_search_path(&config.path)?;
+	let weights = ScoreWeights::resolve(config.weights.as_ref())?;
 	let include_hidden = config.hidden.unwrap_or(false);
 	let respect_gitignore = config.gitignore.unwrap_or(true);
 	let max_results = config.max_results.unwrap_or(100) as usize;
@@ -184,38 +568,175 @@ fn fuzzy_find_sync(config: FuzzyFindConfig, ct: task::CancelToken) -> Result<Fuz
 		return Ok(FuzzyFindResult { matches: Vec::new(), total_matches: 0 });
 	}
 
+	let boosts = usage_boosts(&root, config.recent.as_deref(), config.frecency.as_ref());
 	let use_cache = config.cache.unwrap_or(false);
+	let policy = fs_cache::ScanPolicy::new(include_hidden, respect_gitignore)
+		.with_ignore_sources(config.ignore_sources)?
+		.with_ignore_files(config.ignore_files);
 	let mut scored = if use_cache {
-		let scan = fs_cache::get_or_scan(&root, include_hidden, respect_gitignore, &ct)?;
-		let mut scored =
-			score_entries(&scan.entries, &query_lower, &normalized_query, &query_chars, &ct)?;
+		let scan = fs_cache::get_or_scan(&root, &policy, &ct)?;
+		let mut scored = score_entries(
+			&scan.entries,
+			&query_lower,
+			&normalized_query,
+			&query_chars,
+			&boosts,
+			&weights,
+			&ct,
+		)?;
 		// Empty-result recheck: if the query was non-trivial but produced zero matches
 		// from a cached scan that's old enough, force one rescan before giving up.
 		if scored.is_empty()
 			&& !query_lower.is_empty()
 			&& scan.cache_age_ms >= fs_cache::empty_recheck_ms()
 		{
-			let fresh = fs_cache::force_rescan(&root, include_hidden, respect_gitignore, true, &ct)?;
-			scored = score_entries(&fresh, &query_lower, &normalized_query, &query_chars, &ct)?;
+			let fresh = fs_cache::force_rescan(&root, &policy, true, &ct)?;
+			scored = score_entries(
+				&fresh,
+				&query_lower,
+				&normalized_query,
+				&query_chars,
+				&boosts,
+				&weights,
+				&ct,
+			)?;
 		}
 		scored
 	} else {
-		let fresh = fs_cache::force_rescan(&root, include_hidden, respect_gitignore, false, &ct)?;
-		score_entries(&fresh, &query_lower, &normalized_query, &query_chars, &ct)?
+		let fresh = fs_cache::force_rescan(&root, &policy, false, &ct)?;
+		score_entries(&fresh, &query_lower, &normalized_query, &query_chars, &boosts, &weights, &ct)?
 	};
 
 	scored.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+	if config.search_content.unwrap_or(false) && !query_lower.is_empty() {
+		let candidates = config
+			.content_candidates
+			.unwrap_or(DEFAULT_CONTENT_CANDIDATES) as usize;
+		boost_content_matches(&root, &mut scored, config.query.trim(), candidates, &ct)?;
+		scored.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+	}
 	let total_matches = crate::utils::clamp_u32(scored.len() as u64);
-	let matches = scored.into_iter().take(max_results).collect();
+	let matches = scored
+		.into_iter()
+		.take(max_results)
+		.map(|mut entry| {
+			entry.positions = match_positions(&entry.path, &query_lower, &query_chars, &weights);
+			entry
+		})
+		.collect();
 	Ok(FuzzyFindResult { matches, total_matches })
 }
 
+/// Files `searchContent` checks when `contentCandidates` is unset.
+const DEFAULT_CONTENT_CANDIDATES: u32 = 100;
+/// Score added to a path candidate whose contents contain the query; lifts a
+/// fuzzy path hit above a plain substring hit elsewhere in the path.
+const CONTENT_BOOST: u32 = 40;
+/// Bytes of each file read for the content check.
+const CONTENT_SCAN_BYTES: u64 = 1024 * 1024;
+
+/// Whether the first [`CONTENT_SCAN_BYTES`] of `path` contain a match,
+/// skipping binary files.
+fn file_contains(path: &Path, matcher: &RegexMatcher) -> bool {
+	let Ok(file) = std::fs::File::open(path) else {
+		return false;
+	};
+	let mut bytes = Vec::new();
+	if file
+		.take(CONTENT_SCAN_BYTES)
+		.read_to_end(&mut bytes)
+		.is_err()
+	{
+		return false;
+	}
+	if bytes[..bytes.len().min(8192)].contains(&0) {
+		return false;
+	}
+	matcher.is_match(&bytes).unwrap_or(false)
+}
+
+/// Check the `limit` best-scoring files for `query` (case-insensitive,
+/// literal) and boost the ones that contain it.
+fn boost_content_matches(
+	root: &Path,
+	scored: &mut [FuzzyFindMatch],
+	query: &str,
+	limit: usize,
+	ct: &task::CancelToken,
+) -> Result<()> {
+	let matcher = RegexMatcherBuilder::new()
+		.case_insensitive(true)
+		.fixed_strings(true)
+		.build(query)
+		.map_err(|err| Error::from_reason(format!("Invalid content query: {err}")))?;
+	let candidates: Vec<&mut FuzzyFindMatch> = scored
+		.iter_mut()
+		.filter(|entry| !entry.is_directory)
+		.take(limit)
+		.collect();
+	candidates.into_par_iter().try_for_each(|entry| {
+		ct.heartbeat()?;
+		let found = file_contains(&root.join(&entry.path), &matcher);
+		if found {
+			entry.score = entry.score.saturating_add(CONTENT_BOOST);
+		}
+		entry.content_match = Some(found);
+		Ok(())
+	})
+}
+
+/// Largest boost from `recent` (the most recent path) or `frecency`.
+const MAX_USAGE_BOOST: u32 = 30;
+
+/// Normalize a caller-supplied path to the `/`-separated form used by scan
+/// entries, relative to `root` when it is absolute.
+fn usage_key(root: &Path, path: &str) -> String {
+	let path = Path::new(path);
+	let relative = fs_cache::normalize_relative_path(root, path);
+	relative
+		.trim_start_matches("./")
+		.trim_end_matches('/')
+		.to_string()
+}
+
+/// Per-path score boosts from usage history. Recency fades linearly over the
+/// list; frecency grows with `ln(1 + weight)`, reaching the cap at a weight
+/// of about 20. A path in both gets the larger boost.
+fn usage_boosts(
+	root: &Path,
+	recent: Option<&[String]>,
+	frecency: Option<&HashMap<String, f64>>,
+) -> HashMap<String, u32> {
+	let mut boosts: HashMap<String, u32> = HashMap::new();
+	let mut raise = |path: &str, boost: u32| {
+		if boost > 0 {
+			let slot = boosts.entry(usage_key(root, path)).or_default();
+			*slot = (*slot).max(boost);
+		}
+	};
+	if let Some(recent) = recent {
+		let len = recent.len() as u32;
+		for (index, path) in recent.iter().enumerate() {
+			raise(path, MAX_USAGE_BOOST * (len - index as u32) / len);
+		}
+	}
+	for (path, weight) in frecency.into_iter().flatten() {
+		if weight.is_finite() && *weight > 0.0 {
+			let boost = (weight.ln_1p() * 10.0).min(f64::from(MAX_USAGE_BOOST));
+			raise(path, boost as u32);
+		}
+	}
+	boosts
+}
+
 /// Score all entries against the query, returning only those with score > 0.
 fn score_entries(
 	entries: &[fs_cache::GlobMatch],
 	query_lower: &str,
 	normalized_query: &str,
 	query_chars: &[char],
+	boosts: &HashMap<String, u32>,
+	weights: &ScoreWeights,
 	ct: &task::CancelToken,
 ) -> Result<Vec<FuzzyFindMatch>> {
 	let mut scored = Vec::with_capacity(entries.len().min(256));
@@ -226,17 +747,32 @@ fn score_entries(
 		}
 
 		let is_directory = entry.file_type == fs_cache::FileType::Dir;
-		let score =
-			score_fuzzy_path(&entry.path, is_directory, query_lower, normalized_query, query_chars);
+		let mut score = score_fuzzy_path(
+			&entry.path,
+			is_directory,
+			query_lower,
+			normalized_query,
+			query_chars,
+			weights,
+		);
 		if score == 0 {
 			continue;
 		}
+		if let Some(boost) = boosts.get(&entry.path) {
+			score = score.saturating_add(*boost);
+		}
 
 		let mut path = entry.path.clone();
 		if is_directory {
 			path.push('/');
 		}
-		scored.push(FuzzyFindMatch { path, is_directory, score });
+		scored.push(FuzzyFindMatch {
+			path,
+			is_directory,
+			score,
+			content_match: None,
+			positions: Vec::new(),
+		});
 	}
 	Ok(scored)
 }
@@ -250,9 +786,188 @@ fn score_entries(
 /// Matching file and directory entries sorted by match quality.
 #[napi(js_name = "fuzzyFind")]
 pub fn fuzzy_find(options: FuzzyFindOptions<'_>) -> task::Async<FuzzyFindResult> {
-	let FuzzyFindOptions { query, path, hidden, gitignore, cache, max_results, timeout_ms, signal } =
-		options;
-	let ct = task::CancelToken::new(timeout_ms, signal);
-	let config = FuzzyFindConfig { query, path, hidden, gitignore, max_results, cache };
+	let FuzzyFindOptions {
+		query,
+		path,
+		hidden,
+		gitignore,
+		ignore_sources,
+		ignore_files,
+		cache,
+		max_results,
+		search_content,
+		content_candidates,
+		recent,
+		frecency,
+		weights,
+		timeout_ms,
+		signal,
+		scope,
+	} = options;
+	let ct = task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref());
+	let config = FuzzyFindConfig {
+		query,
+		path,
+		hidden,
+		gitignore,
+		ignore_sources,
+		ignore_files,
+		max_results,
+		cache,
+		search_content,
+		content_candidates,
+		recent,
+		frecency,
+		weights,
+	};
 	task::blocking("fuzzy_find", ct, move |ct| fuzzy_find_sync(config, ct))
 }
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+
+	#[test]
+	fn boosts_files_containing_the_query() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-fd-content-test-{unique}"));
+		fs::create_dir_all(root.join("src")).expect("temp dir should be created");
+		fs::write(root.join("src/auth.ts"), "export const token = 1;").unwrap();
+		fs::write(root.join("src/author.ts"), "// Auth handshake").unwrap();
+
+		let find = |search_content| {
+			fuzzy_find_sync(
+				FuzzyFindConfig {
+					query:              "auth".to_string(),
+					path:               root.to_string_lossy().into_owned(),
+					hidden:             None,
+					gitignore:          None,
+					ignore_sources:     None,
+					ignore_files:       None,
+					max_results:        None,
+					cache:              None,
+					search_content:     Some(search_content),
+					content_candidates: None,
+					recent:             None,
+					frecency:           None,
+					weights:            None,
+				},
+				task::CancelToken::default(),
+			)
+			.expect("fuzzy find should succeed")
+		};
+		let plain = find(false);
+		assert_eq!(plain.matches[0].path, "src/auth.ts");
+		assert_eq!(plain.matches[0].content_match, None);
+
+		let hybrid = find(true);
+		assert_eq!(hybrid.matches[0].path, "src/author.ts");
+		assert_eq!(hybrid.matches[0].content_match, Some(true));
+		assert_eq!(hybrid.matches[1].content_match, Some(false));
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn aligns_at_word_boundaries() {
+		let query: Vec<char> = "fb".chars().collect();
+		let camel = fuzzy_align(&query, "src/FooBar.ts").unwrap();
+		assert_eq!(camel.positions, [4, 7]);
+		let scattered = fuzzy_align(&query, "src/fabric.ts").unwrap();
+		assert!(camel.score > scattered.score);
+		assert!(fuzzy_align(&query, "src/bar.ts").is_none());
+
+		let query: Vec<char> = "bar".chars().collect();
+		let run = fuzzy_align(&query, "bxaxr/bar").unwrap();
+		assert_eq!(run.positions, [6, 7, 8]);
+		assert!(
+			fuzzy_subsequence_score(&query, "bar", SCORE_GAP_START)
+				> fuzzy_subsequence_score(&query, "bxaxr", SCORE_GAP_START)
+		);
+
+		assert_eq!(match_positions("src/ünï/bar.ts", "bar", &query, &ScoreWeights::DEFAULT), [
+			8, 9, 10
+		]);
+		assert_eq!(
+			match_positions("lib/idx.ts", "lib/i", &['l', 'i', 'b', 'i'], &ScoreWeights::DEFAULT),
+			[0, 1, 2, 3, 4]
+		);
+	}
+
+	#[test]
+	fn boosts_recent_and_frequent_paths() {
+		let root = Path::new("/repo");
+		let recent = ["src/b.ts".to_string(), "/repo/src/a.ts".to_string()];
+		let frecency =
+			HashMap::from([("src/a.ts".to_string(), 1000.0), ("src/c.ts".to_string(), 0.0)]);
+		let boosts = usage_boosts(root, Some(&recent), Some(&frecency));
+		assert_eq!(boosts.get("src/b.ts"), Some(&30));
+		assert_eq!(boosts.get("src/a.ts"), Some(&30));
+		assert_eq!(boosts.get("src/c.ts"), None);
+
+		let entries: Vec<fs_cache::GlobMatch> = ["src/index.ts", "src/indexer.ts", "lib/idx.ts"]
+			.into_iter()
+			.map(|path| fs_cache::GlobMatch {
+				path:           path.to_string(),
+				file_type:      fs_cache::FileType::File,
+				mtime:          None,
+				size:           None,
+				root:           None,
+				mode:           None,
+				readonly:       None,
+				symlink_target: None,
+			})
+			.collect();
+		let recent = ["lib/idx.ts".to_string()];
+		let boosts = usage_boosts(root, Some(&recent), None);
+		let query: Vec<char> = "idx".chars().collect();
+		let mut scored = score_entries(
+			&entries,
+			"idx",
+			"idx",
+			&query,
+			&boosts,
+			&ScoreWeights::DEFAULT,
+			&task::CancelToken::default(),
+		)
+		.unwrap();
+		scored.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+		assert_eq!(scored[0].path, "lib/idx.ts");
+		assert!(scored[0].score > 120);
+	}
+
+	#[test]
+	fn validates_and_applies_weights() {
+		let weights = |weights: FuzzyFindWeights| ScoreWeights::resolve(Some(&weights));
+		assert!(weights(FuzzyFindWeights { exact: Some(-1.0), ..Default::default() }).is_err());
+		assert!(weights(FuzzyFindWeights { prefix: Some(f64::NAN), ..Default::default() }).is_err());
+		let custom = weights(FuzzyFindWeights {
+			path_contains: Some(5000.0),
+			gap_penalty: Some(100.0),
+			directory_bonus: Some(0.0),
+			..Default::default()
+		})
+		.unwrap();
+		assert_eq!((custom.path_contains, custom.gap_start, custom.exact), (1000, -SCORE_MATCH, 120));
+
+		let query: Vec<char> = "auth".chars().collect();
+		let score = |path: &str, is_directory: bool, weights: &ScoreWeights| {
+			score_fuzzy_path(path, is_directory, "auth", "auth", &query, weights)
+		};
+		let defaults = ScoreWeights::DEFAULT;
+		assert!(score("src/auth.ts", false, &defaults) > score("auth/index.ts", false, &defaults));
+		assert!(score("src/auth.ts", false, &custom) < score("auth/index.ts", false, &custom));
+		assert_eq!(score("src/auth", true, &defaults), 130);
+		assert_eq!(score("src/auth", true, &custom), 120);
+		let zero = ScoreWeights { contains: 0, ..defaults };
+		assert_eq!(score("src/oauth.ts", false, &zero), 1);
+	}
+}
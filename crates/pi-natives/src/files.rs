@@ -0,0 +1,595 @@
+//! Bounded file removal and moves confined to the workspace.
+//!
+//! `removePaths()` and `movePath()` replace shelling out to `rm -rf` and `mv`:
+//! every target must resolve inside the workspace (`setWorkspaceRoot()`, or the
+//! current directory by default), removals are counted before anything is
+//! deleted so `maxFiles` caps how much one call can destroy, and both honour
+//! read-only mode. `statPaths()` checks many paths in parallel in one call,
+//! under the same workspace confinement.
+//!
+//! Symlinks are never followed: removing one removes the link, and a link
+//! pointing outside the workspace does not make its target removable.
+
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+use crate::{config, fs_cache, readonly, task};
+
+/// Files `removePaths()` deletes at most unless `maxFiles` says otherwise.
+const DEFAULT_MAX_FILES: u32 = 10_000;
+
+/// Canonical workspace root: `workspace`, or the configured root.
+fn workspace_root(workspace: Option<&str>) -> Result<PathBuf> {
+	let root = match workspace {
+		Some(workspace) => PathBuf::from(workspace),
+		None => config::workspace_root()
+			.map_err(|err| Error::from_reason(format!("Failed to read current directory: {err}")))?,
+	};
+	fs::canonicalize(&root)
+		.map_err(|err| Error::from_reason(format!("Invalid workspace {}: {err}", root.display())))
+}
+
+/// Resolve `path` against `workspace` without following a final symlink.
+///
+/// The parent is canonicalized so `..` and symlinked directories cannot
+/// escape the sandbox check; fails with `NotFound` when the parent is missing.
+fn resolve(workspace: &Path, path: &str) -> io::Result<PathBuf> {
+	let joined = workspace.join(path);
+	let name = match joined.file_name() {
+		Some(name) if !path.ends_with("..") => name.to_owned(),
+		_ => {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("{path} does not name a file or directory"),
+			));
+		},
+	};
+	let parent = joined.parent().unwrap_or(workspace);
+	Ok(fs::canonicalize(parent)?.join(name))
+}
+
+/// Reject targets outside the workspace, the workspace itself, and anything
+/// containing it.
+fn check_target(workspace: &Path, target: &Path, allow_outside: bool) -> Result<()> {
+	if workspace.starts_with(target) {
+		return Err(Error::from_reason(format!(
+			"Refusing to modify {}: it contains the workspace",
+			target.display()
+		)));
+	}
+	if !allow_outside && !target.starts_with(workspace) {
+		return Err(Error::from_reason(format!(
+			"{} is outside the workspace {}",
+			target.display(),
+			workspace.display()
+		)));
+	}
+	Ok(())
+}
+
+fn path_string(path: &Path) -> String {
+	path.to_string_lossy().into_owned()
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// removePaths
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Options for `removePaths()`.
+#[napi(object)]
+pub struct RemovePathsOptions {
+	/// Report what would be deleted without deleting it (default: false).
+	#[napi(js_name = "dryRun")]
+	pub dry_run:                 Option<bool>,
+	/// Fail before deleting anything when more files would go (default:
+	/// 10000).
+	#[napi(js_name = "maxFiles")]
+	pub max_files:               Option<u32>,
+	/// Permit targets outside the workspace (default: false).
+	#[napi(js_name = "allowOutsideWorkspace")]
+	pub allow_outside_workspace: Option<bool>,
+	/// Workspace root that targets must stay inside; relative paths resolve
+	/// against it (default: the current directory).
+	pub workspace:               Option<String>,
+}
+
+/// What `removePaths()` deleted, or would delete in a dry run.
+#[napi(object)]
+pub struct RemovePathsResult {
+	/// Absolute paths of the removed targets.
+	pub removed:     Vec<String>,
+	/// Targets that did not exist.
+	pub missing:     Vec<String>,
+	/// Absolute path of every file (or symlink) removed, targets included.
+	pub files:       Vec<String>,
+	/// Number of directories removed.
+	pub directories: u32,
+	/// Total size of the removed files in bytes.
+	pub bytes:       f64,
+	/// Whether this was a dry run.
+	#[napi(js_name = "dryRun")]
+	pub dry_run:     bool,
+}
+
+/// Walk `path` without following symlinks, recording its files against the
+/// `max_files` budget.
+fn measure(path: &Path, max_files: usize, result: &mut RemovePathsResult) -> Result<()> {
+	let io_error =
+		|err: io::Error| Error::from_reason(format!("Failed to read {}: {err}", path.display()));
+	let metadata = fs::symlink_metadata(path).map_err(io_error)?;
+	if metadata.is_dir() {
+		result.directories = result.directories.saturating_add(1);
+		for entry in fs::read_dir(path).map_err(io_error)? {
+			measure(&entry.map_err(io_error)?.path(), max_files, result)?;
+		}
+		return Ok(());
+	}
+	if result.files.len() >= max_files {
+		return Err(Error::from_reason(format!(
+			"removePaths would delete more than {max_files} files; raise `maxFiles` to proceed"
+		)));
+	}
+	result.files.push(path_string(path));
+	result.bytes += metadata.len() as f64;
+	Ok(())
+}
+
+fn remove_paths_sync(
+	paths: &[String],
+	workspace: &Path,
+	max_files: usize,
+	allow_outside: bool,
+	dry_run: bool,
+) -> Result<RemovePathsResult> {
+	let mut result = RemovePathsResult {
+		removed: Vec::new(),
+		missing: Vec::new(),
+		files: Vec::new(),
+		directories: 0,
+		bytes: 0.0,
+		dry_run,
+	};
+
+	let mut targets = Vec::with_capacity(paths.len());
+	for path in paths {
+		let target = match resolve(workspace, path) {
+			Ok(target) => target,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => {
+				result.missing.push(path_string(&workspace.join(path)));
+				continue;
+			},
+			Err(err) => return Err(Error::from_reason(err.to_string())),
+		};
+		check_target(workspace, &target, allow_outside)?;
+		if fs::symlink_metadata(&target).is_err() {
+			result.missing.push(path_string(&target));
+			continue;
+		}
+		targets.push(target);
+	}
+	// Sorting puts a directory before its contents, so nested targets are
+	// covered by their ancestor and counted once.
+	targets.sort();
+	targets.dedup_by(|target, ancestor| target.starts_with(ancestor));
+
+	for target in &targets {
+		measure(target, max_files, &mut result)?;
+	}
+	if !dry_run {
+		for target in &targets {
+			let removed = if fs::symlink_metadata(target).is_ok_and(|meta| meta.is_dir()) {
+				fs::remove_dir_all(target)
+			} else {
+				fs::remove_file(target)
+			};
+			removed.map_err(|err| {
+				Error::from_reason(format!("Failed to remove {}: {err}", target.display()))
+			})?;
+		}
+	}
+	result.removed = targets.iter().map(|target| path_string(target)).collect();
+	Ok(result)
+}
+
+/// Delete files and directory trees inside the workspace.
+///
+/// Every target is counted before the first deletion; when more than
+/// `maxFiles` files would go, nothing is deleted. Missing targets are
+/// reported, not treated as errors.
+///
+/// # Errors
+/// Fails when a target is outside the workspace (unless allowed), contains
+/// the workspace, exceeds the file budget, or cannot be removed, and when
+/// read-only mode is on (except for dry runs).
+#[napi(js_name = "removePaths")]
+pub fn remove_paths(
+	paths: Vec<String>,
+	options: Option<RemovePathsOptions>,
+) -> Result<task::Async<RemovePathsResult>> {
+	let (dry_run, max_files, allow_outside, workspace) =
+		options.map_or((None, None, None, None), |options| {
+			(options.dry_run, options.max_files, options.allow_outside_workspace, options.workspace)
+		});
+	let dry_run = dry_run.unwrap_or(false);
+	if !dry_run {
+		readonly::ensure_writable("removePaths")?;
+	}
+	let workspace = workspace_root(workspace.as_deref())?;
+	let max_files = max_files.unwrap_or(DEFAULT_MAX_FILES) as usize;
+	let allow_outside = allow_outside.unwrap_or(false);
+	Ok(task::blocking("files.remove", (), move |_| {
+		remove_paths_sync(&paths, &workspace, max_files, allow_outside, dry_run)
+	}))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// movePath
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Options for `movePath()`.
+#[napi(object)]
+pub struct MovePathOptions {
+	/// Replace an existing file or symlink at the destination (default:
+	/// false). Existing directories are never replaced.
+	pub overwrite:               Option<bool>,
+	/// Permit paths outside the workspace (default: false).
+	#[napi(js_name = "allowOutsideWorkspace")]
+	pub allow_outside_workspace: Option<bool>,
+	/// Workspace root that both paths must stay inside; relative paths
+	/// resolve against it (default: the current directory).
+	pub workspace:               Option<String>,
+}
+
+/// Outcome of `movePath()`.
+#[napi(object)]
+pub struct MovePathResult {
+	/// Absolute source path.
+	pub from:        String,
+	/// Absolute destination path.
+	pub to:          String,
+	/// Whether an existing destination was replaced.
+	pub overwritten: bool,
+}
+
+/// Copy `from` to `to` recursively, recreating symlinks rather than following
+/// them.
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+	let metadata = fs::symlink_metadata(from)?;
+	if metadata.is_symlink() {
+		let target = fs::read_link(from)?;
+		#[cfg(unix)]
+		return std::os::unix::fs::symlink(target, to);
+		#[cfg(windows)]
+		return if fs::metadata(from).is_ok_and(|meta| meta.is_dir()) {
+			std::os::windows::fs::symlink_dir(target, to)
+		} else {
+			std::os::windows::fs::symlink_file(target, to)
+		};
+	}
+	if metadata.is_dir() {
+		fs::create_dir(to)?;
+		for entry in fs::read_dir(from)? {
+			let entry = entry?;
+			copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+		}
+		return Ok(());
+	}
+	fs::copy(from, to).map(drop)
+}
+
+fn move_path_sync(
+	from: &str,
+	to: &str,
+	workspace: &Path,
+	overwrite: bool,
+	allow_outside: bool,
+) -> Result<MovePathResult> {
+	let resolve = |path: &str| {
+		resolve(workspace, path)
+			.map_err(|err| Error::from_reason(format!("Invalid path {path}: {err}")))
+	};
+	let source = resolve(from)?;
+	let dest = resolve(to)?;
+	check_target(workspace, &source, allow_outside)?;
+	check_target(workspace, &dest, allow_outside)?;
+
+	let source_meta = fs::symlink_metadata(&source)
+		.map_err(|err| Error::from_reason(format!("Failed to read {}: {err}", source.display())))?;
+	if source_meta.is_dir() && dest != source && dest.starts_with(&source) {
+		return Err(Error::from_reason(format!("Cannot move {} into itself", source.display())));
+	}
+	let overwritten = match fs::symlink_metadata(&dest) {
+		Ok(_) if dest == source => false,
+		Ok(meta) if meta.is_dir() => {
+			return Err(Error::from_reason(format!(
+				"{} is an existing directory; remove it first",
+				dest.display()
+			)));
+		},
+		Ok(_) if !overwrite => {
+			return Err(Error::from_reason(format!(
+				"{} already exists; pass `overwrite` to replace it",
+				dest.display()
+			)));
+		},
+		Ok(_) => true,
+		Err(_) => false,
+	};
+
+	let move_error = |err: io::Error| {
+		Error::from_reason(format!(
+			"Failed to move {} to {}: {err}",
+			source.display(),
+			dest.display()
+		))
+	};
+	match fs::rename(&source, &dest) {
+		Ok(()) => {},
+		Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+			if overwritten {
+				fs::remove_file(&dest).map_err(move_error)?;
+			}
+			copy_tree(&source, &dest).map_err(move_error)?;
+			let removed = if source_meta.is_dir() {
+				fs::remove_dir_all(&source)
+			} else {
+				fs::remove_file(&source)
+			};
+			removed.map_err(move_error)?;
+		},
+		Err(err) => return Err(move_error(err)),
+	}
+	Ok(MovePathResult { from: path_string(&source), to: path_string(&dest), overwritten })
+}
+
+/// Move or rename a file or directory inside the workspace.
+///
+/// Falls back to copy-and-delete when the paths are on different devices.
+///
+/// # Errors
+/// Fails when either path is outside the workspace (unless allowed), the
+/// source is missing, the destination exists (unless `overwrite`) or is a
+/// directory, or read-only mode is on.
+#[napi(js_name = "movePath")]
+pub fn move_path(
+	from: String,
+	to: String,
+	options: Option<MovePathOptions>,
+) -> Result<task::Async<MovePathResult>> {
+	let (overwrite, allow_outside, workspace) = options.map_or((None, None, None), |options| {
+		(options.overwrite, options.allow_outside_workspace, options.workspace)
+	});
+	readonly::ensure_writable("movePath")?;
+	let workspace = workspace_root(workspace.as_deref())?;
+	let overwrite = overwrite.unwrap_or(false);
+	let allow_outside = allow_outside.unwrap_or(false);
+	Ok(task::blocking("files.move", (), move |_| {
+		move_path_sync(&from, &to, &workspace, overwrite, allow_outside)
+	}))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// statPaths
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Options for `statPaths()`.
+#[napi(object)]
+pub struct StatPathsOptions {
+	/// Report symlinks as themselves instead of their targets (default:
+	/// false).
+	#[napi(js_name = "noFollow")]
+	pub no_follow:               Option<bool>,
+	/// Permit paths outside the workspace (default: false).
+	#[napi(js_name = "allowOutsideWorkspace")]
+	pub allow_outside_workspace: Option<bool>,
+	/// Workspace root that paths must stay inside; relative paths resolve
+	/// against it (default: the current directory).
+	pub workspace:               Option<String>,
+}
+
+/// Existence and metadata of one path, from `statPaths()`.
+#[napi(object)]
+pub struct PathStat {
+	/// The path as given.
+	pub path:      String,
+	/// Whether the path exists (and may be inspected).
+	pub exists:    bool,
+	/// Entry type; `undefined` for missing paths and special files.
+	#[napi(js_name = "fileType")]
+	pub file_type: Option<fs_cache::FileType>,
+	/// Size in bytes, for files.
+	pub size:      Option<f64>,
+	/// Modification time in milliseconds since epoch.
+	pub mtime:     Option<f64>,
+	/// Why the path could not be inspected (outside the workspace, permission
+	/// denied, ...); unset for paths that simply do not exist.
+	pub error:     Option<String>,
+}
+
+/// Inspect `path`; confinement is checked on the symlink-resolved target, so
+/// links cannot reveal anything outside the workspace.
+fn stat_path(workspace: &Path, path: &str, allow_outside: bool, follow: bool) -> PathStat {
+	let unknown = |error: Option<String>| PathStat {
+		path: path.to_string(),
+		exists: false,
+		file_type: None,
+		size: None,
+		mtime: None,
+		error,
+	};
+	let target = if follow {
+		fs::canonicalize(workspace.join(path))
+	} else {
+		resolve(workspace, path).or_else(|err| {
+			if err.kind() == io::ErrorKind::InvalidInput {
+				fs::canonicalize(workspace.join(path))
+			} else {
+				Err(err)
+			}
+		})
+	};
+	let target = match target {
+		Ok(target) => target,
+		Err(err) if matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::NotADirectory) => {
+			return unknown(None);
+		},
+		Err(err) => return unknown(Some(err.to_string())),
+	};
+	if !allow_outside && !target.starts_with(workspace) {
+		return unknown(Some(format!(
+			"{} is outside the workspace {}",
+			target.display(),
+			workspace.display()
+		)));
+	}
+	let Ok(metadata) = fs::symlink_metadata(&target) else {
+		return unknown(None);
+	};
+	let kind = metadata.file_type();
+	let (file_type, size) = if kind.is_symlink() {
+		(Some(fs_cache::FileType::Symlink), None)
+	} else if kind.is_dir() {
+		(Some(fs_cache::FileType::Dir), None)
+	} else if kind.is_file() {
+		(Some(fs_cache::FileType::File), Some(metadata.len() as f64))
+	} else {
+		(None, None)
+	};
+	let mtime = metadata
+		.modified()
+		.ok()
+		.and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+		.map(|duration| duration.as_millis() as f64);
+	PathStat { path: path.to_string(), exists: true, file_type, size, mtime, error: None }
+}
+
+/// Check existence, type, size, and mtime of many paths in parallel.
+///
+/// Results are in input order. Paths outside the workspace (unless allowed)
+/// are reported as not existing, with an `error`.
+///
+/// # Errors
+/// Fails only when the workspace cannot be resolved.
+#[napi(js_name = "statPaths")]
+pub fn stat_paths(
+	paths: Vec<String>,
+	options: Option<StatPathsOptions>,
+) -> Result<task::Async<Vec<PathStat>>> {
+	let (no_follow, allow_outside, workspace) = options.map_or((None, None, None), |options| {
+		(options.no_follow, options.allow_outside_workspace, options.workspace)
+	});
+	let workspace = workspace_root(workspace.as_deref())?;
+	let follow = !no_follow.unwrap_or(false);
+	let allow_outside = allow_outside.unwrap_or(false);
+	Ok(task::blocking("files.stat", (), move |_| {
+		Ok(paths
+			.par_iter()
+			.map(|path| stat_path(&workspace, path, allow_outside, follow))
+			.collect())
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	use super::*;
+
+	fn scratch_dir() -> PathBuf {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let dir = std::env::temp_dir().join(format!("pi-files-test-{unique}"));
+		fs::create_dir_all(dir.join("ws/build/deep")).unwrap();
+		fs::write(dir.join("ws/build/a.js"), "aaaa").unwrap();
+		fs::write(dir.join("ws/build/deep/b.js"), "bb").unwrap();
+		fs::write(dir.join("ws/keep.txt"), "keep").unwrap();
+		fs::write(dir.join("outside.txt"), "x").unwrap();
+		fs::canonicalize(dir).unwrap()
+	}
+
+	#[test]
+	fn removes_within_budget_and_workspace() {
+		let root = scratch_dir();
+		let ws = root.join("ws");
+		let paths = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+		let dry =
+			remove_paths_sync(&paths(&["build", "build/a.js", "gone"]), &ws, 10, false, true).unwrap();
+		assert_eq!(dry.removed, [path_string(&ws.join("build"))]);
+		assert_eq!(dry.missing, [path_string(&ws.join("gone"))]);
+		assert_eq!((dry.files.len(), dry.directories, dry.bytes), (2, 2, 6.0));
+		assert!(ws.join("build/a.js").exists());
+
+		let over_budget = remove_paths_sync(&paths(&["build"]), &ws, 1, false, false);
+		assert!(over_budget.is_err());
+		assert!(ws.join("build/deep/b.js").exists());
+
+		assert!(remove_paths_sync(&paths(&["../outside.txt"]), &ws, 10, false, true).is_err());
+		assert!(remove_paths_sync(&paths(&["."]), &ws, 10, true, true).is_err());
+		assert!(remove_paths_sync(&paths(&[".."]), &ws, 10, true, true).is_err());
+
+		let removed = remove_paths_sync(&paths(&["build"]), &ws, 10, false, false).unwrap();
+		assert_eq!(removed.files.len(), 2);
+		assert!(!ws.join("build").exists());
+		assert!(ws.join("keep.txt").exists());
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn moves_within_workspace() {
+		let root = scratch_dir();
+		let ws = root.join("ws");
+
+		let moved = move_path_sync("keep.txt", "build/kept.txt", &ws, false, false).unwrap();
+		assert!(!moved.overwritten);
+		assert_eq!(fs::read_to_string(ws.join("build/kept.txt")).unwrap(), "keep");
+
+		assert!(move_path_sync("build/a.js", "build/kept.txt", &ws, false, false).is_err());
+		let replaced = move_path_sync("build/a.js", "build/kept.txt", &ws, true, false).unwrap();
+		assert!(replaced.overwritten);
+		assert_eq!(fs::read_to_string(ws.join("build/kept.txt")).unwrap(), "aaaa");
+
+		assert!(move_path_sync("build", "build/deep/build", &ws, false, false).is_err());
+		assert!(move_path_sync("build/deep", "../deep", &ws, false, false).is_err());
+		assert!(move_path_sync("build/deep", "build", &ws, true, false).is_err());
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn stats_paths_within_workspace() {
+		let root = scratch_dir();
+		let ws = root.join("ws");
+		let stat = |path: &str, follow: bool| stat_path(&ws, path, false, follow);
+
+		let file = stat("build/a.js", true);
+		assert!(file.exists);
+		assert_eq!((file.file_type, file.size), (Some(fs_cache::FileType::File), Some(4.0)));
+		assert!(file.mtime.is_some());
+		assert_eq!(stat(".", true).file_type, Some(fs_cache::FileType::Dir));
+
+		let missing = stat("build/a.js/nope", true);
+		assert!(!missing.exists && missing.error.is_none());
+		let outside = stat("../outside.txt", true);
+		assert!(!outside.exists && outside.error.is_some());
+		assert!(stat_path(&ws, "../outside.txt", true, true).exists);
+
+		#[cfg(unix)]
+		{
+			std::os::unix::fs::symlink(root.join("outside.txt"), ws.join("escape")).unwrap();
+			assert!(stat("escape", true).error.is_some());
+			assert_eq!(stat("escape", false).file_type, Some(fs_cache::FileType::Symlink));
+		}
+
+		let _ = fs::remove_dir_all(&root);
+	}
+}
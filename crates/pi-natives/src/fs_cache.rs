@@ -13,7 +13,7 @@
 use std::{
 	borrow::Cow,
 	path::{Path, PathBuf},
-	sync::LazyLock,
+	sync::{Arc, LazyLock},
 	time::{Duration, Instant},
 };
 
@@ -44,13 +44,27 @@ pub enum FileType {
 #[napi(object)]
 pub struct GlobMatch {
 	/// Relative path from the search root, using forward slashes.
-	pub path:      String,
+	pub path:           String,
 	/// Resolved filesystem type for the match.
 	#[napi(js_name = "fileType")]
-	pub file_type: FileType,
+	pub file_type:      FileType,
 	/// Modification time in milliseconds since Unix epoch (from
 	/// `symlink_metadata`).
-	pub mtime:     Option<f64>,
+	pub mtime:          Option<f64>,
+	/// Size in bytes, for files (from `symlink_metadata`).
+	pub size:           Option<f64>,
+	/// Search root `path` is relative to, as given in `path`; set only when
+	/// searching several roots.
+	pub root:           Option<String>,
+	/// Unix mode bits (`st_mode`, including the file type bits); set only with
+	/// `withMetadata`, on Unix.
+	pub mode:           Option<u32>,
+	/// Whether the entry is read-only; set only with `withMetadata`.
+	pub readonly:       Option<bool>,
+	/// Target of a symlink, as stored in the link; set only with
+	/// `withMetadata`.
+	#[napi(js_name = "symlinkTarget")]
+	pub symlink_target: Option<String>,
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -85,7 +99,7 @@ pub fn empty_recheck_ms() -> u64 {
 	env_u64("FS_SCAN_EMPTY_RECHECK_MS", DEFAULT_EMPTY_RECHECK_MS)
 }
 
-fn max_cache_entries() -> usize {
+pub(crate) fn max_cache_entries() -> usize {
 	env_usize("FS_SCAN_CACHE_MAX_ENTRIES", DEFAULT_MAX_CACHE_ENTRIES)
 }
 
@@ -93,11 +107,106 @@ fn max_cache_entries() -> usize {
 // Cache internals
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// Visibility and ignore rules applied while walking a directory tree.
+///
+/// Part of the cache key: scans with different policies never share entries.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ScanPolicy {
+	/// Include hidden (dot) files and directories.
+	pub include_hidden: bool,
+	/// Honor `.gitignore` (including those above the scan root),
+	/// `.git/info/exclude`, and `core.excludesFile` from the global or
+	/// repository git config.
+	pub git_ignore:     bool,
+	/// Honor `.ignore` files.
+	pub dot_ignore:     bool,
+	/// Honor `.rgignore` files.
+	pub rg_ignore:      bool,
+	/// Additional per-directory ignore file names, honored regardless of the
+	/// source flags above.
+	pub ignore_files:   Vec<String>,
+	/// Deepest level to descend to; direct children of the root are at
+	/// depth 1.
+	pub max_depth:      Option<usize>,
+	/// Directories whose subtrees are skipped: a bare name matches at any
+	/// depth, a name with `/` matches that path relative to the root.
+	pub exclude_dirs:   Vec<String>,
+}
+
+impl ScanPolicy {
+	/// Policy with every standard ignore source on or off and no custom
+	/// ignore files.
+	pub const fn new(include_hidden: bool, use_gitignore: bool) -> Self {
+		Self {
+			include_hidden,
+			git_ignore: use_gitignore,
+			dot_ignore: use_gitignore,
+			rg_ignore: use_gitignore,
+			ignore_files: Vec::new(),
+			max_depth: None,
+			exclude_dirs: Vec::new(),
+		}
+	}
+
+	/// Restrict the standard ignore sources to the given names
+	/// (`"gitignore"`, `"ignore"`, `"rgignore"`). `None` keeps the current
+	/// sources.
+	pub fn with_ignore_sources(mut self, sources: Option<Vec<String>>) -> Result<Self> {
+		let Some(sources) = sources else {
+			return Ok(self);
+		};
+		self.git_ignore = false;
+		self.dot_ignore = false;
+		self.rg_ignore = false;
+		for source in sources {
+			match source.trim().trim_start_matches('.') {
+				"gitignore" => self.git_ignore = true,
+				"ignore" => self.dot_ignore = true,
+				"rgignore" => self.rg_ignore = true,
+				other => {
+					return Err(Error::from_reason(format!(
+						"Unknown ignore source: {other} (expected gitignore, ignore, or rgignore)"
+					)));
+				},
+			}
+		}
+		Ok(self)
+	}
+
+	/// Add custom ignore file names, skipping blanks and duplicates.
+	pub fn with_ignore_files(mut self, names: Option<Vec<String>>) -> Self {
+		for name in names.into_iter().flatten() {
+			let name = name.trim();
+			if !name.is_empty() && !self.ignore_files.iter().any(|existing| existing == name) {
+				self.ignore_files.push(name.to_string());
+			}
+		}
+		self
+	}
+
+	/// Stop descending below `max_depth` and skip the subtrees of
+	/// `exclude_dirs`, ignoring blank names and surrounding slashes.
+	pub fn with_pruning(
+		mut self,
+		max_depth: Option<u32>,
+		exclude_dirs: Option<Vec<String>>,
+	) -> Self {
+		self.max_depth = max_depth.map(|depth| depth as usize);
+		for dir in exclude_dirs.into_iter().flatten() {
+			let dir = dir.replace('\\', "/");
+			let dir = dir.trim().trim_matches('/');
+			if !dir.is_empty() && !self.exclude_dirs.iter().any(|existing| existing == dir) {
+				self.exclude_dirs.push(dir.to_string());
+			}
+		}
+		self
+	}
+}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 struct CacheKey {
-	root:           PathBuf,
-	include_hidden: bool,
-	use_gitignore:  bool,
+	root:   PathBuf,
+	policy: ScanPolicy,
 }
 
 #[derive(Clone)]
@@ -150,6 +259,61 @@ pub fn resolve_search_path(path: &str) -> Result<PathBuf> {
 	Ok(std::fs::canonicalize(&root).unwrap_or(root))
 }
 
+/// Resolve a path string against the cwd, canonicalizing it when it exists.
+pub fn resolve_path(path: &str) -> Result<PathBuf> {
+	let candidate = PathBuf::from(path);
+	let absolute = if candidate.is_absolute() {
+		candidate
+	} else {
+		std::env::current_dir()
+			.map_err(|err| Error::from_reason(format!("Failed to resolve cwd: {err}")))?
+			.join(candidate)
+	};
+	Ok(std::fs::canonicalize(&absolute).unwrap_or(absolute))
+}
+
+/// A root of a search over several paths.
+pub struct SearchRoot {
+	/// Canonical path of the root.
+	pub path:  PathBuf,
+	/// The root as the caller wrote it, reported with its results.
+	pub label: Arc<str>,
+}
+
+/// The paths of a `string | string[]` search path option.
+///
+/// # Errors
+/// Returns an error for an empty array.
+pub fn search_paths(path: Either<String, Vec<String>>) -> Result<Vec<String>> {
+	match path {
+		Either::A(path) => Ok(vec![path]),
+		Either::B(paths) if paths.is_empty() => {
+			Err(Error::from_reason("Search path list is empty".to_string()))
+		},
+		Either::B(paths) => Ok(paths),
+	}
+}
+
+/// Resolve several search paths, dropping any that resolve to a root listed
+/// earlier.
+///
+/// Roots nested in one another are all kept; callers report files reachable
+/// from more than one root under the first.
+pub fn resolve_search_roots(
+	paths: Vec<String>,
+	resolve: impl Fn(&str) -> Result<PathBuf>,
+) -> Result<Vec<SearchRoot>> {
+	let mut roots: Vec<SearchRoot> = Vec::with_capacity(paths.len());
+	for label in paths {
+		let resolved = resolve(&label)?;
+		let path = std::fs::canonicalize(&resolved).unwrap_or(resolved);
+		if roots.iter().all(|root| root.path != path) {
+			roots.push(SearchRoot { path, label: label.into() });
+		}
+	}
+	Ok(roots)
+}
+
 /// Normalize a filesystem path to a forward-slash relative string.
 pub fn normalize_relative_path<'a>(root: &Path, path: &'a Path) -> Cow<'a, str> {
 	let relative = path.strip_prefix(root).unwrap_or(path);
@@ -186,7 +350,9 @@ pub fn should_skip_path(path: &Path, mentions_node_modules: bool) -> bool {
 	false
 }
 
-pub fn classify_file_type(path: &Path) -> Option<(FileType, Option<f64>)> {
+/// File type, mtime in milliseconds, and size in bytes (files only) of
+/// `path`, without following symlinks.
+pub fn classify_file_type(path: &Path) -> Option<(FileType, Option<f64>, Option<f64>)> {
 	let metadata = std::fs::symlink_metadata(path).ok()?;
 	let file_type = metadata.file_type();
 	let mtime_ms = metadata
@@ -195,11 +361,11 @@ pub fn classify_file_type(path: &Path) -> Option<(FileType, Option<f64>)> {
 		.and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
 		.map(|d| d.as_millis() as f64);
 	if file_type.is_symlink() {
-		Some((FileType::Symlink, mtime_ms))
+		Some((FileType::Symlink, mtime_ms, None))
 	} else if file_type.is_dir() {
-		Some((FileType::Dir, mtime_ms))
+		Some((FileType::Dir, mtime_ms, None))
 	} else {
-		Some((FileType::File, mtime_ms))
+		Some((FileType::File, mtime_ms, Some(metadata.len() as f64)))
 	}
 }
 
@@ -209,34 +375,109 @@ pub fn classify_file_type(path: &Path) -> Option<(FileType, Option<f64>)> {
 
 /// Builds a deterministic filesystem walker configured for visibility and
 /// ignore rules.
-pub fn build_walker(root: &Path, include_hidden: bool, use_gitignore: bool) -> WalkBuilder {
+pub fn build_walker(root: &Path, policy: &ScanPolicy) -> WalkBuilder {
 	let mut builder = WalkBuilder::new(root);
 	builder
-		.hidden(!include_hidden)
+		.hidden(!policy.include_hidden)
 		.follow_links(false)
 		.sort_by_file_path(|a, b| a.cmp(b));
 
-	if use_gitignore {
-		// Honor repository and global ignore files for repo-like behavior.
-		builder
-			.git_ignore(true)
-			.git_exclude(true)
-			.git_global(true)
-			.ignore(true)
-			.parents(true);
-	} else {
-		// Disable all ignore sources for exhaustive filesystem traversal.
-		builder
-			.git_ignore(false)
-			.git_exclude(false)
-			.git_global(false)
-			.ignore(false)
-			.parents(false);
+	// Honor repository and global ignore files for repo-like behavior,
+	// including `.gitignore` files above `root`; with every source disabled the
+	// traversal is exhaustive.
+	let any_source =
+		policy.git_ignore || policy.dot_ignore || policy.rg_ignore || !policy.ignore_files.is_empty();
+	builder
+		.git_ignore(policy.git_ignore)
+		.git_exclude(policy.git_ignore)
+		.git_global(policy.git_ignore)
+		.ignore(policy.dot_ignore)
+		.parents(any_source);
+
+	if policy.git_ignore
+		&& let Some(excludes) = repo_excludes_file(root)
+	{
+		builder.add_ignore(excludes);
+	}
+	if policy.rg_ignore {
+		builder.add_custom_ignore_filename(".rgignore");
+	}
+	for name in &policy.ignore_files {
+		builder.add_custom_ignore_filename(name);
+	}
+
+	builder.max_depth(policy.max_depth);
+	if !policy.exclude_dirs.is_empty() {
+		let root = root.to_path_buf();
+		let exclude_dirs = policy.exclude_dirs.clone();
+		builder.filter_entry(move |entry| {
+			if !entry
+				.file_type()
+				.is_some_and(|file_type| file_type.is_dir())
+			{
+				return true;
+			}
+			let name = entry.file_name().to_string_lossy();
+			let relative = normalize_relative_path(&root, entry.path());
+			!exclude_dirs.iter().any(|dir| {
+				if dir.contains('/') {
+					*dir == relative
+				} else {
+					*dir == name
+				}
+			})
+		});
 	}
 
 	builder
 }
 
+/// Last value of `key` in `section` of a git config file (names compared
+/// case-insensitively, subsections ignored).
+fn git_config_value(config: &str, section: &str, key: &str) -> Option<String> {
+	let mut current = String::new();
+	let mut value = None;
+	for line in config.lines() {
+		let line = line.trim();
+		if let Some(header) = line.strip_prefix('[') {
+			let name = header.split([']', ' ', '"']).next().unwrap_or_default();
+			current = name.to_ascii_lowercase();
+			continue;
+		}
+		let Some((name, raw)) = line.split_once('=') else {
+			continue;
+		};
+		if current == section && name.trim().eq_ignore_ascii_case(key) {
+			value = Some(raw.trim().trim_matches('"').to_string());
+		}
+	}
+	value
+}
+
+/// `core.excludesFile` set in the config of the repository containing
+/// `root`. The walker only reads the option from the global git config, but
+/// git also honors it per repository.
+fn repo_excludes_file(root: &Path) -> Option<PathBuf> {
+	let (work_tree, git_dir) = root.ancestors().find_map(|dir| {
+		let dot_git = dir.join(".git");
+		if dot_git.is_dir() {
+			return Some((dir, dot_git));
+		}
+		// Linked worktrees and submodules point at their git dir.
+		let link = std::fs::read_to_string(&dot_git).ok()?;
+		Some((dir, dir.join(link.strip_prefix("gitdir:")?.trim())))
+	})?;
+	let common_dir = std::fs::read_to_string(git_dir.join("commondir"))
+		.map_or_else(|_| git_dir.clone(), |common| git_dir.join(common.trim()));
+	let config = std::fs::read_to_string(common_dir.join("config")).ok()?;
+	let value = git_config_value(&config, "core", "excludesfile")?;
+	let path = match value.strip_prefix("~/") {
+		Some(rest) => std::env::home_dir()?.join(rest),
+		None => PathBuf::from(value),
+	};
+	Some(work_tree.join(path))
+}
+
 /// Scans filesystem entries and records normalized relative paths with file
 /// metadata.
 ///
@@ -244,11 +485,10 @@ pub fn build_walker(root: &Path, include_hidden: bool, use_gitignore: bool) -> W
 /// exclusion.
 fn collect_entries(
 	root: &Path,
-	include_hidden: bool,
-	use_gitignore: bool,
+	policy: &ScanPolicy,
 	ct: &task::CancelToken,
 ) -> Result<Vec<GlobMatch>> {
-	let builder = build_walker(root, include_hidden, use_gitignore);
+	let builder = build_walker(root, policy);
 	let mut entries = Vec::new();
 
 	for entry in builder.build() {
@@ -267,11 +507,20 @@ fn collect_entries(
 			continue;
 		}
 
-		let Some((file_type, mtime)) = classify_file_type(path) else {
+		let Some((file_type, mtime, size)) = classify_file_type(path) else {
 			continue;
 		};
 
-		entries.push(GlobMatch { path: relative.into_owned(), file_type, mtime });
+		entries.push(GlobMatch {
+			path: relative.into_owned(),
+			file_type,
+			mtime,
+			size,
+			root: None,
+			mode: None,
+			readonly: None,
+			symlink_target: None,
+		});
 	}
 
 	Ok(entries)
@@ -287,20 +536,15 @@ fn collect_entries(
 /// empty-result fast recheck: if a query produces zero matches and the cache is
 /// older than [`empty_recheck_ms()`], call [`force_rescan`] before returning
 /// empty.
-pub fn get_or_scan(
-	root: &Path,
-	include_hidden: bool,
-	use_gitignore: bool,
-	ct: &task::CancelToken,
-) -> Result<ScanResult> {
+pub fn get_or_scan(root: &Path, policy: &ScanPolicy, ct: &task::CancelToken) -> Result<ScanResult> {
 	let ttl = cache_ttl_ms();
 	if ttl == 0 {
 		// Caching disabled – always scan fresh.
-		let entries = collect_entries(root, include_hidden, use_gitignore, ct)?;
+		let entries = collect_entries(root, policy, ct)?;
 		return Ok(ScanResult { entries, cache_age_ms: 0 });
 	}
 
-	let key = CacheKey { root: root.to_path_buf(), include_hidden, use_gitignore };
+	let key = CacheKey { root: root.to_path_buf(), policy: policy.clone() };
 
 	let now = Instant::now();
 	if let Some(entry) = FS_CACHE.get(&key) {
@@ -315,7 +559,7 @@ pub fn get_or_scan(
 		FS_CACHE.remove(&key);
 	}
 
-	let entries = collect_entries(root, include_hidden, use_gitignore, ct)?;
+	let entries = collect_entries(root, policy, ct)?;
 	FS_CACHE.insert(key, CacheEntry { created_at: now, entries: entries.clone() });
 	evict_oldest();
 	Ok(ScanResult { entries, cache_age_ms: 0 })
@@ -328,15 +572,14 @@ pub fn get_or_scan(
 /// returned without repopulating the cache.
 pub fn force_rescan(
 	root: &Path,
-	include_hidden: bool,
-	use_gitignore: bool,
+	policy: &ScanPolicy,
 	store: bool,
 	ct: &task::CancelToken,
 ) -> Result<Vec<GlobMatch>> {
-	let key = CacheKey { root: root.to_path_buf(), include_hidden, use_gitignore };
+	let key = CacheKey { root: root.to_path_buf(), policy: policy.clone() };
 	FS_CACHE.remove(&key);
 
-	let entries = collect_entries(root, include_hidden, use_gitignore, ct)?;
+	let entries = collect_entries(root, policy, ct)?;
 	if store {
 		let now = Instant::now();
 		FS_CACHE.insert(key, CacheEntry { created_at: now, entries: entries.clone() });
@@ -349,24 +592,50 @@ pub fn force_rescan(
 // Invalidation
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Invalidate cache entries whose root contains `target`.
+/// Invalidate cache entries whose root contains `target` or lies under it.
 ///
-/// Removes any cache entry whose root is a prefix of (or equal to) `target`,
-/// because a file mutation under that root makes the scan stale.
-pub fn invalidate_path(target: &Path) {
+/// A file mutation under a root makes its scan stale, and so does a bulk
+/// change to a directory (a build or `git checkout`) for every root below it.
+/// Returns the number of entries removed.
+pub fn invalidate_path(target: &Path) -> usize {
 	let keys_to_remove: Vec<CacheKey> = FS_CACHE
 		.iter()
-		.filter(|entry| target.starts_with(&entry.key().root))
+		.filter(|entry| target.starts_with(&entry.key().root) || entry.key().root.starts_with(target))
 		.map(|entry| entry.key().clone())
 		.collect();
-	for key in keys_to_remove {
-		FS_CACHE.remove(&key);
-	}
+	keys_to_remove
+		.iter()
+		.filter(|key| FS_CACHE.remove(key).is_some())
+		.count()
 }
 
-/// Clear the entire scan cache.
-pub fn invalidate_all() {
+/// Clear the entire scan cache, returning the number of entries removed.
+pub fn invalidate_all() -> usize {
+	let removed = FS_CACHE.len();
 	FS_CACHE.clear();
+	removed
+}
+
+/// Resolve a user-supplied path the way cache roots are stored (absolute and
+/// canonical), tolerating a target that no longer exists.
+fn resolve_invalidation_target(path: &str) -> PathBuf {
+	let candidate = PathBuf::from(path);
+	let absolute = if candidate.is_absolute() {
+		candidate
+	} else if let Ok(cwd) = std::env::current_dir() {
+		cwd.join(candidate)
+	} else {
+		PathBuf::from(path)
+	};
+	std::fs::canonicalize(&absolute)
+		.or_else(|_| {
+			absolute
+				.parent()
+				.and_then(|parent| std::fs::canonicalize(parent).ok())
+				.and_then(|parent| absolute.file_name().map(|name| parent.join(name)))
+				.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+		})
+		.unwrap_or(absolute)
 }
 
 /// Invalidate the filesystem scan cache.
@@ -378,27 +647,279 @@ pub fn invalidate_all() {
 /// delete).
 #[napi(js_name = "invalidateFsScanCache")]
 pub fn invalidate_fs_scan_cache(path: Option<String>) {
-	match path {
-		Some(p) => {
-			let candidate = PathBuf::from(&p);
-			let absolute = if candidate.is_absolute() {
-				candidate
-			} else if let Ok(cwd) = std::env::current_dir() {
-				cwd.join(candidate)
-			} else {
-				PathBuf::from(&p)
-			};
-			let target = std::fs::canonicalize(&absolute)
-				.or_else(|_| {
-					absolute
-						.parent()
-						.and_then(|parent| std::fs::canonicalize(parent).ok())
-						.and_then(|parent| absolute.file_name().map(|name| parent.join(name)))
-						.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
-				})
-				.unwrap_or(absolute);
-			invalidate_path(&target);
-		},
+	fs_cache_invalidate(path);
+}
+
+/// Drop cached scans affected by changes at `path`, or every cached scan when
+/// omitted, and return how many were dropped.
+///
+/// Roots containing `path` and roots under it are both dropped, so calling
+/// this with a repository root after a build or `git checkout` clears every
+/// scan inside the repository.
+#[napi(js_name = "fsCacheInvalidate")]
+pub fn fs_cache_invalidate(path: Option<String>) -> u32 {
+	let removed = match path {
+		Some(path) => invalidate_path(&resolve_invalidation_target(&path)),
 		None => invalidate_all(),
+	};
+	crate::utils::clamp_u32(removed as u64)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Statistics
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// One cached scan.
+#[napi(object)]
+pub struct FsCacheRoot {
+	/// Absolute root of the scan.
+	pub root:         String,
+	/// Filesystem entries held for this scan.
+	pub entries:      u32,
+	/// Milliseconds since the scan ran.
+	#[napi(js_name = "ageMs")]
+	pub age_ms:       f64,
+	/// Whether the scan includes hidden files.
+	pub hidden:       bool,
+	/// Whether the scan honors `.gitignore`.
+	pub gitignore:    bool,
+	/// Approximate heap bytes held by the entries.
+	#[napi(js_name = "memoryBytes")]
+	pub memory_bytes: f64,
+}
+
+/// Snapshot of the filesystem scan cache.
+#[napi(object)]
+pub struct FsCacheStats {
+	/// Cached scans.
+	pub scans:        u32,
+	/// Filesystem entries across all scans.
+	pub entries:      u32,
+	/// Approximate heap bytes held by all scans.
+	#[napi(js_name = "memoryBytes")]
+	pub memory_bytes: f64,
+	/// Scan lifetime in milliseconds (`FS_SCAN_CACHE_TTL_MS`).
+	#[napi(js_name = "ttlMs")]
+	pub ttl_ms:       f64,
+	/// Maximum cached scans (`FS_SCAN_CACHE_MAX_ENTRIES`).
+	#[napi(js_name = "maxScans")]
+	pub max_scans:    u32,
+	/// Cached scans, oldest first.
+	pub roots:        Vec<FsCacheRoot>,
+}
+
+fn entries_memory_bytes(entries: &[GlobMatch]) -> usize {
+	std::mem::size_of_val(entries)
+		+ entries
+			.iter()
+			.map(|entry| entry.path.capacity())
+			.sum::<usize>()
+}
+
+/// Report what the filesystem scan cache currently holds.
+#[napi(js_name = "fsCacheStats")]
+pub fn fs_cache_stats() -> FsCacheStats {
+	let now = Instant::now();
+	let mut roots: Vec<(Instant, FsCacheRoot)> = FS_CACHE
+		.iter()
+		.map(|entry| {
+			let (key, value) = entry.pair();
+			let root = FsCacheRoot {
+				root:         key.root.to_string_lossy().into_owned(),
+				entries:      crate::utils::clamp_u32(value.entries.len() as u64),
+				age_ms:       now.duration_since(value.created_at).as_secs_f64() * 1000.0,
+				hidden:       key.policy.include_hidden,
+				gitignore:    key.policy.git_ignore,
+				memory_bytes: entries_memory_bytes(&value.entries) as f64,
+			};
+			(value.created_at, root)
+		})
+		.collect();
+	roots.sort_by_key(|(created_at, _)| *created_at);
+	let roots: Vec<FsCacheRoot> = roots.into_iter().map(|(_, root)| root).collect();
+	FsCacheStats {
+		scans: crate::utils::clamp_u32(roots.len() as u64),
+		entries: roots
+			.iter()
+			.map(|root| root.entries)
+			.fold(0, u32::saturating_add),
+		memory_bytes: roots.iter().map(|root| root.memory_bytes).sum(),
+		ttl_ms: cache_ttl_ms() as f64,
+		max_scans: crate::utils::clamp_u32(max_cache_entries() as u64),
+		roots,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+
+	#[test]
+	fn custom_ignore_files_apply_without_gitignore() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-fs-cache-ignore-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		fs::write(root.join(".rgignore"), "skipped.txt\n").expect(".rgignore should be written");
+		fs::write(root.join("kept.txt"), "").expect("kept.txt should be written");
+		fs::write(root.join("skipped.txt"), "").expect("skipped.txt should be written");
+
+		let ct = task::CancelToken::default();
+		let paths = |policy: &ScanPolicy| {
+			let mut paths = force_rescan(&root, policy, false, &ct)
+				.expect("scan should succeed")
+				.into_iter()
+				.filter(|entry| entry.file_type == FileType::File && !entry.path.starts_with('.'))
+				.map(|entry| entry.path)
+				.collect::<Vec<_>>();
+			paths.sort();
+			paths
+		};
+
+		let plain = ScanPolicy::new(true, false);
+		assert_eq!(paths(&plain), vec!["kept.txt", "skipped.txt"]);
+		let custom = plain.with_ignore_files(Some(vec![".rgignore".to_string()]));
+		assert_eq!(paths(&custom), vec!["kept.txt"]);
+
+		let standard = ScanPolicy::new(true, true);
+		assert_eq!(paths(&standard), vec!["kept.txt"]);
+		let git_only = ScanPolicy::new(true, true)
+			.with_ignore_sources(Some(vec!["gitignore".to_string()]))
+			.expect("gitignore should be a valid source");
+		assert_eq!(paths(&git_only), vec!["kept.txt", "skipped.txt"]);
+		assert!(
+			ScanPolicy::new(true, true)
+				.with_ignore_sources(Some(vec!["hgignore".to_string()]))
+				.is_err()
+		);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn subdirectory_scans_honor_repository_excludes() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-fs-cache-excludes-test-{unique}"));
+		fs::create_dir_all(root.join("sub/secret")).expect("temp dir should be created");
+		let status = std::process::Command::new("git")
+			.arg("-C")
+			.arg(&root)
+			.args(["init", "-q"])
+			.status()
+			.expect("git should run");
+		assert!(status.success());
+		fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+		fs::write(root.join(".git/info/exclude"), "secret/\n").unwrap();
+		fs::write(root.join("excludes"), "*.tmp\n").unwrap();
+		let mut config = fs::OpenOptions::new()
+			.append(true)
+			.open(root.join(".git/config"))
+			.unwrap();
+		std::io::Write::write_all(&mut config, b"[core]\n\texcludesFile = excludes\n").unwrap();
+		for file in ["sub/a.log", "sub/b.tmp", "sub/keep.txt", "sub/secret/x"] {
+			fs::write(root.join(file), "").unwrap();
+		}
+
+		let ct = task::CancelToken::default();
+		let scan = |policy: &ScanPolicy| {
+			let mut paths: Vec<String> = force_rescan(&root.join("sub"), policy, false, &ct)
+				.expect("scan should succeed")
+				.into_iter()
+				.filter(|entry| entry.file_type == FileType::File)
+				.map(|entry| entry.path)
+				.collect();
+			paths.sort();
+			paths
+		};
+		assert_eq!(scan(&ScanPolicy::new(true, true)), ["keep.txt"]);
+		assert_eq!(scan(&ScanPolicy::new(true, false)), ["a.log", "b.tmp", "keep.txt", "secret/x"]);
+		assert_eq!(
+			git_config_value(
+				"[Core]\n\tExcludesFile = \"a\"\n[user]\nexcludesfile = b\n",
+				"core",
+				"excludesfile"
+			),
+			Some("a".to_string())
+		);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn reports_and_invalidates_nested_roots() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let parent = std::env::temp_dir().join(format!("pi-fs-cache-stats-test-{unique}"));
+		let root = parent.join("sub");
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		fs::write(root.join("a.txt"), "").expect("a.txt should be written");
+		let root = fs::canonicalize(&root).unwrap();
+		let root_str = root.to_string_lossy().into_owned();
+
+		force_rescan(&root, &ScanPolicy::new(true, true), true, &task::CancelToken::default())
+			.expect("scan should succeed");
+		let stats = fs_cache_stats();
+		let cached = stats
+			.roots
+			.iter()
+			.find(|cached| cached.root == root_str)
+			.expect("scan should be cached");
+		assert!(cached.entries >= 1 && cached.memory_bytes > 0.0 && cached.hidden);
+
+		assert_eq!(fs_cache_invalidate(Some(parent.to_string_lossy().into_owned())), 1);
+		assert!(
+			!fs_cache_stats()
+				.roots
+				.iter()
+				.any(|cached| cached.root == root_str)
+		);
+
+		let _ = fs::remove_dir_all(&parent);
+	}
+
+	#[test]
+	fn pruning_skips_deep_and_excluded_dirs() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-fs-cache-prune-test-{unique}"));
+		for path in ["a.txt", "src/b.txt", "src/build/c.txt", "build/d.txt", "src/deep/e/f.txt"] {
+			let path = root.join(path);
+			fs::create_dir_all(path.parent().unwrap()).expect("temp dir should be created");
+			fs::write(path, "").expect("fixture should be written");
+		}
+
+		let ct = task::CancelToken::default();
+		let paths = |policy: ScanPolicy| {
+			let mut paths = force_rescan(&root, &policy, false, &ct)
+				.expect("scan should succeed")
+				.into_iter()
+				.filter(|entry| entry.file_type == FileType::File)
+				.map(|entry| entry.path)
+				.collect::<Vec<_>>();
+			paths.sort();
+			paths
+		};
+
+		let by_name = ScanPolicy::new(false, false).with_pruning(None, Some(vec!["build".into()]));
+		assert_eq!(paths(by_name), vec!["a.txt", "src/b.txt", "src/deep/e/f.txt"]);
+		let by_path =
+			ScanPolicy::new(false, false).with_pruning(Some(2), Some(vec!["/build/".into()]));
+		assert_eq!(paths(by_path), vec!["a.txt", "src/b.txt"]);
+
+		let _ = fs::remove_dir_all(&root);
 	}
 }
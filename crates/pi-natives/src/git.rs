@@ -0,0 +1,831 @@
+//! Git queries used to scope and annotate searches, report work tree
+//! status, and read files at a revision.
+//!
+//! The addon has no libgit; it runs the `git` executable the user already
+//! has, with NUL-separated output so paths never need unquoting. Each call is
+//! polled against the caller's [`task::CancelToken`] and the child is killed
+//! on abort, so a slow `git` in a huge repository stays cancellable.
+
+use std::{
+	collections::{HashMap, HashSet},
+	io::{self, Read},
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+	thread,
+	time::Duration,
+};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::{fs_cache, grep, task};
+
+/// Last commit that touched a line.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[napi(object)]
+pub struct BlameInfo {
+	/// Abbreviated commit hash.
+	pub commit:       String,
+	/// Author name.
+	pub author:       String,
+	/// Author email, without angle brackets.
+	#[napi(js_name = "authorEmail")]
+	pub author_email: String,
+	/// Author time in milliseconds since the Unix epoch.
+	pub time:         f64,
+	/// First line of the commit message.
+	pub summary:      String,
+	/// Whether the line has uncommitted changes (all other fields describe
+	/// the working tree, not a commit).
+	pub uncommitted:  bool,
+}
+
+/// Which changes `changedOnly` selects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangedScope {
+	/// Staged, unstaged, and untracked changes relative to `HEAD`.
+	Working,
+	/// Changes staged in the index.
+	Staged,
+	/// Everything changed since the branch forked from a ref, committed or
+	/// not, plus untracked files.
+	Branch(String),
+}
+
+impl ChangedScope {
+	/// Parse `"working"`, `"staged"`, or `"branch:<ref>"`.
+	pub fn parse(value: &str) -> Result<Self> {
+		match value.trim() {
+			"working" => Ok(Self::Working),
+			"staged" => Ok(Self::Staged),
+			value => match value.strip_prefix("branch:").map(str::trim) {
+				Some(reference) if !reference.is_empty() && !reference.starts_with('-') => {
+					Ok(Self::Branch(reference.to_string()))
+				},
+				_ => Err(Error::from_reason(format!(
+					"Invalid changedOnly: {value} (expected \"working\", \"staged\", or \
+					 \"branch:<ref>\")"
+				))),
+			},
+		}
+	}
+}
+
+/// Run `git -C dir <args>` and return its stdout.
+pub fn run_git(dir: &Path, args: &[&str], ct: &task::CancelToken) -> Result<Vec<u8>> {
+	run_git_capped(dir, args, u64::MAX, ct)
+}
+
+/// [`run_git`] keeping at most `limit` bytes of stdout; the rest is drained
+/// so git still exits normally.
+fn run_git_capped(
+	dir: &Path,
+	args: &[&str],
+	limit: u64,
+	ct: &task::CancelToken,
+) -> Result<Vec<u8>> {
+	ct.heartbeat()?;
+	let mut child = Command::new("git")
+		.arg("-C")
+		.arg(dir)
+		.args(args)
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.map_err(|err| Error::from_reason(format!("Failed to run git: {err}")))?;
+	let drain = |pipe: Option<Box<dyn Read + Send>>, limit: u64| {
+		thread::spawn(move || {
+			let mut buf = Vec::new();
+			if let Some(mut pipe) = pipe {
+				let _ = pipe.by_ref().take(limit).read_to_end(&mut buf);
+				let _ = io::copy(&mut pipe, &mut io::sink());
+			}
+			buf
+		})
+	};
+	let stdout = drain(child.stdout.take().map(|pipe| Box::new(pipe) as _), limit);
+	let stderr = drain(child.stderr.take().map(|pipe| Box::new(pipe) as _), u64::MAX);
+	let status = loop {
+		if let Err(err) = ct.heartbeat() {
+			let _ = child.kill();
+			let _ = child.wait();
+			return Err(err);
+		}
+		match child.try_wait() {
+			Ok(Some(status)) => break status,
+			Ok(None) => thread::sleep(Duration::from_millis(5)),
+			Err(err) => return Err(Error::from_reason(format!("Failed to wait for git: {err}"))),
+		}
+	};
+	let stdout = stdout.join().unwrap_or_default();
+	let stderr = stderr.join().unwrap_or_default();
+	if !status.success() {
+		let message = String::from_utf8_lossy(&stderr);
+		return Err(Error::from_reason(format!("git {} failed: {}", args[0], message.trim())));
+	}
+	Ok(stdout)
+}
+
+/// Canonical top-level directory of the work tree containing `dir`.
+pub fn work_tree_root(dir: &Path, ct: &task::CancelToken) -> Result<PathBuf> {
+	let output = run_git(dir, &["rev-parse", "--show-toplevel"], ct)
+		.map_err(|_| Error::from_reason(format!("Not inside a git work tree: {}", dir.display())))?;
+	let root = PathBuf::from(String::from_utf8_lossy(&output).trim_end_matches(['\n', '\r']));
+	Ok(std::fs::canonicalize(&root).unwrap_or(root))
+}
+
+fn split_nul(output: &[u8]) -> impl Iterator<Item = &str> {
+	output
+		.split(|&byte| byte == 0)
+		.filter(|entry| !entry.is_empty())
+		.filter_map(|entry| std::str::from_utf8(entry).ok())
+}
+
+/// One record of `git status --porcelain -z`.
+struct StatusRecord<'a> {
+	/// Index and work tree status codes.
+	code:     &'a str,
+	path:     &'a str,
+	/// Source path of a rename or copy, sent as an extra field.
+	original: Option<&'a str>,
+}
+
+/// Records of `git status --porcelain -z`, with the `## ` branch header
+/// (present with `--branch`) split off.
+fn status_records(output: &[u8]) -> (Option<&str>, Vec<StatusRecord<'_>>) {
+	let mut header = None;
+	let mut records = Vec::new();
+	let mut fields = split_nul(output);
+	while let Some(record) = fields.next() {
+		if let Some(branch) = record.strip_prefix("## ") {
+			header = Some(branch);
+			continue;
+		}
+		let (Some(code), Some(path)) = (record.get(..2), record.get(3..)) else {
+			continue;
+		};
+		let original = if code.contains(['R', 'C']) {
+			fields.next()
+		} else {
+			None
+		};
+		records.push(StatusRecord { code, path, original });
+	}
+	(header, records)
+}
+
+/// Paths from `git status --porcelain -z`.
+fn status_paths(output: &[u8]) -> Vec<&str> {
+	status_records(output)
+		.1
+		.into_iter()
+		.map(|record| record.path)
+		.collect()
+}
+
+/// Files changed in the work tree containing `dir`, as absolute paths under
+/// its canonical top-level directory. Deleted files are left out.
+pub fn changed_files(
+	dir: &Path,
+	scope: &ChangedScope,
+	ct: &task::CancelToken,
+) -> Result<HashSet<PathBuf>> {
+	let root = work_tree_root(dir, ct)?;
+	let untracked = ["ls-files", "-z", "--others", "--exclude-standard"];
+	let mut relative: Vec<String> = Vec::new();
+	let mut collect = |output: &[u8]| relative.extend(split_nul(output).map(str::to_string));
+	match scope {
+		ChangedScope::Working => {
+			let output =
+				run_git(&root, &["status", "--porcelain=v1", "-z", "--untracked-files=all"], ct)?;
+			relative.extend(status_paths(&output).into_iter().map(str::to_string));
+		},
+		ChangedScope::Staged => {
+			collect(&run_git(
+				&root,
+				&["diff", "--cached", "--name-only", "-z", "--diff-filter=d"],
+				ct,
+			)?);
+		},
+		ChangedScope::Branch(reference) => {
+			let base = run_git(&root, &["merge-base", reference, "HEAD"], ct)?;
+			let base = String::from_utf8_lossy(&base).trim().to_string();
+			collect(&run_git(&root, &["diff", "--name-only", "-z", "--diff-filter=d", &base], ct)?);
+			collect(&run_git(&root, &untracked, ct)?);
+		},
+	}
+	Ok(relative
+		.into_iter()
+		.map(|path| root.join(path))
+		.filter(|path| path.is_file())
+		.collect())
+}
+
+/// Collapse sorted line numbers into `-L start,end` ranges.
+fn line_ranges(lines: &[u32]) -> Vec<(u32, u32)> {
+	let mut ranges: Vec<(u32, u32)> = Vec::new();
+	for &line in lines {
+		match ranges.last_mut() {
+			Some((_, end)) if line <= *end + 1 => *end = (*end).max(line),
+			_ => ranges.push((line, line)),
+		}
+	}
+	ranges
+}
+
+/// Parse `git blame --porcelain` output into per-line annotations. Commit
+/// details are only printed the first time a commit appears.
+fn parse_porcelain(output: &str) -> HashMap<u32, BlameInfo> {
+	let mut commits: HashMap<&str, BlameInfo> = HashMap::new();
+	let mut lines: HashMap<u32, &str> = HashMap::new();
+	let mut current: Option<(&str, u32)> = None;
+	for line in output.lines() {
+		if line.starts_with('\t') {
+			if let Some((sha, final_line)) = current {
+				lines.insert(final_line, sha);
+			}
+			continue;
+		}
+		let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+		if key.len() == 40 && key.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+			let final_line = value.split(' ').nth(1).and_then(|line| line.parse().ok());
+			current = final_line.map(|final_line| (key, final_line));
+			commits.entry(key).or_insert_with(|| BlameInfo {
+				commit: key[..8].to_string(),
+				uncommitted: key.bytes().all(|byte| byte == b'0'),
+				..BlameInfo::default()
+			});
+			continue;
+		}
+		let Some(info) = current.and_then(|(sha, _)| commits.get_mut(sha)) else {
+			continue;
+		};
+		match key {
+			"author" => value.clone_into(&mut info.author),
+			"author-mail" => {
+				value
+					.trim_start_matches('<')
+					.trim_end_matches('>')
+					.clone_into(&mut info.author_email);
+			},
+			"author-time" => info.time = value.parse::<f64>().unwrap_or(0.0) * 1000.0,
+			"summary" => value.clone_into(&mut info.summary),
+			_ => {},
+		}
+	}
+	lines
+		.into_iter()
+		.filter_map(|(line, sha)| commits.get(sha).map(|info| (line, info.clone())))
+		.collect()
+}
+
+/// Blame the given 1-indexed lines of `file` in one `git blame` run.
+///
+/// Lines outside the file are ignored. Fails when the file is not tracked.
+pub fn blame_lines(
+	file: &Path,
+	lines: &[u32],
+	ct: &task::CancelToken,
+) -> Result<HashMap<u32, BlameInfo>> {
+	let mut lines: Vec<u32> = lines.iter().copied().filter(|&line| line > 0).collect();
+	lines.sort_unstable();
+	lines.dedup();
+	let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
+		return Ok(HashMap::new());
+	};
+	if lines.is_empty() {
+		return Ok(HashMap::new());
+	}
+	let content = std::fs::read(file)
+		.map_err(|err| Error::from_reason(format!("Failed to read {}: {err}", file.display())))?;
+	let line_count = String::from_utf8_lossy(&content).lines().count();
+	let ranges: Vec<String> = line_ranges(&lines)
+		.into_iter()
+		.filter(|&(start, _)| (start as usize) <= line_count)
+		.map(|(start, end)| format!("-L{start},{}", (end as usize).min(line_count)))
+		.collect();
+	if ranges.is_empty() {
+		return Ok(HashMap::new());
+	}
+	let name = name.to_string_lossy();
+	let mut args = vec!["blame", "--porcelain"];
+	args.extend(ranges.iter().map(String::as_str));
+	args.extend(["--", &name]);
+	let output = run_git(dir, &args, ct)?;
+	Ok(parse_porcelain(&String::from_utf8_lossy(&output)))
+}
+
+/// Attach blame to `items`, running `git blame` once per file.
+///
+/// `file` gives an item's absolute path, `line` its 1-indexed line, and `set`
+/// stores the annotation. Files git cannot blame (untracked or deleted) are
+/// left unannotated.
+pub fn annotate_blame<T>(
+	items: &mut [T],
+	file: impl Fn(&T) -> PathBuf,
+	line: impl Fn(&T) -> u32,
+	set: impl Fn(&mut T, BlameInfo),
+	ct: &task::CancelToken,
+) -> Result<()> {
+	let mut by_file: HashMap<PathBuf, Vec<usize>> = HashMap::new();
+	for (index, item) in items.iter().enumerate() {
+		by_file.entry(file(item)).or_default().push(index);
+	}
+	for (path, indices) in by_file {
+		let lines: Vec<u32> = indices.iter().map(|&index| line(&items[index])).collect();
+		let Ok(blame) = blame_lines(&path, &lines, ct) else {
+			ct.heartbeat()?;
+			continue;
+		};
+		for index in indices {
+			if let Some(info) = blame.get(&line(&items[index])) {
+				set(&mut items[index], info.clone());
+			}
+		}
+	}
+	Ok(())
+}
+
+/// A changed path reported by `gitStatus()`.
+#[napi(object)]
+pub struct GitStatusEntry {
+	/// Path relative to the work tree root.
+	pub path:          String,
+	/// Source path of a rename or copy.
+	#[napi(js_name = "originalPath")]
+	pub original_path: Option<String>,
+	/// Index status code as `git status --short` prints it: "M", "T", "A",
+	/// "D", "R", "C", "U", "?" (untracked), or " " (unchanged).
+	pub index:         String,
+	/// Work tree status code, with the same codes as `index`.
+	#[napi(js_name = "workTree")]
+	pub work_tree:     String,
+}
+
+/// Work tree state from `gitStatus()`.
+#[napi(object)]
+pub struct GitStatus {
+	/// Canonical top-level directory of the work tree.
+	pub root:     String,
+	/// Checked-out branch; absent when `HEAD` is detached.
+	pub branch:   Option<String>,
+	/// Upstream branch the current branch tracks.
+	pub upstream: Option<String>,
+	/// Commits on the branch that are not on its upstream.
+	pub ahead:    u32,
+	/// Commits on the upstream that are not on the branch.
+	pub behind:   u32,
+	/// Changed and untracked paths.
+	pub entries:  Vec<GitStatusEntry>,
+}
+
+/// Parse the `## ` header of `git status --branch`: `main`,
+/// `main...origin/main [ahead 1, behind 2]`, `No commits yet on main`, or
+/// `HEAD (no branch)`.
+fn parse_branch_header(header: &str) -> (Option<String>, Option<String>, u32, u32) {
+	if header.starts_with("HEAD (no branch)") {
+		return (None, None, 0, 0);
+	}
+	let header = header
+		.strip_prefix("No commits yet on ")
+		.or_else(|| header.strip_prefix("Initial commit on "))
+		.unwrap_or(header);
+	let (names, counts) = header.split_once(" [").unwrap_or((header, ""));
+	let (branch, upstream) = match names.split_once("...") {
+		Some((branch, upstream)) => (branch, Some(upstream.to_string())),
+		None => (names, None),
+	};
+	let (mut ahead, mut behind) = (0, 0);
+	for count in counts.trim_end_matches(']').split(", ") {
+		match count.split_once(' ') {
+			Some(("ahead", value)) => ahead = value.parse().unwrap_or(0),
+			Some(("behind", value)) => behind = value.parse().unwrap_or(0),
+			_ => {},
+		}
+	}
+	(Some(branch.to_string()), upstream, ahead, behind)
+}
+
+fn git_status_sync(dir: &Path, ct: &task::CancelToken) -> Result<GitStatus> {
+	let root = work_tree_root(dir, ct)?;
+	let output =
+		run_git(&root, &["status", "--porcelain=v1", "-z", "--branch", "--untracked-files=all"], ct)?;
+	let (header, records) = status_records(&output);
+	let (branch, upstream, ahead, behind) = header.map_or((None, None, 0, 0), parse_branch_header);
+	let entries = records
+		.into_iter()
+		.map(|record| GitStatusEntry {
+			path:          record.path.to_string(),
+			original_path: record.original.map(str::to_string),
+			index:         record.code[..1].to_string(),
+			work_tree:     record.code[1..].to_string(),
+		})
+		.collect();
+	Ok(GitStatus {
+		root: root.to_string_lossy().into_owned(),
+		branch,
+		upstream,
+		ahead,
+		behind,
+		entries,
+	})
+}
+
+/// Report the branch and changed paths of the work tree containing `root`.
+///
+/// # Errors
+/// Fails when `root` is not a directory inside a git work tree.
+#[napi(js_name = "gitStatus")]
+pub fn git_status(root: String) -> Result<task::Async<GitStatus>> {
+	let dir = fs_cache::resolve_search_path(&root)?;
+	Ok(task::blocking("git.status", (), move |ct| git_status_sync(&dir, &ct)))
+}
+
+/// Blame one line of a file.
+///
+/// Resolves to `null` when the line is past the end of the file.
+///
+/// # Errors
+/// Fails when the file cannot be read or git cannot blame it (untracked, or
+/// outside a work tree).
+#[napi(js_name = "gitBlameLine")]
+pub fn git_blame_line(path: String, line: u32) -> Result<task::Async<Option<BlameInfo>>> {
+	let file = fs_cache::resolve_path(&path)?;
+	Ok(task::blocking(
+		"git.blame",
+		(),
+		move |ct| Ok(blame_lines(&file, &[line], &ct)?.remove(&line)),
+	))
+}
+
+/// A file changed between two revisions, from `changedFiles()`.
+#[napi(object)]
+pub struct ChangedFile {
+	/// Absolute path under the work tree root.
+	pub path:          String,
+	/// "added", "modified", "deleted", "renamed", "copied", or "typechange".
+	pub status:        String,
+	/// Absolute source path of a rename or copy.
+	#[napi(js_name = "originalPath")]
+	pub original_path: Option<String>,
+}
+
+/// Parse `git diff --name-status -z` output; rename and copy records carry
+/// the source path before the destination.
+fn parse_name_status(output: &[u8], root: &Path) -> Vec<ChangedFile> {
+	let mut files = Vec::new();
+	let mut fields = split_nul(output);
+	while let Some(code) = fields.next() {
+		let status = match code.as_bytes().first() {
+			Some(b'A') => "added",
+			Some(b'D') => "deleted",
+			Some(b'R') => "renamed",
+			Some(b'C') => "copied",
+			Some(b'T') => "typechange",
+			_ => "modified",
+		};
+		let original = if matches!(status, "renamed" | "copied") {
+			fields.next()
+		} else {
+			None
+		};
+		let Some(path) = fields.next() else {
+			break;
+		};
+		files.push(ChangedFile {
+			path:          root.join(path).to_string_lossy().into_owned(),
+			status:        status.to_string(),
+			original_path: original.map(|path| root.join(path).to_string_lossy().into_owned()),
+		});
+	}
+	files
+}
+
+fn changed_between_sync(
+	dir: &Path,
+	base: &str,
+	head: Option<&str>,
+	ct: &task::CancelToken,
+) -> Result<Vec<ChangedFile>> {
+	let root = work_tree_root(dir, ct)?;
+	let mut args = vec!["diff", "--name-status", "-z", "-M", base];
+	args.extend(head);
+	args.push("--");
+	let mut files = parse_name_status(&run_git(&root, &args, ct)?, &root);
+	if head.is_none() {
+		let untracked = run_git(&root, &["ls-files", "-z", "--others", "--exclude-standard"], ct)?;
+		files.extend(split_nul(&untracked).map(|path| ChangedFile {
+			path:          root.join(path).to_string_lossy().into_owned(),
+			status:        "added".to_string(),
+			original_path: None,
+		}));
+	}
+	Ok(files)
+}
+
+fn check_revision(revision: &str) -> Result<()> {
+	if revision.is_empty() || revision.starts_with('-') {
+		return Err(Error::from_reason(format!("Invalid revision: {revision:?}")));
+	}
+	Ok(())
+}
+
+/// List files changed between `base` and `head` in the work tree containing
+/// `path` (default: the cwd).
+///
+/// Without `head`, compares `base` with the work tree, untracked files
+/// included.
+///
+/// # Errors
+/// Fails when a revision is invalid or unknown, or `path` is not inside a git
+/// work tree.
+#[napi(js_name = "changedFiles")]
+pub fn changed_files_between(
+	base: String,
+	head: Option<String>,
+	path: Option<String>,
+) -> Result<task::Async<Vec<ChangedFile>>> {
+	check_revision(&base)?;
+	if let Some(head) = &head {
+		check_revision(head)?;
+	}
+	let dir = fs_cache::resolve_search_path(path.as_deref().unwrap_or("."))?;
+	Ok(task::blocking("git.changed", (), move |ct| {
+		changed_between_sync(&dir, &base, head.as_deref(), &ct)
+	}))
+}
+
+/// Options for `readFileAtRev()`.
+#[napi(object)]
+#[derive(Default)]
+pub struct ReadAtRevOptions {
+	/// Bytes of content returned before the rest is cut off (default: 4 MiB,
+	/// as in `grep`; 0 disables the cap).
+	#[napi(js_name = "maxFileSize")]
+	pub max_file_size: Option<i64>,
+}
+
+/// A file's content at a git revision, from `readFileAtRev()`.
+#[napi(object)]
+pub struct FileAtRev {
+	/// Path relative to the work tree root.
+	pub path:      String,
+	/// Whether the path is a file at the revision.
+	pub exists:    bool,
+	/// Text content; absent when the file is missing or binary.
+	pub content:   Option<String>,
+	/// Size of the file at the revision in bytes.
+	pub size:      f64,
+	/// Whether the content has NUL bytes, which `grep` treats as binary.
+	pub binary:    bool,
+	/// Whether the file is larger than `maxFileSize`, so `content` holds only
+	/// its start.
+	pub truncated: bool,
+}
+
+/// Work tree root containing `path` and the path relative to it. `path`
+/// itself may be missing from the work tree.
+fn repo_relative(path: &Path, ct: &task::CancelToken) -> Result<(PathBuf, String)> {
+	let existing = path
+		.ancestors()
+		.skip(1)
+		.find(|dir| dir.is_dir())
+		.ok_or_else(|| Error::from_reason(format!("No directory for {}", path.display())))?;
+	let root = work_tree_root(existing, ct)?;
+	let dir = std::fs::canonicalize(existing).unwrap_or_else(|_| existing.to_path_buf());
+	let inside = path.strip_prefix(existing).unwrap_or(path);
+	let relative = dir
+		.join(inside)
+		.strip_prefix(&root)
+		.map(|relative| fs_cache::normalize_relative_path(Path::new(""), relative).into_owned())
+		.map_err(|_| {
+			Error::from_reason(format!("Not inside {}: {}", root.display(), path.display()))
+		})?;
+	Ok((root, relative))
+}
+
+fn read_at_rev_sync(
+	path: &Path,
+	rev: &str,
+	max_file_bytes: u64,
+	ct: &task::CancelToken,
+) -> Result<FileAtRev> {
+	let (root, relative) = repo_relative(path, ct)?;
+	let mut file = FileAtRev {
+		path:      relative,
+		exists:    false,
+		content:   None,
+		size:      0.0,
+		binary:    false,
+		truncated: false,
+	};
+	// "<mode> <type> <object> <size>\t<path>", or nothing when missing.
+	let listing = run_git(&root, &["ls-tree", "-l", "-z", rev, "--", &file.path], ct)?;
+	let listing = String::from_utf8_lossy(&listing);
+	let Some(entry) = split_nul(listing.as_bytes()).next().map(str::to_string) else {
+		return Ok(file);
+	};
+	let (info, _) = entry.split_once('\t').unwrap_or((&entry, ""));
+	let fields: Vec<&str> = info.split_whitespace().collect();
+	let [_, kind, object, size] = fields[..] else {
+		return Err(Error::from_reason(format!("Unexpected git ls-tree output: {entry}")));
+	};
+	if kind != "blob" {
+		return Err(Error::from_reason(format!("Not a file at {rev}: {}", file.path)));
+	}
+	let size: u64 = size.parse().unwrap_or(0);
+	let bytes = run_git_capped(&root, &["cat-file", "blob", object], max_file_bytes, ct)?;
+	file.exists = true;
+	file.size = size as f64;
+	file.truncated = size > max_file_bytes;
+	file.binary = bytes.contains(&0);
+	if !file.binary {
+		file.content = Some(String::from_utf8_lossy(&bytes).into_owned());
+	}
+	Ok(file)
+}
+
+/// Read a file as it was at git revision `rev`, without checking it out.
+///
+/// Resolves with `exists: false` when the path is not a file at `rev`.
+/// Content is cut off at `maxFileSize` and omitted for binary files, as in
+/// `grep`.
+///
+/// # Errors
+/// Fails when `rev` is invalid or unknown, the path is a directory at `rev`,
+/// or the path is not inside a git work tree.
+#[napi(js_name = "readFileAtRev")]
+pub fn read_file_at_rev(
+	path: String,
+	rev: String,
+	options: Option<ReadAtRevOptions>,
+) -> Result<task::Async<FileAtRev>> {
+	check_revision(&rev)?;
+	let path = fs_cache::resolve_path(&path)?;
+	let options = options.unwrap_or_default();
+	let max_file_bytes = grep::resolve_max_file_bytes(options.max_file_size);
+	Ok(task::blocking("git.read_at_rev", (), move |ct| {
+		read_at_rev_sync(&path, &rev, max_file_bytes, &ct)
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+
+	fn git(dir: &Path, args: &[&str]) {
+		let status = Command::new("git")
+			.arg("-C")
+			.arg(dir)
+			.args(["-c", "user.name=pi", "-c", "user.email=pi@example.com"])
+			.args(args)
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.status()
+			.expect("git should run");
+		assert!(status.success(), "git {args:?} failed");
+	}
+
+	#[test]
+	fn lists_changed_files_per_scope() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-git-changed-test-{unique}"));
+		fs::create_dir_all(&root).unwrap();
+		let root = fs::canonicalize(&root).unwrap();
+		let ct = task::CancelToken::default();
+		git(&root, &["init", "-q", "-b", "main"]);
+		fs::write(root.join("base.txt"), "base").unwrap();
+		fs::write(root.join("old.txt"), "old").unwrap();
+		git(&root, &["add", "."]);
+		git(&root, &["commit", "-q", "-m", "base"]);
+		git(&root, &["checkout", "-q", "-b", "feature"]);
+		fs::write(root.join("committed.txt"), "c").unwrap();
+		git(&root, &["add", "."]);
+		git(&root, &["commit", "-q", "-m", "feature"]);
+		fs::write(root.join("staged.txt"), "s").unwrap();
+		git(&root, &["add", "staged.txt"]);
+		git(&root, &["mv", "old.txt", "renamed.txt"]);
+		fs::write(root.join("base.txt"), "edited").unwrap();
+		fs::write(root.join("untracked.txt"), "u").unwrap();
+
+		let names = |scope: ChangedScope| {
+			let mut names: Vec<String> = changed_files(&root, &scope, &ct)
+				.unwrap()
+				.iter()
+				.map(|path| {
+					path
+						.strip_prefix(&root)
+						.unwrap()
+						.to_string_lossy()
+						.into_owned()
+				})
+				.collect();
+			names.sort();
+			names
+		};
+		assert_eq!(names(ChangedScope::Working), [
+			"base.txt",
+			"renamed.txt",
+			"staged.txt",
+			"untracked.txt"
+		]);
+		assert_eq!(names(ChangedScope::Staged), ["renamed.txt", "staged.txt"]);
+		assert_eq!(names(ChangedScope::parse("branch:main").unwrap()), [
+			"base.txt",
+			"committed.txt",
+			"renamed.txt",
+			"staged.txt",
+			"untracked.txt"
+		]);
+		assert!(ChangedScope::parse("branch:").is_err());
+
+		let status = git_status_sync(&root, &ct).unwrap();
+		assert_eq!((status.branch.as_deref(), status.upstream), (Some("feature"), None));
+		let mut entries: Vec<String> = status
+			.entries
+			.iter()
+			.map(|entry| format!("{}{} {}", entry.index, entry.work_tree, entry.path))
+			.collect();
+		entries.sort_by(|a, b| a[3..].cmp(&b[3..]));
+		assert_eq!(entries, [" M base.txt", "R  renamed.txt", "A  staged.txt", "?? untracked.txt"]);
+		assert_eq!(
+			parse_branch_header("main...origin/main [ahead 2, behind 1]"),
+			(Some("main".to_string()), Some("origin/main".to_string()), 2, 1)
+		);
+
+		let committed = changed_between_sync(&root, "main", Some("feature"), &ct).unwrap();
+		assert_eq!(committed.len(), 1);
+		assert!(committed[0].path.ends_with("committed.txt"));
+		assert_eq!(committed[0].status, "added");
+		let working = changed_between_sync(&root, "HEAD", None, &ct).unwrap();
+		let renamed = working.iter().find(|file| file.status == "renamed");
+		assert!(renamed.is_some_and(|file| {
+			file
+				.original_path
+				.as_deref()
+				.is_some_and(|path| path.ends_with("old.txt"))
+		}));
+		assert!(
+			working
+				.iter()
+				.any(|file| file.path.ends_with("untracked.txt"))
+		);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn blames_requested_lines() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-git-blame-test-{unique}"));
+		fs::create_dir_all(&root).unwrap();
+		let ct = task::CancelToken::default();
+		git(&root, &["init", "-q"]);
+		fs::write(root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+		git(&root, &["add", "."]);
+		git(&root, &["commit", "-q", "-m", "add lines"]);
+		fs::write(root.join("a.txt"), "one\nTWO\nthree\n").unwrap();
+
+		let blame = blame_lines(&root.join("a.txt"), &[3, 1, 2, 4, 9], &ct).unwrap();
+		assert_eq!(blame.len(), 3);
+		assert_eq!(blame[&1].author, "pi");
+		assert_eq!(blame[&1].author_email, "pi@example.com");
+		assert_eq!(blame[&1].summary, "add lines");
+		assert_eq!(blame[&1].commit.len(), 8);
+		assert!(blame[&1].time > 0.0 && !blame[&1].uncommitted);
+		assert!(blame[&2].uncommitted);
+		assert_eq!(blame[&3].commit, blame[&1].commit);
+		assert_eq!(line_ranges(&[1, 2, 3, 5, 7, 8]), [(1, 3), (5, 5), (7, 8)]);
+
+		fs::create_dir_all(root.join("sub")).unwrap();
+		fs::write(root.join("sub/bin"), b"\x00\x01").unwrap();
+		git(&root, &["add", "."]);
+		git(&root, &["commit", "-q", "-m", "more"]);
+		fs::remove_dir_all(root.join("sub")).unwrap();
+		let read =
+			|path: &str, rev: &str, max: u64| read_at_rev_sync(&root.join(path), rev, max, &ct);
+		let old = read("a.txt", "HEAD~1", u64::MAX).unwrap();
+		assert_eq!((old.content.as_deref(), old.size), (Some("one\ntwo\nthree\n"), 14.0));
+		let capped = read("a.txt", "HEAD", 4).unwrap();
+		assert_eq!((capped.content.as_deref(), capped.truncated), (Some("one\n"), true));
+		let binary = read("sub/bin", "HEAD", u64::MAX).unwrap();
+		assert_eq!((binary.path.as_str(), binary.binary, binary.content), ("sub/bin", true, None));
+		assert!(!read("sub/bin", "HEAD~1", u64::MAX).unwrap().exists);
+		assert!(read("sub", "HEAD", u64::MAX).is_err());
+		assert!(read("a.txt", "no-such-rev", u64::MAX).is_err());
+
+		let _ = fs::remove_dir_all(&root);
+	}
+}
@@ -2,7 +2,7 @@
 //! caching.
 //!
 //! # Overview
-//! Resolves a search root, obtains scanned entries via [`fs_cache`], applies
+//! Resolves the search roots, obtains scanned entries via [`fs_cache`], applies
 //! glob matching plus optional file-type filtering, and optionally streams each
 //! accepted match through a callback.
 //!
@@ -14,53 +14,112 @@
 //! // JS: await native.glob({ pattern: "*.rs", path: "." })
 //! ```
 
-use std::path::Path;
-
-use globset::GlobSet;
-use napi::{
-	bindgen_prelude::*,
-	threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode},
+use std::{
+	collections::HashSet,
+	path::{Path, PathBuf},
+	sync::Arc,
 };
+
+use globset::{GlobBuilder, GlobMatcher};
+use napi::{bindgen_prelude::*, threadsafe_function::ThreadsafeFunction};
 use napi_derive::napi;
 
 // Re-export entry types so existing `glob::FileType` / `glob::GlobMatch` paths still work.
 pub use crate::fs_cache::{FileType, GlobMatch};
-use crate::{fs_cache, glob_util, task};
+use crate::{
+	fs_cache, git, glob_util,
+	prof::{self, CallTrace, TraceSpan},
+	project, sort, stream, task,
+};
 
 /// Input options for `glob`, including traversal, filtering, and cancellation.
 #[napi(object)]
 pub struct GlobOptions<'env> {
-	/// Glob pattern to match (e.g., "*.ts").
-	pub pattern:              String,
-	/// Directory to search.
-	pub path:                 String,
+	/// Glob pattern to match (e.g., "*.ts"), or several; entries match any
+	/// pattern and none of the patterns starting with `!` (e.g.
+	/// `["src/**/*.ts", "!**/*.test.ts"]`).
+	#[napi(ts_type = "string | string[]")]
+	pub pattern:              Either<String, Vec<String>>,
+	/// Directory to search, or several searched as one; each match then
+	/// reports its `root`, and entries under more than one root are reported
+	/// once, under the first.
+	#[napi(ts_type = "string | string[]")]
+	pub path:                 Either<String, Vec<String>>,
+	/// Workspace package (name or relative path) to search instead of all of
+	/// `path`, resolved from the workspace manifests at `path` (single `path`
+	/// only).
+	pub package:              Option<String>,
+	/// Match only files changed in git: "working" (uncommitted, including
+	/// untracked), "staged", or "branch:<ref>" (changed since forking from
+	/// `<ref>`). Directories never match.
+	#[napi(js_name = "changedOnly", ts_type = "\"working\" | \"staged\" | `branch:${string}`")]
+	pub changed_only:         Option<String>,
 	/// Filter by file type: "file", "dir", or "symlink". Symlinks are
 	/// matched for file/dir filters based on their target type.
 	#[napi(js_name = "fileType")]
 	pub file_type:            Option<FileType>,
 	/// Match simple patterns recursively by default (`*.ts` -> recursive).
 	pub recursive:            Option<bool>,
+	/// Match patterns case-sensitively; by default, matching ignores case
+	/// unless a pattern contains an uppercase letter.
+	#[napi(js_name = "caseSensitive")]
+	pub case_sensitive:       Option<bool>,
 	/// Include hidden files (default: false).
 	pub hidden:               Option<bool>,
 	/// Maximum number of results to return.
 	#[napi(js_name = "maxResults")]
 	pub max_results:          Option<u32>,
+	/// `cursor` of a previous result with the same options, to continue
+	/// after its last match without filtering the entries before it again.
+	/// Not supported with `sortByMtime` or `naturalSort`.
+	pub cursor:               Option<String>,
 	/// Respect .gitignore files (default: true).
 	pub gitignore:            Option<bool>,
+	/// Standard ignore sources to apply ("gitignore", "ignore", "rgignore");
+	/// overrides `gitignore` when set (default: all three).
+	#[napi(js_name = "ignoreSources")]
+	pub ignore_sources:       Option<Vec<String>>,
+	/// Additional ignore file names to honor in each directory.
+	#[napi(js_name = "ignoreFiles")]
+	pub ignore_files:         Option<Vec<String>>,
+	/// Deepest level to scan; direct children of the search root are at
+	/// depth 1.
+	#[napi(js_name = "maxDepth")]
+	pub max_depth:            Option<u32>,
+	/// Directories whose subtrees are never scanned: a bare name (`"build"`)
+	/// matches at any depth, a name with `/` matches that path relative to the
+	/// search root.
+	#[napi(js_name = "excludeDirs")]
+	pub exclude_dirs:         Option<Vec<String>>,
 	/// Enable shared filesystem scan cache (default: false).
 	pub cache:                Option<bool>,
 	/// Sort results by mtime (most recent first) before applying limit.
 	#[napi(js_name = "sortByMtime")]
 	pub sort_by_mtime:        Option<bool>,
+	/// Order results naturally by path (`file2` before `file10`, accents and
+	/// case folded) before applying limit; with `sortByMtime`, this breaks
+	/// mtime ties.
+	#[napi(js_name = "naturalSort")]
+	pub natural_sort:         Option<bool>,
 	/// Include `node_modules` entries when the pattern does not explicitly
 	/// mention them.
 	#[napi(js_name = "includeNodeModules")]
 	pub include_node_modules: Option<bool>,
+	/// Report each match's `mode`, `readonly`, and `symlinkTarget`, at the
+	/// cost of one extra stat per match (default: false).
+	#[napi(js_name = "withMetadata")]
+	pub with_metadata:        Option<bool>,
+	/// Attach per-phase timings to the result as `trace`.
+	pub trace:                Option<bool>,
+	/// Batching of `onMatch` results.
+	pub stream:               Option<stream::StreamOptions>,
 	/// Abort signal for cancelling the operation.
 	pub signal:               Option<Unknown<'env>>,
 	/// Timeout in milliseconds for the operation.
 	#[napi(js_name = "timeoutMs")]
 	pub timeout_ms:           Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:                Option<ClassInstance<'env, task::CancellationScope>>,
 }
 
 /// Result payload returned by a glob operation.
@@ -70,20 +129,58 @@ pub struct GlobResult {
 	pub matches:       Vec<GlobMatch>,
 	/// Number of returned matches (`matches.len()`), clamped to `u32::MAX`.
 	pub total_matches: u32,
+	/// Opaque position after the last match, set when `maxResults` stopped
+	/// the scan before the end; pass it back as `cursor` for the next page.
+	pub cursor:        Option<String>,
+	/// Per-phase timings, when requested with `trace`.
+	pub trace:         Option<TraceSpan>,
+}
+
+/// A batch of streamed `onMatch` results.
+#[napi(object)]
+pub struct GlobMatchBatch {
+	/// Batch number, counting from 0; a gap means a batch was dropped.
+	pub seq:     u32,
+	pub matches: Vec<GlobMatch>,
+	/// Whether this is the last batch of the search.
+	pub done:    bool,
+	/// Matches dropped so far because the JS queue refused their batch.
+	pub dropped: u32,
+}
+
+impl stream::StreamBatch for GlobMatchBatch {
+	type Item = GlobMatch;
+
+	fn new(seq: u32, matches: Vec<GlobMatch>, done: bool, dropped: u32) -> Self {
+		Self { seq, matches, done, dropped }
+	}
+}
+
+/// A directory searched by a glob execution.
+struct GlobRoot {
+	path:    PathBuf,
+	/// Reported as each match's `root` when searching several roots.
+	label:   Option<Arc<str>>,
+	/// Paths relative to `path` of the files git reports as changed.
+	changed: Option<HashSet<String>>,
 }
 
 /// Internal runtime config for a single glob execution.
 struct GlobConfig {
-	root:                  std::path::PathBuf,
-	pattern:               String,
+	roots:                 Vec<GlobRoot>,
+	patterns:              Vec<String>,
 	recursive:             bool,
-	include_hidden:        bool,
+	case_sensitive:        Option<bool>,
+	scan_policy:           fs_cache::ScanPolicy,
 	file_type_filter:      Option<FileType>,
 	max_results:           usize,
-	use_gitignore:         bool,
+	cursor:                Option<String>,
 	mentions_node_modules: bool,
 	sort_by_mtime:         bool,
+	natural_sort:          bool,
 	use_cache:             bool,
+	with_metadata:         bool,
+	trace:                 bool,
 }
 
 fn resolve_symlink_target_type(root: &Path, relative_path: &str) -> Option<FileType> {
@@ -98,7 +195,7 @@ fn resolve_symlink_target_type(root: &Path, relative_path: &str) -> Option<FileT
 	}
 }
 
-fn apply_file_type_filter(entry: &GlobMatch, config: &GlobConfig) -> Option<FileType> {
+fn apply_file_type_filter(entry: &GlobMatch, root: &Path, config: &GlobConfig) -> Option<FileType> {
 	let Some(filter) = config.file_type_filter else {
 		return Some(entry.file_type);
 	};
@@ -110,7 +207,7 @@ fn apply_file_type_filter(entry: &GlobMatch, config: &GlobConfig) -> Option<File
 	}
 	match filter {
 		FileType::File | FileType::Dir => {
-			let resolved = resolve_symlink_target_type(&config.root, &entry.path)?;
+			let resolved = resolve_symlink_target_type(root, &entry.path)?;
 			if resolved == filter {
 				Some(resolved)
 			} else {
@@ -121,99 +218,319 @@ fn apply_file_type_filter(entry: &GlobMatch, config: &GlobConfig) -> Option<File
 	}
 }
 
-/// Filter and collect matching entries from a pre-scanned list.
+/// Fill in the `withMetadata` fields of `entry` from its symlink metadata.
+fn attach_metadata(entry: &mut GlobMatch, root: &Path) {
+	let path = root.join(&entry.path);
+	let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+		return;
+	};
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		entry.mode = Some(metadata.permissions().mode());
+	}
+	entry.readonly = Some(metadata.permissions().readonly());
+	if metadata.file_type().is_symlink() {
+		entry.symlink_target = std::fs::read_link(&path)
+			.ok()
+			.map(|target| target.to_string_lossy().into_owned());
+	}
+}
+
+/// Matches collected across the roots of a glob execution.
+struct Collected {
+	matches:    Vec<GlobMatch>,
+	/// Absolute paths matched so far, tracked only with several roots.
+	seen:       Option<HashSet<PathBuf>>,
+	/// Index of the first entry left unexamined when `maxResults` was
+	/// reached, in the entries last filtered.
+	stopped_at: Option<usize>,
+}
+
+/// Where a paged glob stopped, handed out as an opaque `cursor`.
+struct GlobCursor {
+	/// Index of the root to resume in.
+	root:  usize,
+	/// Index of the next entry to examine in the root's scan.
+	entry: usize,
+	/// Path of that entry, to find it again if entries came or went; empty
+	/// to start at the beginning of the root.
+	path:  String,
+}
+
+impl GlobCursor {
+	fn parse(cursor: &str) -> Result<Self> {
+		let mut parts = cursor.splitn(3, ':');
+		let (Some(root), Some(entry), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+			return Err(Error::from_reason(format!("Invalid cursor: {cursor}")));
+		};
+		match (root.parse(), entry.parse()) {
+			(Ok(root), Ok(entry)) => Ok(Self { root, entry, path: path.to_string() }),
+			_ => Err(Error::from_reason(format!("Invalid cursor: {cursor}"))),
+		}
+	}
+
+	fn encode(&self) -> String {
+		format!("{}:{}:{}", self.root, self.entry, self.path)
+	}
+
+	/// Index of the cursor's entry in `entries`, looked up by path when the
+	/// entry moved.
+	fn position(&self, entries: &[GlobMatch]) -> Result<usize> {
+		if self.path.is_empty() {
+			return Ok(0);
+		}
+		if entries
+			.get(self.entry)
+			.is_some_and(|entry| entry.path == self.path)
+		{
+			return Ok(self.entry);
+		}
+		entries
+			.iter()
+			.position(|entry| entry.path == self.path)
+			.ok_or_else(|| {
+				Error::from_reason(format!(
+					"Stale cursor: {} is no longer scanned; restart without `cursor`",
+					self.path
+				))
+			})
+	}
+}
+
+/// Filter matching entries of `root` from a pre-scanned list into `collected`,
+/// returning how many were added.
+///
+/// Stops at `maxResults` unless sorting; with several roots, skips entries
+/// already matched under an earlier root.
 fn filter_entries(
 	entries: &[GlobMatch],
-	glob_set: &GlobSet,
+	glob_filter: &glob_util::GlobFilter,
+	root: &GlobRoot,
 	config: &GlobConfig,
-	on_match: Option<&ThreadsafeFunction<GlobMatch>>,
+	collected: &mut Collected,
+	on_match: Option<&stream::Batcher<GlobMatchBatch>>,
 	ct: &task::CancelToken,
-) -> Result<Vec<GlobMatch>> {
-	let mut matches = Vec::new();
-	if config.max_results == 0 {
-		return Ok(matches);
+) -> Result<usize> {
+	let sorting = config.sort_by_mtime || config.natural_sort;
+	let start = collected.matches.len();
+	if !sorting && start >= config.max_results {
+		return Ok(0);
 	}
 
-	for entry in entries {
+	for (idx, entry) in entries.iter().enumerate() {
 		ct.heartbeat()?;
 		if fs_cache::should_skip_path(Path::new(&entry.path), config.mentions_node_modules) {
 			// Apply post-scan node_modules policy before glob matching.
 			continue;
 		}
-		if !glob_set.is_match(&entry.path) {
+		if !glob_filter.is_match(&entry.path) {
+			continue;
+		}
+		if root
+			.changed
+			.as_ref()
+			.is_some_and(|changed| !changed.contains(&entry.path))
+		{
 			continue;
 		}
-		let Some(effective_file_type) = apply_file_type_filter(entry, config) else {
+		let Some(effective_file_type) = apply_file_type_filter(entry, &root.path, config) else {
 			continue;
 		};
+		if let Some(seen) = collected.seen.as_mut()
+			&& !seen.insert(root.path.join(&entry.path))
+		{
+			continue;
+		}
 		let mut matched_entry = entry.clone();
 		matched_entry.file_type = effective_file_type;
+		matched_entry.root = root.label.as_deref().map(str::to_string);
+		if config.with_metadata {
+			attach_metadata(&mut matched_entry, &root.path);
+		}
 		if let Some(callback) = on_match {
-			callback.call(Ok(matched_entry.clone()), ThreadsafeFunctionCallMode::NonBlocking);
+			callback.push(matched_entry.clone());
 		}
 
-		matches.push(matched_entry);
-		// Only early-break when not sorting; mtime sort requires full candidate set.
-		if !config.sort_by_mtime && matches.len() >= config.max_results {
+		collected.matches.push(matched_entry);
+		// Only early-break when not sorting; sorting requires the full candidate
+		// set.
+		if !sorting && collected.matches.len() >= config.max_results {
+			collected.stopped_at = Some(idx + 1);
 			break;
 		}
 	}
-	Ok(matches)
+	Ok(collected.matches.len() - start)
 }
 
 /// Executes matching/filtering over scanned entries and optionally streams each
 /// hit.
 fn run_glob(
 	config: GlobConfig,
-	on_match: Option<&ThreadsafeFunction<GlobMatch>>,
+	on_match: Option<&stream::Batcher<GlobMatchBatch>>,
 	ct: task::CancelToken,
 ) -> Result<GlobResult> {
-	let glob_set = glob_util::compile_glob(&config.pattern, config.recursive)?;
+	let trace = CallTrace::start("glob", config.trace);
+	let glob_filter =
+		glob_util::compile_glob_filter(&config.patterns, config.recursive, config.case_sensitive)?;
+	let sorting = config.sort_by_mtime || config.natural_sort;
+	let resume_from = config
+		.cursor
+		.as_deref()
+		.map(GlobCursor::parse)
+		.transpose()?;
+	if resume_from.is_some() && sorting {
+		return Err(Error::from_reason(
+			"`cursor` is not supported with `sortByMtime` or `naturalSort`".to_string(),
+		));
+	}
 	if config.max_results == 0 {
-		return Ok(GlobResult { matches: Vec::new(), total_matches: 0 });
+		return Ok(GlobResult {
+			matches:       Vec::new(),
+			total_matches: 0,
+			cursor:        None,
+			trace:         trace.map(CallTrace::finish),
+		});
 	}
 
-	let mut matches = if config.use_cache {
-		let scan =
-			fs_cache::get_or_scan(&config.root, config.include_hidden, config.use_gitignore, &ct)?;
-		let mut matches = filter_entries(&scan.entries, &glob_set, &config, on_match, &ct)?;
-		// Empty-result recheck: if we got zero matches from a cached scan that's old
-		// enough, force a rescan and try once more before returning empty.
-		if matches.is_empty() && scan.cache_age_ms >= fs_cache::empty_recheck_ms() {
-			let fresh = fs_cache::force_rescan(
-				&config.root,
-				config.include_hidden,
-				config.use_gitignore,
-				true,
+	let mut collected = Collected {
+		matches:    Vec::new(),
+		seen:       (config.roots.len() > 1).then(HashSet::new),
+		stopped_at: None,
+	};
+	let mut cursor = None;
+	// Earlier pages covered the roots before the cursor's.
+	let first_root = resume_from.as_ref().map_or(0, |resume| resume.root);
+	for (root_idx, root) in config.roots.iter().enumerate().skip(first_root) {
+		let scan = |rescan: bool| {
+			let _span = prof::trace_span(trace.as_ref(), "scan");
+			fs_cache::force_rescan(&root.path, &config.scan_policy, rescan, &ct)
+		};
+		let mut filter = |entries: &[GlobMatch]| {
+			let _span = prof::trace_span(trace.as_ref(), "filter");
+			let start = match &resume_from {
+				Some(resume) if resume.root == root_idx => resume.position(entries)?,
+				_ => 0,
+			};
+			let added = filter_entries(
+				&entries[start..],
+				&glob_filter,
+				root,
+				&config,
+				&mut collected,
+				on_match,
 				&ct,
 			)?;
-			matches = filter_entries(&fresh, &glob_set, &config, on_match, &ct)?;
+			if let Some(stopped_at) = collected.stopped_at.take() {
+				let next = start + stopped_at;
+				cursor = match entries.get(next) {
+					Some(entry) => {
+						Some(GlobCursor { root: root_idx, entry: next, path: entry.path.clone() })
+					},
+					None => (root_idx + 1 < config.roots.len()).then(|| GlobCursor {
+						root:  root_idx + 1,
+						entry: 0,
+						path:  String::new(),
+					}),
+				};
+			}
+			Ok::<_, Error>(added)
+		};
+		if config.use_cache {
+			let scan_span = prof::trace_span(trace.as_ref(), "scan");
+			let cached = fs_cache::get_or_scan(&root.path, &config.scan_policy, &ct)?;
+			drop(scan_span);
+			// Empty-result recheck: if we got zero matches from a cached scan that's old
+			// enough, force a rescan and try once more before returning empty.
+			if filter(&cached.entries)? == 0 && cached.cache_age_ms >= fs_cache::empty_recheck_ms() {
+				filter(&scan(true)?)?;
+			}
+		} else {
+			filter(&scan(false)?)?;
 		}
-		matches
-	} else {
-		let fresh = fs_cache::force_rescan(
-			&config.root,
-			config.include_hidden,
-			config.use_gitignore,
-			false,
-			&ct,
-		)?;
-		filter_entries(&fresh, &glob_set, &config, on_match, &ct)?
-	};
+		if !sorting && collected.matches.len() >= config.max_results {
+			break;
+		}
+	}
+	let mut matches = collected.matches;
 
-	if config.sort_by_mtime {
-		// Sorting mode: rank by mtime descending, then apply max-results truncation.
+	if sorting {
+		let _span = prof::trace_span(trace.as_ref(), "sort");
+		// Sorting mode: rank by mtime descending and/or natural path order, then
+		// apply max-results truncation.
 		matches.sort_by(|a, b| {
-			let a_mtime = a.mtime.unwrap_or(0.0);
-			let b_mtime = b.mtime.unwrap_or(0.0);
-			b_mtime
-				.partial_cmp(&a_mtime)
-				.unwrap_or(std::cmp::Ordering::Equal)
+			let by_mtime = if config.sort_by_mtime {
+				let a_mtime = a.mtime.unwrap_or(0.0);
+				let b_mtime = b.mtime.unwrap_or(0.0);
+				b_mtime
+					.partial_cmp(&a_mtime)
+					.unwrap_or(std::cmp::Ordering::Equal)
+			} else {
+				std::cmp::Ordering::Equal
+			};
+			by_mtime.then_with(|| {
+				if config.natural_sort {
+					sort::natural_compare(&a.path, &b.path)
+				} else {
+					std::cmp::Ordering::Equal
+				}
+			})
 		});
 		matches.truncate(config.max_results);
 	}
 	let total_matches = matches.len().min(u32::MAX as usize) as u32;
-	Ok(GlobResult { matches, total_matches })
+	Ok(GlobResult {
+		matches,
+		total_matches,
+		cursor: cursor.map(|cursor| cursor.encode()),
+		trace: trace.map(CallTrace::finish),
+	})
+}
+
+/// Resolve the directories to search, with the changed files of each when
+/// `changed_only` is set.
+fn resolve_roots(
+	path: Either<String, Vec<String>>,
+	package: Option<&str>,
+	changed_only: Option<&str>,
+	ct: &task::CancelToken,
+) -> Result<Vec<GlobRoot>> {
+	let paths = fs_cache::search_paths(path)?;
+	let roots: Vec<(PathBuf, Option<Arc<str>>)> = if let [path] = paths.as_slice() {
+		let mut root = fs_cache::resolve_search_path(path)?;
+		if let Some(package) = package {
+			root = project::resolve_package_dir(&root, package, ct)?;
+		}
+		vec![(root, None)]
+	} else {
+		if package.is_some() {
+			return Err(Error::from_reason("`package` needs a single search path".to_string()));
+		}
+		fs_cache::resolve_search_roots(paths, fs_cache::resolve_search_path)?
+			.into_iter()
+			.map(|root| (root.path, Some(root.label)))
+			.collect()
+	};
+	let scope = changed_only.map(git::ChangedScope::parse).transpose()?;
+	roots
+		.into_iter()
+		.map(|(path, label)| {
+			let changed = match &scope {
+				Some(scope) => {
+					let changed = git::changed_files(&path, scope, ct)?;
+					Some(
+						changed
+							.iter()
+							.filter(|changed| changed.starts_with(&path))
+							.map(|changed| fs_cache::normalize_relative_path(&path, changed).into_owned())
+							.collect(),
+					)
+				},
+				None => None,
+			};
+			Ok(GlobRoot { path, label, changed })
+		})
+		.collect()
 }
 
 /// Find filesystem entries matching a glob pattern.
@@ -231,48 +548,416 @@ fn run_glob(
 #[napi(js_name = "glob")]
 pub fn glob(
 	options: GlobOptions<'_>,
-	#[napi(ts_arg_type = "((match: GlobMatch) => void) | undefined | null")] on_match: Option<
-		ThreadsafeFunction<GlobMatch>,
-	>,
+	#[napi(
+		ts_arg_type = "((error: Error | null, batch: GlobMatchBatch) => void) | undefined | null"
+	)]
+	on_match: Option<ThreadsafeFunction<GlobMatchBatch>>,
 ) -> task::Async<GlobResult> {
 	let GlobOptions {
 		pattern,
 		path,
+		package,
+		changed_only,
 		file_type,
 		recursive,
+		case_sensitive,
 		hidden,
 		max_results,
+		cursor,
 		gitignore,
+		ignore_sources,
+		ignore_files,
+		max_depth,
+		exclude_dirs,
 		sort_by_mtime,
+		natural_sort,
 		cache,
 		include_node_modules,
+		with_metadata,
+		trace,
+		stream,
 		timeout_ms,
 		signal,
+		scope,
 	} = options;
 
-	let pattern = pattern.trim();
-	let pattern = if pattern.is_empty() { "*" } else { pattern };
-	let pattern = pattern.to_string();
+	let patterns = match pattern {
+		Either::A(pattern) => vec![pattern],
+		Either::B(patterns) => patterns,
+	};
 
-	let ct = task::CancelToken::new(timeout_ms, signal);
+	let ct = task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref());
+	let batcher = on_match.map(|callback| stream::Batcher::new(callback, stream));
 
 	task::blocking("glob", ct, move |ct| {
-		run_glob(
+		let on_match = batcher.as_ref();
+		let result = (move || {
+			let roots = resolve_roots(path, package.as_deref(), changed_only.as_deref(), &ct)?;
+			run_glob(
+				GlobConfig {
+					roots,
+					scan_policy: fs_cache::ScanPolicy::new(
+						hidden.unwrap_or(false),
+						gitignore.unwrap_or(true),
+					)
+					.with_ignore_sources(ignore_sources)?
+					.with_ignore_files(ignore_files)
+					.with_pruning(max_depth, exclude_dirs),
+					file_type_filter: file_type,
+					recursive: recursive.unwrap_or(true),
+					case_sensitive,
+					max_results: max_results.map_or(usize::MAX, |value| value as usize),
+					cursor,
+					mentions_node_modules: include_node_modules
+						.unwrap_or_else(|| patterns.iter().any(|p| p.contains("node_modules"))),
+					sort_by_mtime: sort_by_mtime.unwrap_or(false),
+					natural_sort: natural_sort.unwrap_or(false),
+					use_cache: cache.unwrap_or(false),
+					with_metadata: with_metadata.unwrap_or(false),
+					trace: trace.unwrap_or(false),
+					patterns,
+				},
+				on_match,
+				ct,
+			)
+		})();
+		if let Some(batcher) = &batcher {
+			batcher.finish();
+		}
+		result
+	})
+}
+
+/// Options for `explainGlob()`.
+#[napi(object)]
+pub struct ExplainGlobOptions {
+	/// Match simple patterns recursively, as `glob()` does (default: true).
+	pub recursive:      Option<bool>,
+	/// Match case-sensitively, as `glob()` does (default: only when the
+	/// pattern contains an uppercase letter).
+	#[napi(js_name = "caseSensitive")]
+	pub case_sensitive: Option<bool>,
+}
+
+/// Whether a path matches a glob pattern, and why not.
+#[napi(object)]
+pub struct GlobExplanation {
+	/// Whether `glob()` would accept the path.
+	pub matched:          bool,
+	/// Pattern after normalization, exactly as `glob()` compiles it.
+	pub pattern:          String,
+	/// Whether `**/` was prepended because the pattern has no `/`.
+	#[napi(js_name = "autoPrefixed")]
+	pub auto_prefixed:    bool,
+	/// Number of `}` appended to close unclosed `{` groups.
+	#[napi(js_name = "closedBraces")]
+	pub closed_braces:    u32,
+	/// Index of the first `/`-separated pattern component that could not be
+	/// matched; absent when the pattern matched or the path is too long.
+	#[napi(js_name = "failedComponent")]
+	pub failed_component: Option<u32>,
+	/// Text of the failed pattern component.
+	#[napi(js_name = "failedPattern")]
+	pub failed_pattern:   Option<String>,
+	/// Path component the failed pattern component was compared with, or the
+	/// unmatched remainder of the path when the pattern ended first.
+	#[napi(js_name = "failedPath")]
+	pub failed_path:      Option<String>,
+	/// Human-readable explanation.
+	pub reason:           String,
+}
+
+/// Backtracking component-wise match, tracking the furthest point reached.
+struct ComponentWalk<'a> {
+	/// Compiled components; `None` for `**` (any number of path components).
+	matchers: Vec<Option<GlobMatcher>>,
+	path:     Vec<&'a str>,
+	seen:     HashSet<(usize, usize)>,
+	/// Furthest (pattern, path) component indices reached.
+	furthest: (usize, usize),
+}
+
+impl ComponentWalk<'_> {
+	fn walk(&mut self, pi: usize, si: usize) -> bool {
+		if !self.seen.insert((pi, si)) {
+			return false;
+		}
+		self.furthest = self.furthest.max((pi, si));
+		let Some(component) = self.matchers.get(pi) else {
+			return si == self.path.len();
+		};
+		match component {
+			None => (si..=self.path.len()).any(|next| self.walk(pi + 1, next)),
+			Some(matcher) => {
+				si < self.path.len() && matcher.is_match(self.path[si]) && self.walk(pi + 1, si + 1)
+			},
+		}
+	}
+}
+
+fn explain_glob_match(
+	pattern: &str,
+	path: &str,
+	recursive: bool,
+	case_sensitive: Option<bool>,
+) -> Result<GlobExplanation> {
+	let rewrite = glob_util::rewrite_glob_pattern(pattern, recursive);
+	let glob_set = glob_util::compile_glob(pattern, recursive, case_sensitive)?;
+	let ignore_case = glob_util::ignores_case([pattern], case_sensitive);
+	let path = path.replace('\\', "/");
+	let path = path.trim_start_matches("./").trim_end_matches('/');
+	let matched = glob_set.is_match(path);
+
+	let components = glob_util::split_glob_components(&rewrite.pattern);
+	let matchers = components
+		.iter()
+		.map(|component| {
+			if *component == "**" {
+				return Ok(None);
+			}
+			GlobBuilder::new(component)
+				.literal_separator(true)
+				.case_insensitive(ignore_case)
+				.build()
+				.map(|glob| Some(glob.compile_matcher()))
+		})
+		.collect::<std::result::Result<Vec<_>, _>>()
+		.map_err(|err| Error::from_reason(format!("Invalid glob pattern: {err}")))?;
+	let path_components: Vec<&str> = if path.is_empty() {
+		Vec::new()
+	} else {
+		path.split('/').collect()
+	};
+	let mut walk =
+		ComponentWalk { matchers, path: path_components, seen: HashSet::new(), furthest: (0, 0) };
+	let components_matched = walk.walk(0, 0);
+	let (pi, si) = walk.furthest;
+
+	let mut explanation = GlobExplanation {
+		matched,
+		auto_prefixed: rewrite.auto_prefixed,
+		closed_braces: rewrite.closed_braces as u32,
+		failed_component: None,
+		failed_pattern: None,
+		failed_path: None,
+		reason: String::new(),
+		pattern: String::new(),
+	};
+	explanation.reason = if matched {
+		"Path matches the pattern".to_string()
+	} else if components_matched {
+		// Alternations spanning `/` can defeat the component-wise walk.
+		"Pattern does not match the path as a whole".to_string()
+	} else if pi == components.len() {
+		let rest = walk.path[si..].join("/");
+		let reason = format!("Pattern ends before the rest of the path: `{rest}`");
+		explanation.failed_path = Some(rest);
+		reason
+	} else {
+		explanation.failed_component = Some(pi as u32);
+		explanation.failed_pattern = Some(components[pi].to_string());
+		if let Some(actual) = walk.path.get(si) {
+			explanation.failed_path = Some((*actual).to_string());
+			format!("Pattern component `{}` does not match `{actual}`", components[pi])
+		} else {
+			format!("Path ends before pattern component `{}`", components[pi])
+		}
+	};
+	if !matched && !recursive && !pattern.contains(['/', '\\']) {
+		explanation.reason.push_str(
+			"; with `recursive: false` a pattern without `/` only matches top-level entries",
+		);
+	}
+	explanation.pattern = rewrite.pattern;
+	Ok(explanation)
+}
+
+/// Explain whether `path` (relative to the search root) matches a glob.
+///
+/// The pattern is normalized exactly as `glob()` does, including the `**/`
+/// prefix and brace closing; on a miss, the first pattern component that
+/// could not be matched is reported.
+///
+/// # Errors
+/// Returns an error when the glob pattern is invalid.
+#[napi(js_name = "explainGlob")]
+pub fn explain_glob(
+	pattern: String,
+	path: String,
+	options: Option<ExplainGlobOptions>,
+) -> Result<GlobExplanation> {
+	let (recursive, case_sensitive) =
+		options.map_or((None, None), |options| (options.recursive, options.case_sensitive));
+	explain_glob_match(&pattern, &path, recursive.unwrap_or(true), case_sensitive)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	use napi::Either;
+
+	use super::{
+		GlobConfig, attach_metadata, explain_glob_match, fs_cache, resolve_roots, run_glob, task,
+	};
+
+	#[test]
+	fn explains_failed_components() {
+		let hit = explain_glob_match("*.{ts,tsx", "src/app.tsx", true, None).unwrap();
+		assert!(hit.matched && hit.auto_prefixed);
+		assert_eq!((hit.pattern.as_str(), hit.closed_braces), ("**/*.{ts,tsx}", 1));
+
+		let miss = explain_glob_match("src/*/index.ts", "src/a/b/index.ts", true, None).unwrap();
+		assert!(!miss.matched);
+		assert_eq!(miss.failed_component, Some(2));
+		assert_eq!(miss.failed_path.as_deref(), Some("b"));
+
+		let short = explain_glob_match("src/**/*.rs", "src", true, None).unwrap();
+		assert_eq!(short.failed_pattern.as_deref(), Some("*.rs"));
+		assert_eq!(short.failed_path, None);
+
+		let long = explain_glob_match("src", "src/lib.rs", false, None).unwrap();
+		assert_eq!((long.failed_component, long.failed_path.as_deref()), (None, Some("lib.rs")));
+
+		let top_level = explain_glob_match("*.rs", "src/lib.rs", false, None).unwrap();
+		assert_eq!(top_level.failed_path.as_deref(), Some("src"));
+		assert!(top_level.reason.contains("recursive: false"));
+	}
+
+	#[test]
+	fn globs_several_roots_once() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-glob-roots-test-{unique}"));
+		for path in ["ws/a.rs", "ws/vendor/b.rs", "other/c.rs"] {
+			let path = root.join(path);
+			std::fs::create_dir_all(path.parent().unwrap()).expect("temp dir should be created");
+			std::fs::write(path, "").expect("fixture should be written");
+		}
+		let label = |path: &str| root.join(path).to_string_lossy().into_owned();
+		let ct = task::CancelToken::default();
+		let paths = vec![label("ws/vendor"), label("ws"), label("other"), label("ws/vendor")];
+		let roots = resolve_roots(Either::B(paths), None, None, &ct).expect("roots should resolve");
+		assert_eq!(roots.len(), 3);
+
+		let result = run_glob(
 			GlobConfig {
-				root: fs_cache::resolve_search_path(&path)?,
-				include_hidden: hidden.unwrap_or(false),
-				file_type_filter: file_type,
-				recursive: recursive.unwrap_or(true),
-				max_results: max_results.map_or(usize::MAX, |value| value as usize),
-				use_gitignore: gitignore.unwrap_or(true),
-				mentions_node_modules: include_node_modules
-					.unwrap_or_else(|| pattern.contains("node_modules")),
-				sort_by_mtime: sort_by_mtime.unwrap_or(false),
-				use_cache: cache.unwrap_or(false),
-				pattern,
+				roots,
+				patterns: vec!["*.rs".to_string()],
+				recursive: true,
+				case_sensitive: None,
+				scan_policy: fs_cache::ScanPolicy::new(false, true),
+				file_type_filter: None,
+				max_results: usize::MAX,
+				cursor: None,
+				mentions_node_modules: false,
+				sort_by_mtime: false,
+				natural_sort: false,
+				use_cache: false,
+				with_metadata: false,
+				trace: false,
 			},
-			on_match.as_ref(),
+			None,
 			ct,
 		)
-	})
+		.expect("glob should succeed");
+		let mut found: Vec<_> = result
+			.matches
+			.iter()
+			.map(|entry| (entry.root.clone().unwrap_or_default(), entry.path.as_str()))
+			.collect();
+		found.sort_unstable();
+		assert_eq!(found, [
+			(label("other"), "c.rs"),
+			(label("ws"), "a.rs"),
+			(label("ws/vendor"), "b.rs"),
+		]);
+
+		let _ = std::fs::remove_dir_all(&root);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn attaches_metadata_for_symlinks() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap()
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-glob-metadata-test-{unique}"));
+		std::fs::create_dir_all(&root).expect("temp dir should be created");
+		std::fs::write(root.join("a.txt"), "abc").expect("fixture should be written");
+		std::os::unix::fs::symlink("a.txt", root.join("link")).expect("symlink should be created");
+
+		let entries = fs_cache::force_rescan(
+			&root,
+			&fs_cache::ScanPolicy::new(false, false),
+			false,
+			&task::CancelToken::default(),
+		)
+		.expect("scan should succeed");
+		let mut link = entries
+			.iter()
+			.find(|entry| entry.path == "link")
+			.cloned()
+			.expect("link should be scanned");
+		assert_eq!(link.symlink_target, None);
+		attach_metadata(&mut link, &root);
+		assert_eq!(link.symlink_target.as_deref(), Some("a.txt"));
+		assert_eq!(link.readonly, Some(false));
+		assert!(link.mode.is_some_and(|mode| mode & 0o170_000 == 0o120_000));
+
+		let _ = std::fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn pages_with_cursor() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap()
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-glob-cursor-test-{unique}"));
+		for path in ["a/1.rs", "a/2.rs", "b/3.rs", "b/4.txt", "c/5.rs"] {
+			let path = root.join(path);
+			std::fs::create_dir_all(path.parent().unwrap()).expect("temp dir should be created");
+			std::fs::write(path, "").expect("fixture should be written");
+		}
+		let label = |path: &str| root.join(path).to_string_lossy().into_owned();
+		let run = |cursor: Option<String>| {
+			let ct = task::CancelToken::default();
+			let paths = Either::B(vec![label("a"), label("b"), label("c")]);
+			run_glob(
+				GlobConfig {
+					roots: resolve_roots(paths, None, None, &ct).expect("roots should resolve"),
+					patterns: vec!["*.rs".to_string()],
+					recursive: true,
+					case_sensitive: None,
+					scan_policy: fs_cache::ScanPolicy::new(false, true),
+					file_type_filter: None,
+					max_results: 2,
+					cursor,
+					mentions_node_modules: false,
+					sort_by_mtime: false,
+					natural_sort: false,
+					use_cache: false,
+					with_metadata: false,
+					trace: false,
+				},
+				None,
+				ct,
+			)
+			.expect("glob should succeed")
+		};
+
+		let first = run(None);
+		let paths: Vec<_> = first.matches.iter().map(|m| m.path.as_str()).collect();
+		assert_eq!(paths, ["1.rs", "2.rs"]);
+		let second = run(first.cursor);
+		let paths: Vec<_> = second.matches.iter().map(|m| m.path.as_str()).collect();
+		assert_eq!(paths, ["3.rs", "5.rs"]);
+		assert!(second.cursor.is_none());
+
+		let _ = std::fs::remove_dir_all(&root);
+	}
 }
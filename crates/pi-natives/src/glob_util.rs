@@ -4,49 +4,171 @@
 use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use napi::bindgen_prelude::*;
 
+/// A raw glob after normalization, with the rewrites that were applied.
+pub struct GlobRewrite {
+	/// Pattern handed to `globset`.
+	pub pattern:       String,
+	/// Whether `**/` was prepended for recursive matching.
+	pub auto_prefixed: bool,
+	/// Number of `}` appended to close unclosed alternation groups.
+	pub closed_braces: usize,
+}
+
 /// Normalize a raw glob string: fix path separators, optionally prepend `**/`
 /// for recursive matching, and close any unclosed `{` alternation groups.
 pub fn build_glob_pattern(glob: &str, recursive: bool) -> String {
+	rewrite_glob_pattern(glob, recursive).pattern
+}
+
+/// Like [`build_glob_pattern`], but also reports which rewrites were applied.
+pub fn rewrite_glob_pattern(glob: &str, recursive: bool) -> GlobRewrite {
 	let normalized = glob.replace('\\', "/");
-	let pattern = if !recursive || normalized.contains('/') || normalized.starts_with("**") {
-		normalized
-	} else {
+	let auto_prefixed = recursive && !normalized.contains('/') && !normalized.starts_with("**");
+	let pattern = if auto_prefixed {
 		format!("**/{normalized}")
+	} else {
+		normalized
 	};
-	fix_unclosed_braces(pattern)
+	let (pattern, closed_braces) = fix_unclosed_braces(pattern);
+	GlobRewrite { pattern, auto_prefixed, closed_braces }
+}
+
+/// Split a normalized pattern into `/`-separated components, keeping
+/// separators inside `{...}` and `[...]` with their component.
+pub fn split_glob_components(pattern: &str) -> Vec<&str> {
+	let mut components = Vec::new();
+	let (mut depth, mut in_class, mut start) = (0usize, false, 0);
+	let mut chars = pattern.char_indices();
+	while let Some((idx, ch)) = chars.next() {
+		match ch {
+			'\\' => {
+				chars.next();
+			},
+			'[' if !in_class => in_class = true,
+			']' if in_class => in_class = false,
+			'{' if !in_class => depth += 1,
+			'}' if !in_class => depth = depth.saturating_sub(1),
+			'/' if depth == 0 && !in_class => {
+				components.push(&pattern[start..idx]);
+				start = idx + 1;
+			},
+			_ => {},
+		}
+	}
+	components.push(&pattern[start..]);
+	components
+}
+
+/// Whether globs should match case-insensitively: as `case_sensitive` says,
+/// or, when unset, unless a pattern contains an uppercase letter.
+pub fn ignores_case<'a>(
+	patterns: impl IntoIterator<Item = &'a str>,
+	case_sensitive: Option<bool>,
+) -> bool {
+	case_sensitive.map_or_else(
+		|| {
+			!patterns
+				.into_iter()
+				.any(|pattern| pattern.chars().any(char::is_uppercase))
+		},
+		|sensitive| !sensitive,
+	)
 }
 
 /// Compile a glob pattern string into a [`GlobSet`].
 ///
 /// When `recursive` is true, simple patterns (no path separators, no leading
-/// `**`) are automatically prefixed with `**/`.
-pub fn compile_glob(glob: &str, recursive: bool) -> Result<GlobSet> {
-	let mut builder = GlobSetBuilder::new();
-	let pattern = build_glob_pattern(glob, recursive);
-	let glob = GlobBuilder::new(&pattern)
-		.literal_separator(true)
-		.build()
-		.map_err(|err| Error::from_reason(format!("Invalid glob pattern: {err}")))?;
-	builder.add(glob);
-	builder
-		.build()
-		.map_err(|err| Error::from_reason(format!("Failed to build glob matcher: {err}")))
+/// `**`) are automatically prefixed with `**/`. Casing follows
+/// [`ignores_case`].
+pub fn compile_glob(glob: &str, recursive: bool, case_sensitive: Option<bool>) -> Result<GlobSet> {
+	compile_glob_set(&[glob], recursive, ignores_case([glob], case_sensitive))
 }
 
 /// Like [`compile_glob`], but accepts an `Option<&str>` — returns `Ok(None)`
 /// when the input is `None`, empty, or whitespace-only.
-pub fn try_compile_glob(glob: Option<&str>, recursive: bool) -> Result<Option<GlobSet>> {
+pub fn try_compile_glob(
+	glob: Option<&str>,
+	recursive: bool,
+	case_sensitive: Option<bool>,
+) -> Result<Option<GlobSet>> {
 	let Some(glob) = glob.map(str::trim).filter(|v| !v.is_empty()) else {
 		return Ok(None);
 	};
-	compile_glob(glob, recursive).map(Some)
+	compile_glob(glob, recursive, case_sensitive).map(Some)
+}
+
+/// Include and exclude globs compiled from a pattern list, where patterns
+/// starting with `!` exclude.
+pub struct GlobFilter {
+	allow: GlobSet,
+	deny:  Option<GlobSet>,
+}
+
+impl GlobFilter {
+	/// Whether `path` matches an include pattern and no exclude pattern.
+	pub fn is_match(&self, path: &str) -> bool {
+		self.allow.is_match(path) && !self.deny.as_ref().is_some_and(|deny| deny.is_match(path))
+	}
+}
+
+/// Compile a pattern list into a [`GlobFilter`], normalizing each pattern as
+/// [`compile_glob`] does.
+///
+/// With `case_sensitive` unset, the whole list matches case-insensitively
+/// unless some pattern contains an uppercase letter. Empty patterns are
+/// ignored; with no include patterns, every path is included.
+pub fn compile_glob_filter(
+	patterns: &[String],
+	recursive: bool,
+	case_sensitive: Option<bool>,
+) -> Result<GlobFilter> {
+	let mut allow = Vec::new();
+	let mut deny = Vec::new();
+	for pattern in patterns.iter().map(|pattern| pattern.trim()) {
+		match pattern.strip_prefix('!') {
+			Some(negated) if !negated.trim().is_empty() => deny.push(negated.trim()),
+			Some(_) => {},
+			None if !pattern.is_empty() => allow.push(pattern),
+			None => {},
+		}
+	}
+	let ignore_case = ignores_case(allow.iter().chain(&deny).copied(), case_sensitive);
+	if allow.is_empty() {
+		allow.push("*");
+	}
+	Ok(GlobFilter {
+		allow: compile_glob_set(&allow, recursive, ignore_case)?,
+		deny:  if deny.is_empty() {
+			None
+		} else {
+			Some(compile_glob_set(&deny, recursive, ignore_case)?)
+		},
+	})
 }
 
-/// Close unclosed `{` alternation groups in a glob pattern.
+fn compile_glob_set(globs: &[&str], recursive: bool, ignore_case: bool) -> Result<GlobSet> {
+	let mut builder = GlobSetBuilder::new();
+	for glob in globs {
+		let pattern = build_glob_pattern(glob, recursive);
+		builder.add(
+			GlobBuilder::new(&pattern)
+				.literal_separator(true)
+				.case_insensitive(ignore_case)
+				.build()
+				.map_err(|err| Error::from_reason(format!("Invalid glob pattern `{glob}`: {err}")))?,
+		);
+	}
+	builder
+		.build()
+		.map_err(|err| Error::from_reason(format!("Failed to build glob matcher: {err}")))
+}
+
+/// Close unclosed `{` alternation groups in a glob pattern, returning the
+/// fixed pattern and the number of braces appended.
 ///
 /// LLMs occasionally produce patterns like `*.{ts,js` without the closing `}`.
 /// Rather than failing, we append the missing braces.
-fn fix_unclosed_braces(pattern: String) -> String {
+fn fix_unclosed_braces(pattern: String) -> (String, usize) {
 	let opens = pattern.chars().filter(|&c| c == '{').count();
 	let closes = pattern.chars().filter(|&c| c == '}').count();
 	if opens > closes {
@@ -54,9 +176,9 @@ fn fix_unclosed_braces(pattern: String) -> String {
 		for _ in 0..(opens - closes) {
 			fixed.push('}');
 		}
-		fixed
+		(fixed, opens - closes)
 	} else {
-		pattern
+		(pattern, 0)
 	}
 }
 
@@ -104,13 +226,51 @@ mod tests {
 		assert_eq!(build_glob_pattern("*.{ts,js}", true), "**/*.{ts,js}");
 	}
 
+	#[test]
+	fn rewrite_reports_applied_fixes() {
+		let rewrite = rewrite_glob_pattern("*.{ts,js", true);
+		assert_eq!((rewrite.auto_prefixed, rewrite.closed_braces), (true, 1));
+		assert!(!rewrite_glob_pattern("src/*.ts", true).auto_prefixed);
+	}
+
+	#[test]
+	fn components_keep_braced_separators() {
+		assert_eq!(split_glob_components("**/{a/b,c}/[/]x"), ["**", "{a/b,c}", "[/]x"]);
+	}
+
 	#[test]
 	fn compile_glob_accepts_valid_pattern() {
-		assert!(compile_glob("*.ts", true).is_ok());
+		assert!(compile_glob("*.ts", true, None).is_ok());
 	}
 
 	#[test]
 	fn compile_glob_fixes_unclosed_brace() {
-		assert!(compile_glob("*.{ts,tsx,js", true).is_ok());
+		assert!(compile_glob("*.{ts,tsx,js", true, None).is_ok());
+	}
+
+	#[test]
+	fn filter_applies_negated_patterns() {
+		let patterns = ["src/**/*.{ts,tsx}", "!**/*.test.ts"].map(String::from);
+		let filter = compile_glob_filter(&patterns, true, None).unwrap();
+		assert!(filter.is_match("src/a/b.ts"));
+		assert!(filter.is_match("src/c.tsx"));
+		assert!(!filter.is_match("src/a/b.test.ts"));
+		assert!(!filter.is_match("lib/d.ts"));
+
+		let only_negated = compile_glob_filter(&["!*.md".to_string()], true, None).unwrap();
+		assert!(only_negated.is_match("src/main.rs"));
+		assert!(!only_negated.is_match("docs/README.md"));
+	}
+
+	#[test]
+	fn smart_case_follows_pattern_casing() {
+		let smart = compile_glob("*.md", true, None).unwrap();
+		assert!(smart.is_match("docs/README.MD"));
+		let upper = compile_glob("README*", true, None).unwrap();
+		assert!(!upper.is_match("docs/readme.md"));
+		let forced = compile_glob("README*", true, Some(false)).unwrap();
+		assert!(forced.is_match("docs/readme.md"));
+		let exact = compile_glob("*.md", true, Some(true)).unwrap();
+		assert!(!exact.is_match("docs/README.MD"));
 	}
 }
@@ -9,29 +9,46 @@
 
 use std::{
 	borrow::Cow,
+	collections::{BTreeMap, HashMap, HashSet, VecDeque},
 	fs::File,
 	io::{self, Cursor, Read},
 	path::{Path, PathBuf},
+	sync::{
+		Arc, LazyLock,
+		atomic::{AtomicUsize, Ordering},
+	},
 };
 
 use globset::GlobSet;
 use grep_matcher::Matcher;
-use grep_regex::RegexMatcherBuilder;
 use grep_searcher::{
 	BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch,
 };
-use napi::{
-	JsString,
-	bindgen_prelude::*,
-	threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode},
-};
+use napi::{JsString, bindgen_prelude::*, threadsafe_function::ThreadsafeFunction};
 use napi_derive::napi;
+use parking_lot::Mutex;
 use rayon::prelude::*;
 use smallvec::SmallVec;
 
-use crate::{fs_cache, glob_util, task};
+use crate::{
+	classify::{self, PathClass},
+	fs_cache, git, glob_util,
+	prof::{self, CallTrace, TraceSpan},
+	project,
+	regex_engine::{PatternMatcher, RegexEngine},
+	sort, stream, task, text,
+};
 
+/// Default per-file read cap; overridable with `maxFileSize`.
 const MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
+/// Largest batch of files searched in parallel under a global limit.
+const MAX_LIMITED_BATCH: usize = 1024;
+/// Matched/context lines between cancellation checks inside one file.
+const HEARTBEAT_LINES: u32 = 256;
+/// Default histogram buckets per file for `heatmap`.
+const DEFAULT_HEATMAP_BUCKETS: u32 = 32;
+/// Largest accepted `heatmapBuckets`.
+const MAX_HEATMAP_BUCKETS: u32 = 1024;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum OutputMode {
@@ -43,78 +60,182 @@ enum OutputMode {
 #[napi(object)]
 pub struct SearchOptions {
 	/// Regex pattern to search for.
-	pub pattern:        String,
+	pub pattern:           String,
 	/// Case-insensitive search.
 	#[napi(js_name = "ignoreCase")]
-	pub ignore_case:    Option<bool>,
+	pub ignore_case:       Option<bool>,
 	/// Enable multiline matching.
-	pub multiline:      Option<bool>,
+	pub multiline:         Option<bool>,
+	/// Regex engine: "default" or "pcre2" for lookaround and backreferences
+	/// (default falls back to pcre2 when the pattern fails to compile).
+	pub engine:            Option<String>,
 	/// Maximum number of matches to return.
 	#[napi(js_name = "maxCount")]
-	pub max_count:      Option<u32>,
+	pub max_count:         Option<u32>,
 	/// Skip first N matches.
-	pub offset:         Option<u32>,
+	pub offset:            Option<u32>,
 	/// Lines of context before matches.
 	#[napi(js_name = "contextBefore")]
-	pub context_before: Option<u32>,
+	pub context_before:    Option<u32>,
 	/// Lines of context after matches.
 	#[napi(js_name = "contextAfter")]
-	pub context_after:  Option<u32>,
+	pub context_after:     Option<u32>,
 	/// Lines of context before/after matches (legacy).
-	pub context:        Option<u32>,
-	/// Truncate lines longer than this (characters).
+	pub context:           Option<u32>,
+	/// Truncate lines longer than this (bytes, or cells with
+	/// `truncateByWidth`).
 	#[napi(js_name = "maxColumns")]
-	pub max_columns:    Option<u32>,
+	pub max_columns:       Option<u32>,
+	/// Count `maxColumns` in display cells, wide characters two and tabs
+	/// three, as the text module measures, instead of bytes.
+	#[napi(js_name = "truncateByWidth")]
+	pub truncate_by_width: Option<bool>,
 	/// Output mode (content or count).
-	pub mode:           Option<String>,
+	pub mode:              Option<String>,
+	/// First line to search (1-based, inclusive); line numbers in the result
+	/// stay relative to the whole content.
+	#[napi(js_name = "lineStart")]
+	pub line_start:        Option<u32>,
+	/// Last line to search (1-based, inclusive). Context never reaches
+	/// outside `lineStart`..`lineEnd`.
+	#[napi(js_name = "lineEnd")]
+	pub line_end:          Option<u32>,
 }
 
 /// Options for searching files on disk.
 #[napi(object)]
 pub struct GrepOptions<'env> {
 	/// Regex pattern to search for.
-	pub pattern:        String,
-	/// Directory or file to search.
-	pub path:           String,
+	pub pattern:             String,
+	/// Directory or file to search, or several searched as one; each result
+	/// then reports its `root`, and files under more than one root are
+	/// searched once, under the first.
+	#[napi(ts_type = "string | string[]")]
+	pub path:                Either<String, Vec<String>>,
+	/// Workspace package (name or relative path) to search instead of all of
+	/// `path`, resolved from the workspace manifests at `path` (single `path`
+	/// only).
+	pub package:             Option<String>,
+	/// Search only files changed in git: "working" (uncommitted, including
+	/// untracked), "staged", or "branch:<ref>" (changed since forking from
+	/// `<ref>`).
+	#[napi(js_name = "changedOnly", ts_type = "\"working\" | \"staged\" | `branch:${string}`")]
+	pub changed_only:        Option<String>,
 	/// Glob filter for filenames (e.g., "*.ts").
-	pub glob:           Option<String>,
-	/// Filter by file type (e.g., "js", "py", "rust").
+	pub glob:                Option<String>,
+	/// Match `glob` case-sensitively; by default, matching ignores case unless
+	/// `glob` contains an uppercase letter.
+	#[napi(js_name = "globCaseSensitive")]
+	pub glob_case_sensitive: Option<bool>,
+	/// Filter by file type (e.g., "js", "py", "rust"), or by `classifyPath`
+	/// class ("test", "source", "fixture", "config", "docs"); prefix a class
+	/// with "!" to exclude it.
 	#[napi(js_name = "type")]
-	pub type_filter:    Option<String>,
+	pub type_filter:         Option<String>,
 	/// Case-insensitive search.
 	#[napi(js_name = "ignoreCase")]
-	pub ignore_case:    Option<bool>,
+	pub ignore_case:         Option<bool>,
 	/// Enable multiline matching.
-	pub multiline:      Option<bool>,
+	pub multiline:           Option<bool>,
+	/// Regex engine: "default" or "pcre2" for lookaround and backreferences
+	/// (default falls back to pcre2 when the pattern fails to compile).
+	pub engine:              Option<String>,
 	/// Include hidden files (default: true).
-	pub hidden:         Option<bool>,
+	pub hidden:              Option<bool>,
 	/// Respect .gitignore files (default: true).
-	pub gitignore:      Option<bool>,
+	pub gitignore:           Option<bool>,
+	/// Standard ignore sources to apply ("gitignore", "ignore", "rgignore");
+	/// overrides `gitignore` when set (default: all three).
+	#[napi(js_name = "ignoreSources")]
+	pub ignore_sources:      Option<Vec<String>>,
+	/// Additional ignore file names to honor in each directory (e.g.
+	/// ".dockerignore").
+	#[napi(js_name = "ignoreFiles")]
+	pub ignore_files:        Option<Vec<String>>,
 	/// Enable shared filesystem scan cache (default: false).
-	pub cache:          Option<bool>,
+	pub cache:               Option<bool>,
 	/// Maximum number of matches to return.
 	#[napi(js_name = "maxCount")]
-	pub max_count:      Option<u32>,
-	/// Skip first N matches.
-	pub offset:         Option<u32>,
+	pub max_count:           Option<u32>,
+	/// Skip first N matches; ignored with `cursor`, which already accounts
+	/// for it.
+	pub offset:              Option<u32>,
+	/// `cursor` of a previous result with the same options, to continue
+	/// after its last match without searching the files before it again.
+	/// Resending the same `offset` alongside it does not skip again.
+	pub cursor:              Option<String>,
+	/// Order files naturally (`file2` before `file10`, accents and case
+	/// folded) instead of by byte value.
+	#[napi(js_name = "naturalSort")]
+	pub natural_sort:        Option<bool>,
 	/// Lines of context before matches.
 	#[napi(js_name = "contextBefore")]
-	pub context_before: Option<u32>,
+	pub context_before:      Option<u32>,
 	/// Lines of context after matches.
 	#[napi(js_name = "contextAfter")]
-	pub context_after:  Option<u32>,
+	pub context_after:       Option<u32>,
 	/// Lines of context before/after matches (legacy).
-	pub context:        Option<u32>,
-	/// Truncate lines longer than this (characters).
+	pub context:             Option<u32>,
+	/// Truncate lines longer than this (bytes, or cells with
+	/// `truncateByWidth`).
 	#[napi(js_name = "maxColumns")]
-	pub max_columns:    Option<u32>,
-	/// Output mode (content, filesWithMatches, or count).
-	pub mode:           Option<String>,
+	pub max_columns:         Option<u32>,
+	/// Count `maxColumns` in display cells, as the text module measures with
+	/// `tabWidth`, instead of bytes.
+	#[napi(js_name = "truncateByWidth")]
+	pub truncate_by_width:   Option<bool>,
+	/// Report where each match starts on its line as `columns`, counted in
+	/// bytes, UTF-16 code units, and display cells (content mode only).
+	pub columns:             Option<bool>,
+	/// Cells per tab in display columns, as in text rendering (default: 3).
+	#[napi(js_name = "tabWidth")]
+	pub tab_width:           Option<u32>,
+	/// Report each match's ranges within `line` in `spans`, as UTF-16 code
+	/// unit offsets for slicing JS strings (content mode only).
+	pub spans:               Option<bool>,
+	/// Bytes read per file before the rest is skipped (default: 4 MiB; 0
+	/// disables the cap).
+	#[napi(js_name = "maxFileSize")]
+	pub max_file_size:       Option<i64>,
+	/// Search only the first files, in search order, whose sizes (capped at
+	/// `maxFileSize`) add up to at most this many bytes; see
+	/// `budgetExhausted`.
+	#[napi(js_name = "maxTotalBytes")]
+	pub max_total_bytes:     Option<i64>,
+	/// Search at most this many files, in search order; see
+	/// `budgetExhausted`.
+	#[napi(js_name = "maxFiles")]
+	pub max_files:           Option<u32>,
+	/// Output mode (content, filesWithMatches, filesWithoutMatch, count, or
+	/// countByDir).
+	pub mode:                Option<String>,
+	/// Directory depth used to group counts in countByDir mode (default: 1).
+	#[napi(js_name = "dirDepth")]
+	pub dir_depth:           Option<u32>,
+	/// Annotate each match with the last commit that touched its line, using
+	/// one `git blame` per matched file (content mode only).
+	pub blame:               Option<bool>,
+	/// Report a match-density histogram for each file in the result as
+	/// `heatmaps`, counted over the whole file regardless of `maxCount`.
+	pub heatmap:             Option<bool>,
+	/// Histogram buckets per file with `heatmap` (default: 32, max 1024).
+	#[napi(js_name = "heatmapBuckets")]
+	pub heatmap_buckets:     Option<u32>,
+	/// Group adjacent matches of a file with their context into `blocks`, so
+	/// no line is listed twice; matches then carry no context (content mode
+	/// only).
+	pub blocks:              Option<bool>,
+	/// Attach per-phase timings to the result as `trace`.
+	pub trace:               Option<bool>,
+	/// Batching of `onMatch` results.
+	pub stream:              Option<stream::StreamOptions>,
 	/// Abort signal for cancelling the operation.
-	pub signal:         Option<Unknown<'env>>,
+	pub signal:              Option<Unknown<'env>>,
 	/// Timeout in milliseconds for the operation.
 	#[napi(js_name = "timeoutMs")]
-	pub timeout_ms:     Option<u32>,
+	pub timeout_ms:          Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:               Option<ClassInstance<'env, task::CancellationScope>>,
 }
 
 /// A context line (before or after a match).
@@ -127,22 +248,54 @@ pub struct ContextLine {
 	pub line:        String,
 }
 
+/// A matched range within a line.
+#[derive(Clone, Copy)]
+#[napi(object)]
+pub struct MatchSpan {
+	/// Start offset in UTF-16 code units (inclusive).
+	pub start: u32,
+	/// End offset in UTF-16 code units (exclusive).
+	pub end:   u32,
+}
+
+/// Where a match starts on its line, in the units different consumers count
+/// columns in (all 1-indexed).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[napi(object)]
+pub struct MatchColumn {
+	/// Byte column, as ripgrep's `--column` reports it.
+	pub byte:    u32,
+	/// UTF-16 code unit column, for indexing JS strings.
+	pub utf16:   u32,
+	/// Display column, with tabs `tabWidth` cells wide and wide characters
+	/// two cells.
+	pub display: u32,
+}
+
 /// A single match in the content.
 #[napi(object)]
 pub struct Match {
 	/// 1-indexed line number.
 	#[napi(js_name = "lineNumber")]
-	pub line_number:    u32,
+	pub line_number:     u32,
 	/// The matched line content.
-	pub line:           String,
+	pub line:            String,
 	/// Context lines before the match.
 	#[napi(js_name = "contextBefore")]
-	pub context_before: Option<Vec<ContextLine>>,
+	pub context_before:  Option<Vec<ContextLine>>,
 	/// Context lines after the match.
 	#[napi(js_name = "contextAfter")]
-	pub context_after:  Option<Vec<ContextLine>>,
+	pub context_after:   Option<Vec<ContextLine>>,
 	/// Whether the line was truncated.
-	pub truncated:      Option<bool>,
+	pub truncated:       Option<bool>,
+	/// Matched ranges within `line`, as UTF-16 code unit offsets.
+	pub spans:           Vec<MatchSpan>,
+	/// Last line of a match spanning several lines (multiline only).
+	#[napi(js_name = "endLineNumber")]
+	pub end_line_number: Option<u32>,
+	/// Full text from the start of the first match to the end of the last,
+	/// untruncated, for a match spanning several lines (multiline only).
+	pub text:            Option<String>,
 }
 
 /// Result of searching content.
@@ -160,12 +313,53 @@ pub struct SearchResult {
 	pub error:         Option<String>,
 }
 
+/// A named piece of content for `searchDocuments`.
+#[napi(object)]
+pub struct SearchDocument {
+	/// Caller-chosen identifier, echoed in the result.
+	pub id:      String,
+	/// Text to search.
+	pub content: String,
+}
+
+/// Result of searching one document.
+#[napi(object)]
+pub struct DocumentSearchResult {
+	/// Identifier of the document.
+	pub id:            String,
+	/// Matches in the document (empty in count modes).
+	pub matches:       Vec<Match>,
+	/// Total number of matches in the document.
+	#[napi(js_name = "matchCount")]
+	pub match_count:   u32,
+	/// Whether `maxCount` stopped the search of this document.
+	#[napi(js_name = "limitReached")]
+	pub limit_reached: bool,
+	/// Error message, if searching this document failed.
+	pub error:         Option<String>,
+}
+
+/// Result of searching several documents.
+#[napi(object)]
+pub struct SearchDocumentsResult {
+	/// Per-document results, in input order.
+	pub documents:     Vec<DocumentSearchResult>,
+	/// Matches across all documents.
+	#[napi(js_name = "totalMatches")]
+	pub total_matches: u32,
+	/// Error message, if the pattern failed to compile.
+	pub error:         Option<String>,
+}
+
 /// A single match in a grep result.
 #[derive(Clone)]
 #[napi(object)]
 pub struct GrepMatch {
 	/// File path for the match (relative for directory searches).
 	pub path:           String,
+	/// Search root `path` is relative to, as given in `path`; set only when
+	/// searching several roots.
+	pub root:           Option<String>,
 	/// 1-indexed line number (0 for count-only entries).
 	#[napi(js_name = "lineNumber")]
 	pub line_number:    u32,
@@ -182,6 +376,13 @@ pub struct GrepMatch {
 	/// Per-file match count (count mode only).
 	#[napi(js_name = "matchCount")]
 	pub match_count:    Option<u32>,
+	/// Last commit that touched the line (`blame: true` only).
+	pub blame:          Option<git::BlameInfo>,
+	/// Start of each match on the line (`columns: true` only).
+	pub columns:        Option<Vec<MatchColumn>>,
+	/// Ranges of the match within `line`, as UTF-16 code unit offsets (with
+	/// `spans`).
+	pub spans:          Option<Vec<MatchSpan>>,
 }
 
 /// Result of searching files.
@@ -201,11 +402,113 @@ pub struct GrepResult {
 	/// Whether the limit/offset stopped the search early.
 	#[napi(js_name = "limitReached")]
 	pub limit_reached:      Option<bool>,
+	/// Files larger than `maxFileSize` that were only partially searched,
+	/// prefixed with their root when searching several roots.
+	#[napi(js_name = "truncatedFiles")]
+	pub truncated_files:    Option<Vec<String>>,
+	/// Per-directory totals keyed by directory path (countByDir mode only).
+	pub directories:        Option<HashMap<String, DirectoryCount>>,
+	/// Files whose results were reused from a `GrepSession` (`grepIncremental`
+	/// only).
+	#[napi(js_name = "reusedFiles")]
+	pub reused_files:       Option<u32>,
+	/// Match density per file in `matches` (`heatmap` only).
+	pub heatmaps:           Option<Vec<FileHeatmap>>,
+	/// Matches grouped with their context (`blocks` only).
+	pub blocks:             Option<Vec<GrepBlock>>,
+	/// Opaque position after the last match returned, set when `maxCount`
+	/// or a budget stopped the search before the end; pass it back as
+	/// `cursor` for the next page.
+	pub cursor:             Option<String>,
+	/// Budget that left files unsearched: "maxFiles" or "maxTotalBytes".
+	#[napi(js_name = "budgetExhausted", ts_type = "\"maxFiles\" | \"maxTotalBytes\"")]
+	pub budget_exhausted:   Option<String>,
+	/// Per-phase timings, when requested with `trace`.
+	pub trace:              Option<TraceSpan>,
+}
+
+/// Consecutive lines of one file covering adjacent matches and their context.
+#[napi(object)]
+pub struct GrepBlock {
+	/// File path, as in `matches`.
+	pub path:       String,
+	/// Search root of the file, as in `matches`.
+	pub root:       Option<String>,
+	/// First line of the block (1-indexed).
+	#[napi(js_name = "startLine")]
+	pub start_line: u32,
+	/// Last line of the block (1-indexed, inclusive).
+	#[napi(js_name = "endLine")]
+	pub end_line:   u32,
+	pub lines:      Vec<BlockLine>,
+}
+
+/// A line of a [`GrepBlock`].
+#[napi(object)]
+pub struct BlockLine {
+	#[napi(js_name = "lineNumber")]
+	pub line_number: u32,
+	pub line:        String,
+	/// Whether the line is part of a match rather than context.
+	#[napi(js_name = "isMatch")]
+	pub is_match:    bool,
+}
+
+/// Where matches fall in one file, for a results minimap.
+#[napi(object)]
+pub struct FileHeatmap {
+	/// File path, as in `matches`.
+	pub path:    String,
+	/// Search root of the file, as in `matches`.
+	pub root:    Option<String>,
+	/// Lines in the file (in the searched part for files over `maxFileSize`).
+	pub lines:   u32,
+	/// Matching lines counted into `buckets`.
+	pub matches: u32,
+	/// Matching lines in each equal slice of the file, first line to last.
+	pub buckets: Vec<u32>,
+}
+
+/// A batch of streamed `onMatch` results.
+#[napi(object)]
+pub struct GrepMatchBatch {
+	/// Batch number, counting from 0; a gap means a batch was dropped.
+	pub seq:     u32,
+	pub matches: Vec<GrepMatch>,
+	/// Whether this is the last batch of the search.
+	pub done:    bool,
+	/// Matches dropped so far because the JS queue refused their batch.
+	pub dropped: u32,
+}
+
+impl stream::StreamBatch for GrepMatchBatch {
+	type Item = GrepMatch;
+
+	fn new(seq: u32, matches: Vec<GrepMatch>, done: bool, dropped: u32) -> Self {
+		Self { seq, matches, done, dropped }
+	}
+}
+
+/// Aggregated matches under one directory (countByDir mode).
+#[napi(object)]
+pub struct DirectoryCount {
+	/// Files with at least one match.
+	pub files:   u32,
+	/// Total matches.
+	pub matches: u32,
 }
 
 enum TypeFilter {
-	Known { exts: &'static [&'static str], names: &'static [&'static str] },
+	Known {
+		exts:  &'static [&'static str],
+		names: &'static [&'static str],
+	},
 	Custom(String),
+	/// A `classifyPath` class, e.g. "test", or "!test" to exclude it.
+	Class {
+		class:  PathClass,
+		negate: bool,
+	},
 }
 
 impl TypeFilter {
@@ -213,6 +516,7 @@ impl TypeFilter {
 		match self {
 			Self::Known { exts, .. } => exts.iter().any(|e| ext.eq_ignore_ascii_case(e)),
 			Self::Custom(custom_ext) => ext.eq_ignore_ascii_case(custom_ext),
+			Self::Class { .. } => false,
 		}
 	}
 
@@ -220,11 +524,12 @@ impl TypeFilter {
 		match self {
 			Self::Known { names, .. } => names.iter().any(|n| name.eq_ignore_ascii_case(n)),
 			Self::Custom(ext) => ext.eq_ignore_ascii_case(name),
+			Self::Class { .. } => false,
 		}
 	}
 }
 
-struct MatchCollector {
+struct MatchCollector<'m> {
 	matches:         Vec<CollectedMatch>,
 	match_count:     u64,
 	collected_count: u64,
@@ -233,16 +538,30 @@ struct MatchCollector {
 	skipped:         u64,
 	limit_reached:   bool,
 	context_before:  SmallVec<[ContextLine; 8]>,
-	max_columns:     Option<usize>,
+	max_columns:     Option<ColumnLimit>,
 	collect_matches: bool,
+	/// Matcher used to compute per-line spans; `None` skips span collection.
+	span_matcher:    Option<&'m PatternMatcher>,
+	/// Matcher and tab width used to compute match columns; `None` skips them.
+	column_matcher:  Option<(&'m PatternMatcher, usize)>,
+	ct:              &'m task::CancelToken,
+	/// Sink calls since the last cancellation check.
+	since_heartbeat: u32,
 }
 
+#[derive(Clone)]
 struct CollectedMatch {
-	line_number:    u64,
-	line:           String,
-	context_before: SmallVec<[ContextLine; 8]>,
-	context_after:  SmallVec<[ContextLine; 8]>,
-	truncated:      bool,
+	line_number:     u64,
+	line:            String,
+	context_before:  SmallVec<[ContextLine; 8]>,
+	context_after:   SmallVec<[ContextLine; 8]>,
+	truncated:       bool,
+	spans:           Vec<MatchSpan>,
+	columns:         Vec<MatchColumn>,
+	/// Set when the match spans several lines.
+	end_line_number: Option<u64>,
+	/// Matched text, for matches spanning several lines.
+	text:            Option<String>,
 }
 
 struct SearchResultInternal {
@@ -252,23 +571,117 @@ struct SearchResultInternal {
 	limit_reached: bool,
 }
 
+/// A file or directory searched by `grep()`.
+struct GrepRoot {
+	path:  PathBuf,
+	/// Reported as each result's `root` when searching several roots.
+	label: Option<Arc<str>>,
+}
+
+/// Files git reports as changed, for `changedOnly`.
+struct ChangedSet {
+	/// Canonical search root that relative paths are resolved against.
+	root:  PathBuf,
+	files: HashSet<PathBuf>,
+}
+
+impl ChangedSet {
+	fn contains(&self, relative: &str) -> bool {
+		self.files.contains(&self.root.join(relative))
+	}
+}
+
 struct FileEntry {
 	path:          PathBuf,
 	relative_path: String,
+	root:          Option<Arc<str>>,
+	/// Size in bytes when listed.
+	size:          u64,
+}
+
+impl FileEntry {
+	/// Path listed in `truncatedFiles`.
+	fn listed_path(&self) -> String {
+		listed_path(self.root.as_deref(), &self.relative_path)
+	}
 }
 
 struct FileSearchResult {
 	relative_path: String,
+	root:          Option<Arc<str>>,
 	matches:       Vec<CollectedMatch>,
 	match_count:   u64,
+	/// File exceeded the read cap and was only partially searched.
+	truncated:     bool,
+}
+
+/// Totals accumulated by a multi-file search with a global offset/limit.
+struct LimitedSearch {
+	matches:            Vec<GrepMatch>,
+	total_matches:      u64,
+	files_with_matches: u32,
+	files_searched:     u32,
+	limit_reached:      bool,
+	truncated_files:    Vec<String>,
+	/// Where to resume when the limit stopped the search before the end.
+	resume:             Option<SearchCursor>,
+}
+
+/// Where a paged search stopped, handed out as an opaque `cursor`.
+struct SearchCursor {
+	/// Index of the file to resume in, in search order.
+	file: usize,
+	/// Matches of that file already returned.
+	skip: u64,
+	/// Absolute path of that file, to find it again if files came or went.
+	path: String,
+}
+
+impl SearchCursor {
+	fn new(file: usize, skip: u64, path: &Path) -> Self {
+		Self { file, skip, path: path.to_string_lossy().into_owned() }
+	}
+
+	fn parse(cursor: &str) -> Result<Self> {
+		let mut parts = cursor.splitn(3, ':');
+		let (Some(file), Some(skip), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+			return Err(Error::from_reason(format!("Invalid cursor: {cursor}")));
+		};
+		match (file.parse(), skip.parse()) {
+			(Ok(file), Ok(skip)) => Ok(Self { file, skip, path: path.to_string() }),
+			_ => Err(Error::from_reason(format!("Invalid cursor: {cursor}"))),
+		}
+	}
+
+	fn encode(&self) -> String {
+		format!("{}:{}:{}", self.file, self.skip, self.path)
+	}
+
+	/// Index of the cursor's file in `entries`, looked up by path when the
+	/// file moved.
+	fn position(&self, entries: &[FileEntry]) -> Result<usize> {
+		let is_file = |entry: &FileEntry| entry.path.to_string_lossy() == self.path;
+		if entries.get(self.file).is_some_and(is_file) {
+			return Ok(self.file);
+		}
+		entries.iter().position(is_file).ok_or_else(|| {
+			Error::from_reason(format!(
+				"Stale cursor: {} is no longer searched; restart without `cursor`",
+				self.path
+			))
+		})
+	}
 }
 
-impl MatchCollector {
+impl<'m> MatchCollector<'m> {
 	fn new(
 		max_count: Option<u64>,
 		offset: u64,
-		max_columns: Option<usize>,
+		max_columns: Option<ColumnLimit>,
 		collect_matches: bool,
+		span_matcher: Option<&'m PatternMatcher>,
+		column_matcher: Option<(&'m PatternMatcher, usize)>,
+		ct: &'m task::CancelToken,
 	) -> Self {
 		Self {
 			matches: Vec::new(),
@@ -281,19 +694,117 @@ impl MatchCollector {
 			context_before: SmallVec::new(),
 			max_columns,
 			collect_matches,
+			span_matcher,
+			column_matcher,
+			ct,
+			since_heartbeat: 0,
+		}
+	}
+
+	/// Honor cancellation every [`HEARTBEAT_LINES`] sink calls, so a file with
+	/// many matches stops mid-search.
+	fn heartbeat(&mut self) -> io::Result<()> {
+		self.since_heartbeat += 1;
+		if self.since_heartbeat < HEARTBEAT_LINES {
+			return Ok(());
 		}
+		self.since_heartbeat = 0;
+		self.ct.heartbeat().map_err(cancelled_error)
 	}
 
 	fn truncate_line(&self, line: &str) -> (String, bool) {
-		match self.max_columns {
-			Some(max) if line.len() > max => {
-				let cut = max.saturating_sub(3);
-				let boundary = line.floor_char_boundary(cut);
-				let truncated = format!("{}...", &line[..boundary]);
-				(truncated, true)
+		let boundary = match self.max_columns {
+			Some(ColumnLimit::Bytes(max)) if line.len() > max => {
+				line.floor_char_boundary(max.saturating_sub(3))
 			},
-			_ => (line.to_string(), false),
+			Some(ColumnLimit::Cells { max, tab_width })
+				if text::display_width(line, tab_width) > max =>
+			{
+				text::width_prefix_len(line, max.saturating_sub(3), tab_width)
+			},
+			_ => return (line.to_string(), false),
+		};
+		(format!("{}...", &line[..boundary]), true)
+	}
+}
+
+/// Collect all matched ranges in `line`, clipped to the first `visible_len`
+/// bytes and converted to UTF-16 code unit offsets.
+pub(crate) fn collect_spans(
+	matcher: &PatternMatcher,
+	line: &str,
+	visible_len: usize,
+) -> Vec<MatchSpan> {
+	let mut byte_spans = Vec::new();
+	let _ = matcher.find_iter(line.as_bytes(), |m| {
+		if m.start() >= visible_len {
+			return false;
+		}
+		if m.start() < m.end() {
+			byte_spans.push((m.start(), m.end().min(visible_len)));
+		}
+		true
+	});
+	if byte_spans.is_empty() {
+		return Vec::new();
+	}
+
+	let to_utf16 = |byte_offset: usize| -> u32 {
+		let prefix = &line[..line.floor_char_boundary(byte_offset)];
+		crate::utils::clamp_u32(prefix.encode_utf16().count() as u64)
+	};
+	byte_spans
+		.into_iter()
+		.map(|(start, end)| MatchSpan { start: to_utf16(start), end: to_utf16(end) })
+		.collect()
+}
+
+/// Text from the start of the first match in `lines` to the end of the last.
+fn matched_text(matcher: &PatternMatcher, lines: &str) -> Option<String> {
+	let mut range: Option<(usize, usize)> = None;
+	let _ = matcher.find_iter(lines.as_bytes(), |m| {
+		let start = range.map_or_else(|| m.start(), |(start, _)| start);
+		range = Some((start, m.end()));
+		true
+	});
+	range.map(|(start, end)| lines[start..lines.floor_char_boundary(end)].to_string())
+}
+
+/// Columns where the matches in the first line of `line` start.
+fn collect_columns(matcher: &PatternMatcher, line: &str, tab_width: usize) -> Vec<MatchColumn> {
+	let mut columns = Vec::new();
+	let _ = matcher.find_iter(line.as_bytes(), |m| {
+		let prefix = &line[..line.floor_char_boundary(m.start())];
+		if prefix.contains('\n') {
+			return false;
 		}
+		let column = |width: usize| crate::utils::clamp_u32(width as u64 + 1);
+		columns.push(MatchColumn {
+			byte:    column(prefix.len()),
+			utf16:   column(prefix.encode_utf16().count()),
+			display: column(text::display_width(prefix, tab_width)),
+		});
+		true
+	});
+	columns
+}
+
+fn cancelled_error(err: Error) -> io::Error {
+	io::Error::new(io::ErrorKind::Interrupted, err.reason.clone())
+}
+
+/// Reader that honors cancellation before every read, so files with few
+/// matches (or multiline searches, which read the whole file first) still
+/// stop mid-file.
+struct CancelReader<'a, R> {
+	inner: R,
+	ct:    &'a task::CancelToken,
+}
+
+impl<R: Read> Read for CancelReader<'_, R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.ct.heartbeat().map_err(cancelled_error)?;
+		self.inner.read(buf)
 	}
 }
 
@@ -304,7 +815,7 @@ fn bytes_to_trimmed_string(bytes: &[u8]) -> String {
 	}
 }
 
-impl Sink for MatchCollector {
+impl Sink for MatchCollector<'_> {
 	type Error = io::Error;
 
 	fn matched(
@@ -312,6 +823,7 @@ impl Sink for MatchCollector {
 		_searcher: &Searcher,
 		mat: &SinkMatch<'_>,
 	) -> std::result::Result<bool, Self::Error> {
+		self.heartbeat()?;
 		self.match_count += 1;
 
 		// If we already hit the limit, stop now (after-context for previous match was
@@ -330,6 +842,28 @@ impl Sink for MatchCollector {
 			let raw_line = bytes_to_trimmed_string(mat.bytes());
 			let (line, truncated) = self.truncate_line(&raw_line);
 			let line_number = mat.line_number().unwrap_or(0);
+			let spans = self.span_matcher.map_or_else(Vec::new, |matcher| {
+				let visible_len = if truncated {
+					line.len() - 3
+				} else {
+					line.len()
+				};
+				collect_spans(matcher, &raw_line, visible_len)
+			});
+			let columns = self
+				.column_matcher
+				.map_or_else(Vec::new, |(matcher, tab_width)| {
+					collect_columns(matcher, &raw_line, tab_width)
+				});
+			let line_breaks = raw_line.matches('\n').count() as u64;
+			let (end_line_number, text) = if line_breaks > 0 {
+				let text = self
+					.span_matcher
+					.and_then(|matcher| matched_text(matcher, &raw_line));
+				(Some(line_number + line_breaks), text)
+			} else {
+				(None, None)
+			};
 
 			self.matches.push(CollectedMatch {
 				line_number,
@@ -337,6 +871,10 @@ impl Sink for MatchCollector {
 				context_before: std::mem::take(&mut self.context_before),
 				context_after: SmallVec::new(),
 				truncated,
+				spans,
+				columns,
+				end_line_number,
+				text,
 			});
 		} else {
 			self.context_before.clear();
@@ -359,6 +897,7 @@ impl Sink for MatchCollector {
 		_searcher: &Searcher,
 		ctx: &SinkContext<'_>,
 	) -> std::result::Result<bool, Self::Error> {
+		self.heartbeat()?;
 		if !self.collect_matches {
 			return Ok(true);
 		}
@@ -389,7 +928,7 @@ impl Sink for MatchCollector {
 
 fn parse_output_mode(mode: Option<&str>) -> OutputMode {
 	match mode {
-		Some("count" | "filesWithMatches") => OutputMode::Count,
+		Some("count" | "filesWithMatches" | "filesWithoutMatch" | "countByDir") => OutputMode::Count,
 		_ => OutputMode::Content,
 	}
 }
@@ -409,6 +948,12 @@ fn resolve_type_filter(type_name: Option<&str>) -> Option<TypeFilter> {
 		.map(str::trim)
 		.filter(|value| !value.is_empty())
 		.map(|value| value.trim_start_matches('.').to_lowercase())?;
+	let (negate, class_name) = normalized
+		.strip_prefix('!')
+		.map_or((false, normalized.as_str()), |rest| (true, rest));
+	if let Some(class) = PathClass::parse(class_name) {
+		return Some(TypeFilter::Class { class, negate });
+	}
 
 	let (exts, names): (&[&str], &[&str]) = match normalized.as_str() {
 		"js" | "javascript" => (&["js", "jsx", "mjs", "cjs"], &[]),
@@ -447,6 +992,9 @@ fn resolve_type_filter(type_name: Option<&str>) -> Option<TypeFilter> {
 }
 
 fn matches_type_filter(path: &Path, filter: &TypeFilter) -> bool {
+	if let TypeFilter::Class { class, negate } = filter {
+		return (classify::classify(path) == *class) != *negate;
+	}
 	let base_name = path
 		.file_name()
 		.and_then(|name| name.to_str())
@@ -474,38 +1022,72 @@ fn resolve_context(
 	}
 }
 
-fn build_searcher(before_context: u32, after_context: u32) -> Searcher {
+fn build_searcher(before_context: u32, after_context: u32, multiline: bool) -> Searcher {
 	SearcherBuilder::new()
 		.binary_detection(BinaryDetection::quit(b'\x00'))
 		.line_number(true)
+		.multi_line(multiline)
 		.before_context(before_context as usize)
 		.after_context(after_context as usize)
 		.build()
 }
 
+/// Where `maxColumns` truncates a line.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColumnLimit {
+	Bytes(usize),
+	/// Display cells, as measured by the text module.
+	Cells {
+		max:       usize,
+		tab_width: usize,
+	},
+}
+
+impl ColumnLimit {
+	fn new(
+		max_columns: Option<u32>,
+		by_width: Option<bool>,
+		tab_width: Option<u32>,
+	) -> Option<Self> {
+		let max = max_columns? as usize;
+		Some(if by_width.unwrap_or(false) {
+			Self::Cells { max, tab_width: text::clamp_tab_width(tab_width) }
+		} else {
+			Self::Bytes(max)
+		})
+	}
+}
+
 #[derive(Clone, Copy)]
 struct SearchParams {
 	context_before: u32,
 	context_after:  u32,
-	max_columns:    Option<u32>,
+	max_columns:    Option<ColumnLimit>,
 	mode:           OutputMode,
 	max_count:      Option<u64>,
 	offset:         u64,
+	/// Compute per-line match spans for collected matches.
+	spans:          bool,
+	/// Tab width for match columns of collected matches; `None` skips them.
+	columns:        Option<usize>,
+	/// Let matches span lines.
+	multiline:      bool,
 }
 
 fn run_search(
-	matcher: &grep_regex::RegexMatcher,
+	matcher: &PatternMatcher,
 	content: &[u8],
 	params: SearchParams,
 ) -> io::Result<SearchResultInternal> {
-	run_search_reader(matcher, Cursor::new(content), params)
+	run_search_reader(matcher, Cursor::new(content), params, &task::CancelToken::default())
 }
 
 /// Stream-based search that reads directly from a `Read` without buffering.
 fn run_search_reader<R: Read>(
-	matcher: &grep_regex::RegexMatcher,
+	matcher: &PatternMatcher,
 	reader: R,
 	params: SearchParams,
+	ct: &task::CancelToken,
 ) -> io::Result<SearchResultInternal> {
 	let mut searcher = build_searcher(
 		if params.mode == OutputMode::Content {
@@ -518,14 +1100,18 @@ fn run_search_reader<R: Read>(
 		} else {
 			0
 		},
+		params.multiline,
 	);
 	let mut collector = MatchCollector::new(
 		params.max_count,
 		params.offset,
-		params.max_columns.map(|v| v as usize),
+		params.max_columns,
 		params.mode == OutputMode::Content,
+		params.spans.then_some(matcher),
+		params.columns.map(|tab_width| (matcher, tab_width)),
+		ct,
 	);
-	searcher.search_reader(matcher, reader, &mut collector)?;
+	searcher.search_reader(matcher, CancelReader { inner: reader, ct }, &mut collector)?;
 	Ok(SearchResultInternal {
 		matches:       collector.matches,
 		match_count:   collector.match_count,
@@ -551,10 +1137,21 @@ fn to_public_match(matched: CollectedMatch) -> Match {
 		context_before,
 		context_after,
 		truncated: if matched.truncated { Some(true) } else { None },
+		spans: matched.spans,
+		end_line_number: matched.end_line_number.map(crate::utils::clamp_u32),
+		text: matched.text,
 	}
 }
 
-fn to_grep_match(path: &str, matched: CollectedMatch) -> GrepMatch {
+/// A file's path prefixed with its search root, when there is one.
+fn listed_path(root: Option<&str>, relative: &str) -> String {
+	match root {
+		Some(root) => format!("{}/{relative}", root.trim_end_matches('/')),
+		None => relative.to_string(),
+	}
+}
+
+fn to_grep_match(path: &str, root: Option<&str>, matched: CollectedMatch) -> GrepMatch {
 	let context_before = if matched.context_before.is_empty() {
 		None
 	} else {
@@ -567,41 +1164,235 @@ fn to_grep_match(path: &str, matched: CollectedMatch) -> GrepMatch {
 	};
 	GrepMatch {
 		path: path.to_string(),
+		root: root.map(str::to_string),
 		line_number: crate::utils::clamp_u32(matched.line_number),
 		line: matched.line,
 		context_before,
 		context_after,
 		truncated: if matched.truncated { Some(true) } else { None },
 		match_count: None,
+		blame: None,
+		columns: (!matched.columns.is_empty()).then_some(matched.columns),
+		spans: (!matched.spans.is_empty()).then_some(matched.spans),
 	}
 }
 
+/// Search one file's `content` as `grep()` does in content mode, keeping up to
+/// `max_count` matches under `path`; returns them with the file's total match
+/// count, or `None` when the search failed.
+pub(crate) fn search_file_bytes(
+	matcher: &PatternMatcher,
+	path: &str,
+	content: &[u8],
+	context: u32,
+	max_count: Option<u64>,
+	ct: &task::CancelToken,
+) -> Option<(Vec<GrepMatch>, u64)> {
+	let params = SearchParams {
+		context_before: context,
+		context_after: context,
+		max_columns: None,
+		mode: OutputMode::Content,
+		max_count,
+		offset: 0,
+		spans: false,
+		columns: None,
+		multiline: false,
+	};
+	let search = run_search_reader(matcher, content, params, ct).ok()?;
+	let matches = search
+		.matches
+		.into_iter()
+		.map(|matched| to_grep_match(path, None, matched))
+		.collect();
+	Some((matches, search.match_count))
+}
+
 const fn empty_search_result(error: Option<String>) -> SearchResult {
 	SearchResult { matches: Vec::new(), match_count: 0, limit_reached: false, error }
 }
 
 /// Internal configuration for grep, extracted from options.
 struct GrepConfig {
-	pattern:        String,
-	path:           String,
-	glob:           Option<String>,
-	type_filter:    Option<String>,
-	ignore_case:    Option<bool>,
-	multiline:      Option<bool>,
-	hidden:         Option<bool>,
-	gitignore:      Option<bool>,
-	cache:          Option<bool>,
-	max_count:      Option<u32>,
-	offset:         Option<u32>,
-	context_before: Option<u32>,
-	context_after:  Option<u32>,
-	context:        Option<u32>,
-	max_columns:    Option<u32>,
-	mode:           Option<String>,
+	pattern:             String,
+	path:                Either<String, Vec<String>>,
+	package:             Option<String>,
+	changed_only:        Option<String>,
+	glob:                Option<String>,
+	glob_case_sensitive: Option<bool>,
+	type_filter:         Option<String>,
+	ignore_case:         Option<bool>,
+	multiline:           Option<bool>,
+	engine:              Option<String>,
+	hidden:              Option<bool>,
+	gitignore:           Option<bool>,
+	ignore_sources:      Option<Vec<String>>,
+	ignore_files:        Option<Vec<String>>,
+	cache:               Option<bool>,
+	max_count:           Option<u32>,
+	offset:              Option<u32>,
+	cursor:              Option<String>,
+	natural_sort:        Option<bool>,
+	context_before:      Option<u32>,
+	context_after:       Option<u32>,
+	context:             Option<u32>,
+	max_columns:         Option<u32>,
+	truncate_by_width:   Option<bool>,
+	columns:             Option<bool>,
+	tab_width:           Option<u32>,
+	spans:               Option<bool>,
+	max_file_size:       Option<i64>,
+	max_total_bytes:     Option<i64>,
+	max_files:           Option<u32>,
+	mode:                Option<String>,
+	dir_depth:           Option<u32>,
+	blame:               Option<bool>,
+	heatmap:             Option<bool>,
+	heatmap_buckets:     Option<u32>,
+	blocks:              Option<bool>,
+	trace:               Option<bool>,
+	stream:              Option<stream::StreamOptions>,
+	/// Reuse per-file results across calls (`grepIncremental`).
+	session:             Option<Arc<Mutex<GrepSessionState>>>,
+}
+
+/// Resolve the per-file read cap; zero or negative disables it.
+pub(crate) const fn resolve_max_file_bytes(max_file_size: Option<i64>) -> u64 {
+	match max_file_size {
+		None => MAX_FILE_BYTES,
+		Some(size) if size <= 0 => u64::MAX,
+		Some(size) => size.unsigned_abs(),
+	}
+}
+
+/// Open `path` for reading at most `max_file_bytes`; the flag reports whether
+/// the file is larger than the cap.
+fn open_capped(path: &Path, max_file_bytes: u64) -> Option<(io::Take<File>, bool)> {
+	let file = File::open(path).ok()?;
+	let truncated = file
+		.metadata()
+		.is_ok_and(|metadata| metadata.len() > max_file_bytes);
+	Some((file.take(max_file_bytes), truncated))
+}
+
+/// Result entry for a file with no matches (filesWithoutMatch mode).
+fn without_match_entry(path: String, root: Option<&str>) -> GrepMatch {
+	GrepMatch {
+		path,
+		root: root.map(str::to_string),
+		line_number: 0,
+		line: String::new(),
+		context_before: None,
+		context_after: None,
+		truncated: None,
+		match_count: Some(0),
+		blame: None,
+		columns: None,
+		spans: None,
+	}
+}
+
+/// Result entry carrying a file's match count (count mode).
+fn count_entry(path: String, root: Option<&str>, match_count: u64) -> GrepMatch {
+	GrepMatch {
+		match_count: Some(crate::utils::clamp_u32(match_count)),
+		..without_match_entry(path, root)
+	}
+}
+
+/// A file's entries in the result of an unlimited search.
+fn file_grep_matches(result: &FileSearchResult, mode: OutputMode) -> Vec<GrepMatch> {
+	if result.match_count == 0 {
+		return Vec::new();
+	}
+	let root = result.root.as_deref();
+	match mode {
+		OutputMode::Content => result
+			.matches
+			.iter()
+			.map(|matched| to_grep_match(&result.relative_path, root, matched.clone()))
+			.collect(),
+		OutputMode::Count => {
+			vec![count_entry(result.relative_path.clone(), root, result.match_count)]
+		},
+	}
+}
+
+/// Streams the files without a match to `on_match` as the search reaches
+/// them, paged like [`files_without_match_result`].
+fn stream_without_match(
+	on_match: &stream::Batcher<GrepMatchBatch>,
+	offset: u64,
+	max_count: Option<u64>,
+) -> impl FnMut(GrepMatch) + Send + '_ {
+	let mut skip = offset;
+	let mut left = max_count.unwrap_or(u64::MAX);
+	move |entry| {
+		if skip > 0 {
+			skip -= 1;
+		} else if left > 0 {
+			left -= 1;
+			on_match.push(entry);
+		}
+	}
+}
+
+fn truncated_files(files: Vec<String>) -> Option<Vec<String>> {
+	if files.is_empty() { None } else { Some(files) }
+}
+
+/// Page the files without a match (filesWithoutMatch mode); `offset` and
+/// `maxCount` count files.
+fn files_without_match_result(
+	results: Vec<FileSearchResult>,
+	max_count: Option<u64>,
+	offset: u64,
+) -> GrepResult {
+	let files_searched = crate::utils::clamp_u32(results.len() as u64);
+	let mut total_matches = 0u64;
+	let mut files_with_matches = 0u32;
+	let mut truncated = Vec::new();
+	let mut without = Vec::new();
+	for result in results {
+		if result.truncated {
+			truncated.push(listed_path(result.root.as_deref(), &result.relative_path));
+		}
+		if result.match_count == 0 {
+			without.push((result.relative_path, result.root));
+		} else {
+			files_with_matches = files_with_matches.saturating_add(1);
+			total_matches = total_matches.saturating_add(result.match_count);
+		}
+	}
+
+	let max_files = max_count.map_or(usize::MAX, |max| max as usize);
+	let limit_reached = without.len().saturating_sub(offset as usize) > max_files;
+	let matches: Vec<GrepMatch> = without
+		.into_iter()
+		.skip(offset as usize)
+		.take(max_files)
+		.map(|(path, root)| without_match_entry(path, root.as_deref()))
+		.collect();
+
+	GrepResult {
+		matches,
+		total_matches: crate::utils::clamp_u32(total_matches),
+		files_with_matches,
+		files_searched,
+		limit_reached: limit_reached.then_some(true),
+		truncated_files: truncated_files(truncated),
+		directories: None,
+		reused_files: None,
+		heatmaps: None,
+		blocks: None,
+		cursor: None,
+		budget_exhausted: None,
+		trace: None,
+	}
 }
 
 fn collect_files(
-	root: &Path,
+	root: &GrepRoot,
 	scanned_entries: &[fs_cache::GlobMatch],
 	glob_set: Option<&GlobSet>,
 	type_filter: Option<&TypeFilter>,
@@ -616,13 +1407,17 @@ fn collect_files(
 		{
 			continue;
 		}
-		let path = root.join(&entry.path);
 		if let Some(filter) = type_filter
-			&& !matches_type_filter(&path, filter)
+			&& !matches_type_filter(Path::new(&entry.path), filter)
 		{
 			continue;
 		}
-		entries.push(FileEntry { path, relative_path: entry.path.clone() });
+		entries.push(FileEntry {
+			path:          root.path.join(&entry.path),
+			relative_path: entry.path.clone(),
+			root:          root.label.clone(),
+			size:          entry.size.map_or(0, |size| size as u64),
+		});
 	}
 	entries
 }
@@ -630,7 +1425,7 @@ fn collect_files(
 /// Check if `bytes[start]` (which must be `b'{'`) begins a valid repetition
 /// quantifier: `{N}`, `{N,}`, or `{N,M}` where N and M are decimal digits.
 /// Returns the byte index of the closing `}` if valid.
-fn find_valid_repetition(bytes: &[u8], start: usize) -> Option<usize> {
+const fn find_valid_repetition(bytes: &[u8], start: usize) -> Option<usize> {
 	let len = bytes.len();
 	let mut i = start + 1;
 	// Must start with at least one digit.
@@ -663,7 +1458,7 @@ fn find_valid_repetition(bytes: &[u8], start: usize) -> Option<usize> {
 	None
 }
 
-fn find_braced_escape_end(bytes: &[u8], start: usize) -> Option<usize> {
+const fn find_braced_escape_end(bytes: &[u8], start: usize) -> Option<usize> {
 	let mut i = start + 1;
 	while i < bytes.len() {
 		if bytes[i] == b'}' {
@@ -745,190 +1540,1644 @@ fn sanitize_braces(pattern: &str) -> Cow<'_, str> {
 
 #[cfg(test)]
 mod tests {
-	use super::sanitize_braces;
+	use std::{
+		fs, io,
+		path::Path,
+		sync::Arc,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use grep_matcher::Matcher;
+	use napi::Either;
+
+	use super::{
+		GrepConfig, GrepMatch, GrepSession, InOrder, MATCHER_CACHE, MatchColumn, OutputMode,
+		SearchDocument, SearchOptions, SearchParams, build_matcher, count_by_dir, grep_sync,
+		matches_type_filter, resolve_type_filter, run_search_reader, sanitize_braces,
+		search_documents, search_sync,
+	};
+	use crate::{regex_engine::RegexEngine, task};
+
+	fn grep_config(root: &Path, pattern: &str) -> GrepConfig {
+		GrepConfig {
+			pattern:             pattern.to_string(),
+			path:                Either::A(root.to_string_lossy().into_owned()),
+			package:             None,
+			changed_only:        None,
+			glob:                None,
+			glob_case_sensitive: None,
+			type_filter:         None,
+			ignore_case:         None,
+			multiline:           None,
+			engine:              None,
+			hidden:              None,
+			gitignore:           None,
+			ignore_sources:      None,
+			ignore_files:        None,
+			cache:               None,
+			max_count:           None,
+			offset:              None,
+			cursor:              None,
+			natural_sort:        None,
+			context_before:      None,
+			context_after:       None,
+			context:             None,
+			max_columns:         None,
+			truncate_by_width:   None,
+			columns:             None,
+			tab_width:           None,
+			spans:               None,
+			max_file_size:       None,
+			max_total_bytes:     None,
+			max_files:           None,
+			mode:                None,
+			dir_depth:           None,
+			blame:               None,
+			heatmap:             None,
+			heatmap_buckets:     None,
+			blocks:              None,
+			trace:               None,
+			stream:              None,
+			session:             None,
+		}
+	}
+
+	fn content_options(pattern: &str) -> SearchOptions {
+		SearchOptions {
+			pattern:           pattern.to_string(),
+			ignore_case:       None,
+			multiline:         None,
+			engine:            None,
+			max_count:         None,
+			offset:            None,
+			context_before:    None,
+			context_after:     None,
+			context:           None,
+			max_columns:       None,
+			truncate_by_width: None,
+			mode:              None,
+			line_start:        None,
+			line_end:          None,
+		}
+	}
 
 	#[test]
-	fn preserves_unicode_property_escapes() {
-		assert_eq!(sanitize_braces(r"\p{Greek}").as_ref(), r"\p{Greek}");
+	fn filters_by_path_class() {
+		let not_test = resolve_type_filter(Some("!test")).unwrap();
+		let test = resolve_type_filter(Some("test")).unwrap();
+		assert!(matches_type_filter(Path::new("src/lib.rs"), &not_test));
+		assert!(!matches_type_filter(Path::new("src/lib.test.ts"), &not_test));
+		assert!(matches_type_filter(Path::new("tests/api.rs"), &test));
+		assert!(matches_type_filter(Path::new("a.ts"), &resolve_type_filter(Some("ts")).unwrap()));
 	}
 
 	#[test]
-	fn preserves_hex_brace_escapes() {
-		assert_eq!(sanitize_braces(r"\x{41}").as_ref(), r"\x{41}");
+	fn searches_line_range_with_absolute_numbers() {
+		let content = b"foo 1\nbar\nfoo 3\nfoo 4\nfoo 5\n";
+		let options = SearchOptions {
+			line_start: Some(2),
+			line_end: Some(4),
+			context_before: Some(1),
+			..content_options("foo")
+		};
+		let result = search_sync(content, options);
+		let lines: Vec<_> = result.matches.iter().map(|m| m.line_number).collect();
+		assert_eq!(lines, [3, 4]);
+		let before = result.matches[0].context_before.as_ref().unwrap();
+		assert_eq!((before[0].line_number, before[0].line.as_str()), (2, "bar"));
+
+		let tail = SearchOptions { line_start: Some(5), ..content_options("foo") };
+		assert_eq!(search_sync(content, tail).matches[0].line_number, 5);
+		let past_end = SearchOptions { line_start: Some(9), ..content_options("foo") };
+		assert_eq!(search_sync(content, past_end).match_count, 0);
 	}
 
 	#[test]
-	fn preserves_malformed_braced_escapes() {
-		assert_eq!(sanitize_braces(r"\p{Greek").as_ref(), r"\p{Greek");
+	fn searches_documents_in_one_call() {
+		let documents = ["foo\nfoo\n", "bar\n", "a foo\n"]
+			.iter()
+			.enumerate()
+			.map(|(i, content)| SearchDocument {
+				id:      i.to_string(),
+				content: content.to_string(),
+			})
+			.collect();
+		let options =
+			SearchOptions { mode: Some("filesWithMatches".to_string()), ..content_options("foo") };
+		let result = search_documents(documents, options);
+		let counts: Vec<_> = result
+			.documents
+			.iter()
+			.map(|d| (d.id.as_str(), d.match_count, d.matches.len()))
+			.collect();
+		assert_eq!(counts, [("0", 2, 0), ("2", 1, 0)]);
+		assert_eq!(result.total_matches, 3);
+		assert!(
+			search_documents(Vec::new(), content_options("("))
+				.error
+				.is_some()
+		);
 	}
 
 	#[test]
-	fn escapes_non_quantifier_braces() {
-		assert_eq!(sanitize_braces("${platform}").as_ref(), "$\\{platform\\}");
+	fn reports_multiline_match_extent() {
+		let options = SearchOptions { multiline: Some(true), ..content_options(r"b\nc+") };
+		let result = search_sync(b"a\nb\ncc d\ne\n", options);
+		let matched = &result.matches[0];
+		assert_eq!((matched.line_number, matched.end_line_number), (2, Some(3)));
+		assert_eq!(matched.text.as_deref(), Some("b\ncc"));
+
+		let single = search_sync(b"a\nb\n", content_options("b"));
+		assert_eq!(
+			(single.matches[0].end_line_number, single.matches[0].text.as_deref()),
+			(None, None)
+		);
 	}
 
 	#[test]
-	fn preserves_valid_quantifiers() {
-		assert_eq!(sanitize_braces("a{2,4}").as_ref(), "a{2,4}");
+	fn reports_spans_in_utf16_units() {
+		let result = search_sync("héllo 🦀 foo foo\n".as_bytes(), content_options("foo"));
+		let spans: Vec<_> = result.matches[0]
+			.spans
+			.iter()
+			.map(|s| (s.start, s.end))
+			.collect();
+		assert_eq!(spans, vec![(9, 12), (13, 16)]);
 	}
-}
-fn build_matcher(
-	pattern: &str,
-	ignore_case: bool,
-	multiline: bool,
-) -> Result<grep_regex::RegexMatcher> {
-	let sanitized = sanitize_braces(pattern);
-	RegexMatcherBuilder::new()
-		.case_insensitive(ignore_case)
-		.multi_line(multiline)
-		.build(&sanitized)
-		.map_err(|err| Error::from_reason(format!("Regex error: {err}")))
-}
 
-fn run_parallel_search(
-	entries: &[FileEntry],
-	matcher: &grep_regex::RegexMatcher,
-	context_before: u32,
-	context_after: u32,
-	max_columns: Option<u32>,
-	mode: OutputMode,
-) -> Vec<FileSearchResult> {
-	let params =
-		SearchParams { context_before, context_after, max_columns, mode, max_count: None, offset: 0 };
-	let mut results: Vec<FileSearchResult> = entries
-		.par_iter()
-		.filter_map(|entry| {
-			let file = File::open(&entry.path).ok()?;
-			let reader = file.take(MAX_FILE_BYTES);
-			let search = run_search_reader(matcher, reader, params).ok()?;
-			Some(FileSearchResult {
-				relative_path: entry.relative_path.clone(),
-				matches:       search.matches,
-				match_count:   search.match_count,
-			})
-		})
-		.collect();
+	#[test]
+	fn clips_spans_to_truncated_line() {
+		let mut options = content_options("b+");
+		options.max_columns = Some(8);
+		let result = search_sync(b"aabbbbbbbbbb\n", options);
+		let spans: Vec<_> = result.matches[0]
+			.spans
+			.iter()
+			.map(|s| (s.start, s.end))
+			.collect();
+		assert_eq!(spans, vec![(2, 5)]);
+	}
 
-	results.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
-	results
-}
+	#[test]
+	fn truncates_lines_by_display_width() {
+		let options = SearchOptions {
+			max_columns: Some(8),
+			truncate_by_width: Some(true),
+			..content_options("x")
+		};
+		let result = search_sync("日本語のx文字列\n".as_bytes(), options);
+		assert_eq!(result.matches[0].line, "日本...");
+		assert_eq!(result.matches[0].truncated, Some(true));
 
-fn run_sequential_search(
-	entries: &[FileEntry],
-	matcher: &grep_regex::RegexMatcher,
-	params: SearchParams,
-) -> (Vec<GrepMatch>, u64, u32, u32, bool) {
-	let SearchParams { mode, max_count, offset, .. } = params;
-	let mut matches = Vec::new();
-	let mut total_matches = 0u64;
-	let mut collected = 0u64;
-	let mut files_with_matches = 0u32;
-	let mut files_searched = 0u32;
-	let mut limit_reached = false;
+		let bytes = SearchOptions { max_columns: Some(8), ..content_options("x") };
+		assert_eq!(search_sync("日本語のx文字列\n".as_bytes(), bytes).matches[0].line, "日...");
+	}
 
-	for entry in entries {
-		if limit_reached {
-			break;
-		}
+	#[test]
+	fn reports_files_truncated_by_max_file_size() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-max-size-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		fs::write(root.join("big.txt"), "head\npadding\ntail\n").expect("big.txt should be written");
+		fs::write(root.join("small.txt"), "tail\n").expect("small.txt should be written");
+
+		let run = |max_file_size: Option<i64>, mode: Option<&str>| {
+			let config = GrepConfig {
+				max_file_size,
+				mode: mode.map(str::to_string),
+				trace: Some(true),
+				..grep_config(&root, "tail")
+			};
+			grep_sync(config, None, task::CancelToken::default()).expect("grep should succeed")
+		};
 
-		// Calculate offset for this file (skip matches we've already seen)
-		let file_offset = offset.saturating_sub(total_matches);
-		// Calculate remaining based on collected count, not total matches
-		let remaining = max_count.map(|max| max.saturating_sub(collected));
-		if remaining == Some(0) {
-			limit_reached = true;
-			break;
-		}
+		let capped = run(Some(8), None);
+		assert_eq!(capped.total_matches, 1);
+		assert_eq!(capped.truncated_files, Some(vec!["big.txt".to_string()]));
+		let phases: Vec<_> = capped
+			.trace
+			.as_ref()
+			.expect("trace should be attached")
+			.children
+			.iter()
+			.map(|span| span.name.as_str())
+			.collect();
+		assert_eq!(phases, ["scan", "filter", "match", "serialize"]);
+
+		let uncapped = run(Some(0), None);
+		assert_eq!(uncapped.total_matches, 2);
+		assert_eq!(uncapped.truncated_files, None);
+
+		let without = run(Some(8), Some("filesWithoutMatch"));
+		let paths: Vec<_> = without.matches.iter().map(|m| m.path.as_str()).collect();
+		assert_eq!(paths, ["big.txt"]);
+		assert_eq!(without.files_with_matches, 1);
+
+		let _ = fs::remove_dir_all(&root);
+	}
 
-		// Open file and search directly - no intermediate buffer, no precheck scan
-		let Ok(file) = File::open(&entry.path) else {
-			continue;
+	#[test]
+	fn reports_match_heatmaps() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-heatmap-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		let lines: Vec<String> = (1..=8)
+			.map(|line| {
+				if line == 1 || line >= 7 {
+					"hit".to_string()
+				} else {
+					"miss".to_string()
+				}
+			})
+			.collect();
+		fs::write(root.join("a.txt"), lines.join("\n")).expect("a.txt should be written");
+		fs::write(root.join("b.txt"), "miss\n").expect("b.txt should be written");
+
+		let config = GrepConfig {
+			max_count: Some(1),
+			heatmap: Some(true),
+			heatmap_buckets: Some(4),
+			..grep_config(&root, "hit")
 		};
-		files_searched = files_searched.saturating_add(1);
-		let reader = file.take(MAX_FILE_BYTES);
+		let result =
+			grep_sync(config, None, task::CancelToken::default()).expect("grep should succeed");
+		assert_eq!(result.matches.len(), 1);
+		let heatmaps = result.heatmaps.expect("heatmaps should be attached");
+		assert_eq!(heatmaps.len(), 1);
+		assert_eq!(heatmaps[0].path, "a.txt");
+		assert_eq!((heatmaps[0].lines, heatmaps[0].matches), (8, 3));
+		assert_eq!(heatmaps[0].buckets, [1, 0, 0, 2]);
+
+		let _ = fs::remove_dir_all(&root);
+	}
 
-		let file_params = SearchParams { max_count: remaining, offset: file_offset, ..params };
-		let Ok(search) = run_search_reader(matcher, reader, file_params) else {
-			continue;
+	#[test]
+	fn cancels_mid_file() {
+		let scope = task::CancellationScope::new();
+		let ct = task::CancelToken::default().with_scope(Some(&scope));
+		let matcher = build_matcher("hit", RegexEngine::Default, false, false).unwrap();
+		let params = SearchParams {
+			context_before: 0,
+			context_after:  0,
+			max_columns:    None,
+			mode:           OutputMode::Count,
+			max_count:      None,
+			offset:         0,
+			spans:          false,
+			columns:        None,
+			multiline:      false,
 		};
+		let content = "hit\n".repeat(100_000);
 
-		if search.match_count == 0 {
-			continue;
+		let whole = run_search_reader(&matcher, content.as_bytes(), params, &ct).unwrap();
+		assert_eq!(whole.match_count, 100_000);
+
+		// Cancel once the first chunk has been read.
+		let reader = ReadHook { inner: content.as_bytes(), hook: || scope.cancel() };
+		let err = run_search_reader(&matcher, reader, params, &ct).err();
+		assert_eq!(err.map(|err| err.kind()), Some(io::ErrorKind::Interrupted));
+	}
+
+	struct ReadHook<R, F> {
+		inner: R,
+		hook:  F,
+	}
+
+	impl<R: io::Read, F: FnMut()> io::Read for ReadHook<R, F> {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			let read = self.inner.read(buf)?;
+			(self.hook)();
+			Ok(read)
 		}
+	}
 
-		files_with_matches = files_with_matches.saturating_add(1);
-		total_matches = total_matches.saturating_add(search.match_count);
-		collected = collected.saturating_add(search.collected);
+	#[test]
+	fn caches_compiled_matchers() {
+		let cached = |pattern: &str| {
+			MATCHER_CACHE
+				.lock()
+				.iter()
+				.any(|((key, ..), _)| key == pattern)
+		};
+		assert!(build_matcher("cache_me_{x}", RegexEngine::Default, true, false).is_ok());
+		assert!(cached("cache_me_{x}"));
+		let matcher = build_matcher("cache_me_{x}", RegexEngine::Default, true, false).unwrap();
+		assert!(matcher.is_match(b"CACHE_ME_{X}").unwrap());
 
-		match mode {
-			OutputMode::Content => {
-				for matched in search.matches {
-					matches.push(to_grep_match(&entry.relative_path, matched));
-				}
+		assert!(build_matcher("cache_me(", RegexEngine::Default, false, false).is_err());
+		assert!(!cached("cache_me("));
+	}
+
+	#[test]
+	fn pages_parallel_results_in_path_order() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-paging-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		for file in 0..40 {
+			fs::write(root.join(format!("f{file:02}.txt")), "hit 1\nmiss\nhit 2\nhit 3\n")
+				.expect("fixture should be written");
+		}
+
+		let page = grep_sync(
+			GrepConfig {
+				offset: Some(4),
+				max_count: Some(5),
+				context_after: Some(1),
+				..grep_config(&root, "hit")
 			},
-			OutputMode::Count => {
-				matches.push(GrepMatch {
-					path:           entry.relative_path.clone(),
-					line_number:    0,
-					line:           String::new(),
-					context_before: None,
-					context_after:  None,
-					truncated:      None,
-					match_count:    Some(crate::utils::clamp_u32(search.match_count)),
-				});
+			None,
+			task::CancelToken::default(),
+		)
+		.expect("grep should succeed");
+		let lines: Vec<_> = page
+			.matches
+			.iter()
+			.map(|m| format!("{}:{}", m.path, m.line_number))
+			.collect();
+		assert_eq!(lines, ["f01.txt:3", "f01.txt:4", "f02.txt:1", "f02.txt:3", "f02.txt:4"]);
+		assert_eq!(page.matches[2].context_after.as_ref().map(Vec::len), Some(1));
+		assert_eq!((page.total_matches, page.files_searched), (9, 3));
+		assert_eq!(page.limit_reached, Some(true));
+
+		let counts = grep_sync(
+			GrepConfig {
+				max_count: Some(2),
+				mode: Some("count".to_string()),
+				..grep_config(&root, "hit")
+			},
+			None,
+			task::CancelToken::default(),
+		)
+		.expect("grep should succeed");
+		assert_eq!(counts.matches.len(), 1);
+		assert_eq!((counts.matches[0].path.as_str(), counts.total_matches), ("f00.txt", 3));
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn searches_several_roots_once() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-roots-test-{unique}"));
+		for (path, content) in
+			[("ws/src/x.txt", "hit\n"), ("ws/vendor/v.txt", "hit\nhit\n"), ("other/o.txt", "hit\n")]
+		{
+			let path = root.join(path);
+			fs::create_dir_all(path.parent().unwrap()).expect("temp dir should be created");
+			fs::write(path, content).expect("fixture should be written");
+		}
+		let ws = root.join("ws").to_string_lossy().into_owned();
+		let other = root.join("other").to_string_lossy().into_owned();
+		let vendor = root.join("ws/vendor").to_string_lossy().into_owned();
+
+		let result = grep_sync(
+			GrepConfig {
+				path: Either::B(vec![ws.clone(), vendor, other.clone(), format!("{ws}/.")]),
+				..grep_config(&root, "hit")
 			},
+			None,
+			task::CancelToken::default(),
+		)
+		.expect("grep should succeed");
+		let found: Vec<_> = result
+			.matches
+			.iter()
+			.map(|m| (m.root.as_deref(), m.path.as_str(), m.line_number))
+			.collect();
+		assert_eq!(found, [
+			(Some(ws.as_str()), "src/x.txt", 1),
+			(Some(ws.as_str()), "vendor/v.txt", 1),
+			(Some(ws.as_str()), "vendor/v.txt", 2),
+			(Some(other.as_str()), "o.txt", 1),
+		]);
+		assert_eq!(result.files_searched, 3);
+
+		let by_dir = grep_sync(
+			GrepConfig {
+				path: Either::B(vec![ws.clone(), other.clone()]),
+				mode: Some("countByDir".to_string()),
+				..grep_config(&root, "hit")
+			},
+			None,
+			task::CancelToken::default(),
+		)
+		.expect("grep should succeed")
+		.directories
+		.expect("directories should be reported");
+		assert_eq!(by_dir[&format!("{ws}/vendor")].matches, 2);
+		assert_eq!(by_dir[&other].files, 1);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn reports_tab_aware_columns() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-columns-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		fs::write(root.join("a.txt"), "\té foo bar foo\n").expect("fixture should be written");
+
+		let result = grep_sync(
+			GrepConfig { columns: Some(true), tab_width: Some(4), ..grep_config(&root, "foo") },
+			None,
+			task::CancelToken::default(),
+		)
+		.expect("grep should succeed");
+		let columns = result.matches[0]
+			.columns
+			.as_deref()
+			.expect("columns should be reported");
+		assert_eq!(columns, [MatchColumn { byte: 5, utf16: 4, display: 7 }, MatchColumn {
+			byte:    13,
+			utf16:   12,
+			display: 15,
+		},]);
+
+		let plain = grep_sync(grep_config(&root, "foo"), None, task::CancelToken::default())
+			.expect("grep should succeed");
+		assert_eq!(plain.matches[0].columns, None);
+		assert!(plain.matches[0].spans.is_none());
+
+		let with_spans = grep_sync(
+			GrepConfig { spans: Some(true), ..grep_config(&root, "foo") },
+			None,
+			task::CancelToken::default(),
+		)
+		.expect("grep should succeed");
+		let spans: Vec<_> = with_spans.matches[0]
+			.spans
+			.iter()
+			.flatten()
+			.map(|span| (span.start, span.end))
+			.collect();
+		assert_eq!(spans, [(3, 6), (11, 14)]);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn reuses_unchanged_files_in_session() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-session-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		for (name, content) in [("a.txt", "hit\nhit\n"), ("b.txt", "miss\n"), ("c.txt", "hit\n")] {
+			fs::write(root.join(name), content).expect("fixture should be written");
 		}
 
-		if search.limit_reached || max_count.is_some_and(|max| collected >= max) {
+		let session = GrepSession::new();
+		let run = |offset: Option<u32>, max_count: Option<u32>| {
+			let config = GrepConfig {
+				offset,
+				max_count,
+				session: Some(Arc::clone(&session.state)),
+				..grep_config(&root, "hit")
+			};
+			grep_sync(config, None, task::CancelToken::default()).expect("grep should succeed")
+		};
+
+		let first = run(None, None);
+		assert_eq!((first.total_matches, first.reused_files), (3, Some(0)));
+
+		fs::write(root.join("c.txt"), "hit\nhit again\n").expect("c.txt should be rewritten");
+		fs::remove_file(root.join("b.txt")).expect("b.txt should be removed");
+		let page = run(Some(1), Some(2));
+		assert_eq!(page.reused_files, Some(1));
+		assert_eq!(page.total_matches, 4);
+		assert_eq!(page.limit_reached, Some(true));
+		let lines: Vec<_> = page
+			.matches
+			.iter()
+			.map(|m| format!("{}:{}", m.path, m.line_number))
+			.collect();
+		assert_eq!(lines, ["a.txt:2", "c.txt:1"]);
+		assert_eq!(session.file_count(), 2);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn pages_with_cursor() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-cursor-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		for (name, content) in [("a.txt", "hit\nhit\nhit\n"), ("b.txt", "miss\n"), ("c.txt", "hit\n")]
+		{
+			fs::write(root.join(name), content).expect("fixture should be written");
+		}
+
+		let mut cursor = None;
+		let mut pages = Vec::new();
+		loop {
+			let config =
+				GrepConfig { max_count: Some(2), cursor: cursor.take(), ..grep_config(&root, "hit") };
+			let page =
+				grep_sync(config, None, task::CancelToken::default()).expect("grep should succeed");
+			pages.push(
+				page
+					.matches
+					.iter()
+					.map(|m| format!("{}:{}", m.path, m.line_number))
+					.collect::<Vec<_>>(),
+			);
+			cursor = page.cursor;
+			if cursor.is_none() {
+				break;
+			}
+		}
+		assert_eq!(pages, [vec!["a.txt:1", "a.txt:2"], vec!["a.txt:3", "c.txt:1"]]);
+
+		let stale = GrepConfig {
+			cursor: Some(format!("0:1:{}", root.join("gone.txt").display())),
+			..grep_config(&root, "hit")
+		};
+		assert!(grep_sync(stale, None, task::CancelToken::default()).is_err());
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn pages_with_cursor_and_offset() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-cursor-offset-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		for (name, content) in
+			[("a.txt", "hit\nhit\nhit\n"), ("c.txt", "hit\n"), ("d.txt", "hit\nhit\n")]
+		{
+			fs::write(root.join(name), content).expect("fixture should be written");
+		}
+		// Every page resends the same options, offset included.
+		let pages_of = |path: &Path| {
+			let mut cursor = None;
+			let mut pages = Vec::new();
+			loop {
+				let config = GrepConfig {
+					max_count: Some(2),
+					offset: Some(1),
+					cursor: cursor.take(),
+					..grep_config(path, "hit")
+				};
+				let page =
+					grep_sync(config, None, task::CancelToken::default()).expect("grep should succeed");
+				pages.push(
+					page
+						.matches
+						.iter()
+						.map(|m| m.line_number)
+						.collect::<Vec<_>>(),
+				);
+				cursor = page.cursor;
+				if cursor.is_none() {
+					return pages;
+				}
+			}
+		};
+
+		assert_eq!(pages_of(&root), [vec![2, 3], vec![1, 1], vec![2]]);
+		let many = root.join("many.txt");
+		fs::write(&many, "hit\n".repeat(6)).expect("fixture should be written");
+		assert_eq!(pages_of(&many), [vec![2, 3], vec![4, 5], vec![6]]);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn stops_at_file_and_byte_budgets() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-budget-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		for (name, content) in [("a.txt", "hit\n"), ("b.txt", "hit hit hit\n"), ("c.txt", "hit\n")] {
+			fs::write(root.join(name), content).expect("fixture should be written");
+		}
+		let run = |max_files: Option<u32>, max_total_bytes: Option<i64>| {
+			let config = GrepConfig { max_total_bytes, max_files, ..grep_config(&root, "hit") };
+			grep_sync(config, None, task::CancelToken::default()).expect("grep should succeed")
+		};
+
+		let by_files = run(Some(1), None);
+		assert_eq!(
+			(by_files.files_searched, by_files.budget_exhausted.as_deref()),
+			(1, Some("maxFiles"))
+		);
+		let rest = GrepConfig { cursor: by_files.cursor, ..grep_config(&root, "hit") };
+		let rest = grep_sync(rest, None, task::CancelToken::default()).expect("grep should succeed");
+		let paths: Vec<_> = rest.matches.iter().map(|m| m.path.as_str()).collect();
+		assert_eq!(paths, ["b.txt", "c.txt"]);
+
+		let by_bytes = run(None, Some(10));
+		assert_eq!(by_bytes.files_searched, 1);
+		assert_eq!(by_bytes.budget_exhausted.as_deref(), Some("maxTotalBytes"));
+		let unbounded = run(Some(3), Some(100));
+		assert_eq!((unbounded.files_searched, unbounded.budget_exhausted), (3, None));
+		assert_eq!(unbounded.cursor, None);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn groups_matches_into_blocks() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-grep-blocks-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		fs::write(root.join("a.txt"), "a\nfoo\nb\nfoo\nc\nd\ne\nf\nfoo\n")
+			.expect("fixture should be written");
+
+		let config = GrepConfig { blocks: Some(true), context: Some(1), ..grep_config(&root, "foo") };
+		let result =
+			grep_sync(config, None, task::CancelToken::default()).expect("grep should succeed");
+		assert!(
+			result
+				.matches
+				.iter()
+				.all(|m| m.context_before.is_none() && m.context_after.is_none())
+		);
+		let blocks: Vec<_> = result
+			.blocks
+			.iter()
+			.flatten()
+			.map(|block| {
+				let lines: Vec<_> = block
+					.lines
+					.iter()
+					.map(|line| format!("{}{}", line.line_number, if line.is_match { "*" } else { "" }))
+					.collect();
+				(block.start_line, block.end_line, lines.join(" "))
+			})
+			.collect();
+		assert_eq!(blocks, [(1, 5, "1 2* 3 4* 5".to_string()), (8, 9, "8 9*".to_string())]);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn groups_counts_by_directory_depth() {
+		let count = |path: &str, match_count: u32| GrepMatch {
+			path:           path.to_string(),
+			root:           None,
+			line_number:    0,
+			line:           String::new(),
+			context_before: None,
+			context_after:  None,
+			truncated:      None,
+			match_count:    Some(match_count),
+			blame:          None,
+			columns:        None,
+			spans:          None,
+		};
+		let matches = [
+			count("src/a/x.rs", 2),
+			count("src/a/y.rs", 1),
+			count("src/b/z.rs", 4),
+			count("README", 1),
+		];
+
+		let top = count_by_dir(&matches, 1);
+		assert_eq!((top["src"].files, top["src"].matches), (3, 7));
+		assert_eq!((top["."].files, top["."].matches), (1, 1));
+
+		let nested = count_by_dir(&matches, 2);
+		assert_eq!((nested["src/a"].files, nested["src/a"].matches), (2, 3));
+		assert_eq!(nested["src/b"].matches, 4);
+	}
+
+	#[test]
+	fn preserves_unicode_property_escapes() {
+		assert_eq!(sanitize_braces(r"\p{Greek}").as_ref(), r"\p{Greek}");
+	}
+
+	#[test]
+	fn preserves_hex_brace_escapes() {
+		assert_eq!(sanitize_braces(r"\x{41}").as_ref(), r"\x{41}");
+	}
+
+	#[test]
+	fn preserves_malformed_braced_escapes() {
+		assert_eq!(sanitize_braces(r"\p{Greek").as_ref(), r"\p{Greek");
+	}
+
+	#[test]
+	fn escapes_non_quantifier_braces() {
+		assert_eq!(sanitize_braces("${platform}").as_ref(), "$\\{platform\\}");
+	}
+
+	#[test]
+	fn preserves_valid_quantifiers() {
+		assert_eq!(sanitize_braces("a{2,4}").as_ref(), "a{2,4}");
+	}
+
+	#[test]
+	fn hands_on_file_results_in_entry_order() {
+		let mut seen = Vec::new();
+		{
+			let in_order = InOrder::new(|value: usize| seen.push(value));
+			in_order.complete(2, Some(2));
+			in_order.complete(1, None);
+			in_order.complete(3, Some(3));
+			in_order.complete(0, Some(0));
+			in_order.complete(4, Some(4));
+		}
+		assert_eq!(seen, [0, 2, 3, 4]);
+	}
+}
+/// Pattern, engine, case-insensitivity, and multiline flag.
+type MatcherKey = (String, RegexEngine, bool, bool);
+
+const MATCHER_CACHE_SIZE: usize = 32;
+
+/// Most recently compiled matchers, newest first. Repeated calls with the
+/// same pattern (retries, pagination) skip regex compilation.
+static MATCHER_CACHE: LazyLock<Mutex<VecDeque<(MatcherKey, PatternMatcher)>>> =
+	LazyLock::new(|| Mutex::new(VecDeque::with_capacity(MATCHER_CACHE_SIZE)));
+
+/// Compile `pattern`, reusing a cached matcher for the same pattern and flags.
+/// Compile errors are not cached.
+pub(crate) fn build_matcher(
+	pattern: &str,
+	engine: RegexEngine,
+	ignore_case: bool,
+	multiline: bool,
+) -> Result<PatternMatcher> {
+	let key = (pattern.to_string(), engine, ignore_case, multiline);
+	let mut cache = MATCHER_CACHE.lock();
+	if let Some(pos) = cache.iter().position(|(cached, _)| *cached == key) {
+		let entry = cache.remove(pos).expect("position is in bounds");
+		let matcher = entry.1.clone();
+		cache.push_front(entry);
+		return Ok(matcher);
+	}
+	drop(cache);
+
+	let sanitized = sanitize_braces(pattern);
+	let matcher = PatternMatcher::build(&sanitized, engine, ignore_case, multiline)?;
+	let mut cache = MATCHER_CACHE.lock();
+	cache.truncate(MATCHER_CACHE_SIZE - 1);
+	cache.push_front((key, matcher.clone()));
+	Ok(matcher)
+}
+
+/// Number of leading `entries` that fit the file and byte budgets, and the
+/// budget that left the rest out.
+///
+/// Sizes count up to the per-file read cap, since no more is read.
+fn within_budget(
+	entries: &[FileEntry],
+	max_files: Option<u32>,
+	max_total_bytes: Option<i64>,
+	max_file_bytes: u64,
+) -> (usize, Option<&'static str>) {
+	let max_total_bytes = max_total_bytes.map(|max| max.max(0).unsigned_abs());
+	let mut total_bytes = 0u64;
+	for (idx, entry) in entries.iter().enumerate() {
+		if max_files.is_some_and(|max| idx >= max as usize) {
+			return (idx, Some("maxFiles"));
+		}
+		total_bytes = total_bytes.saturating_add(entry.size.min(max_file_bytes));
+		if max_total_bytes.is_some_and(|max| total_bytes > max) {
+			return (idx, Some("maxTotalBytes"));
+		}
+	}
+	(entries.len(), None)
+}
+
+/// Hands per-file values to `consume` in `entries` order while a parallel
+/// search is still running: each value waits only for the files before it.
+struct InOrder<T, F> {
+	state: Mutex<InOrderState<T, F>>,
+}
+
+struct InOrderState<T, F> {
+	next:    usize,
+	/// Finished files past `next`; `None` for files with nothing to hand on.
+	ready:   BTreeMap<usize, Option<T>>,
+	consume: F,
+}
+
+impl<T, F: FnMut(T)> InOrder<T, F> {
+	const fn new(consume: F) -> Self {
+		Self { state: Mutex::new(InOrderState { next: 0, ready: BTreeMap::new(), consume }) }
+	}
+
+	/// Record the value of file `idx` and hand on every value now in order.
+	fn complete(&self, idx: usize, value: Option<T>) {
+		let mut state = self.state.lock();
+		let state = &mut *state;
+		state.ready.insert(idx, value);
+		while let Some(value) = state.ready.remove(&state.next) {
+			state.next += 1;
+			if let Some(value) = value {
+				(state.consume)(value);
+			}
+		}
+	}
+}
+
+/// Search every file in parallel without a limit; `params.max_count` and
+/// `params.offset` are ignored. Results keep the order of `entries`.
+///
+/// `on_file` sees each file's result, `None` when it was skipped, as soon as
+/// the file is searched.
+fn run_parallel_search(
+	entries: &[FileEntry],
+	matcher: &PatternMatcher,
+	params: SearchParams,
+	max_file_bytes: u64,
+	ct: &task::CancelToken,
+	on_file: impl Fn(usize, Option<&FileSearchResult>) + Sync,
+) -> Vec<FileSearchResult> {
+	let params = SearchParams { max_count: None, offset: 0, ..params };
+	let results: Vec<FileSearchResult> = entries
+		.par_iter()
+		.enumerate()
+		.filter_map(|(idx, entry)| {
+			let result = (|| {
+				if ct.aborted() {
+					return None;
+				}
+				let (reader, truncated) = open_capped(&entry.path, max_file_bytes)?;
+				let search = run_search_reader(matcher, reader, params, ct).ok()?;
+				Some(FileSearchResult {
+					relative_path: entry.relative_path.clone(),
+					root: entry.root.clone(),
+					matches: search.matches,
+					match_count: search.match_count,
+					truncated,
+				})
+			})();
+			on_file(idx, result.as_ref());
+			result
+		})
+		.collect();
+	results
+}
+
+/// Parallel search honoring a global `offset`/`maxCount` over `entries` in
+/// their order.
+///
+/// Files are searched in parallel batches that grow geometrically, then folded
+/// in order exactly as a one-file-at-a-time search would see them, so results
+/// and counts do not depend on scheduling. No file needs more than the rest of
+/// the offset window plus the remaining limit, so each search is capped there;
+/// a file that fills that cap on its own cancels every later file in its batch.
+///
+/// The first `first_skip` matches of the first file were returned by an
+/// earlier page and are dropped before the offset applies. Kept matches go to
+/// `on_match` as each batch is folded.
+fn run_limited_search(
+	entries: &[FileEntry],
+	matcher: &PatternMatcher,
+	params: SearchParams,
+	max_file_bytes: u64,
+	first_skip: u64,
+	on_match: Option<&stream::Batcher<GrepMatchBatch>>,
+	ct: &task::CancelToken,
+) -> LimitedSearch {
+	let SearchParams { mode, max_count, offset, .. } = params;
+	let mut matches = Vec::new();
+	let mut total_matches = 0u64;
+	let mut collected = 0u64;
+	let mut files_with_matches = 0u32;
+	let mut files_searched = 0u32;
+	let mut limit_reached = false;
+	let mut truncated_files = Vec::new();
+	let mut resume = None;
+
+	let mut next = 0;
+	let mut batch_len = rayon::current_num_threads().max(1);
+	'batches: while next < entries.len() && !ct.aborted() {
+		let remaining = max_count.map(|max| max.saturating_sub(collected));
+		if remaining == Some(0) {
 			limit_reached = true;
+			break;
+		}
+		let cap =
+			remaining.map(|remaining| remaining.saturating_add(offset.saturating_sub(total_matches)));
+		let batch = &entries[next..(next + batch_len).min(entries.len())];
+		let stop_at = AtomicUsize::new(usize::MAX);
+		// `None`: unreadable, or cancelled by an earlier file that filled the cap.
+		let results: Vec<Option<(bool, Option<SearchResultInternal>)>> = batch
+			.par_iter()
+			.enumerate()
+			.map(|(idx, entry)| {
+				if idx > stop_at.load(Ordering::Relaxed) || ct.aborted() {
+					return None;
+				}
+				let (reader, truncated) = open_capped(&entry.path, max_file_bytes)?;
+				let cap = if next + idx == 0 {
+					cap.map(|cap| cap.saturating_add(first_skip))
+				} else {
+					cap
+				};
+				let file_params = SearchParams { max_count: cap, offset: 0, ..params };
+				let search = run_search_reader(matcher, reader, file_params, ct).ok();
+				if search.as_ref().is_some_and(|search| search.limit_reached) {
+					stop_at.fetch_min(idx, Ordering::Relaxed);
+				}
+				Some((truncated, search))
+			})
+			.collect();
+
+		for (idx, (entry, result)) in batch.iter().zip(results).enumerate() {
+			let Some((truncated, search)) = result else {
+				continue;
+			};
+			files_searched = files_searched.saturating_add(1);
+			if truncated {
+				truncated_files.push(entry.listed_path());
+			}
+			let Some(mut search) = search else {
+				continue;
+			};
+			let returned = if next + idx == 0 {
+				first_skip.min(search.collected)
+			} else {
+				0
+			};
+			search
+				.matches
+				.drain(..search.matches.len().min(returned as usize));
+			search.collected -= returned;
+			search.match_count -= returned;
+			if search.match_count == 0 {
+				continue;
+			}
+
+			// Replay this file's window: skip what the offset still covers, keep
+			// up to the remaining limit.
+			let skipped = offset.saturating_sub(total_matches).min(search.collected);
+			let remaining = max_count.map(|max| max.saturating_sub(collected));
+			let kept = (search.collected - skipped).min(remaining.unwrap_or(u64::MAX));
+			let file_limit_reached = remaining.is_some_and(|remaining| kept >= remaining);
+			// A limited search stops at the first match past the limit.
+			let match_count = if file_limit_reached {
+				search.match_count.min(skipped + kept + 1)
+			} else {
+				search.match_count
+			};
+
+			files_with_matches = files_with_matches.saturating_add(1);
+			total_matches = total_matches.saturating_add(match_count);
+			collected = collected.saturating_add(kept);
+
+			let first = matches.len();
+			match mode {
+				OutputMode::Content => {
+					for matched in search
+						.matches
+						.into_iter()
+						.skip(skipped as usize)
+						.take(kept as usize)
+					{
+						matches.push(to_grep_match(&entry.relative_path, entry.root.as_deref(), matched));
+					}
+				},
+				OutputMode::Count => {
+					matches.push(count_entry(
+						entry.relative_path.clone(),
+						entry.root.as_deref(),
+						match_count,
+					));
+				},
+			}
+			if let Some(callback) = on_match {
+				for grep_match in &matches[first..] {
+					callback.push(grep_match.clone());
+				}
+			}
+
+			if file_limit_reached {
+				limit_reached = true;
+				let file = next + idx;
+				resume = if search.match_count > skipped + kept {
+					Some(SearchCursor::new(file, returned + skipped + kept, &entry.path))
+				} else {
+					entries
+						.get(file + 1)
+						.map(|entry| SearchCursor::new(file + 1, 0, &entry.path))
+				};
+				break 'batches;
+			}
 		}
+
+		next += batch.len();
+		batch_len = (batch_len * 2).min(MAX_LIMITED_BATCH);
 	}
 
-	(matches, total_matches, files_with_matches, files_searched, limit_reached)
+	LimitedSearch {
+		matches,
+		total_matches,
+		files_with_matches,
+		files_searched,
+		limit_reached,
+		truncated_files,
+		resume,
+	}
+}
+
+/// Everything that changes a file's search result, besides its content.
+#[derive(Clone, PartialEq, Eq)]
+struct SessionQuery {
+	root:           PathBuf,
+	pattern:        String,
+	engine:         RegexEngine,
+	ignore_case:    bool,
+	multiline:      bool,
+	context_before: u32,
+	context_after:  u32,
+	max_columns:    Option<ColumnLimit>,
+	columns:        Option<usize>,
+	spans:          bool,
+	mode:           OutputMode,
+	max_file_bytes: u64,
+}
+
+/// Size and mtime used to decide whether a cached result is still valid.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+	size:  u64,
+	mtime: Option<std::time::SystemTime>,
+}
+
+impl FileStamp {
+	fn of(path: &Path) -> Option<Self> {
+		let metadata = std::fs::metadata(path).ok()?;
+		Some(Self { size: metadata.len(), mtime: metadata.modified().ok() })
+	}
+}
+
+/// Complete (unpaged) result for one file.
+struct CachedFile {
+	stamp:       FileStamp,
+	truncated:   bool,
+	match_count: u64,
+	/// Content-mode matches; empty in count modes.
+	matches:     Vec<GrepMatch>,
+}
+
+#[derive(Default)]
+struct GrepSessionState {
+	query: Option<SessionQuery>,
+	files: HashMap<String, Arc<CachedFile>>,
+}
+
+/// Per-file grep results remembered across `grepIncremental()` calls.
+#[napi]
+#[derive(Default)]
+pub struct GrepSession {
+	state: Arc<Mutex<GrepSessionState>>,
+}
+
+#[napi]
+impl GrepSession {
+	/// Create an empty session.
+	#[napi(constructor)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Number of files with remembered results.
+	#[napi(getter, js_name = "fileCount")]
+	pub fn file_count(&self) -> u32 {
+		crate::utils::clamp_u32(self.state.lock().files.len() as u64)
+	}
+
+	/// Forget all remembered results.
+	#[napi]
+	pub fn clear(&self) {
+		let mut state = self.state.lock();
+		state.query = None;
+		state.files.clear();
+	}
+}
+
+/// Search `entries` in full, reusing `session` results for unchanged files.
+///
+/// Returns results in `entries` order and the number of reused files. The
+/// session keeps results only for `entries`, so deleted files drop out.
+/// `on_file` sees each file's result, `None` when it was skipped, as soon as
+/// the file is searched or reused.
+fn run_incremental_search(
+	entries: &[FileEntry],
+	matcher: &PatternMatcher,
+	params: SearchParams,
+	query: SessionQuery,
+	session: &Mutex<GrepSessionState>,
+	ct: &task::CancelToken,
+	on_file: impl Fn(usize, Option<&(String, Arc<CachedFile>)>) + Sync,
+) -> (Vec<(String, Arc<CachedFile>)>, u32) {
+	let params = SearchParams { max_count: None, offset: 0, ..params };
+	let max_file_bytes = query.max_file_bytes;
+	let mut state = session.lock();
+	if state.query.as_ref() != Some(&query) {
+		state.files.clear();
+		state.query = Some(query);
+	}
+	let previous = std::mem::take(&mut state.files);
+	let reused = AtomicUsize::new(0);
+	let results: Vec<(String, Arc<CachedFile>)> = entries
+		.par_iter()
+		.enumerate()
+		.filter_map(|(idx, entry)| {
+			let result = (|| {
+				if ct.aborted() {
+					return None;
+				}
+				let stamp = FileStamp::of(&entry.path)?;
+				if let Some(cached) = previous.get(&entry.relative_path)
+					&& cached.stamp == stamp
+				{
+					reused.fetch_add(1, Ordering::Relaxed);
+					return Some((entry.relative_path.clone(), Arc::clone(cached)));
+				}
+				let (reader, truncated) = open_capped(&entry.path, max_file_bytes)?;
+				let search = run_search_reader(matcher, reader, params, ct).ok()?;
+				let matches = search
+					.matches
+					.into_iter()
+					.map(|matched| to_grep_match(&entry.relative_path, entry.root.as_deref(), matched))
+					.collect();
+				let cached = CachedFile { stamp, truncated, match_count: search.match_count, matches };
+				Some((entry.relative_path.clone(), Arc::new(cached)))
+			})();
+			on_file(idx, result.as_ref());
+			result
+		})
+		.collect();
+
+	state.files = results
+		.iter()
+		.map(|(path, cached)| (path.clone(), Arc::clone(cached)))
+		.collect();
+	if ct.aborted() {
+		// Keep results for files the cancelled call never reached.
+		for (path, cached) in previous {
+			state.files.entry(path).or_insert(cached);
+		}
+	}
+	(results, crate::utils::clamp_u32(reused.into_inner() as u64))
+}
+
+/// Pages complete per-file results with a global `offset`/`maxCount`, fed in
+/// file order while the search runs.
+struct IncrementalPager<'a> {
+	mode:      OutputMode,
+	max_count: Option<u64>,
+	offset:    u64,
+	collected: u64,
+	search:    LimitedSearch,
+	on_match:  Option<&'a stream::Batcher<GrepMatchBatch>>,
+}
+
+impl<'a> IncrementalPager<'a> {
+	const fn new(
+		mode: OutputMode,
+		max_count: Option<u64>,
+		offset: u64,
+		on_match: Option<&'a stream::Batcher<GrepMatchBatch>>,
+	) -> Self {
+		Self {
+			mode,
+			max_count,
+			offset,
+			collected: 0,
+			search: LimitedSearch {
+				matches:            Vec::new(),
+				total_matches:      0,
+				files_with_matches: 0,
+				files_searched:     0,
+				limit_reached:      false,
+				truncated_files:    Vec::new(),
+				resume:             None,
+			},
+			on_match,
+		}
+	}
+
+	/// Add the next file's result, streaming the entries it contributes.
+	fn add(&mut self, path: String, file: &CachedFile) {
+		let search = &mut self.search;
+		search.files_searched = search.files_searched.saturating_add(1);
+		if file.truncated {
+			search.truncated_files.push(path.clone());
+		}
+		if file.match_count == 0 {
+			return;
+		}
+		let skipped = self
+			.offset
+			.saturating_sub(search.total_matches)
+			.min(file.match_count);
+		search.files_with_matches = search.files_with_matches.saturating_add(1);
+		search.total_matches = search.total_matches.saturating_add(file.match_count);
+		if search.limit_reached {
+			return;
+		}
+
+		let remaining = self.max_count.map(|max| max.saturating_sub(self.collected));
+		let kept = (file.match_count - skipped).min(remaining.unwrap_or(u64::MAX));
+		self.collected = self.collected.saturating_add(kept);
+		search.limit_reached = remaining.is_some_and(|remaining| kept >= remaining);
+		let first = search.matches.len();
+		match self.mode {
+			OutputMode::Content => search.matches.extend(
+				file
+					.matches
+					.iter()
+					.skip(skipped as usize)
+					.take(kept as usize)
+					.cloned(),
+			),
+			OutputMode::Count => search
+				.matches
+				.push(count_entry(path, None, file.match_count)),
+		}
+		if let Some(callback) = self.on_match {
+			for grep_match in &search.matches[first..] {
+				callback.push(grep_match.clone());
+			}
+		}
+	}
+}
+
+/// The lines `start..=end` (1-based) of `content`, and how many lines precede
+/// them.
+fn line_range(content: &[u8], start: Option<u32>, end: Option<u32>) -> (&[u8], u64) {
+	let line_offset = |line: u32| {
+		if line <= 1 {
+			return 0;
+		}
+		content
+			.iter()
+			.enumerate()
+			.filter(|&(_, &byte)| byte == b'\n')
+			.nth(line as usize - 2)
+			.map_or(content.len(), |(idx, _)| idx + 1)
+	};
+	let first = start.unwrap_or(1).max(1);
+	let from = line_offset(first);
+	let to = end.map_or(content.len(), |end| line_offset(end.saturating_add(1)).max(from));
+	(&content[from..to], u64::from(first - 1))
 }
 
 fn search_sync(content: &[u8], options: SearchOptions) -> SearchResult {
+	match search_matcher(&options) {
+		Ok(matcher) => search_with(&matcher, content, &options),
+		Err(err) => empty_search_result(Some(err.to_string())),
+	}
+}
+
+fn search_matcher(options: &SearchOptions) -> Result<PatternMatcher> {
 	let ignore_case = options.ignore_case.unwrap_or(false);
 	let multiline = options.multiline.unwrap_or(false);
-	let mode = parse_output_mode(options.mode.as_deref());
-	let matcher = match build_matcher(&options.pattern, ignore_case, multiline) {
-		Ok(matcher) => matcher,
-		Err(err) => return empty_search_result(Some(err.to_string())),
-	};
+	RegexEngine::parse(options.engine.as_deref())
+		.and_then(|engine| build_matcher(&options.pattern, engine, ignore_case, multiline))
+}
 
+fn search_with(matcher: &PatternMatcher, content: &[u8], options: &SearchOptions) -> SearchResult {
+	let mode = parse_output_mode(options.mode.as_deref());
 	let (context_before, context_after) =
 		resolve_context(options.context, options.context_before, options.context_after);
-	let max_columns = options.max_columns;
+	let max_columns = ColumnLimit::new(options.max_columns, options.truncate_by_width, None);
 	let max_count = options.max_count.map(u64::from);
 	let offset = options.offset.unwrap_or(0) as u64;
-	let params =
-		SearchParams { context_before, context_after, max_columns, mode, max_count, offset };
+	let params = SearchParams {
+		context_before,
+		context_after,
+		max_columns,
+		mode,
+		max_count,
+		offset,
+		spans: true,
+		columns: None,
+		multiline: options.multiline.unwrap_or(false),
+	};
 
-	let result = match run_search(&matcher, content, params) {
+	let (content, lines_before) = line_range(content, options.line_start, options.line_end);
+	let result = match run_search(matcher, content, params) {
 		Ok(result) => result,
 		Err(err) => return empty_search_result(Some(err.to_string())),
 	};
+	let shift = |line_number: u32| {
+		crate::utils::clamp_u32(u64::from(line_number).saturating_add(lines_before))
+	};
 
 	SearchResult {
-		matches:       result.matches.into_iter().map(to_public_match).collect(),
+		matches:       result
+			.matches
+			.into_iter()
+			.map(to_public_match)
+			.map(|mut matched| {
+				matched.line_number = shift(matched.line_number);
+				matched.end_line_number = matched.end_line_number.map(shift);
+				for line in matched
+					.context_before
+					.iter_mut()
+					.chain(matched.context_after.iter_mut())
+					.flatten()
+				{
+					line.line_number = shift(line.line_number);
+				}
+				matched
+			})
+			.collect(),
 		match_count:   crate::utils::clamp_u32(result.match_count),
 		limit_reached: result.limit_reached,
 		error:         None,
 	}
 }
 
+/// Group consecutive matches of a file whose lines, context included, overlap
+/// or touch into blocks, moving the context out of the matches.
+fn group_blocks(matches: &mut [GrepMatch]) -> Vec<GrepBlock> {
+	let mut blocks: Vec<GrepBlock> = Vec::new();
+	for grep_match in matches {
+		let context_before = grep_match.context_before.take().unwrap_or_default();
+		let context_after = grep_match.context_after.take().unwrap_or_default();
+		// A multiline match spans one line per `\n`.
+		let match_lines = grep_match
+			.line
+			.split('\n')
+			.zip(grep_match.line_number..)
+			.map(|(line, line_number)| (line_number, line.to_string(), true));
+		let mut lines = context_before
+			.into_iter()
+			.map(|context| (context.line_number, context.line, false))
+			.chain(match_lines)
+			.chain(
+				context_after
+					.into_iter()
+					.map(|context| (context.line_number, context.line, false)),
+			)
+			.peekable();
+		let Some(&(first_line, ..)) = lines.peek() else {
+			continue;
+		};
+		let block = match blocks.last_mut() {
+			Some(block)
+				if block.path == grep_match.path
+					&& block.root == grep_match.root
+					&& first_line <= block.end_line.saturating_add(1) =>
+			{
+				block
+			},
+			_ => {
+				blocks.push(GrepBlock {
+					path:       grep_match.path.clone(),
+					root:       grep_match.root.clone(),
+					start_line: first_line,
+					end_line:   first_line,
+					lines:      Vec::new(),
+				});
+				blocks.last_mut().expect("block was just pushed")
+			},
+		};
+		for (line_number, line, is_match) in lines {
+			match block.lines.last_mut() {
+				Some(last) if last.line_number >= line_number => {
+					if last.line_number == line_number {
+						last.is_match |= is_match;
+					}
+				},
+				_ => block.lines.push(BlockLine { line_number, line, is_match }),
+			}
+			block.end_line = block.end_line.max(line_number);
+		}
+	}
+	blocks
+}
+
+/// Group per-file count entries by their first `depth` directory components.
+///
+/// Files directly under the search root (and single-file searches) are keyed
+/// as "."; with several roots, keys are prefixed with the root, which alone
+/// keys the files directly under it.
+fn count_by_dir(matches: &[GrepMatch], depth: usize) -> HashMap<String, DirectoryCount> {
+	let mut directories: HashMap<String, DirectoryCount> = HashMap::new();
+	for grep_match in matches {
+		let path = Path::new(&grep_match.path);
+		let dir = if path.is_absolute() {
+			String::from(".")
+		} else {
+			let components: Vec<_> = path
+				.parent()
+				.into_iter()
+				.flat_map(Path::components)
+				.take(depth)
+				.map(|component| component.as_os_str().to_string_lossy())
+				.collect();
+			if components.is_empty() {
+				String::from(".")
+			} else {
+				components.join("/")
+			}
+		};
+		let dir = match grep_match.root.as_deref() {
+			Some(root) if dir == "." => root.to_string(),
+			root => listed_path(root, &dir),
+		};
+		let entry = directories
+			.entry(dir)
+			.or_insert(DirectoryCount { files: 0, matches: 0 });
+		entry.files = entry.files.saturating_add(1);
+		entry.matches = entry
+			.matches
+			.saturating_add(grep_match.match_count.unwrap_or(0));
+	}
+	directories
+}
+
+/// Count matching lines of `absolute` into `buckets` equal slices of the
+/// file; `None` when the file cannot be read or the search was cancelled.
+fn file_heatmap(
+	path: &str,
+	root: Option<&str>,
+	absolute: &Path,
+	matcher: &PatternMatcher,
+	buckets: usize,
+	max_file_bytes: u64,
+	ct: &task::CancelToken,
+) -> Option<FileHeatmap> {
+	let (mut reader, _) = open_capped(absolute, max_file_bytes)?;
+	let mut content = Vec::new();
+	reader.read_to_end(&mut content).ok()?;
+	let lines = content.split_inclusive(|&byte| byte == b'\n').count();
+	let mut counts = vec![0u32; buckets];
+	let mut matches = 0u32;
+	let sink = grep_searcher::sinks::Bytes(|line_number, _| {
+		let line = usize::try_from(line_number.saturating_sub(1)).unwrap_or(usize::MAX);
+		let bucket = (line.saturating_mul(buckets) / lines.max(1)).min(buckets - 1);
+		counts[bucket] = counts[bucket].saturating_add(1);
+		matches = matches.saturating_add(1);
+		Ok(!ct.aborted())
+	});
+	build_searcher(0, 0, false)
+		.search_slice(matcher, &content, sink)
+		.ok()?;
+	(!ct.aborted()).then(|| FileHeatmap {
+		path: path.to_string(),
+		root: root.map(str::to_string),
+		lines: crate::utils::clamp_u32(lines as u64),
+		matches,
+		buckets: counts,
+	})
+}
+
+/// Resolve the files or directories `grep()` searches.
+///
+/// A single path is searched as given; several are canonicalized and
+/// deduplicated, and their results report which root they came from.
+fn resolve_grep_roots(options: &GrepConfig, ct: &task::CancelToken) -> Result<Vec<GrepRoot>> {
+	let paths = fs_cache::search_paths(options.path.clone())?;
+	if let [path] = paths.as_slice() {
+		let mut path = resolve_search_path(path)?;
+		if let Some(package) = options.package.as_deref() {
+			path = project::resolve_package_dir(&path, package, ct)?;
+		}
+		return Ok(vec![GrepRoot { path, label: None }]);
+	}
+	if options.package.is_some() {
+		return Err(Error::from_reason("`package` needs a single search path".to_string()));
+	}
+	if options.session.is_some() {
+		return Err(Error::from_reason("`grepIncremental()` needs a single search path".to_string()));
+	}
+	Ok(fs_cache::resolve_search_roots(paths, resolve_search_path)?
+		.into_iter()
+		.map(|root| GrepRoot { path: root.path, label: Some(root.label) })
+		.collect())
+}
+
 fn grep_sync(
 	options: GrepConfig,
-	on_match: Option<&ThreadsafeFunction<GrepMatch>>,
+	on_match: Option<&stream::Batcher<GrepMatchBatch>>,
 	ct: task::CancelToken,
 ) -> Result<GrepResult> {
-	let search_path = resolve_search_path(&options.path)?;
-	let metadata = std::fs::metadata(&search_path)
-		.map_err(|err| Error::from_reason(format!("Path not found: {err}")))?;
+	let trace = CallTrace::start("grep", options.trace.unwrap_or(false));
+	let dir_depth = (options.mode.as_deref() == Some("countByDir"))
+		.then(|| options.dir_depth.unwrap_or(1).max(1) as usize);
+	let on_match = if dir_depth.is_some() { None } else { on_match };
+	let roots = resolve_grep_roots(&options, &ct)?;
+	let blame = options.blame.unwrap_or(false)
+		&& matches!(parse_output_mode(options.mode.as_deref()), OutputMode::Content);
+	let blocks = options.blocks.unwrap_or(false)
+		&& matches!(parse_output_mode(options.mode.as_deref()), OutputMode::Content);
+	let heatmap = (options.heatmap.unwrap_or(false)
+		&& options.mode.as_deref() != Some("filesWithoutMatch"))
+	.then(|| -> Result<_> {
+		let engine = RegexEngine::parse(options.engine.as_deref())?;
+		let matcher = build_matcher(
+			&options.pattern,
+			engine,
+			options.ignore_case.unwrap_or(false),
+			options.multiline.unwrap_or(false),
+		)?;
+		let buckets = options
+			.heatmap_buckets
+			.unwrap_or(DEFAULT_HEATMAP_BUCKETS)
+			.clamp(1, MAX_HEATMAP_BUCKETS);
+		Ok((matcher, buckets as usize, resolve_max_file_bytes(options.max_file_size)))
+	})
+	.transpose()?;
+	let mut result = grep_files(options, &roots, on_match, trace.as_ref(), &ct)?;
+	// Results name their root only with several roots.
+	let absolute = |path: &str, root: Option<&str>| {
+		let root = roots
+			.iter()
+			.find(|candidate| candidate.label.as_deref() == root)
+			.unwrap_or(&roots[0]);
+		root.path.join(path)
+	};
+	if blame && !result.matches.is_empty() {
+		let _span = prof::trace_span(trace.as_ref(), "blame");
+		for root in &roots {
+			git::work_tree_root(&root.path, &ct)?;
+		}
+		git::annotate_blame(
+			&mut result.matches,
+			|grep_match| absolute(&grep_match.path, grep_match.root.as_deref()),
+			|grep_match| grep_match.line_number,
+			|grep_match, info| grep_match.blame = Some(info),
+			&ct,
+		)?;
+	}
+	if let Some((matcher, buckets, max_file_bytes)) = heatmap {
+		let _span = prof::trace_span(trace.as_ref(), "heatmap");
+		let mut paths: Vec<(&str, Option<&str>)> = result
+			.matches
+			.iter()
+			.map(|grep_match| (grep_match.path.as_str(), grep_match.root.as_deref()))
+			.collect();
+		paths.dedup();
+		let heatmaps = paths
+			.par_iter()
+			.filter_map(|&(path, root)| {
+				let absolute = absolute(path, root);
+				file_heatmap(path, root, &absolute, &matcher, buckets, max_file_bytes, &ct)
+			})
+			.collect();
+		ct.heartbeat()?;
+		result.heatmaps = Some(heatmaps);
+	}
+	if blocks {
+		result.blocks = Some(group_blocks(&mut result.matches));
+	}
+	if let Some(depth) = dir_depth {
+		let _span = prof::trace_span(trace.as_ref(), "serialize");
+		result.directories = Some(count_by_dir(&result.matches, depth));
+		result.matches.clear();
+	}
+	result.trace = trace.map(CallTrace::finish);
+	Ok(result)
+}
+
+fn grep_files(
+	options: GrepConfig,
+	roots: &[GrepRoot],
+	on_match: Option<&stream::Batcher<GrepMatchBatch>>,
+	trace: Option<&CallTrace>,
+	ct: &task::CancelToken,
+) -> Result<GrepResult> {
+	let metadata = roots
+		.iter()
+		.map(|root| {
+			std::fs::metadata(&root.path)
+				.map_err(|err| Error::from_reason(format!("Path not found: {err}")))
+		})
+		.collect::<Result<Vec<_>>>()?;
 	let ignore_case = options.ignore_case.unwrap_or(false);
 	let multiline = options.multiline.unwrap_or(false);
 	let output_mode = parse_output_mode(options.mode.as_deref());
-	let matcher = build_matcher(&options.pattern, ignore_case, multiline)?;
+	let files_without_match = options.mode.as_deref() == Some("filesWithoutMatch");
+	let engine = RegexEngine::parse(options.engine.as_deref())?;
+	let matcher = build_matcher(&options.pattern, engine, ignore_case, multiline)?;
 
 	let (context_before, context_after) =
 		resolve_context(options.context, options.context_before, options.context_after);
@@ -937,18 +3186,66 @@ fn grep_sync(
 	} else {
 		(0, 0)
 	};
-	let max_columns = options.max_columns;
+	let max_columns =
+		ColumnLimit::new(options.max_columns, options.truncate_by_width, options.tab_width);
+	let columns = (options.columns.unwrap_or(false) && output_mode == OutputMode::Content)
+		.then(|| text::clamp_tab_width(options.tab_width));
+	let spans = options.spans.unwrap_or(false) && output_mode == OutputMode::Content;
 	let max_count = options.max_count.map(u64::from);
-	let offset = options.offset.unwrap_or(0) as u64;
-	let include_hidden = options.hidden.unwrap_or(true);
-	let use_gitignore = options.gitignore.unwrap_or(true);
+	let cursor = options
+		.cursor
+		.as_deref()
+		.map(SearchCursor::parse)
+		.transpose()?;
+	// A cursor's position already includes the offset of the first page.
+	let offset = if cursor.is_some() {
+		0
+	} else {
+		u64::from(options.offset.unwrap_or(0))
+	};
+	if cursor.is_some() && (files_without_match || options.session.is_some()) {
+		return Err(Error::from_reason(
+			"`cursor` is not supported with `filesWithoutMatch` or a `GrepSession`".to_string(),
+		));
+	}
+	let max_file_bytes = resolve_max_file_bytes(options.max_file_size);
+	let scan_policy =
+		fs_cache::ScanPolicy::new(options.hidden.unwrap_or(true), options.gitignore.unwrap_or(true))
+			.with_ignore_sources(options.ignore_sources)?
+			.with_ignore_files(options.ignore_files);
 	let use_cache = options.cache.unwrap_or(false);
-	let glob_set = glob_util::try_compile_glob(options.glob.as_deref(), true)?;
+	let glob_set =
+		glob_util::try_compile_glob(options.glob.as_deref(), true, options.glob_case_sensitive)?;
 	let type_filter = resolve_type_filter(options.type_filter.as_deref());
+	let changed_scope = options
+		.changed_only
+		.as_deref()
+		.map(git::ChangedScope::parse)
+		.transpose()?;
+	let changed_in = |root: &GrepRoot, is_file: bool| -> Result<Option<ChangedSet>> {
+		let Some(scope) = &changed_scope else {
+			return Ok(None);
+		};
+		let _span = prof::trace_span(trace, "git");
+		let dir = if is_file {
+			root.path.parent().unwrap_or(&root.path)
+		} else {
+			&root.path
+		};
+		Ok(Some(ChangedSet {
+			root:  std::fs::canonicalize(&root.path).unwrap_or_else(|_| root.path.clone()),
+			files: git::changed_files(dir, scope, ct)?,
+		}))
+	};
 
-	if metadata.is_file() {
-		if let Some(filter) = type_filter.as_ref()
-			&& !matches_type_filter(&search_path, filter)
+	if let ([root], [metadata]) = (roots, metadata.as_slice())
+		&& metadata.is_file()
+	{
+		let search_path = root.path.as_path();
+		if type_filter
+			.as_ref()
+			.is_some_and(|filter| !matches_type_filter(search_path, filter))
+			|| changed_in(root, true)?.is_some_and(|changed| !changed.contains(""))
 		{
 			return Ok(GrepResult {
 				matches:            Vec::new(),
@@ -956,20 +3253,36 @@ fn grep_sync(
 				files_with_matches: 0,
 				files_searched:     0,
 				limit_reached:      None,
+				truncated_files:    None,
+				directories:        None,
+				reused_files:       None,
+				heatmaps:           None,
+				blocks:             None,
+				cursor:             None,
+				budget_exhausted:   None,
+				trace:              None,
 			});
 		}
 
-		let Ok(file) = File::open(&search_path) else {
+		let Some((reader, truncated)) = open_capped(search_path, max_file_bytes) else {
 			return Ok(GrepResult {
 				matches:            Vec::new(),
 				total_matches:      0,
 				files_with_matches: 0,
 				files_searched:     0,
 				limit_reached:      None,
+				truncated_files:    None,
+				directories:        None,
+				reused_files:       None,
+				heatmaps:           None,
+				blocks:             None,
+				cursor:             None,
+				budget_exhausted:   None,
+				trace:              None,
 			});
 		};
-		let reader = file.take(MAX_FILE_BYTES);
 
+		let offset = offset + cursor.as_ref().map_or(0, |cursor| cursor.skip);
 		let params = SearchParams {
 			context_before,
 			context_after,
@@ -977,43 +3290,101 @@ fn grep_sync(
 			mode: output_mode,
 			max_count,
 			offset,
+			spans,
+			columns,
+			multiline,
+		};
+		let search = {
+			let _span = prof::trace_span(trace, "match");
+			match run_search_reader(&matcher, reader, params, ct) {
+				Ok(search) => search,
+				Err(err) => {
+					ct.heartbeat()?;
+					return Err(Error::from_reason(format!("Search failed: {err}")));
+				},
+			}
 		};
-		let search = run_search_reader(&matcher, reader, params)
-			.map_err(|err| Error::from_reason(format!("Search failed: {err}")))?;
 
+		let path_string = search_path.to_string_lossy().into_owned();
+		let truncated_files = truncated_files(if truncated {
+			vec![path_string.clone()]
+		} else {
+			Vec::new()
+		});
+		if files_without_match {
+			let matches = if search.match_count == 0 {
+				vec![without_match_entry(path_string, None)]
+			} else {
+				Vec::new()
+			};
+			if let Some(callback) = on_match {
+				for grep_match in &matches {
+					callback.push(grep_match.clone());
+				}
+			}
+			return Ok(GrepResult {
+				matches,
+				total_matches: crate::utils::clamp_u32(search.match_count),
+				files_with_matches: u32::from(search.match_count > 0),
+				files_searched: 1,
+				limit_reached: None,
+				truncated_files,
+				directories: None,
+				reused_files: None,
+				heatmaps: None,
+				blocks: None,
+				cursor: None,
+				budget_exhausted: None,
+				trace: None,
+			});
+		}
 		if search.match_count == 0 {
 			return Ok(GrepResult {
-				matches:            Vec::new(),
-				total_matches:      0,
+				matches: Vec::new(),
+				total_matches: 0,
 				files_with_matches: 0,
-				files_searched:     1,
-				limit_reached:      None,
+				files_searched: 1,
+				limit_reached: None,
+				truncated_files,
+				directories: None,
+				reused_files: None,
+				heatmaps: None,
+				blocks: None,
+				cursor: None,
+				budget_exhausted: None,
+				trace: None,
 			});
 		}
 
-		let path_string = search_path.to_string_lossy().into_owned();
 		let mut matches = Vec::new();
 		match output_mode {
 			OutputMode::Content => {
 				for matched in search.matches {
-					matches.push(to_grep_match(&path_string, matched));
+					matches.push(to_grep_match(&path_string, None, matched));
 				}
 			},
 			OutputMode::Count => {
 				matches.push(GrepMatch {
 					path:           path_string,
+					root:           None,
 					line_number:    0,
 					line:           String::new(),
 					context_before: None,
 					context_after:  None,
 					truncated:      None,
 					match_count:    Some(crate::utils::clamp_u32(search.match_count)),
+					blame:          None,
+					columns:        None,
+					spans:          None,
 				});
 			},
 		}
 
 		let limit_reached =
 			search.limit_reached || max_count.is_some_and(|max| search.collected >= max);
+		let returned = offset + search.collected;
+		let cursor = (limit_reached && search.match_count > returned)
+			.then(|| SearchCursor::new(0, returned, search_path).encode());
 
 		return Ok(GrepResult {
 			matches,
@@ -1021,23 +3392,82 @@ fn grep_sync(
 			files_with_matches: 1,
 			files_searched: 1,
 			limit_reached: if limit_reached { Some(true) } else { None },
+			truncated_files,
+			directories: None,
+			reused_files: None,
+			heatmaps: None,
+			blocks: None,
+			cursor,
+			budget_exhausted: None,
+			trace: None,
 		});
 	}
 
-	let entries = if use_cache {
-		let scan = fs_cache::get_or_scan(&search_path, include_hidden, use_gitignore, &ct)?;
-		let mut entries =
-			collect_files(&search_path, &scan.entries, glob_set.as_ref(), type_filter.as_ref());
-		if entries.is_empty() && scan.cache_age_ms >= fs_cache::empty_recheck_ms() {
-			let fresh =
-				fs_cache::force_rescan(&search_path, include_hidden, use_gitignore, true, &ct)?;
-			entries = collect_files(&search_path, &fresh, glob_set.as_ref(), type_filter.as_ref());
+	let mut entries = Vec::new();
+	// Files reachable from several roots are searched under the first.
+	let mut seen = (roots.len() > 1).then(HashSet::new);
+	for (root, metadata) in roots.iter().zip(&metadata) {
+		let changed = changed_in(root, metadata.is_file())?;
+		if metadata.is_file() {
+			// Only with several roots: a lone file root was searched above.
+			let is_match = type_filter
+				.as_ref()
+				.is_none_or(|filter| matches_type_filter(&root.path, filter))
+				&& changed.is_none_or(|changed| changed.contains(""));
+			if is_match
+				&& seen
+					.as_mut()
+					.is_none_or(|seen| seen.insert(root.path.clone()))
+			{
+				entries.push(FileEntry {
+					path:          root.path.clone(),
+					relative_path: root.path.to_string_lossy().into_owned(),
+					root:          None,
+					size:          metadata.len(),
+				});
+			}
+			continue;
 		}
-		entries
-	} else {
-		let fresh = fs_cache::force_rescan(&search_path, include_hidden, use_gitignore, false, &ct)?;
-		collect_files(&search_path, &fresh, glob_set.as_ref(), type_filter.as_ref())
-	};
+		let filter = |scanned: &[fs_cache::GlobMatch]| {
+			let _span = prof::trace_span(trace, "filter");
+			let mut files = collect_files(root, scanned, glob_set.as_ref(), type_filter.as_ref());
+			if let Some(changed) = &changed {
+				files.retain(|file| changed.contains(&file.relative_path));
+			}
+			files
+		};
+		let mut files = if use_cache {
+			let scan = {
+				let _span = prof::trace_span(trace, "scan");
+				fs_cache::get_or_scan(&root.path, &scan_policy, ct)?
+			};
+			let mut files = filter(&scan.entries);
+			if files.is_empty() && scan.cache_age_ms >= fs_cache::empty_recheck_ms() {
+				let fresh = {
+					let _span = prof::trace_span(trace, "scan");
+					fs_cache::force_rescan(&root.path, &scan_policy, true, ct)?
+				};
+				files = filter(&fresh);
+			}
+			files
+		} else {
+			let fresh = {
+				let _span = prof::trace_span(trace, "scan");
+				fs_cache::force_rescan(&root.path, &scan_policy, false, ct)?
+			};
+			filter(&fresh)
+		};
+		// Every search below keeps this order.
+		if options.natural_sort.unwrap_or(false) {
+			files.sort_by(|a, b| sort::natural_compare(&a.relative_path, &b.relative_path));
+		} else {
+			files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+		}
+		if let Some(seen) = seen.as_mut() {
+			files.retain(|file| seen.insert(file.path.clone()));
+		}
+		entries.append(&mut files);
+	}
 	// Check cancellation before heavy work
 	ct.heartbeat()?;
 	if entries.is_empty() {
@@ -1047,25 +3477,195 @@ fn grep_sync(
 			files_with_matches: 0,
 			files_searched:     0,
 			limit_reached:      None,
+			truncated_files:    None,
+			directories:        None,
+			reused_files:       None,
+			heatmaps:           None,
+			blocks:             None,
+			cursor:             None,
+			budget_exhausted:   None,
+			trace:              None,
 		});
 	}
+	// Earlier pages covered the files before the cursor.
+	let start = cursor
+		.as_ref()
+		.map(|cursor| cursor.position(&entries))
+		.transpose()?
+		.unwrap_or(0);
+	let first_skip = cursor.as_ref().map_or(0, |cursor| cursor.skip);
+	let (searched, budget_exhausted) =
+		within_budget(&entries[start..], options.max_files, options.max_total_bytes, max_file_bytes);
+	let end = start + searched;
+	// Where the next page starts when a budget left files unsearched.
+	let past_budget = entries
+		.get(end)
+		.map(|entry| SearchCursor::new(end, 0, &entry.path));
+	let budget_exhausted = budget_exhausted.map(str::to_string);
+	let entries = &entries[start..end];
+	if let Some(session) = options.session.as_deref() {
+		let match_span = prof::trace_span(trace, "match");
+		let mode = if files_without_match {
+			OutputMode::Count
+		} else {
+			output_mode
+		};
+		let params = SearchParams {
+			context_before,
+			context_after,
+			max_columns,
+			mode,
+			max_count: None,
+			offset: 0,
+			spans,
+			columns,
+			multiline,
+		};
+		let query = SessionQuery {
+			root: roots[0].path.clone(),
+			pattern: options.pattern.clone(),
+			engine,
+			ignore_case,
+			multiline,
+			context_before,
+			context_after,
+			max_columns,
+			columns,
+			spans,
+			mode,
+			max_file_bytes,
+		};
+		let mut pager = IncrementalPager::new(output_mode, max_count, offset, on_match);
+		let (results, reused) = if files_without_match {
+			let streamed = on_match
+				.map(|callback| InOrder::new(stream_without_match(callback, offset, max_count)));
+			run_incremental_search(entries, &matcher, params, query, session, ct, |idx, result| {
+				if let Some(streamed) = &streamed {
+					let entry = result
+						.filter(|(_, file)| file.match_count == 0)
+						.map(|(path, _)| without_match_entry(path.clone(), None));
+					streamed.complete(idx, entry);
+				}
+			})
+		} else {
+			let paged = InOrder::new(|(path, file): (String, Arc<CachedFile>)| pager.add(path, &file));
+			run_incremental_search(entries, &matcher, params, query, session, ct, |idx, result| {
+				paged.complete(idx, result.cloned());
+			})
+		};
+		drop(match_span);
+		ct.heartbeat()?;
+		let _span = prof::trace_span(trace, "serialize");
+
+		let mut result = if files_without_match {
+			let results = results
+				.into_iter()
+				.map(|(relative_path, file)| FileSearchResult {
+					relative_path,
+					root: None,
+					matches: Vec::new(),
+					match_count: file.match_count,
+					truncated: file.truncated,
+				})
+				.collect();
+			files_without_match_result(results, max_count, offset)
+		} else {
+			let search = pager.search;
+			GrepResult {
+				matches:            search.matches,
+				total_matches:      crate::utils::clamp_u32(search.total_matches),
+				files_with_matches: search.files_with_matches,
+				files_searched:     search.files_searched,
+				limit_reached:      search.limit_reached.then_some(true),
+				truncated_files:    truncated_files(search.truncated_files),
+				directories:        None,
+				reused_files:       None,
+				heatmaps:           None,
+				blocks:             None,
+				cursor:             None,
+				budget_exhausted:   None,
+				trace:              None,
+			}
+		};
+		result.reused_files = Some(reused);
+		result.budget_exhausted = budget_exhausted;
+		return Ok(result);
+	}
+
+	if files_without_match {
+		let match_span = prof::trace_span(trace, "match");
+		let params = SearchParams {
+			context_before: 0,
+			context_after: 0,
+			max_columns: None,
+			mode: OutputMode::Count,
+			max_count: None,
+			offset: 0,
+			spans: false,
+			columns: None,
+			multiline,
+		};
+		let streamed =
+			on_match.map(|callback| InOrder::new(stream_without_match(callback, offset, max_count)));
+		let results =
+			run_parallel_search(entries, &matcher, params, max_file_bytes, ct, |idx, result| {
+				if let Some(streamed) = &streamed {
+					let entry = result
+						.filter(|result| result.match_count == 0)
+						.map(|result| {
+							without_match_entry(result.relative_path.clone(), result.root.as_deref())
+						});
+					streamed.complete(idx, entry);
+				}
+			});
+		drop(match_span);
+		// Files skipped after cancellation would otherwise look match-free.
+		ct.heartbeat()?;
+		let _span = prof::trace_span(trace, "serialize");
+		let mut result = files_without_match_result(results, max_count, offset);
+		result.budget_exhausted = budget_exhausted;
+		return Ok(result);
+	}
 
-	let allow_parallel = max_count.is_none() && offset == 0;
-	if allow_parallel {
-		let results = run_parallel_search(
-			&entries,
-			&matcher,
+	if max_count.is_none() && offset == 0 && cursor.is_none() {
+		let match_span = prof::trace_span(trace, "match");
+		let params = SearchParams {
 			context_before,
 			context_after,
 			max_columns,
-			output_mode,
-		);
+			mode: output_mode,
+			max_count: None,
+			offset: 0,
+			spans,
+			columns,
+			multiline,
+		};
+		let streamed = on_match.map(|callback| {
+			InOrder::new(|matches: Vec<GrepMatch>| {
+				for grep_match in matches {
+					callback.push(grep_match);
+				}
+			})
+		});
+		let results =
+			run_parallel_search(entries, &matcher, params, max_file_bytes, ct, |idx, result| {
+				if let Some(streamed) = &streamed {
+					streamed.complete(idx, result.map(|result| file_grep_matches(result, output_mode)));
+				}
+			});
+		drop(match_span);
+		ct.heartbeat()?;
+		let _span = prof::trace_span(trace, "serialize");
 		let mut matches = Vec::new();
 		let mut total_matches = 0u64;
 		let mut files_with_matches = 0u32;
 		let files_searched = crate::utils::clamp_u32(results.len() as u64);
+		let mut truncated = Vec::new();
 
 		for result in results {
+			if result.truncated {
+				truncated.push(listed_path(result.root.as_deref(), &result.relative_path));
+			}
 			if result.match_count == 0 {
 				continue;
 			}
@@ -1075,27 +3675,19 @@ fn grep_sync(
 			match output_mode {
 				OutputMode::Content => {
 					for matched in result.matches {
-						let grep_match = to_grep_match(&result.relative_path, matched);
-						if let Some(callback) = on_match {
-							callback.call(Ok(grep_match.clone()), ThreadsafeFunctionCallMode::NonBlocking);
-						}
-						matches.push(grep_match);
+						matches.push(to_grep_match(
+							&result.relative_path,
+							result.root.as_deref(),
+							matched,
+						));
 					}
 				},
 				OutputMode::Count => {
-					let grep_match = GrepMatch {
-						path:           result.relative_path.clone(),
-						line_number:    0,
-						line:           String::new(),
-						context_before: None,
-						context_after:  None,
-						truncated:      None,
-						match_count:    Some(crate::utils::clamp_u32(result.match_count)),
-					};
-					if let Some(callback) = on_match {
-						callback.call(Ok(grep_match.clone()), ThreadsafeFunctionCallMode::NonBlocking);
-					}
-					matches.push(grep_match);
+					matches.push(count_entry(
+						result.relative_path.clone(),
+						result.root.as_deref(),
+						result.match_count,
+					));
 				},
 			}
 		}
@@ -1106,32 +3698,66 @@ fn grep_sync(
 			files_with_matches,
 			files_searched,
 			limit_reached: None,
+			truncated_files: truncated_files(truncated),
+			directories: None,
+			reused_files: None,
+			heatmaps: None,
+			blocks: None,
+			cursor: past_budget.map(|cursor| cursor.encode()),
+			budget_exhausted,
+			trace: None,
 		});
 	}
 
-	let (matches, total_matches, files_with_matches, files_searched, limit_reached) =
-		run_sequential_search(&entries, &matcher, SearchParams {
+	let match_span = prof::trace_span(trace, "match");
+	let search = run_limited_search(
+		entries,
+		&matcher,
+		SearchParams {
 			context_before,
 			context_after,
 			max_columns,
 			mode: output_mode,
 			max_count,
 			offset,
-		});
-
-	// Fire callbacks for sequential search results
-	if let Some(callback) = on_match {
-		for grep_match in &matches {
-			callback.call(Ok(grep_match.clone()), ThreadsafeFunctionCallMode::NonBlocking);
-		}
-	}
+			spans,
+			columns,
+			multiline,
+		},
+		max_file_bytes,
+		first_skip,
+		on_match,
+		ct,
+	);
+	drop(match_span);
+	ct.heartbeat()?;
+	let _span = prof::trace_span(trace, "serialize");
 
 	Ok(GrepResult {
-		matches,
-		total_matches: crate::utils::clamp_u32(total_matches),
-		files_with_matches,
-		files_searched,
-		limit_reached: if limit_reached { Some(true) } else { None },
+		matches: search.matches,
+		total_matches: crate::utils::clamp_u32(search.total_matches),
+		files_with_matches: search.files_with_matches,
+		files_searched: search.files_searched,
+		limit_reached: if search.limit_reached {
+			Some(true)
+		} else {
+			None
+		},
+		truncated_files: truncated_files(search.truncated_files),
+		directories: None,
+		reused_files: None,
+		heatmaps: None,
+		blocks: None,
+		cursor: search
+			.resume
+			.map(|mut resume| {
+				resume.file += start;
+				resume
+			})
+			.or(past_budget)
+			.map(|cursor| cursor.encode()),
+		budget_exhausted,
+		trace: None,
 	})
 }
 
@@ -1158,6 +3784,52 @@ pub fn search(content: Either<JsString, Uint8Array>, options: SearchOptions) ->
 	}
 }
 
+/// Search many in-memory documents with one compiled pattern.
+///
+/// `options` apply to each document on its own, so `maxCount` and `offset`
+/// are per document. With `mode: "count"` documents carry only counts;
+/// `"filesWithMatches"` also drops documents without a match.
+#[napi(js_name = "searchDocuments")]
+pub fn search_documents(
+	documents: Vec<SearchDocument>,
+	options: SearchOptions,
+) -> SearchDocumentsResult {
+	let matcher = match search_matcher(&options) {
+		Ok(matcher) => matcher,
+		Err(err) => {
+			return SearchDocumentsResult {
+				documents:     Vec::new(),
+				total_matches: 0,
+				error:         Some(err.to_string()),
+			};
+		},
+	};
+	let files_only = options.mode.as_deref() == Some("filesWithMatches");
+	let results: Vec<DocumentSearchResult> = documents
+		.par_iter()
+		.map(|document| {
+			let result = search_with(&matcher, document.content.as_bytes(), &options);
+			DocumentSearchResult {
+				id:            document.id.clone(),
+				matches:       result.matches,
+				match_count:   result.match_count,
+				limit_reached: result.limit_reached,
+				error:         result.error,
+			}
+		})
+		.filter(|result| !files_only || result.match_count > 0)
+		.collect();
+	let total = results
+		.iter()
+		.map(|result| u64::from(result.match_count))
+		.sum();
+	SearchDocumentsResult {
+		documents:     results,
+		total_matches: crate::utils::clamp_u32(total),
+		error:         None,
+	}
+}
+
 /// Quick check if content matches a pattern.
 ///
 /// # Arguments
@@ -1200,7 +3872,7 @@ pub fn has_match(
 		},
 	};
 
-	let matcher = build_matcher(pattern_ref, ignore_case, multiline)?;
+	let matcher = build_matcher(pattern_ref, RegexEngine::Default, ignore_case, multiline)?;
 	Ok(matcher.is_match(content_slice).unwrap_or(false))
 }
 
@@ -1208,57 +3880,150 @@ pub fn has_match(
 ///
 /// # Arguments
 /// - `options`: Pattern, path, filters, and output mode.
-/// - `on_match`: Optional callback invoked per match/result.
+/// - `on_match`: Optional callback receiving matches/results in batches (see
+///   `stream`).
 ///
 /// # Returns
 /// Aggregated results across matching files.
 #[napi(js_name = "grep")]
 pub fn grep(
 	options: GrepOptions<'_>,
-	#[napi(ts_arg_type = "((match: GrepMatch) => void) | undefined | null")] on_match: Option<
-		ThreadsafeFunction<GrepMatch>,
-	>,
+	#[napi(
+		ts_arg_type = "((error: Error | null, batch: GrepMatchBatch) => void) | undefined | null"
+	)]
+	on_match: Option<ThreadsafeFunction<GrepMatchBatch>>,
+) -> task::Async<GrepResult> {
+	let (config, ct) = split_grep_options(options, None);
+	run_grep(config, on_match, ct)
+}
+
+/// Search files like [`grep`], reusing `session`'s results for files whose
+/// mtime and size are unchanged since the previous call.
+///
+/// Only changed and added files are searched again. A session remembers one
+/// query: changing the pattern, its flags, context, `maxColumns`,
+/// `maxFileSize`, the output mode, or the root discards its results. Totals
+/// are exact even with `maxCount`/`offset`, since every file is searched in
+/// full.
+#[napi(js_name = "grepIncremental")]
+pub fn grep_incremental(
+	session: &GrepSession,
+	options: GrepOptions<'_>,
+	#[napi(
+		ts_arg_type = "((error: Error | null, batch: GrepMatchBatch) => void) | undefined | null"
+	)]
+	on_match: Option<ThreadsafeFunction<GrepMatchBatch>>,
+) -> task::Async<GrepResult> {
+	let (config, ct) = split_grep_options(options, Some(Arc::clone(&session.state)));
+	run_grep(config, on_match, ct)
+}
+
+/// Run `grep_sync` on the blocking pool, streaming `on_match` batches and
+/// closing the stream with a `done` batch.
+fn run_grep(
+	config: GrepConfig,
+	on_match: Option<ThreadsafeFunction<GrepMatchBatch>>,
+	ct: task::CancelToken,
 ) -> task::Async<GrepResult> {
+	let batcher = on_match.map(|callback| stream::Batcher::new(callback, config.stream));
+	task::blocking("grep", ct, move |ct| {
+		let result = grep_sync(config, batcher.as_ref(), ct);
+		if let Some(batcher) = &batcher {
+			batcher.finish();
+		}
+		result
+	})
+}
+
+fn split_grep_options(
+	options: GrepOptions<'_>,
+	session: Option<Arc<Mutex<GrepSessionState>>>,
+) -> (GrepConfig, task::CancelToken) {
 	let GrepOptions {
 		pattern,
 		path,
+		package,
+		changed_only,
 		glob,
+		glob_case_sensitive,
 		type_filter,
 		ignore_case,
 		multiline,
+		engine,
 		hidden,
 		gitignore,
+		ignore_sources,
+		ignore_files,
 		cache,
 		max_count,
 		offset,
+		cursor,
+		natural_sort,
 		context_before,
 		context_after,
 		context,
 		max_columns,
+		truncate_by_width,
+		columns,
+		tab_width,
+		spans,
+		max_file_size,
+		max_total_bytes,
+		max_files,
 		mode,
+		dir_depth,
+		blame,
+		heatmap,
+		heatmap_buckets,
+		blocks,
+		trace,
+		stream,
 		timeout_ms,
 		signal,
+		scope,
 	} = options;
 
 	let config = GrepConfig {
 		pattern,
 		path,
+		package,
+		changed_only,
 		glob,
+		glob_case_sensitive,
 		type_filter,
 		ignore_case,
 		multiline,
+		engine,
 		hidden,
 		gitignore,
+		ignore_sources,
+		ignore_files,
 		cache,
 		max_count,
 		offset,
+		cursor,
+		natural_sort,
 		context_before,
 		context_after,
 		context,
 		max_columns,
+		truncate_by_width,
+		columns,
+		tab_width,
+		spans,
+		max_file_size,
+		max_total_bytes,
+		max_files,
 		mode,
+		dir_depth,
+		blame,
+		heatmap,
+		heatmap_buckets,
+		blocks,
+		trace,
+		stream,
+		session,
 	};
-
-	let ct = task::CancelToken::new(timeout_ms, signal);
-	task::blocking("grep", ct, move |ct| grep_sync(config, on_match.as_ref(), ct))
+	let ct = task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref());
+	(config, ct)
 }
@@ -0,0 +1,451 @@
+//! Content hashing and duplicate detection.
+//!
+//! Files are hashed with XXH3-128 by default, which is fast enough to hash a
+//! whole tree on every check, or with BLAKE3 when a cryptographic digest is
+//! needed (e.g. verifying integrity across machines). Directory scans reuse
+//! the [`fs_cache`] walker, so ignore rules match `glob()` and `grep()`.
+//!
+//! `duplicateFiles()` only hashes files whose size collides with another
+//! file, so trees of mostly unique files cost little more than a scan.
+
+use std::{
+	collections::HashMap,
+	fmt::Write as _,
+	fs::File,
+	io::{self, Read},
+	path::{Path, PathBuf},
+};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{fs_cache, glob_util, task};
+
+/// Bytes read per chunk while hashing; also the cancellation check interval.
+const CHUNK_BYTES: usize = 256 * 1024;
+
+/// Digest algorithm requested by a hashing call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+	/// XXH3-128: non-cryptographic, fastest.
+	#[default]
+	Xxh3,
+	/// BLAKE3-256: cryptographic.
+	Blake3,
+}
+
+impl HashAlgorithm {
+	/// Parse the `algorithm` option ("xxh3" or "blake3").
+	///
+	/// # Errors
+	/// Fails on any other value.
+	pub fn parse(value: Option<&str>) -> Result<Self> {
+		match value {
+			None | Some("xxh3") => Ok(Self::Xxh3),
+			Some("blake3") => Ok(Self::Blake3),
+			Some(other) => Err(Error::from_reason(format!(
+				"Unknown hash algorithm: {other} (expected \"xxh3\" or \"blake3\")"
+			))),
+		}
+	}
+}
+
+enum Hasher {
+	Xxh3(Box<Xxh3>),
+	Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+	fn new(algorithm: HashAlgorithm) -> Self {
+		match algorithm {
+			HashAlgorithm::Xxh3 => Self::Xxh3(Box::new(Xxh3::new())),
+			HashAlgorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+		}
+	}
+
+	fn update(&mut self, bytes: &[u8]) {
+		match self {
+			Self::Xxh3(hasher) => hasher.update(bytes),
+			Self::Blake3(hasher) => {
+				hasher.update(bytes);
+			},
+		}
+	}
+
+	/// Lowercase hex digest.
+	fn finish(self) -> String {
+		let mut hex = String::with_capacity(64);
+		match self {
+			Self::Xxh3(hasher) => {
+				let _ = write!(hex, "{:032x}", hasher.digest128());
+			},
+			Self::Blake3(hasher) => hex.push_str(hasher.finalize().to_hex().as_str()),
+		}
+		hex
+	}
+}
+
+/// Hash the file at `path`, returning the hex digest and byte count.
+fn hash_path(
+	path: &Path,
+	algorithm: HashAlgorithm,
+	ct: &task::CancelToken,
+) -> Result<(String, u64)> {
+	let mut file = File::open(path)
+		.map_err(|err| Error::from_reason(format!("Failed to open {}: {err}", path.display())))?;
+	let mut hasher = Hasher::new(algorithm);
+	let mut buf = vec![0u8; CHUNK_BYTES];
+	let mut size = 0u64;
+	loop {
+		ct.heartbeat()?;
+		let read = match file.read(&mut buf) {
+			Ok(0) => break,
+			Ok(read) => read,
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => {
+				return Err(Error::from_reason(format!("Failed to read {}: {err}", path.display())));
+			},
+		};
+		hasher.update(&buf[..read]);
+		size += read as u64;
+	}
+	Ok((hasher.finish(), size))
+}
+
+/// Options for `hashFile()`.
+#[napi(object)]
+pub struct HashFileOptions {
+	/// Digest algorithm: "xxh3" (default) or "blake3".
+	pub algorithm: Option<String>,
+}
+
+/// Digest of one file.
+#[napi(object)]
+pub struct FileHash {
+	/// File path (relative to the root for tree hashes).
+	pub path: String,
+	/// Lowercase hex digest.
+	pub hash: String,
+	/// Bytes hashed.
+	pub size: i64,
+}
+
+/// Options for hashing a directory tree.
+#[napi(object)]
+pub struct HashTreeOptions<'env> {
+	/// Digest algorithm: "xxh3" (default) or "blake3".
+	pub algorithm:  Option<String>,
+	/// Glob filter for files (e.g., "*.ts").
+	pub glob:       Option<String>,
+	/// Include hidden files (default: true).
+	pub hidden:     Option<bool>,
+	/// Respect .gitignore and other ignore files (default: true).
+	pub gitignore:  Option<bool>,
+	/// Abort signal for cancelling the operation.
+	pub signal:     Option<Unknown<'env>>,
+	/// Timeout in milliseconds for the operation.
+	#[napi(js_name = "timeoutMs")]
+	pub timeout_ms: Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:      Option<ClassInstance<'env, task::CancellationScope>>,
+}
+
+/// Digest of a directory tree.
+#[napi(object)]
+pub struct TreeHash {
+	/// Digest over every file's relative path and digest, in path order.
+	pub hash:  String,
+	/// Per-file digests, sorted by path.
+	pub files: Vec<FileHash>,
+}
+
+/// Options for `duplicateFiles()`.
+#[napi(object)]
+pub struct DuplicateFilesOptions<'env> {
+	/// Digest algorithm: "xxh3" (default) or "blake3".
+	pub algorithm:  Option<String>,
+	/// Glob filter for files (e.g., "*.ts").
+	pub glob:       Option<String>,
+	/// Include hidden files (default: true).
+	pub hidden:     Option<bool>,
+	/// Respect .gitignore and other ignore files (default: true).
+	pub gitignore:  Option<bool>,
+	/// Ignore files smaller than this many bytes (default: 1, skipping empty
+	/// files).
+	#[napi(js_name = "minSize")]
+	pub min_size:   Option<i64>,
+	/// Abort signal for cancelling the operation.
+	pub signal:     Option<Unknown<'env>>,
+	/// Timeout in milliseconds for the operation.
+	#[napi(js_name = "timeoutMs")]
+	pub timeout_ms: Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:      Option<ClassInstance<'env, task::CancellationScope>>,
+}
+
+/// Files with identical content.
+#[napi(object)]
+pub struct DuplicateGroup {
+	/// Shared digest.
+	pub hash:  String,
+	/// Size of each file in bytes.
+	pub size:  i64,
+	/// Relative paths, sorted.
+	pub paths: Vec<String>,
+}
+
+/// A file selected for hashing.
+struct TreeFile {
+	path:          PathBuf,
+	relative_path: String,
+}
+
+/// List regular files under `root` that pass the glob filter, in path order.
+fn tree_files(
+	root: &Path,
+	glob: Option<&str>,
+	hidden: bool,
+	gitignore: bool,
+	ct: &task::CancelToken,
+) -> Result<Vec<TreeFile>> {
+	let glob_set = glob_util::try_compile_glob(glob, true, Some(true))?;
+	let policy = fs_cache::ScanPolicy::new(hidden, gitignore);
+	let mut files: Vec<TreeFile> = fs_cache::force_rescan(root, &policy, false, ct)?
+		.into_iter()
+		.filter(|entry| entry.file_type == fs_cache::FileType::File)
+		.filter(|entry| {
+			glob_set
+				.as_ref()
+				.is_none_or(|glob_set| glob_set.is_match(&entry.path))
+		})
+		.map(|entry| TreeFile { path: root.join(&entry.path), relative_path: entry.path })
+		.collect();
+	files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+	Ok(files)
+}
+
+fn to_i64(size: u64) -> i64 {
+	i64::try_from(size).unwrap_or(i64::MAX)
+}
+
+fn hash_tree_sync(
+	files: &[TreeFile],
+	algorithm: HashAlgorithm,
+	ct: &task::CancelToken,
+) -> Result<TreeHash> {
+	let files = files
+		.par_iter()
+		.map(|file| {
+			let (hash, size) = hash_path(&file.path, algorithm, ct)?;
+			Ok(FileHash { path: file.relative_path.clone(), hash, size: to_i64(size) })
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	let mut tree = Hasher::new(algorithm);
+	for file in &files {
+		tree.update(file.path.as_bytes());
+		tree.update(b"\0");
+		tree.update(file.hash.as_bytes());
+		tree.update(b"\n");
+	}
+	Ok(TreeHash { hash: tree.finish(), files })
+}
+
+fn duplicate_files_sync(
+	files: Vec<TreeFile>,
+	min_size: u64,
+	algorithm: HashAlgorithm,
+	ct: &task::CancelToken,
+) -> Result<Vec<DuplicateGroup>> {
+	// Only files that share a size can share content.
+	let mut by_size: HashMap<u64, Vec<TreeFile>> = HashMap::new();
+	for file in files {
+		let Ok(metadata) = std::fs::metadata(&file.path) else {
+			continue;
+		};
+		if metadata.len() >= min_size {
+			by_size.entry(metadata.len()).or_default().push(file);
+		}
+	}
+	let candidates: Vec<(u64, TreeFile)> = by_size
+		.into_iter()
+		.filter(|(_, files)| files.len() > 1)
+		.flat_map(|(size, files)| files.into_iter().map(move |file| (size, file)))
+		.collect();
+
+	// Unreadable files, or files that changed size while hashing, are skipped.
+	let hashed: Vec<(u64, String, String)> = candidates
+		.into_par_iter()
+		.filter_map(|(size, file)| {
+			let (hash, hashed_size) = hash_path(&file.path, algorithm, ct).ok()?;
+			(hashed_size == size).then_some((size, hash, file.relative_path))
+		})
+		.collect();
+	ct.heartbeat()?;
+
+	let mut groups: HashMap<(u64, String), Vec<String>> = HashMap::new();
+	for (size, hash, path) in hashed {
+		groups.entry((size, hash)).or_default().push(path);
+	}
+	let mut groups: Vec<DuplicateGroup> = groups
+		.into_iter()
+		.filter(|(_, paths)| paths.len() > 1)
+		.map(|((size, hash), mut paths)| {
+			paths.sort();
+			DuplicateGroup { hash, size: to_i64(size), paths }
+		})
+		.collect();
+	// Largest groups by bytes wasted first.
+	groups.sort_by(|a, b| {
+		let wasted = |group: &DuplicateGroup| group.size.saturating_mul(group.paths.len() as i64 - 1);
+		wasted(b)
+			.cmp(&wasted(a))
+			.then_with(|| a.paths[0].cmp(&b.paths[0]))
+	});
+	Ok(groups)
+}
+
+/// Hash a single file.
+///
+/// # Errors
+/// Fails when the file cannot be read or the algorithm is unknown.
+#[napi(js_name = "hashFile")]
+pub fn hash_file(path: String, options: Option<HashFileOptions>) -> task::Async<FileHash> {
+	let algorithm = options.and_then(|options| options.algorithm);
+	task::blocking("hash.file", (), move |ct| {
+		let algorithm = HashAlgorithm::parse(algorithm.as_deref())?;
+		let (hash, size) = hash_path(Path::new(&path), algorithm, &ct)?;
+		Ok(FileHash { path, hash, size: to_i64(size) })
+	})
+}
+
+/// Hash every file under a directory, plus a digest of the whole tree.
+///
+/// The tree digest covers relative paths and file digests, so renames,
+/// additions, and deletions change it as well as edits.
+///
+/// # Errors
+/// Fails when the root is not a directory, a file cannot be read, the glob or
+/// algorithm is invalid, or the call is cancelled.
+#[napi(js_name = "hashTree")]
+pub fn hash_tree(path: String, options: Option<HashTreeOptions<'_>>) -> task::Async<TreeHash> {
+	let HashTreeOptions { algorithm, glob, hidden, gitignore, signal, timeout_ms, scope } = options
+		.unwrap_or(HashTreeOptions {
+			algorithm:  None,
+			glob:       None,
+			hidden:     None,
+			gitignore:  None,
+			signal:     None,
+			timeout_ms: None,
+			scope:      None,
+		});
+	let ct = task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref());
+	task::blocking("hash.tree", ct, move |ct| {
+		let algorithm = HashAlgorithm::parse(algorithm.as_deref())?;
+		let root = fs_cache::resolve_search_path(&path)?;
+		let files = tree_files(
+			&root,
+			glob.as_deref(),
+			hidden.unwrap_or(true),
+			gitignore.unwrap_or(true),
+			&ct,
+		)?;
+		hash_tree_sync(&files, algorithm, &ct)
+	})
+}
+
+/// Group files under a directory that have identical content.
+///
+/// Groups are sorted by bytes wasted (size times extra copies), largest first.
+///
+/// # Errors
+/// Fails when the root is not a directory, the glob or algorithm is invalid,
+/// or the call is cancelled.
+#[napi(js_name = "duplicateFiles")]
+pub fn duplicate_files(
+	path: String,
+	options: Option<DuplicateFilesOptions<'_>>,
+) -> task::Async<Vec<DuplicateGroup>> {
+	let DuplicateFilesOptions {
+		algorithm,
+		glob,
+		hidden,
+		gitignore,
+		min_size,
+		signal,
+		timeout_ms,
+		scope,
+	} = options.unwrap_or(DuplicateFilesOptions {
+		algorithm:  None,
+		glob:       None,
+		hidden:     None,
+		gitignore:  None,
+		min_size:   None,
+		signal:     None,
+		timeout_ms: None,
+		scope:      None,
+	});
+	let ct = task::CancelToken::new(timeout_ms, signal).with_scope(scope.as_deref());
+	task::blocking("hash.duplicates", ct, move |ct| {
+		let algorithm = HashAlgorithm::parse(algorithm.as_deref())?;
+		let root = fs_cache::resolve_search_path(&path)?;
+		let files = tree_files(
+			&root,
+			glob.as_deref(),
+			hidden.unwrap_or(true),
+			gitignore.unwrap_or(true),
+			&ct,
+		)?;
+		let min_size = min_size.map_or(1, |size| size.max(0).unsigned_abs());
+		duplicate_files_sync(files, min_size, algorithm, &ct)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+
+	#[test]
+	fn hashes_trees_and_groups_duplicates() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-hash-test-{unique}"));
+		fs::create_dir_all(root.join("sub")).expect("temp dir should be created");
+		for (name, content) in
+			[("a.txt", "same\n"), ("sub/b.txt", "same\n"), ("c.txt", "diff\n"), ("empty", "")]
+		{
+			fs::write(root.join(name), content).expect("fixture should be written");
+		}
+		let ct = task::CancelToken::default();
+
+		let files = tree_files(&root, None, true, true, &ct).unwrap();
+		let tree = hash_tree_sync(&files, HashAlgorithm::Xxh3, &ct).unwrap();
+		let paths: Vec<_> = tree.files.iter().map(|file| file.path.as_str()).collect();
+		assert_eq!(paths, ["a.txt", "c.txt", "empty", "sub/b.txt"]);
+		assert_eq!(tree.files[0].hash, tree.files[3].hash);
+		assert_eq!(tree.files[0].hash.len(), 32);
+
+		let blake = hash_tree_sync(&files, HashAlgorithm::Blake3, &ct).unwrap();
+		assert_eq!(blake.files[0].hash, blake3::hash(b"same\n").to_hex().as_str());
+
+		let groups = duplicate_files_sync(files, 1, HashAlgorithm::Xxh3, &ct).unwrap();
+		assert_eq!(groups.len(), 1);
+		assert_eq!(groups[0].paths, ["a.txt", "sub/b.txt"]);
+
+		fs::write(root.join("c.txt"), "changed\n").expect("c.txt should be rewritten");
+		let files = tree_files(&root, None, true, true, &ct).unwrap();
+		let changed = hash_tree_sync(&files, HashAlgorithm::Xxh3, &ct).unwrap();
+		assert_ne!(changed.hash, tree.hash);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+}
@@ -0,0 +1,258 @@
+//! Syntax highlighting spans computed with tree-sitter.
+//!
+//! `highlight()` parses code with the grammars compiled in for ast-grep and
+//! runs each grammar's bundled `highlights.scm` query, so the terminal
+//! renderer can colorize code blocks without a JS highlighter. Captures are
+//! mapped to the same semantic categories `highlightCode` uses, and resolved
+//! like tree-sitter-highlight does: inner captures win over the nodes that
+//! enclose them, and for the same node the earliest pattern in the query wins.
+//! Compiled queries are cached per language.
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, LazyLock},
+};
+
+use ast_grep_core::tree_sitter::LanguageExt;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use parking_lot::Mutex;
+use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+
+use crate::{ast, language::SupportLang};
+
+/// Highlight categories, indexed by [`category`].
+const CATEGORIES: [&str; 9] = [
+	"comment",
+	"keyword",
+	"function",
+	"variable",
+	"string",
+	"number",
+	"type",
+	"operator",
+	"punctuation",
+];
+
+/// A highlighted range of the input.
+#[napi(object)]
+pub struct HighlightSpan {
+	/// Start offset in UTF-16 code units (inclusive).
+	pub start:    u32,
+	/// End offset in UTF-16 code units (exclusive).
+	pub end:      u32,
+	/// "comment", "keyword", "function", "variable", "string", "number",
+	/// "type", "operator", or "punctuation".
+	pub category: String,
+}
+
+/// Category index for a capture name such as `function.method` or
+/// `string.special`.
+fn category(capture: &str) -> Option<u8> {
+	let head = capture.split('.').next().unwrap_or_default();
+	Some(match head {
+		"comment" => 0,
+		"keyword" | "conditional" | "repeat" | "include" | "exception" | "storageclass" | "tag" => 1,
+		"function" | "method" | "constructor" => 2,
+		"variable" | "property" | "parameter" | "field" | "label" => 3,
+		"string" | "character" | "escape" => 4,
+		"number" | "float" | "boolean" | "constant" => 5,
+		"type" | "attribute" => 6,
+		"operator" => 7,
+		"punctuation" => 8,
+		_ => return None,
+	})
+}
+
+/// Highlight query source for `lang`, when its grammar ships one.
+fn query_source(lang: SupportLang) -> Option<String> {
+	let js = tree_sitter_javascript::HIGHLIGHT_QUERY;
+	let jsx = tree_sitter_javascript::JSX_HIGHLIGHT_QUERY;
+	let ts = tree_sitter_typescript::HIGHLIGHTS_QUERY;
+	Some(match lang {
+		SupportLang::Bash => tree_sitter_bash::HIGHLIGHT_QUERY.to_string(),
+		SupportLang::C => tree_sitter_c::HIGHLIGHT_QUERY.to_string(),
+		SupportLang::Cpp => {
+			format!("{}\n{}", tree_sitter_cpp::HIGHLIGHT_QUERY, tree_sitter_c::HIGHLIGHT_QUERY)
+		},
+		SupportLang::Css => tree_sitter_css::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Diff => tree_sitter_diff::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Elixir => tree_sitter_elixir::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Go => tree_sitter_go::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Haskell => tree_sitter_haskell::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Html => tree_sitter_html::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Java => tree_sitter_java::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::JavaScript => format!("{js}\n{jsx}"),
+		SupportLang::Json => tree_sitter_json::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Kotlin => tree_sitter_kotlin::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Lua => tree_sitter_lua::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Make => tree_sitter_make::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Markdown => tree_sitter_md::HIGHLIGHT_QUERY_BLOCK.to_string(),
+		SupportLang::Nix => tree_sitter_nix::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::ObjC => tree_sitter_objc::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Odin => tree_sitter_odin::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Php => tree_sitter_php::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Python => tree_sitter_python::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Regex => tree_sitter_regex::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Ruby => tree_sitter_ruby::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Rust => tree_sitter_rust::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Scala => tree_sitter_scala::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Solidity => tree_sitter_solidity::HIGHLIGHT_QUERY.to_string(),
+		SupportLang::Starlark => tree_sitter_starlark::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Swift => tree_sitter_swift::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Toml => tree_sitter_toml_ng::HIGHLIGHTS_QUERY.to_string(),
+		// TypeScript's query only adds to the JavaScript one.
+		SupportLang::Tsx => format!("{ts}\n{js}\n{jsx}"),
+		SupportLang::TypeScript => format!("{ts}\n{js}"),
+		SupportLang::Yaml => tree_sitter_yaml::HIGHLIGHTS_QUERY.to_string(),
+		SupportLang::Zig => tree_sitter_zig::HIGHLIGHTS_QUERY.to_string(),
+		_ => return None,
+	})
+}
+
+/// A compiled highlight query with the category of each capture.
+struct HighlightQuery {
+	query:      Query,
+	categories: Vec<Option<u8>>,
+}
+
+static QUERIES: LazyLock<Mutex<HashMap<SupportLang, Arc<HighlightQuery>>>> =
+	LazyLock::new(Mutex::default);
+
+fn highlight_query(lang: SupportLang) -> Result<Arc<HighlightQuery>> {
+	if let Some(query) = QUERIES.lock().get(&lang) {
+		return Ok(query.clone());
+	}
+	let source = query_source(lang).ok_or_else(|| {
+		Error::from_reason(format!("No highlight query for language: {}", lang.canonical_name()))
+	})?;
+	let query = Query::new(&lang.get_ts_language(), &source).map_err(|err| {
+		Error::from_reason(format!("Invalid highlight query for {}: {err}", lang.canonical_name()))
+	})?;
+	let categories = query
+		.capture_names()
+		.iter()
+		.map(|name| category(name))
+		.collect();
+	let compiled = Arc::new(HighlightQuery { query, categories });
+	QUERIES.lock().insert(lang, compiled.clone());
+	Ok(compiled)
+}
+
+/// Non-overlapping highlight spans of `source` as UTF-16 offsets.
+fn highlight_spans(source: &str, lang: SupportLang) -> Result<Vec<HighlightSpan>> {
+	let compiled = highlight_query(lang)?;
+	let HighlightQuery { query, categories } = &*compiled;
+	let mut parser = Parser::new();
+	parser
+		.set_language(&lang.get_ts_language())
+		.map_err(|err| Error::from_reason(format!("Failed to load grammar: {err}")))?;
+	let Some(tree) = parser.parse(source, None) else {
+		return Ok(Vec::new());
+	};
+
+	// (start, end, pattern, category) of every categorized capture.
+	let mut captures = Vec::new();
+	let mut cursor = QueryCursor::new();
+	let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+	while let Some(found) = matches.next() {
+		for capture in found.captures {
+			if let Some(category) = categories[capture.index as usize] {
+				let range = capture.node.byte_range();
+				captures.push((range.start, range.end, found.pattern_index, category));
+			}
+		}
+	}
+	// Paint outer ranges first so inner ones overwrite them, and later
+	// patterns before earlier ones on the same range.
+	captures.sort_by(|a, b| (b.1 - b.0).cmp(&(a.1 - a.0)).then(b.2.cmp(&a.2)));
+	let mut paint = vec![u8::MAX; source.len()];
+	for (start, end, _, category) in captures {
+		paint[start..end].fill(category);
+	}
+
+	let mut spans = Vec::new();
+	let mut utf16 = 0u32;
+	let mut open: Option<(u32, u8)> = None;
+	for (index, ch) in source.char_indices() {
+		let painted = paint[index];
+		if open.is_some_and(|(_, category)| category != painted) {
+			let (start, category) = open.take().unwrap_or_default();
+			spans.push(HighlightSpan {
+				start,
+				end: utf16,
+				category: CATEGORIES[category as usize].to_string(),
+			});
+		}
+		if open.is_none() && painted != u8::MAX {
+			open = Some((utf16, painted));
+		}
+		utf16 += ch.len_utf16() as u32;
+	}
+	if let Some((start, category)) = open {
+		spans.push(HighlightSpan {
+			start,
+			end: utf16,
+			category: CATEGORIES[category as usize].to_string(),
+		});
+	}
+	Ok(spans)
+}
+
+/// Compute syntax highlighting spans for `content` with tree-sitter.
+///
+/// Spans are sorted, non-overlapping, and cover only highlighted text.
+///
+/// # Errors
+/// Fails when `lang` is unknown or its grammar has no highlight query.
+#[napi(js_name = "highlight")]
+pub fn highlight(content: String, lang: String) -> Result<Vec<HighlightSpan>> {
+	let lang = ast::resolve_supported_lang(lang.trim())?;
+	highlight_spans(&content, lang)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn tokens(source: &str, lang: SupportLang) -> Vec<(String, String)> {
+		let units: Vec<u16> = source.encode_utf16().collect();
+		highlight_spans(source, lang)
+			.unwrap()
+			.into_iter()
+			.map(|span| {
+				let text = String::from_utf16_lossy(&units[span.start as usize..span.end as usize]);
+				(span.category, text)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn highlights_rust() {
+		let spans = tokens("// é note\nfn main() { let s = \"hi\"; }\n", SupportLang::Rust);
+		for expected in [
+			("comment", "// é note"),
+			("keyword", "fn"),
+			("function", "main"),
+			("keyword", "let"),
+			("string", "\"hi\""),
+		] {
+			assert!(
+				spans.contains(&(expected.0.to_string(), expected.1.to_string())),
+				"missing {expected:?} in {spans:?}"
+			);
+		}
+	}
+
+	#[test]
+	fn compiles_every_query() {
+		for &lang in SupportLang::all_langs() {
+			if query_source(lang).is_some() {
+				highlight_query(lang).unwrap_or_else(|err| panic!("{lang:?}: {err}"));
+			}
+		}
+		let spans = tokens("const n: number = 1;\n", SupportLang::TypeScript);
+		assert!(spans.contains(&("type".to_string(), "number".to_string())), "{spans:?}");
+		assert!(spans.contains(&("number".to_string(), "1".to_string())), "{spans:?}");
+	}
+}
@@ -0,0 +1,252 @@
+//! Embedded code regions in host documents.
+//!
+//! `astGrep` with `injections` searches code that lives inside another
+//! file format: `<script>` and `<style>` blocks in HTML, Vue, and Svelte
+//! files, and fenced code blocks in Markdown. Regions are found with a
+//! lightweight text scan (no host grammar is needed, so `.vue` and `.svelte`
+//! work too) and each is parsed on its own; [`Region`] records where it
+//! starts so match positions can be reported in host-file coordinates.
+
+use std::path::Path;
+
+use crate::{ast::resolve_supported_lang, language::SupportLang};
+
+/// Host document formats that can embed code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Host {
+	/// HTML-like markup with `<script>` / `<style>` blocks.
+	Markup,
+	/// Markdown with fenced code blocks.
+	Markdown,
+}
+
+fn host_of(path: &Path) -> Option<Host> {
+	let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+	match ext.as_str() {
+		"html" | "htm" | "vue" | "svelte" => Some(Host::Markup),
+		"md" | "mdx" | "markdown" => Some(Host::Markdown),
+		_ => None,
+	}
+}
+
+/// Whether `path` is a document whose embedded code can be searched.
+pub fn is_host(path: &Path) -> bool {
+	host_of(path).is_some()
+}
+
+/// A block of embedded code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+	pub lang:   SupportLang,
+	/// Byte range of the code in the host source.
+	pub start:  usize,
+	pub end:    usize,
+	/// 0-based host line of `start`.
+	pub line:   usize,
+	/// 0-based column (in characters) of `start` within its line.
+	pub column: usize,
+}
+
+impl Region {
+	fn new(source: &str, lang: SupportLang, start: usize, end: usize) -> Self {
+		let before = &source[..start];
+		let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+		Self {
+			lang,
+			start,
+			end,
+			line: before.bytes().filter(|&byte| byte == b'\n').count(),
+			column: before[line_start..].chars().count(),
+		}
+	}
+}
+
+/// Embedded code regions of `source`, in file order. Blocks in unsupported
+/// languages are skipped.
+pub fn find_regions(path: &Path, source: &str) -> Vec<Region> {
+	match host_of(path) {
+		Some(Host::Markup) => markup_regions(source),
+		Some(Host::Markdown) => fenced_regions(source),
+		None => Vec::new(),
+	}
+}
+
+/// Value of attribute `name` in the text between a tag name and its `>`.
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+	let lower = attributes.to_ascii_lowercase();
+	let mut from = 0;
+	while let Some(found) = lower[from..].find(name) {
+		let at = from + found;
+		from = at + name.len();
+		let preceded = at == 0 || lower.as_bytes()[at - 1].is_ascii_whitespace();
+		let rest = attributes[from..].trim_start();
+		if !preceded || !rest.starts_with('=') {
+			continue;
+		}
+		let value = rest[1..].trim_start();
+		return Some(match value.chars().next() {
+			Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+			_ => value
+				.split(|ch: char| ch.is_ascii_whitespace() || ch == '>')
+				.next()
+				.unwrap_or_default(),
+		});
+	}
+	None
+}
+
+/// Language of a `<script>` block from its `lang` or `type` attribute.
+fn script_lang(attributes: &str) -> Option<SupportLang> {
+	if let Some(lang) = attribute(attributes, "lang") {
+		return resolve_supported_lang(lang).ok();
+	}
+	let Some(kind) = attribute(attributes, "type") else {
+		return Some(SupportLang::JavaScript);
+	};
+	match kind.to_ascii_lowercase().as_str() {
+		"" | "module" | "text/javascript" | "application/javascript" | "text/babel" => {
+			Some(SupportLang::JavaScript)
+		},
+		"text/typescript" | "application/typescript" => Some(SupportLang::TypeScript),
+		"application/json" | "application/ld+json" | "importmap" => Some(SupportLang::Json),
+		_ => None,
+	}
+}
+
+fn markup_regions(source: &str) -> Vec<Region> {
+	let lower = source.to_ascii_lowercase();
+	let mut regions = Vec::new();
+	let mut cursor = 0;
+	while let Some(found) = lower[cursor..].find('<') {
+		let open = cursor + found;
+		cursor = open + 1;
+		let Some(tag) = ["script", "style"]
+			.into_iter()
+			.find(|tag| lower[open + 1..].starts_with(tag))
+		else {
+			continue;
+		};
+		let after_name = open + 1 + tag.len();
+		if !lower[after_name..].starts_with(|ch: char| ch.is_ascii_whitespace() || ch == '>') {
+			continue;
+		}
+		let Some(close) = lower[after_name..]
+			.find('>')
+			.map(|index| after_name + index)
+		else {
+			break;
+		};
+		let attributes = &source[after_name..close];
+		cursor = close + 1;
+		if attributes.trim_end().ends_with('/') {
+			continue;
+		}
+		let end = lower[cursor..]
+			.find(&format!("</{tag}"))
+			.map_or(source.len(), |index| cursor + index);
+		let lang = if tag == "script" {
+			script_lang(attributes)
+		} else {
+			attribute(attributes, "lang")
+				.map_or(Some(SupportLang::Css), |lang| resolve_supported_lang(lang).ok())
+		};
+		if let Some(lang) = lang
+			&& !source[cursor..end].trim().is_empty()
+		{
+			regions.push(Region::new(source, lang, cursor, end));
+		}
+		cursor = end;
+	}
+	regions
+}
+
+/// Fence marker of `line` (character and run length) if it opens or closes a
+/// fenced code block, plus the text after it.
+fn fence(line: &str) -> Option<(char, usize, &str)> {
+	let trimmed = line.trim_start_matches(' ');
+	if line.len() - trimmed.len() > 3 {
+		return None;
+	}
+	let marker = trimmed
+		.chars()
+		.next()
+		.filter(|ch| matches!(ch, '`' | '~'))?;
+	let run = trimmed.len() - trimmed.trim_start_matches(marker).len();
+	(run >= 3).then(|| (marker, run, &trimmed[run..]))
+}
+
+fn fenced_regions(source: &str) -> Vec<Region> {
+	let mut regions = Vec::new();
+	// Open fence: marker, run length, language, and where its content starts.
+	let mut open: Option<(char, usize, Option<SupportLang>, usize)> = None;
+	let mut offset = 0;
+	for line in source.split_inclusive('\n') {
+		let line_start = offset;
+		offset += line.len();
+		let Some((marker, run, rest)) = fence(line.trim_end_matches(['\n', '\r'])) else {
+			continue;
+		};
+		if let Some((open_marker, open_run, lang, start)) = open {
+			if marker == open_marker && run >= open_run && rest.trim().is_empty() {
+				if let Some(lang) = lang
+					&& start < line_start
+				{
+					regions.push(Region::new(source, lang, start, line_start));
+				}
+				open = None;
+			}
+		} else {
+			let lang = rest
+				.trim()
+				.split(|ch: char| ch.is_ascii_whitespace() || ch == '{' || ch == ',')
+				.next()
+				.filter(|name| !name.is_empty())
+				.and_then(|name| resolve_supported_lang(name).ok());
+			open = Some((marker, run, lang, offset));
+		}
+	}
+	if let Some((_, _, Some(lang), start)) = open
+		&& start < source.len()
+	{
+		regions.push(Region::new(source, lang, start, source.len()));
+	}
+	regions
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn texts<'a>(source: &'a str, path: &str) -> Vec<(SupportLang, &'a str, usize, usize)> {
+		find_regions(Path::new(path), source)
+			.into_iter()
+			.map(|region| (region.lang, &source[region.start..region.end], region.line, region.column))
+			.collect()
+	}
+
+	#[test]
+	fn finds_script_and_style_blocks() {
+		let source =
+			"<template><div/></template>\n<script lang=\"ts\">\nconst a: number = \
+			 1;\n</script>\n<SCRIPT>let b = 2</SCRIPT>\n<script src=\"x.js\"></script>\n<script \
+			 type=\"text/template\"><b></b></script>\n<style scoped>a { color: red; }</style>\n";
+		assert_eq!(texts(source, "App.vue"), [
+			(SupportLang::TypeScript, "\nconst a: number = 1;\n", 1, 18),
+			(SupportLang::JavaScript, "let b = 2", 4, 8),
+			(SupportLang::Css, "a { color: red; }", 7, 14),
+		]);
+		assert_eq!(find_regions(Path::new("notes.txt"), source), []);
+	}
+
+	#[test]
+	fn finds_fenced_code_blocks() {
+		let source = "# Title\n\n```rust\nfn main() {}\n```\n\n~~~~ py \
+		              title=\"x\"\nprint(1)\n```\nx = \
+		              2\n~~~~\n\n```\nplain\n```\n\n```unknown\nskip\n```\n```js\nlet open = 1\n";
+		assert_eq!(texts(source, "README.md"), [
+			(SupportLang::Rust, "fn main() {}\n", 3, 0),
+			(SupportLang::Python, "print(1)\n```\nx = 2\n", 7, 0),
+			(SupportLang::JavaScript, "let open = 1\n", 20, 0),
+		]);
+	}
+}
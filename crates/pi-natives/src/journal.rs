@@ -0,0 +1,558 @@
+//! Write-ahead journal for multi-file writers.
+//!
+//! Mutating APIs that touch many files (e.g. `astEdit`) record every intended
+//! change before applying it and delete the record once all writes land. A
+//! crash mid-apply leaves the journal behind; `recoverJournal()` replays it
+//! forward (finish the writes) or backward (restore the original contents).
+//!
+//! # Layout
+//! Journals live in one directory, one `<owner>.<seq>.journal` file per
+//! operation. They are written to `<owner>.<seq>.tmp`, synced, then renamed
+//! into place, so a torn journal is never replayed.
+//!
+//! `<owner>` names the writing process, which holds an exclusive lock on
+//! `<owner>.lock` for as long as it lives. Recovery skips owners whose lock
+//! is held, so it never replays the in-flight journals of a live process.
+//!
+//! # Policy Configuration (environment overrides)
+//! - `FS_JOURNAL_DIR` – default `<state>/pi-natives/journal/<workspace>`, where
+//!   `<state>` is the user state directory (`XDG_STATE_HOME`, `~/.local/state`,
+//!   `~/Library/Application Support`, or `%LOCALAPPDATA%`) and `<workspace>` a
+//!   hash of the current directory
+
+use std::{
+	collections::BTreeMap,
+	fs::{self, File, TryLockError},
+	io::{self, Read, Write},
+	path::{Path, PathBuf},
+	sync::{
+		LazyLock,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use dashmap::DashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::{config, readonly, task};
+
+const MAGIC: &[u8; 4] = b"PIJ1";
+const JOURNAL_EXT: &str = "journal";
+const TEMP_EXT: &str = "tmp";
+const LOCK_EXT: &str = "lock";
+/// Lock file of an owner that is not set up yet.
+const PENDING_LOCK_EXT: &str = "locking";
+
+/// A single intended file mutation.
+///
+/// `None` content means "file absent": `before: None` marks a newly created
+/// file, `after: None` marks a deletion.
+pub struct JournalEntry {
+	pub path:   PathBuf,
+	pub before: Option<Vec<u8>>,
+	pub after:  Option<Vec<u8>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+	Forward,
+	Backward,
+}
+
+/// Per-user directory for state that must outlive the process.
+fn state_dir() -> Option<PathBuf> {
+	if let Some(dir) = std::env::var_os("XDG_STATE_HOME").map(PathBuf::from)
+		&& dir.is_absolute()
+	{
+		return Some(dir);
+	}
+	if cfg!(windows) {
+		return std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+	}
+	let home = std::env::home_dir()?;
+	Some(if cfg!(target_os = "macos") {
+		home.join("Library").join("Application Support")
+	} else {
+		home.join(".local").join("state")
+	})
+}
+
+/// Directory holding pending journals: `FS_JOURNAL_DIR`, or a directory of
+/// the workspace root (see `setWorkspaceRoot()`) under the user state
+/// directory.
+pub fn journal_dir() -> PathBuf {
+	if let Some(dir) = std::env::var_os("FS_JOURNAL_DIR").filter(|value| !value.is_empty()) {
+		return PathBuf::from(dir);
+	}
+	let workspace = config::workspace_root()
+		.map_or_else(|_| PathBuf::new(), |root| fs::canonicalize(&root).unwrap_or(root));
+	let key = format!("{:016x}", xxh3_64(workspace.as_os_str().as_encoded_bytes()));
+	state_dir()
+		.unwrap_or_else(std::env::temp_dir)
+		.join("pi-natives")
+		.join("journal")
+		.join(key)
+}
+
+fn next_owner_id() -> String {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+	let nanos = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |d| d.as_nanos());
+	let seq = COUNTER.fetch_add(1, Ordering::Relaxed);
+	format!("{nanos:x}-{}-{seq}", std::process::id())
+}
+
+/// A process's claim on its journals in one directory.
+///
+/// The lock on `<id>.lock` is held until the owner is dropped, which for the
+/// owners of [`OWNERS`] is when the process exits.
+struct Owner {
+	id:    String,
+	_lock: File,
+}
+
+impl Owner {
+	fn create(dir: &Path) -> io::Result<Self> {
+		fs::create_dir_all(dir)?;
+		let id = next_owner_id();
+		// Lock under a pending name first: recovery treats an unlocked
+		// `<id>.lock` as the leftover of a dead process.
+		let pending = dir.join(format!("{id}.{PENDING_LOCK_EXT}"));
+		let lock = File::create_new(&pending)?;
+		lock.try_lock()?;
+		fs::rename(&pending, dir.join(format!("{id}.{LOCK_EXT}")))?;
+		Ok(Self { id, _lock: lock })
+	}
+}
+
+/// Owner of this process per journal directory.
+static OWNERS: LazyLock<DashMap<PathBuf, Owner>> = LazyLock::new(DashMap::new);
+
+/// Id of this process's owner in `dir`, created on first use.
+fn owner_id(dir: &Path) -> io::Result<String> {
+	let owner = OWNERS
+		.entry(dir.to_path_buf())
+		.or_try_insert_with(|| Owner::create(dir))?;
+	Ok(owner.id.clone())
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Encoding
+// ═══════════════════════════════════════════════════════════════════════════
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+	out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+	out.extend_from_slice(bytes);
+}
+
+fn write_optional(out: &mut Vec<u8>, bytes: Option<&[u8]>) {
+	match bytes {
+		Some(bytes) => {
+			out.push(1);
+			write_bytes(out, bytes);
+		},
+		None => out.push(0),
+	}
+}
+
+fn encode(entries: &[JournalEntry]) -> io::Result<Vec<u8>> {
+	let mut out = Vec::new();
+	out.extend_from_slice(MAGIC);
+	out.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+	for entry in entries {
+		let path = entry.path.to_str().ok_or_else(|| {
+			io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("Path is not valid UTF-8: {}", entry.path.display()),
+			)
+		})?;
+		write_bytes(&mut out, path.as_bytes());
+		write_optional(&mut out, entry.before.as_deref());
+		write_optional(&mut out, entry.after.as_deref());
+	}
+	Ok(out)
+}
+
+struct Decoder<'a> {
+	data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+	fn truncated() -> io::Error {
+		io::Error::new(io::ErrorKind::UnexpectedEof, "Journal is truncated")
+	}
+
+	fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+		if self.data.len() < len {
+			return Err(Self::truncated());
+		}
+		let (head, tail) = self.data.split_at(len);
+		self.data = tail;
+		Ok(head)
+	}
+
+	fn u64(&mut self) -> io::Result<u64> {
+		let bytes = self.take(8)?;
+		Ok(u64::from_le_bytes(bytes.try_into().map_err(|_| Self::truncated())?))
+	}
+
+	fn bytes(&mut self) -> io::Result<&'a [u8]> {
+		let len = usize::try_from(self.u64()?).map_err(|_| Self::truncated())?;
+		self.take(len)
+	}
+
+	fn optional(&mut self) -> io::Result<Option<Vec<u8>>> {
+		match self.take(1)?[0] {
+			0 => Ok(None),
+			_ => Ok(Some(self.bytes()?.to_vec())),
+		}
+	}
+}
+
+fn decode(data: &[u8]) -> io::Result<Vec<JournalEntry>> {
+	let mut decoder = Decoder { data };
+	if decoder.take(MAGIC.len())? != MAGIC {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a journal file"));
+	}
+	let count = decoder.u64()?;
+	let mut entries = Vec::new();
+	for _ in 0..count {
+		let path = std::str::from_utf8(decoder.bytes()?)
+			.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+		let before = decoder.optional()?;
+		let after = decoder.optional()?;
+		entries.push(JournalEntry { path: PathBuf::from(path), before, after });
+	}
+	Ok(entries)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Apply / replay
+// ═══════════════════════════════════════════════════════════════════════════
+
+fn write_state(path: &Path, content: Option<&[u8]>) -> io::Result<()> {
+	match content {
+		Some(content) => {
+			if let Some(parent) = path.parent()
+				&& !parent.as_os_str().is_empty()
+			{
+				fs::create_dir_all(parent)?;
+			}
+			fs::write(path, content)
+		},
+		None => match fs::remove_file(path) {
+			Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+			_ => Ok(()),
+		},
+	}
+}
+
+fn replay(entries: &[JournalEntry], direction: Direction) -> io::Result<()> {
+	for entry in entries {
+		let content = match direction {
+			Direction::Forward => entry.after.as_deref(),
+			Direction::Backward => entry.before.as_deref(),
+		};
+		write_state(&entry.path, content)?;
+	}
+	Ok(())
+}
+
+fn persist(dir: &Path, owner: &str, entries: &[JournalEntry]) -> io::Result<PathBuf> {
+	static SEQ: AtomicU64 = AtomicU64::new(0);
+	let id = format!("{owner}.{}", SEQ.fetch_add(1, Ordering::Relaxed));
+	let temp_path = dir.join(format!("{id}.{TEMP_EXT}"));
+	let final_path = dir.join(format!("{id}.{JOURNAL_EXT}"));
+	let mut file = File::create(&temp_path)?;
+	file.write_all(&encode(entries)?)?;
+	file.sync_all()?;
+	drop(file);
+	fs::rename(&temp_path, &final_path)?;
+	Ok(final_path)
+}
+
+/// Apply `entries` atomically with respect to crashes.
+///
+/// The journal is persisted before the first write and removed after the
+/// last. If a write fails, already-applied entries are rolled back.
+pub fn apply(entries: &[JournalEntry]) -> Result<()> {
+	if entries.is_empty() {
+		return Ok(());
+	}
+	readonly::ensure_writable("Journaled write")?;
+	let dir = journal_dir();
+	let journal_path = owner_id(&dir)
+		.and_then(|owner| persist(&dir, &owner, entries))
+		.map_err(|err| Error::from_reason(format!("Failed to write journal: {err}")))?;
+
+	for (index, entry) in entries.iter().enumerate() {
+		if let Err(err) = write_state(&entry.path, entry.after.as_deref()) {
+			let rollback = replay(&entries[..=index], Direction::Backward);
+			if rollback.is_ok() {
+				let _ = fs::remove_file(&journal_path);
+			}
+			return Err(Error::from_reason(format!(
+				"Failed to write {}: {err}",
+				entry.path.display()
+			)));
+		}
+	}
+
+	let _ = fs::remove_file(&journal_path);
+	Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// N-API
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Options for `recoverJournal`.
+#[napi(object)]
+pub struct RecoverJournalOptions {
+	/// Journal directory (default: `FS_JOURNAL_DIR` or the workspace's
+	/// directory under the user state directory).
+	pub dir:       Option<String>,
+	/// "forward" finishes interrupted writes (default); "backward" restores
+	/// the original contents.
+	pub direction: Option<String>,
+}
+
+/// Outcome of replaying leftover journals.
+#[napi(object)]
+pub struct RecoverJournalResult {
+	/// Number of journals replayed and removed.
+	pub journals:       u32,
+	/// Number of files written or deleted while replaying.
+	#[napi(js_name = "filesRestored")]
+	pub files_restored: u32,
+	/// Journals that could not be replayed (left in place).
+	pub errors:         Option<Vec<String>>,
+}
+
+fn parse_direction(direction: Option<&str>) -> Result<Direction> {
+	match direction.map(str::trim) {
+		None | Some("" | "forward") => Ok(Direction::Forward),
+		Some("backward") => Ok(Direction::Backward),
+		Some(other) => Err(Error::from_reason(format!(
+			"Invalid journal direction: {other} (expected \"forward\" or \"backward\")"
+		))),
+	}
+}
+
+/// Files of one owner found in a journal directory.
+#[derive(Default)]
+struct OwnerFiles {
+	/// `<owner>.lock` exists: a process that may still be running.
+	locked:   bool,
+	journals: Vec<(u64, PathBuf)>,
+	torn:     Vec<PathBuf>,
+}
+
+/// Take the lock of a dead owner; `None` while its process is alive.
+fn lock_dead_owner(lock_path: &Path) -> io::Result<Option<File>> {
+	let file = match File::open(lock_path) {
+		Ok(file) => file,
+		// Removed by a concurrent recovery that already replayed the owner.
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+		Err(err) => return Err(err),
+	};
+	match file.try_lock() {
+		Ok(()) => Ok(Some(file)),
+		Err(TryLockError::WouldBlock) => Ok(None),
+		Err(TryLockError::Error(err)) => Err(err),
+	}
+}
+
+fn recover_sync(dir: &Path, direction: Direction) -> Result<RecoverJournalResult> {
+	let mut result =
+		RecoverJournalResult { journals: 0, files_restored: 0, errors: None };
+	let read_dir = match fs::read_dir(dir) {
+		Ok(read_dir) => read_dir,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(result),
+		Err(err) => {
+			return Err(Error::from_reason(format!("Failed to read {}: {err}", dir.display())));
+		},
+	};
+
+	let mut owners = BTreeMap::<String, OwnerFiles>::new();
+	let mut pending_locks = Vec::new();
+	for entry in read_dir.flatten() {
+		let path = entry.path();
+		let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+			continue;
+		};
+		let Some((owner, rest)) = name.split_once('.') else {
+			continue;
+		};
+		match rest.rsplit_once('.') {
+			Some((seq, JOURNAL_EXT)) => {
+				if let Ok(seq) = seq.parse() {
+					owners
+						.entry(owner.to_string())
+						.or_default()
+						.journals
+						.push((seq, path));
+				}
+			},
+			Some((_, TEMP_EXT)) => owners.entry(owner.to_string()).or_default().torn.push(path),
+			_ if rest == LOCK_EXT => owners.entry(owner.to_string()).or_default().locked = true,
+			_ if rest == PENDING_LOCK_EXT => pending_locks.push(path),
+			_ => {},
+		}
+	}
+
+	let mut errors = Vec::new();
+	for path in pending_locks {
+		// A process that died while setting up its owner.
+		if let Ok(Some(_lock)) = lock_dead_owner(&path) {
+			let _ = fs::remove_file(&path);
+		}
+	}
+	for (owner, mut files) in owners {
+		let lock_path = dir.join(format!("{owner}.{LOCK_EXT}"));
+		// Held until the owner's journals are replayed, so concurrent
+		// recoveries never replay them twice.
+		let _lock = if files.locked {
+			match lock_dead_owner(&lock_path) {
+				Ok(Some(lock)) => Some(lock),
+				Ok(None) => continue,
+				Err(err) => {
+					errors.push(format!("{}: {err}", lock_path.display()));
+					continue;
+				},
+			}
+		} else {
+			None
+		};
+
+		// Torn journal: no write was applied yet, drop it.
+		for torn in &files.torn {
+			let _ = fs::remove_file(torn);
+		}
+		files.journals.sort();
+		let mut failed = false;
+		for (_, journal) in &files.journals {
+			let replayed = File::open(journal)
+				.and_then(|mut file| {
+					let mut data = Vec::new();
+					file.read_to_end(&mut data)?;
+					Ok(data)
+				})
+				.and_then(|data| decode(&data))
+				.and_then(|entries| replay(&entries, direction).map(|()| entries.len()));
+			match replayed {
+				Ok(count) => {
+					let _ = fs::remove_file(journal);
+					result.journals = result.journals.saturating_add(1);
+					result.files_restored = result
+						.files_restored
+						.saturating_add(crate::utils::clamp_u32(count as u64));
+				},
+				Err(err) => {
+					failed = true;
+					errors.push(format!("{}: {err}", journal.display()));
+				},
+			}
+		}
+		if files.locked && !failed {
+			let _ = fs::remove_file(&lock_path);
+		}
+	}
+	result.errors = (!errors.is_empty()).then_some(errors);
+	Ok(result)
+}
+
+/// Replay journals left behind by an interrupted multi-file write.
+///
+/// Journals of processes that are still running are left alone.
+///
+/// # Arguments
+/// - `options`: Journal directory and replay direction.
+///
+/// # Returns
+/// Number of journals and files recovered, plus any journals that failed.
+#[napi(js_name = "recoverJournal")]
+pub fn recover_journal(
+	options: Option<RecoverJournalOptions>,
+) -> Result<task::Async<RecoverJournalResult>> {
+	let (dir, direction) = options.map_or((None, None), |opt| (opt.dir, opt.direction));
+	readonly::ensure_writable("recoverJournal")?;
+	let direction = parse_direction(direction.as_deref())?;
+	let dir = dir.map_or_else(journal_dir, PathBuf::from);
+	Ok(task::blocking("recover_journal", (), move |_| recover_sync(&dir, direction)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn scratch_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("pi-journal-test-{name}-{}", next_owner_id()));
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn recovers_interrupted_apply_in_both_directions() {
+		let root = scratch_dir("recover");
+		let edited = root.join("edited.txt");
+		let created = root.join("created.txt");
+		fs::write(&edited, "old").unwrap();
+		let entries = vec![
+			JournalEntry {
+				path:   edited.clone(),
+				before: Some(b"old".to_vec()),
+				after:  Some(b"new".to_vec()),
+			},
+			JournalEntry { path: created.clone(), before: None, after: Some(b"fresh".to_vec()) },
+		];
+
+		// Simulate a crash after the journal landed but before any write.
+		let journals = root.join("journals");
+		let owner = Owner::create(&journals).unwrap();
+		persist(&journals, &owner.id, &entries).unwrap();
+		drop(owner);
+		let forward = recover_sync(&journals, Direction::Forward).unwrap();
+		assert_eq!((forward.journals, forward.files_restored), (1, 2));
+		assert_eq!(fs::read_to_string(&edited).unwrap(), "new");
+		assert_eq!(fs::read_to_string(&created).unwrap(), "fresh");
+
+		let owner = Owner::create(&journals).unwrap();
+		persist(&journals, &owner.id, &entries).unwrap();
+		drop(owner);
+		recover_sync(&journals, Direction::Backward).unwrap();
+		assert_eq!(fs::read_to_string(&edited).unwrap(), "old");
+		assert!(!created.exists());
+		assert_eq!(fs::read_dir(&journals).unwrap().count(), 0);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn skips_journals_of_live_owners() {
+		let root = scratch_dir("live");
+		let target = root.join("target.txt");
+		let entries =
+			vec![JournalEntry { path: target.clone(), before: None, after: Some(b"x".to_vec()) }];
+		let journals = root.join("journals");
+		let owner = Owner::create(&journals).unwrap();
+		persist(&journals, &owner.id, &entries).unwrap();
+
+		let live = recover_sync(&journals, Direction::Forward).unwrap();
+		assert_eq!((live.journals, live.errors.is_none()), (0, true));
+		assert!(!target.exists());
+		assert_eq!(fs::read_dir(&journals).unwrap().count(), 2);
+
+		drop(owner);
+		let dead = recover_sync(&journals, Direction::Forward).unwrap();
+		assert_eq!(dead.journals, 1);
+		assert_eq!(fs::read_to_string(&target).unwrap(), "x");
+		assert_eq!(fs::read_dir(&journals).unwrap().count(), 0);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+}
@@ -0,0 +1,937 @@
+//! JSONC/JSON5 parsing with source spans, and comment-preserving edits.
+//!
+//! `parseJsonc()` returns a value tree whose nodes carry their source offsets.
+//! `editJsonc()` sets or removes the value at a path by splicing the original
+//! text, so comments, key order, and formatting elsewhere in the file survive.
+//! Both accept JSON5: comments, trailing commas, single-quoted strings,
+//! unquoted keys, hex numbers, `Infinity`, and `NaN`.
+
+use std::fmt::Write as _;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Parser
+// ═══════════════════════════════════════════════════════════════════════════
+
+enum Value {
+	Object(Vec<Item>),
+	Array(Vec<Item>),
+	String(String),
+	Number(f64),
+	Bool(bool),
+	Null,
+}
+
+/// A parsed value and its byte span in the source.
+struct Node {
+	value: Value,
+	start: usize,
+	end:   usize,
+}
+
+/// An object member or array element.
+struct Item {
+	/// Member name and the byte span of its key (objects only).
+	key:   Option<(String, usize, usize)>,
+	node:  Node,
+	/// Byte offset of the comma after the item, if any.
+	comma: Option<usize>,
+}
+
+impl Item {
+	fn start(&self) -> usize {
+		self
+			.key
+			.as_ref()
+			.map_or(self.node.start, |(_, start, _)| *start)
+	}
+
+	fn end_with_comma(&self) -> usize {
+		self.comma.map_or(self.node.end, |comma| comma + 1)
+	}
+}
+
+fn is_ident_start(ch: char) -> bool {
+	ch.is_alphabetic() || ch == '_' || ch == '$'
+}
+
+fn is_ident_continue(ch: char) -> bool {
+	ch.is_alphanumeric() || ch == '_' || ch == '$'
+}
+
+/// Deepest nesting of objects and arrays the parser accepts. The parser
+/// recurses per level, so deeper input would overflow the stack.
+const MAX_DEPTH: usize = 512;
+
+struct Parser<'a> {
+	src:   &'a str,
+	pos:   usize,
+	/// Containers currently open.
+	depth: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn error<T>(&self, message: &str) -> Result<T> {
+		let before = &self.src[..self.pos];
+		let line = before.matches('\n').count() + 1;
+		let column = before
+			.rfind('\n')
+			.map_or(before, |newline| &before[newline + 1..])
+			.chars()
+			.count()
+			+ 1;
+		Err(Error::from_reason(format!("Invalid JSONC at {line}:{column}: {message}")))
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.src[self.pos..].chars().next()
+	}
+
+	fn skip_trivia(&mut self) -> Result<()> {
+		loop {
+			let rest = &self.src[self.pos..];
+			match self.peek() {
+				Some(ch) if ch.is_whitespace() || ch == '\u{feff}' => self.pos += ch.len_utf8(),
+				Some('/') if rest.starts_with("//") => {
+					self.pos += rest.find('\n').unwrap_or(rest.len());
+				},
+				Some('/') if rest.starts_with("/*") => {
+					let Some(close) = rest[2..].find("*/") else {
+						return self.error("unterminated block comment");
+					};
+					self.pos += close + 4;
+				},
+				_ => return Ok(()),
+			}
+		}
+	}
+
+	fn parse_value(&mut self) -> Result<Node> {
+		self.skip_trivia()?;
+		let start = self.pos;
+		let value = match self.peek() {
+			Some('{') => self.parse_container(true)?,
+			Some('[') => self.parse_container(false)?,
+			Some(quote @ ('"' | '\'')) => Value::String(self.parse_string(quote)?),
+			Some(ch) if ch.is_ascii_digit() || matches!(ch, '-' | '+' | '.') => {
+				Value::Number(self.parse_number()?)
+			},
+			Some(ch) if is_ident_start(ch) => match self.parse_ident() {
+				"true" => Value::Bool(true),
+				"false" => Value::Bool(false),
+				"null" => Value::Null,
+				"Infinity" => Value::Number(f64::INFINITY),
+				"NaN" => Value::Number(f64::NAN),
+				word => {
+					self.pos = start;
+					return self.error(&format!("unexpected identifier `{word}`"));
+				},
+			},
+			Some(ch) => return self.error(&format!("unexpected character `{ch}`")),
+			None => return self.error("unexpected end of input"),
+		};
+		Ok(Node { value, start, end: self.pos })
+	}
+
+	fn parse_container(&mut self, object: bool) -> Result<Value> {
+		if self.depth >= MAX_DEPTH {
+			return self.error(&format!("nesting deeper than {MAX_DEPTH} levels"));
+		}
+		self.depth += 1;
+		let close = if object { '}' } else { ']' };
+		self.pos += 1;
+		let mut items: Vec<Item> = Vec::new();
+		loop {
+			self.skip_trivia()?;
+			if self.peek() == Some(close) {
+				self.pos += 1;
+				break;
+			}
+			if items.last().is_some_and(|item| item.comma.is_none()) {
+				return self.error(&format!("expected `,` or `{close}`"));
+			}
+			let key = if object {
+				Some(self.parse_key()?)
+			} else {
+				None
+			};
+			let node = self.parse_value()?;
+			self.skip_trivia()?;
+			let comma = (self.peek() == Some(',')).then_some(self.pos);
+			if comma.is_some() {
+				self.pos += 1;
+			}
+			items.push(Item { key, node, comma });
+		}
+		self.depth -= 1;
+		Ok(if object {
+			Value::Object(items)
+		} else {
+			Value::Array(items)
+		})
+	}
+
+	fn parse_key(&mut self) -> Result<(String, usize, usize)> {
+		let start = self.pos;
+		let key = match self.peek() {
+			Some(quote @ ('"' | '\'')) => self.parse_string(quote)?,
+			Some(ch) if is_ident_start(ch) => self.parse_ident().to_string(),
+			_ => return self.error("expected a member name"),
+		};
+		let end = self.pos;
+		self.skip_trivia()?;
+		if self.peek() != Some(':') {
+			return self.error("expected `:`");
+		}
+		self.pos += 1;
+		Ok((key, start, end))
+	}
+
+	fn parse_ident(&mut self) -> &'a str {
+		let start = self.pos;
+		while let Some(ch) = self.peek()
+			&& is_ident_continue(ch)
+		{
+			self.pos += ch.len_utf8();
+		}
+		&self.src[start..self.pos]
+	}
+
+	fn parse_number(&mut self) -> Result<f64> {
+		let start = self.pos;
+		let sign = match self.peek() {
+			Some('-') => -1.0,
+			_ => 1.0,
+		};
+		if matches!(self.peek(), Some('-' | '+')) {
+			self.pos += 1;
+		}
+		let rest = &self.src[self.pos..];
+		if rest.starts_with("Infinity") {
+			self.pos += "Infinity".len();
+			return Ok(sign * f64::INFINITY);
+		}
+		if rest.starts_with("NaN") {
+			self.pos += "NaN".len();
+			return Ok(f64::NAN);
+		}
+		let hex = rest.starts_with("0x") || rest.starts_with("0X");
+		if hex {
+			self.pos += 2;
+		}
+		let digits_start = self.pos;
+		while let Some(ch) = self.peek()
+			&& (ch.is_ascii_hexdigit() || matches!(ch, '.' | '+' | '-') && !hex)
+		{
+			// A sign only continues a number as part of an exponent.
+			if matches!(ch, '+' | '-') && !self.src[..self.pos].ends_with(['e', 'E']) {
+				break;
+			}
+			self.pos += 1;
+		}
+		let digits = &self.src[digits_start..self.pos];
+		let parsed = if hex {
+			u64::from_str_radix(digits, 16)
+				.ok()
+				.map(|value| value as f64)
+		} else {
+			digits
+				.parse::<f64>()
+				.ok()
+				.filter(|_| digits.bytes().any(|byte| byte.is_ascii_digit()))
+		};
+		let Some(value) = parsed else {
+			self.pos = start;
+			return self.error("invalid number");
+		};
+		Ok(sign * value)
+	}
+
+	fn parse_string(&mut self, quote: char) -> Result<String> {
+		let start = self.pos;
+		self.pos += 1;
+		let mut out = String::new();
+		loop {
+			let Some(ch) = self.peek() else {
+				self.pos = start;
+				return self.error("unterminated string");
+			};
+			self.pos += ch.len_utf8();
+			match ch {
+				ch if ch == quote => return Ok(out),
+				'\n' | '\r' => {
+					self.pos = start;
+					return self.error("unterminated string");
+				},
+				'\\' => self.parse_escape(&mut out)?,
+				ch => out.push(ch),
+			}
+		}
+	}
+
+	fn parse_escape(&mut self, out: &mut String) -> Result<()> {
+		let Some(ch) = self.peek() else {
+			return self.error("unterminated string");
+		};
+		self.pos += ch.len_utf8();
+		match ch {
+			'b' => out.push('\u{8}'),
+			'f' => out.push('\u{c}'),
+			'n' => out.push('\n'),
+			'r' => out.push('\r'),
+			't' => out.push('\t'),
+			'v' => out.push('\u{b}'),
+			'0' => out.push('\0'),
+			// Line continuations.
+			'\r' => {
+				if self.peek() == Some('\n') {
+					self.pos += 1;
+				}
+			},
+			'\n' | '\u{2028}' | '\u{2029}' => {},
+			'x' => {
+				let code = self.hex_digits(2)?;
+				out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+			},
+			'u' => {
+				let high = self.hex_digits(4)?;
+				let mut code = high;
+				if (0xd800..0xdc00).contains(&high) && self.src[self.pos..].starts_with("\\u") {
+					self.pos += 2;
+					let low = self.hex_digits(4)?;
+					if (0xdc00..0xe000).contains(&low) {
+						code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+					} else {
+						// Unpaired: read the second escape on its own.
+						self.pos -= 6;
+					}
+				}
+				out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+			},
+			ch => out.push(ch),
+		}
+		Ok(())
+	}
+
+	fn hex_digits(&mut self, count: usize) -> Result<u32> {
+		let Some(value) = self
+			.src
+			.get(self.pos..self.pos + count)
+			.filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+			.and_then(|digits| u32::from_str_radix(digits, 16).ok())
+		else {
+			return self.error("invalid escape sequence");
+		};
+		self.pos += count;
+		Ok(value)
+	}
+}
+
+fn parse(src: &str) -> Result<Node> {
+	let mut parser = Parser { src, pos: 0, depth: 0 };
+	let root = parser.parse_value()?;
+	parser.skip_trivia()?;
+	if parser.pos < src.len() {
+		return parser.error("unexpected content after the value");
+	}
+	Ok(root)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Parse API
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A node of a parsed JSONC document. Offsets are UTF-16 code units, so
+/// `text.slice(start, end)` is the node's source.
+#[napi(object)]
+pub struct JsoncNode {
+	/// Value type.
+	#[napi(ts_type = "\"object\" | \"array\" | \"string\" | \"number\" | \"boolean\" | \"null\"")]
+	pub kind:      String,
+	/// Start of the value.
+	pub start:     u32,
+	/// End of the value (exclusive).
+	pub end:       u32,
+	/// Member name, for object members.
+	pub key:       Option<String>,
+	/// Start of the member name, including quotes.
+	#[napi(js_name = "keyStart")]
+	pub key_start: Option<u32>,
+	/// End of the member name (exclusive).
+	#[napi(js_name = "keyEnd")]
+	pub key_end:   Option<u32>,
+	/// Decoded value of strings, numbers, and booleans.
+	pub value:     Option<Either3<String, f64, bool>>,
+	/// Members or elements, for objects and arrays.
+	pub children:  Option<Vec<Self>>,
+}
+
+/// Byte to UTF-16 offset conversion for one source text.
+struct Utf16Offsets {
+	/// UTF-16 offset at each byte offset; empty when the text is ASCII.
+	table: Vec<u32>,
+}
+
+impl Utf16Offsets {
+	fn new(src: &str) -> Self {
+		if src.is_ascii() {
+			return Self { table: Vec::new() };
+		}
+		let mut table = vec![0u32; src.len() + 1];
+		let mut offset = 0u32;
+		for (byte, ch) in src.char_indices() {
+			table[byte] = offset;
+			offset += ch.len_utf16() as u32;
+		}
+		table[src.len()] = offset;
+		Self { table }
+	}
+
+	fn get(&self, byte: usize) -> u32 {
+		self
+			.table
+			.get(byte)
+			.copied()
+			.unwrap_or_else(|| crate::utils::clamp_u32(byte as u64))
+	}
+}
+
+fn to_js(node: Node, key: Option<(String, usize, usize)>, offsets: &Utf16Offsets) -> JsoncNode {
+	let (kind, value, children) = match node.value {
+		Value::Object(items) => {
+			let children = items
+				.into_iter()
+				.map(|item| to_js(item.node, item.key, offsets))
+				.collect();
+			("object", None, Some(children))
+		},
+		Value::Array(items) => {
+			let children = items
+				.into_iter()
+				.map(|item| to_js(item.node, None, offsets))
+				.collect();
+			("array", None, Some(children))
+		},
+		Value::String(text) => ("string", Some(Either3::A(text)), None),
+		Value::Number(number) => ("number", Some(Either3::B(number)), None),
+		Value::Bool(flag) => ("boolean", Some(Either3::C(flag)), None),
+		Value::Null => ("null", None, None),
+	};
+	let (key, key_start, key_end) = match key {
+		Some((name, start, end)) => (Some(name), Some(offsets.get(start)), Some(offsets.get(end))),
+		None => (None, None, None),
+	};
+	JsoncNode {
+		kind: kind.to_string(),
+		start: offsets.get(node.start),
+		end: offsets.get(node.end),
+		key,
+		key_start,
+		key_end,
+		value,
+		children,
+	}
+}
+
+/// Parse JSONC/JSON5 text into a tree of nodes with source spans.
+///
+/// # Errors
+/// Returns an error with the line and column of the first syntax error, or of
+/// the first container nested deeper than 512 levels.
+#[napi(js_name = "parseJsonc")]
+pub fn parse_jsonc(text: String) -> Result<JsoncNode> {
+	let root = parse(&text)?;
+	Ok(to_js(root, None, &Utf16Offsets::new(&text)))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Edits
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// One step of an edit path.
+enum Segment {
+	Key(String),
+	Index(usize),
+}
+
+fn describe_path(path: &[Segment]) -> String {
+	if path.is_empty() {
+		return "the root".to_string();
+	}
+	let mut out = String::new();
+	for segment in path {
+		match segment {
+			Segment::Key(key) if out.is_empty() => out.push_str(key),
+			Segment::Key(key) => {
+				out.push('.');
+				out.push_str(key);
+			},
+			Segment::Index(index) => {
+				let _ = write!(out, "[{index}]");
+			},
+		}
+	}
+	out
+}
+
+fn quote(key: &str) -> String {
+	serde_json::Value::String(key.to_string()).to_string()
+}
+
+/// How inserted values are laid out: the file's indent unit, and whether
+/// containers span several lines.
+struct Layout<'a> {
+	unit:      &'a str,
+	multiline: bool,
+}
+
+/// Indentation of the first indented line, or two spaces.
+fn detect_indent(src: &str) -> &str {
+	src.lines()
+		.find_map(|line| {
+			let content = line.trim_start_matches([' ', '\t']);
+			let indent = &line[..line.len() - content.len()];
+			(!indent.is_empty() && !content.is_empty()).then(|| {
+				if indent.starts_with('\t') {
+					"\t"
+				} else {
+					indent
+				}
+			})
+		})
+		.unwrap_or("  ")
+}
+
+fn line_start(src: &str, pos: usize) -> usize {
+	src[..pos].rfind('\n').map_or(0, |newline| newline + 1)
+}
+
+/// End of the line containing `pos`, before its line break.
+fn line_end(src: &str, pos: usize) -> usize {
+	let end = src[pos..]
+		.find('\n')
+		.map_or(src.len(), |newline| pos + newline);
+	if src[..end].ends_with('\r') {
+		end - 1
+	} else {
+		end
+	}
+}
+
+fn line_indent(src: &str, pos: usize) -> &str {
+	let line = &src[line_start(src, pos)..];
+	&line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Whether only indentation precedes `pos` on its line.
+fn starts_line(src: &str, pos: usize) -> bool {
+	src[line_start(src, pos)..pos]
+		.chars()
+		.all(|ch| ch == ' ' || ch == '\t')
+}
+
+/// Whether the rest of the line after `pos` is blank or a line comment.
+fn rest_is_trivia(src: &str, pos: usize) -> bool {
+	let rest = src[pos..line_end(src, pos)].trim();
+	rest.is_empty() || rest.starts_with("//")
+}
+
+fn format_value(node: &Node, src: &str, layout: &Layout<'_>, indent: &str, out: &mut String) {
+	let (items, open, close) = match &node.value {
+		Value::Object(items) => (items, '{', '}'),
+		Value::Array(items) => (items, '[', ']'),
+		_ => {
+			out.push_str(&src[node.start..node.end]);
+			return;
+		},
+	};
+	out.push(open);
+	if items.is_empty() {
+		out.push(close);
+		return;
+	}
+	let inner = format!("{indent}{}", layout.unit);
+	let object = open == '{';
+	for (index, item) in items.iter().enumerate() {
+		if index > 0 {
+			out.push(',');
+		}
+		if layout.multiline {
+			out.push('\n');
+			out.push_str(&inner);
+		} else if index > 0 || object {
+			out.push(' ');
+		}
+		if let Some((key, ..)) = &item.key {
+			out.push_str(&quote(key));
+			out.push_str(": ");
+		}
+		format_value(&item.node, src, layout, &inner, out);
+	}
+	if layout.multiline {
+		out.push('\n');
+		out.push_str(indent);
+	} else if object {
+		out.push(' ');
+	}
+	out.push(close);
+}
+
+/// A replacement of a byte range.
+type Splice = (usize, usize, String);
+
+/// Applies `splices` back to front; insertions at the same offset keep their
+/// order.
+fn apply(src: &str, splices: Vec<Splice>) -> String {
+	let mut splices: Vec<(usize, Splice)> = splices.into_iter().enumerate().collect();
+	splices.sort_by_key(|(order, (start, ..))| std::cmp::Reverse((*start, *order)));
+	let mut out = src.to_string();
+	for (_, (start, end, text)) in splices {
+		out.replace_range(start..end, &text);
+	}
+	out
+}
+
+/// Splices that remove `items[index]` from `container`, along with its comma
+/// and, when it sits on its own lines, those lines.
+fn remove_item(src: &str, container: &Node, items: &[Item], index: usize) -> Vec<Splice> {
+	let item = &items[index];
+	let own_lines = starts_line(src, item.start());
+	if let Some(next) = items.get(index + 1) {
+		let after = item.end_with_comma();
+		if own_lines && rest_is_trivia(src, after) {
+			let end = src[after..]
+				.find('\n')
+				.map_or(src.len(), |newline| after + newline + 1);
+			return vec![(line_start(src, item.start()), end, String::new())];
+		}
+		return vec![(item.start(), next.start(), String::new())];
+	}
+	if index == 0 {
+		let (inner_start, inner_end) = (container.start + 1, container.end - 1);
+		let after = item.end_with_comma();
+		if src[inner_start..item.start()].trim().is_empty() && src[after..inner_end].trim().is_empty()
+		{
+			return vec![(inner_start, inner_end, String::new())];
+		}
+		return vec![(item.start(), after, String::new())];
+	}
+	// Last of several: drop the separating comma unless the list keeps a
+	// trailing one.
+	let previous = &items[index - 1];
+	if item.comma.is_some() {
+		let after = item.end_with_comma();
+		if own_lines && rest_is_trivia(src, after) {
+			let end = src[after..]
+				.find('\n')
+				.map_or(src.len(), |newline| after + newline + 1);
+			return vec![(line_start(src, item.start()), end, String::new())];
+		}
+		return vec![(previous.end_with_comma(), after, String::new())];
+	}
+	let mut splices = Vec::new();
+	if let Some(comma) = previous.comma {
+		splices.push((comma, comma + 1, String::new()));
+	}
+	if own_lines {
+		let start = line_start(src, item.start()).saturating_sub(1);
+		let start = if src[..start].ends_with('\r') {
+			start - 1
+		} else {
+			start
+		};
+		let end = if rest_is_trivia(src, item.node.end) {
+			line_end(src, item.node.end)
+		} else {
+			item.node.end
+		};
+		splices.push((start.max(previous.end_with_comma()), end, String::new()));
+	} else {
+		let start = src[..item.start()].trim_end().len();
+		splices.push((start.max(previous.end_with_comma()), item.node.end, String::new()));
+	}
+	splices
+}
+
+/// Splices that add `member` (already formatted, including any key) as the
+/// last item of `container`.
+fn insert_item(
+	src: &str,
+	container: &Node,
+	items: &[Item],
+	member: &str,
+	multiline: bool,
+	unit: &str,
+) -> Vec<Splice> {
+	let (inner_start, inner_end) = (container.start + 1, container.end - 1);
+	let Some(last) = items.last() else {
+		let inner = &src[inner_start..inner_end];
+		let parent_indent = line_indent(src, container.start);
+		return if multiline {
+			let text = format!("\n{parent_indent}{unit}{member}");
+			if inner.trim().is_empty() {
+				vec![(inner_start, inner_end, format!("{text}\n{parent_indent}"))]
+			} else {
+				vec![(inner_start, inner_start, text)]
+			}
+		} else if inner.trim().is_empty() {
+			let padded = if src[container.start..].starts_with('{') {
+				format!(" {member} ")
+			} else {
+				member.to_string()
+			};
+			vec![(inner_start, inner_end, padded)]
+		} else {
+			vec![(inner_start, inner_start, format!(" {member}"))]
+		};
+	};
+	if !multiline {
+		return match last.comma {
+			Some(comma) => vec![(comma + 1, comma + 1, format!(" {member},"))],
+			None => vec![(last.node.end, last.node.end, format!(", {member}"))],
+		};
+	}
+	let indent = if starts_line(src, last.start()) {
+		line_indent(src, last.start()).to_string()
+	} else {
+		format!("{}{unit}", line_indent(src, container.start))
+	};
+	// Insert after any comment trailing the last item on its line.
+	let after = last.end_with_comma();
+	let at = if rest_is_trivia(src, after) {
+		line_end(src, after).min(inner_end)
+	} else {
+		after
+	};
+	match last.comma {
+		Some(_) => vec![(at, at, format!("\n{indent}{member},"))],
+		None => vec![
+			(last.node.end, last.node.end, ",".to_string()),
+			(at, at, format!("\n{indent}{member}")),
+		],
+	}
+}
+
+fn edit(src: &str, path: &[Segment], value: Option<&str>) -> Result<String> {
+	let root = parse(src)?;
+	let unit = detect_indent(src);
+	let Some((last_segment, parents)) = path.split_last() else {
+		let Some(value) = value else {
+			return Err(Error::from_reason("editJsonc: cannot remove the root value"));
+		};
+		let node = parse(value)?;
+		let mut text = String::new();
+		format_value(&node, value, &Layout { unit, multiline: true }, "", &mut text);
+		return Ok(apply(src, vec![(root.start, root.end, text)]));
+	};
+
+	// Walk to the parent container, noting where the path stops existing.
+	let mut container = &root;
+	let mut depth = 0;
+	for segment in parents {
+		let Some(found) = find_item(container, segment, &path[..depth])? else {
+			break;
+		};
+		container = found;
+		depth += 1;
+	}
+	let multiline = src[container.start..container.end].contains('\n');
+	let items = match &container.value {
+		Value::Object(items) | Value::Array(items) => items.as_slice(),
+		_ => unreachable!("find_item only descends into containers"),
+	};
+
+	if depth == parents.len()
+		&& let Some(index) = item_index(container, last_segment, &path[..depth])?
+	{
+		let item = &items[index];
+		let Some(value) = value else {
+			return Ok(apply(src, remove_item(src, container, items, index)));
+		};
+		let node = parse(value)?;
+		let mut text = String::new();
+		let indent = line_indent(src, item.start());
+		format_value(&node, value, &Layout { unit, multiline }, indent, &mut text);
+		return Ok(apply(src, vec![(item.node.start, item.node.end, text)]));
+	}
+
+	// Missing: nothing to remove, or create the rest of the path.
+	let Some(value) = value else {
+		return Ok(src.to_string());
+	};
+	let segment = &path[depth];
+	if let Segment::Index(index) = segment
+		&& *index != items.len()
+	{
+		return Err(Error::from_reason(format!(
+			"editJsonc: index {index} is out of range at {}",
+			describe_path(&path[..depth])
+		)));
+	}
+	let mut wrapped = value.to_string();
+	for (offset, segment) in path[depth + 1..].iter().enumerate().rev() {
+		let Segment::Key(key) = segment else {
+			return Err(Error::from_reason(format!(
+				"editJsonc: cannot create array element {}",
+				describe_path(&path[..=depth + 1 + offset])
+			)));
+		};
+		wrapped = format!("{{{}: {wrapped}}}", quote(key));
+	}
+	let node = parse(&wrapped)?;
+	let multiline = multiline || (items.is_empty() && src.contains('\n'));
+	let indent = if multiline {
+		match items.last() {
+			Some(last) if starts_line(src, last.start()) => line_indent(src, last.start()).to_string(),
+			_ => format!("{}{unit}", line_indent(src, container.start)),
+		}
+	} else {
+		String::new()
+	};
+	let mut member = match segment {
+		Segment::Key(key) => format!("{}: ", quote(key)),
+		Segment::Index(_) => String::new(),
+	};
+	format_value(&node, &wrapped, &Layout { unit, multiline }, &indent, &mut member);
+	Ok(apply(src, insert_item(src, container, items, &member, multiline, unit)))
+}
+
+/// Index of the item `segment` names in `container`; errors if the container
+/// has the wrong type.
+fn item_index(container: &Node, segment: &Segment, at: &[Segment]) -> Result<Option<usize>> {
+	match (&container.value, segment) {
+		(Value::Object(items), Segment::Key(key)) => Ok(items
+			.iter()
+			.rposition(|item| item.key.as_ref().is_some_and(|(name, ..)| name == key))),
+		(Value::Array(items), Segment::Index(index)) => Ok((*index < items.len()).then_some(*index)),
+		(_, Segment::Key(_)) => {
+			Err(Error::from_reason(format!("editJsonc: {} is not an object", describe_path(at))))
+		},
+		(_, Segment::Index(_)) => {
+			Err(Error::from_reason(format!("editJsonc: {} is not an array", describe_path(at))))
+		},
+	}
+}
+
+fn find_item<'a>(
+	container: &'a Node,
+	segment: &Segment,
+	at: &[Segment],
+) -> Result<Option<&'a Node>> {
+	let index = item_index(container, segment, at)?;
+	Ok(index.map(|index| match &container.value {
+		Value::Object(items) | Value::Array(items) => &items[index].node,
+		_ => unreachable!("item_index only matches containers"),
+	}))
+}
+
+/// Set or remove the value at `path` in JSONC/JSON5 text, changing only the
+/// affected span so comments and formatting elsewhere are preserved.
+///
+/// `value` is the new value as JSON text; `None` removes the member or
+/// element. Missing object members along the path are created.
+///
+/// # Errors
+/// Returns an error if `text` or `value` does not parse, if the path crosses
+/// a value of the wrong type, or if an array index is past the end.
+#[napi(js_name = "editJsonc")]
+pub fn edit_jsonc(
+	text: String,
+	path: Vec<Either<String, u32>>,
+	value: Option<String>,
+) -> Result<String> {
+	let path: Vec<Segment> = path
+		.into_iter()
+		.map(|segment| match segment {
+			Either::A(key) => Segment::Key(key),
+			Either::B(index) => Segment::Index(index as usize),
+		})
+		.collect();
+	edit(&text, &path, value.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn key(name: &str) -> Segment {
+		Segment::Key(name.to_string())
+	}
+
+	#[test]
+	fn parses_json5_with_spans() {
+		let src = "// settings\n{\n  unquoted: 'it\\'s',\n  \"hex\": 0x1F, nan: NaN,\n  list: [1, \
+		           .5, -Infinity,],\n  \"é\": true, /* c */\n}";
+		let root = parse_jsonc(src.to_string()).unwrap();
+		assert_eq!(root.kind, "object");
+		let children = root.children.unwrap();
+		let keys: Vec<_> = children
+			.iter()
+			.map(|child| child.key.as_deref().unwrap())
+			.collect();
+		assert_eq!(keys, ["unquoted", "hex", "nan", "list", "é"]);
+		assert!(matches!(&children[0].value, Some(Either3::A(text)) if text == "it's"));
+		assert!(matches!(children[1].value, Some(Either3::B(31.0))));
+		assert!(matches!(children[2].value, Some(Either3::B(number)) if number.is_nan()));
+		let list = children[3].children.as_ref().unwrap();
+		assert!(matches!(list[2].value, Some(Either3::B(number)) if number == f64::NEG_INFINITY));
+		let utf16: Vec<u16> = src.encode_utf16().collect();
+		let slice =
+			|start: u32, end: u32| String::from_utf16(&utf16[start as usize..end as usize]).unwrap();
+		assert_eq!(slice(children[4].start, children[4].end), "true");
+		assert_eq!(slice(children[4].key_start.unwrap(), children[4].key_end.unwrap()), "\"é\"");
+
+		let err = parse_jsonc("{\n  \"a\": 1\n  \"b\": 2\n}".to_string())
+			.err()
+			.expect("missing comma should fail");
+		assert!(err.reason.contains("3:3"), "{}", err.reason);
+	}
+
+	#[test]
+	fn rejects_excessive_nesting() {
+		let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+		assert!(parse_jsonc(nested(MAX_DEPTH)).is_ok());
+
+		let err = parse_jsonc(nested(MAX_DEPTH + 1))
+			.err()
+			.expect("nesting past the limit should fail");
+		assert!(err.reason.contains("nesting deeper than 512 levels"), "{}", err.reason);
+		let err = parse_jsonc("{\"a\":".repeat(100_000))
+			.err()
+			.expect("deep objects should fail without overflowing the stack");
+		assert!(err.reason.contains("nesting"), "{}", err.reason);
+	}
+
+	#[test]
+	fn edits_preserve_comments() {
+		let src = "{\n\t// compiler\n\t\"compilerOptions\": {\n\t\t\"strict\": false, // \
+		           loose\n\t\t\"target\": \"es5\"\n\t},\n\t\"include\": [\"src\"]\n}\n";
+
+		let set = edit(src, &[key("compilerOptions"), key("strict")], Some("true")).unwrap();
+		assert_eq!(set, src.replace("\"strict\": false", "\"strict\": true"));
+
+		let added =
+			edit(src, &[key("compilerOptions"), key("paths"), key("@/*")], Some("[\"src/*\"]"))
+				.unwrap();
+		assert!(
+			added.contains(
+				"\"target\": \"es5\",\n\t\t\"paths\": {\n\t\t\t\"@/*\": \
+				 [\n\t\t\t\t\"src/*\"\n\t\t\t]\n\t\t}\n\t},"
+			),
+			"{added}"
+		);
+
+		let removed = edit(src, &[key("compilerOptions"), key("strict")], None).unwrap();
+		assert_eq!(removed, src.replace("\t\t\"strict\": false, // loose\n", ""));
+		let removed = edit(src, &[key("compilerOptions"), key("target")], None).unwrap();
+		assert_eq!(removed, src.replace(", // loose\n\t\t\"target\": \"es5\"", " // loose"));
+
+		let appended = edit(src, &[key("include"), Segment::Index(1)], Some("\"test\"")).unwrap();
+		assert!(appended.contains("[\"src\", \"test\"]"), "{appended}");
+		assert!(edit(src, &[key("include"), Segment::Index(5)], Some("1")).is_err());
+		assert!(edit(src, &[key("include"), key("x")], Some("1")).is_err());
+		assert_eq!(edit(src, &[key("missing")], None).unwrap(), src);
+	}
+}
@@ -12,6 +12,7 @@
 
 use std::borrow::Cow;
 
+use napi::{Error, Result};
 use napi_derive::napi;
 use phf::phf_map;
 
@@ -30,6 +31,7 @@ const FUNC_PAGE_DOWN: i32 = -13;
 const FUNC_HOME: i32 = -14;
 const FUNC_END: i32 = -15;
 const FUNC_CLEAR: i32 = -16;
+const FUNC_MENU: i32 = -17;
 
 const FUNC_F1: i32 = -20;
 const FUNC_F2: i32 = -21;
@@ -61,10 +63,20 @@ const CP_KP_7: i32 = 57406;
 const CP_KP_8: i32 = 57407;
 const CP_KP_9: i32 = 57408;
 const CP_KP_DECIMAL: i32 = 57409;
+const CP_KP_DIVIDE: i32 = 57410;
+const CP_KP_MULTIPLY: i32 = 57411;
+const CP_KP_SUBTRACT: i32 = 57412;
+const CP_KP_ADD: i32 = 57413;
+const CP_KP_EQUAL: i32 = 57415;
+const CP_PRINT_SCREEN: i32 = 57361;
+const CP_MENU: i32 = 57363;
 
 const MOD_SHIFT: u32 = 1;
 const MOD_ALT: u32 = 2;
 const MOD_CTRL: u32 = 4;
+const MOD_SUPER: u32 = 8;
+const MOD_HYPER: u32 = 16;
+const MOD_META: u32 = 32;
 const MOD_NUM_LOCK: u32 = 128;
 
 #[inline]
@@ -85,6 +97,19 @@ const fn map_keypad_nav(codepoint: i32) -> Option<i32> {
 	}
 }
 
+/// Keypad operator keys send the same character with or without num lock.
+#[inline]
+const fn map_keypad_operator(codepoint: i32) -> Option<i32> {
+	match codepoint {
+		CP_KP_DIVIDE => Some(b'/' as i32),
+		CP_KP_MULTIPLY => Some(b'*' as i32),
+		CP_KP_SUBTRACT => Some(b'-' as i32),
+		CP_KP_ADD => Some(b'+' as i32),
+		CP_KP_EQUAL => Some(b'=' as i32),
+		_ => None,
+	}
+}
+
 #[inline]
 const fn keypad_num_lock_text(codepoint: i32) -> Option<&'static str> {
 	match codepoint {
@@ -122,7 +147,7 @@ pub struct ParsedKittyResult {
 	pub shifted_key:     Option<i32>,
 	/// Optional base layout key codepoint from the sequence.
 	pub base_layout_key: Option<i32>,
-	/// Modifier bitmask (shift/alt/ctrl), excluding lock bits.
+	/// Modifier bitmask (shift/alt/ctrl/super/hyper/meta), excluding lock bits.
 	pub modifier:        u32,
 	/// Optional event type (1 = press, 2 = repeat, 3 = release).
 	pub event_type:      Option<u32>,
@@ -138,6 +163,8 @@ static LEGACY_SEQUENCES: phf::Map<&'static [u8], &'static str> = phf_map! {
 	b"\x1b[H" => "home", b"\x1b[F" => "end",
 	b"\x1b[1~" => "home", b"\x1b[7~" => "home",
 	b"\x1b[4~" => "end", b"\x1b[8~" => "end",
+	// Keypad operators (application keypad mode)
+	b"\x1bOo" => "/", b"\x1bOj" => "*", b"\x1bOm" => "-", b"\x1bOk" => "+",
 	// Clear
 	b"\x1b[E" => "clear", b"\x1bOE" => "clear", b"\x1bOe" => "ctrl+clear", b"\x1b[e" => "shift+clear",
 	// Insert/Delete
@@ -257,9 +284,7 @@ pub fn matches_kitty_sequence(
 		&& base == expected_codepoint
 	{
 		let cp = parsed.codepoint;
-		let is_ascii_letter = u8::try_from(cp)
-			.ok()
-			.is_some_and(|b| b.is_ascii_alphabetic());
+		let is_ascii_letter = u8::try_from(cp).is_ok_and(|b| b.is_ascii_alphabetic());
 		let is_known_symbol = is_symbol_key(cp);
 		if !is_ascii_letter && !is_known_symbol {
 			return true;
@@ -396,6 +421,18 @@ fn parse_key_id(key_id: &str) -> Option<ParsedKeyId<'_>> {
 				modifier |= MOD_ALT;
 				continue;
 			},
+			b's' | b'S' if p.eq_ignore_ascii_case("super") => {
+				modifier |= MOD_SUPER;
+				continue;
+			},
+			b'h' | b'H' if p.eq_ignore_ascii_case("hyper") => {
+				modifier |= MOD_HYPER;
+				continue;
+			},
+			b'm' | b'M' if p.eq_ignore_ascii_case("meta") => {
+				modifier |= MOD_META;
+				continue;
+			},
 			_ => {},
 		}
 
@@ -468,13 +505,34 @@ fn parse_modify_other_keys(bytes: &[u8]) -> Option<(u32, i32)> {
 	Some((modifier, keycode))
 }
 
+/// Terminal input parsed once for matching against many key ids.
+struct KeyInput<'a> {
+	bytes: &'a [u8],
+	kitty: Option<ParsedKittySequence>,
+	mok:   Option<(u32, i32)>,
+}
+
+impl<'a> KeyInput<'a> {
+	fn parse(bytes: &'a [u8]) -> Self {
+		Self { bytes, kitty: parse_kitty_sequence(bytes), mok: parse_modify_other_keys(bytes) }
+	}
+}
+
 fn matches_key_inner(bytes: &[u8], key_id: &str, kitty_protocol_active: bool) -> bool {
 	let Some(ParsedKeyId { key, modifier }) = parse_key_id(key_id) else {
 		return false;
 	};
+	matches_parsed_key(&KeyInput::parse(bytes), key, modifier, kitty_protocol_active)
+}
 
-	// Parse Kitty once (avoid repeated parsing in branches).
-	let kitty_parsed = parse_kitty_sequence(bytes);
+fn matches_parsed_key(
+	input: &KeyInput<'_>,
+	key: &str,
+	modifier: u32,
+	kitty_protocol_active: bool,
+) -> bool {
+	let bytes = input.bytes;
+	let kitty_parsed = &input.kitty;
 	let kitty_matches = |codepoint: i32, m: u32| -> bool {
 		let Some(p) = kitty_parsed.as_ref() else {
 			return false;
@@ -487,7 +545,7 @@ fn matches_key_inner(bytes: &[u8], key_id: &str, kitty_protocol_active: bool) ->
 		if actual_mod != expected_mod {
 			return false;
 		}
-		let mut parsed_codepoint = p.codepoint;
+		let mut parsed_codepoint = map_keypad_operator(p.codepoint).unwrap_or(p.codepoint);
 		let mut parsed_base = p.base_layout_key;
 		if p.text_codepoint.is_none() {
 			if p.modifier & MOD_NUM_LOCK != 0 {
@@ -524,9 +582,8 @@ fn matches_key_inner(bytes: &[u8], key_id: &str, kitty_protocol_active: bool) ->
 		if let Some(base) = parsed_base
 			&& base == codepoint
 		{
-			let is_ascii_letter = u8::try_from(parsed_codepoint)
-				.ok()
-				.is_some_and(|b| b.is_ascii_alphabetic());
+			let is_ascii_letter =
+				u8::try_from(parsed_codepoint).is_ok_and(|b| b.is_ascii_alphabetic());
 			let is_known_symbol = is_symbol_key(parsed_codepoint);
 			if !is_ascii_letter && !is_known_symbol {
 				return true;
@@ -535,8 +592,7 @@ fn matches_key_inner(bytes: &[u8], key_id: &str, kitty_protocol_active: bool) ->
 		false
 	};
 
-	// Parse modifyOtherKeys once.
-	let mok = parse_modify_other_keys(bytes);
+	let mok = input.mok;
 	let mok_matches =
 		|keycode: i32, m: u32| -> bool { mok.is_some_and(|(mm, kk)| kk == keycode && mm == m) };
 
@@ -745,6 +801,14 @@ fn matches_key_inner(bytes: &[u8], key_id: &str, kitty_protocol_active: bool) ->
 			|| kitty_matches(ARROW_RIGHT, modifier);
 	}
 
+	if key.eq_ignore_ascii_case("menu") {
+		return kitty_matches(FUNC_MENU, modifier) || kitty_matches(CP_MENU, modifier);
+	}
+
+	if key.eq_ignore_ascii_case("printscreen") {
+		return kitty_matches(CP_PRINT_SCREEN, modifier);
+	}
+
 	// Function keys (now allow modifiers via CSI forms too)
 	let f_code = match key.as_bytes() {
 		[b'f' | b'F', n @ b'1'..=b'9'] => Some(FUNC_F1 + (n - b'1') as i32),
@@ -821,8 +885,10 @@ fn matches_key_inner(bytes: &[u8], key_id: &str, kitty_protocol_active: bool) ->
 			return kitty_matches(codepoint, modifier) || mok_matches(codepoint, modifier);
 		}
 
-		// plain key
-		return (bytes.len() == 1 && bytes[0] == ch) || kitty_matches(codepoint, 0);
+		// plain key (keypad operators send SS3 in application keypad mode)
+		return (bytes.len() == 1 && bytes[0] == ch)
+			|| matches_legacy_key(bytes, key)
+			|| kitty_matches(codepoint, 0);
 	}
 
 	false
@@ -1197,6 +1263,8 @@ fn parse_functional(bytes: &[u8]) -> Option<ParsedKittySequence> {
 		23 => FUNC_F11,
 		24 => FUNC_F12,
 
+		29 => FUNC_MENU,
+
 		_ => return None,
 	};
 
@@ -1216,14 +1284,9 @@ fn parse_functional(bytes: &[u8]) -> Option<ParsedKittySequence> {
 
 fn format_kitty_key(parsed: &ParsedKittySequence) -> Option<Cow<'static, str>> {
 	let effective_mod = parsed.modifier & !LOCK_MASK;
-	if effective_mod & !(MOD_SHIFT | MOD_CTRL | MOD_ALT) != 0 {
-		return None;
-	}
 	let effective_codepoint = {
 		let cp = parsed.codepoint;
-		let is_ascii_letter = u8::try_from(cp)
-			.ok()
-			.is_some_and(|b| b.is_ascii_alphabetic());
+		let is_ascii_letter = u8::try_from(cp).is_ok_and(|b| b.is_ascii_alphabetic());
 		let is_known_symbol = is_symbol_key(cp);
 		if is_ascii_letter || is_known_symbol {
 			cp
@@ -1269,6 +1332,13 @@ fn format_key_name(codepoint: i32) -> Option<&'static str> {
 		CP_KP_8 => Some("up"),
 		CP_KP_9 => Some("pageUp"),
 		CP_KP_DECIMAL => Some("delete"),
+		CP_KP_DIVIDE => Some("/"),
+		CP_KP_MULTIPLY => Some("*"),
+		CP_KP_SUBTRACT => Some("-"),
+		CP_KP_ADD => Some("+"),
+		CP_KP_EQUAL => Some("="),
+		CP_PRINT_SCREEN => Some("printScreen"),
+		CP_MENU | FUNC_MENU => Some("menu"),
 
 		FUNC_DELETE => Some("delete"),
 		FUNC_INSERT => Some("insert"),
@@ -1314,10 +1384,356 @@ fn format_with_mods(mods: u32, key_name: &str) -> String {
 	if mods & MOD_ALT != 0 {
 		result.push_str("alt+");
 	}
+	if mods & MOD_SUPER != 0 {
+		result.push_str("super+");
+	}
+	if mods & MOD_HYPER != 0 {
+		result.push_str("hyper+");
+	}
+	if mods & MOD_META != 0 {
+		result.push_str("meta+");
+	}
 	result.push_str(key_name);
 	result
 }
 
+// =============================================================================
+// Key Binding Sets
+// =============================================================================
+
+/// A list of key ids compiled once and matched against input together.
+/// Each input is parsed a single time, however many bindings there are.
+#[napi]
+pub struct KeyBindingSet {
+	/// (key, modifier) per binding, in input order.
+	bindings: Vec<(String, u32)>,
+}
+
+#[napi]
+impl KeyBindingSet {
+	/// Compile `key_ids` (e.g. "ctrl+c", "super+k"). Fails on an id with no
+	/// key.
+	#[napi(constructor)]
+	pub fn new(key_ids: Vec<String>) -> Result<Self> {
+		let bindings = key_ids
+			.iter()
+			.map(|key_id| {
+				parse_key_id(key_id)
+					.map(|ParsedKeyId { key, modifier }| (key.to_string(), modifier))
+					.ok_or_else(|| Error::from_reason(format!("Invalid key id: {key_id:?}")))
+			})
+			.collect::<Result<Vec<_>>>()?;
+		Ok(Self { bindings })
+	}
+
+	/// Number of bindings in the set.
+	#[napi(getter)]
+	pub fn size(&self) -> u32 {
+		crate::utils::clamp_u32(self.bindings.len() as u64)
+	}
+
+	/// Index of the first binding that `data` matches, or null.
+	#[napi(js_name = "match")]
+	pub fn find(&self, data: String, kitty_protocol_active: bool) -> Option<u32> {
+		self.matching(data.as_bytes(), kitty_protocol_active).next()
+	}
+
+	/// Indices of every binding that `data` matches, in binding order.
+	#[napi(js_name = "matchAll")]
+	pub fn find_all(&self, data: String, kitty_protocol_active: bool) -> Vec<u32> {
+		self
+			.matching(data.as_bytes(), kitty_protocol_active)
+			.collect()
+	}
+}
+
+impl KeyBindingSet {
+	fn matching<'a>(
+		&'a self,
+		bytes: &'a [u8],
+		kitty_protocol_active: bool,
+	) -> impl Iterator<Item = u32> + 'a {
+		let input = KeyInput::parse(bytes);
+		self
+			.bindings
+			.iter()
+			.enumerate()
+			.filter(move |(_, (key, modifier))| {
+				matches_parsed_key(&input, key, *modifier, kitty_protocol_active)
+			})
+			.map(|(index, _)| crate::utils::clamp_u32(index as u64))
+	}
+}
+
+// =============================================================================
+// Input Splitting
+// =============================================================================
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// One event split from a terminal input buffer.
+#[napi(object)]
+pub struct InputEvent {
+	/// Event kind.
+	#[napi(ts_type = "\"key\" | \"paste\" | \"mouse\" | \"unknown\"")]
+	pub kind: String,
+	/// Raw input of the event; for pastes, the pasted text without markers.
+	pub data: String,
+	/// Normalized key id (as returned by `parseKey`) for recognized keys.
+	pub key:  Option<String>,
+}
+
+/// Events split from a terminal input buffer.
+#[napi(object)]
+pub struct SplitInputResult {
+	/// Complete events, in input order.
+	pub events: Vec<InputEvent>,
+	/// Trailing incomplete sequence or paste, to prepend to the next chunk.
+	pub rest:   String,
+}
+
+/// Length of the string-terminated sequence (OSC/DCS/APC/PM/SOS) at the
+/// start of `bytes`, ending at BEL (OSC only) or ST.
+fn string_sequence_len(bytes: &[u8], allow_bel: bool) -> Option<usize> {
+	let mut i = 2;
+	while i < bytes.len() {
+		match bytes[i] {
+			0x07 if allow_bel => return Some(i + 1),
+			0x1b if bytes.get(i + 1) == Some(&b'\\') => return Some(i + 2),
+			_ => i += 1,
+		}
+	}
+	None
+}
+
+/// Length of the UTF-8 character starting with `lead`. Input comes from a JS
+/// string, so it is always valid.
+const fn utf8_char_len(lead: u8) -> usize {
+	match lead {
+		0x00..=0x7f => 1,
+		0xc0..=0xdf => 2,
+		0xe0..=0xef => 3,
+		_ => 4,
+	}
+}
+
+/// Length of the escape sequence at the start of `bytes` (which begins with
+/// ESC), or `None` when more input is needed to complete it.
+fn escape_sequence_len(bytes: &[u8]) -> Option<usize> {
+	match *bytes.get(1)? {
+		b'[' => {
+			// X10 mouse: ESC [ M + three characters (coordinates past 95 are
+			// non-ASCII).
+			if bytes.get(2) == Some(&b'M') {
+				let mut len = 3;
+				for _ in 0..3 {
+					len += utf8_char_len(*bytes.get(len)?);
+				}
+				return (len <= bytes.len()).then_some(len);
+			}
+			bytes[2..]
+				.iter()
+				.position(|b| (0x40..=0x7e).contains(b))
+				.map(|pos| pos + 3)
+		},
+		b']' => string_sequence_len(bytes, true),
+		b'P' | b'_' | b'^' | b'X' => string_sequence_len(bytes, false),
+		b'O' => (bytes.len() >= 3).then_some(3),
+		// ESC + one character (alt/meta prefix).
+		lead => {
+			let len = 1 + utf8_char_len(lead);
+			(len <= bytes.len()).then_some(len)
+		},
+	}
+}
+
+const fn is_mouse_sequence(seq: &[u8]) -> bool {
+	matches!(seq, [0x1b, b'[', b'<', .., b'M' | b'm'] | [0x1b, b'[', b'M', ..])
+}
+
+fn classify_input(seq: &[u8], kitty_active: bool) -> InputEvent {
+	let data = String::from_utf8_lossy(seq).into_owned();
+	if is_mouse_sequence(seq) {
+		return InputEvent { kind: "mouse".to_string(), data, key: None };
+	}
+	let key = parse_key_inner(seq, kitty_active).map(Cow::into_owned);
+	// Kitty release events and unnamed characters are still keys.
+	let is_key = key.is_some()
+		|| seq.first() != Some(&0x1b)
+		|| parse_kitty_sequence(seq).is_some()
+		|| parse_modify_other_keys(seq).is_some();
+	let kind = if is_key { "key" } else { "unknown" };
+	InputEvent { kind: kind.to_string(), data, key }
+}
+
+fn split_input_events_inner(
+	data: &[u8],
+	kitty_active: bool,
+	flush: bool,
+) -> (Vec<InputEvent>, usize) {
+	let mut events = Vec::new();
+	let mut pos = 0;
+	while pos < data.len() {
+		let rest = &data[pos..];
+		if rest.starts_with(PASTE_START) {
+			let body = &rest[PASTE_START.len()..];
+			let Some(end) = body.windows(PASTE_END.len()).position(|w| w == PASTE_END) else {
+				if !flush {
+					return (events, pos);
+				}
+				events.push(InputEvent {
+					kind: "paste".to_string(),
+					data: String::from_utf8_lossy(body).into_owned(),
+					key:  None,
+				});
+				return (events, data.len());
+			};
+			events.push(InputEvent {
+				kind: "paste".to_string(),
+				data: String::from_utf8_lossy(&body[..end]).into_owned(),
+				key:  None,
+			});
+			pos += PASTE_START.len() + end + PASTE_END.len();
+			continue;
+		}
+		let len = if rest[0] == 0x1b {
+			match escape_sequence_len(rest) {
+				Some(len) => len,
+				None if flush => rest.len(),
+				None => return (events, pos),
+			}
+		} else {
+			utf8_char_len(rest[0]).min(rest.len())
+		};
+		events.push(classify_input(&rest[..len], kitty_active));
+		pos += len;
+	}
+	(events, pos)
+}
+
+/// Split a terminal input buffer into key, bracketed paste, mouse, and unknown
+/// events.
+///
+/// A trailing incomplete escape sequence or paste is returned in `rest` for
+/// the caller to prepend to the next chunk; with `flush` (e.g. after an input
+/// timeout) it is emitted as-is instead, so a lone ESC becomes the escape key.
+#[napi(js_name = "splitInputEvents")]
+pub fn split_input_events(
+	data: String,
+	kitty_active: bool,
+	flush: Option<bool>,
+) -> SplitInputResult {
+	let bytes = data.as_bytes();
+	let (events, consumed) = split_input_events_inner(bytes, kitty_active, flush.unwrap_or(false));
+	SplitInputResult { events, rest: data[consumed..].to_string() }
+}
+
+// =============================================================================
+// Bracketed Paste
+// =============================================================================
+
+/// A run of ordinary input or a complete bracketed paste.
+#[napi(object)]
+pub struct PasteSegment {
+	/// Segment kind.
+	#[napi(ts_type = "\"input\" | \"paste\"")]
+	pub kind: String,
+	/// Input text, or the pasted text without markers.
+	pub data: String,
+}
+
+/// Length of the longest suffix of `data` that is a proper prefix of
+/// `marker`, i.e. a marker possibly completed by the next read.
+fn partial_marker_len(data: &[u8], marker: &[u8]) -> usize {
+	(1..marker.len().min(data.len() + 1))
+		.rev()
+		.find(|&len| data.ends_with(&marker[..len]))
+		.unwrap_or(0)
+}
+
+/// Incremental bracketed paste parser. Pastes split across reads are
+/// buffered and returned whole once `ESC[201~` arrives; a marker split across
+/// reads is held back until it is complete.
+#[napi]
+#[derive(Default)]
+pub struct BracketedPasteParser {
+	pending:  String,
+	in_paste: bool,
+	/// Bytes of `pending` already searched for the end marker.
+	scanned:  usize,
+}
+
+#[napi]
+impl BracketedPasteParser {
+	/// Create a parser outside of any paste.
+	#[napi(constructor)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Whether a paste has started but not yet ended.
+	#[napi(getter, js_name = "inPaste")]
+	pub const fn in_paste(&self) -> bool {
+		self.in_paste
+	}
+
+	/// Feed the next read; returns the input runs and pastes it completes, in
+	/// order.
+	#[napi]
+	pub fn push(&mut self, chunk: String) -> Vec<PasteSegment> {
+		self.pending.push_str(&chunk);
+		let mut segments = Vec::new();
+		loop {
+			let marker = if self.in_paste {
+				PASTE_END
+			} else {
+				PASTE_START
+			};
+			let bytes = self.pending.as_bytes();
+			let found = bytes[self.scanned..]
+				.windows(marker.len())
+				.position(|w| w == marker)
+				.map(|pos| pos + self.scanned);
+			let end = found.unwrap_or_else(|| bytes.len() - partial_marker_len(bytes, marker));
+			// A paste is only emitted once it is complete.
+			if !self.in_paste && end > 0 {
+				segments.push(PasteSegment {
+					kind: "input".to_string(),
+					data: self.pending[..end].to_string(),
+				});
+			}
+			let Some(start) = found else {
+				if self.in_paste {
+					self.scanned = end;
+				} else {
+					self.pending.drain(..end);
+				}
+				return segments;
+			};
+			if self.in_paste {
+				segments.push(PasteSegment {
+					kind: "paste".to_string(),
+					data: self.pending[..start].to_string(),
+				});
+			}
+			self.pending.drain(..start + marker.len());
+			self.in_paste = !self.in_paste;
+			self.scanned = 0;
+		}
+	}
+
+	/// Release held-back input (e.g. a lone ESC after an input timeout). An
+	/// unfinished paste keeps buffering.
+	#[napi]
+	pub fn flush(&mut self) -> Vec<PasteSegment> {
+		if self.in_paste || self.pending.is_empty() {
+			return Vec::new();
+		}
+		vec![PasteSegment { kind: "input".to_string(), data: std::mem::take(&mut self.pending) }]
+	}
+}
+
 // =============================================================================
 // Digit Parsing Helpers
 // =============================================================================
@@ -1359,8 +1775,28 @@ mod tests {
 	}
 
 	#[test]
-	fn parse_key_ignores_kitty_sequences_with_unsupported_modifiers() {
-		assert_eq!(parse_key_inner(b"\x1b[99;9u", true).as_deref(), None);
+	fn parse_key_formats_super_hyper_and_meta_modifiers() {
+		assert_eq!(parse_key_inner(b"\x1b[99;9u", true).as_deref(), Some("super+c"));
+		assert_eq!(
+			parse_key_inner(b"\x1b[107;61u", true).as_deref(),
+			Some("ctrl+super+hyper+meta+k")
+		);
+		assert!(matches_key_inner(b"\x1b[107;9u", "super+k", true));
+		assert!(matches_key_inner(b"\x1b[107;21u", "hyper+ctrl+k", true));
+		assert!(!matches_key_inner(b"\x1b[107;9u", "ctrl+k", true));
+	}
+
+	#[test]
+	fn parse_key_names_menu_print_screen_and_keypad_operators() {
+		assert_eq!(parse_key_inner(b"\x1b[57363u", true).as_deref(), Some("menu"));
+		assert_eq!(parse_key_inner(b"\x1b[29;5~", false).as_deref(), Some("ctrl+menu"));
+		assert_eq!(parse_key_inner(b"\x1b[57361;9u", true).as_deref(), Some("super+printScreen"));
+		assert_eq!(parse_key_inner(b"\x1b[57413u", true).as_deref(), Some("+"));
+		assert_eq!(parse_key_inner(b"\x1bOo", false).as_deref(), Some("/"));
+		assert!(matches_key_inner(b"\x1b[29~", "menu", false));
+		assert!(matches_key_inner(b"\x1b[57361u", "printScreen", true));
+		assert!(matches_key_inner(b"\x1b[57411;5u", "ctrl+*", true));
+		assert!(matches_key_inner(b"\x1bOk", "plus", false));
 	}
 
 	#[test]
@@ -1370,6 +1806,76 @@ mod tests {
 		assert_eq!(parse_key_inner(b"\x1b[127;1:3u", true).as_deref(), None);
 	}
 
+	#[test]
+	fn split_input_events_segments_mixed_buffers() {
+		let (events, consumed) = split_input_events_inner(
+			"a\x1b[A\x1b[200~pasted\ntext\x1b[201~\x1b[<0;10;5M\x1b]11;rgb:0/0/0\x07é\x1b[".as_bytes(),
+			true,
+			false,
+		);
+		let summary: Vec<(&str, &str, Option<&str>)> = events
+			.iter()
+			.map(|e| (e.kind.as_str(), e.data.as_str(), e.key.as_deref()))
+			.collect();
+		assert_eq!(summary, [
+			("key", "a", Some("a")),
+			("key", "\x1b[A", Some("up")),
+			("paste", "pasted\ntext", None),
+			("mouse", "\x1b[<0;10;5M", None),
+			("unknown", "\x1b]11;rgb:0/0/0\x07", None),
+			("key", "é", None),
+		]);
+		// The partial CSI is left for the next chunk.
+		assert_eq!(
+			consumed,
+			"a\x1b[A\x1b[200~pasted\ntext\x1b[201~\x1b[<0;10;5M\x1b]11;rgb:0/0/0\x07é".len()
+		);
+		// X10 mouse coordinates past 95 arrive as non-ASCII characters.
+		let (events, consumed) = split_input_events_inner("\x1b[M ÀÁ\x1b".as_bytes(), false, false);
+		assert_eq!((events[0].kind.as_str(), consumed), ("mouse", "\x1b[M ÀÁ".len()));
+		let (events, consumed) = split_input_events_inner(b"\x1b", false, true);
+		assert_eq!((events[0].key.as_deref(), consumed), (Some("escape"), 1));
+	}
+
+	#[test]
+	fn bracketed_paste_parser_joins_split_pastes() {
+		let mut parser = BracketedPasteParser::new();
+		let mut push = |chunk: &str| -> Vec<(String, String)> {
+			parser
+				.push(chunk.to_string())
+				.into_iter()
+				.map(|s| (s.kind, s.data))
+				.collect()
+		};
+		let seg = |kind: &str, data: &str| (kind.to_string(), data.to_string());
+		assert_eq!(push("ab\x1b[20"), [seg("input", "ab")]);
+		assert_eq!(push("0~hel"), []);
+		assert_eq!(push("lo\x1b[201"), []);
+		assert_eq!(push("~cd\x1b[200~x\x1b[201~\x1b"), [
+			seg("paste", "hello"),
+			seg("input", "cd"),
+			seg("paste", "x")
+		]);
+		assert!(!parser.in_paste());
+		assert_eq!(parser.flush().pop().map(|s| s.data).as_deref(), Some("\x1b"));
+	}
+
+	#[test]
+	fn key_binding_set_matches_compiled_ids() {
+		let set = KeyBindingSet::new(
+			["ctrl+c", "up", "super+k", "escape", "ctrl+c"]
+				.map(String::from)
+				.to_vec(),
+		)
+		.unwrap();
+		assert_eq!(set.find("\x03".to_string(), false), Some(0));
+		assert_eq!(set.find_all("\x1b[99;5u".to_string(), true), [0, 4]);
+		assert_eq!(set.find("\x1bOA".to_string(), false), Some(1));
+		assert_eq!(set.find("\x1b[107;9u".to_string(), true), Some(2));
+		assert_eq!(set.find("x".to_string(), false), None);
+		assert!(KeyBindingSet::new(vec!["ctrl+".to_string()]).is_err());
+	}
+
 	#[test]
 	fn num_lock_keypad_digits_stay_text() {
 		assert_eq!(parse_key_inner(b"\x1b[57400;129u", true).as_deref(), Some("1"));
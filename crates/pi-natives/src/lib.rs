@@ -23,22 +23,66 @@
 
 pub mod appearance;
 pub mod ast;
+pub mod ast_cache;
+pub mod ast_rule;
+pub mod batch;
+pub mod block;
+pub mod changeset;
+pub mod classify;
 pub mod clipboard;
+pub mod comments;
+pub mod config;
+pub mod delimited;
+pub mod diff;
+pub mod exit_status;
+pub mod expand;
 pub mod fd;
+pub mod files;
 pub mod fs_cache;
+pub mod git;
 pub mod glob;
 pub mod glob_util;
 pub mod grep;
+pub mod hash;
 pub mod highlight;
+pub mod highlight_spans;
 pub mod html;
 pub mod image;
+pub mod injection;
+pub mod journal;
+pub mod jsonc;
 pub mod keys;
 pub mod language;
+pub mod line_index;
+pub mod merge;
+pub mod outline;
+pub mod pack;
+pub mod patch;
 pub mod prof;
+pub mod project;
 pub mod projfs_overlay;
 pub mod ps;
 pub mod pty;
+pub mod readonly;
+pub mod regex_engine;
+pub mod scratch;
+#[cfg(feature = "shell")]
 pub mod shell;
+pub mod similar;
+pub mod sort;
+pub mod stream;
+pub mod symbols;
 pub mod task;
 pub mod text;
 pub(crate) mod utils;
+pub mod watch;
+
+/// Optional cargo features compiled into this addon (e.g. `"shell"`).
+#[napi_derive::napi(js_name = "nativeFeatures")]
+pub fn native_features() -> Vec<String> {
+	let mut features = Vec::new();
+	if cfg!(feature = "shell") {
+		features.push("shell".to_string());
+	}
+	features
+}
@@ -0,0 +1,246 @@
+//! Line offset index for large files.
+//!
+//! `buildLineIndex()` scans a file once and records the byte offset of every
+//! line start, so goto-line, previews, and `readFileLines()` can seek directly
+//! instead of rescanning multi-hundred-MB logs. Indexes are cached per path and
+//! reused until the file's mtime or size changes.
+
+use std::{
+	fs::File,
+	io::{BufRead, BufReader, Read, Seek, SeekFrom},
+	path::{Path, PathBuf},
+	sync::{Arc, LazyLock},
+	time::UNIX_EPOCH,
+};
+
+use dashmap::DashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::{fs_cache, task};
+
+const SCAN_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Immutable line offsets for one version of a file.
+struct LineIndexData {
+	path:     PathBuf,
+	mtime_ms: f64,
+	len:      u64,
+	/// Byte offset of each line start; empty for an empty file.
+	offsets:  Vec<u64>,
+}
+
+static CACHE: LazyLock<DashMap<PathBuf, Arc<LineIndexData>>> = LazyLock::new(DashMap::new);
+
+fn file_version(path: &Path) -> Result<(f64, u64)> {
+	let metadata = std::fs::metadata(path)
+		.map_err(|err| Error::from_reason(format!("Failed to stat {}: {err}", path.display())))?;
+	if !metadata.is_file() {
+		return Err(Error::from_reason(format!("Not a file: {}", path.display())));
+	}
+	let mtime_ms = metadata
+		.modified()
+		.ok()
+		.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+		.map_or(0.0, |duration| duration.as_secs_f64() * 1000.0);
+	Ok((mtime_ms, metadata.len()))
+}
+
+fn scan_offsets(reader: &mut impl Read, len: u64, ct: &task::CancelToken) -> Result<Vec<u64>> {
+	let mut offsets = if len == 0 { Vec::new() } else { vec![0] };
+	let mut buf = vec![0u8; SCAN_CHUNK_BYTES];
+	let mut base = 0u64;
+	loop {
+		ct.heartbeat()?;
+		let read = reader
+			.read(&mut buf)
+			.map_err(|err| Error::from_reason(format!("Failed to read file: {err}")))?;
+		if read == 0 {
+			break;
+		}
+		for (idx, _) in buf[..read]
+			.iter()
+			.enumerate()
+			.filter(|(_, byte)| **byte == b'\n')
+		{
+			let next = base + idx as u64 + 1;
+			if next < len {
+				offsets.push(next);
+			}
+		}
+		base += read as u64;
+	}
+	Ok(offsets)
+}
+
+/// Return the cached index for `path`, rebuilding it if the file changed.
+fn get_or_build(path: &Path, ct: &task::CancelToken) -> Result<Arc<LineIndexData>> {
+	let (mtime_ms, len) = file_version(path)?;
+	if let Some(cached) = CACHE.get(path)
+		&& cached.mtime_ms.to_bits() == mtime_ms.to_bits()
+		&& cached.len == len
+	{
+		return Ok(Arc::clone(&cached));
+	}
+
+	let mut file = File::open(path)
+		.map_err(|err| Error::from_reason(format!("Failed to open {}: {err}", path.display())))?;
+	let offsets = scan_offsets(&mut file, len, ct)?;
+	let data = Arc::new(LineIndexData { path: path.to_path_buf(), mtime_ms, len, offsets });
+	CACHE.insert(path.to_path_buf(), Arc::clone(&data));
+	Ok(data)
+}
+
+/// Read up to `count` lines starting at 1-indexed `start`, without line
+/// terminators.
+fn read_range(data: &LineIndexData, start: u32, count: u32) -> Result<Vec<String>> {
+	let first = start.max(1) as usize - 1;
+	if first >= data.offsets.len() || count == 0 {
+		return Ok(Vec::new());
+	}
+	let last = first.saturating_add(count as usize).min(data.offsets.len());
+	let end = data.offsets.get(last).copied().unwrap_or(data.len);
+
+	let mut file = File::open(&data.path).map_err(|err| {
+		Error::from_reason(format!("Failed to open {}: {err}", data.path.display()))
+	})?;
+	file
+		.seek(SeekFrom::Start(data.offsets[first]))
+		.map_err(|err| Error::from_reason(format!("Failed to seek: {err}")))?;
+	let mut reader = BufReader::new(file.take(end - data.offsets[first]));
+	let mut lines = Vec::with_capacity(last - first);
+	let mut line = Vec::new();
+	while lines.len() < last - first {
+		line.clear();
+		let read = reader
+			.read_until(b'\n', &mut line)
+			.map_err(|err| Error::from_reason(format!("Failed to read file: {err}")))?;
+		if read == 0 {
+			break;
+		}
+		let text = line.strip_suffix(b"\n").unwrap_or(&line);
+		let text = text.strip_suffix(b"\r").unwrap_or(text);
+		lines.push(String::from_utf8_lossy(text).into_owned());
+	}
+	Ok(lines)
+}
+
+/// Handle to a line offset index built by `buildLineIndex()`.
+///
+/// Reflects the file as it was when built; call `buildLineIndex()` again to
+/// pick up changes (unchanged files reuse the cached index).
+#[napi]
+pub struct LineIndex {
+	data: Arc<LineIndexData>,
+}
+
+#[napi]
+impl LineIndex {
+	/// Number of lines in the file.
+	#[napi(getter, js_name = "lineCount")]
+	pub fn line_count(&self) -> u32 {
+		crate::utils::clamp_u32(self.data.offsets.len() as u64)
+	}
+
+	/// File size in bytes when the index was built.
+	#[napi(getter, js_name = "byteLength")]
+	pub fn byte_length(&self) -> i64 {
+		i64::try_from(self.data.len).unwrap_or(i64::MAX)
+	}
+
+	/// File modification time (ms since epoch) when the index was built.
+	#[napi(getter, js_name = "mtimeMs")]
+	pub fn mtime_ms(&self) -> f64 {
+		self.data.mtime_ms
+	}
+
+	/// Byte offset where 1-indexed `line` starts, or `null` when out of range.
+	#[napi(js_name = "lineOffset")]
+	pub fn line_offset(&self, line: u32) -> Option<i64> {
+		let idx = (line as usize).checked_sub(1)?;
+		self
+			.data
+			.offsets
+			.get(idx)
+			.map(|offset| i64::try_from(*offset).unwrap_or(i64::MAX))
+	}
+
+	/// 1-indexed line containing byte `offset`, or `null` when out of range.
+	#[napi(js_name = "lineAt")]
+	pub fn line_at(&self, offset: i64) -> Option<u32> {
+		let offset = u64::try_from(offset).ok()?;
+		if offset >= self.data.len {
+			return None;
+		}
+		let idx = self.data.offsets.partition_point(|start| *start <= offset);
+		Some(crate::utils::clamp_u32(idx as u64))
+	}
+
+	/// Read up to `count` lines starting at 1-indexed `start`.
+	#[napi(js_name = "readLines")]
+	pub fn read_lines(&self, start: u32, count: u32) -> task::Async<Vec<String>> {
+		let data = Arc::clone(&self.data);
+		task::blocking("line_index.read", (), move |_| read_range(&data, start, count))
+	}
+}
+
+/// Build (or reuse) the line offset index for a file.
+///
+/// Runs as background work: the scan yields to interactive calls (see
+/// `setConcurrencyPolicy()`).
+///
+/// # Errors
+/// Fails when the path is not a readable regular file.
+#[napi(js_name = "buildLineIndex")]
+pub fn build_line_index(path: String) -> task::Async<LineIndex> {
+	task::background("line_index.build", (), move |ct| {
+		let path = fs_cache::resolve_path(&path)?;
+		Ok(LineIndex { data: get_or_build(&path, &ct)? })
+	})
+}
+
+/// Read up to `count` lines starting at 1-indexed `start`, using the cached
+/// line index so repeated reads of large files never rescan them.
+#[napi(js_name = "readFileLines")]
+pub fn read_file_lines(path: String, start: u32, count: u32) -> task::Async<Vec<String>> {
+	task::blocking("line_index.read", (), move |ct| {
+		let path = fs_cache::resolve_path(&path)?;
+		let data = get_or_build(&path, &ct)?;
+		read_range(&data, start, count)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::SystemTime;
+
+	use super::*;
+
+	#[test]
+	fn indexes_line_starts_and_reads_ranges() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let path = std::env::temp_dir().join(format!("pi-line-index-test-{unique}.log"));
+		std::fs::write(&path, "alpha\r\nbeta\n\ngamma\n").expect("temp file should be written");
+
+		let index = LineIndex {
+			data: get_or_build(&path, &task::CancelToken::default()).expect("index should build"),
+		};
+		assert_eq!(index.line_count(), 4);
+		assert_eq!(index.line_offset(2), Some(7));
+		assert_eq!(index.line_offset(5), None);
+		assert_eq!(index.line_at(8), Some(2));
+		assert_eq!(index.line_at(13), Some(4));
+		assert!(Arc::ptr_eq(
+			&index.data,
+			&get_or_build(&path, &task::CancelToken::default()).expect("index should be cached")
+		));
+
+		let lines = read_range(&index.data, 2, 10).expect("lines should be read");
+		assert_eq!(lines, vec!["beta", "", "gamma"]);
+
+		let _ = std::fs::remove_file(&path);
+	}
+}
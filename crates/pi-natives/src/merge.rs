@@ -0,0 +1,224 @@
+//! Three-way text merges.
+//!
+//! `merge3()` merges two edited copies of a text against their common base
+//! the way `git merge-file` does: both sides are diffed against the base
+//! with [`diff::diff_lines`], lines unchanged on both sides anchor the merge,
+//! and each region between anchors takes whichever side changed it. Regions
+//! both sides changed differently become conflicts, written with
+//! `<<<<<<<`/`=======`/`>>>>>>>` markers and reported with their line ranges.
+
+use napi_derive::napi;
+
+use crate::diff::{self, LineOp};
+
+/// Options for `merge3()`.
+#[napi(object)]
+#[derive(Default)]
+pub struct Merge3Options {
+	/// Label after `<<<<<<<` (default: "ours").
+	#[napi(js_name = "oursLabel")]
+	pub ours_label:   Option<String>,
+	/// Label after `|||||||` in diff3 style (default: "base").
+	#[napi(js_name = "baseLabel")]
+	pub base_label:   Option<String>,
+	/// Label after `>>>>>>>` (default: "theirs").
+	#[napi(js_name = "theirsLabel")]
+	pub theirs_label: Option<String>,
+	/// Include the base text of conflicts between `|||||||` and `=======`
+	/// (default: false).
+	pub diff3:        Option<bool>,
+}
+
+/// A region both sides changed differently.
+#[napi(object)]
+pub struct MergeConflict {
+	/// Line of the `<<<<<<<` marker in the merged content (1-based).
+	#[napi(js_name = "startLine")]
+	pub start_line:   u32,
+	/// Line of the `>>>>>>>` marker in the merged content (1-based).
+	#[napi(js_name = "endLine")]
+	pub end_line:     u32,
+	/// First conflicting line of the base (1-based).
+	#[napi(js_name = "baseStart")]
+	pub base_start:   u32,
+	/// Conflicting lines of the base.
+	#[napi(js_name = "baseLines")]
+	pub base_lines:   u32,
+	/// First conflicting line of ours (1-based).
+	#[napi(js_name = "oursStart")]
+	pub ours_start:   u32,
+	/// Conflicting lines of ours.
+	#[napi(js_name = "oursLines")]
+	pub ours_lines:   u32,
+	/// First conflicting line of theirs (1-based).
+	#[napi(js_name = "theirsStart")]
+	pub theirs_start: u32,
+	/// Conflicting lines of theirs.
+	#[napi(js_name = "theirsLines")]
+	pub theirs_lines: u32,
+	/// Base text of the region.
+	pub base:         String,
+	/// Our text of the region.
+	pub ours:         String,
+	/// Their text of the region.
+	pub theirs:       String,
+}
+
+/// Result of `merge3()`.
+#[napi(object)]
+pub struct Merge3Result {
+	/// Merged content, with conflict markers around unresolved regions.
+	pub content:   String,
+	/// Whether the merge had no conflicts.
+	pub clean:     bool,
+	/// Conflicts in content order.
+	pub conflicts: Vec<MergeConflict>,
+}
+
+/// For each base line, the line of `side` it is kept as, if any.
+fn matches(base: &[&str], side: &[&str]) -> Vec<Option<usize>> {
+	let mut kept = vec![None; base.len()];
+	for op in diff::diff_lines(base, side) {
+		if let LineOp::Equal { old, new } = op {
+			kept[old] = Some(new);
+		}
+	}
+	kept
+}
+
+/// Append a conflict marker line, ending the previous line first when it has
+/// no newline.
+fn push_marker(out: &mut String, marker: &str, label: &str) {
+	if !out.is_empty() && !out.ends_with('\n') {
+		out.push('\n');
+	}
+	out.push_str(marker);
+	if !label.is_empty() {
+		out.push(' ');
+		out.push_str(label);
+	}
+	out.push('\n');
+}
+
+fn line_number(index: usize) -> u32 {
+	crate::utils::clamp_u32(index as u64 + 1)
+}
+
+fn merge_lines(base: &str, ours: &str, theirs: &str, options: &Merge3Options) -> Merge3Result {
+	let ours_label = options.ours_label.as_deref().unwrap_or("ours");
+	let base_label = options.base_label.as_deref().unwrap_or("base");
+	let theirs_label = options.theirs_label.as_deref().unwrap_or("theirs");
+	let (base, ours, theirs) =
+		(diff::split_lines(base), diff::split_lines(ours), diff::split_lines(theirs));
+	let (in_ours, in_theirs) = (matches(&base, &ours), matches(&base, &theirs));
+
+	let mut out = String::new();
+	let mut lines = 0;
+	let mut conflicts = Vec::new();
+	let (mut o, mut a, mut b) = (0, 0, 0);
+	while o < base.len() || a < ours.len() || b < theirs.len() {
+		// Lines unchanged on both sides.
+		if o < base.len() && in_ours[o] == Some(a) && in_theirs[o] == Some(b) {
+			out.push_str(base[o]);
+			lines += 1;
+			(o, a, b) = (o + 1, a + 1, b + 1);
+			continue;
+		}
+		// The region up to the next base line both sides kept.
+		let (next_o, next_a, next_b) = (o..base.len())
+			.find_map(|index| Some((index, in_ours[index]?, in_theirs[index]?)))
+			.unwrap_or((base.len(), ours.len(), theirs.len()));
+		let (old, left, right) = (&base[o..next_o], &ours[a..next_a], &theirs[b..next_b]);
+		let taken = if left == old || left == right {
+			Some(right)
+		} else if right == old {
+			Some(left)
+		} else {
+			None
+		};
+		if let Some(taken) = taken {
+			out.extend(taken.iter().copied());
+			lines += taken.len();
+		} else {
+			let start_line = lines + 1;
+			push_marker(&mut out, "<<<<<<<", ours_label);
+			out.extend(left.iter().copied());
+			if options.diff3.unwrap_or(false) {
+				push_marker(&mut out, "|||||||", base_label);
+				out.extend(old.iter().copied());
+				lines += old.len() + 1;
+			}
+			push_marker(&mut out, "=======", "");
+			out.extend(right.iter().copied());
+			push_marker(&mut out, ">>>>>>>", theirs_label);
+			lines += left.len() + right.len() + 3;
+			conflicts.push(MergeConflict {
+				start_line:   crate::utils::clamp_u32(start_line as u64),
+				end_line:     crate::utils::clamp_u32(lines as u64),
+				base_start:   line_number(o),
+				base_lines:   crate::utils::clamp_u32(old.len() as u64),
+				ours_start:   line_number(a),
+				ours_lines:   crate::utils::clamp_u32(left.len() as u64),
+				theirs_start: line_number(b),
+				theirs_lines: crate::utils::clamp_u32(right.len() as u64),
+				base:         old.concat(),
+				ours:         left.concat(),
+				theirs:       right.concat(),
+			});
+		}
+		(o, a, b) = (next_o, next_a, next_b);
+	}
+	Merge3Result { content: out, clean: conflicts.is_empty(), conflicts }
+}
+
+/// Merge `ours` and `theirs`, two edits of `base`, line by line.
+///
+/// Regions only one side changed take that side; regions both changed the
+/// same way merge cleanly; the rest are conflicts.
+#[napi(js_name = "merge3")]
+pub fn merge3(
+	base: String,
+	ours: String,
+	theirs: String,
+	options: Option<Merge3Options>,
+) -> Merge3Result {
+	merge_lines(&base, &ours, &theirs, &options.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn merges_independent_edits() {
+		let base = "a\nb\nc\nd\ne\n";
+		let merged =
+			merge_lines(base, "A\nb\nc\nd\ne\n", "a\nb\nc\nd\nE\nf", &Merge3Options::default());
+		assert!(merged.clean);
+		assert_eq!(merged.content, "A\nb\nc\nd\nE\nf");
+
+		let same = merge_lines(base, "a\nB\nc\nd\ne\n", "a\nB\nc\nd\ne\n", &Merge3Options::default());
+		assert_eq!((same.clean, same.content.as_str()), (true, "a\nB\nc\nd\ne\n"));
+	}
+
+	#[test]
+	fn marks_conflicts() {
+		let base = "a\nb\nc\n";
+		let options = Merge3Options { diff3: Some(true), ..Default::default() };
+		let merged = merge_lines(base, "a\nours\nc\n", "a\ntheirs\nc\n", &options);
+		assert!(!merged.clean);
+		assert_eq!(
+			merged.content,
+			"a\n<<<<<<< ours\nours\n||||||| base\nb\n=======\ntheirs\n>>>>>>> theirs\nc\n"
+		);
+		let conflict = &merged.conflicts[0];
+		assert_eq!((conflict.start_line, conflict.end_line), (2, 8));
+		assert_eq!((conflict.base_start, conflict.base_lines), (2, 1));
+		assert_eq!((conflict.ours.as_str(), conflict.theirs.as_str()), ("ours\n", "theirs\n"));
+
+		// Both sides appending different text to a file without a final
+		// newline.
+		let merged = merge_lines("x", "x\ny", "x\nz", &Merge3Options::default());
+		assert_eq!(merged.content, "<<<<<<< ours\nx\ny\n=======\nx\nz\n>>>>>>> theirs\n");
+	}
+}
@@ -0,0 +1,173 @@
+//! Structured outline of a single source file.
+//!
+//! `outline()` turns the declarations found by the symbol index into a tree
+//! for a collapsible outline panel: each node holds the declarations whose
+//! range lies inside its own. Members declared outside their type (Rust
+//! `impl` blocks, Go methods with a receiver) are attached to the type they
+//! belong to when it is declared at the top level of the same file.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::{ast, fs_cache, symbols, task};
+
+/// A declaration in a file outline.
+#[napi(object)]
+pub struct OutlineNode {
+	/// Declaration name.
+	pub name:         String,
+	/// Declaration kind, as reported by `symbolIndex()`.
+	pub kind:         String,
+	/// First line of the declaration (1-indexed).
+	#[napi(js_name = "startLine")]
+	pub start_line:   u32,
+	/// First column of the declaration (1-indexed).
+	#[napi(js_name = "startColumn")]
+	pub start_column: u32,
+	/// Last line of the declaration (1-indexed, inclusive).
+	#[napi(js_name = "endLine")]
+	pub end_line:     u32,
+	/// Column after the declaration's last character (1-indexed).
+	#[napi(js_name = "endColumn")]
+	pub end_column:   u32,
+	/// Nested declarations in source order.
+	pub children:     Vec<Self>,
+}
+
+impl OutlineNode {
+	const fn start(&self) -> (u32, u32) {
+		(self.start_line, self.start_column)
+	}
+
+	const fn end(&self) -> (u32, u32) {
+		(self.end_line, self.end_column)
+	}
+
+	fn contains(&self, other: &Self) -> bool {
+		self.start() <= other.start() && other.end() <= self.end()
+	}
+}
+
+/// Kinds that members declared elsewhere can be attached to.
+fn is_type_kind(kind: &str) -> bool {
+	matches!(kind, "class" | "struct" | "enum" | "interface" | "trait" | "type")
+}
+
+/// Close the innermost open node, attaching it to its parent.
+fn close(stack: &mut Vec<(OutlineNode, Option<String>)>, roots: &mut Vec<OutlineNode>) {
+	let Some((node, container)) = stack.pop() else {
+		return;
+	};
+	if let Some((parent, _)) = stack.last_mut() {
+		parent.children.push(node);
+		return;
+	}
+	let owner = container.and_then(|container| {
+		roots
+			.iter_mut()
+			.rev()
+			.find(|root| root.name == container && is_type_kind(&root.kind))
+	});
+	match owner {
+		Some(owner) => owner.children.push(node),
+		None => roots.push(node),
+	}
+}
+
+/// Nest declarations (in source order) by range containment.
+fn build_tree(symbols: Vec<symbols::SymbolInfo>) -> Vec<OutlineNode> {
+	let mut roots = Vec::new();
+	let mut stack: Vec<(OutlineNode, Option<String>)> = Vec::new();
+	for symbol in symbols {
+		let node = OutlineNode {
+			name:         symbol.name,
+			kind:         symbol.kind,
+			start_line:   symbol.start_line,
+			start_column: symbol.start_column,
+			end_line:     symbol.end_line,
+			end_column:   symbol.end_column,
+			children:     Vec::new(),
+		};
+		while stack.last().is_some_and(|(open, _)| !open.contains(&node)) {
+			close(&mut stack, &mut roots);
+		}
+		stack.push((node, symbol.container));
+	}
+	while !stack.is_empty() {
+		close(&mut stack, &mut roots);
+	}
+	roots
+}
+
+/// Outline of `source` parsed as `lang`.
+fn file_outline(source: String, lang: crate::language::SupportLang) -> Vec<OutlineNode> {
+	let mut symbols = symbols::file_symbols(source, lang, "");
+	// Parents before the declarations they enclose.
+	symbols.sort_by(|a, b| {
+		(a.start_line, a.start_column)
+			.cmp(&(b.start_line, b.start_column))
+			.then_with(|| (b.end_line, b.end_column).cmp(&(a.end_line, a.end_column)))
+	});
+	build_tree(symbols)
+}
+
+/// Build a hierarchical outline of a source file's declarations.
+///
+/// # Errors
+/// Fails when the file cannot be read or its language is unsupported.
+#[napi(js_name = "outline")]
+pub fn outline(path: String, lang: Option<String>) -> Result<task::Async<Vec<OutlineNode>>> {
+	let path = fs_cache::resolve_path(&path)?;
+	let lang = ast::resolve_language(lang.as_deref(), &path)?;
+	Ok(task::blocking("outline", (), move |_| {
+		let source = std::fs::read_to_string(&path)
+			.map_err(|err| Error::from_reason(format!("Failed to read {}: {err}", path.display())))?;
+		Ok(file_outline(source, lang))
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::language::SupportLang;
+
+	fn shape(nodes: &[OutlineNode]) -> Vec<String> {
+		nodes
+			.iter()
+			.map(|node| {
+				let children = shape(&node.children);
+				if children.is_empty() {
+					format!("{} {}", node.kind, node.name)
+				} else {
+					format!("{} {} [{}]", node.kind, node.name, children.join(", "))
+				}
+			})
+			.collect()
+	}
+
+	#[test]
+	fn nests_declarations() {
+		let rust = "mod util {\n    pub fn helper() {}\n}\nstruct Server;\nimpl Server {\n    fn \
+		            run(&self) {}\n}\nfn main() {}\n";
+		let tree = file_outline(rust.to_string(), SupportLang::Rust);
+		assert_eq!(shape(&tree), [
+			"module util [function helper]",
+			"struct Server [method run]",
+			"function main",
+		]);
+		assert_eq!((tree[0].start_line, tree[0].end_line), (1, 3));
+
+		let ts = "export class Store {\n  get(key: string) {}\n  set() {}\n}\nnamespace Api {\n  \
+		          export function call() {}\n}\n";
+		assert_eq!(shape(&file_outline(ts.to_string(), SupportLang::TypeScript)), [
+			"class Store [method get, method set]",
+			"module Api [function call]",
+		]);
+
+		let go = "package main\nfunc (s *Server) Run() {}\ntype Server struct{}\n";
+		assert_eq!(shape(&file_outline(go.to_string(), SupportLang::Go)), [
+			"method Run",
+			"type Server",
+		]);
+	}
+}
@@ -0,0 +1,325 @@
+//! Token-budgeted packing of search results.
+//!
+//! `packResults()` renders `grep`/`astFind` matches as prompt text and keeps
+//! as many as fit a token budget, trimming the entry that straddles the limit
+//! instead of dropping it outright. Token counts come from
+//! [`estimate_tokens`], a tokenizer-free estimate calibrated for source code:
+//! no BPE tables ship with the addon, so the count is an approximation that
+//! errs on the high side.
+
+use std::{collections::HashMap, fmt::Write as _};
+
+use napi_derive::napi;
+
+use crate::grep::ContextLine;
+
+/// Model families that use the GPT ratio besides `gpt-*`, matched as a whole
+/// name or a `-` prefix so that names like "opus-4" are not mistaken for them.
+const OPENAI_REASONING_MODELS: &[&str] = &["o1", "o3", "o4"];
+
+/// Characters per token for a run of letters, by model family.
+///
+/// This is a character-ratio heuristic, not a tokenizer: it only picks the
+/// ratio [`estimate_with`] divides letter runs by.
+fn chars_per_token(model: Option<&str>) -> f64 {
+	let model = model.unwrap_or_default().to_ascii_lowercase();
+	let is_openai_reasoning = OPENAI_REASONING_MODELS.iter().any(|family| {
+		model
+			.strip_prefix(family)
+			.is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+	});
+	if model.starts_with("gpt") || is_openai_reasoning {
+		4.0
+	} else {
+		// Claude and unknown models: the smaller ratio over-counts slightly.
+		3.5
+	}
+}
+
+fn estimate_with(text: &str, chars_per_token: f64) -> u32 {
+	let mut tokens = 0f64;
+	let mut chars = text.chars().peekable();
+	while let Some(ch) = chars.next() {
+		let mut run = 1usize;
+		let mut take_run = |chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+		                    same: fn(char) -> bool| {
+			while chars.peek().copied().is_some_and(same) {
+				chars.next();
+				run += 1;
+			}
+		};
+		if ch.is_ascii_alphabetic() {
+			take_run(&mut chars, |ch| ch.is_ascii_alphabetic());
+			tokens += (run as f64 / chars_per_token).ceil();
+		} else if ch.is_ascii_digit() {
+			take_run(&mut chars, |ch| ch.is_ascii_digit());
+			tokens += (run as f64 / 3.0).ceil();
+		} else if ch == '\n' {
+			take_run(&mut chars, |ch| ch == '\n');
+			tokens += 1.0;
+		} else if ch == ' ' || ch == '\t' {
+			// A single space joins the following word; indentation does not.
+			take_run(&mut chars, |ch| ch == ' ' || ch == '\t');
+			if run > 1 {
+				tokens += (run as f64 / 4.0).ceil();
+			}
+		} else if ch.is_ascii() {
+			tokens += 1.0;
+		} else {
+			// Non-ASCII text rarely shares merges with code: count each
+			// character, and wide scripts a little more.
+			tokens += if ch.len_utf8() >= 3 { 1.5 } else { 1.0 };
+		}
+	}
+	tokens.ceil() as u32
+}
+
+/// Estimate how many tokens `text` uses for `model` (e.g. "claude",
+/// "gpt-4o"; default: Claude).
+pub fn estimate_tokens(text: &str, model: Option<&str>) -> u32 {
+	estimate_with(text, chars_per_token(model))
+}
+
+/// Estimate the token count of `text` without a tokenizer.
+#[napi(js_name = "estimateTokens")]
+pub fn estimate_tokens_napi(text: String, model: Option<String>) -> u32 {
+	estimate_tokens(&text, model.as_deref())
+}
+
+/// A `grep` or `astFind` match to pack; fields of either shape are accepted.
+#[napi(object)]
+pub struct PackInput {
+	/// File path.
+	pub path:           String,
+	/// Matched line number (`grep`).
+	#[napi(js_name = "lineNumber")]
+	pub line_number:    Option<u32>,
+	/// Matched line (`grep`).
+	pub line:           Option<String>,
+	/// First line of the match (`astFind`).
+	#[napi(js_name = "startLine")]
+	pub start_line:     Option<u32>,
+	/// Matched text, possibly several lines (`astFind`).
+	pub text:           Option<String>,
+	/// Context lines before the match (`grep`).
+	#[napi(js_name = "contextBefore")]
+	pub context_before: Option<Vec<ContextLine>>,
+	/// Context lines after the match (`grep`).
+	#[napi(js_name = "contextAfter")]
+	pub context_after:  Option<Vec<ContextLine>>,
+}
+
+/// Options for `packResults()`.
+#[napi(object)]
+pub struct PackResultsOptions {
+	/// Token budget for the packed text.
+	#[napi(js_name = "maxTokens")]
+	pub max_tokens: u32,
+	/// Model family the budget is for (e.g. "claude", "gpt-4o").
+	pub model:      Option<String>,
+}
+
+/// A match left out of the packed text.
+#[napi(object)]
+pub struct PackDropped {
+	/// File path.
+	pub path: String,
+	/// Matched line number.
+	pub line: u32,
+}
+
+/// Result of `packResults()`.
+#[napi(object)]
+pub struct PackResult {
+	/// Matches rendered grouped by file: `path`, then `N:line` for matched
+	/// lines and `N-line` for context.
+	pub text:     String,
+	/// Estimated tokens used by `text`.
+	pub tokens:   u32,
+	/// Matches included (whole or trimmed).
+	pub included: u32,
+	/// Matches included with context or trailing lines removed.
+	pub trimmed:  u32,
+	/// Matches that did not fit, in input order.
+	pub dropped:  Vec<PackDropped>,
+}
+
+/// Rendered lines of one match, matched lines first in priority.
+struct Entry {
+	path:    String,
+	line:    u32,
+	matched: Vec<String>,
+	context: Vec<(u32, String)>,
+}
+
+impl Entry {
+	fn new(input: PackInput) -> Self {
+		let line = input.line_number.or(input.start_line).unwrap_or(0);
+		let text = input.line.or(input.text).unwrap_or_default();
+		let matched = text
+			.lines()
+			.enumerate()
+			.map(|(offset, text)| format!("{}:{text}", line as usize + offset))
+			.collect();
+		let context = input
+			.context_before
+			.into_iter()
+			.chain(input.context_after)
+			.flatten()
+			.map(|context| (context.line_number, format!("{}-{}", context.line_number, context.line)))
+			.collect();
+		Self { path: input.path, line, matched, context }
+	}
+
+	/// Lines in file order, with or without context.
+	fn render(&self, matched: usize, with_context: bool) -> Vec<(u32, String)> {
+		let mut lines: Vec<(u32, String)> = self
+			.matched
+			.iter()
+			.take(matched)
+			.enumerate()
+			.map(|(offset, text)| (self.line + offset as u32, text.clone()))
+			.collect();
+		if with_context {
+			lines.extend(self.context.iter().cloned());
+		}
+		lines.sort_by_key(|(line, _)| *line);
+		lines
+	}
+}
+
+fn cost(lines: &[(u32, String)], chars_per_token: f64) -> u32 {
+	lines
+		.iter()
+		.map(|(_, text)| estimate_with(text, chars_per_token) + 1)
+		.sum()
+}
+
+/// Pack `results` into text that fits `maxTokens`.
+///
+/// Matches are taken in order; a match that does not fit whole loses its
+/// context lines, then trailing lines of a multi-line match, before it is
+/// dropped. Each file's path is printed once, above its first match.
+#[napi(js_name = "packResults")]
+pub fn pack_results(results: Vec<PackInput>, options: PackResultsOptions) -> PackResult {
+	let ratio = chars_per_token(options.model.as_deref());
+	let mut remaining = options.max_tokens;
+	let mut files: Vec<(String, Vec<(u32, String)>)> = Vec::new();
+	let mut file_index: HashMap<String, usize> = HashMap::new();
+	let (mut included, mut trimmed) = (0u32, 0u32);
+	let mut dropped = Vec::new();
+
+	for entry in results.into_iter().map(Entry::new) {
+		let header = if file_index.contains_key(&entry.path) {
+			0
+		} else {
+			estimate_with(&entry.path, ratio) + 1
+		};
+		let full = entry.render(entry.matched.len(), true);
+		let chosen = if header + cost(&full, ratio) <= remaining {
+			Some((full, false))
+		} else {
+			(1..=entry.matched.len())
+				.rev()
+				.map(|matched| entry.render(matched, false))
+				.find(|lines| header + cost(lines, ratio) <= remaining)
+				.map(|lines| (lines, true))
+		};
+		let Some((lines, was_trimmed)) = chosen else {
+			dropped.push(PackDropped { path: entry.path, line: entry.line });
+			continue;
+		};
+		remaining -= header + cost(&lines, ratio);
+		included += 1;
+		trimmed += u32::from(was_trimmed);
+		let index = *file_index.entry(entry.path.clone()).or_insert_with(|| {
+			files.push((entry.path.clone(), Vec::new()));
+			files.len() - 1
+		});
+		files[index].1.extend(lines);
+	}
+
+	let mut text = String::new();
+	for (path, mut lines) in files {
+		lines.sort_by_key(|(line, _)| *line);
+		lines.dedup_by_key(|(line, _)| *line);
+		if !text.is_empty() {
+			text.push('\n');
+		}
+		let _ = writeln!(text, "{path}");
+		for (_, line) in lines {
+			let _ = writeln!(text, "{line}");
+		}
+	}
+	PackResult { tokens: options.max_tokens - remaining, text, included, trimmed, dropped }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn grep_match(path: &str, line: u32, text: &str, context: &[(u32, &str)]) -> PackInput {
+		PackInput {
+			path:           path.to_string(),
+			line_number:    Some(line),
+			line:           Some(text.to_string()),
+			start_line:     None,
+			text:           None,
+			context_before: None,
+			context_after:  Some(
+				context
+					.iter()
+					.map(|(line_number, line)| ContextLine {
+						line_number: *line_number,
+						line:        (*line).to_string(),
+					})
+					.collect(),
+			),
+		}
+	}
+
+	#[test]
+	fn picks_the_ratio_by_model_family() {
+		for model in ["gpt-4o", "o1", "o3-mini", "o4-mini-high"] {
+			assert!((chars_per_token(Some(model)) - 4.0).abs() < f64::EPSILON, "{model}");
+		}
+		for model in ["opus-4", "claude-opus-4-1", "o365", "ollama"] {
+			assert!((chars_per_token(Some(model)) - 3.5).abs() < f64::EPSILON, "{model}");
+		}
+	}
+
+	#[test]
+	fn packs_within_budget() {
+		assert_eq!(estimate_tokens("", None), 0);
+		assert!(estimate_tokens("fn main() {}", None) >= 5);
+		assert!(estimate_tokens("abcdefgh", Some("gpt-4o")) < estimate_tokens("abcdefgh", None) + 1);
+
+		let results = || {
+			vec![
+				grep_match("src/a.rs", 3, "let alpha = 1;", &[(4, "let beta = alpha + 1;")]),
+				grep_match("src/b.rs", 10, "let gamma = 2;", &[]),
+				grep_match("src/a.rs", 20, "let delta = 3;", &[]),
+			]
+		};
+		let all = pack_results(results(), PackResultsOptions { max_tokens: 1000, model: None });
+		assert_eq!(all.included, 3);
+		assert!(all.dropped.is_empty());
+		assert_eq!(
+			all.text,
+			"src/a.rs\n3:let alpha = 1;\n4-let beta = alpha + 1;\n20:let delta = \
+			 3;\n\nsrc/b.rs\n10:let gamma = 2;\n"
+		);
+		assert!(all.tokens <= 1000 && all.tokens > 0);
+
+		let tight = pack_results(results(), PackResultsOptions {
+			max_tokens: all.tokens - 12,
+			model:      None,
+		});
+		assert!(tight.tokens <= all.tokens - 12);
+		assert!(tight.trimmed + tight.dropped.len() as u32 >= 1);
+		assert!(!tight.text.contains("beta") || !tight.dropped.is_empty());
+
+		let none = pack_results(results(), PackResultsOptions { max_tokens: 0, model: None });
+		assert_eq!((none.included, none.dropped.len(), none.text.as_str()), (0, 3, ""));
+	}
+}
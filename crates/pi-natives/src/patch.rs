@@ -0,0 +1,367 @@
+//! Applying unified patches with fuzzy context matching.
+//!
+//! `applyPatch()` applies the hunks of a single-file unified diff (as produced
+//! by `diff -u`, `git diff`, or [`crate::diff::unified_diff`]) to a string.
+//! Each hunk is looked up nearest to the line its header names, so hunks
+//! still apply after unrelated lines were added or removed above them. When
+//! the context no longer matches exactly, up to `fuzz` context lines are
+//! ignored at each end of the hunk, like `patch --fuzz`. Context lines are
+//! kept from the original text, and added lines follow its line endings.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::diff;
+
+/// Context lines ignored at each end of a hunk unless `fuzz` says otherwise.
+const DEFAULT_FUZZ: u32 = 2;
+
+/// Options for `applyPatch()`.
+#[napi(object)]
+#[derive(Default)]
+pub struct ApplyPatchOptions {
+	/// Context lines that may be ignored at each end of a hunk when it does
+	/// not match exactly (default: 2; 0 requires exact context).
+	pub fuzz:              Option<u32>,
+	/// Compare lines ignoring differences in whitespace (default: false).
+	#[napi(js_name = "ignoreWhitespace")]
+	pub ignore_whitespace: Option<bool>,
+	/// Apply the hunks that match even when others fail (default: false, in
+	/// which case a failed hunk leaves the content unchanged).
+	#[napi(js_name = "allowPartial")]
+	pub allow_partial:     Option<bool>,
+}
+
+/// How one hunk applied.
+#[napi(object)]
+pub struct PatchHunkResult {
+	/// The hunk's `@@` header line.
+	pub header: String,
+	/// "exact" (at the line the header names), "offset" (elsewhere), "fuzz"
+	/// (with context lines ignored), or "failed".
+	pub status: String,
+	/// Line of the original content where the hunk applied (1-based).
+	pub line:   Option<u32>,
+	/// Lines between where the header placed the hunk and where it applied.
+	pub offset: i32,
+	/// Context lines ignored at each end to apply the hunk.
+	pub fuzz:   u32,
+}
+
+/// Result of `applyPatch()`.
+#[napi(object)]
+pub struct ApplyPatchResult {
+	/// Patched content; unchanged when a hunk failed and `allowPartial` is
+	/// off.
+	pub content: String,
+	/// Whether every hunk applied.
+	pub applied: bool,
+	/// Per-hunk outcome, in patch order.
+	pub hunks:   Vec<PatchHunkResult>,
+}
+
+/// A parsed hunk: its header, old start line, and body lines with markers.
+struct Hunk {
+	header:    String,
+	old_start: usize,
+	/// (`' '`, `'-'`, or `'+'`, line text with its terminator).
+	lines:     Vec<(char, String)>,
+}
+
+impl Hunk {
+	/// Leading and trailing context lines.
+	fn context_ends(&self) -> (usize, usize) {
+		let lead = self
+			.lines
+			.iter()
+			.take_while(|(marker, _)| *marker == ' ')
+			.count();
+		let trail = self
+			.lines
+			.iter()
+			.rev()
+			.take_while(|(marker, _)| *marker == ' ')
+			.count();
+		(lead, trail.min(self.lines.len() - lead))
+	}
+}
+
+/// Parse `@@ -a[,b] +c[,d] @@` into (a, b, d).
+fn parse_header(line: &str) -> Option<(usize, usize, usize)> {
+	let ranges = line.strip_prefix("@@ -")?;
+	let (ranges, _) = ranges.split_once(" @@")?;
+	let (old, new) = ranges.split_once(" +")?;
+	let range = |text: &str| -> Option<(usize, usize)> {
+		match text.split_once(',') {
+			Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+			None => Some((text.parse().ok()?, 1)),
+		}
+	};
+	let (old_start, old_len) = range(old)?;
+	let (_, new_len) = range(new)?;
+	Some((old_start, old_len, new_len))
+}
+
+fn parse_patch(patch: &str) -> Result<Vec<Hunk>> {
+	let mut hunks: Vec<Hunk> = Vec::new();
+	let mut files = 0;
+	// Old and new lines the current hunk still expects.
+	let (mut old_left, mut new_left) = (0usize, 0usize);
+	for line in patch.split_inclusive('\n') {
+		if line.starts_with('\\') {
+			// "\ No newline at end of file" applies to the line before it.
+			if let Some((_, text)) = hunks.last_mut().and_then(|hunk| hunk.lines.last_mut()) {
+				let trimmed = text.trim_end_matches(['\n', '\r']).len();
+				text.truncate(trimmed);
+			}
+			continue;
+		}
+		if old_left > 0 || new_left > 0 {
+			let body = line.trim_end_matches(['\n', '\r']);
+			let (marker, text) = match line.chars().next() {
+				Some(marker @ (' ' | '-' | '+')) => (marker, &line[1..]),
+				// Blank context lines whose leading space was stripped.
+				_ if body.is_empty() => (' ', line),
+				_ => {
+					return Err(Error::from_reason(format!("Malformed hunk line: {body:?}")));
+				},
+			};
+			if marker != '+' {
+				old_left = old_left.checked_sub(1).ok_or_else(|| too_long(&hunks))?;
+			}
+			if marker != '-' {
+				new_left = new_left.checked_sub(1).ok_or_else(|| too_long(&hunks))?;
+			}
+			if let Some(hunk) = hunks.last_mut() {
+				hunk.lines.push((marker, text.to_string()));
+			}
+			continue;
+		}
+		if line.starts_with("--- ") {
+			files += 1;
+			if files > 1 {
+				return Err(Error::from_reason("Patch touches more than one file"));
+			}
+		} else if line.starts_with("@@ ") {
+			let header = line.trim_end_matches(['\n', '\r']);
+			let (old_start, old_len, new_len) = parse_header(header)
+				.ok_or_else(|| Error::from_reason(format!("Malformed hunk header: {header}")))?;
+			(old_left, new_left) = (old_len, new_len);
+			hunks.push(Hunk { header: header.to_string(), old_start, lines: Vec::new() });
+		}
+	}
+	if old_left > 0 || new_left > 0 {
+		let header = hunks.last().map_or("", |hunk| hunk.header.as_str());
+		return Err(Error::from_reason(format!("Truncated hunk: {header}")));
+	}
+	if hunks.is_empty() {
+		return Err(Error::from_reason("Patch contains no hunks"));
+	}
+	Ok(hunks)
+}
+
+fn too_long(hunks: &[Hunk]) -> Error {
+	let header = hunks.last().map_or("", |hunk| hunk.header.as_str());
+	Error::from_reason(format!("Hunk longer than its header says: {header}"))
+}
+
+fn same_line(left: &str, right: &str, ignore_whitespace: bool) -> bool {
+	if ignore_whitespace {
+		left.split_whitespace().eq(right.split_whitespace())
+	} else {
+		left.trim_end_matches(['\n', '\r']) == right.trim_end_matches(['\n', '\r'])
+	}
+}
+
+/// Position in `lines[from..]` nearest `expected` where `old` matches.
+fn locate(
+	lines: &[&str],
+	old: &[&str],
+	from: usize,
+	expected: usize,
+	ignore_whitespace: bool,
+) -> Option<usize> {
+	let last = lines.len().checked_sub(old.len())?;
+	if from > last {
+		return None;
+	}
+	let expected = expected.clamp(from, last);
+	let matches = |at: usize| {
+		old.iter()
+			.zip(&lines[at..])
+			.all(|(want, have)| same_line(want, have, ignore_whitespace))
+	};
+	// Nearest first, earlier positions winning ties.
+	(0..=(last - from))
+		.flat_map(|distance| [expected.checked_sub(distance), Some(expected + distance)])
+		.flatten()
+		.filter(|&at| (from..=last).contains(&at))
+		.find(|&at| matches(at))
+}
+
+fn apply_hunks(content: &str, hunks: &[Hunk], options: &ApplyPatchOptions) -> ApplyPatchResult {
+	let fuzz = options.fuzz.unwrap_or(DEFAULT_FUZZ) as usize;
+	let ignore_whitespace = options.ignore_whitespace.unwrap_or(false);
+	let lines = diff::split_lines(content);
+	let crlf = lines.first().is_some_and(|line| line.ends_with("\r\n"));
+
+	let mut out = String::with_capacity(content.len());
+	let mut cursor = 0;
+	let mut drift = 0isize;
+	let mut results = Vec::with_capacity(hunks.len());
+	for hunk in hunks {
+		let (lead_context, trail_context) = hunk.context_ends();
+		// Old side of the hunk with `lead`/`trail` context lines dropped, where
+		// it matched.
+		let found = (0..=fuzz).find_map(|level| {
+			let lead = level.min(lead_context);
+			let trail = level.min(trail_context);
+			let body = &hunk.lines[lead..hunk.lines.len() - trail];
+			let old: Vec<&str> = body
+				.iter()
+				.filter(|(marker, _)| *marker != '+')
+				.map(|(_, text)| text.as_str())
+				.collect();
+			// A hunk with no old lines inserts after line `old_start`.
+			let anchor = if old.is_empty() && lead == 0 {
+				hunk.old_start
+			} else {
+				hunk.old_start.saturating_sub(1) + lead
+			};
+			let expected = (anchor as isize + drift).max(0) as usize;
+			let at = if old.is_empty() {
+				(expected >= cursor && expected <= lines.len()).then_some(expected)
+			} else {
+				locate(&lines, &old, cursor, expected, ignore_whitespace)
+			}?;
+			Some((level, lead, body, at, at as isize - anchor as isize))
+		});
+
+		let Some((level, lead, body, at, moved)) = found else {
+			results.push(PatchHunkResult {
+				header: hunk.header.clone(),
+				status: "failed".to_string(),
+				line:   None,
+				offset: 0,
+				fuzz:   0,
+			});
+			continue;
+		};
+		out.extend(lines[cursor..at].iter().copied());
+		let mut original = at;
+		for (marker, text) in body {
+			match marker {
+				' ' => {
+					out.push_str(lines[original]);
+					original += 1;
+				},
+				'-' => original += 1,
+				_ if crlf && text.ends_with('\n') && !text.ends_with("\r\n") => {
+					out.push_str(&text[..text.len() - 1]);
+					out.push_str("\r\n");
+				},
+				_ => out.push_str(text),
+			}
+		}
+		cursor = original;
+		drift = moved;
+		let status = if level > 0 {
+			"fuzz"
+		} else if moved != 0 {
+			"offset"
+		} else {
+			"exact"
+		};
+		results.push(PatchHunkResult {
+			header: hunk.header.clone(),
+			status: status.to_string(),
+			line:   Some(crate::utils::clamp_u32((at - lead) as u64 + 1)),
+			offset: drift.clamp(i32::MIN as isize, i32::MAX as isize) as i32,
+			fuzz:   level as u32,
+		});
+	}
+	out.extend(lines[cursor..].iter().copied());
+
+	let applied = results.iter().all(|hunk| hunk.status != "failed");
+	let content = if applied || options.allow_partial.unwrap_or(false) {
+		out
+	} else {
+		content.to_string()
+	};
+	ApplyPatchResult { content, applied, hunks: results }
+}
+
+/// Apply a single-file unified diff to `content`.
+///
+/// Hunks are matched nearest the line their header names and may ignore up
+/// to `fuzz` context lines at each end; see [`ApplyPatchResult`] for the
+/// per-hunk outcome.
+///
+/// # Errors
+/// Fails when the patch is malformed, has no hunks, or touches more than one
+/// file.
+#[napi(js_name = "applyPatch")]
+pub fn apply_patch(
+	content: String,
+	patch: String,
+	options: Option<ApplyPatchOptions>,
+) -> Result<ApplyPatchResult> {
+	let hunks = parse_patch(&patch)?;
+	Ok(apply_hunks(&content, &hunks, &options.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn apply(content: &str, patch: &str, fuzz: u32) -> ApplyPatchResult {
+		let options = ApplyPatchOptions { fuzz: Some(fuzz), ..Default::default() };
+		apply_hunks(content, &parse_patch(patch).unwrap(), &options)
+	}
+
+	fn statuses(result: &ApplyPatchResult) -> Vec<(&str, i32)> {
+		result
+			.hunks
+			.iter()
+			.map(|hunk| (hunk.status.as_str(), hunk.offset))
+			.collect()
+	}
+
+	#[test]
+	fn round_trips_unified_diffs() {
+		let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+		let new = "one\nTWO\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\neleven";
+		let patch = diff::unified_diff("x", old, new, 2).patch;
+		let result = apply(old, &patch, 0);
+		assert_eq!(result.content, new);
+		assert_eq!(statuses(&result), [("exact", 0), ("exact", 0)]);
+
+		let shifted = format!("zero\nzero\n{old}");
+		let result = apply(&shifted, &patch, 0);
+		assert_eq!(result.content, format!("zero\nzero\n{new}"));
+		assert_eq!(statuses(&result), [("offset", 2), ("offset", 2)]);
+		assert_eq!(result.hunks[0].line, Some(3));
+	}
+
+	#[test]
+	fn fuzzes_context_and_reports_failures() {
+		let patch = "--- a/x\n+++ b/x\n@@ -1,5 +1,5 @@\n a\n b\n-c\n+C\n d\n e\n";
+		let drifted = "A\nb\nc\nd\nE\n";
+		assert!(!apply(drifted, patch, 0).applied);
+		let result = apply(drifted, patch, 1);
+		assert_eq!(result.content, "A\nb\nC\nd\nE\n");
+		assert_eq!((result.hunks[0].status.as_str(), result.hunks[0].fuzz), ("fuzz", 1));
+
+		let failed = apply("x\ny\n", patch, 2);
+		assert_eq!((failed.applied, failed.content.as_str()), (false, "x\ny\n"));
+		assert_eq!(statuses(&failed), [("failed", 0)]);
+
+		let crlf = apply("a\r\nb\r\nc\r\nd\r\ne\r\n", patch, 0);
+		assert_eq!(crlf.content, "a\r\nb\r\nC\r\nd\r\ne\r\n");
+		let insert = apply("a\n", "@@ -1,0 +2 @@\n+b\n", 0);
+		assert_eq!(insert.content, "a\nb\n");
+
+		assert!(parse_patch("@@ -1,2 +1 @@\n a\n").is_err());
+		assert!(parse_patch("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n--- a/y\n").is_err());
+	}
+}
@@ -213,6 +213,84 @@ fn generate_svg(folded: &str) -> Option<String> {
 	}
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Per-call Traces
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A timed phase of a traced call, returned when an export is called with
+/// `trace: true`.
+#[napi(object)]
+#[derive(Clone)]
+pub struct TraceSpan {
+	/// Phase name (e.g. "scan", "filter", "match", "serialize").
+	pub name:        String,
+	/// Start offset from the beginning of the call, in milliseconds.
+	#[napi(js_name = "startMs")]
+	pub start_ms:    f64,
+	/// Phase duration in milliseconds.
+	#[napi(js_name = "durationMs")]
+	pub duration_ms: f64,
+	/// Nested phases, in start order.
+	pub children:    Vec<Self>,
+}
+
+/// Phase timings for one traced call.
+///
+/// Unlike the always-on profile buffer, a trace belongs to a single call and
+/// is returned with its result.
+pub struct CallTrace {
+	name:  &'static str,
+	start: Instant,
+	spans: RefCell<Vec<TraceSpan>>,
+}
+
+impl CallTrace {
+	/// Start a trace for `name`, or return `None` when tracing is off.
+	pub fn start(name: &'static str, enabled: bool) -> Option<Self> {
+		enabled.then(|| Self { name, start: Instant::now(), spans: RefCell::new(Vec::new()) })
+	}
+
+	/// Time a phase until the returned guard is dropped.
+	pub fn span(&self, name: &'static str) -> TraceGuard<'_> {
+		TraceGuard { trace: self, name, start: Instant::now() }
+	}
+
+	/// Close the trace, returning the root span with each phase as a child.
+	pub fn finish(self) -> TraceSpan {
+		TraceSpan {
+			name:        self.name.to_string(),
+			start_ms:    0.0,
+			duration_ms: self.start.elapsed().as_secs_f64() * 1000.0,
+			children:    self.spans.into_inner(),
+		}
+	}
+}
+
+/// RAII guard that records a phase span on its trace when dropped.
+pub struct TraceGuard<'a> {
+	trace: &'a CallTrace,
+	name:  &'static str,
+	start: Instant,
+}
+
+impl Drop for TraceGuard<'_> {
+	fn drop(&mut self) {
+		let start_ms = self.start.duration_since(self.trace.start).as_secs_f64() * 1000.0;
+		self.trace.spans.borrow_mut().push(TraceSpan {
+			name: self.name.to_string(),
+			start_ms,
+			duration_ms: self.start.elapsed().as_secs_f64() * 1000.0,
+			children: Vec::new(),
+		});
+	}
+}
+
+/// Time a phase on an optional trace; a no-op when tracing is off.
+#[inline]
+pub fn trace_span<'a>(trace: Option<&'a CallTrace>, name: &'static str) -> Option<TraceGuard<'a>> {
+	trace.map(|trace| trace.span(name))
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // N-API Exports
 // ─────────────────────────────────────────────────────────────────────────────
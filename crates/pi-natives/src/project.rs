@@ -0,0 +1,774 @@
+//! Project root discovery.
+//!
+//! `findProjectRoot()` walks up from a path to the nearest directory holding
+//! a project marker (`.git`, `package.json`, `Cargo.toml`, ...) and reports
+//! which kinds of project live there. Keeping the marker table here gives
+//! every caller the same answer for "where does this project start".
+//!
+//! `discoverPackages()` reads a monorepo's workspace manifests (npm/yarn
+//! `workspaces`, `pnpm-workspace.yaml`, Cargo `[workspace]`, and `go.work`)
+//! and lists its member packages; `grep` and `glob` use it to resolve their
+//! `package` option to a directory.
+//!
+//! `dirProfile()` summarizes a tree by extension and size from the cached
+//! directory scan, for project overviews.
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::{fs_cache, task};
+
+/// Default markers and the project kind each one implies, in report order.
+const MARKERS: &[(&str, &str)] = &[
+	(".git", "git"),
+	(".hg", "hg"),
+	(".svn", "svn"),
+	("package.json", "node"),
+	("deno.json", "deno"),
+	("deno.jsonc", "deno"),
+	("Cargo.toml", "rust"),
+	("pyproject.toml", "python"),
+	("setup.py", "python"),
+	("setup.cfg", "python"),
+	("go.mod", "go"),
+	("pom.xml", "java"),
+	("build.gradle", "java"),
+	("build.gradle.kts", "java"),
+	("Gemfile", "ruby"),
+	("composer.json", "php"),
+	("mix.exs", "elixir"),
+	("Package.swift", "swift"),
+	("CMakeLists.txt", "cmake"),
+];
+
+/// Version-control markers; their directory bounds the project.
+const VCS_MARKERS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Options for `findProjectRoot()`.
+#[napi(object)]
+pub struct FindProjectRootOptions {
+	/// Marker file or directory names to look for (default: VCS directories
+	/// and common manifests such as `package.json` and `Cargo.toml`).
+	pub markers:   Option<Vec<String>>,
+	/// Return the outermost matching directory below the version-control root
+	/// instead of the nearest one (default: false).
+	pub outermost: Option<bool>,
+}
+
+/// A discovered project root.
+#[napi(object)]
+pub struct ProjectRoot {
+	/// Absolute path of the project root.
+	pub root:     String,
+	/// Markers present in `root`.
+	pub markers:  Vec<String>,
+	/// Project kinds implied by those markers (e.g. "git", "node", "rust").
+	pub kinds:    Vec<String>,
+	/// Nearest enclosing version-control root, if any.
+	#[napi(js_name = "vcsRoot")]
+	pub vcs_root: Option<String>,
+}
+
+fn marker_kind(marker: &str) -> Option<&'static str> {
+	MARKERS
+		.iter()
+		.find(|(name, _)| *name == marker)
+		.map(|(_, kind)| *kind)
+}
+
+fn present_markers<'m>(dir: &Path, markers: &'m [String]) -> Vec<&'m str> {
+	markers
+		.iter()
+		.map(String::as_str)
+		.filter(|marker| dir.join(marker).symlink_metadata().is_ok())
+		.collect()
+}
+
+fn is_vcs_root(dir: &Path) -> bool {
+	VCS_MARKERS
+		.iter()
+		.any(|marker| dir.join(marker).symlink_metadata().is_ok())
+}
+
+/// Walk up from `start` to the first version-control root (or the filesystem
+/// root) and pick the nearest or outermost directory holding a marker.
+fn find_root(start: &Path, markers: &[String], outermost: bool) -> Option<ProjectRoot> {
+	let mut found: Option<(&Path, Vec<&str>)> = None;
+	let mut vcs_root = None;
+	for dir in start.ancestors() {
+		let present = present_markers(dir, markers);
+		if !present.is_empty() && (outermost || found.is_none()) {
+			found = Some((dir, present));
+		}
+		if is_vcs_root(dir) {
+			vcs_root = Some(dir);
+			break;
+		}
+	}
+
+	let (root, present) = found?;
+	let mut kinds: Vec<String> = Vec::new();
+	for marker in &present {
+		if let Some(kind) = marker_kind(marker)
+			&& !kinds.iter().any(|existing| existing == kind)
+		{
+			kinds.push(kind.to_string());
+		}
+	}
+	Some(ProjectRoot {
+		root: root.to_string_lossy().into_owned(),
+		markers: present.into_iter().map(str::to_string).collect(),
+		kinds,
+		vcs_root: vcs_root.map(|path| path.to_string_lossy().into_owned()),
+	})
+}
+
+/// Find the project root containing `startPath`.
+///
+/// Walks up from `startPath` (or its directory, for a file) to the nearest
+/// directory holding one of the markers, never past the first version-control
+/// root. Returns `null` when no marker is found.
+///
+/// # Errors
+/// Fails when `startPath` does not exist.
+#[napi(js_name = "findProjectRoot")]
+pub fn find_project_root(
+	start_path: String,
+	options: Option<FindProjectRootOptions>,
+) -> Result<Option<ProjectRoot>> {
+	let (markers, outermost) = options
+		.map_or((None, false), |options| (options.markers, options.outermost.unwrap_or(false)));
+	let markers = markers.unwrap_or_else(|| {
+		MARKERS
+			.iter()
+			.map(|(marker, _)| (*marker).to_string())
+			.collect()
+	});
+	let start = std::path::absolute(&start_path)
+		.and_then(std::fs::canonicalize)
+		.map_err(|err| Error::from_reason(format!("Path not found: {start_path}: {err}")))?;
+	let start = if start.is_dir() {
+		start
+	} else {
+		start.parent().map(Path::to_path_buf).unwrap_or(start)
+	};
+	Ok(find_root(&start, &markers, outermost))
+}
+
+/// A member package of a monorepo workspace.
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkspacePackage {
+	/// Package name from its manifest (directory name when unnamed).
+	pub name: String,
+	/// Package directory relative to the workspace root ("." for the root).
+	pub path: String,
+	/// Ecosystem of the manifest: "node", "rust", or "go".
+	#[napi(ts_type = "\"node\" | \"rust\" | \"go\"")]
+	pub kind: String,
+}
+
+/// Member globs declared by one workspace manifest.
+struct MemberGlobs {
+	kind:     &'static str,
+	manifest: &'static str,
+	include:  Vec<String>,
+	exclude:  Vec<String>,
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+	match fs::read_to_string(path) {
+		Ok(content) => Ok(Some(content)),
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+		Err(err) => Err(Error::from_reason(format!("Failed to read {}: {err}", path.display()))),
+	}
+}
+
+/// Split workspace patterns into includes and `!`-prefixed excludes.
+fn member_globs(
+	kind: &'static str,
+	manifest: &'static str,
+	patterns: impl IntoIterator<Item = String>,
+) -> MemberGlobs {
+	let mut globs = MemberGlobs { kind, manifest, include: Vec::new(), exclude: Vec::new() };
+	for pattern in patterns {
+		match pattern.strip_prefix('!') {
+			Some(excluded) => globs.exclude.push(excluded.to_string()),
+			None => globs.include.push(pattern),
+		}
+	}
+	globs
+}
+
+/// `workspaces` from package.json: an array, or `{ packages: [...] }`.
+fn npm_workspaces(content: &str) -> Result<Vec<String>> {
+	let manifest: serde_json::Value = serde_json::from_str(content)
+		.map_err(|err| Error::from_reason(format!("Invalid package.json: {err}")))?;
+	let workspaces = manifest.get("workspaces");
+	let list = workspaces
+		.and_then(|value| value.get("packages"))
+		.or(workspaces)
+		.and_then(serde_json::Value::as_array);
+	Ok(list
+		.into_iter()
+		.flatten()
+		.filter_map(serde_json::Value::as_str)
+		.map(str::to_string)
+		.collect())
+}
+
+fn unquote(value: &str) -> &str {
+	let value = value.trim();
+	value
+		.strip_prefix('\'')
+		.and_then(|rest| rest.strip_suffix('\''))
+		.or_else(|| {
+			value
+				.strip_prefix('"')
+				.and_then(|rest| rest.strip_suffix('"'))
+		})
+		.unwrap_or(value)
+}
+
+/// The top-level `packages` list of pnpm-workspace.yaml, in block
+/// (`- 'apps/*'`) or flow (`['apps/*']`) style.
+fn pnpm_packages(content: &str) -> Vec<String> {
+	let mut packages = Vec::new();
+	let mut in_packages = false;
+	for line in content.lines() {
+		let line = line.split(" #").next().unwrap_or_default().trim_end();
+		if line.trim().is_empty() || line.trim_start().starts_with('#') {
+			continue;
+		}
+		if !line.starts_with([' ', '\t', '-']) {
+			in_packages = false;
+			if let Some(rest) = line.strip_prefix("packages:") {
+				let rest = rest.trim();
+				if let Some(flow) = rest
+					.strip_prefix('[')
+					.and_then(|rest| rest.strip_suffix(']'))
+				{
+					packages.extend(
+						flow
+							.split(',')
+							.map(unquote)
+							.filter(|item| !item.is_empty())
+							.map(str::to_string),
+					);
+				} else {
+					in_packages = rest.is_empty();
+				}
+			}
+			continue;
+		}
+		if in_packages && let Some(item) = line.trim_start().strip_prefix('-') {
+			packages.push(unquote(item).to_string());
+		}
+	}
+	packages
+}
+
+fn toml_strings(table: &toml::Table, key: &str) -> Vec<String> {
+	table
+		.get(key)
+		.and_then(toml::Value::as_array)
+		.into_iter()
+		.flatten()
+		.filter_map(toml::Value::as_str)
+		.map(str::to_string)
+		.collect()
+}
+
+fn parse_toml(content: &str, path: &Path) -> Result<toml::Table> {
+	content
+		.parse::<toml::Table>()
+		.map_err(|err| Error::from_reason(format!("Invalid {}: {err}", path.display())))
+}
+
+/// `use` directives of go.work, single-line or parenthesized.
+fn go_work_uses(content: &str) -> Vec<String> {
+	let mut uses = Vec::new();
+	let mut in_block = false;
+	for line in content.lines() {
+		let line = line.split("//").next().unwrap_or_default().trim();
+		if in_block {
+			if line == ")" {
+				in_block = false;
+			} else if !line.is_empty() {
+				uses.push(unquote(line).to_string());
+			}
+		} else if let Some(rest) = line.strip_prefix("use") {
+			let rest = rest.trim();
+			if rest == "(" {
+				in_block = true;
+			} else if !rest.is_empty() && line.starts_with("use ") {
+				uses.push(unquote(rest).to_string());
+			}
+		}
+	}
+	uses
+}
+
+fn compile_member_globs(patterns: &[String]) -> Result<GlobSet> {
+	let mut builder = GlobSetBuilder::new();
+	for pattern in patterns {
+		let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+		let glob = GlobBuilder::new(if pattern.is_empty() { "." } else { pattern })
+			.literal_separator(true)
+			.build()
+			.map_err(|err| {
+				Error::from_reason(format!("Invalid workspace pattern {pattern}: {err}"))
+			})?;
+		builder.add(glob);
+	}
+	builder
+		.build()
+		.map_err(|err| Error::from_reason(format!("Failed to build workspace patterns: {err}")))
+}
+
+/// Name declared by a member manifest, if any.
+fn package_name(kind: &str, manifest: &Path) -> Result<Option<String>> {
+	let Some(content) = read_optional(manifest)? else {
+		return Ok(None);
+	};
+	Ok(match kind {
+		"node" => serde_json::from_str::<serde_json::Value>(&content)
+			.ok()
+			.and_then(|value| value.get("name")?.as_str().map(str::to_string)),
+		"rust" => parse_toml(&content, manifest)?
+			.get("package")
+			.and_then(|package| package.get("name")?.as_str().map(str::to_string)),
+		_ => content.lines().find_map(|line| {
+			let module = line.trim().strip_prefix("module")?;
+			module
+				.starts_with(char::is_whitespace)
+				.then(|| unquote(module).to_string())
+		}),
+	})
+}
+
+/// Member globs from every workspace manifest at `root`.
+fn workspace_globs(root: &Path) -> Result<Vec<MemberGlobs>> {
+	let mut sources = Vec::new();
+	if let Some(content) = read_optional(&root.join("pnpm-workspace.yaml"))? {
+		sources.push(member_globs("node", "package.json", pnpm_packages(&content)));
+	} else if let Some(content) = read_optional(&root.join("package.json"))? {
+		sources.push(member_globs("node", "package.json", npm_workspaces(&content)?));
+	}
+	let cargo_path = root.join("Cargo.toml");
+	if let Some(content) = read_optional(&cargo_path)? {
+		let manifest = parse_toml(&content, &cargo_path)?;
+		if let Some(workspace) = manifest.get("workspace").and_then(toml::Value::as_table) {
+			let mut globs = member_globs("rust", "Cargo.toml", toml_strings(workspace, "members"));
+			globs.exclude.extend(toml_strings(workspace, "exclude"));
+			// A root `[package]` is a member of its own workspace.
+			if manifest.contains_key("package") {
+				globs.include.push(".".to_string());
+			}
+			sources.push(globs);
+		}
+	}
+	if let Some(content) = read_optional(&root.join("go.work"))? {
+		sources.push(member_globs("go", "go.mod", go_work_uses(&content)));
+	}
+	Ok(sources)
+}
+
+/// List the member packages declared by the workspace manifests at `root`,
+/// sorted by path.
+pub fn discover_workspace_packages(
+	root: &Path,
+	ct: &task::CancelToken,
+) -> Result<Vec<WorkspacePackage>> {
+	let sources = workspace_globs(root)?;
+	if sources.iter().all(|source| source.include.is_empty()) {
+		return Ok(Vec::new());
+	}
+	let mut dirs: Vec<String> = vec![".".to_string()];
+	dirs.extend(
+		fs_cache::force_rescan(root, &fs_cache::ScanPolicy::new(false, true), false, ct)?
+			.into_iter()
+			.filter(|entry| entry.file_type == fs_cache::FileType::Dir)
+			.filter(|entry| !fs_cache::should_skip_path(Path::new(&entry.path), false))
+			.map(|entry| entry.path),
+	);
+
+	let mut packages = Vec::new();
+	for source in sources {
+		let include = compile_member_globs(&source.include)?;
+		let exclude = compile_member_globs(&source.exclude)?;
+		for dir in &dirs {
+			if !include.is_match(dir) || exclude.is_match(dir) {
+				continue;
+			}
+			let manifest: PathBuf = root.join(dir).join(source.manifest);
+			if !manifest.is_file() {
+				continue;
+			}
+			let name = package_name(source.kind, &manifest)?.unwrap_or_else(|| {
+				let dir_path = root.join(dir);
+				dir_path
+					.file_name()
+					.map_or_else(|| dir.clone(), |name| name.to_string_lossy().into_owned())
+			});
+			packages.push(WorkspacePackage { name, path: dir.clone(), kind: source.kind.to_string() });
+		}
+	}
+	packages.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.kind.cmp(&b.kind)));
+	packages.dedup();
+	Ok(packages)
+}
+
+/// Directory of the workspace package named `name` (or at relative path
+/// `name`) under `root`.
+///
+/// # Errors
+/// Fails when no workspace package matches.
+pub fn resolve_package_dir(root: &Path, name: &str, ct: &task::CancelToken) -> Result<PathBuf> {
+	let packages = discover_workspace_packages(root, ct)?;
+	packages
+		.iter()
+		.find(|package| package.name == name)
+		.or_else(|| {
+			packages
+				.iter()
+				.find(|package| package.path == name.trim_start_matches("./"))
+		})
+		.map(|package| root.join(&package.path))
+		.ok_or_else(|| Error::from_reason(format!("Unknown workspace package: {name}")))
+}
+
+/// List a monorepo's member packages from its workspace manifests
+/// (`package.json` workspaces, `pnpm-workspace.yaml`, Cargo `[workspace]`,
+/// and `go.work`).
+///
+/// # Errors
+/// Fails when `root` is not a directory or a manifest cannot be parsed.
+#[napi(js_name = "discoverPackages")]
+pub fn discover_packages(root: String) -> task::Async<Vec<WorkspacePackage>> {
+	task::blocking("project.packages", (), move |ct| {
+		let root = fs_cache::resolve_search_path(&root)?;
+		discover_workspace_packages(&root, &ct)
+	})
+}
+
+/// Options for `dirProfile()`.
+#[napi(object)]
+pub struct DirProfileOptions<'env> {
+	/// Deepest directory level to report; the root is level 0 (default: 1).
+	/// Files below it count toward their ancestor at that level.
+	pub depth:      Option<u32>,
+	/// Largest files to list per directory (default: 5).
+	pub largest:    Option<u32>,
+	/// Include hidden files (default: false).
+	pub hidden:     Option<bool>,
+	/// Respect .gitignore files (default: true).
+	pub gitignore:  Option<bool>,
+	/// Abort signal for cancelling the operation.
+	pub signal:     Option<Unknown<'env>>,
+	/// Timeout in milliseconds for the operation.
+	#[napi(js_name = "timeoutMs")]
+	pub timeout_ms: Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:      Option<ClassInstance<'env, task::CancellationScope>>,
+}
+
+/// Files with one extension.
+#[napi(object)]
+pub struct ExtensionStats {
+	/// Lowercase extension without the dot ("" for none).
+	pub extension: String,
+	/// Number of files.
+	pub files:     u32,
+	/// Total size in bytes.
+	pub bytes:     f64,
+}
+
+/// A file and its size.
+#[napi(object)]
+pub struct FileSize {
+	/// Path relative to the profiled root.
+	pub path: String,
+	/// Size in bytes.
+	pub size: f64,
+}
+
+/// Totals for one directory, including everything below it.
+#[napi(object)]
+pub struct DirStats {
+	/// Directory relative to the profiled root ("." for the root).
+	pub path:       String,
+	/// Number of files.
+	pub files:      u32,
+	/// Total size in bytes.
+	pub bytes:      f64,
+	/// File counts by extension, most common first.
+	pub extensions: Vec<ExtensionStats>,
+	/// Largest files, largest first.
+	pub largest:    Vec<FileSize>,
+}
+
+#[derive(Default)]
+struct DirAccumulator {
+	files:      u32,
+	bytes:      f64,
+	extensions: HashMap<String, (u32, f64)>,
+	largest:    Vec<(f64, String)>,
+}
+
+impl DirAccumulator {
+	fn add(&mut self, path: &str, extension: &str, size: f64, keep: usize) {
+		self.files += 1;
+		self.bytes += size;
+		let stats = self.extensions.entry(extension.to_string()).or_default();
+		stats.0 += 1;
+		stats.1 += size;
+		if keep > 0 && (self.largest.len() < keep || size > self.largest[keep - 1].0) {
+			let at = self.largest.partition_point(|(other, _)| *other >= size);
+			self.largest.insert(at, (size, path.to_string()));
+			self.largest.truncate(keep);
+		}
+	}
+
+	fn finish(self, path: String) -> DirStats {
+		let mut extensions: Vec<ExtensionStats> = self
+			.extensions
+			.into_iter()
+			.map(|(extension, (files, bytes))| ExtensionStats { extension, files, bytes })
+			.collect();
+		extensions.sort_by(|a, b| {
+			b.files
+				.cmp(&a.files)
+				.then_with(|| a.extension.cmp(&b.extension))
+		});
+		DirStats {
+			path,
+			files: self.files,
+			bytes: self.bytes,
+			extensions,
+			largest: self
+				.largest
+				.into_iter()
+				.map(|(size, path)| FileSize { path, size })
+				.collect(),
+		}
+	}
+}
+
+fn dir_profile_sync(
+	root: &Path,
+	depth: usize,
+	largest: usize,
+	policy: &fs_cache::ScanPolicy,
+	ct: &task::CancelToken,
+) -> Result<Vec<DirStats>> {
+	let scan = fs_cache::get_or_scan(root, policy, ct)?;
+	let mut dirs: BTreeMap<String, DirAccumulator> = BTreeMap::new();
+	for entry in &scan.entries {
+		ct.heartbeat()?;
+		if entry.file_type != fs_cache::FileType::File
+			|| fs_cache::should_skip_path(Path::new(&entry.path), false)
+		{
+			continue;
+		}
+		let size = entry.size.unwrap_or(0.0);
+		let name = entry.path.rsplit('/').next().unwrap_or_default();
+		let extension = name
+			.rsplit_once('.')
+			.filter(|(stem, _)| !stem.is_empty())
+			.map_or_else(String::new, |(_, extension)| extension.to_ascii_lowercase());
+		let parents: Vec<&str> = entry.path.split('/').collect();
+		let parents = &parents[..parents.len() - 1];
+		for level in 0..=depth.min(parents.len()) {
+			let dir = if level == 0 {
+				".".to_string()
+			} else {
+				parents[..level].join("/")
+			};
+			dirs
+				.entry(dir)
+				.or_default()
+				.add(&entry.path, &extension, size, largest);
+		}
+	}
+	Ok(dirs
+		.into_iter()
+		.map(|(path, stats)| stats.finish(path))
+		.collect())
+}
+
+/// Summarize a directory tree: file counts by extension, total sizes, and
+/// the largest files of each directory down to `depth`, from one cached scan.
+///
+/// Directories are listed root first, then in path order; ignored files and
+/// `node_modules` are skipped.
+///
+/// # Errors
+/// Fails when `root` is not a directory.
+#[napi(js_name = "dirProfile")]
+pub fn dir_profile(
+	root: String,
+	options: Option<DirProfileOptions<'_>>,
+) -> Result<task::Async<Vec<DirStats>>> {
+	let (depth, largest, hidden, gitignore, ct) = match options {
+		Some(options) => (
+			options.depth,
+			options.largest,
+			options.hidden,
+			options.gitignore,
+			task::CancelToken::new(options.timeout_ms, options.signal)
+				.with_scope(options.scope.as_deref()),
+		),
+		None => (None, None, None, None, task::CancelToken::default()),
+	};
+	let root = fs_cache::resolve_search_path(&root)?;
+	let policy = fs_cache::ScanPolicy::new(hidden.unwrap_or(false), gitignore.unwrap_or(true));
+	let depth = depth.unwrap_or(1) as usize;
+	let largest = largest.unwrap_or(5) as usize;
+	Ok(task::blocking("project.profile", ct, move |ct| {
+		dir_profile_sync(&root, depth, largest, &policy, &ct)
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+
+	#[test]
+	fn finds_nearest_and_outermost_roots() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let repo = std::env::temp_dir().join(format!("pi-project-test-{unique}"));
+		let package = repo.join("packages/app");
+		fs::create_dir_all(package.join("src")).expect("temp dir should be created");
+		fs::create_dir_all(repo.join(".git")).expect(".git should be created");
+		fs::write(repo.join("package.json"), "{}").expect("package.json should be written");
+		fs::write(repo.join("Cargo.toml"), "").expect("Cargo.toml should be written");
+		fs::write(package.join("package.json"), "{}").expect("package.json should be written");
+		fs::write(package.join("src/main.ts"), "").expect("main.ts should be written");
+		let repo = fs::canonicalize(&repo).unwrap();
+		let find = |path: &Path, options| {
+			find_project_root(path.to_string_lossy().into_owned(), options)
+				.unwrap()
+				.expect("root should be found")
+		};
+
+		let nearest = find(&package.join("src/main.ts"), None);
+		assert_eq!(Path::new(&nearest.root), repo.join("packages/app"));
+		assert_eq!(nearest.markers, ["package.json"]);
+		assert_eq!(nearest.kinds, ["node"]);
+		assert_eq!(nearest.vcs_root.as_deref().map(Path::new), Some(repo.as_path()));
+
+		let outer =
+			find(&package, Some(FindProjectRootOptions { markers: None, outermost: Some(true) }));
+		assert_eq!(Path::new(&outer.root), repo);
+		assert_eq!(outer.kinds, ["git", "node", "rust"]);
+
+		let custom = FindProjectRootOptions {
+			markers:   Some(vec!["Cargo.toml".to_string()]),
+			outermost: None,
+		};
+		assert_eq!(find(&package, Some(custom)).markers, ["Cargo.toml"]);
+
+		let _ = fs::remove_dir_all(&repo);
+	}
+
+	#[test]
+	fn discovers_workspace_members() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-workspace-test-{unique}"));
+		let write = |path: &str, content: &str| {
+			let path = root.join(path);
+			fs::create_dir_all(path.parent().unwrap()).expect("dir should be created");
+			fs::write(path, content).expect("fixture should be written");
+		};
+		write("pnpm-workspace.yaml", "packages:\n  - 'packages/*'\n  - \"!packages/skip\"\n");
+		write("packages/ui/package.json", r#"{ "name": "@acme/ui" }"#);
+		write("packages/skip/package.json", r#"{ "name": "skip" }"#);
+		write("packages/ui/nested/package.json", r#"{ "name": "nested" }"#);
+		write("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n\n[package]\nname = \"tool\"\n");
+		write("crates/core/Cargo.toml", "[package]\nname = \"core\"\n");
+		write("go.work", "go 1.22\n\nuse (\n\t./svc // api\n)\n");
+		write("svc/go.mod", "module example.com/svc\n");
+		let ct = task::CancelToken::default();
+
+		let packages = discover_workspace_packages(&root, &ct).unwrap();
+		let summary: Vec<_> = packages
+			.iter()
+			.map(|package| format!("{} {} {}", package.kind, package.path, package.name))
+			.collect();
+		assert_eq!(summary, [
+			"rust . tool",
+			"rust crates/core core",
+			"node packages/ui @acme/ui",
+			"go svc example.com/svc",
+		]);
+		assert_eq!(resolve_package_dir(&root, "@acme/ui", &ct).unwrap(), root.join("packages/ui"));
+		assert!(resolve_package_dir(&root, "skip", &ct).is_err());
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn profiles_directories() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-profile-test-{unique}"));
+		let write = |relative: &str, size: usize| {
+			let path = root.join(relative);
+			fs::create_dir_all(path.parent().unwrap()).expect("dir should be created");
+			fs::write(path, "x".repeat(size)).expect("file should be written");
+		};
+		write("README.md", 10);
+		write("src/a.ts", 100);
+		write("src/b.ts", 300);
+		write("src/deep/c.TS", 50);
+		write("src/deep/Makefile", 5);
+		write("node_modules/x/index.js", 1000);
+		let policy = fs_cache::ScanPolicy::new(false, true);
+		let ct = task::CancelToken::default();
+
+		let profile = dir_profile_sync(&root, 1, 2, &policy, &ct).unwrap();
+		let paths: Vec<_> = profile.iter().map(|dir| dir.path.as_str()).collect();
+		assert_eq!(paths, [".", "src"]);
+		let root_stats = &profile[0];
+		assert_eq!((root_stats.files, root_stats.bytes), (5, 465.0));
+		let extensions: Vec<_> = root_stats
+			.extensions
+			.iter()
+			.map(|ext| (ext.extension.as_str(), ext.files, ext.bytes))
+			.collect();
+		assert_eq!(extensions, [("ts", 3, 450.0), ("", 1, 5.0), ("md", 1, 10.0)]);
+		let largest: Vec<_> = profile[1]
+			.largest
+			.iter()
+			.map(|file| (file.path.as_str(), file.size))
+			.collect();
+		assert_eq!(largest, [("src/b.ts", 300.0), ("src/a.ts", 100.0)]);
+
+		let deeper = dir_profile_sync(&root, 5, 0, &policy, &ct).unwrap();
+		assert_eq!(deeper.len(), 3);
+		assert_eq!(deeper[0].largest.len(), 0);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+}
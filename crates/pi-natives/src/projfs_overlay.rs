@@ -18,6 +18,7 @@ pub fn projfs_overlay_probe() -> ProjfsOverlayProbeResult {
 
 #[napi(js_name = "projfsOverlayStart")]
 pub fn projfs_overlay_start(lower_root: String, projection_root: String) -> Result<()> {
+	crate::readonly::ensure_writable("projfsOverlayStart")?;
 	imp::start(&lower_root, &projection_root)
 }
 
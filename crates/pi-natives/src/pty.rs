@@ -2,7 +2,10 @@
 //!
 //! # Overview
 //! Provides a stateful PTY session that supports streaming output and stdin
-//! passthrough while a command is running.
+//! passthrough while a command is running. Output is also captured in a
+//! bounded line buffer readable with `readOutput()`.
+
+mod output;
 
 use std::{
 	collections::HashMap,
@@ -19,37 +22,54 @@ use napi::{
 use napi_derive::napi;
 use portable_pty::{Child, CommandBuilder, PtySize, native_pty_system};
 
-use crate::task;
+use self::output::{DEFAULT_OUTPUT_BYTES, OutputRing, OverflowPolicy};
+pub use self::output::{PtyOutput, PtyReadOutputOptions};
+use crate::{exit_status::ExitInfo, task};
+
+const DEFAULT_READ_LINES: u32 = 200;
 
 /// Options for running a command in a PTY session.
 #[napi(object)]
 pub struct PtyStartOptions<'env> {
 	/// Command string to execute.
-	pub command:    String,
+	pub command:             String,
 	/// Working directory for command execution.
-	pub cwd:        Option<String>,
+	pub cwd:                 Option<String>,
 	/// Environment variables for this command.
-	pub env:        Option<HashMap<String, String>>,
+	pub env:                 Option<HashMap<String, String>>,
 	/// Timeout in milliseconds before cancelling.
 	#[napi(js_name = "timeoutMs")]
-	pub timeout_ms: Option<u32>,
+	pub timeout_ms:          Option<u32>,
 	/// Abort signal for cancelling the operation.
-	pub signal:     Option<Unknown<'env>>,
+	pub signal:              Option<Unknown<'env>>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:               Option<ClassInstance<'env, task::CancellationScope>>,
 	/// PTY column count.
-	pub cols:       Option<u16>,
+	pub cols:                Option<u16>,
 	/// PTY row count.
-	pub rows:       Option<u16>,
+	pub rows:                Option<u16>,
+	/// Bytes of recent output kept for `readOutput()` (default: 1 MiB; 0
+	/// disables the buffer).
+	#[napi(js_name = "outputBufferBytes")]
+	pub output_buffer_bytes: Option<u32>,
+	/// When the output buffer is full: "dropOldest" (default) evicts old lines;
+	/// "block" stops reading the PTY until `readOutput()` consumes lines.
+	#[napi(js_name = "outputOverflow")]
+	pub output_overflow:     Option<String>,
 }
 
 /// Result of a PTY command run.
 #[napi(object)]
 pub struct PtyRunResult {
-	/// Exit code when the command completes.
+	/// Exit code when the command completes (`128 + N` when killed by signal
+	/// N).
 	pub exit_code: Option<i32>,
 	/// Whether command was cancelled by signal/user kill.
 	pub cancelled: bool,
 	/// Whether command timed out.
 	pub timed_out: bool,
+	/// Structured exit status and classification.
+	pub exit:      ExitInfo,
 }
 
 #[derive(Clone)]
@@ -59,6 +79,7 @@ struct PtyRunConfig {
 	env:     Option<HashMap<String, String>>,
 	cols:    u16,
 	rows:    u16,
+	output:  Option<Arc<OutputRing>>,
 }
 
 enum ReaderEvent {
@@ -87,7 +108,9 @@ struct PtySessionCore {
 /// Stateful PTY session for interactive stdin/stdout passthrough.
 #[napi]
 pub struct PtySession {
-	core: Arc<Mutex<Option<PtySessionCore>>>,
+	core:   Arc<Mutex<Option<PtySessionCore>>>,
+	/// Output buffer of the current or last run.
+	output: Mutex<Option<Arc<OutputRing>>>,
 }
 
 impl Default for PtySession {
@@ -100,7 +123,7 @@ impl Default for PtySession {
 impl PtySession {
 	#[napi(constructor)]
 	pub fn new() -> Self {
-		Self { core: Arc::new(Mutex::new(None)) }
+		Self { core: Arc::new(Mutex::new(None)), output: Mutex::new(None) }
 	}
 
 	/// Start a PTY command and stream output chunks via callback.
@@ -113,14 +136,21 @@ impl PtySession {
 			ThreadsafeFunction<String>,
 		>,
 	) -> Result<PromiseRaw<'env, PtyRunResult>> {
+		let policy = OverflowPolicy::parse(options.output_overflow.as_deref())?;
+		let output = match options.output_buffer_bytes.unwrap_or(DEFAULT_OUTPUT_BYTES) {
+			0 => None,
+			capacity => Some(Arc::new(OutputRing::new(capacity as usize, policy))),
+		};
 		let run_config = PtyRunConfig {
 			command: options.command,
 			cwd:     options.cwd,
 			env:     options.env,
 			cols:    options.cols.unwrap_or(120).clamp(20, 400),
 			rows:    options.rows.unwrap_or(40).clamp(5, 200),
+			output:  output.clone(),
 		};
-		let ct = task::CancelToken::new(options.timeout_ms, options.signal);
+		let ct = task::CancelToken::new(options.timeout_ms, options.signal)
+			.with_scope(options.scope.as_deref());
 		let core = Arc::clone(&self.core);
 
 		// Register control channel synchronously so write()/kill() work immediately.
@@ -134,6 +164,10 @@ impl PtySession {
 			}
 			*guard = Some(PtySessionCore { control_tx });
 		}
+		*self
+			.output
+			.lock()
+			.map_err(|_| Error::from_reason("PTY session lock poisoned"))? = output;
 		task::future(env, "pty.start", async move {
 			let run_result =
 				tokio::task::spawn_blocking(move || run_pty_sync(run_config, on_chunk, control_rx, ct))
@@ -173,6 +207,21 @@ impl PtySession {
 	pub fn kill(&self) -> Result<()> {
 		self.send_control(ControlMessage::Kill)
 	}
+
+	/// Read buffered output lines of the current or last run (by default the
+	/// last 200). Returned lines count as consumed for the "block" policy.
+	#[napi(js_name = "readOutput")]
+	pub fn read_output(&self, options: Option<PtyReadOutputOptions>) -> Result<PtyOutput> {
+		let (from_line, max_lines) =
+			options.map_or((None, None), |options| (options.from_line, options.max_lines));
+		let guard = self
+			.output
+			.lock()
+			.map_err(|_| Error::from_reason("PTY session lock poisoned"))?;
+		Ok(guard.as_ref().map_or_else(PtyOutput::empty, |output| {
+			output.read(from_line, max_lines.unwrap_or(DEFAULT_READ_LINES))
+		}))
+	}
 }
 
 impl PtySession {
@@ -276,7 +325,14 @@ fn run_pty_sync(
 		.map_err(|err| Error::from_reason(format!("Failed to create PTY reader: {err}")))?;
 
 	let (reader_tx, reader_rx) = mpsc::channel::<ReaderEvent>();
+	let output = config.output.clone();
 	let reader_thread = std::thread::spawn(move || {
+		let send = |text: String| {
+			if let Some(output) = output.as_deref() {
+				output.push(&text);
+			}
+			let _ = reader_tx.send(ReaderEvent::Chunk(text));
+		};
 		const REPLACEMENT: &str = "\u{FFFD}";
 		const BUF: usize = 4096;
 		let mut buf = [0u8; BUF + 4];
@@ -292,7 +348,7 @@ fn run_pty_sync(
 						let pending = &buf[..it];
 						match str::from_utf8(pending) {
 							Ok(text) => {
-								let _ = reader_tx.send(ReaderEvent::Chunk(text.to_string()));
+								send(text.to_string());
 								it = 0;
 								break;
 							},
@@ -301,13 +357,13 @@ fn run_pty_sync(
 								if valid_up_to > 0 {
 									// SAFETY: [..valid_up_to] is guaranteed valid UTF-8 by valid_up_to().
 									let text = unsafe { str::from_utf8_unchecked(&pending[..valid_up_to]) };
-									let _ = reader_tx.send(ReaderEvent::Chunk(text.to_string()));
+									send(text.to_string());
 									buf.copy_within(valid_up_to..it, 0);
 									it -= valid_up_to;
 								}
 								match err.error_len() {
 									Some(invalid_len) => {
-										let _ = reader_tx.send(ReaderEvent::Chunk(REPLACEMENT.to_string()));
+										send(REPLACEMENT.to_string());
 										buf.copy_within(invalid_len..it, 0);
 										it -= invalid_len;
 									},
@@ -327,10 +383,10 @@ fn run_pty_sync(
 		for chunk in buf[..it].utf8_chunks() {
 			let valid = chunk.valid();
 			if !valid.is_empty() {
-				let _ = reader_tx.send(ReaderEvent::Chunk(valid.to_string()));
+				send(valid.to_string());
 			}
 			if !chunk.invalid().is_empty() {
-				let _ = reader_tx.send(ReaderEvent::Chunk(REPLACEMENT.to_string()));
+				send(REPLACEMENT.to_string());
 			}
 		}
 		let _ = reader_tx.send(ReaderEvent::Done);
@@ -346,10 +402,10 @@ fn run_pty_sync(
 	let mut timed_out = false;
 	let mut cancelled = false;
 	let mut reader_done = false;
-	let mut exit_code: Option<i32> = None;
+	let mut exit: Option<ExitInfo> = None;
 	let mut terminate_requested = false;
 	let mut reader_drain_deadline: Option<Instant> = None;
-	while exit_code.is_none() || !reader_done {
+	while exit.is_none() || !reader_done {
 		if !terminate_requested && let Err(err) = ct.heartbeat() {
 			let message = err.to_string();
 			timed_out = message.contains("Timeout");
@@ -357,6 +413,7 @@ fn run_pty_sync(
 			terminate_pty_processes(&mut child, child_pid, process_group_id);
 			terminate_requested = true;
 			reader_drain_deadline = Some(Instant::now() + POST_CANCEL_DRAIN_TIMEOUT);
+			close_output(config.output.as_deref());
 		}
 
 		for _ in 0..CONTROL_MESSAGES_PER_TICK {
@@ -374,6 +431,7 @@ fn run_pty_sync(
 						terminate_pty_processes(&mut child, child_pid, process_group_id);
 						terminate_requested = true;
 						reader_drain_deadline = Some(Instant::now() + POST_CANCEL_DRAIN_TIMEOUT);
+						close_output(config.output.as_deref());
 					}
 				},
 				Err(mpsc::TryRecvError::Empty) => break,
@@ -395,12 +453,10 @@ fn run_pty_sync(
 				},
 			}
 		}
-		if exit_code.is_none()
-			&& let Some(status) = child
-				.try_wait()
-				.map_err(|err| Error::from_reason(format!("Failed checking PTY status: {err}")))?
+		if exit.is_none()
+			&& let Some(status) = wait_exit(&mut child, child_pid, false)?
 		{
-			exit_code = Some(i32::try_from(status.exit_code()).unwrap_or(i32::MAX));
+			exit = Some(status);
 			if !reader_done && reader_drain_deadline.is_none() {
 				reader_drain_deadline = Some(Instant::now() + POST_EXIT_DRAIN_TIMEOUT);
 			}
@@ -411,7 +467,7 @@ fn run_pty_sync(
 		{
 			break;
 		}
-		if exit_code.is_none() || !reader_done {
+		if exit.is_none() || !reader_done {
 			let wait_duration = reader_drain_deadline.map_or(Duration::from_millis(16), |deadline| {
 				deadline
 					.saturating_duration_since(Instant::now())
@@ -423,31 +479,21 @@ fn run_pty_sync(
 				Err(mpsc::RecvTimeoutError::Timeout) => {},
 				Err(mpsc::RecvTimeoutError::Disconnected) => {
 					reader_done = true;
-					if exit_code.is_none() {
+					if exit.is_none() {
 						std::thread::sleep(wait_duration);
 					}
 				},
 			}
 		}
 	}
-	if exit_code.is_none() {
-		if terminate_requested {
-			if let Some(status) = child
-				.try_wait()
-				.map_err(|err| Error::from_reason(format!("Failed checking PTY status: {err}")))?
-			{
-				exit_code = Some(i32::try_from(status.exit_code()).unwrap_or(i32::MAX));
-			}
-		} else {
-			let status = child
-				.wait()
-				.map_err(|err| Error::from_reason(format!("Failed waiting PTY process: {err}")))?;
-			exit_code = Some(i32::try_from(status.exit_code()).unwrap_or(i32::MAX));
-		}
+	if exit.is_none() {
+		exit = wait_exit(&mut child, child_pid, !terminate_requested)?;
 	}
 
 	drop(writer);
 	drop(master);
+	// A reader blocked on a full buffer would otherwise never see EOF.
+	close_output(config.output.as_deref());
 
 	if !reader_done {
 		let finalize_deadline = Instant::now() + FINAL_READER_DRAIN_TIMEOUT;
@@ -474,7 +520,66 @@ fn run_pty_sync(
 	if reader_done {
 		let _ = reader_thread.join();
 	}
-	Ok(PtyRunResult { exit_code, cancelled, timed_out })
+	let exit_code = exit.as_ref().and_then(ExitInfo::shell_code);
+	let exit = match exit {
+		Some(exit) if cancelled || timed_out => exit.with_abort(timed_out),
+		Some(exit) => exit,
+		None => ExitInfo::aborted(timed_out),
+	};
+	Ok(PtyRunResult { exit_code, cancelled, timed_out, exit })
+}
+
+/// Poll for the command's exit status, or wait for it when `block` is set.
+///
+/// On Unix this reaps with `waitpid` directly: portable-pty's status keeps
+/// only a signal description and drops the core-dump flag.
+fn wait_exit(
+	child: &mut Box<dyn Child + Send + Sync>,
+	child_pid: Option<i32>,
+	block: bool,
+) -> Result<Option<ExitInfo>> {
+	#[cfg(unix)]
+	if let Some(pid) = child_pid {
+		let flags = if block { 0 } else { libc::WNOHANG };
+		let mut status = 0;
+		let reaped = loop {
+			// SAFETY: `status` is a valid out-pointer and `pid` is our own child.
+			let reaped = unsafe { libc::waitpid(pid, &raw mut status, flags) };
+			if reaped != -1
+				|| std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted
+			{
+				break reaped;
+			}
+		};
+		if reaped == pid {
+			return Ok(Some(ExitInfo::from_wait_status(status)));
+		}
+		if reaped == 0 {
+			return Ok(None);
+		}
+		// Reaped elsewhere; fall back to portable-pty's view.
+	}
+	#[cfg(not(unix))]
+	let _ = child_pid;
+
+	let status = if block {
+		Some(
+			child
+				.wait()
+				.map_err(|err| Error::from_reason(format!("Failed waiting PTY process: {err}")))?,
+		)
+	} else {
+		child
+			.try_wait()
+			.map_err(|err| Error::from_reason(format!("Failed checking PTY status: {err}")))?
+	};
+	Ok(status.map(|status| ExitInfo::exited(i32::try_from(status.exit_code()).unwrap_or(i32::MAX))))
+}
+
+fn close_output(output: Option<&OutputRing>) {
+	if let Some(output) = output {
+		output.close();
+	}
 }
 
 fn emit_chunk(text: &str, callback: Option<&ThreadsafeFunction<String>>) {
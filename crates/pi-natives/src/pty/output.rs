@@ -0,0 +1,265 @@
+//! Bounded, line-oriented capture of PTY output.
+//!
+//! Keeps the last N bytes of a session's output as numbered lines so callers
+//! can ask for "the last 200 lines" without accumulating every chunk in JS.
+//! When full, the buffer either evicts its oldest lines or, with the `block`
+//! policy, stops draining the PTY until unread lines are consumed, which
+//! stalls the child's writes instead of dropping output.
+
+use std::collections::VecDeque;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+/// Default capacity of the output buffer, in bytes.
+pub const DEFAULT_OUTPUT_BYTES: u32 = 1024 * 1024;
+
+/// What happens when the output buffer is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+	/// Evict the oldest lines.
+	DropOldest,
+	/// Stop reading the PTY until unread lines are consumed by `readOutput()`.
+	Block,
+}
+
+impl OverflowPolicy {
+	/// Parse the `outputOverflow` option ("dropOldest" or "block").
+	///
+	/// # Errors
+	/// Fails on any other value.
+	pub fn parse(value: Option<&str>) -> Result<Self> {
+		match value {
+			None | Some("dropOldest") => Ok(Self::DropOldest),
+			Some("block") => Ok(Self::Block),
+			Some(other) => Err(Error::from_reason(format!(
+				"Unknown output overflow policy: {other} (expected \"dropOldest\" or \"block\")"
+			))),
+		}
+	}
+}
+
+/// Options for `PtySession.readOutput()`.
+#[napi(object)]
+pub struct PtyReadOutputOptions {
+	/// First line to return (0-based, counted from the start of the session).
+	/// Defaults to the last `maxLines` lines.
+	#[napi(js_name = "fromLine")]
+	pub from_line: Option<u32>,
+	/// Maximum number of lines to return (default: 200).
+	#[napi(js_name = "maxLines")]
+	pub max_lines: Option<u32>,
+}
+
+/// Lines read from a PTY session's output buffer.
+#[napi(object)]
+pub struct PtyOutput {
+	/// Lines without terminators; the last may be an unterminated line.
+	pub lines:         Vec<String>,
+	/// Line number of `lines[0]` (0-based).
+	#[napi(js_name = "firstLine")]
+	pub first_line:    u32,
+	/// Line number to pass as `fromLine` to continue reading.
+	#[napi(js_name = "nextLine")]
+	pub next_line:     u32,
+	/// Lines produced so far, including evicted ones.
+	#[napi(js_name = "totalLines")]
+	pub total_lines:   u32,
+	/// Oldest line number still retained.
+	#[napi(js_name = "oldestLine")]
+	pub oldest_line:   u32,
+	/// Bytes evicted from the buffer so far.
+	#[napi(js_name = "droppedBytes")]
+	pub dropped_bytes: i64,
+}
+
+impl PtyOutput {
+	pub const fn empty() -> Self {
+		Self {
+			lines:         Vec::new(),
+			first_line:    0,
+			next_line:     0,
+			total_lines:   0,
+			oldest_line:   0,
+			dropped_bytes: 0,
+		}
+	}
+}
+
+struct RingState {
+	/// Complete lines, oldest first, without terminators.
+	lines:         VecDeque<String>,
+	/// Unterminated trailing line.
+	partial:       String,
+	/// Line number of `lines[0]`.
+	first_line:    u64,
+	/// Bytes held in `lines` and `partial`.
+	bytes:         usize,
+	dropped_bytes: u64,
+	/// Lines before this one were returned by `read()`.
+	read_upto:     u64,
+	/// Stop blocking writers; set once the session ends.
+	closed:        bool,
+}
+
+impl RingState {
+	fn total_lines(&self) -> u64 {
+		self.first_line + self.lines.len() as u64 + u64::from(!self.partial.is_empty())
+	}
+
+	fn evict_front(&mut self) {
+		if let Some(line) = self.lines.pop_front() {
+			self.bytes -= line.len();
+			self.dropped_bytes += line.len() as u64;
+			self.first_line += 1;
+		}
+	}
+
+	fn line(&self, number: u64) -> &str {
+		let idx = (number - self.first_line) as usize;
+		self
+			.lines
+			.get(idx)
+			.map_or(self.partial.as_str(), String::as_str)
+	}
+}
+
+/// Output buffer shared between a PTY reader thread and `readOutput()`.
+pub struct OutputRing {
+	capacity: usize,
+	policy:   OverflowPolicy,
+	state:    Mutex<RingState>,
+	/// Signalled when lines are read or the ring is closed.
+	space:    Condvar,
+}
+
+impl OutputRing {
+	pub const fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+		Self {
+			capacity,
+			policy,
+			state: Mutex::new(RingState {
+				lines:         VecDeque::new(),
+				partial:       String::new(),
+				first_line:    0,
+				bytes:         0,
+				dropped_bytes: 0,
+				read_upto:     0,
+				closed:        false,
+			}),
+			space: Condvar::new(),
+		}
+	}
+
+	/// Append decoded output, evicting or waiting for space per the policy.
+	pub fn push(&self, text: &str) {
+		let mut state = self.state.lock();
+		for piece in text.split_inclusive('\n') {
+			state.partial.push_str(piece);
+			state.bytes += piece.len();
+			if state.partial.ends_with('\n') {
+				let mut line = std::mem::take(&mut state.partial);
+				let terminated_len = line.len();
+				line.pop();
+				if line.ends_with('\r') {
+					line.pop();
+				}
+				state.bytes -= terminated_len - line.len();
+				state.lines.push_back(line);
+			}
+			self.make_room(&mut state);
+		}
+	}
+
+	fn make_room(&self, state: &mut MutexGuard<'_, RingState>) {
+		while state.bytes > self.capacity && !state.lines.is_empty() {
+			if self.policy == OverflowPolicy::Block
+				&& !state.closed
+				&& state.first_line >= state.read_upto
+			{
+				self.space.wait(state);
+				continue;
+			}
+			state.evict_front();
+		}
+		// A single unterminated line longer than the buffer keeps its tail.
+		if state.bytes > self.capacity && state.lines.is_empty() {
+			let excess = state.partial.len() - self.capacity;
+			let cut = state.partial.ceil_char_boundary(excess);
+			state.partial.drain(..cut);
+			state.bytes -= cut;
+			state.dropped_bytes += cut as u64;
+		}
+	}
+
+	/// Release a blocked writer; later overflow evicts the oldest lines.
+	pub fn close(&self) {
+		self.state.lock().closed = true;
+		self.space.notify_all();
+	}
+
+	/// Read lines and mark them consumed.
+	pub fn read(&self, from_line: Option<u32>, max_lines: u32) -> PtyOutput {
+		let mut state = self.state.lock();
+		let total = state.total_lines();
+		let start = from_line
+			.map_or_else(|| total.saturating_sub(u64::from(max_lines)), u64::from)
+			.clamp(state.first_line, total);
+		let end = start.saturating_add(u64::from(max_lines)).min(total);
+		let lines = (start..end)
+			.map(|number| state.line(number).to_string())
+			.collect();
+		if end > state.read_upto {
+			state.read_upto = end;
+			self.space.notify_all();
+		}
+		PtyOutput {
+			lines,
+			first_line: crate::utils::clamp_u32(start),
+			next_line: crate::utils::clamp_u32(end),
+			total_lines: crate::utils::clamp_u32(total),
+			oldest_line: crate::utils::clamp_u32(state.first_line),
+			dropped_bytes: i64::try_from(state.dropped_bytes).unwrap_or(i64::MAX),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{sync::Arc, time::Duration};
+
+	use super::*;
+
+	#[test]
+	fn keeps_last_lines_within_capacity() {
+		let ring = OutputRing::new(12, OverflowPolicy::DropOldest);
+		ring.push("one\r\ntwo\nthr");
+		ring.push("ee\nfour\nfi");
+
+		let tail = ring.read(None, 2);
+		assert_eq!(tail.lines, ["four", "fi"]);
+		assert_eq!((tail.first_line, tail.next_line, tail.total_lines), (3, 5, 5));
+
+		let all = ring.read(Some(0), 10);
+		assert_eq!(all.lines, ["three", "four", "fi"]);
+		assert_eq!((all.oldest_line, all.dropped_bytes), (2, 6));
+	}
+
+	#[test]
+	fn block_policy_waits_for_reader() {
+		let ring = Arc::new(OutputRing::new(8, OverflowPolicy::Block));
+		ring.push("aaaa\nbbbb\n");
+
+		let writer = std::thread::spawn({
+			let ring = Arc::clone(&ring);
+			move || ring.push("cccc\n")
+		});
+		std::thread::sleep(Duration::from_millis(20));
+		assert!(!writer.is_finished());
+
+		assert_eq!(ring.read(Some(0), 1).lines, ["aaaa"]);
+		writer.join().unwrap();
+		assert_eq!(ring.read(Some(0), 10).lines, ["bbbb", "cccc"]);
+	}
+}
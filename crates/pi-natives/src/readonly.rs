@@ -0,0 +1,46 @@
+//! Process-wide read-only switch for the native layer.
+//!
+//! When enabled, every export that mutates the filesystem (`astEdit` and
+//! `removePaths` without `dryRun`, `movePath`, journal writes and recovery,
+//! `projfsOverlayStart`, `createScratchDir`, `cleanupScratch`) fails with an
+//! error whose message starts with [`READ_ONLY_PREFIX`], so plan-mode style
+//! guarantees hold even if a JS caller forgets to check. The switch is part of
+//! [`config::Config`], so a call that already started keeps the mode it
+//! started with.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::config;
+
+/// Error message prefix for operations blocked by read-only mode.
+pub const READ_ONLY_PREFIX: &str = "READ_ONLY:";
+
+/// Whether read-only mode is enabled for the current call.
+pub fn enabled() -> bool {
+	config::current().read_only
+}
+
+/// Fail with a `READ_ONLY:` error if read-only mode is enabled.
+///
+/// `operation` names the blocked export in the error message.
+pub fn ensure_writable(operation: &str) -> Result<()> {
+	if enabled() {
+		return Err(Error::from_reason(format!(
+			"{READ_ONLY_PREFIX} {operation} is blocked while the native layer is read-only"
+		)));
+	}
+	Ok(())
+}
+
+/// Enable or disable read-only mode for all mutating native exports.
+#[napi(js_name = "setReadOnly")]
+pub fn set_read_only(enabled: bool) {
+	config::update(|config| config.read_only = enabled);
+}
+
+/// Whether read-only mode is currently enabled.
+#[napi(js_name = "isReadOnly")]
+pub fn is_read_only() -> bool {
+	enabled()
+}
@@ -0,0 +1,192 @@
+//! Regex engine selection for content search.
+//!
+//! The default engine is `grep_regex` (finite automata, linear time), which
+//! rejects lookaround and backreferences. The `pcre2` engine accepts that
+//! PCRE2-style syntax through `fancy-regex`, a backtracking engine that
+//! delegates lookaround-free subexpressions to the default engine. Patterns
+//! that fail to compile on the default engine fall back to it automatically.
+
+use std::io;
+
+use grep_matcher::{ByteSet, LineMatchKind, LineTerminator, Match, Matcher, NoCaptures};
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use napi::bindgen_prelude::*;
+
+/// Regex engine requested by a search call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegexEngine {
+	/// `grep_regex`, falling back to `Pcre2` when the pattern fails to compile.
+	#[default]
+	Default,
+	/// Backtracking engine with lookaround and backreference support.
+	Pcre2,
+}
+
+impl RegexEngine {
+	/// Parse the `engine` option ("default" or "pcre2").
+	///
+	/// # Errors
+	/// Fails on any other value.
+	pub fn parse(value: Option<&str>) -> Result<Self> {
+		match value.map(str::trim) {
+			None | Some("" | "default") => Ok(Self::Default),
+			Some("pcre2") => Ok(Self::Pcre2),
+			Some(other) => Err(Error::from_reason(format!(
+				"Unknown regex engine: {other} (expected \"default\" or \"pcre2\")"
+			))),
+		}
+	}
+}
+
+/// A compiled pattern from either engine, usable with `grep_searcher`.
+#[derive(Clone, Debug)]
+pub enum PatternMatcher {
+	Default(RegexMatcher),
+	Pcre2(FancyMatcher),
+}
+
+impl PatternMatcher {
+	/// Compile `pattern` with the requested engine.
+	///
+	/// With [`RegexEngine::Default`], a compile error retries on the `pcre2`
+	/// engine; the original error is reported if both fail.
+	///
+	/// # Errors
+	/// Fails when the pattern is invalid for the selected engine(s).
+	pub fn build(
+		pattern: &str,
+		engine: RegexEngine,
+		ignore_case: bool,
+		multiline: bool,
+	) -> Result<Self> {
+		if engine == RegexEngine::Pcre2 {
+			return FancyMatcher::new(pattern, ignore_case, multiline).map(Self::Pcre2);
+		}
+		match RegexMatcherBuilder::new()
+			.case_insensitive(ignore_case)
+			.multi_line(multiline)
+			.build(pattern)
+		{
+			Ok(matcher) => Ok(Self::Default(matcher)),
+			Err(err) => FancyMatcher::new(pattern, ignore_case, multiline)
+				.map(Self::Pcre2)
+				.map_err(|_| Error::from_reason(format!("Regex error: {err}"))),
+		}
+	}
+}
+
+impl Matcher for PatternMatcher {
+	type Captures = NoCaptures;
+	type Error = io::Error;
+
+	fn find_at(&self, haystack: &[u8], at: usize) -> io::Result<Option<Match>> {
+		match self {
+			Self::Default(matcher) => Ok(matcher.find_at(haystack, at)?),
+			Self::Pcre2(matcher) => matcher.find_at(haystack, at),
+		}
+	}
+
+	fn new_captures(&self) -> io::Result<NoCaptures> {
+		Ok(NoCaptures::new())
+	}
+
+	fn is_match_at(&self, haystack: &[u8], at: usize) -> io::Result<bool> {
+		match self {
+			Self::Default(matcher) => Ok(matcher.is_match_at(haystack, at)?),
+			Self::Pcre2(matcher) => Ok(matcher.find_at(haystack, at)?.is_some()),
+		}
+	}
+
+	fn non_matching_bytes(&self) -> Option<&ByteSet> {
+		match self {
+			Self::Default(matcher) => matcher.non_matching_bytes(),
+			Self::Pcre2(_) => None,
+		}
+	}
+
+	fn line_terminator(&self) -> Option<LineTerminator> {
+		match self {
+			Self::Default(matcher) => matcher.line_terminator(),
+			// Without a line terminator the searcher matches line by line, which
+			// keeps `\s` and lookaround from crossing lines in non-multiline mode.
+			Self::Pcre2(_) => None,
+		}
+	}
+
+	fn find_candidate_line(&self, haystack: &[u8]) -> io::Result<Option<LineMatchKind>> {
+		match self {
+			Self::Default(matcher) => Ok(matcher.find_candidate_line(haystack)?),
+			Self::Pcre2(matcher) => Ok(matcher
+				.find_at(haystack, 0)?
+				.map(|m| LineMatchKind::Confirmed(m.start()))),
+		}
+	}
+}
+
+/// `fancy-regex` adapter for byte haystacks.
+#[derive(Clone, Debug)]
+pub struct FancyMatcher {
+	regex: fancy_regex::Regex,
+}
+
+impl FancyMatcher {
+	fn new(pattern: &str, ignore_case: bool, multiline: bool) -> Result<Self> {
+		let regex = fancy_regex::RegexBuilder::new(pattern)
+			.case_insensitive(ignore_case)
+			.multi_line(multiline)
+			.build()
+			.map_err(|err| Error::from_reason(format!("Regex error: {err}")))?;
+		Ok(Self { regex })
+	}
+
+	/// Find the first match starting at or after byte `at`.
+	///
+	/// Invalid UTF-8 sequences never match; each valid run is searched on its
+	/// own, so lookbehind does not see past an invalid byte.
+	fn find_at(&self, haystack: &[u8], at: usize) -> io::Result<Option<Match>> {
+		if let Ok(text) = std::str::from_utf8(haystack) {
+			return self.find_in(text, 0, at);
+		}
+		let mut base = 0;
+		for chunk in haystack.utf8_chunks() {
+			let valid = chunk.valid();
+			if base + valid.len() >= at
+				&& let Some(found) = self.find_in(valid, base, at)?
+			{
+				return Ok(Some(found));
+			}
+			base += valid.len() + chunk.invalid().len();
+		}
+		Ok(None)
+	}
+
+	/// Search `text` (located at byte `base` of the haystack) from haystack
+	/// offset `at`, returning haystack offsets.
+	fn find_in(&self, text: &str, base: usize, at: usize) -> io::Result<Option<Match>> {
+		let from = text.ceil_char_boundary(at.saturating_sub(base));
+		let found = self
+			.regex
+			.find_from_pos(text, from)
+			.map_err(io::Error::other)?;
+		Ok(found.map(|m| Match::new(base + m.start(), base + m.end())))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn falls_back_to_pcre2_for_lookaround() {
+		let matcher = PatternMatcher::build(r"(?<=foo)bar", RegexEngine::Default, false, false)
+			.expect("lookbehind should compile via fallback");
+		assert!(matches!(matcher, PatternMatcher::Pcre2(_)));
+		assert_eq!(matcher.find(b"bar foobar").unwrap(), Some(Match::new(7, 10)));
+
+		let backref = PatternMatcher::build(r"(\w+) \1", RegexEngine::Pcre2, false, false).unwrap();
+		assert_eq!(backref.find(b"\xffsay hello hello").unwrap(), Some(Match::new(5, 16)));
+
+		assert!(PatternMatcher::build("(", RegexEngine::Default, false, false).is_err());
+		assert!(RegexEngine::parse(Some("onig")).is_err());
+	}
+}
@@ -0,0 +1,288 @@
+//! Scratch directories for temporary files.
+//!
+//! `createScratchDir()` hands out temporary directories so callers need not
+//! invent their own temp handling. Every directory lives under one root and is
+//! tracked for the JS environment that created it; the tracked directories are
+//! removed when that environment shuts down. Directories a crashed process
+//! left behind are removed with `cleanupScratch(olderThanMs)`.
+//!
+//! # Layout
+//! `<root>/<prefix>-<pid>-<seq>-<nanos>`, next to a `<name>.lock` file the
+//! owning process holds an exclusive lock on while the directory is in use.
+//! `cleanupScratch()` skips directories whose lock is held, so a live
+//! process's directories survive however long they sit idle, even when its
+//! pid was reused.
+//!
+//! # Policy Configuration (environment overrides)
+//! - `FS_SCRATCH_DIR` – default `<tmp>/pi-natives-scratch`
+
+use std::{
+	collections::HashSet,
+	fs::{self, File, TryLockError},
+	io,
+	path::{Path, PathBuf},
+	sync::{
+		LazyLock,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use napi::{Env, bindgen_prelude::*};
+use napi_derive::napi;
+use parking_lot::Mutex;
+
+use crate::{readonly, task};
+
+/// Longest prefix kept in directory names.
+const MAX_PREFIX_LEN: usize = 32;
+
+/// Directory holding all scratch directories.
+pub fn scratch_root() -> PathBuf {
+	std::env::var_os("FS_SCRATCH_DIR")
+		.filter(|value| !value.is_empty())
+		.map_or_else(|| std::env::temp_dir().join("pi-natives-scratch"), PathBuf::from)
+}
+
+/// A scratch directory and the lock marking it as in use.
+struct ScratchDir {
+	path: PathBuf,
+	lock: File,
+}
+
+impl ScratchDir {
+	/// Remove the directory, then release and remove its lock.
+	fn remove(self) {
+		let _ = fs::remove_dir_all(&self.path);
+		drop(self.lock);
+		let _ = fs::remove_file(lock_path(&self.path));
+	}
+}
+
+/// Scratch directories of this process, keyed by the creating environment.
+static SESSION: LazyLock<Mutex<Vec<(usize, ScratchDir)>>> = LazyLock::new(Mutex::default);
+/// Environments with a cleanup hook registered.
+static HOOKED: LazyLock<Mutex<HashSet<usize>>> = LazyLock::new(Mutex::default);
+
+/// Keep `[A-Za-z0-9_-]` of `prefix`, so it cannot escape the root.
+fn sanitize_prefix(prefix: Option<&str>) -> String {
+	let cleaned: String = prefix
+		.unwrap_or_default()
+		.chars()
+		.filter(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+		.take(MAX_PREFIX_LEN)
+		.collect();
+	if cleaned.is_empty() {
+		"scratch".to_string()
+	} else {
+		cleaned
+	}
+}
+
+/// Lock file marking the scratch directory `dir` as in use.
+fn lock_path(dir: &Path) -> PathBuf {
+	let mut name = dir.as_os_str().to_owned();
+	name.push(".lock");
+	PathBuf::from(name)
+}
+
+/// Create a fresh directory under `root`, locked before it exists so cleanup
+/// never sees it unclaimed.
+fn create_in(root: &Path, prefix: &str) -> io::Result<ScratchDir> {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+	fs::create_dir_all(root)?;
+	loop {
+		let nanos = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |d| d.as_nanos());
+		let seq = COUNTER.fetch_add(1, Ordering::Relaxed);
+		let path = root.join(format!("{prefix}-{}-{seq}-{nanos:x}", std::process::id()));
+		let lock = match File::create_new(lock_path(&path)) {
+			Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+			result => result?,
+		};
+		lock.try_lock()?;
+		match fs::create_dir(&path) {
+			Ok(()) => return Ok(ScratchDir { path, lock }),
+			Err(err) => {
+				let _ = fs::remove_file(lock_path(&path));
+				if err.kind() != io::ErrorKind::AlreadyExists {
+					return Err(err);
+				}
+			},
+		}
+	}
+}
+
+/// Whether a live process holds the lock of scratch directory `dir`.
+fn in_use(dir: &Path) -> io::Result<bool> {
+	let lock = match File::open(lock_path(dir)) {
+		Ok(lock) => lock,
+		// Never locked, or its owner finished cleaning up.
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+		Err(err) => return Err(err),
+	};
+	match lock.try_lock() {
+		Ok(()) => Ok(false),
+		Err(TryLockError::WouldBlock) => Ok(true),
+		Err(TryLockError::Error(err)) => Err(err),
+	}
+}
+
+/// Owning process id encoded in a scratch directory name.
+fn owner_pid(name: &str) -> Option<u32> {
+	let mut parts = name.rsplitn(4, '-');
+	let (_nanos, _seq, pid) = (parts.next()?, parts.next()?, parts.next()?);
+	parts.next()?;
+	pid.parse().ok()
+}
+
+/// Newest modification time of `dir` and its direct entries.
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+	let own = fs::metadata(dir)
+		.and_then(|metadata| metadata.modified())
+		.ok()?;
+	let entries = fs::read_dir(dir).ok()?;
+	Some(
+		entries
+			.filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+			.fold(own, SystemTime::max),
+	)
+}
+
+/// Remove directories under `root` whose owning process is gone and that were
+/// unmodified for `max_age`.
+fn cleanup_in(root: &Path, max_age: Duration, ct: &task::CancelToken) -> Result<Vec<String>> {
+	let entries = match fs::read_dir(root) {
+		Ok(entries) => entries,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(err) => {
+			return Err(Error::from_reason(format!("Failed to read {}: {err}", root.display())));
+		},
+	};
+	let now = SystemTime::now();
+	let mut removed = Vec::new();
+	for entry in entries.flatten() {
+		ct.heartbeat()?;
+		let path = entry.path();
+		let Some(pid) = path
+			.file_name()
+			.and_then(|name| name.to_str())
+			.and_then(owner_pid)
+		else {
+			continue;
+		};
+		if pid == std::process::id()
+			|| !entry.file_type().is_ok_and(|kind| kind.is_dir())
+			|| in_use(&path).unwrap_or(true)
+		{
+			continue;
+		}
+		let stale = last_modified(&path)
+			.and_then(|modified| now.duration_since(modified).ok())
+			.is_some_and(|age| age >= max_age);
+		if stale && fs::remove_dir_all(&path).is_ok() {
+			let _ = fs::remove_file(lock_path(&path));
+			removed.push(path.to_string_lossy().into_owned());
+		}
+	}
+	removed.sort();
+	Ok(removed)
+}
+
+/// Remove the scratch directories created from environment `env`.
+fn cleanup_env(env: usize) {
+	HOOKED.lock().remove(&env);
+	let dirs: Vec<ScratchDir> = {
+		let mut session = SESSION.lock();
+		let (owned, kept) = session.drain(..).partition(|(owner, _)| *owner == env);
+		*session = kept;
+		owned.into_iter().map(|(_, dir)| dir).collect()
+	};
+	for dir in dirs {
+		dir.remove();
+	}
+}
+
+/// Create an empty scratch directory and return its absolute path.
+///
+/// The directory is removed when this JS environment shuts down.
+///
+/// # Errors
+/// Fails when the scratch root cannot be created, or in read-only mode.
+#[napi(js_name = "createScratchDir")]
+pub fn create_scratch_dir(env: &Env, prefix: Option<String>) -> Result<String> {
+	readonly::ensure_writable("createScratchDir")?;
+	let dir = create_in(&scratch_root(), &sanitize_prefix(prefix.as_deref()))
+		.map_err(|err| Error::from_reason(format!("Failed to create scratch directory: {err}")))?;
+	let path = dir.path.to_string_lossy().into_owned();
+	let owner = env.raw() as usize;
+	if HOOKED.lock().insert(owner) {
+		env.add_env_cleanup_hook(owner, cleanup_env)?;
+	}
+	SESSION.lock().push((owner, dir));
+	Ok(path)
+}
+
+/// Remove scratch directories left behind by exited processes and return
+/// their paths.
+///
+/// Only directories not modified in the last `olderThanMs` (default: one day)
+/// are removed. Directories of running processes, this one included, are
+/// never removed.
+///
+/// # Errors
+/// Fails in read-only mode.
+#[napi(js_name = "cleanupScratch")]
+pub fn cleanup_scratch(older_than_ms: Option<f64>) -> Result<task::Async<Vec<String>>> {
+	readonly::ensure_writable("cleanupScratch")?;
+	let max_age =
+		Duration::from_millis(older_than_ms.map_or(24 * 60 * 60 * 1000, |ms| ms.max(0.0) as u64));
+	Ok(task::blocking("scratch.cleanup", (), move |ct| cleanup_in(&scratch_root(), max_age, &ct)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn creates_and_cleans_scratch_dirs() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-scratch-test-{unique}"));
+		let own = create_in(&root, &sanitize_prefix(Some("../thumbs"))).unwrap();
+		let name = own.path.file_name().unwrap().to_str().unwrap();
+		assert!(name.starts_with("thumbs-"));
+		assert_eq!(owner_pid(name), Some(std::process::id()));
+		assert_eq!(sanitize_prefix(None), "scratch");
+
+		let orphan = root.join("backup-1-0-1f");
+		fs::create_dir_all(orphan.join("nested")).unwrap();
+		File::create(lock_path(&orphan)).unwrap();
+		fs::create_dir(root.join("unrelated")).unwrap();
+		// A live process's directory, held under another pid.
+		let live = root.join("archive-2-0-2f");
+		fs::create_dir(&live).unwrap();
+		let live_lock = File::create(lock_path(&live)).unwrap();
+		live_lock.try_lock().unwrap();
+
+		let ct = task::CancelToken::default();
+		assert_eq!(cleanup_in(&root, Duration::from_secs(3600), &ct).unwrap(), Vec::<String>::new());
+		let removed = cleanup_in(&root, Duration::ZERO, &ct).unwrap();
+		assert_eq!(removed, [orphan.to_string_lossy().into_owned()]);
+		assert!(!lock_path(&orphan).exists());
+		assert!(own.path.exists() && live.exists() && root.join("unrelated").exists());
+
+		drop(live_lock);
+		assert_eq!(cleanup_in(&root, Duration::ZERO, &ct).unwrap(), [live
+			.to_string_lossy()
+			.into_owned()]);
+		let own_path = own.path.clone();
+		own.remove();
+		assert!(!own_path.exists() && !lock_path(&own_path).exists());
+
+		let _ = fs::remove_dir_all(&root);
+	}
+}
@@ -50,7 +50,7 @@ use tokio_util::sync::CancellationToken;
 #[cfg(windows)]
 use windows::configure_windows_path;
 
-use crate::task;
+use crate::{exit_status::ExitInfo, task};
 
 struct ShellSessionCore {
 	shell:         BrushShell,
@@ -96,6 +96,8 @@ pub struct ShellRunOptions<'env> {
 	pub timeout_ms: Option<u32>,
 	/// Abort signal for cancelling the operation.
 	pub signal:     Option<Unknown<'env>>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:      Option<ClassInstance<'env, task::CancellationScope>>,
 }
 
 /// Result of running a shell command.
@@ -107,6 +109,8 @@ pub struct ShellRunResult {
 	pub cancelled: bool,
 	/// Whether the command timed out before completion.
 	pub timed_out: bool,
+	/// Structured exit status and classification.
+	pub exit:      ExitInfo,
 }
 
 /// Persistent brush-core shell session.
@@ -144,7 +148,8 @@ impl Shell {
 			ThreadsafeFunction<String>,
 		>,
 	) -> Result<PromiseRaw<'e, ShellRunResult>> {
-		let ct = task::CancelToken::new(options.timeout_ms, options.signal);
+		let ct = task::CancelToken::new(options.timeout_ms, options.signal)
+			.with_scope(options.scope.as_deref());
 		let session = self.session.clone();
 		let config = self.config.clone();
 
@@ -211,10 +216,12 @@ async fn run_shell_session(
 			if let Ok(mut guard) = session.try_lock() {
 				*guard = None;
 			}
+			let timed_out = matches!(reason, task::AbortReason::Timeout);
 			return Ok(ShellRunResult {
 				exit_code: None,
 				cancelled: matches!(reason, task::AbortReason::Signal),
-				timed_out: matches!(reason, task::AbortReason::Timeout),
+				timed_out,
+				exit: ExitInfo::aborted(timed_out),
 			});
 		}
 	};
@@ -230,7 +237,13 @@ async fn run_shell_session(
 	} else {
 		*session.lock().await = None;
 	}
-	Ok(ShellRunResult { exit_code: Some(exit_code(&res?)), cancelled: false, timed_out: false })
+	let exit_code = exit_code(&res?);
+	Ok(ShellRunResult {
+		exit_code: Some(exit_code),
+		cancelled: false,
+		timed_out: false,
+		exit:      ExitInfo::from_shell_code(exit_code),
+	})
 }
 
 /// Options for executing a shell command via brush-core.
@@ -252,6 +265,8 @@ pub struct ShellExecuteOptions<'env> {
 	pub snapshot_path: Option<String>,
 	/// Abort signal for cancelling the operation.
 	pub signal:        Option<Unknown<'env>>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:         Option<ClassInstance<'env, task::CancellationScope>>,
 }
 
 /// Result of executing a shell command via brush-core.
@@ -263,6 +278,8 @@ pub struct ShellExecuteResult {
 	pub cancelled: bool,
 	/// Whether the command timed out before completion.
 	pub timed_out: bool,
+	/// Structured exit status and classification.
+	pub exit:      ExitInfo,
 }
 
 /// Execute a brush shell command.
@@ -283,7 +300,8 @@ pub fn execute_shell<'env>(
 	let run_config =
 		ShellRunConfig { command: options.command, cwd: options.cwd, env: options.env };
 
-	let ct = task::CancelToken::new(options.timeout_ms, options.signal);
+	let ct = task::CancelToken::new(options.timeout_ms, options.signal)
+		.with_scope(options.scope.as_deref());
 	task::future(env, "shell.execute", async move {
 		run_shell_oneshot(config, run_config, on_chunk, ct).await
 	})
@@ -315,10 +333,12 @@ async fn run_shell_oneshot(
 				task.abort();
 				let _ = task.await;
 			}
+			let timed_out = matches!(reason, task::AbortReason::Timeout);
 			return Ok(ShellExecuteResult {
 				exit_code: None,
 				cancelled: matches!(reason, task::AbortReason::Signal),
-				timed_out: matches!(reason, task::AbortReason::Timeout),
+				timed_out,
+				exit: ExitInfo::aborted(timed_out),
 			})
 		},
 	};
@@ -326,7 +346,13 @@ async fn run_shell_oneshot(
 	let res = run_result
 		.unwrap_or_else(|e| Err(Error::from_reason(format!("Shell execution task failed: {e}"))));
 
-	Ok(ShellExecuteResult { exit_code: Some(exit_code(&res?)), cancelled: false, timed_out: false })
+	let exit_code = exit_code(&res?);
+	Ok(ShellExecuteResult {
+		exit_code: Some(exit_code),
+		cancelled: false,
+		timed_out: false,
+		exit:      ExitInfo::from_shell_code(exit_code),
+	})
 }
 
 fn null_file() -> Result<OpenFile> {
@@ -0,0 +1,409 @@
+//! Near-duplicate code search.
+//!
+//! `findSimilarCode()` answers "has this logic been written elsewhere?". The
+//! snippet and every candidate file are split into tokens (identifiers,
+//! numbers, and single punctuation characters; whitespace is ignored), and
+//! runs of [`SHINGLE_TOKENS`] tokens are hashed into shingles. A file is only
+//! examined further when it contains enough of the snippet's shingles to
+//! possibly reach `minSimilarity`; windows of the snippet's line count are
+//! then compared by the `MinHash` estimate of their Jaccard similarity.
+
+use std::{collections::HashSet, path::Path};
+
+use ast_grep_core::Language;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::{ast, fs_cache, glob_util, language::SupportLang, task};
+
+/// Tokens per shingle.
+const SHINGLE_TOKENS: usize = 4;
+/// `MinHash` signature length; the similarity estimate has a resolution of
+/// `1 / SIGNATURE_LEN`.
+const SIGNATURE_LEN: usize = 64;
+const DEFAULT_MIN_SIMILARITY: f64 = 0.6;
+const DEFAULT_LIMIT: u32 = 20;
+/// Files larger than this are skipped.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Options for `findSimilarCode()`.
+#[napi(object)]
+pub struct FindSimilarCodeOptions<'env> {
+	/// Directory to search (default: current directory).
+	pub root:           Option<String>,
+	/// Only search files of this language (e.g. "typescript"); by default
+	/// every file with a supported language extension is searched.
+	pub lang:           Option<String>,
+	/// Glob filter for file paths relative to `root`.
+	pub glob:           Option<String>,
+	/// Lowest similarity to report, between 0 and 1 (default: 0.6).
+	#[napi(js_name = "minSimilarity")]
+	pub min_similarity: Option<f64>,
+	/// Maximum number of matches to return (default: 20).
+	pub limit:          Option<u32>,
+	/// Abort signal for cancelling the operation.
+	pub signal:         Option<Unknown<'env>>,
+	/// Timeout in milliseconds for the operation.
+	#[napi(js_name = "timeoutMs")]
+	pub timeout_ms:     Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:          Option<ClassInstance<'env, task::CancellationScope>>,
+}
+
+/// A region similar to the snippet.
+#[derive(Clone, Debug)]
+#[napi(object)]
+pub struct SimilarCodeMatch {
+	/// File path relative to `root`, using forward slashes.
+	pub path:       String,
+	/// First line of the region (1-indexed).
+	#[napi(js_name = "startLine")]
+	pub start_line: u32,
+	/// Last line of the region (1-indexed, inclusive).
+	#[napi(js_name = "endLine")]
+	pub end_line:   u32,
+	/// Estimated Jaccard similarity of the region's shingles to the
+	/// snippet's, between 0 and 1.
+	pub similarity: f64,
+}
+
+/// Result of `findSimilarCode()`.
+#[napi(object)]
+pub struct SimilarCodeResult {
+	/// Matches, most similar first.
+	pub matches:        Vec<SimilarCodeMatch>,
+	/// Number of files searched.
+	#[napi(js_name = "filesSearched")]
+	pub files_searched: u32,
+}
+
+struct Token {
+	hash: u64,
+	line: u32,
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+	let mut tokens = Vec::new();
+	let mut line = 1u32;
+	let mut chars = text.char_indices().peekable();
+	while let Some((start, ch)) = chars.next() {
+		if ch == '\n' {
+			line += 1;
+			continue;
+		}
+		if ch.is_whitespace() {
+			continue;
+		}
+		let mut end = start + ch.len_utf8();
+		if ch.is_alphanumeric() || ch == '_' || ch == '$' {
+			while let Some(&(index, next)) = chars.peek() {
+				if !(next.is_alphanumeric() || next == '_' || next == '$') {
+					break;
+				}
+				end = index + next.len_utf8();
+				chars.next();
+			}
+		}
+		tokens.push(Token { hash: xxh3_64(&text.as_bytes()[start..end]), line });
+	}
+	tokens
+}
+
+/// A run of [`SHINGLE_TOKENS`] tokens and the lines it spans.
+struct Shingle {
+	hash:       u64,
+	first_line: u32,
+	last_line:  u32,
+}
+
+fn shingles(tokens: &[Token]) -> Vec<Shingle> {
+	tokens
+		.windows(SHINGLE_TOKENS)
+		.map(|window| Shingle {
+			hash:       window
+				.iter()
+				.fold(0u64, |acc, token| acc.rotate_left(17) ^ token.hash)
+				.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+			first_line: window[0].line,
+			last_line:  window[SHINGLE_TOKENS - 1].line,
+		})
+		.collect()
+}
+
+/// The `index`-th hash of a shingle, from a splitmix64 finalizer.
+const fn permute(hash: u64, index: usize) -> u64 {
+	let mut z = hash ^ (index as u64).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+	z ^ (z >> 31)
+}
+
+fn signature<'a>(hashes: impl Iterator<Item = &'a u64>) -> [u64; SIGNATURE_LEN] {
+	let mut signature = [u64::MAX; SIGNATURE_LEN];
+	for &hash in hashes {
+		for (index, slot) in signature.iter_mut().enumerate() {
+			*slot = (*slot).min(permute(hash, index));
+		}
+	}
+	signature
+}
+
+fn estimate(left: &[u64; SIGNATURE_LEN], right: &[u64; SIGNATURE_LEN]) -> f64 {
+	let equal = left.iter().zip(right).filter(|(a, b)| a == b).count();
+	equal as f64 / SIGNATURE_LEN as f64
+}
+
+/// The snippet, prepared once for every file.
+struct Needle {
+	shingles:  HashSet<u64>,
+	signature: [u64; SIGNATURE_LEN],
+	lines:     u32,
+}
+
+impl Needle {
+	fn new(snippet: &str) -> Result<Self> {
+		let tokens = tokenize(snippet);
+		let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+			return Err(Error::from_reason("Snippet has no code"));
+		};
+		let lines = last.line - first.line + 1;
+		let shingles: HashSet<u64> = shingles(&tokens).iter().map(|s| s.hash).collect();
+		if shingles.is_empty() {
+			return Err(Error::from_reason(format!(
+				"Snippet is too short: need at least {SHINGLE_TOKENS} tokens"
+			)));
+		}
+		let signature = signature(shingles.iter());
+		Ok(Self { shingles, signature, lines })
+	}
+}
+
+/// Regions of `text` similar to the needle, in line order, with overlapping
+/// windows merged.
+fn search_text(
+	text: &str,
+	needle: &Needle,
+	min_similarity: f64,
+	ct: &task::CancelToken,
+) -> Result<Vec<(u32, u32, f64)>> {
+	let shingles = shingles(&tokenize(text));
+	let shared: HashSet<u64> = shingles
+		.iter()
+		.map(|shingle| shingle.hash)
+		.filter(|hash| needle.shingles.contains(hash))
+		.collect();
+	// A window's Jaccard similarity cannot exceed the share of the snippet's
+	// shingles found anywhere in the file.
+	if (shared.len() as f64) < min_similarity * needle.shingles.len() as f64 {
+		return Ok(Vec::new());
+	}
+
+	let last_line = shingles.last().map_or(0, |shingle| shingle.last_line);
+	let stride = (needle.lines / 4).max(1);
+	let mut hits: Vec<(u32, u32, f64)> = Vec::new();
+	// Reformatting can spread the same code over a few more lines.
+	for height in [needle.lines, needle.lines + stride] {
+		let mut first = 0usize;
+		let mut start = 1u32;
+		while start <= last_line {
+			ct.heartbeat()?;
+			let end = start + height - 1;
+			while first < shingles.len() && shingles[first].first_line < start {
+				first += 1;
+			}
+			let window: HashSet<u64> = shingles[first..]
+				.iter()
+				.take_while(|shingle| shingle.first_line <= end)
+				.filter(|shingle| shingle.last_line <= end)
+				.map(|shingle| shingle.hash)
+				.collect();
+			if window.iter().any(|hash| shared.contains(hash)) {
+				let similarity = estimate(&needle.signature, &signature(window.iter()));
+				if similarity >= min_similarity {
+					hits.push((start, end.min(last_line), similarity));
+				}
+			}
+			start += stride;
+		}
+	}
+
+	hits.sort_by_key(|&(start, end, _)| (start, end));
+	let mut regions: Vec<(u32, u32, f64)> = Vec::new();
+	for (start, end, similarity) in hits {
+		match regions.last_mut() {
+			Some(region) if region.1 >= start => {
+				region.1 = region.1.max(end);
+				region.2 = region.2.max(similarity);
+			},
+			_ => regions.push((start, end, similarity)),
+		}
+	}
+	Ok(regions)
+}
+
+pub(crate) fn candidate_files(
+	root: &Path,
+	lang: Option<SupportLang>,
+	glob: Option<&str>,
+	ct: &task::CancelToken,
+) -> Result<Vec<String>> {
+	let glob_set = glob_util::try_compile_glob(glob, true, Some(true))?;
+	let policy = fs_cache::ScanPolicy::new(true, true);
+	let entries = fs_cache::get_or_scan(root, &policy, ct)?.entries;
+	Ok(entries
+		.into_iter()
+		.filter(|entry| entry.file_type == fs_cache::FileType::File)
+		.filter(|entry| !fs_cache::should_skip_path(Path::new(&entry.path), false))
+		.filter(|entry| {
+			glob_set
+				.as_ref()
+				.is_none_or(|glob_set| glob_set.is_match(&entry.path))
+		})
+		.filter(|entry| {
+			let detected = SupportLang::from_path(&entry.path);
+			match lang {
+				Some(lang) => detected == Some(lang),
+				None => detected.is_some(),
+			}
+		})
+		.map(|entry| entry.path)
+		.collect())
+}
+
+fn find_similar_sync(
+	snippet: &str,
+	root: &Path,
+	lang: Option<SupportLang>,
+	glob: Option<&str>,
+	min_similarity: f64,
+	limit: usize,
+	ct: &task::CancelToken,
+) -> Result<SimilarCodeResult> {
+	let needle = Needle::new(snippet)?;
+	let files = candidate_files(root, lang, glob, ct)?;
+	let per_file = files
+		.par_iter()
+		.map(|relative| {
+			ct.heartbeat()?;
+			let path = root.join(relative);
+			let text = match std::fs::metadata(&path) {
+				Ok(metadata) if metadata.len() <= MAX_FILE_BYTES => {
+					std::fs::read_to_string(&path).unwrap_or_default()
+				},
+				_ => String::new(),
+			};
+			let regions = search_text(&text, &needle, min_similarity, ct)?;
+			Ok(regions
+				.into_iter()
+				.map(|(start_line, end_line, similarity)| SimilarCodeMatch {
+					path: relative.clone(),
+					start_line,
+					end_line,
+					similarity,
+				})
+				.collect::<Vec<_>>())
+		})
+		.collect::<Result<Vec<_>>>()?;
+	let mut matches: Vec<SimilarCodeMatch> = per_file.into_iter().flatten().collect();
+	matches.sort_by(|a, b| {
+		b.similarity
+			.total_cmp(&a.similarity)
+			.then_with(|| a.path.cmp(&b.path))
+			.then(a.start_line.cmp(&b.start_line))
+	});
+	matches.truncate(limit);
+	Ok(SimilarCodeResult { matches, files_searched: crate::utils::clamp_u32(files.len() as u64) })
+}
+
+/// Find code similar to `snippet` under `root`.
+///
+/// Regions are compared by shingled-token `MinHash`, so whitespace and
+/// reformatting do not matter, but renamed identifiers lower the score.
+///
+/// # Errors
+/// Fails when the snippet has fewer than four tokens, `minSimilarity` is
+/// outside 0..=1, or `lang` is unknown.
+#[napi(js_name = "findSimilarCode")]
+pub fn find_similar_code(
+	snippet: String,
+	options: Option<FindSimilarCodeOptions<'_>>,
+) -> Result<task::Async<SimilarCodeResult>> {
+	let (root, lang, glob, min_similarity, limit, ct) = match options {
+		Some(options) => (
+			options.root,
+			options.lang,
+			options.glob,
+			options.min_similarity,
+			options.limit,
+			task::CancelToken::new(options.timeout_ms, options.signal)
+				.with_scope(options.scope.as_deref()),
+		),
+		None => (None, None, None, None, None, task::CancelToken::default()),
+	};
+	let min_similarity = min_similarity.unwrap_or(DEFAULT_MIN_SIMILARITY);
+	if !(0.0..=1.0).contains(&min_similarity) {
+		return Err(Error::from_reason(format!(
+			"minSimilarity must be between 0 and 1, got {min_similarity}"
+		)));
+	}
+	let lang = lang
+		.as_deref()
+		.map(str::trim)
+		.filter(|lang| !lang.is_empty())
+		.map(ast::resolve_supported_lang)
+		.transpose()?;
+	let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+	let root = fs_cache::resolve_search_path(root.as_deref().unwrap_or("."))?;
+	Ok(task::blocking("find_similar_code", ct, move |ct| {
+		find_similar_sync(&snippet, &root, lang, glob.as_deref(), min_similarity, limit, &ct)
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+
+	#[test]
+	fn finds_reformatted_copies() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-similar-test-{unique}"));
+		fs::create_dir_all(&root).expect("temp dir should be created");
+		let snippet = "function total(items) {\n  let sum = 0;\n  for (const item of items) {\n    \
+		               sum += item.price * item.qty;\n  }\n  return sum;\n}";
+		fs::write(
+			root.join("copy.js"),
+			"import x from 'y';\n\nfunction   total(items)\n{\n  let sum = 0;\n  for (const item of \
+			 items) {\n    sum += item.price * item.qty;\n  }\n  return sum;\n}\n",
+		)
+		.unwrap();
+		fs::write(
+			root.join("other.js"),
+			"export const answer = () => fetch('/api').then(r => r.json());\n",
+		)
+		.unwrap();
+		fs::write(root.join("notes.txt"), snippet).unwrap();
+
+		let result =
+			find_similar_sync(snippet, &root, None, None, 0.6, 10, &task::CancelToken::default())
+				.expect("search should succeed");
+		assert_eq!(result.files_searched, 2);
+		assert_eq!(result.matches.len(), 1);
+		let found = &result.matches[0];
+		assert_eq!(found.path, "copy.js");
+		assert!(found.start_line <= 3 && found.end_line >= 10, "{found:?}");
+		assert!(found.similarity >= 0.9, "{found:?}");
+		assert!(Needle::new("a + b").is_err());
+
+		let _ = fs::remove_dir_all(&root);
+	}
+}
@@ -0,0 +1,341 @@
+//! Sorting and de-duplication of large result lists off the JS thread.
+//!
+//! `sortColumns()` orders rows described by parallel key columns (strings or
+//! numbers) and returns the kept row indices, so the JS wrapper can reorder
+//! arbitrary objects without copying them through N-API; `sortLines()` does
+//! the same for newline-delimited buffers and returns the lines.
+//!
+//! # Collation
+//! By default strings compare by code point, like `Array.prototype.sort()`.
+//! With `locale`, they compare in three levels like `localeCompare` in the
+//! root locale: base letters first (case and Latin accents folded, and
+//! punctuation before digits before letters), then accents, then case with
+//! lowercase first. There is no per-locale tailoring.
+
+use std::cmp::Ordering;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+use crate::task;
+
+/// Base letters of U+00C0..=U+00FF; `×` and `÷` stand for themselves.
+const LATIN_1_BASES: &str = "aaaaaaaceeeeiiiidnooooo×ouuuuyþsaaaaaaaceeeeiiiidnooooo÷ouuuuyþy";
+/// Base letters of U+0100..=U+017F (Latin Extended-A).
+const LATIN_EXT_A_BASES: &str =
+	"aaaaaaccccccccddddeeeeeeeeeegggggggghhhhiiiiiiiiiiiijjkkkllllllllllnnnnnnnnnoooooooorrrrrrssssssssttttttuuuuuuuuuuuuwwyyyzzzzzzs";
+
+/// Lowercase base letter of `ch` with Latin accents removed.
+fn base_letter(ch: char) -> char {
+	let code = ch as u32;
+	let table = match code {
+		0xc0..=0xff => LATIN_1_BASES.chars().nth((code - 0xc0) as usize),
+		0x100..=0x17f => LATIN_EXT_A_BASES.chars().nth((code - 0x100) as usize),
+		_ => None,
+	};
+	table.unwrap_or_else(|| ch.to_lowercase().next().unwrap_or(ch))
+}
+
+/// Primary collation weight: punctuation and symbols, then digits, then
+/// letters, then other scripts by code point.
+fn primary_weight(ch: char) -> u32 {
+	let base = base_letter(ch);
+	match base {
+		'0'..='9' => 0x80 + (base as u32 - '0' as u32),
+		'a'..='z' => 0x100 + (base as u32 - 'a' as u32),
+		_ if base.is_ascii() => base as u32,
+		_ => 0x200 + base as u32,
+	}
+}
+
+/// Precomputed `locale` collation levels of a string.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CollationKey {
+	primary:   Vec<u32>,
+	secondary: Vec<char>,
+	tertiary:  Vec<bool>,
+}
+
+impl CollationKey {
+	pub fn new(text: &str) -> Self {
+		Self {
+			primary:   text.chars().map(primary_weight).collect(),
+			secondary: text
+				.chars()
+				.map(|ch| ch.to_lowercase().next().unwrap_or(ch))
+				.collect(),
+			tertiary:  text.chars().map(char::is_uppercase).collect(),
+		}
+	}
+}
+
+/// Compare two strings with `locale` collation, falling back to code points
+/// so the order is total.
+pub fn locale_compare(left: &str, right: &str) -> Ordering {
+	CollationKey::new(left)
+		.cmp(&CollationKey::new(right))
+		.then_with(|| left.cmp(right))
+}
+
+/// Split off the leading run of ASCII digits or of other characters.
+fn leading_run(text: &str) -> (&str, &str) {
+	let digits = text.starts_with(|ch: char| ch.is_ascii_digit());
+	let end = text
+		.find(|ch: char| ch.is_ascii_digit() != digits)
+		.unwrap_or(text.len());
+	text.split_at(end)
+}
+
+/// Digits of `run` without leading zeros, if it is a run of digits.
+fn numeric(run: &str) -> Option<&str> {
+	run.starts_with(|ch: char| ch.is_ascii_digit())
+		.then(|| run.trim_start_matches('0'))
+}
+
+/// Compare strings in natural order, so `file2` sorts before `file10`.
+///
+/// Runs of ASCII digits compare by numeric value and the text between them
+/// by base letters as in `locale` collation. Ties fall back to
+/// [`locale_compare`].
+pub fn natural_compare(left: &str, right: &str) -> Ordering {
+	let (mut rest_left, mut rest_right) = (left, right);
+	while !rest_left.is_empty() && !rest_right.is_empty() {
+		let (run_left, tail_left) = leading_run(rest_left);
+		let (run_right, tail_right) = leading_run(rest_right);
+		let order = match (numeric(run_left), numeric(run_right)) {
+			(Some(a), Some(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+			_ => run_left
+				.chars()
+				.map(primary_weight)
+				.cmp(run_right.chars().map(primary_weight)),
+		};
+		if order.is_ne() {
+			return order;
+		}
+		(rest_left, rest_right) = (tail_left, tail_right);
+	}
+	(!rest_left.is_empty())
+		.cmp(&!rest_right.is_empty())
+		.then_with(|| locale_compare(left, right))
+}
+
+/// Options for `sortColumns()` and `sortLines()`.
+#[napi(object)]
+#[derive(Default)]
+pub struct SortOptions {
+	/// Drop rows whose keys all equal an earlier row's (default: false).
+	pub unique:     Option<bool>,
+	/// Compare strings with root-locale collation instead of code points
+	/// (default: false).
+	pub locale:     Option<bool>,
+	/// Sort in descending order (default: false).
+	pub descending: Option<bool>,
+}
+
+enum Column {
+	Text { values: Vec<String>, keys: Option<Vec<CollationKey>> },
+	Number(Vec<f64>),
+}
+
+impl Column {
+	const fn len(&self) -> usize {
+		match self {
+			Self::Text { values, .. } => values.len(),
+			Self::Number(values) => values.len(),
+		}
+	}
+
+	fn compare(&self, left: usize, right: usize) -> Ordering {
+		match self {
+			Self::Text { values, keys: Some(keys) } => keys[left]
+				.cmp(&keys[right])
+				.then_with(|| values[left].cmp(&values[right])),
+			Self::Text { values, keys: None } => values[left].cmp(&values[right]),
+			// NaN (missing values) sorts last.
+			Self::Number(values) => match (values[left].is_nan(), values[right].is_nan()) {
+				(false, false) => values[left].total_cmp(&values[right]),
+				(left_nan, right_nan) => left_nan.cmp(&right_nan),
+			},
+		}
+	}
+
+	fn same(&self, left: usize, right: usize) -> bool {
+		match self {
+			Self::Text { values, .. } => values[left] == values[right],
+			Self::Number(_) => self.compare(left, right).is_eq(),
+		}
+	}
+}
+
+/// Stable sort of row indices by `columns`, optionally dropping duplicates.
+fn sort_rows(columns: &[Column], unique: bool, descending: bool) -> Vec<u32> {
+	let rows = columns.first().map_or(0, Column::len);
+	let mut order: Vec<u32> = (0..crate::utils::clamp_u32(rows as u64)).collect();
+	let compare = |left: &u32, right: &u32| {
+		let (left, right) = (*left as usize, *right as usize);
+		let ordering = columns
+			.iter()
+			.map(|column| column.compare(left, right))
+			.find(|ordering| ordering.is_ne())
+			.unwrap_or(Ordering::Equal);
+		if descending {
+			ordering.reverse()
+		} else {
+			ordering
+		}
+	};
+	order.par_sort_by(compare);
+	if unique {
+		// Equal keys are adjacent after sorting; the stable sort keeps the
+		// first occurrence of each.
+		order.dedup_by(|row, kept| {
+			columns
+				.iter()
+				.all(|column| column.same(*row as usize, *kept as usize))
+		});
+	}
+	order
+}
+
+fn build_columns(columns: Vec<Either<Vec<String>, Vec<f64>>>, locale: bool) -> Result<Vec<Column>> {
+	let columns: Vec<Column> = columns
+		.into_iter()
+		.map(|column| match column {
+			Either::A(values) => {
+				let keys = locale.then(|| {
+					values
+						.par_iter()
+						.map(|value| CollationKey::new(value))
+						.collect()
+				});
+				Column::Text { values, keys }
+			},
+			Either::B(values) => Column::Number(values),
+		})
+		.collect();
+	if let Some(first) = columns.first()
+		&& columns.iter().any(|column| column.len() != first.len())
+	{
+		return Err(Error::from_reason("All sort columns must have the same length"));
+	}
+	Ok(columns)
+}
+
+/// Sort rows given as parallel key columns and return the kept row indices
+/// in order.
+///
+/// # Errors
+/// Fails when the columns differ in length.
+#[napi(js_name = "sortColumns")]
+pub fn sort_columns(
+	columns: Vec<Either<Vec<String>, Float64Array>>,
+	options: Option<SortOptions>,
+) -> task::Async<Uint32Array> {
+	let options = options.unwrap_or_default();
+	let columns: Vec<Either<Vec<String>, Vec<f64>>> = columns
+		.into_iter()
+		.map(|column| match column {
+			Either::A(values) => Either::A(values),
+			Either::B(values) => Either::B(values.to_vec()),
+		})
+		.collect();
+	task::blocking("sort.columns", (), move |_| {
+		let columns = build_columns(columns, options.locale.unwrap_or(false))?;
+		let order =
+			sort_rows(&columns, options.unique.unwrap_or(false), options.descending.unwrap_or(false));
+		Ok(Uint32Array::new(order))
+	})
+}
+
+/// Sort the lines of a newline-delimited UTF-8 buffer (`\r\n` accepted; a
+/// trailing newline does not add an empty line).
+#[napi(js_name = "sortLines")]
+pub fn sort_lines(buffer: Uint8Array, options: Option<SortOptions>) -> task::Async<Vec<String>> {
+	let options = options.unwrap_or_default();
+	let text = String::from_utf8_lossy(&buffer).into_owned();
+	task::blocking("sort.lines", (), move |_| {
+		let lines: Vec<String> = text.lines().map(str::to_string).collect();
+		let columns = build_columns(vec![Either::A(lines)], options.locale.unwrap_or(false))?;
+		let order =
+			sort_rows(&columns, options.unique.unwrap_or(false), options.descending.unwrap_or(false));
+		let Some(Column::Text { mut values, .. }) = columns.into_iter().next() else {
+			return Ok(Vec::new());
+		};
+		Ok(order
+			.into_iter()
+			.map(|row| std::mem::take(&mut values[row as usize]))
+			.collect())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn text(values: &[&str], locale: bool) -> Column {
+		let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+		build_columns(vec![Either::A(values)], locale)
+			.unwrap()
+			.remove(0)
+	}
+
+	#[test]
+	fn folds_latin_letters() {
+		assert_eq!(LATIN_1_BASES.chars().count(), 64);
+		assert_eq!(LATIN_EXT_A_BASES.chars().count(), 128);
+		assert_eq!(base_letter('É'), 'e');
+		assert_eq!(base_letter('ł'), 'l');
+		assert_eq!(base_letter('Ž'), 'z');
+	}
+
+	#[test]
+	fn sorts_and_dedupes_rows() {
+		let words = ["b", "B", "a", "é", "e", "_x", "10", "Z", "b"];
+		let column = text(&words, false);
+		let order = sort_rows(std::slice::from_ref(&column), true, false);
+		let sorted: Vec<_> = order.iter().map(|&row| words[row as usize]).collect();
+		assert_eq!(sorted, ["10", "B", "Z", "_x", "a", "b", "e", "é"]);
+
+		let column = text(&words, true);
+		let order = sort_rows(std::slice::from_ref(&column), false, false);
+		let sorted: Vec<_> = order.iter().map(|&row| words[row as usize]).collect();
+		assert_eq!(sorted, ["_x", "10", "a", "b", "b", "B", "e", "é", "Z"]);
+
+		let paths = text(&["b.rs", "a.rs", "b.rs", "a.rs"], false);
+		let lines = Column::Number(vec![2.0, f64::NAN, 1.0, 3.0]);
+		let order = sort_rows(&[paths, lines], true, false);
+		assert_eq!(order, [3, 1, 2, 0]);
+		let order = sort_rows(&[text(&["x", "y"], false)], false, true);
+		assert_eq!(order, [1, 0]);
+	}
+
+	#[test]
+	fn compares_naturally() {
+		let mut names = [
+			"file10.ts",
+			"File2.ts",
+			"file2.ts",
+			"file1.ts",
+			"file01.ts",
+			"éclair",
+			"file2b.ts",
+			"src/a",
+			"eclair",
+			"z",
+		];
+		names.sort_by(|a, b| natural_compare(a, b));
+		assert_eq!(names, [
+			"eclair",
+			"éclair",
+			"file01.ts",
+			"file1.ts",
+			"file2.ts",
+			"File2.ts",
+			"file2b.ts",
+			"file10.ts",
+			"src/a",
+			"z",
+		]);
+	}
+}
@@ -0,0 +1,184 @@
+//! Batched delivery of streamed results to JS callbacks.
+//!
+//! Streaming callbacks (`grep` and `glob` `onMatch`) used to fire once per
+//! item, which floods the JS event queue on large result sets. A [`Batcher`]
+//! buffers items and hands them to the threadsafe function in batches of up
+//! to `maxBatch`. A timer thread flushes a partial batch once its oldest item
+//! has waited `maxDelayMs`, so a slow search still delivers its first matches
+//! promptly. Every delivered batch carries a sequence number, and the stream
+//! always ends with a `done` batch; a batch the JS queue refused is counted in
+//! `dropped` on the following ones, so consumers can tell a complete stream
+//! from a lossy one.
+
+use std::{
+	sync::Arc,
+	thread::JoinHandle,
+	time::{Duration, Instant},
+};
+
+use napi::{
+	bindgen_prelude::*,
+	threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode},
+};
+use napi_derive::napi;
+use parking_lot::{Condvar, Mutex};
+
+const DEFAULT_MAX_BATCH: u32 = 64;
+const DEFAULT_MAX_DELAY_MS: u32 = 16;
+
+/// How streamed results are batched.
+#[napi(object)]
+#[derive(Clone, Copy, Default)]
+pub struct StreamOptions {
+	/// Most items per batch (default: 64).
+	#[napi(js_name = "maxBatch")]
+	pub max_batch:    Option<u32>,
+	/// Longest an item waits before its batch is sent, in milliseconds
+	/// (default: 16).
+	#[napi(js_name = "maxDelayMs")]
+	pub max_delay_ms: Option<u32>,
+}
+
+/// A batch of streamed items as passed to JS.
+pub trait StreamBatch: JsValuesTupleIntoVec + 'static {
+	type Item: Send + 'static;
+
+	/// `seq` numbers batches from 0; `dropped` counts items lost so far.
+	fn new(seq: u32, items: Vec<Self::Item>, done: bool, dropped: u32) -> Self;
+}
+
+struct BatchState<T> {
+	items:   Vec<T>,
+	/// When the oldest buffered item arrived.
+	started: Option<Instant>,
+	seq:     u32,
+	dropped: u32,
+	done:    bool,
+}
+
+struct Shared<B: StreamBatch> {
+	callback:  ThreadsafeFunction<B>,
+	max_batch: usize,
+	max_delay: Duration,
+	state:     Mutex<BatchState<B::Item>>,
+	/// Wakes the timer when the first item of a batch arrives or the stream
+	/// ends.
+	wake:      Condvar,
+}
+
+/// Buffers streamed items and delivers them to a JS callback in batches.
+pub struct Batcher<B: StreamBatch> {
+	shared: Arc<Shared<B>>,
+	timer:  Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<B: StreamBatch> Batcher<B> {
+	pub fn new(callback: ThreadsafeFunction<B>, options: Option<StreamOptions>) -> Self {
+		let options = options.unwrap_or_default();
+		let shared = Arc::new(Shared {
+			callback,
+			max_batch: options.max_batch.unwrap_or(DEFAULT_MAX_BATCH).max(1) as usize,
+			max_delay: Duration::from_millis(u64::from(
+				options.max_delay_ms.unwrap_or(DEFAULT_MAX_DELAY_MS),
+			)),
+			state: Mutex::new(BatchState {
+				items:   Vec::new(),
+				started: None,
+				seq:     0,
+				dropped: 0,
+				done:    false,
+			}),
+			wake: Condvar::new(),
+		});
+		let timer = {
+			let shared = Arc::clone(&shared);
+			std::thread::Builder::new()
+				.name("pi-stream-flush".to_string())
+				.spawn(move || shared.flush_overdue())
+				.ok()
+		};
+		Self { shared, timer: Mutex::new(timer) }
+	}
+
+	/// Queue `item`, sending the batch when it is full or overdue.
+	pub fn push(&self, item: B::Item) {
+		let shared = &*self.shared;
+		let mut state = shared.state.lock();
+		if state.done {
+			return;
+		}
+		if state.started.is_none() {
+			state.started = Some(Instant::now());
+			shared.wake.notify_one();
+		}
+		state.items.push(item);
+		let overdue = state
+			.started
+			.is_some_and(|started| started.elapsed() >= shared.max_delay);
+		if state.items.len() >= shared.max_batch || overdue {
+			shared.send(&mut state, false);
+		}
+	}
+
+	/// Send what is buffered as the final `done` batch; later pushes are
+	/// ignored.
+	pub fn finish(&self) {
+		{
+			let shared = &*self.shared;
+			let mut state = shared.state.lock();
+			if !state.done {
+				shared.send(&mut state, true);
+				state.done = true;
+			}
+			shared.wake.notify_one();
+		}
+		let timer = self.timer.lock().take();
+		if let Some(timer) = timer {
+			let _ = timer.join();
+		}
+	}
+}
+
+impl<B: StreamBatch> Drop for Batcher<B> {
+	fn drop(&mut self) {
+		self.finish();
+	}
+}
+
+impl<B: StreamBatch> Shared<B> {
+	/// Timer loop: send each partial batch once its oldest item is
+	/// `max_delay` old, until the stream is done.
+	fn flush_overdue(&self) {
+		let mut state = self.state.lock();
+		while !state.done {
+			match state.started {
+				None => self.wake.wait(&mut state),
+				Some(started) => {
+					let deadline = started + self.max_delay;
+					if Instant::now() >= deadline {
+						self.send(&mut state, false);
+					} else {
+						self.wake.wait_until(&mut state, deadline);
+					}
+				},
+			}
+		}
+	}
+
+	fn send(&self, state: &mut BatchState<B::Item>, done: bool) {
+		let items = std::mem::take(&mut state.items);
+		let count = crate::utils::clamp_u32(items.len() as u64);
+		state.started = None;
+		let batch = B::new(state.seq, items, done, state.dropped);
+		state.seq = state.seq.saturating_add(1);
+		// The final batch must not be lost to a full queue.
+		let mode = if done {
+			ThreadsafeFunctionCallMode::Blocking
+		} else {
+			ThreadsafeFunctionCallMode::NonBlocking
+		};
+		if self.callback.call(Ok(batch), mode) != Status::Ok {
+			state.dropped = state.dropped.saturating_add(count);
+		}
+	}
+}
@@ -0,0 +1,717 @@
+//! Symbol index for "goto symbol" without a language server.
+//!
+//! `symbolIndex()` parses files with the ast-grep tree-sitter languages and
+//! lists their top-level declarations (functions, classes, types, constants,
+//! and modules) plus the members of classes, traits, and impl blocks. With a
+//! `query`, symbols are ranked by the same fzf-style scoring as `fuzzyFind`.
+//!
+//! `fileSyntaxStats()` reuses the same declaration table to count a file's
+//! functions, classes, imports, TODO comments, and block nesting in one
+//! parse, for ranking files during context packing.
+
+use std::path::Path;
+
+use ast_grep_core::{
+	Language, Node,
+	tree_sitter::{LanguageExt, StrDoc},
+};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+use crate::{ast, comments, fd, fs_cache, language::SupportLang, similar, task};
+
+const DEFAULT_LIMIT: u32 = 1000;
+/// Files larger than this are skipped.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Options for `symbolIndex()`.
+#[napi(object)]
+pub struct SymbolIndexOptions<'env> {
+	/// Only index files of this language (e.g. "rust"); by default every file
+	/// with a supported language extension is indexed.
+	pub lang:       Option<String>,
+	/// Glob filter for file paths relative to the search root.
+	pub glob:       Option<String>,
+	/// Only return symbols of these kinds (e.g. `["function", "class"]`).
+	pub kinds:      Option<Vec<String>>,
+	/// Fuzzy query matched against symbol names; results are ranked by score.
+	pub query:      Option<String>,
+	/// Maximum number of symbols to return (default: 1000).
+	pub limit:      Option<u32>,
+	/// Abort signal for cancelling the operation.
+	pub signal:     Option<Unknown<'env>>,
+	/// Timeout in milliseconds for the operation.
+	#[napi(js_name = "timeoutMs")]
+	pub timeout_ms: Option<u32>,
+	/// Shared cancellation scope; cancelling it aborts the operation.
+	pub scope:      Option<ClassInstance<'env, task::CancellationScope>>,
+}
+
+/// A declared symbol.
+#[napi(object)]
+pub struct SymbolInfo {
+	/// Symbol name.
+	pub name:         String,
+	/// "function", "method", "class", "struct", "enum", "interface",
+	/// "trait", "type", "const", "variable", "module", or "macro".
+	pub kind:         String,
+	/// File path relative to the search root, using forward slashes.
+	pub path:         String,
+	/// Enclosing class, trait, impl target, or module, for members.
+	pub container:    Option<String>,
+	/// First line of the declaration (1-indexed).
+	#[napi(js_name = "startLine")]
+	pub start_line:   u32,
+	/// First column of the declaration (1-indexed).
+	#[napi(js_name = "startColumn")]
+	pub start_column: u32,
+	/// Last line of the declaration (1-indexed, inclusive).
+	#[napi(js_name = "endLine")]
+	pub end_line:     u32,
+	/// Column after the declaration's last character (1-indexed).
+	#[napi(js_name = "endColumn")]
+	pub end_column:   u32,
+	/// Fuzzy match score for `query` (higher is better).
+	pub score:        Option<u32>,
+}
+
+/// Result of `symbolIndex()`.
+#[napi(object)]
+pub struct SymbolIndexResult {
+	/// Symbols in path and line order, or by score with `query`.
+	pub symbols:       Vec<SymbolInfo>,
+	/// Symbols found before `limit` was applied.
+	#[napi(js_name = "totalSymbols")]
+	pub total_symbols: u32,
+	/// Number of files parsed.
+	#[napi(js_name = "filesIndexed")]
+	pub files_indexed: u32,
+}
+
+type SgNode<'r> = Node<'r, StrDoc<SupportLang>>;
+
+/// Symbol kind for a declaration node kind, across the supported grammars.
+fn declaration_kind(kind: &str) -> Option<&'static str> {
+	Some(match kind {
+		"function_declaration"
+		| "function_definition"
+		| "function_item"
+		| "function_signature_item"
+		| "generator_function_declaration" => "function",
+		"method_declaration" | "method_definition" | "method_signature" | "method"
+		| "singleton_method" => "method",
+		"class_declaration"
+		| "class_definition"
+		| "abstract_class_declaration"
+		| "class_specifier"
+		| "class"
+		| "object_declaration" => "class",
+		"struct_item" | "struct_specifier" | "struct_declaration" => "struct",
+		"enum_item" | "enum_declaration" | "enum_specifier" => "enum",
+		"interface_declaration" | "protocol_declaration" => "interface",
+		"trait_item" | "trait_declaration" => "trait",
+		"type_alias_declaration" | "type_item" | "type_spec" | "type_alias" => "type",
+		"const_item" | "static_item" | "const_spec" => "const",
+		"mod_item" | "module" | "namespace_definition" | "internal_module" => "module",
+		"macro_definition" => "macro",
+		_ => return None,
+	})
+}
+
+/// Name of a declaration: its `name` field, the innermost C-style
+/// `declarator`, or its first identifier-like child.
+fn declaration_name(node: &SgNode<'_>) -> Option<String> {
+	if let Some(name) = node.field("name") {
+		return Some(name.text().into_owned());
+	}
+	if let Some(mut declarator) = node.field("declarator") {
+		while let Some(inner) = declarator.field("declarator") {
+			declarator = inner;
+		}
+		return Some(declarator.text().into_owned());
+	}
+	node
+		.children()
+		.find(|child| {
+			let kind = child.kind();
+			kind.ends_with("identifier") || kind == "constant"
+		})
+		.map(|child| child.text().into_owned())
+}
+
+/// Receiver type of a Go method, e.g. `Server` for `func (s *Server) Run()`.
+fn go_receiver(node: &SgNode<'_>) -> Option<String> {
+	let parameter = node.field("receiver")?.children().find(SgNode::is_named)?;
+	let receiver = parameter.field("type")?.text().into_owned();
+	Some(receiver.trim_start_matches('*').to_string())
+}
+
+struct Collector<'a> {
+	path:    &'a str,
+	symbols: Vec<SymbolInfo>,
+}
+
+impl Collector<'_> {
+	fn push(&mut self, node: &SgNode<'_>, name: String, kind: &str, container: Option<&str>) {
+		let start = node.start_pos();
+		let end = node.end_pos();
+		self.symbols.push(SymbolInfo {
+			name,
+			kind: kind.to_string(),
+			path: self.path.to_string(),
+			container: container.map(str::to_string),
+			start_line: crate::utils::clamp_u32(start.line() as u64 + 1),
+			start_column: crate::utils::clamp_u32(start.column(node) as u64 + 1),
+			end_line: crate::utils::clamp_u32(end.line() as u64 + 1),
+			end_column: crate::utils::clamp_u32(end.column(node) as u64 + 1),
+			score: None,
+		});
+	}
+
+	fn collect(&mut self, parent: &SgNode<'_>, container: Option<&str>, in_type: bool) {
+		for child in parent.children().filter(SgNode::is_named) {
+			self.visit(&child, container, in_type);
+		}
+	}
+
+	fn visit(&mut self, node: &SgNode<'_>, container: Option<&str>, in_type: bool) {
+		let kind = node.kind();
+		match kind.as_ref() {
+			// Wrappers around a declaration.
+			"export_statement"
+			| "decorated_definition"
+			| "ambient_declaration"
+			| "template_declaration" => {
+				self.collect(node, container, in_type);
+			},
+			// Go groups and JS/Python variable statements.
+			"type_declaration" | "const_declaration" | "expression_statement" => {
+				self.collect(node, container, in_type);
+			},
+			"lexical_declaration" | "variable_declaration" => {
+				let constant = node.child(0).is_some_and(|token| token.kind() == "const");
+				for declarator in node
+					.children()
+					.filter(|child| child.kind() == "variable_declarator")
+				{
+					let Some(name) = declarator
+						.field("name")
+						.filter(|name| name.kind() == "identifier")
+					else {
+						continue;
+					};
+					let value_kind = declarator
+						.field("value")
+						.map(|value| value.kind().into_owned());
+					let symbol_kind = match value_kind.as_deref() {
+						Some("arrow_function" | "function_expression" | "function") => "function",
+						_ if constant => "const",
+						_ => "variable",
+					};
+					self.push(node, name.text().into_owned(), symbol_kind, container);
+				}
+			},
+			// Module-level Python assignments.
+			"assignment" if !in_type => {
+				if let Some(left) = node
+					.field("left")
+					.filter(|left| left.kind() == "identifier")
+				{
+					self.push(node, left.text().into_owned(), "variable", container);
+				}
+			},
+			"impl_item" => {
+				let target = node.field("type").map(|target| target.text().into_owned());
+				if let Some(body) = node.field("body") {
+					self.collect(&body, target.as_deref().or(container), true);
+				}
+			},
+			other => {
+				let Some(mut symbol_kind) = declaration_kind(other) else {
+					return;
+				};
+				let Some(name) = declaration_name(node) else {
+					return;
+				};
+				if in_type && symbol_kind == "function" {
+					symbol_kind = "method";
+				}
+				let receiver = (other == "method_declaration")
+					.then(|| go_receiver(node))
+					.flatten();
+				self.push(node, name.clone(), symbol_kind, receiver.as_deref().or(container));
+				let is_type =
+					matches!(symbol_kind, "class" | "struct" | "enum" | "interface" | "trait");
+				if is_type || symbol_kind == "module" {
+					let body = node.field("body").unwrap_or_else(|| node.clone());
+					self.collect(&body, Some(&name), is_type);
+				}
+			},
+		}
+	}
+}
+
+pub(crate) fn file_symbols(source: String, lang: SupportLang, path: &str) -> Vec<SymbolInfo> {
+	let ast = lang.ast_grep(source);
+	let mut collector = Collector { path, symbols: Vec::new() };
+	collector.collect(&ast.root(), None, false);
+	collector.symbols
+}
+
+struct SymbolIndexConfig {
+	lang:  Option<SupportLang>,
+	glob:  Option<String>,
+	kinds: Option<Vec<String>>,
+	query: Option<String>,
+	limit: usize,
+}
+
+fn symbol_index_sync(
+	root: &Path,
+	config: &SymbolIndexConfig,
+	ct: &task::CancelToken,
+) -> Result<SymbolIndexResult> {
+	let (base, files) = if root.is_file() {
+		let name = root
+			.file_name()
+			.map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+		(root.parent().unwrap_or(root).to_path_buf(), vec![name])
+	} else {
+		let files = similar::candidate_files(root, config.lang, config.glob.as_deref(), ct)?;
+		(root.to_path_buf(), files)
+	};
+	let per_file = files
+		.par_iter()
+		.map(|relative| {
+			ct.heartbeat()?;
+			let path = base.join(relative);
+			let Some(lang) = config.lang.or_else(|| SupportLang::from_path(&path)) else {
+				return Ok(Vec::new());
+			};
+			let source = match std::fs::metadata(&path) {
+				Ok(metadata) if metadata.len() <= MAX_FILE_BYTES => {
+					std::fs::read_to_string(&path).unwrap_or_default()
+				},
+				_ => return Ok(Vec::new()),
+			};
+			Ok(file_symbols(source, lang, relative))
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	let mut symbols: Vec<SymbolInfo> = per_file
+		.into_iter()
+		.flatten()
+		.filter(|symbol| {
+			config
+				.kinds
+				.as_ref()
+				.is_none_or(|kinds| kinds.contains(&symbol.kind))
+		})
+		.collect();
+	let query: Vec<char> = config
+		.query
+		.as_deref()
+		.unwrap_or_default()
+		.chars()
+		.filter(|ch| !ch.is_whitespace())
+		.flat_map(char::to_lowercase)
+		.collect();
+	if query.is_empty() {
+		symbols.sort_by(|a, b| a.path.cmp(&b.path).then(a.start_line.cmp(&b.start_line)));
+	} else {
+		symbols.retain_mut(|symbol| {
+			let Some(alignment) = fd::fuzzy_align(&query, &symbol.name) else {
+				return false;
+			};
+			// Exact names first, then shorter names among equal alignments.
+			let exact = if symbol.name.to_lowercase().chars().eq(query.iter().copied()) {
+				1000
+			} else {
+				0
+			};
+			let score = alignment.score.max(0) as u32 * 4 + exact;
+			symbol.score = Some(score.saturating_sub(symbol.name.chars().count() as u32));
+			true
+		});
+		symbols.sort_by(|a, b| {
+			b.score
+				.cmp(&a.score)
+				.then_with(|| a.path.cmp(&b.path))
+				.then(a.start_line.cmp(&b.start_line))
+		});
+	}
+	let total_symbols = crate::utils::clamp_u32(symbols.len() as u64);
+	symbols.truncate(config.limit);
+	Ok(SymbolIndexResult {
+		symbols,
+		total_symbols,
+		files_indexed: crate::utils::clamp_u32(files.len() as u64),
+	})
+}
+
+/// Index the declared symbols of a file or of every supported file under a
+/// directory.
+///
+/// # Errors
+/// Fails when `path` does not exist or `lang` is unknown.
+#[napi(js_name = "symbolIndex")]
+pub fn symbol_index(
+	path: String,
+	options: Option<SymbolIndexOptions<'_>>,
+) -> Result<task::Async<SymbolIndexResult>> {
+	let (lang, glob, kinds, query, limit, ct) = match options {
+		Some(options) => (
+			options.lang,
+			options.glob,
+			options.kinds,
+			options.query,
+			options.limit,
+			task::CancelToken::new(options.timeout_ms, options.signal)
+				.with_scope(options.scope.as_deref()),
+		),
+		None => (None, None, None, None, None, task::CancelToken::default()),
+	};
+	let lang = lang
+		.as_deref()
+		.map(str::trim)
+		.filter(|lang| !lang.is_empty())
+		.map(ast::resolve_supported_lang)
+		.transpose()?;
+	let config = SymbolIndexConfig {
+		lang,
+		glob,
+		kinds,
+		query,
+		limit: limit.unwrap_or(DEFAULT_LIMIT) as usize,
+	};
+	let root = fs_cache::resolve_path(&path)?;
+	if !root.exists() {
+		return Err(Error::from_reason(format!("Path not found: {}", root.display())));
+	}
+	Ok(task::blocking("symbol_index", ct, move |ct| symbol_index_sync(&root, &config, &ct)))
+}
+
+/// Syntax statistics of one file.
+#[napi(object)]
+pub struct FileSyntaxStats {
+	/// Language the file was parsed as, or its extension when only its comment
+	/// syntax is known.
+	pub lang:      String,
+	/// Number of lines.
+	pub lines:     u32,
+	/// Functions and methods, including functions bound to variables.
+	pub functions: u32,
+	/// Classes, structs, enums, interfaces, and traits.
+	pub classes:   u32,
+	/// Imported modules: one per `import`/`use`/`#include`, or per spec in a
+	/// Go import group.
+	pub imports:   u32,
+	/// Comments containing TODO, FIXME, XXX, or HACK.
+	pub todos:     u32,
+	/// Deepest nesting of blocks and bodies (0 for a file without any).
+	#[napi(js_name = "maxDepth")]
+	pub max_depth: u32,
+}
+
+const TODO_MARKERS: &[&str] = &["TODO", "FIXME", "XXX", "HACK"];
+
+/// Whether a comment mentions a TODO marker as a whole word.
+fn has_todo_marker(comment: &str) -> bool {
+	TODO_MARKERS.iter().any(|marker| {
+		comment.match_indices(marker).any(|(start, _)| {
+			let end = start + marker.len();
+			let word = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_';
+			!comment.as_bytes()[..start]
+				.last()
+				.copied()
+				.is_some_and(word)
+				&& !comment.as_bytes().get(end).copied().is_some_and(word)
+		})
+	})
+}
+
+/// Node kinds importing a module, across the supported grammars.
+fn is_import(kind: &str, lang: SupportLang) -> bool {
+	match kind {
+		"import_statement"
+		| "import_from_statement"
+		| "use_declaration"
+		| "preproc_include"
+		| "import_header"
+		| "using_directive"
+		| "namespace_use_declaration"
+		| "import_spec" => true,
+		// Go groups specs under one declaration; the specs are counted instead.
+		"import_declaration" => lang != SupportLang::Go,
+		_ => false,
+	}
+}
+
+/// Node kinds that open a nested block or body.
+fn is_block(kind: &str) -> bool {
+	kind.ends_with("block")
+		|| kind.ends_with("body")
+		|| matches!(
+			kind,
+			"compound_statement" | "declaration_list" | "field_declaration_list" | "suite"
+		)
+}
+
+fn syntax_stats(source: &str, lang: SupportLang) -> FileSyntaxStats {
+	let ast = lang.ast_grep(source);
+	let mut stats = FileSyntaxStats {
+		lang:      lang.canonical_name().to_string(),
+		lines:     crate::utils::clamp_u32(source.lines().count() as u64),
+		functions: 0,
+		classes:   0,
+		imports:   0,
+		todos:     0,
+		max_depth: 0,
+	};
+	// Iterative walk: generated files can nest deeper than the thread stack.
+	let mut stack = vec![(ast.root(), 0u32)];
+	while let Some((node, depth)) = stack.pop() {
+		let kind = node.kind();
+		let kind = kind.as_ref();
+		let mut child_depth = depth;
+		if kind.contains("comment") {
+			if has_todo_marker(&node.text()) {
+				stats.todos += 1;
+			}
+			continue;
+		}
+		if !node.is_named() {
+			continue;
+		}
+		match declaration_kind(kind) {
+			Some("function" | "method") => stats.functions += 1,
+			Some("class" | "struct" | "enum" | "interface" | "trait") => stats.classes += 1,
+			_ => {},
+		}
+		if kind == "variable_declarator"
+			&& node.field("value").is_some_and(|value| {
+				matches!(value.kind().as_ref(), "arrow_function" | "function_expression" | "function")
+			}) {
+			stats.functions += 1;
+		}
+		if is_import(kind, lang) {
+			stats.imports += 1;
+		}
+		if is_block(kind) {
+			child_depth += 1;
+			stats.max_depth = stats.max_depth.max(child_depth);
+		}
+		stack.extend(node.children().map(|child| (child, child_depth)));
+	}
+	stats
+}
+
+/// Count the functions, classes, imports, TODO comments, and maximum block
+/// nesting of a source file in a single parse.
+///
+/// Files without a grammar whose extension has a comment syntax (built in or
+/// from `registerCommentSyntax()`) get only line and TODO counts.
+///
+/// # Errors
+/// Fails when the file cannot be read or neither its language nor its comment
+/// syntax is known.
+#[napi(js_name = "fileSyntaxStats")]
+pub fn file_syntax_stats(
+	path: String,
+	lang: Option<String>,
+) -> Result<task::Async<FileSyntaxStats>> {
+	let path = fs_cache::resolve_path(&path)?;
+	let lang = match ast::resolve_language(lang.as_deref(), &path) {
+		Ok(lang) => Some(lang),
+		// Without a grammar, files of a known comment syntax still get line and
+		// TODO counts.
+		Err(_) if lang.is_none() && comments::lookup_path(&path).is_some() => None,
+		Err(err) => return Err(err),
+	};
+	Ok(task::blocking("symbols.stats", (), move |_| file_syntax_stats_sync(&path, lang)))
+}
+
+fn file_syntax_stats_sync(path: &Path, lang: Option<SupportLang>) -> Result<FileSyntaxStats> {
+	let source = std::fs::read_to_string(path)
+		.map_err(|err| Error::from_reason(format!("Failed to read {}: {err}", path.display())))?;
+	Ok(match lang {
+		Some(lang) => syntax_stats(&source, lang),
+		None => comment_stats(&source, path),
+	})
+}
+
+/// Line and TODO counts of a file without a grammar, from the comment syntax
+/// registered for its extension; `lang` is the extension.
+fn comment_stats(source: &str, path: &Path) -> FileSyntaxStats {
+	let todos = comments::lookup_path(path).map_or(0, |syntax| {
+		comments::find_comments(source, &syntax)
+			.into_iter()
+			.filter(|comment| has_todo_marker(comment))
+			.count()
+	});
+	FileSyntaxStats {
+		lang:      path
+			.extension()
+			.or_else(|| path.file_name())
+			.map_or_else(String::new, |name| name.to_string_lossy().to_ascii_lowercase()),
+		lines:     crate::utils::clamp_u32(source.lines().count() as u64),
+		functions: 0,
+		classes:   0,
+		imports:   0,
+		todos:     crate::utils::clamp_u32(todos as u64),
+		max_depth: 0,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn names(source: &str, lang: SupportLang) -> Vec<(String, String, Option<String>)> {
+		file_symbols(source.to_string(), lang, "file")
+			.into_iter()
+			.map(|symbol| (symbol.name, symbol.kind, symbol.container))
+			.collect()
+	}
+
+	#[test]
+	fn extracts_declarations() {
+		let rust = "mod util { pub fn helper() {} }\nconst LIMIT: u32 = 1;\nstruct Server;\nimpl \
+		            Server {\n    fn run(&self) {}\n}\nfn main() { let local = 1; }\n";
+		assert_eq!(names(rust, SupportLang::Rust), [
+			("util".to_string(), "module".to_string(), None),
+			("helper".to_string(), "function".to_string(), Some("util".to_string())),
+			("LIMIT".to_string(), "const".to_string(), None),
+			("Server".to_string(), "struct".to_string(), None),
+			("run".to_string(), "method".to_string(), Some("Server".to_string())),
+			("main".to_string(), "function".to_string(), None),
+		]);
+
+		let ts = "export class Store {\n  get(key: string) {}\n}\nexport const load = () => \
+		          1;\ntype Id = string;\ninterface Shape { area(): number }\nlet count = 0;\n";
+		assert_eq!(names(ts, SupportLang::TypeScript), [
+			("Store".to_string(), "class".to_string(), None),
+			("get".to_string(), "method".to_string(), Some("Store".to_string())),
+			("load".to_string(), "function".to_string(), None),
+			("Id".to_string(), "type".to_string(), None),
+			("Shape".to_string(), "interface".to_string(), None),
+			("area".to_string(), "method".to_string(), Some("Shape".to_string())),
+			("count".to_string(), "variable".to_string(), None),
+		]);
+
+		let python = "MAX = 3\n@cache\ndef fetch(url):\n    pass\nclass Client:\n    def \
+		              send(self):\n        pass\n";
+		assert_eq!(names(python, SupportLang::Python), [
+			("MAX".to_string(), "variable".to_string(), None),
+			("fetch".to_string(), "function".to_string(), None),
+			("Client".to_string(), "class".to_string(), None),
+			("send".to_string(), "method".to_string(), Some("Client".to_string())),
+		]);
+
+		let go = "package main\ntype Server struct{}\nfunc (s *Server) Run() {}\nfunc main() {}\n";
+		assert_eq!(names(go, SupportLang::Go), [
+			("Server".to_string(), "type".to_string(), None),
+			("Run".to_string(), "method".to_string(), Some("Server".to_string())),
+			("main".to_string(), "function".to_string(), None),
+		]);
+	}
+
+	#[test]
+	fn indexes_a_single_file_root() {
+		let unique = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let dir = std::env::temp_dir().join(format!("pi-symbols-file-root-test-{unique}"));
+		std::fs::create_dir_all(&dir).unwrap();
+		let file = dir.join("lib.rs");
+		std::fs::write(&file, "struct Config;\nfn load() {}\n").unwrap();
+		let path = file.to_string_lossy().into_owned();
+
+		assert!(symbol_index(path.clone(), None).is_ok());
+		assert!(symbol_index(dir.join("missing.rs").to_string_lossy().into_owned(), None).is_err());
+
+		let config = SymbolIndexConfig {
+			lang:  None,
+			glob:  None,
+			kinds: None,
+			query: None,
+			limit: DEFAULT_LIMIT as usize,
+		};
+		let root = fs_cache::resolve_path(&path).unwrap();
+		let result = symbol_index_sync(&root, &config, &task::CancelToken::default()).unwrap();
+		assert_eq!(result.files_indexed, 1);
+		let mut names: Vec<_> = result
+			.symbols
+			.iter()
+			.map(|symbol| symbol.name.as_str())
+			.collect();
+		names.sort_unstable();
+		assert_eq!(names, ["Config", "load"]);
+
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn counts_syntax_stats() {
+		let rust = "use std::fs;\nuse std::io;\n// TODO: split\nstruct Config;\nimpl Config {\n    \
+		            fn load() {\n        if true {\n            loop {}\n        }\n    }\n}\n// \
+		            TODOS are not todos\n";
+		let stats = syntax_stats(rust, SupportLang::Rust);
+		assert_eq!(
+			(stats.functions, stats.classes, stats.imports, stats.todos, stats.max_depth),
+			(1, 1, 2, 1, 4)
+		);
+		assert_eq!((stats.lang.as_str(), stats.lines), ("rust", 12));
+
+		let ts = "import a from \"a\";\nimport { b } from \"b\";\nconst f = () => 1;\nclass C { m() \
+		          {} }\n/* FIXME */\n";
+		let stats = syntax_stats(ts, SupportLang::TypeScript);
+		assert_eq!((stats.functions, stats.classes, stats.imports, stats.todos), (2, 1, 2, 1));
+
+		let go = "package main\nimport (\n\t\"fmt\"\n\t\"os\"\n)\nfunc main() {}\n";
+		let stats = syntax_stats(go, SupportLang::Go);
+		assert_eq!((stats.functions, stats.imports), (1, 2));
+	}
+
+	#[test]
+	fn counts_syntax_stats_of_a_file() {
+		let unique = std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let dir = std::env::temp_dir().join(format!("pi-symbols-stats-test-{unique}"));
+		std::fs::create_dir_all(&dir).unwrap();
+		let file = dir.join("main.py");
+		std::fs::write(&file, "import os\n# TODO: cache\ndef main():\n    pass\n").unwrap();
+		let path = file.to_string_lossy().into_owned();
+
+		assert!(file_syntax_stats(path.clone(), None).is_ok());
+		assert!(
+			file_syntax_stats(dir.join("missing.txt").to_string_lossy().into_owned(), None).is_err()
+		);
+
+		let resolved = fs_cache::resolve_path(&path).unwrap();
+		let stats = file_syntax_stats_sync(&resolved, Some(SupportLang::Python)).unwrap();
+		assert_eq!((stats.lang.as_str(), stats.lines), ("python", 4));
+		assert_eq!((stats.functions, stats.imports, stats.todos), (1, 1, 1));
+
+		// Files without a grammar fall back to their registered comment syntax.
+		let dsl = dir.join("rules.piStats");
+		std::fs::write(&dsl, "rule a ;; TODO: tighten\n#| FIXME\nspans |# rule b\n").unwrap();
+		let dsl = dsl.to_string_lossy().into_owned();
+		assert!(file_syntax_stats(dsl.clone(), None).is_err());
+		comments::register_comment_syntax("pistats".to_string(), comments::CommentSyntax {
+			line:        Some(";;".to_string()),
+			block_start: Some("#|".to_string()),
+			block_end:   Some("|#".to_string()),
+		})
+		.unwrap();
+		assert!(file_syntax_stats(dsl.clone(), None).is_ok());
+		let stats = file_syntax_stats_sync(&fs_cache::resolve_path(&dsl).unwrap(), None).unwrap();
+		assert_eq!((stats.lang.as_str(), stats.lines, stats.todos), ("pistats", 3, 2));
+
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+}
@@ -8,10 +8,19 @@
 //! Pass a `CancelToken` to blocking tasks. Work must check
 //! `CancelToken::heartbeat()` periodically to respect cancellation.
 //!
+//! A [`CancellationScope`] can be shared across several native calls (e.g.
+//! grep + astGrep for one agent turn) and cancelled once, aborting all of them.
+//!
 //! # Profiling
 //! Samples are always collected into a circular buffer. Call
 //! `get_work_profile()` to retrieve the last N seconds of data.
 //!
+//! # Adaptive Concurrency
+//! Work started with [`background`] pauses inside `heartbeat()` while
+//! interactive calls are queued or running, or while the system load average
+//! is high, so index builds never compete with the calls a user is waiting on.
+//! `setConcurrencyPolicy()` tunes the thresholds.
+//!
 //! # Usage
 //! ```ignore
 //! use crate::work::{blocking_task, CancelToken};
@@ -28,18 +37,20 @@
 //! ```
 
 use std::{
+	cell::Cell,
 	future::Future,
 	sync::{
-		Arc, Weak,
-		atomic::{AtomicU8, Ordering},
+		Arc, LazyLock, Weak,
+		atomic::{AtomicU8, AtomicU32, AtomicU64, AtomicUsize, Ordering},
 	},
 	time::{Duration, Instant},
 };
 
 use napi::{Env, Error, Result, Task, bindgen_prelude::*};
+use napi_derive::napi;
 use tokio::sync::Notify;
 
-use crate::prof::profile_region;
+use crate::{config, prof::profile_region};
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Cancellation
@@ -104,10 +115,27 @@ impl Flag {
 ///
 /// Call `heartbeat()` periodically inside long-running work to check for
 /// cancellation requests from timeouts or abort signals.
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct CancelToken {
-	deadline: Option<Instant>,
-	flag:     Option<Arc<Flag>>,
+	deadline:   Option<Instant>,
+	flag:       Option<Arc<Flag>>,
+	scope:      Option<Arc<Flag>>,
+	/// Throttle this work while interactive calls are in flight.
+	background: bool,
+	/// Settings captured when the token was created.
+	config:     config::Config,
+}
+
+impl Default for CancelToken {
+	fn default() -> Self {
+		Self {
+			deadline:   None,
+			flag:       None,
+			scope:      None,
+			background: false,
+			config:     config::snapshot(),
+		}
+	}
 }
 
 impl From<()> for CancelToken {
@@ -138,14 +166,43 @@ impl CancelToken {
 		result
 	}
 
+	/// Attach a shared cancellation scope to this token.
+	///
+	/// The token is considered aborted once either its own signal/timeout or
+	/// the scope fires.
+	pub fn with_scope(mut self, scope: Option<&CancellationScope>) -> Self {
+		self.scope = scope.map(|scope| scope.flag.clone());
+		self
+	}
+
+	/// Settings snapshot of the call this token belongs to.
+	pub const fn config(&self) -> &config::Config {
+		&self.config
+	}
+
+	fn cause(&self) -> Option<AbortReason> {
+		self
+			.flag
+			.as_ref()
+			.and_then(|flag| flag.cause())
+			.or_else(|| self.scope.as_ref().and_then(|scope| scope.cause()))
+	}
+
 	/// Check if cancellation has been requested.
 	///
 	/// Returns `Ok(())` if work should continue, or an error if cancelled.
-	/// Call this periodically in long-running loops.
+	/// Call this periodically in long-running loops. Background work may also
+	/// pause here while interactive calls are in flight.
 	pub fn heartbeat(&self) -> Result<()> {
-		if let Some(flag) = &self.flag
-			&& let Some(reason) = flag.cause()
-		{
+		self.check()?;
+		if self.background {
+			throttle_background(self)?;
+		}
+		Ok(())
+	}
+
+	fn check(&self) -> Result<()> {
+		if let Some(reason) = self.cause() {
 			return Err(Error::from_reason(format!("Aborted: {reason:?}")));
 		}
 		if let Some(deadline) = self.deadline
@@ -158,9 +215,8 @@ impl CancelToken {
 
 	/// Wait for the cancel token to be aborted.
 	pub async fn wait(&self) -> AbortReason {
-		let flag = self.flag.as_ref();
-		if let Some(flag) = flag.and_then(|f| f.cause()) {
-			return flag;
+		if let Some(reason) = self.cause() {
+			return reason;
 		}
 		let fflag = async {
 			let Some(flag) = self.flag.as_ref() else {
@@ -169,6 +225,13 @@ impl CancelToken {
 			flag.wait().await
 		};
 
+		let fscope = async {
+			let Some(scope) = self.scope.as_ref() else {
+				return std::future::pending().await;
+			};
+			scope.wait().await
+		};
+
 		let fttl = async {
 			let Some(ttl) = self.deadline else {
 				return std::future::pending().await;
@@ -186,6 +249,7 @@ impl CancelToken {
 
 		tokio::select! {
 			reason = fflag => reason,
+			reason = fscope => reason,
 			reason = fttl => reason,
 			reason = fuser => reason,
 		}
@@ -203,9 +267,7 @@ impl CancelToken {
 
 	/// Check if already aborted (non-blocking).
 	pub fn aborted(&self) -> bool {
-		if let Some(flag) = &self.flag
-			&& flag.cause().is_some()
-		{
+		if self.cause().is_some() {
 			return true;
 		}
 		if let Some(deadline) = self.deadline
@@ -232,6 +294,41 @@ impl AbortToken {
 	}
 }
 
+/// Cancellation handle shared across multiple native calls.
+///
+/// Pass the same scope to several operations (via their `scope` option) and
+/// call `cancel()` once to abort all of them, instead of tracking one
+/// `AbortSignal` per call.
+#[napi]
+#[derive(Default)]
+pub struct CancellationScope {
+	flag: Arc<Flag>,
+}
+
+#[napi]
+impl CancellationScope {
+	/// Create a new, not-yet-cancelled scope.
+	#[napi(constructor)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Cancel every operation attached to this scope.
+	///
+	/// Operations started with an already-cancelled scope abort on their first
+	/// heartbeat.
+	#[napi]
+	pub fn cancel(&self) {
+		self.flag.abort(AbortReason::Signal);
+	}
+
+	/// Whether `cancel()` has been called.
+	#[napi(getter)]
+	pub fn cancelled(&self) -> bool {
+		self.flag.cause().is_some()
+	}
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Blocking Task - libuv thread pool integration
 // ─────────────────────────────────────────────────────────────────────────────
@@ -247,6 +344,7 @@ where
 	tag:          &'static str,
 	cancel_token: CancelToken,
 	work:         Option<Box<dyn FnOnce(CancelToken) -> Result<T> + Send>>,
+	_in_flight:   InFlight,
 }
 
 impl<T> Task for Blocking<T>
@@ -262,7 +360,12 @@ where
 			.work
 			.take()
 			.ok_or_else(|| Error::from_reason("BlockingTask: work already consumed"))?;
-		work(self.cancel_token.clone())
+		if self.cancel_token.background {
+			// Yield before starting, not just at the first heartbeat.
+			throttle_background(&self.cancel_token)?;
+		}
+		let cancel_token = self.cancel_token.clone();
+		config::with_snapshot(cancel_token.config, || work(cancel_token))
 	}
 
 	fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -305,7 +408,235 @@ where
 	F: FnOnce(CancelToken) -> Result<T> + Send + 'static,
 	T: ToNapiValue + TypeName + Send + 'static,
 {
-	AsyncTask::new(Blocking { tag, cancel_token: cancel_token.into(), work: Some(Box::new(work)) })
+	AsyncTask::new(Blocking {
+		tag,
+		cancel_token: cancel_token.into(),
+		work: Some(Box::new(work)),
+		_in_flight: InFlight::new(&INTERACTIVE_IN_FLIGHT),
+	})
+}
+
+/// Like [`blocking`], for work nobody is waiting on (index builds, warmups).
+///
+/// The work pauses before it starts and at each `heartbeat()` while the
+/// concurrency policy reports pressure; see `setConcurrencyPolicy()`.
+pub fn background<T, F>(
+	tag: &'static str,
+	cancel_token: impl Into<CancelToken>,
+	work: F,
+) -> AsyncTask<Blocking<T>>
+where
+	F: FnOnce(CancelToken) -> Result<T> + Send + 'static,
+	T: ToNapiValue + TypeName + Send + 'static,
+{
+	let mut cancel_token = cancel_token.into();
+	cancel_token.background = true;
+	AsyncTask::new(Blocking {
+		tag,
+		cancel_token,
+		work: Some(Box::new(work)),
+		_in_flight: InFlight::new(&BACKGROUND_IN_FLIGHT),
+	})
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Adaptive Concurrency
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Interactive blocking tasks queued or running.
+static INTERACTIVE_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+/// Background blocking tasks queued or running.
+static BACKGROUND_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+/// Total time background work spent paused, in microseconds.
+static THROTTLED_US: AtomicU64 = AtomicU64::new(0);
+
+/// Cached 1-minute load average per core, in thousandths.
+static LOAD_MILLI: AtomicU32 = AtomicU32::new(0);
+/// When `LOAD_MILLI` was sampled, in ms since `CLOCK_START` (0 = never).
+static LOAD_SAMPLED_MS: AtomicU64 = AtomicU64::new(0);
+static CLOCK_START: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+const LOAD_SAMPLE_INTERVAL_MS: u64 = 1000;
+const PRESSURE_POLL: Duration = Duration::from_millis(5);
+/// Minimum time between pressure checks on one thread.
+const PRESSURE_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+thread_local! {
+	/// Background work on this thread skips pressure checks until then.
+	static NEXT_PRESSURE_CHECK: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Counts a blocking task as in flight from creation until it is dropped.
+struct InFlight(&'static AtomicUsize);
+
+impl InFlight {
+	fn new(counter: &'static AtomicUsize) -> Self {
+		counter.fetch_add(1, Ordering::SeqCst);
+		Self(counter)
+	}
+}
+
+impl Drop for InFlight {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+/// Background work throttling settings, part of [`config::Config`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Policy {
+	enabled:         bool,
+	max_interactive: usize,
+	max_load:        f64,
+	max_pause:       Duration,
+}
+
+impl Policy {
+	pub const DEFAULT: Self = Self {
+		enabled:         true,
+		max_interactive: 0,
+		max_load:        0.9,
+		max_pause:       Duration::from_millis(250),
+	};
+}
+
+impl From<Policy> for ConcurrencyPolicy {
+	fn from(policy: Policy) -> Self {
+		Self {
+			enabled:         Some(policy.enabled),
+			max_interactive: Some(crate::utils::clamp_u32(policy.max_interactive as u64)),
+			max_load:        Some(policy.max_load),
+			max_pause_ms:    Some(crate::utils::clamp_u32(policy.max_pause.as_millis() as u64)),
+		}
+	}
+}
+
+/// Tuning for background work throttling; omitted fields keep their value.
+#[napi(object)]
+pub struct ConcurrencyPolicy {
+	/// Throttle background work at all (default: true).
+	pub enabled:         Option<bool>,
+	/// Background work pauses while more interactive calls than this are
+	/// queued or running (default: 0).
+	#[napi(js_name = "maxInteractive")]
+	pub max_interactive: Option<u32>,
+	/// Background work pauses while the 1-minute load average per core
+	/// exceeds this (default: 0.9; unavailable on Windows).
+	#[napi(js_name = "maxLoad")]
+	pub max_load:        Option<f64>,
+	/// Longest single pause; background work then runs for the same time
+	/// before pausing again (default: 250).
+	#[napi(js_name = "maxPauseMs")]
+	pub max_pause_ms:    Option<u32>,
+}
+
+/// Snapshot of scheduler pressure, from `getConcurrencyStats()`.
+#[napi(object)]
+pub struct ConcurrencyStats {
+	/// Interactive calls queued or running.
+	pub interactive:  u32,
+	/// Background calls queued or running.
+	pub background:   u32,
+	/// Last sampled 1-minute load average per core.
+	pub load:         f64,
+	/// Total time background work has spent paused, in milliseconds.
+	#[napi(js_name = "throttledMs")]
+	pub throttled_ms: f64,
+}
+
+fn read_load_per_core() -> f64 {
+	#[cfg(any(target_os = "linux", target_os = "macos"))]
+	{
+		let mut load = [0f64; 1];
+		// SAFETY: `load` has room for the single sample requested.
+		if unsafe { libc::getloadavg(load.as_mut_ptr(), 1) } == 1 {
+			let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+			return load[0] / cores as f64;
+		}
+	}
+	0.0
+}
+
+/// 1-minute load average per core, resampled at most once per second.
+fn load_per_core() -> f64 {
+	let now_ms = CLOCK_START.elapsed().as_millis() as u64 + 1;
+	let sampled = LOAD_SAMPLED_MS.load(Ordering::Relaxed);
+	if (sampled == 0 || now_ms.saturating_sub(sampled) >= LOAD_SAMPLE_INTERVAL_MS)
+		&& LOAD_SAMPLED_MS
+			.compare_exchange(sampled, now_ms, Ordering::AcqRel, Ordering::Relaxed)
+			.is_ok()
+	{
+		let load = (read_load_per_core() * 1000.0).clamp(0.0, f64::from(u32::MAX)) as u32;
+		LOAD_MILLI.store(load, Ordering::Relaxed);
+	}
+	f64::from(LOAD_MILLI.load(Ordering::Relaxed)) / 1000.0
+}
+
+fn under_pressure(policy: &Policy) -> bool {
+	INTERACTIVE_IN_FLIGHT.load(Ordering::SeqCst) > policy.max_interactive
+		|| load_per_core() > policy.max_load
+}
+
+/// Pause background work while the system is under pressure.
+///
+/// Each pause lasts at most `maxPauseMs`; the work then runs for the same
+/// time before it can be paused again, so it always makes progress.
+fn throttle_background(ct: &CancelToken) -> Result<()> {
+	let now = Instant::now();
+	if NEXT_PRESSURE_CHECK.get().is_some_and(|next| now < next) {
+		return Ok(());
+	}
+	let policy = ct.config.concurrency;
+	NEXT_PRESSURE_CHECK.set(Some(now + PRESSURE_CHECK_INTERVAL));
+	if !policy.enabled || !under_pressure(&policy) {
+		return Ok(());
+	}
+
+	while now.elapsed() < policy.max_pause && under_pressure(&policy) {
+		ct.check()?;
+		std::thread::sleep(PRESSURE_POLL);
+	}
+	let paused = now.elapsed();
+	THROTTLED_US.fetch_add(paused.as_micros() as u64, Ordering::Relaxed);
+	NEXT_PRESSURE_CHECK.set(Some(Instant::now() + paused.max(PRESSURE_CHECK_INTERVAL)));
+	ct.check()
+}
+
+/// Tune background work throttling. Omitted fields keep their current value.
+#[napi(js_name = "setConcurrencyPolicy")]
+pub fn set_concurrency_policy(policy: ConcurrencyPolicy) {
+	config::update(|config| {
+		let current = &mut config.concurrency;
+		if let Some(enabled) = policy.enabled {
+			current.enabled = enabled;
+		}
+		if let Some(max_interactive) = policy.max_interactive {
+			current.max_interactive = max_interactive as usize;
+		}
+		if let Some(max_load) = policy.max_load.filter(|load| load.is_finite()) {
+			current.max_load = max_load.max(0.0);
+		}
+		if let Some(max_pause_ms) = policy.max_pause_ms {
+			current.max_pause = Duration::from_millis(u64::from(max_pause_ms));
+		}
+	});
+}
+
+/// Current background work throttling policy.
+#[napi(js_name = "getConcurrencyPolicy")]
+pub fn get_concurrency_policy() -> ConcurrencyPolicy {
+	config::snapshot().concurrency.into()
+}
+
+/// Snapshot of in-flight work and system load used by the throttle.
+#[napi(js_name = "getConcurrencyStats")]
+pub fn get_concurrency_stats() -> ConcurrencyStats {
+	ConcurrencyStats {
+		interactive:  crate::utils::clamp_u32(INTERACTIVE_IN_FLIGHT.load(Ordering::SeqCst) as u64),
+		background:   crate::utils::clamp_u32(BACKGROUND_IN_FLIGHT.load(Ordering::SeqCst) as u64),
+		load:         load_per_core(),
+		throttled_ms: THROTTLED_US.load(Ordering::Relaxed) as f64 / 1000.0,
+	}
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -346,3 +677,44 @@ where
 		work.await
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scope_cancel_aborts_every_attached_token() {
+		let scope = CancellationScope::new();
+		let first = CancelToken::default().with_scope(Some(&scope));
+		let second = CancelToken::default().with_scope(Some(&scope));
+		assert!(first.heartbeat().is_ok());
+
+		scope.cancel();
+
+		assert!(scope.cancelled());
+		assert!(first.aborted());
+		assert!(second.heartbeat().is_err());
+	}
+
+	#[test]
+	fn background_work_pauses_for_interactive_calls() {
+		// A local policy keeps the global one untouched for other tests.
+		let mut config = config::snapshot();
+		config.concurrency.enabled = true;
+		config.concurrency.max_load = f64::MAX;
+		config.concurrency.max_pause = Duration::from_millis(20);
+
+		let interactive = InFlight::new(&INTERACTIVE_IN_FLIGHT);
+		let ct = CancelToken { background: true, config, ..CancelToken::default() };
+		let started = Instant::now();
+		assert!(ct.heartbeat().is_ok());
+		assert!(started.elapsed() >= Duration::from_millis(20));
+		drop(interactive);
+
+		// The grant window lets the work run without pausing again.
+		let resumed = Instant::now();
+		assert!(ct.heartbeat().is_ok());
+		assert!(resumed.elapsed() < Duration::from_millis(20));
+		assert!(get_concurrency_stats().throttled_ms >= 20.0);
+	}
+}
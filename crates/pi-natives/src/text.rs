@@ -7,34 +7,31 @@
 //! - Width checks early-exit
 //! - Ellipsis decoded lazily
 //! - truncateToWidth returns the original `JsString` when possible
+//!
+//! The layout core (ANSI state, widths, wrapping, truncation, slicing) lives
+//! in the `pi-text` crate, which also builds for wasm; this module adds the
+//! N-API exports and the JS-facing helpers around it.
 
-use std::cell::RefCell;
+use std::{
+	borrow::Cow,
+	collections::VecDeque,
+	hash::{DefaultHasher, Hash, Hasher},
+	sync::{Arc, LazyLock},
+};
 
 use napi::{JsString, bindgen_prelude::*};
 use napi_derive::napi;
-use smallvec::{SmallVec, smallvec};
-use unicode_segmentation::UnicodeSegmentation;
-use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
-
-const DEFAULT_TAB_WIDTH: usize = 3;
-const MIN_TAB_WIDTH: usize = 1;
-const MAX_TAB_WIDTH: usize = 16;
-const ESC: u16 = 0x1b;
-
-#[inline]
-const fn clamp_tab_width(tab_width: Option<u32>) -> usize {
-	let width = match tab_width {
-		Some(tab_width) => tab_width as usize,
-		None => DEFAULT_TAB_WIDTH,
-	};
-	if width < MIN_TAB_WIDTH {
-		MIN_TAB_WIDTH
-	} else if width > MAX_TAB_WIDTH {
-		MAX_TAB_WIDTH
-	} else {
-		width
-	}
-}
+use parking_lot::Mutex;
+pub use pi_text::WidthPolicy;
+use pi_text::{
+	AnsiState, ESC, ansi_seq_len_u16, ascii_cell_width_u16, for_each_grapheme_u16_slow,
+	for_each_paragraph, is_sgr_u16, slice_with_width_impl, truncate_to_width_impl,
+	visible_width_u16, wrap_paragraph, wrap_paragraphs, wrap_text_with_ansi_impl,
+};
+pub(crate) use pi_text::{clamp_tab_width, display_width, width_prefix_len};
+use smallvec::SmallVec;
+
+use crate::config;
 
 fn build_utf16_string(mut data: Vec<u16>) -> Utf16String {
 	while data.last() == Some(&0) {
@@ -70,739 +67,610 @@ pub struct ExtractSegmentsResult {
 	pub after_width:  u32,
 }
 
-// ============================================================================
-// ANSI State Tracking - Zero Allocation
-// ============================================================================
-
-const ATTR_BOLD: u16 = 1 << 0;
-const ATTR_DIM: u16 = 1 << 1;
-const ATTR_ITALIC: u16 = 1 << 2;
-const ATTR_UNDERLINE: u16 = 1 << 3;
-const ATTR_BLINK: u16 = 1 << 4;
-const ATTR_INVERSE: u16 = 1 << 6;
-const ATTR_HIDDEN: u16 = 1 << 7;
-const ATTR_STRIKE: u16 = 1 << 8;
-
-type ColorVal = u32;
-const COLOR_NONE: ColorVal = 0;
-
-#[derive(Clone, Copy, Default)]
-struct AnsiState {
-	attrs: u16,
-	fg:    ColorVal,
-	bg:    ColorVal,
-}
-
-impl AnsiState {
-	#[inline]
-	const fn new() -> Self {
-		Self { attrs: 0, fg: COLOR_NONE, bg: COLOR_NONE }
-	}
-
-	#[inline]
-	const fn is_empty(&self) -> bool {
-		self.attrs == 0 && self.fg == COLOR_NONE && self.bg == COLOR_NONE
-	}
-
-	#[inline]
-	const fn reset(&mut self) {
-		*self = Self::new();
-	}
-
-	fn apply_sgr_u16(&mut self, params: &[u16]) {
-		if params.is_empty() {
-			self.reset();
-			return;
-		}
-
-		let mut i = 0;
-		while i < params.len() {
-			let (code, next_i) = parse_sgr_num_u16(params, i);
-			i = next_i;
-
-			match code {
-				0 => self.reset(),
-				1 => self.attrs |= ATTR_BOLD,
-				2 => self.attrs |= ATTR_DIM,
-				3 => self.attrs |= ATTR_ITALIC,
-				4 => self.attrs |= ATTR_UNDERLINE,
-				5 => self.attrs |= ATTR_BLINK,
-				7 => self.attrs |= ATTR_INVERSE,
-				8 => self.attrs |= ATTR_HIDDEN,
-				9 => self.attrs |= ATTR_STRIKE,
-
-				21 => self.attrs &= !ATTR_BOLD,
-				22 => self.attrs &= !(ATTR_BOLD | ATTR_DIM),
-				23 => self.attrs &= !ATTR_ITALIC,
-				24 => self.attrs &= !ATTR_UNDERLINE,
-				25 => self.attrs &= !ATTR_BLINK,
-				27 => self.attrs &= !ATTR_INVERSE,
-				28 => self.attrs &= !ATTR_HIDDEN,
-				29 => self.attrs &= !ATTR_STRIKE,
-
-				30..=37 => self.fg = (code - 29) as ColorVal,
-				39 => self.fg = COLOR_NONE,
-				40..=47 => self.bg = (code - 39) as ColorVal,
-				49 => self.bg = COLOR_NONE,
-				90..=97 => self.fg = (code - 81) as ColorVal,
-				100..=107 => self.bg = (code - 91) as ColorVal,
-
-				38 | 48 => {
-					let (mode, ni) = parse_sgr_num_u16(params, i);
-					i = ni;
-
-					let color = match mode {
-						5 => {
-							let (idx, ni) = parse_sgr_num_u16(params, i);
-							i = ni;
-							0x100 | (idx as ColorVal & 0xff)
-						},
-						2 => {
-							let (r, ni) = parse_sgr_num_u16(params, i);
-							let (g, ni) = parse_sgr_num_u16(params, ni);
-							let (b, ni) = parse_sgr_num_u16(params, ni);
-							i = ni;
-							0x1000000
-								| ((r as ColorVal & 0xff) << 16)
-								| ((g as ColorVal & 0xff) << 8)
-								| (b as ColorVal & 0xff)
-						},
-						_ => continue,
-					};
-
-					if code == 38 {
-						self.fg = color;
-					} else {
-						self.bg = color;
-					}
-				},
-
-				_ => {},
-			}
-		}
-	}
-
-	fn write_restore_u16(&self, out: &mut Vec<u16>) {
-		if self.is_empty() {
-			return;
-		}
-
-		out.extend_from_slice(&[ESC, b'[' as u16]);
-		let mut first = true;
-
-		macro_rules! push_code {
-			($code:expr) => {{
-				if !first {
-					out.push(b';' as u16);
-				}
-				first = false;
-				write_u32_u16(out, $code);
-			}};
-		}
-
-		if self.attrs & ATTR_BOLD != 0 {
-			push_code!(1);
-		}
-		if self.attrs & ATTR_DIM != 0 {
-			push_code!(2);
-		}
-		if self.attrs & ATTR_ITALIC != 0 {
-			push_code!(3);
-		}
-		if self.attrs & ATTR_UNDERLINE != 0 {
-			push_code!(4);
+impl From<WidthPolicy> for TextWidthPolicy {
+	fn from(policy: WidthPolicy) -> Self {
+		Self {
+			ambiguous:          Some(if policy.ambiguous_wide { 2 } else { 1 }),
+			emoji_presentation: Some(if policy.emoji_wide { 2 } else { 1 }),
 		}
-		if self.attrs & ATTR_BLINK != 0 {
-			push_code!(5);
-		}
-		if self.attrs & ATTR_INVERSE != 0 {
-			push_code!(7);
-		}
-		if self.attrs & ATTR_HIDDEN != 0 {
-			push_code!(8);
-		}
-		if self.attrs & ATTR_STRIKE != 0 {
-			push_code!(9);
-		}
-
-		write_color_u16(out, self.fg, 38, &mut first);
-		write_color_u16(out, self.bg, 48, &mut first);
-
-		out.push(b'm' as u16);
 	}
 }
 
-#[inline]
-fn write_color_u16(out: &mut Vec<u16>, color: ColorVal, base: u32, first: &mut bool) {
-	if color == COLOR_NONE {
-		return;
-	}
-
-	if !*first {
-		out.push(b';' as u16);
-	}
-	*first = false;
-
-	if color < 0x100 {
-		let code = if color <= 8 { color + 29 } else { color + 81 };
-		let code = if base == 48 { code + 10 } else { code };
-		write_u32_u16(out, code);
-	} else if color < 0x1000000 {
-		write_u32_u16(out, base);
-		out.extend_from_slice(&[b';' as u16, b'5' as u16, b';' as u16]);
-		write_u32_u16(out, color & 0xff);
-	} else {
-		write_u32_u16(out, base);
-		out.extend_from_slice(&[b';' as u16, b'2' as u16, b';' as u16]);
-		write_u32_u16(out, (color >> 16) & 0xff);
-		out.push(b';' as u16);
-		write_u32_u16(out, (color >> 8) & 0xff);
-		out.push(b';' as u16);
-		write_u32_u16(out, color & 0xff);
-	}
+/// Cells for characters terminals disagree on; omitted fields keep their
+/// value.
+#[napi(object)]
+pub struct TextWidthPolicy {
+	/// Cells for East Asian ambiguous-width characters such as `±` or `Ω`:
+	/// 1 (default) or 2, as terminals in CJK locales draw them.
+	pub ambiguous:          Option<u32>,
+	/// Cells for emoji presentation sequences such as `❤️` (a character
+	/// followed by U+FE0F): 2 (default) or 1.
+	#[napi(js_name = "emojiPresentation")]
+	pub emoji_presentation: Option<u32>,
 }
 
-#[inline]
-fn parse_sgr_num_u16(params: &[u16], mut i: usize) -> (u32, usize) {
-	while i < params.len() && params[i] == b';' as u16 {
-		i += 1;
-	}
-
-	let mut val: u32 = 0;
-	while i < params.len() {
-		let b = params[i];
-		if b == b';' as u16 {
-			i += 1;
-			break;
+/// Set how every text function measures ambiguous-width characters and emoji
+/// presentation sequences. Omitted fields keep their current value.
+///
+/// # Errors
+/// Fails when a width is not 1 or 2.
+#[napi(js_name = "setWidthPolicy")]
+pub fn set_width_policy(policy: TextWidthPolicy) -> Result<()> {
+	let wide = |cells: Option<u32>, name: &str| match cells {
+		None => Ok(None),
+		Some(1) => Ok(Some(false)),
+		Some(2) => Ok(Some(true)),
+		Some(cells) => {
+			Err(Error::from_reason(format!("Invalid {name} width {cells}: expected 1 or 2")))
+		},
+	};
+	let ambiguous_wide = wide(policy.ambiguous, "ambiguous")?;
+	let emoji_wide = wide(policy.emoji_presentation, "emojiPresentation")?;
+	config::update(|config| {
+		let current = &mut config.width;
+		if let Some(ambiguous_wide) = ambiguous_wide {
+			current.ambiguous_wide = ambiguous_wide;
 		}
-		if (b'0' as u16..=b'9' as u16).contains(&b) {
-			val = val
-				.saturating_mul(10)
-				.saturating_add((b - b'0' as u16) as u32);
+		if let Some(emoji_wide) = emoji_wide {
+			current.emoji_wide = emoji_wide;
 		}
-		i += 1;
-	}
-	(val, i)
+	});
+	Ok(())
 }
 
-#[inline]
-fn write_u32_u16(out: &mut Vec<u16>, mut val: u32) {
-	if val == 0 {
-		out.push(b'0' as u16);
-		return;
-	}
-	let start = out.len();
-	while val > 0 {
-		out.push(b'0' as u16 + (val % 10) as u16);
-		val /= 10;
-	}
-	out[start..].reverse();
+/// Current width policy of the text functions.
+#[napi(js_name = "getWidthPolicy")]
+pub fn get_width_policy() -> TextWidthPolicy {
+	config::snapshot().width.into()
 }
 
-// ============================================================================
-// ANSI Sequence Detection - UTF-16
-// ============================================================================
-
-#[inline]
-fn ansi_seq_len_u16(data: &[u16], pos: usize) -> Option<usize> {
-	if pos >= data.len() || data[pos] != ESC {
-		return None;
-	}
-	if pos + 1 >= data.len() {
-		return None;
-	}
+/// Make `pi-text` measure with the width policy of the running call.
+#[napi_derive::module_init]
+fn install_width_policy_source() {
+	pi_text::set_width_policy_source(|| config::current().width);
+}
 
-	match data[pos + 1] {
-		0x5b => {
-			// '[' CSI
-			for (i, b) in data[pos + 2..].iter().enumerate() {
-				if (0x40..=0x7e).contains(b) {
-					return Some(i + 3);
-				}
-			}
-			None
-		},
-		0x5d => {
-			// ']' OSC
-			for (i, &b) in data[pos + 2..].iter().enumerate() {
-				if b == 0x07 {
-					return Some(i + 3);
-				}
-				if b == ESC && data.get(pos + 2 + i + 1) == Some(&0x5c) {
-					return Some(i + 4);
-				}
-			}
-			None
-		},
-		0x50 | 0x58 | 0x5e | 0x5f => {
-			// 'P' DCS, 'X' SOS, '^' PM, '_' APC (terminated by ST)
-			for (i, &b) in data[pos + 2..].iter().enumerate() {
-				if b == ESC && data.get(pos + 2 + i + 1) == Some(&0x5c) {
-					return Some(i + 4);
-				}
-			}
-			None
-		},
-		0x20..=0x2f => {
-			// ESC + intermediates + final byte
-			for (i, b) in data[pos + 2..].iter().enumerate() {
-				if (0x30..=0x7e).contains(b) {
-					return Some(i + 3);
-				}
-			}
-			None
-		},
-		0x40..=0x7e => Some(2),
-		_ => None,
-	}
+/// Wrap text to a visible width, preserving ANSI escape codes across line
+/// breaks.
+///
+/// Returns UTF-16 lines with active SGR codes carried across line boundaries.
+/// With `tab_stops`, tabs are first expanded to the next tab stop.
+#[napi(js_name = "wrapTextWithAnsi")]
+pub fn wrap_text_with_ansi(
+	text: JsString,
+	width: u32,
+	tab_width: Option<u32>,
+	tab_stops: Option<bool>,
+) -> Result<Vec<Utf16String>> {
+	let text_u16 = text.into_utf16()?;
+	let tab_width = clamp_tab_width(tab_width);
+	let text = apply_tab_stops(text_u16.as_slice(), tab_stops, tab_width);
+	let lines = wrap_text_with_ansi_impl(&text, width as usize, tab_width);
+	Ok(lines.into_iter().map(build_utf16_string).collect())
 }
 
-#[inline]
-fn is_sgr_u16(seq: &[u16]) -> bool {
-	seq.len() >= 3 && seq[1] == b'[' as u16 && *seq.last().unwrap() == b'm' as u16
+/// Wrap many texts in one call, returning the wrapped lines of each.
+///
+/// Equivalent to calling `wrapTextWithAnsi` per text, without the per-call
+/// N-API overhead.
+#[napi(js_name = "wrapTextWithAnsiBatch")]
+pub fn wrap_text_with_ansi_batch(
+	texts: Vec<JsString>,
+	width: u32,
+	tab_width: Option<u32>,
+) -> Result<Vec<Vec<Utf16String>>> {
+	let tab_width = clamp_tab_width(tab_width);
+	texts
+		.into_iter()
+		.map(|text| {
+			let text_u16 = text.into_utf16()?;
+			let lines = wrap_text_with_ansi_impl(text_u16.as_slice(), width as usize, tab_width);
+			Ok(lines.into_iter().map(build_utf16_string).collect())
+		})
+		.collect()
 }
 
 // ============================================================================
-// Grapheme / Width
+// wrapWindow
 // ============================================================================
 
-#[inline]
-const fn ascii_cell_width_u16(u: u16, tab_width: usize) -> usize {
-	let b = u as u8;
-	match b {
-		b'\t' => tab_width,
-		0x20..=0x7e => 1,
-		_ => 0,
-	}
+/// Options for `wrapWindow`.
+#[napi(object)]
+pub struct WrapWindowOptions {
+	/// First wrapped line to return (0-indexed, default: 0).
+	#[napi(js_name = "firstLine")]
+	pub first_line: Option<u32>,
+	/// Maximum number of wrapped lines to return (default: all remaining).
+	#[napi(js_name = "lineCount")]
+	pub line_count: Option<u32>,
+	#[napi(js_name = "tabWidth")]
+	pub tab_width:  Option<u32>,
 }
 
-#[inline]
-fn grapheme_width_str(g: &str, tab_width: usize) -> usize {
-	if g == "\t" {
-		return tab_width;
-	}
-	let mut it = g.chars();
-	let Some(c0) = it.next() else {
-		return 0;
-	};
-	if it.next().is_none() {
-		return UnicodeWidthChar::width(c0).unwrap_or(0);
-	}
-	UnicodeWidthStr::width(g)
+#[napi(object)]
+pub struct WrapWindowResult {
+	/// UTF-16 wrapped lines in the requested window.
+	pub lines:       Vec<Utf16String>,
+	/// Index of `lines[0]` in the full wrapped output (clamped to
+	/// `totalLines`).
+	#[napi(js_name = "firstLine")]
+	pub first_line:  u32,
+	/// Wrapped line count of the whole text.
+	#[napi(js_name = "totalLines")]
+	pub total_lines: u32,
 }
 
-thread_local! {
-  static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+/// A source paragraph with the index of its first wrapped line.
+#[derive(Clone)]
+struct WrapParagraph {
+	start:      usize,
+	end:        usize,
+	first_line: usize,
+	state:      AnsiState,
 }
 
-/// Iterate graphemes in a non-ASCII UTF-16 segment.
-///
-/// Callback returns `true` to continue, `false` to stop early.
-#[inline]
-fn for_each_grapheme_u16_slow<F>(segment: &[u16], tab_width: usize, mut f: F) -> bool
-where
-	F: FnMut(&[u16], usize) -> bool,
-{
-	if segment.is_empty() {
-		return true;
-	}
-
-	SCRATCH.with_borrow_mut(|scratch| {
-		scratch.clear();
-		scratch.reserve(segment.len());
-
-		for r in std::char::decode_utf16(segment.iter().copied()) {
-			scratch.push(r.unwrap_or('\u{FFFD}'));
-		}
+/// Wrapped line layout of one text at one width, without the lines
+/// themselves.
+struct WrapIndex {
+	paragraphs:  Vec<WrapParagraph>,
+	total_lines: usize,
+}
 
-		let mut utf16_pos = 0usize;
-		for g in scratch.graphemes(true) {
-			let w = grapheme_width_str(g, tab_width);
+/// Content hash, length, width, tab width, and width policy.
+type WrapIndexKey = (u64, usize, usize, usize, WidthPolicy);
+type WrapIndexCache = VecDeque<(WrapIndexKey, Arc<WrapIndex>)>;
 
-			let g_u16_len: usize = g.chars().map(|c| c.len_utf16()).sum();
-			let u16_slice = &segment[utf16_pos..utf16_pos + g_u16_len];
-			utf16_pos += g_u16_len;
+const WRAP_INDEX_CACHE_SIZE: usize = 8;
 
-			if !f(u16_slice, w) {
-				return false;
-			}
-		}
+/// Most recently used wrap indexes, newest first.
+static WRAP_INDEX_CACHE: LazyLock<Mutex<WrapIndexCache>> =
+	LazyLock::new(|| Mutex::new(VecDeque::with_capacity(WRAP_INDEX_CACHE_SIZE)));
 
-		true
-	})
+fn build_wrap_index(text: &[u16], width: usize, tab_width: usize) -> WrapIndex {
+	let mut paragraphs = Vec::new();
+	let mut total_lines = 0usize;
+	for_each_paragraph(text, |start, end, state| {
+		paragraphs.push(WrapParagraph { start, end, first_line: total_lines, state: state.clone() });
+		total_lines += wrap_paragraph(text, start, end, state, width, tab_width).len();
+	});
+	WrapIndex { paragraphs, total_lines }
 }
 
-/// Visible width, with early-exit if width exceeds `limit`.
-fn visible_width_u16_up_to(data: &[u16], limit: usize, tab_width: usize) -> (usize, bool) {
-	let mut width = 0usize;
-	let mut i = 0usize;
-	let len = data.len();
-
-	while i < len {
-		if data[i] == ESC {
-			if let Some(seq_len) = ansi_seq_len_u16(data, i) {
-				i += seq_len;
-				continue;
-			}
-			i += 1;
-			continue;
-		}
+fn wrap_index(text: &[u16], width: usize, tab_width: usize) -> Arc<WrapIndex> {
+	let mut hasher = DefaultHasher::new();
+	text.hash(&mut hasher);
+	let key = (hasher.finish(), text.len(), width, tab_width, config::current().width);
+
+	let mut cache = WRAP_INDEX_CACHE.lock();
+	if let Some(pos) = cache.iter().position(|(cached, _)| *cached == key) {
+		let entry = cache.remove(pos).expect("position is in bounds");
+		let index = Arc::clone(&entry.1);
+		cache.push_front(entry);
+		return index;
+	}
+	drop(cache);
 
-		let start = i;
-		let mut is_ascii = true;
-		while i < len && data[i] != ESC {
-			if data[i] > 0x7f {
-				is_ascii = false;
-			}
-			i += 1;
-		}
-		let seg = &data[start..i];
+	let index = Arc::new(build_wrap_index(text, width, tab_width));
+	let mut cache = WRAP_INDEX_CACHE.lock();
+	cache.truncate(WRAP_INDEX_CACHE_SIZE - 1);
+	cache.push_front((key, Arc::clone(&index)));
+	index
+}
 
-		if is_ascii {
-			for &u in seg {
-				width += ascii_cell_width_u16(u, tab_width);
-				if width > limit {
-					return (width, true);
-				}
-			}
-		} else {
-			let ok = for_each_grapheme_u16_slow(seg, tab_width, |_, w| {
-				width += w;
-				width <= limit
-			});
-			if !ok {
-				return (width, true);
-			}
+fn wrap_window_impl(
+	text: &[u16],
+	width: usize,
+	tab_width: usize,
+	first_line: usize,
+	line_count: Option<usize>,
+) -> (Vec<Vec<u16>>, usize, usize) {
+	let index = wrap_index(text, width, tab_width);
+	let first = first_line.min(index.total_lines);
+	let end = line_count
+		.map_or(index.total_lines, |count| first.saturating_add(count).min(index.total_lines));
+
+	let mut lines = Vec::with_capacity(end - first);
+	let start_para = index
+		.paragraphs
+		.partition_point(|para| para.first_line <= first)
+		.saturating_sub(1);
+	for para in &index.paragraphs[start_para..] {
+		if para.first_line >= end {
+			break;
 		}
+		let wrapped = wrap_paragraph(text, para.start, para.end, &para.state, width, tab_width);
+		let skip = first.saturating_sub(para.first_line);
+		let take = end - (para.first_line + skip).min(end);
+		lines.extend(wrapped.into_iter().skip(skip).take(take));
 	}
-
-	(width, width > limit)
+	(lines, first, index.total_lines)
 }
 
-fn visible_width_u16(data: &[u16], tab_width: usize) -> usize {
-	visible_width_u16_up_to(data, usize::MAX, tab_width).0
+/// Wrap text like [`wrap_text_with_ansi`] but return only a window of the
+/// wrapped lines plus the total wrapped line count.
+///
+/// The line layout is cached by content hash, width, and tab width, so
+/// scrolling the same text only re-wraps the paragraphs in view.
+#[napi(js_name = "wrapWindow")]
+pub fn wrap_window(
+	text: JsString,
+	width: u32,
+	options: Option<WrapWindowOptions>,
+) -> Result<WrapWindowResult> {
+	let text_u16 = text.into_utf16()?;
+	let (first_line, line_count, tab_width) = options.map_or((0, None, None), |options| {
+		(options.first_line.unwrap_or(0), options.line_count, options.tab_width)
+	});
+	let (lines, first, total) = wrap_window_impl(
+		text_u16.as_slice(),
+		width as usize,
+		clamp_tab_width(tab_width),
+		first_line as usize,
+		line_count.map(|count| count as usize),
+	);
+	Ok(WrapWindowResult {
+		lines:       lines.into_iter().map(build_utf16_string).collect(),
+		first_line:  crate::utils::clamp_u32(first as u64),
+		total_lines: crate::utils::clamp_u32(total as u64),
+	})
 }
 
 // ============================================================================
-// wrapTextWithAnsi
+// Bidi segmentation
 // ============================================================================
 
-#[inline]
-fn write_active_codes(state: &AnsiState, out: &mut Vec<u16>) {
-	if !state.is_empty() {
-		state.write_restore_u16(out);
-	}
-}
-
-#[inline]
-fn write_line_end_reset(state: &AnsiState, out: &mut Vec<u16>) {
-	let has_underline = state.attrs & ATTR_UNDERLINE != 0;
-	let has_strike = state.attrs & ATTR_STRIKE != 0;
-	if !has_underline && !has_strike {
-		return;
-	}
-
-	out.extend_from_slice(&[ESC, b'[' as u16]);
-	if has_underline {
-		out.extend_from_slice(&[b'2' as u16, b'4' as u16]);
-		if has_strike {
-			out.push(b';' as u16);
-		}
-	}
-	if has_strike {
-		out.extend_from_slice(&[b'2' as u16, b'9' as u16]);
-	}
-	out.push(b'm' as u16);
+/// A run of characters sharing one resolved bidi embedding level.
+#[napi(object)]
+pub struct BidiRun {
+	/// Start offset in the line (UTF-16 code units, inclusive).
+	pub start:     u32,
+	/// End offset in the line (UTF-16 code units, exclusive).
+	pub end:       u32,
+	/// Resolved embedding level (odd levels are right-to-left).
+	pub level:     u32,
+	/// "ltr" or "rtl".
+	pub direction: String,
 }
 
-fn update_state_from_text(data: &[u16], state: &mut AnsiState) {
-	let mut i = 0usize;
-	while i < data.len() {
-		if data[i] == ESC
-			&& let Some(seq_len) = ansi_seq_len_u16(data, i)
-		{
-			let seq = &data[i..i + seq_len];
-			if is_sgr_u16(seq) {
-				state.apply_sgr_u16(&seq[2..seq_len - 1]);
-			}
-			i += seq_len;
-			continue;
-		}
-		i += 1;
-	}
+/// A wrapped line with its bidi runs in logical order.
+#[napi(object)]
+pub struct BidiLine {
+	/// UTF-16 line content (ANSI codes preserved).
+	pub text:      Utf16String,
+	/// Paragraph direction, "ltr" or "rtl".
+	pub direction: String,
+	/// Level runs covering the whole line; ANSI codes belong to the run of the
+	/// character that follows them.
+	pub runs:      Vec<BidiRun>,
 }
 
-fn token_is_whitespace(token: &[u16]) -> bool {
-	let mut i = 0usize;
-	while i < token.len() {
-		if token[i] == ESC
-			&& let Some(seq_len) = ansi_seq_len_u16(token, i)
-		{
-			i += seq_len;
-			continue;
-		}
-		if token[i] != b' ' as u16 {
-			return false;
-		}
-		i += 1;
-	}
-	true
+/// Bidi character types (UAX #9), reduced to the classes the resolver uses.
+///
+/// Explicit embeddings and isolates are treated as other neutrals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BidiClass {
+	/// Strong left-to-right.
+	L,
+	/// Strong right-to-left (Hebrew and similar scripts).
+	R,
+	/// Strong right-to-left Arabic letter.
+	Al,
+	/// European number.
+	En,
+	/// Arabic number.
+	An,
+	/// European separator.
+	Es,
+	/// European number terminator.
+	Et,
+	/// Common number separator.
+	Cs,
+	/// Nonspacing mark.
+	Nsm,
+	/// Whitespace.
+	Ws,
+	/// Other neutral.
+	On,
 }
 
-fn trim_end_spaces_in_place(line: &mut Vec<u16>) {
-	while let Some(&last) = line.last() {
-		if last == b' ' as u16 {
-			line.pop();
-		} else {
-			break;
-		}
+fn bidi_class(c: char) -> BidiClass {
+	match c {
+		'\u{200E}' => BidiClass::L,
+		'\u{200F}' => BidiClass::R,
+		'\u{061C}' => BidiClass::Al,
+		'0'..='9' | '\u{06F0}'..='\u{06F9}' => BidiClass::En,
+		'\u{0660}'..='\u{0669}' | '\u{066B}' | '\u{066C}' => BidiClass::An,
+		'+' | '-' => BidiClass::Es,
+		'#'
+		| '$'
+		| '%'
+		| '\u{00A2}'..='\u{00A5}'
+		| '\u{00B0}'
+		| '\u{066A}'
+		| '\u{2030}'
+		| '\u{20A0}'..='\u{20CF}' => BidiClass::Et,
+		',' | '.' | ':' | '/' | '\u{00A0}' => BidiClass::Cs,
+		'\u{0300}'..='\u{036F}'
+		| '\u{1AB0}'..='\u{1AFF}'
+		| '\u{1DC0}'..='\u{1DFF}'
+		| '\u{200D}'
+		| '\u{20D0}'..='\u{20FF}'
+		| '\u{FE00}'..='\u{FE0F}'
+		| '\u{FE20}'..='\u{FE2F}' => BidiClass::Nsm,
+		'\t' | ' ' | '\u{2000}'..='\u{200A}' | '\u{3000}' => BidiClass::Ws,
+		'\u{0590}'..='\u{05FF}'
+		| '\u{07C0}'..='\u{085F}'
+		| '\u{FB1D}'..='\u{FB4F}'
+		| '\u{10800}'..='\u{10FFF}'
+		| '\u{1E800}'..='\u{1EDFF}' => BidiClass::R,
+		'\u{0600}'..='\u{07BF}'
+		| '\u{0860}'..='\u{08FF}'
+		| '\u{FB50}'..='\u{FDFF}'
+		| '\u{FE70}'..='\u{FEFE}'
+		| '\u{1EE00}'..='\u{1EEFF}' => BidiClass::Al,
+		c if c.is_alphabetic() => BidiClass::L,
+		_ => BidiClass::On,
 	}
 }
 
-fn split_into_tokens_with_ansi(line: &[u16]) -> SmallVec<[Vec<u16>; 4]> {
-	let mut tokens = SmallVec::<[Vec<u16>; 4]>::new();
-	let mut current = Vec::<u16>::new();
-	let mut pending_ansi = SmallVec::<[u16; 32]>::new();
-	let mut in_whitespace = false;
+/// Decode the visible characters of a UTF-16 line, skipping ANSI sequences.
+///
+/// Returns each character's bidi class and UTF-16 start offset.
+fn bidi_chars_u16(line: &[u16]) -> Vec<(BidiClass, usize)> {
+	let mut chars = Vec::new();
 	let mut i = 0usize;
-
 	while i < line.len() {
 		if line[i] == ESC
 			&& let Some(seq_len) = ansi_seq_len_u16(line, i)
 		{
-			pending_ansi.extend_from_slice(&line[i..i + seq_len]);
 			i += seq_len;
 			continue;
 		}
+		let is_pair = (0xd800..0xdc00).contains(&line[i])
+			&& line
+				.get(i + 1)
+				.is_some_and(|u| (0xdc00..0xe000).contains(u));
+		let end = if is_pair { i + 2 } else { i + 1 };
+		let c = std::char::decode_utf16(line[i..end].iter().copied())
+			.next()
+			.and_then(std::result::Result::ok)
+			.unwrap_or('\u{FFFD}');
+		chars.push((bidi_class(c), i));
+		i = end;
+	}
+	chars
+}
 
-		let ch = line[i];
-		let char_is_space = ch == b' ' as u16;
-		if char_is_space != in_whitespace && !current.is_empty() {
-			tokens.push(current);
-			current = Vec::new();
-		}
+/// Paragraph embedding level from the first strong character (rules P2/P3).
+fn bidi_paragraph_level(paragraph: &[u16]) -> u8 {
+	bidi_chars_u16(paragraph)
+		.into_iter()
+		.find_map(|(class, _)| match class {
+			BidiClass::L => Some(0),
+			BidiClass::R | BidiClass::Al => Some(1),
+			_ => None,
+		})
+		.unwrap_or(0)
+}
 
-		if !pending_ansi.is_empty() {
-			current.extend_from_slice(&pending_ansi);
-			pending_ansi.clear();
+/// Resolve embedding levels for one line of a paragraph using the weak,
+/// neutral, and implicit rules of UAX #9 (explicit embeddings are ignored).
+///
+/// `last_strong` carries the last strong class seen earlier in the paragraph
+/// so numbers resolve the same way on every wrapped line; it is updated for
+/// the next line.
+fn resolve_bidi_levels(
+	classes: &mut [BidiClass],
+	para_level: u8,
+	last_strong: &mut BidiClass,
+) -> Vec<u8> {
+	use BidiClass::{Al, An, Cs, En, Es, Et, L, Nsm, On, R, Ws};
+
+	let embedding = if para_level % 2 == 1 { R } else { L };
+	let trailing_ws = classes
+		.iter()
+		.rev()
+		.take_while(|class| **class == Ws)
+		.count();
+
+	// W1: nonspacing marks take the type of the preceding character.
+	let mut prev = embedding;
+	for class in classes.iter_mut() {
+		if *class == Nsm {
+			*class = prev;
 		}
-
-		in_whitespace = char_is_space;
-		current.push(ch);
-		i += 1;
+		prev = *class;
 	}
 
-	if !pending_ansi.is_empty() {
-		current.extend_from_slice(&pending_ansi);
+	// W2/W3: European numbers after Arabic letters become Arabic numbers, then
+	// Arabic letters become R.
+	let prior_strong = if *last_strong == Al { R } else { *last_strong };
+	let mut strong = *last_strong;
+	for class in classes.iter_mut() {
+		match *class {
+			L | R | Al => strong = *class,
+			En if strong == Al => *class = An,
+			_ => {},
+		}
+		if *class == Al {
+			*class = R;
+		}
 	}
-
-	if !current.is_empty() {
-		tokens.push(current);
+	*last_strong = strong;
+
+	// W4: a single separator between two numbers of the same type joins them.
+	for i in 1..classes.len().saturating_sub(1) {
+		let (before, after) = (classes[i - 1], classes[i + 1]);
+		match classes[i] {
+			Es if before == En && after == En => classes[i] = En,
+			Cs if before == after && matches!(before, En | An) => classes[i] = before,
+			_ => {},
+		}
 	}
 
-	tokens
-}
-
-fn break_long_word(
-	word: &[u16],
-	width: usize,
-	tab_width: usize,
-	state: &mut AnsiState,
-) -> SmallVec<[Vec<u16>; 4]> {
-	let mut lines = SmallVec::<[Vec<u16>; 4]>::new();
-	let mut current_line = Vec::<u16>::new();
-	write_active_codes(state, &mut current_line);
-	let mut current_width = 0usize;
-	let mut i = 0usize;
-
-	while i < word.len() {
-		if word[i] == ESC
-			&& let Some(seq_len) = ansi_seq_len_u16(word, i)
-		{
-			let seq = &word[i..i + seq_len];
-			current_line.extend_from_slice(seq);
-			if is_sgr_u16(seq) {
-				state.apply_sgr_u16(&seq[2..seq_len - 1]);
-			}
-			i += seq_len;
+	// W5: terminators adjacent to European numbers become European numbers.
+	let mut i = 0;
+	while i < classes.len() {
+		if classes[i] != Et {
+			i += 1;
 			continue;
 		}
-
 		let start = i;
-		let mut is_ascii = true;
-		while i < word.len() && word[i] != ESC {
-			if word[i] > 0x7f {
-				is_ascii = false;
-			}
+		while i < classes.len() && classes[i] == Et {
 			i += 1;
 		}
-		let seg = &word[start..i];
-
-		if is_ascii {
-			for &u in seg {
-				let gw = ascii_cell_width_u16(u, tab_width);
-				if current_width + gw > width {
-					write_line_end_reset(state, &mut current_line);
-					lines.push(current_line);
-					current_line = Vec::new();
-					write_active_codes(state, &mut current_line);
-					current_width = 0;
-				}
-				current_line.push(u);
-				current_width += gw;
-			}
-		} else {
-			let _ = for_each_grapheme_u16_slow(seg, tab_width, |gu16, gw| {
-				if current_width + gw > width {
-					write_line_end_reset(state, &mut current_line);
-					lines.push(std::mem::take(&mut current_line));
-					write_active_codes(state, &mut current_line);
-					current_width = 0;
-				}
-				current_line.extend_from_slice(gu16);
-				current_width += gw;
-				true
-			});
+		if (start > 0 && classes[start - 1] == En) || classes.get(i) == Some(&En) {
+			classes[start..i].fill(En);
 		}
 	}
 
-	if !current_line.is_empty() {
-		lines.push(current_line);
-	}
-
-	lines
-}
-
-fn wrap_single_line(line: &[u16], width: usize, tab_width: usize) -> SmallVec<[Vec<u16>; 4]> {
-	if line.is_empty() {
-		return smallvec![Vec::new()];
-	}
-
-	if visible_width_u16(line, tab_width) <= width {
-		return smallvec![line.to_vec()];
+	// W6: leftover separators and terminators are neutral. W7: European
+	// numbers after L resolve to L.
+	let mut strong = prior_strong;
+	for class in classes.iter_mut() {
+		match *class {
+			Es | Et | Cs => *class = On,
+			L | R => strong = *class,
+			En if strong == L => *class = L,
+			_ => {},
+		}
 	}
 
-	let tokens = split_into_tokens_with_ansi(line);
-	let mut wrapped = SmallVec::<[Vec<u16>; 4]>::new();
-	let mut current_line = Vec::<u16>::new();
-	let mut current_width = 0usize;
-	let mut state = AnsiState::new();
-
-	for token in tokens {
-		let token_width = visible_width_u16(&token, tab_width);
-		let is_whitespace = token_is_whitespace(&token);
-
-		if token_width > width && !is_whitespace {
-			if !current_line.is_empty() {
-				write_line_end_reset(&state, &mut current_line);
-				wrapped.push(current_line);
-				current_line = Vec::new();
-				current_width = 0;
-			}
-
-			let mut broken = break_long_word(&token, width, tab_width, &mut state);
-			if let Some(last) = broken.pop() {
-				wrapped.extend(broken);
-				current_line = last;
-				current_width = visible_width_u16(&current_line, tab_width);
-			}
+	// N1/N2: neutral runs take the surrounding direction when both sides
+	// agree (numbers count as R), else the embedding direction.
+	let strong_direction = |class: BidiClass| match class {
+		L => Some(L),
+		R | En | An => Some(R),
+		_ => None,
+	};
+	let mut i = 0;
+	while i < classes.len() {
+		if strong_direction(classes[i]).is_some() {
+			i += 1;
 			continue;
 		}
-
-		let total_needed = current_width + token_width;
-		if total_needed > width && current_width > 0 {
-			let mut line_to_wrap = current_line;
-			trim_end_spaces_in_place(&mut line_to_wrap);
-			write_line_end_reset(&state, &mut line_to_wrap);
-			wrapped.push(line_to_wrap);
-
-			current_line = Vec::new();
-			write_active_codes(&state, &mut current_line);
-			if is_whitespace {
-				current_width = 0;
-			} else {
-				current_line.extend_from_slice(&token);
-				current_width = token_width;
-			}
-		} else {
-			current_line.extend_from_slice(&token);
-			current_width += token_width;
+		let start = i;
+		while i < classes.len() && strong_direction(classes[i]).is_none() {
+			i += 1;
 		}
-
-		update_state_from_text(&token, &mut state);
+		let before = start
+			.checked_sub(1)
+			.and_then(|prev| strong_direction(classes[prev]))
+			.unwrap_or(embedding);
+		let after = classes
+			.get(i)
+			.and_then(|class| strong_direction(*class))
+			.unwrap_or(embedding);
+		classes[start..i].fill(if before == after { before } else { embedding });
 	}
 
-	if !current_line.is_empty() {
-		wrapped.push(current_line);
-	}
+	// I1/I2: implicit levels.
+	let odd = para_level % 2 == 1;
+	let mut levels: Vec<u8> = classes
+		.iter()
+		.map(|class| match (odd, class) {
+			(false, R) | (true, L | En | An) => para_level + 1,
+			(false, En | An) => para_level + 2,
+			_ => para_level,
+		})
+		.collect();
+
+	// L1: trailing whitespace takes the paragraph level.
+	let len = levels.len();
+	levels[len - trailing_ws..].fill(para_level);
+	levels
+}
 
-	for line in &mut wrapped {
-		trim_end_spaces_in_place(line);
-	}
+const fn bidi_direction(level: u8) -> &'static str {
+	if level % 2 == 1 { "rtl" } else { "ltr" }
+}
 
-	if wrapped.is_empty() {
-		wrapped.push(Vec::new());
+/// Split a wrapped line into level runs.
+fn bidi_runs_u16(line: &[u16], para_level: u8, last_strong: &mut BidiClass) -> Vec<BidiRun> {
+	let chars = bidi_chars_u16(line);
+	if chars.is_empty() {
+		return Vec::new();
 	}
-
-	wrapped
+	let mut classes: Vec<BidiClass> = chars.iter().map(|(class, _)| *class).collect();
+	let levels = resolve_bidi_levels(&mut classes, para_level, last_strong);
+
+	let make_run = |start: usize, end: usize, level: u8| BidiRun {
+		start:     start as u32,
+		end:       end as u32,
+		level:     u32::from(level),
+		direction: bidi_direction(level).to_string(),
+	};
+	let mut runs = Vec::new();
+	let mut run_start = 0usize;
+	for i in 1..chars.len() {
+		if levels[i] != levels[i - 1] {
+			runs.push(make_run(run_start, chars[i].1, levels[i - 1]));
+			run_start = chars[i].1;
+		}
+	}
+	runs.push(make_run(run_start, line.len(), levels[levels.len() - 1]));
+	runs
 }
 
-fn wrap_text_with_ansi_impl(
+fn wrap_text_with_bidi_impl(
 	text: &[u16],
 	width: usize,
 	tab_width: usize,
-) -> SmallVec<[Vec<u16>; 4]> {
-	if text.is_empty() {
-		return smallvec![Vec::new()];
-	}
-
-	let mut result = SmallVec::<[Vec<u16>; 4]>::new();
-	let mut state = AnsiState::new();
-	let mut line_start = 0usize;
-
-	for i in 0..=text.len() {
-		if i == text.len() || text[i] == b'\n' as u16 {
-			let line = &text[line_start..i];
-			let mut line_with_prefix: Vec<u16> = Vec::new();
-			if !result.is_empty() {
-				write_active_codes(&state, &mut line_with_prefix);
-			}
-			line_with_prefix.extend_from_slice(line);
-
-			let wrapped = wrap_single_line(&line_with_prefix, width, tab_width);
-			result.extend(wrapped);
-			update_state_from_text(line, &mut state);
-			line_start = i + 1;
+	forced_level: Option<u8>,
+) -> Vec<(Vec<u16>, u8, Vec<BidiRun>)> {
+	let mut result = Vec::new();
+	wrap_paragraphs(text, width, tab_width, |paragraph, wrapped| {
+		let para_level = forced_level.unwrap_or_else(|| bidi_paragraph_level(paragraph));
+		let mut last_strong = if para_level % 2 == 1 {
+			BidiClass::R
+		} else {
+			BidiClass::L
+		};
+		for line in wrapped {
+			let runs = bidi_runs_u16(&line, para_level, &mut last_strong);
+			result.push((line, para_level, runs));
 		}
-	}
-
-	if result.is_empty() {
-		result.push(Vec::new());
-	}
-
+	});
 	result
 }
 
-/// Wrap text to a visible width, preserving ANSI escape codes across line
-/// breaks.
+/// Wrap text like [`wrap_text_with_ansi`] and segment each line into bidi
+/// level runs.
 ///
-/// Returns UTF-16 lines with active SGR codes carried across line boundaries.
-#[napi(js_name = "wrapTextWithAnsi")]
-pub fn wrap_text_with_ansi(
+/// `direction` is "auto" (default; from the first strong character of each
+/// paragraph), "ltr", or "rtl". Runs are in logical order so callers can apply
+/// per-line visual reordering.
+#[napi(js_name = "wrapTextWithBidi")]
+pub fn wrap_text_with_bidi(
 	text: JsString,
 	width: u32,
 	tab_width: Option<u32>,
-) -> Result<Vec<Utf16String>> {
+	direction: Option<String>,
+) -> Result<Vec<BidiLine>> {
+	let forced_level = match direction.as_deref() {
+		None | Some("auto") => None,
+		Some("ltr") => Some(0),
+		Some("rtl") => Some(1),
+		Some(other) => {
+			return Err(Error::from_reason(format!(
+				"Invalid bidi direction: {other} (expected auto, ltr, or rtl)"
+			)));
+		},
+	};
 	let text_u16 = text.into_utf16()?;
 	let tab_width = clamp_tab_width(tab_width);
-	let lines = wrap_text_with_ansi_impl(text_u16.as_slice(), width as usize, tab_width);
-	Ok(lines.into_iter().map(build_utf16_string).collect())
+	let lines =
+		wrap_text_with_bidi_impl(text_u16.as_slice(), width as usize, tab_width, forced_level);
+	Ok(lines
+		.into_iter()
+		.map(|(line, para_level, runs)| BidiLine {
+			text: build_utf16_string(line),
+			direction: bidi_direction(para_level).to_string(),
+			runs,
+		})
+		.collect())
 }
 
-// ============================================================================
-// truncateToWidth
-// ============================================================================
-
 /// Truncate text to a visible width, preserving ANSI codes.
 ///
 /// `ellipsis_kind`: 0 = "…", 1 = "...", 2 = "" (omit); pads with spaces when
@@ -815,261 +683,340 @@ pub fn truncate_to_width(
 	pad: bool,
 	tab_width: Option<u32>,
 ) -> Result<Either<JsString<'_>, Utf16String>> {
-	let max_width = max_width as usize;
-	let tab_width = clamp_tab_width(tab_width);
-
 	// Keep original handle so we can return it without allocating.
 	let original = text;
-
 	let text_u16 = text.into_utf16()?;
-	let text = text_u16.as_slice();
-
-	// Fast path: early-exit width check
-	let (text_w, exceeded) = visible_width_u16_up_to(text, max_width, tab_width);
-	if !exceeded {
-		if !pad {
-			// Return original JsString handle: zero output allocation.
-			return Ok(Either::A(original));
-		}
-
-		if text_w < max_width {
-			let mut out = Vec::with_capacity(text.len() + (max_width - text_w));
-			out.extend_from_slice(text);
-			out.resize(out.len() + (max_width - text_w), b' ' as u16);
-			return Ok(Either::B(build_utf16_string(out)));
-		}
-
-		// Exactly fits and padding requested: return original is still fine.
-		return Ok(Either::A(original));
-	}
+	let truncated = truncate_to_width_impl(
+		text_u16.as_slice(),
+		max_width as usize,
+		ellipsis_kind,
+		pad,
+		clamp_tab_width(tab_width),
+	);
+	Ok(truncated.map_or(Either::A(original), |out| Either::B(build_utf16_string(out))))
+}
 
-	// Map ellipsis kind to UTF-16 data and width
-	const ELLIPSIS_UNICODE: &[u16] = &[0x2026]; // "…"
-	const ELLIPSIS_ASCII: &[u16] = &[0x2e, 0x2e, 0x2e]; // "..."
-	const ELLIPSIS_OMIT: &[u16] = &[];
+/// Truncate many texts to a visible width in one call.
+///
+/// Equivalent to calling `truncateToWidth` per text, without the per-call
+/// N-API overhead. Texts that fit are returned unchanged.
+#[napi(js_name = "truncateToWidthBatch")]
+pub fn truncate_to_width_batch(
+	texts: Vec<JsString<'_>>,
+	max_width: u32,
+	ellipsis_kind: u8,
+	pad: bool,
+	tab_width: Option<u32>,
+) -> Result<Vec<Either<JsString<'_>, Utf16String>>> {
+	let tab_width = clamp_tab_width(tab_width);
+	texts
+		.into_iter()
+		.map(|text| {
+			let text_u16 = text.into_utf16()?;
+			let truncated = truncate_to_width_impl(
+				text_u16.as_slice(),
+				max_width as usize,
+				ellipsis_kind,
+				pad,
+				tab_width,
+			);
+			Ok(truncated.map_or(Either::A(text), |out| Either::B(build_utf16_string(out))))
+		})
+		.collect()
+}
 
-	let (ellipsis, ellipsis_w): (&[u16], usize) = match ellipsis_kind {
-		0 => (ELLIPSIS_UNICODE, 1),
-		1 => (ELLIPSIS_ASCII, 3),
-		2 => (ELLIPSIS_OMIT, 0),
-		_ => (ELLIPSIS_UNICODE, 1), // Default to Unicode for invalid values
-	};
+// ============================================================================
+// fitSegments
+// ============================================================================
 
-	let target_w = max_width.saturating_sub(ellipsis_w);
+/// Options for `fitSegments`.
+#[napi(object)]
+#[derive(Default)]
+pub struct FitSegmentsOptions {
+	/// Text placed between segments (default: "/").
+	pub separator:       Option<String>,
+	/// Width no segment is truncated below, unless it is narrower already
+	/// (default: 1).
+	#[napi(js_name = "minPerSegment")]
+	pub min_per_segment: Option<u32>,
+	/// Width used when measuring tab characters.
+	#[napi(js_name = "tabWidth")]
+	pub tab_width:       Option<u32>,
+}
 
-	// If ellipsis alone doesn't fit, return ellipsis cut to max_width
-	if target_w == 0 {
-		let mut out = Vec::with_capacity(ellipsis.len().min(max_width * 2));
-		let mut w = 0usize;
-		let _ = for_each_grapheme_u16_slow(ellipsis, tab_width, |gu16, gw| {
-			if w + gw > max_width {
-				return false;
-			}
-			out.extend_from_slice(gu16);
-			w += gw;
-			true
-		});
+/// Segments fitted to a total width.
+#[napi(object)]
+pub struct FitSegmentsResult {
+	/// Fitted segments joined by the separator.
+	pub text:     Utf16String,
+	/// Each segment after fitting, in input order.
+	pub segments: Vec<Utf16String>,
+	/// Visible width of `text`.
+	pub width:    u32,
+}
 
-		if pad && w < max_width {
-			out.resize(out.len() + (max_width - w), b' ' as u16);
-		}
-		return Ok(Either::B(build_utf16_string(out)));
+/// Shorten `segment` (visible width `width`) to at most `target` cells by
+/// replacing its middle with "…". Styles are reset before the ellipsis and the
+/// tail re-applies the styles active where it starts. Returns the text and its
+/// width, which is short of `target` when a wide grapheme straddles a cut.
+fn middle_truncate_u16(
+	segment: &[u16],
+	width: usize,
+	target: usize,
+	tab_width: usize,
+) -> (Vec<u16>, usize) {
+	const ELLIPSIS: u16 = 0x2026;
+	if target == 0 {
+		return (Vec::new(), 0);
 	}
+	let kept = target - 1;
+	let (_, head_w) = slice_with_width_impl(segment, 0, kept.div_ceil(2), true, tab_width);
+	let tail_len = kept - head_w;
+	let (tail, tail_w) = slice_with_width_impl(segment, width - tail_len, tail_len, true, tab_width);
+	// Cells the tail could not use go back to the head.
+	let (head, head_w) = slice_with_width_impl(segment, 0, kept - tail_w, true, tab_width);
+	let mut out = Vec::with_capacity(head.len() + tail.len() + 5);
+	const RESET: &[u16] = &[ESC, b'[' as u16, b'0' as u16, b'm' as u16];
+	out.extend_from_slice(&head);
+	if segment.contains(&ESC) && !head.ends_with(RESET) {
+		out.extend_from_slice(RESET);
+	}
+	out.push(ELLIPSIS);
+	out.extend_from_slice(&tail);
+	(out, head_w + 1 + tail_w)
+}
 
-	// Main truncation
-	let mut out = Vec::with_capacity(text.len().min(max_width * 2) + ellipsis.len() + 8);
-	let mut w = 0usize;
-	let mut i = 0usize;
-	let text_len = text.len();
-
-	let mut saw_sgr = false;
-
-	while i < text_len {
-		if text[i] == ESC {
-			if let Some(seq_len) = ansi_seq_len_u16(text, i) {
-				let seq = &text[i..i + seq_len];
-				out.extend_from_slice(seq);
-				if is_sgr_u16(seq) {
-					saw_sgr = true;
-				}
-				i += seq_len;
-				continue;
-			}
-			out.push(ESC);
-			i += 1;
-			continue;
-		}
-
-		let start = i;
-		let mut is_ascii = true;
-		while i < text_len && text[i] != ESC {
-			if text[i] > 0x7f {
-				is_ascii = false;
-			}
-			i += 1;
-		}
-		let seg = &text[start..i];
-
-		if is_ascii {
-			for &u in seg {
-				let gw = ascii_cell_width_u16(u, tab_width);
-				if w + gw > target_w {
-					break;
-				}
-				out.push(u);
-				w += gw;
-			}
-			if w >= target_w {
-				break;
-			}
+/// Visible width each segment gets: the widest segments shrink first, down to
+/// `min_per_segment`, and cells left over after levelling go to the leftmost
+/// shrunk segments.
+fn fit_segment_widths(widths: &[usize], available: usize, min_per_segment: usize) -> Vec<usize> {
+	let floor = |width: usize| width.min(min_per_segment);
+	let total_at = |cap: usize| -> usize { widths.iter().map(|&w| w.min(cap.max(floor(w)))).sum() };
+	if widths.iter().sum::<usize>() <= available {
+		return widths.to_vec();
+	}
+	// Largest cap whose total fits; total_at is monotonic in cap.
+	let (mut low, mut high) = (0usize, widths.iter().copied().max().unwrap_or(0));
+	while low < high {
+		let mid = (low + high).div_ceil(2);
+		if total_at(mid) <= available {
+			low = mid;
 		} else {
-			let keep_going = for_each_grapheme_u16_slow(seg, tab_width, |gu16, gw| {
-				if w + gw > target_w {
-					return false;
-				}
-				out.extend_from_slice(gu16);
-				w += gw;
-				true
-			});
-			if !keep_going {
-				break;
-			}
+			high = mid - 1;
 		}
 	}
-
-	// Only reset if we actually copied SGR codes into the output.
-	if saw_sgr {
-		out.extend_from_slice(&[ESC, b'[' as u16, b'0' as u16, b'm' as u16]);
-	}
-	out.extend_from_slice(ellipsis);
-
-	if pad {
-		let out_w = w + ellipsis_w;
-		if out_w < max_width {
-			out.resize(out.len() + (max_width - out_w), b' ' as u16);
+	let mut fitted: Vec<usize> = widths.iter().map(|&w| w.min(low.max(floor(w)))).collect();
+	let mut spare = available.saturating_sub(fitted.iter().sum());
+	for (fit, &width) in fitted.iter_mut().zip(widths) {
+		if spare == 0 {
+			break;
+		}
+		if *fit < width && *fit == low {
+			*fit += 1;
+			spare -= 1;
 		}
 	}
-
-	Ok(Either::B(build_utf16_string(out)))
+	fitted
 }
 
-// ============================================================================
-// sliceWithWidth
-// ============================================================================
-
-fn slice_with_width_impl(
-	line: &[u16],
-	start_col: usize,
-	length: usize,
-	strict: bool,
+fn fit_segments_impl(
+	segments: &[Vec<u16>],
+	total_width: usize,
+	separator: &[u16],
+	min_per_segment: usize,
 	tab_width: usize,
-) -> (Vec<u16>, usize) {
-	let end_col = start_col.saturating_add(length);
-
-	let mut out = Vec::with_capacity(length * 2);
-	let mut out_w = 0usize;
-
-	let mut current_col = 0usize;
-	let mut i = 0usize;
-	let line_len = line.len();
-
-	// Store pending ANSI ranges (pos, len) to avoid copying until needed
-	let mut pending_ansi: SmallVec<[(usize, usize); 4]> = SmallVec::new();
-
-	while i < line_len && current_col < end_col {
-		if line[i] == ESC {
-			if let Some(seq_len) = ansi_seq_len_u16(line, i) {
-				if current_col >= start_col {
-					out.extend_from_slice(&line[i..i + seq_len]);
-				} else {
-					pending_ansi.push((i, seq_len));
-				}
-				i += seq_len;
-				continue;
-			}
-			if current_col >= start_col {
-				out.push(ESC);
-			}
-			i += 1;
-			continue;
-		}
-
-		let start = i;
-		let mut is_ascii = true;
-		while i < line_len && line[i] != ESC {
-			if line[i] > 0x7f {
-				is_ascii = false;
-			}
-			i += 1;
-		}
-		let seg = &line[start..i];
-
-		if is_ascii {
-			for &u in seg {
-				if current_col >= end_col {
-					break;
-				}
-				let gw = ascii_cell_width_u16(u, tab_width);
-				let in_range = current_col >= start_col;
-				let fits = !strict || current_col + gw <= end_col;
-
-				if in_range && fits {
-					if !pending_ansi.is_empty() {
-						for &(p, l) in &pending_ansi {
-							out.extend_from_slice(&line[p..p + l]);
-						}
-						pending_ansi.clear();
-					}
-					out.push(u);
-					out_w += gw;
-				}
-				current_col += gw;
-			}
-		} else {
-			let _ = for_each_grapheme_u16_slow(seg, tab_width, |gu16, gw| {
-				if current_col >= end_col {
-					return false;
-				}
-
-				let in_range = current_col >= start_col;
-				let fits = !strict || current_col + gw <= end_col;
-
-				if in_range && fits {
-					if !pending_ansi.is_empty() {
-						for &(p, l) in &pending_ansi {
-							out.extend_from_slice(&line[p..p + l]);
-						}
-						pending_ansi.clear();
-					}
-					out.extend_from_slice(gu16);
-					out_w += gw;
-				}
+) -> (Vec<Vec<u16>>, usize) {
+	let widths: Vec<usize> = segments
+		.iter()
+		.map(|segment| visible_width_u16(segment, tab_width))
+		.collect();
+	let separators = visible_width_u16(separator, tab_width) * segments.len().saturating_sub(1);
+	let available = total_width.saturating_sub(separators);
+	let fitted = fit_segment_widths(&widths, available, min_per_segment);
+	let mut width = separators;
+	let out = segments
+		.iter()
+		.zip(widths.iter().zip(&fitted))
+		.map(|(segment, (&full, &target))| {
+			let (text, text_w) = if target >= full {
+				(segment.clone(), full)
+			} else {
+				middle_truncate_u16(segment, full, target, tab_width)
+			};
+			width += text_w;
+			text
+		})
+		.collect();
+	(out, width)
+}
 
-				current_col += gw;
-				current_col < end_col
-			});
+/// Fit breadcrumb segments into `total_width` cells, separator included.
+///
+/// The widest segments are middle-truncated first ("src/comp…ents"), never
+/// below `minPerSegment`; when even that does not fit, the result is wider
+/// than `total_width`. Widths are grapheme-aware and ignore ANSI codes.
+#[napi(js_name = "fitSegments")]
+pub fn fit_segments(
+	segments: Vec<String>,
+	total_width: u32,
+	options: Option<FitSegmentsOptions>,
+) -> FitSegmentsResult {
+	let options = options.unwrap_or_default();
+	let separator: Vec<u16> = options
+		.separator
+		.as_deref()
+		.unwrap_or("/")
+		.encode_utf16()
+		.collect();
+	let segments: Vec<Vec<u16>> = segments
+		.iter()
+		.map(|segment| segment.encode_utf16().collect())
+		.collect();
+	let (fitted, width) = fit_segments_impl(
+		&segments,
+		total_width as usize,
+		&separator,
+		options.min_per_segment.unwrap_or(1) as usize,
+		clamp_tab_width(options.tab_width),
+	);
+	let mut text = Vec::new();
+	for (index, segment) in fitted.iter().enumerate() {
+		if index > 0 {
+			text.extend_from_slice(&separator);
 		}
+		text.extend_from_slice(segment);
+	}
+	FitSegmentsResult {
+		text:     build_utf16_string(text),
+		segments: fitted.into_iter().map(build_utf16_string).collect(),
+		width:    crate::utils::clamp_u32(width as u64),
 	}
+}
 
-	// Include trailing ANSI sequences (e.g., reset codes) that immediately follow
-	while i < line.len() {
-		if line[i] == ESC
-			&& let Some(len) = ansi_seq_len_u16(line, i)
-		{
-			out.extend_from_slice(&line[i..i + len]);
-			i += len;
-			continue;
-		}
-		break;
+// ============================================================================
+// padToWidth / columnize
+// ============================================================================
+
+#[derive(Clone, Copy)]
+enum Align {
+	Left,
+	Right,
+	Center,
+}
+
+fn parse_align(align: Option<&str>) -> Result<Align> {
+	match align {
+		None | Some("left") => Ok(Align::Left),
+		Some("right") => Ok(Align::Right),
+		Some("center") => Ok(Align::Center),
+		Some(other) => Err(Error::from_reason(format!(
+			"Invalid alignment: {other} (expected left, right, or center)"
+		))),
 	}
+}
+
+/// `text` fitted to exactly `width` cells: truncated with "…" when wider,
+/// otherwise padded with spaces on the side(s) given by `align`. Centering
+/// puts the odd cell on the right.
+fn pad_to_width_impl(text: &[u16], width: usize, align: Align, tab_width: usize) -> Vec<u16> {
+	let truncated = truncate_to_width_impl(text, width, 0, false, tab_width);
+	let text = truncated.as_deref().unwrap_or(text);
+	let spare = width.saturating_sub(visible_width_u16(text, tab_width));
+	let left = match align {
+		Align::Left => 0,
+		Align::Right => spare,
+		Align::Center => spare / 2,
+	};
+	let mut out = Vec::with_capacity(text.len() + spare);
+	out.resize(left, b' ' as u16);
+	out.extend_from_slice(text);
+	out.resize(out.len() + spare - left, b' ' as u16);
+	out
+}
+
+/// Pad or truncate text to exactly `width` visible cells.
+///
+/// `align` is "left" (default), "right", or "center". Text wider than `width`
+/// is truncated with "…". Widths are grapheme-aware and ignore ANSI codes.
+#[napi(js_name = "padToWidth")]
+pub fn pad_to_width(
+	text: JsString,
+	width: u32,
+	#[napi(ts_arg_type = "\"left\" | \"right\" | \"center\"")] align: Option<String>,
+	tab_width: Option<u32>,
+) -> Result<Utf16String> {
+	let align = parse_align(align.as_deref())?;
+	let text_u16 = text.into_utf16()?;
+	Ok(build_utf16_string(pad_to_width_impl(
+		text_u16.as_slice(),
+		width as usize,
+		align,
+		clamp_tab_width(tab_width),
+	)))
+}
 
-	(out, out_w)
+/// Options for `columnize`.
+#[napi(object)]
+#[derive(Default)]
+pub struct ColumnizeOptions {
+	/// Text placed between columns (default: " ").
+	pub separator: Option<String>,
+	/// Alignment of each column: "left" (default), "right", or "center".
+	#[napi(ts_type = "Array<\"left\" | \"right\" | \"center\">")]
+	pub align:     Option<Vec<String>>,
+	/// Width used when measuring tab characters.
+	#[napi(js_name = "tabWidth")]
+	pub tab_width: Option<u32>,
+}
+
+/// Lay out table rows as lines with each cell padded or truncated to its
+/// column width.
+///
+/// Missing cells are blank; cells past the last entry of `widths` are dropped.
+/// Widths are grapheme-aware and ignore ANSI codes.
+#[napi]
+pub fn columnize(
+	rows: Vec<Vec<String>>,
+	widths: Vec<u32>,
+	options: Option<ColumnizeOptions>,
+) -> Result<Vec<Utf16String>> {
+	let options = options.unwrap_or_default();
+	let separator: Vec<u16> = options
+		.separator
+		.as_deref()
+		.unwrap_or(" ")
+		.encode_utf16()
+		.collect();
+	let aligns = options
+		.align
+		.unwrap_or_default()
+		.iter()
+		.map(|align| parse_align(Some(align)))
+		.collect::<Result<Vec<_>>>()?;
+	let tab_width = clamp_tab_width(options.tab_width);
+	Ok(rows
+		.iter()
+		.map(|row| {
+			let mut line = Vec::new();
+			for (column, &width) in widths.iter().enumerate() {
+				if column > 0 {
+					line.extend_from_slice(&separator);
+				}
+				let cell: Vec<u16> = row
+					.get(column)
+					.map_or_else(Vec::new, |cell| cell.encode_utf16().collect());
+				let align = aligns.get(column).copied().unwrap_or(Align::Left);
+				line.extend(pad_to_width_impl(&cell, width as usize, align, tab_width));
+			}
+			build_utf16_string(line)
+		})
+		.collect())
 }
 
 /// Slice a range of visible columns from a line.
 ///
 /// Counts terminal cells, skipping ANSI escapes, and optionally enforces strict
-/// width.
+/// width. With `tab_stops`, tabs are first expanded to the next tab stop.
 #[napi(js_name = "sliceWithWidth")]
 pub fn slice_with_width(
 	line: JsString,
@@ -1077,13 +1024,14 @@ pub fn slice_with_width(
 	length: u32,
 	strict: bool,
 	tab_width: Option<u32>,
+	tab_stops: Option<bool>,
 ) -> Result<SliceResult> {
 	let line_u16 = line.into_utf16()?;
-	let line = line_u16.as_slice();
-
 	let tab_width = clamp_tab_width(tab_width);
+	let line = apply_tab_stops(line_u16.as_slice(), tab_stops, tab_width);
+
 	let (out, w) =
-		slice_with_width_impl(line, start_col as usize, length as usize, strict, tab_width);
+		slice_with_width_impl(&line, start_col as usize, length as usize, strict, tab_width);
 
 	Ok(SliceResult { text: build_utf16_string(out), width: crate::utils::clamp_u32(w as u64) })
 }
@@ -1343,22 +1291,595 @@ pub fn sanitize_text(text: JsString<'_>) -> Result<Either<JsString<'_>, Utf16Str
 	Ok(Either::B(build_utf16_string(out)))
 }
 
+// ============================================================================
+// StripAnsiStream
+// ============================================================================
+
+/// Longest unterminated escape sequence held back between chunks; anything
+/// longer is treated as garbage and only its introducer is dropped.
+const MAX_PENDING_ESCAPE: usize = 4096;
+
+/// Whether the ESC at `pos` starts a sequence that more input could complete
+/// (given that `ansi_seq_len_u16` found no complete one).
+fn is_partial_escape_u16(data: &[u16], pos: usize) -> bool {
+	data
+		.get(pos + 1)
+		.is_none_or(|next| matches!(next, 0x5b | 0x5d | 0x50 | 0x58 | 0x5e | 0x5f | 0x20..=0x2f))
+}
+
+/// Strip escape sequences from `data`. Unless `at_end`, stops at a trailing
+/// partial sequence and returns its offset so it can be carried over.
+fn strip_ansi_chunk_u16(data: &[u16], at_end: bool) -> (Vec<u16>, usize) {
+	let mut out = Vec::with_capacity(data.len());
+	let mut last = 0usize;
+	let mut i = 0usize;
+	while i < data.len() {
+		if data[i] != ESC {
+			i += 1;
+			continue;
+		}
+		out.extend_from_slice(&data[last..i]);
+		if let Some(seq_len) = ansi_seq_len_u16(data, i) {
+			i += seq_len;
+		} else if is_partial_escape_u16(data, i) {
+			if at_end {
+				return (out, data.len());
+			}
+			if data.len() - i <= MAX_PENDING_ESCAPE {
+				return (out, i);
+			}
+			i += 2;
+		} else {
+			// Lone ESC.
+			i += 1;
+		}
+		last = i;
+	}
+	out.extend_from_slice(&data[last..]);
+	(out, data.len())
+}
+
+/// Incremental ANSI stripper for streamed output. Escape sequences split
+/// across chunks are held back until complete, so no fragments leak into the
+/// text.
+#[napi]
+#[derive(Default)]
+pub struct StripAnsiStream {
+	pending: Vec<u16>,
+}
+
+#[napi]
+impl StripAnsiStream {
+	/// Create a stream with nothing pending.
+	#[napi(constructor)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Strip ANSI/OSC sequences from the next chunk. A trailing partial
+	/// sequence is held back and completed by the following push.
+	#[napi]
+	pub fn push(&mut self, chunk: JsString<'_>) -> Result<Utf16String> {
+		let chunk = chunk.into_utf16()?;
+		let mut data = std::mem::take(&mut self.pending);
+		data.extend_from_slice(chunk.as_slice());
+		let (out, rest) = strip_ansi_chunk_u16(&data, false);
+		self.pending = data[rest..].to_vec();
+		Ok(build_utf16_string(out))
+	}
+
+	/// End the stream, dropping any unterminated sequence. The stream can be
+	/// reused afterwards.
+	#[napi]
+	pub fn finish(&mut self) -> Utf16String {
+		let data = std::mem::take(&mut self.pending);
+		build_utf16_string(strip_ansi_chunk_u16(&data, true).0)
+	}
+}
+
+// ============================================================================
+// stripAnsi
+// ============================================================================
+
+/// ANSI-stripped text with the original index of each of its code units.
+#[napi(object)]
+pub struct StripAnsiResult {
+	/// Text with escape sequences removed.
+	pub text:    Utf16String,
+	/// Original UTF-16 index of each code unit of `text`. A match at
+	/// `start..end` of `text` covers `indices[start]..indices[end - 1] + 1` of
+	/// the original.
+	pub indices: Uint32Array,
+}
+
+/// Strip escape sequences (and stray ESC characters) from `data`, recording
+/// where each kept code unit came from.
+fn strip_ansi_mapped_u16(data: &[u16]) -> (Vec<u16>, Vec<u32>) {
+	let mut out = Vec::with_capacity(data.len());
+	let mut indices = Vec::with_capacity(data.len());
+	let mut i = 0usize;
+	while i < data.len() {
+		if data[i] == ESC {
+			i += ansi_seq_len_u16(data, i).unwrap_or(1);
+			continue;
+		}
+		out.push(data[i]);
+		indices.push(crate::utils::clamp_u32(i as u64));
+		i += 1;
+	}
+	(out, indices)
+}
+
+/// Strip ANSI escape sequences and map each remaining code unit back to its
+/// index in `text`, so hits found in the plain text can be located in the
+/// styled string.
+#[napi(js_name = "stripAnsi")]
+pub fn strip_ansi(text: JsString) -> Result<StripAnsiResult> {
+	let text_u16 = text.into_utf16()?;
+	let (out, indices) = strip_ansi_mapped_u16(text_u16.as_slice());
+	Ok(StripAnsiResult { text: build_utf16_string(out), indices: Uint32Array::new(indices) })
+}
+
+// ============================================================================
+// highlightMatches
+// ============================================================================
+
+/// Options for `highlightMatches`.
+#[napi(object)]
+pub struct HighlightMatchesOptions {
+	/// Highlight SGR, either a full sequence ("\x1b[43m") or its parameters
+	/// ("1;33"). Default: inverse video.
+	pub style:       Option<String>,
+	/// Case-insensitive matching.
+	#[napi(js_name = "ignoreCase")]
+	pub ignore_case: Option<bool>,
+}
+
+const DEFAULT_HIGHLIGHT_SGR: &str = "\x1b[7m";
+
+fn highlight_sgr_u16(style: Option<&str>) -> Vec<u16> {
+	match style {
+		None | Some("") => DEFAULT_HIGHLIGHT_SGR.encode_utf16().collect(),
+		Some(style) if style.starts_with('\x1b') => style.encode_utf16().collect(),
+		Some(params) => format!("\x1b[{params}m").encode_utf16().collect(),
+	}
+}
+
+/// Matched ranges of the visible (ANSI-stripped) text, as `start..end`
+/// offsets into `data`.
+///
+/// Matching runs on the visible text so a match may span style changes.
+fn visible_match_ranges_u16(
+	data: &[u16],
+	matcher: &crate::regex_engine::PatternMatcher,
+) -> Vec<(usize, usize)> {
+	use grep_matcher::Matcher;
+
+	let mut visible = String::new();
+	// (byte offset in `visible`, `data` range) per visible character.
+	let mut chars: Vec<(usize, usize, usize)> = Vec::new();
+	let mut i = 0usize;
+	while i < data.len() {
+		if data[i] == ESC
+			&& let Some(seq_len) = ansi_seq_len_u16(data, i)
+		{
+			i += seq_len;
+			continue;
+		}
+		let units = if (0xd800..0xdc00).contains(&data[i])
+			&& data
+				.get(i + 1)
+				.is_some_and(|next| (0xdc00..0xe000).contains(next))
+		{
+			2
+		} else {
+			1
+		};
+		let c = char::decode_utf16(data[i..i + units].iter().copied())
+			.next()
+			.and_then(std::result::Result::ok)
+			.unwrap_or(char::REPLACEMENT_CHARACTER);
+		chars.push((visible.len(), i, i + units));
+		visible.push(c);
+		i += units;
+	}
+
+	let char_at = |byte: usize| chars.partition_point(|(start, ..)| *start < byte);
+	let mut ranges = Vec::new();
+	let _ = matcher.find_iter(visible.as_bytes(), |m| {
+		if m.start() < m.end() {
+			// End just after the last matched character, before any ANSI that
+			// follows it.
+			let (_, start, _) = chars[char_at(m.start())];
+			let (_, _, end) = chars[char_at(m.end()) - 1];
+			ranges.push((start, end));
+		}
+		true
+	});
+	ranges
+}
+
+fn highlight_matches_impl(data: &[u16], ranges: &[(usize, usize)], style: &[u16]) -> Vec<u16> {
+	let mut out = Vec::with_capacity(data.len() + ranges.len() * (style.len() + 8));
+	let mut state = AnsiState::new();
+	let mut next = 0usize;
+	let mut in_match = false;
+	let mut i = 0usize;
+	loop {
+		if in_match && i >= ranges[next].1 {
+			out.extend_from_slice(&[ESC, b'[' as u16, b'0' as u16, b'm' as u16]);
+			state.write_restore_u16(&mut out);
+			in_match = false;
+			next += 1;
+		}
+		if !in_match && next < ranges.len() && i >= ranges[next].0 {
+			out.extend_from_slice(style);
+			in_match = true;
+		}
+		if i >= data.len() {
+			break;
+		}
+		if data[i] == ESC
+			&& let Some(seq_len) = ansi_seq_len_u16(data, i)
+		{
+			let seq = &data[i..i + seq_len];
+			out.extend_from_slice(seq);
+			if is_sgr_u16(seq) {
+				state.apply_sgr_u16(&seq[2..seq_len - 1]);
+				// Keep the highlight on top of style changes inside the match.
+				if in_match {
+					out.extend_from_slice(style);
+				}
+			}
+			i += seq_len;
+			continue;
+		}
+		out.push(data[i]);
+		i += 1;
+	}
+	out
+}
+
+/// Wrap regex matches in an already-styled string with a highlight SGR.
+///
+/// Matching runs on the visible text (ANSI codes skipped). After each match
+/// the styles active in the original string are restored, so surrounding
+/// colors and underlines survive.
+///
+/// # Errors
+/// Fails when the pattern is not a valid regex.
+#[napi(js_name = "highlightMatches")]
+pub fn highlight_matches(
+	text: JsString,
+	pattern: String,
+	options: Option<HighlightMatchesOptions>,
+) -> Result<Utf16String> {
+	let text_u16 = text.into_utf16()?;
+	let data = text_u16.as_slice();
+	let (style, ignore_case) = options
+		.map_or((None, false), |options| (options.style, options.ignore_case.unwrap_or(false)));
+	let matcher = crate::grep::build_matcher(
+		&pattern,
+		crate::regex_engine::RegexEngine::Default,
+		ignore_case,
+		false,
+	)?;
+	let ranges = visible_match_ranges_u16(data, &matcher);
+	if ranges.is_empty() {
+		return Ok(build_utf16_string(data.to_vec()));
+	}
+	let style = highlight_sgr_u16(style.as_deref());
+	Ok(build_utf16_string(highlight_matches_impl(data, &ranges, &style)))
+}
+
+// ============================================================================
+// highlightRanges
+// ============================================================================
+
+/// A range of visible columns, `start` inclusive and `end` exclusive.
+#[napi(object)]
+pub struct ColumnRange {
+	/// First column of the range.
+	pub start: u32,
+	/// Column just past the range.
+	pub end:   u32,
+}
+
+/// Convert visible-column ranges of `data` to sorted, disjoint `start..end`
+/// offsets. A grapheme belongs to a range when any of its cells does, so wide
+/// characters are never split.
+fn column_ranges_to_offsets(
+	data: &[u16],
+	ranges: &[(usize, usize)],
+	tab_width: usize,
+) -> Vec<(usize, usize)> {
+	// (column, width, data range) per grapheme.
+	let mut graphemes: Vec<(usize, usize, usize, usize)> = Vec::new();
+	let mut col = 0usize;
+	for_each_visible_grapheme_u16(data, tab_width, |pos, gu16, gw| {
+		graphemes.push((col, gw, pos, pos + gu16.len()));
+		col += gw;
+	});
+	let mut offsets: Vec<(usize, usize)> = ranges
+		.iter()
+		.filter(|(start, end)| start < end)
+		.filter_map(|&(start, end)| {
+			let hit =
+				|&&(col, gw, ..): &&(usize, usize, usize, usize)| col < end && col + gw.max(1) > start;
+			let &(.., first, _) = graphemes.iter().find(hit)?;
+			let &(.., last) = graphemes.iter().rfind(hit)?;
+			Some((first, last))
+		})
+		.collect();
+	offsets.sort_unstable();
+	let mut merged: Vec<(usize, usize)> = Vec::with_capacity(offsets.len());
+	for (start, end) in offsets {
+		match merged.last_mut() {
+			Some(prev) if start <= prev.1 => prev.1 = prev.1.max(end),
+			_ => merged.push((start, end)),
+		}
+	}
+	merged
+}
+
+/// Wrap visible-column ranges of an already-styled line with a highlight SGR.
+///
+/// `sgr` is a full sequence ("\x1b[43m") or its parameters ("1;33"); the
+/// default is inverse video. After each range the styles active in the
+/// original line are restored, so nested styles survive. Ranges may be
+/// unsorted or overlap.
+#[napi(js_name = "highlightRanges")]
+pub fn highlight_ranges(
+	line: JsString,
+	ranges: Vec<ColumnRange>,
+	sgr: Option<String>,
+	tab_width: Option<u32>,
+) -> Result<Utf16String> {
+	let line_u16 = line.into_utf16()?;
+	let data = line_u16.as_slice();
+	let ranges: Vec<(usize, usize)> = ranges
+		.iter()
+		.map(|range| (range.start as usize, range.end as usize))
+		.collect();
+	let offsets = column_ranges_to_offsets(data, &ranges, clamp_tab_width(tab_width));
+	if offsets.is_empty() {
+		return Ok(build_utf16_string(data.to_vec()));
+	}
+	let style = highlight_sgr_u16(sgr.as_deref());
+	Ok(build_utf16_string(highlight_matches_impl(data, &offsets, &style)))
+}
+
+// ============================================================================
+// graphemeInfo
+// ============================================================================
+
+/// A grapheme cluster with its position, width, and emoji classification.
+#[napi(object)]
+pub struct GraphemeCluster {
+	/// Start offset (UTF-16 code units, inclusive).
+	pub start:        u32,
+	/// End offset (UTF-16 code units, exclusive).
+	pub end:          u32,
+	/// Visible width in terminal cells, as used by every width function.
+	pub width:        u32,
+	/// Cluster renders as an emoji.
+	pub emoji:        bool,
+	/// Cluster joins several emoji with ZERO WIDTH JOINER (e.g. families).
+	#[napi(js_name = "zwjSequence")]
+	pub zwj_sequence: bool,
+	/// Cluster carries a skin tone modifier.
+	#[napi(js_name = "skinTone")]
+	pub skin_tone:    bool,
+	/// Cluster is a flag (regional indicator pair or tag sequence).
+	pub flag:         bool,
+}
+
+/// Unicode versions of the pinned segmentation and width tables.
+#[napi(object)]
+pub struct UnicodeVersionInfo {
+	/// Grapheme cluster segmentation tables (UAX #29).
+	pub segmentation: String,
+	/// East Asian width and emoji width tables.
+	pub width:        String,
+}
+
+const ZWJ: char = '\u{200D}';
+
+const fn is_regional_indicator(c: char) -> bool {
+	matches!(c, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+const fn is_skin_tone(c: char) -> bool {
+	matches!(c, '\u{1F3FB}'..='\u{1F3FF}')
+}
+
+const fn is_emoji_base(c: char) -> bool {
+	matches!(
+		c,
+		'\u{1F000}'..='\u{1FAFF}' | '\u{2600}'..='\u{27BF}' | '\u{2B00}'..='\u{2BFF}' | '\u{2300}'..='\u{23FF}'
+	)
+}
+
+fn classify_grapheme(g: &str, start: usize, end: usize, width: usize) -> GraphemeCluster {
+	let first = g.chars().next().unwrap_or_default();
+	let has_zwj = g.contains(ZWJ) && g.chars().count() > 1;
+	let skin_tone = g.chars().any(is_skin_tone);
+	let flag = (is_regional_indicator(first)
+		&& g.chars().filter(|c| is_regional_indicator(*c)).count() == 2)
+		|| (first == '\u{1F3F4}' && g.chars().any(|c| matches!(c, '\u{E0020}'..='\u{E007F}')));
+	let emoji = flag || skin_tone || is_emoji_base(first) || g.contains('\u{FE0F}');
+	GraphemeCluster {
+		start: start as u32,
+		end: end as u32,
+		width: width as u32,
+		emoji,
+		zwj_sequence: has_zwj && emoji,
+		skin_tone,
+		flag,
+	}
+}
+
+/// Call `f(offset, grapheme, width)` for each grapheme of `text`, skipping
+/// ANSI escape sequences.
+fn for_each_visible_grapheme_u16<F>(text: &[u16], tab_width: usize, mut f: F)
+where
+	F: FnMut(usize, &[u16], usize),
+{
+	let mut i = 0usize;
+	while i < text.len() {
+		if text[i] == ESC
+			&& let Some(seq_len) = ansi_seq_len_u16(text, i)
+		{
+			i += seq_len;
+			continue;
+		}
+		let start = i;
+		while i < text.len() && !(text[i] == ESC && ansi_seq_len_u16(text, i).is_some()) {
+			i += 1;
+		}
+		let mut pos = start;
+		let _ = for_each_grapheme_u16_slow(&text[start..i], tab_width, |gu16, gw| {
+			f(pos, gu16, gw);
+			pos += gu16.len();
+			true
+		});
+	}
+}
+
+fn grapheme_info_u16(text: &[u16], tab_width: usize) -> Vec<GraphemeCluster> {
+	let mut clusters = Vec::new();
+	for_each_visible_grapheme_u16(text, tab_width, |pos, gu16, gw| {
+		let g = String::from_utf16_lossy(gu16);
+		clusters.push(classify_grapheme(&g, pos, pos + gu16.len(), gw));
+	});
+	clusters
+}
+
+/// Split text into grapheme clusters with widths and emoji classification.
+///
+/// ANSI escape sequences are skipped; offsets index the original string.
+/// Widths come from the same per-grapheme measure as `visibleWidth`,
+/// `sliceWithWidth`, and `wrapTextWithAnsi`, so clusters such as ZWJ
+/// families are never split.
+#[napi(js_name = "graphemeInfo")]
+pub fn grapheme_info(text: JsString, tab_width: Option<u32>) -> Result<Vec<GraphemeCluster>> {
+	let text_u16 = text.into_utf16()?;
+	Ok(grapheme_info_u16(text_u16.as_slice(), clamp_tab_width(tab_width)))
+}
+
+/// Report the Unicode versions of the pinned segmentation and width tables.
+#[napi(js_name = "unicodeVersion")]
+pub fn unicode_version() -> UnicodeVersionInfo {
+	let (major, minor, patch) = unicode_segmentation::UNICODE_VERSION;
+	let (w_major, w_minor, w_patch) = unicode_width::UNICODE_VERSION;
+	UnicodeVersionInfo {
+		segmentation: format!("{major}.{minor}.{patch}"),
+		width:        format!("{w_major}.{w_minor}.{w_patch}"),
+	}
+}
+
+// ============================================================================
+// expandTabs
+// ============================================================================
+
+/// Replace each tab in `data` with spaces up to the next multiple of
+/// `tab_width` columns, counting from the start of its line. ANSI escapes are
+/// kept and take no columns. Returns `None` when there are no tabs.
+fn expand_tabs_u16(data: &[u16], tab_width: usize) -> Option<Vec<u16>> {
+	if !data.contains(&(b'\t' as u16)) {
+		return None;
+	}
+	let mut out = Vec::with_capacity(data.len() + tab_width * 4);
+	let mut col = 0usize;
+	let mut last = 0usize;
+	for_each_visible_grapheme_u16(data, tab_width, |pos, gu16, gw| {
+		// Copy the escape sequences skipped since the previous grapheme.
+		out.extend_from_slice(&data[last..pos]);
+		last = pos + gu16.len();
+		if gu16 == [b'\t' as u16] {
+			let advance = tab_width - col % tab_width;
+			out.resize(out.len() + advance, b' ' as u16);
+			col += advance;
+			return;
+		}
+		out.extend_from_slice(gu16);
+		if gu16.contains(&(b'\n' as u16)) {
+			col = 0;
+		} else {
+			col += gw;
+		}
+	});
+	out.extend_from_slice(&data[last..]);
+	Some(out)
+}
+
+/// `data` with tabs expanded to column-aware stops when `tab_stops` is set.
+fn apply_tab_stops(data: &[u16], tab_stops: Option<bool>, tab_width: usize) -> Cow<'_, [u16]> {
+	if tab_stops == Some(true)
+		&& let Some(expanded) = expand_tabs_u16(data, tab_width)
+	{
+		Cow::Owned(expanded)
+	} else {
+		Cow::Borrowed(data)
+	}
+}
+
+/// Expand tabs to spaces, advancing each to the next tab stop like a
+/// terminal does.
+///
+/// Columns count from the start of each line and ignore ANSI escapes.
+#[napi(js_name = "expandTabs")]
+pub fn expand_tabs(
+	text: JsString<'_>,
+	tab_width: Option<u32>,
+) -> Result<Either<JsString<'_>, Utf16String>> {
+	let original = text;
+	let text_u16 = text.into_utf16()?;
+	Ok(expand_tabs_u16(text_u16.as_slice(), clamp_tab_width(tab_width))
+		.map_or(Either::A(original), |out| Either::B(build_utf16_string(out))))
+}
+
 // ============================================================================
 // visibleWidth
 // ============================================================================
 
 /// Calculate visible width of text, excluding ANSI escape sequences.
 ///
-/// Tabs count as a fixed-width cell.
+/// Tabs count as a fixed-width cell, or advance to the next tab stop when
+/// `tab_stops` is set.
 #[napi(js_name = "visibleWidth")]
-pub fn visible_width_napi(text: JsString, tab_width: Option<u32>) -> Result<u32> {
+pub fn visible_width_napi(
+	text: JsString,
+	tab_width: Option<u32>,
+	tab_stops: Option<bool>,
+) -> Result<u32> {
 	let text_u16 = text.into_utf16()?;
 	let tab_width = clamp_tab_width(tab_width);
-	Ok(crate::utils::clamp_u32(visible_width_u16(text_u16.as_slice(), tab_width) as u64))
+	let text = apply_tab_stops(text_u16.as_slice(), tab_stops, tab_width);
+	Ok(crate::utils::clamp_u32(visible_width_u16(&text, tab_width) as u64))
+}
+
+/// Measure the visible width of many lines in one call.
+///
+/// Equivalent to calling `visibleWidth` per line, without the per-call N-API
+/// overhead.
+#[napi(js_name = "visibleWidthBatch")]
+pub fn visible_width_batch(lines: Vec<JsString>, tab_width: Option<u32>) -> Result<Uint32Array> {
+	let tab_width = clamp_tab_width(tab_width);
+	let widths = lines
+		.into_iter()
+		.map(|line| {
+			let line_u16 = line.into_utf16()?;
+			Ok(crate::utils::clamp_u32(visible_width_u16(line_u16.as_slice(), tab_width) as u64))
+		})
+		.collect::<Result<Vec<u32>>>()?;
+	Ok(Uint32Array::new(widths))
 }
 
 #[cfg(test)]
 mod tests {
+	use pi_text::DEFAULT_TAB_WIDTH;
+
 	use super::*;
 
 	fn to_u16(s: &str) -> Vec<u16> {
@@ -1366,88 +1887,184 @@ mod tests {
 	}
 
 	#[test]
-	fn test_visible_width() {
-		assert_eq!(visible_width_u16(&to_u16("hello"), DEFAULT_TAB_WIDTH), 5);
-		assert_eq!(visible_width_u16(&to_u16("\x1b[31mhello\x1b[0m"), DEFAULT_TAB_WIDTH), 5);
-		assert_eq!(visible_width_u16(&to_u16("\x1b[38;5;196mred\x1b[0m"), DEFAULT_TAB_WIDTH), 3);
-		assert_eq!(visible_width_u16(&to_u16("a\tb"), DEFAULT_TAB_WIDTH), 1 + DEFAULT_TAB_WIDTH + 1);
+	fn test_fit_segments() {
+		let fit = |segments: &[&str], total: usize, min: usize| {
+			let segments: Vec<Vec<u16>> = segments.iter().map(|s| to_u16(s)).collect();
+			let (out, width) =
+				fit_segments_impl(&segments, total, &to_u16("/"), min, DEFAULT_TAB_WIDTH);
+			let out: Vec<String> = out.iter().map(|s| String::from_utf16_lossy(s)).collect();
+			(out.join("/"), width)
+		};
+		assert_eq!(fit(&["src", "lib"], 20, 1), ("src/lib".to_string(), 7));
+		// The longest segment shrinks first.
+		assert_eq!(fit(&["src", "components", "ui"], 14, 1), ("src/com…nts/ui".to_string(), 14));
+		// Emoji are two cells wide and never split.
+		assert_eq!(fit(&["📁📁📁📁", "ab"], 7, 1), ("📁…/ab".to_string(), 6));
+		// Segments stop shrinking at the minimum.
+		assert_eq!(fit(&["abcdef", "ghijkl"], 5, 3), ("a…f/g…l".to_string(), 7));
+		let styled = fit(&["\x1b[31mabcdefgh\x1b[0m"], 5, 1).0;
+		assert_eq!(styled, "\x1b[31mab\x1b[0m…\x1b[31mgh\x1b[0m");
 	}
 
 	#[test]
-	fn test_ansi_detection() {
-		let data = to_u16("\x1b[31mred\x1b[0m");
-		assert_eq!(ansi_seq_len_u16(&data, 0), Some(5)); // \x1b[31m
-		assert_eq!(ansi_seq_len_u16(&data, 8), Some(4)); // \x1b[0m
+	fn test_pad_to_width() {
+		let pad = |text: &str, width: usize, align: Align| {
+			String::from_utf16_lossy(&pad_to_width_impl(
+				&to_u16(text),
+				width,
+				align,
+				DEFAULT_TAB_WIDTH,
+			))
+		};
+		assert_eq!(pad("ab", 5, Align::Left), "ab   ");
+		assert_eq!(pad("ab", 5, Align::Right), "   ab");
+		assert_eq!(pad("ab", 5, Align::Center), " ab  ");
+		assert_eq!(pad("\x1b[31m日本\x1b[0m", 6, Align::Center), " \x1b[31m日本\x1b[0m ");
+		assert_eq!(pad("abcdef", 4, Align::Right), "abc…");
+		// A wide grapheme that straddles the cut leaves a cell to pad.
+		assert_eq!(pad("日本語", 4, Align::Left), "日… ");
 	}
 
 	#[test]
-	fn test_slice_basic() {
-		let data = to_u16("hello world");
-		let (out, width) = slice_with_width_impl(&data, 0, 5, false, DEFAULT_TAB_WIDTH);
-		assert_eq!(String::from_utf16_lossy(&out), "hello");
-		assert_eq!(width, 5);
+	fn test_strip_ansi_maps_indices() {
+		let (text, indices) = strip_ansi_mapped_u16(&to_u16("a\x1b[31mbc\x1b[0md\x1b"));
+		assert_eq!(String::from_utf16_lossy(&text), "abcd");
+		assert_eq!(indices, [0, 6, 7, 12]);
 	}
 
 	#[test]
-	fn test_slice_with_ansi() {
-		let data = to_u16("\x1b[31mhello\x1b[0m world");
-		let (out, width) = slice_with_width_impl(&data, 0, 5, false, DEFAULT_TAB_WIDTH);
-		assert_eq!(String::from_utf16_lossy(&out), "\x1b[31mhello\x1b[0m");
-		assert_eq!(width, 5);
+	fn test_highlight_ranges_by_column() {
+		let data = to_u16("\x1b[4mab日本\x1b[24mcd");
+		let offsets = column_ranges_to_offsets(&data, &[(7, 9), (1, 3), (2, 3)], DEFAULT_TAB_WIDTH);
+		// Column 2 starts "日", so the range takes the whole wide character.
+		assert_eq!(offsets, [(5, 7), (14, 15)]);
+		let style = to_u16("\x1b[7m");
+		let out = String::from_utf16_lossy(&highlight_matches_impl(&data, &offsets, &style));
+		assert_eq!(out, "\x1b[4ma\x1b[7mb日\x1b[0m\x1b[4m本\x1b[24mc\x1b[7md\x1b[0m");
 	}
 
 	#[test]
-	fn test_ascii_fast_path() {
-		fn is_ascii(seg: &[u16]) -> bool {
-			seg.iter().all(|&u| u <= 0x7f)
-		}
+	fn test_expand_tabs_to_next_stop() {
+		let expand =
+			|text: &str| expand_tabs_u16(&to_u16(text), 4).map(|out| String::from_utf16_lossy(&out));
+		assert_eq!(expand("no tabs"), None);
+		assert_eq!(expand("a\tbc\td").as_deref(), Some("a   bc  d"));
+		assert_eq!(
+			expand("\x1b[31m日\t\x1b[0mx\n\ty").as_deref(),
+			Some("\x1b[31m日  \x1b[0mx\n    y")
+		);
+	}
 
-		let ascii = to_u16("hello world 12345");
-		assert!(is_ascii(&ascii));
+	#[test]
+	fn test_strip_ansi_across_chunks() {
+		let chunks =
+			["plain \x1b[3", "1mred\x1b", "[0m \x1b]8;;http://x\x1b", "\\link\x1b]8;;\x07 end\x1b["];
+		let mut pending = Vec::new();
+		let mut out = String::new();
+		for chunk in chunks {
+			pending.extend(to_u16(chunk));
+			let (clean, rest) = strip_ansi_chunk_u16(&pending, false);
+			out.push_str(&String::from_utf16_lossy(&clean));
+			pending.drain(..rest);
+		}
+		assert_eq!(out, "plain red link end");
+		assert_eq!(String::from_utf16_lossy(&pending), "\x1b[");
+		assert_eq!(strip_ansi_chunk_u16(&pending, true), (Vec::new(), 2));
+		assert_eq!(strip_ansi_chunk_u16(&to_u16("a\x1b\x01b"), false), (to_u16("a\x01b"), 4));
+	}
 
-		let non_ascii = to_u16("hello 世界");
-		assert!(!is_ascii(&non_ascii));
+	#[test]
+	fn test_width_policy_changes_ambiguous_and_emoji_widths() {
+		let data = to_u16("±± ❤\u{FE0F} 👨\u{200D}👩");
+		assert_eq!(visible_width_u16(&data, DEFAULT_TAB_WIDTH), 8);
+		let policy = WidthPolicy { ambiguous_wide: true, emoji_wide: false };
+		let config = config::Config { width: policy, ..config::snapshot() };
+		let width = config::with_snapshot(config, || visible_width_u16(&data, DEFAULT_TAB_WIDTH));
+		assert_eq!(width, 9);
 	}
 
 	#[test]
-	fn test_early_exit() {
-		let data = to_u16(&"a]b".repeat(1000));
-		let (w, exceeded) = visible_width_u16_up_to(&data, 10, DEFAULT_TAB_WIDTH);
-		assert!(exceeded);
-		assert!(w > 10);
+	fn test_highlight_matches_restores_outer_style() {
+		let data = to_u16("\x1b[4mfoo \x1b[31mbar\x1b[0m baz");
+		let matcher =
+			crate::grep::build_matcher("o b", crate::regex_engine::RegexEngine::Default, false, false)
+				.unwrap();
+		let ranges = visible_match_ranges_u16(&data, &matcher);
+		let style = highlight_sgr_u16(Some("43"));
+		let out = String::from_utf16_lossy(&highlight_matches_impl(&data, &ranges, &style));
+		let expected =
+			["\x1b[4mfo", "\x1b[43mo \x1b[31m\x1b[43mb", "\x1b[0m\x1b[4;31mar", "\x1b[0m baz"];
+		assert_eq!(out, expected.concat());
 	}
 
 	#[test]
-	fn test_wrap_text_with_ansi_preserves_color() {
-		let data = to_u16("\x1b[38;2;156;163;176mhello world\x1b[0m");
-		let lines = wrap_text_with_ansi_impl(&data, 5, DEFAULT_TAB_WIDTH);
-		assert_eq!(lines.len(), 2);
-		let first = String::from_utf16_lossy(&lines[0]);
-		let second = String::from_utf16_lossy(&lines[1]);
-		assert!(first.starts_with("\x1b[38;2;156;163;176m"));
-		assert!(second.starts_with("\x1b[38;2;156;163;176m"));
-		assert!(second.contains("world"));
+	fn test_wrap_window_matches_full_wrap() {
+		let data = to_u16("\x1b[31mred words that wrap\nplain\n\nmore words here\x1b[0m tail");
+		let full = wrap_text_with_ansi_impl(&data, 6, DEFAULT_TAB_WIDTH);
+
+		let (lines, first, total) = wrap_window_impl(&data, 6, DEFAULT_TAB_WIDTH, 2, Some(4));
+		assert_eq!(total, full.len());
+		assert_eq!(first, 2);
+		assert_eq!(lines.as_slice(), &full[2..6]);
+
+		let (tail, ..) = wrap_window_impl(&data, 6, DEFAULT_TAB_WIDTH, 5, None);
+		assert_eq!(tail.as_slice(), &full[5..]);
+
+		let (past_end, first, _) = wrap_window_impl(&data, 6, DEFAULT_TAB_WIDTH, 100, Some(3));
+		assert_eq!(past_end.len(), 0);
+		assert_eq!(first, full.len());
+	}
+
+	type RunSpans = Vec<(u32, u32, u32)>;
+
+	fn bidi_spans(text: &str, width: usize) -> Vec<(u8, RunSpans)> {
+		wrap_text_with_bidi_impl(&to_u16(text), width, DEFAULT_TAB_WIDTH, None)
+			.into_iter()
+			.map(|(_, para_level, runs)| {
+				(
+					para_level,
+					runs
+						.iter()
+						.map(|run| (run.start, run.end, run.level))
+						.collect(),
+				)
+			})
+			.collect()
 	}
 
 	#[test]
-	fn test_wrap_text_with_ansi_resets_strike_without_resetting_colors() {
-		let data =
-			to_u16("\x1b[38;5;196m\x1b[48;5;236m\x1b[9mstrikethrough content wraps\x1b[29m\x1b[0m");
-		let lines = wrap_text_with_ansi_impl(&data, 12, DEFAULT_TAB_WIDTH);
-		assert!(lines.len() > 1);
-
-		for line in &lines[..lines.len() - 1] {
-			let line_text = String::from_utf16_lossy(line);
-			if line_text.contains("\x1b[9m") {
-				assert!(line_text.ends_with("\x1b[29m"));
-				assert!(!line_text.ends_with("\x1b[0m"));
-			}
-		}
+	fn test_wrap_text_with_bidi_segments_runs() {
+		let hebrew_in_ltr = bidi_spans("abc \u{05E9}\u{05DC}\u{05D5}\u{05DD} 123 def", 80);
+		assert_eq!(hebrew_in_ltr, vec![(0, vec![(0, 4, 0), (4, 9, 1), (9, 12, 2), (12, 16, 0)])]);
 
-		for line in &lines[1..] {
-			let line_text = String::from_utf16_lossy(line);
-			assert!(line_text.contains("38;5;196"));
-			assert!(line_text.contains("48;5;236"));
-		}
+		let arabic = bidi_spans("\x1b[1m\u{0645}\u{0631}\u{062D}\u{0628}\u{0627}\x1b[0m 42", 80);
+		assert_eq!(arabic, vec![(1, vec![(0, 14, 1), (14, 16, 2)])]);
+
+		let wrapped = bidi_spans("\u{05E9}\u{05DC}\u{05D5}\u{05DD} abc", 4);
+		assert_eq!(wrapped, vec![(1, vec![(0, 4, 1)]), (1, vec![(0, 3, 2)])]);
+	}
+
+	#[test]
+	fn test_grapheme_info_keeps_emoji_sequences_whole() {
+		let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+		let text = format!("a\x1b[1m{family}\x1b[0m\u{1F44D}\u{1F3FD}\u{1F1FA}\u{1F1F8}");
+		let clusters = grapheme_info_u16(&to_u16(&text), DEFAULT_TAB_WIDTH);
+		let summary: Vec<_> = clusters
+			.iter()
+			.map(|c| (c.start, c.end, c.width, c.zwj_sequence, c.skin_tone, c.flag))
+			.collect();
+		assert_eq!(summary, vec![
+			(0, 1, 1, false, false, false),
+			(5, 13, 2, true, false, false),
+			(17, 21, 2, false, true, false),
+			(21, 25, 2, false, false, true),
+		]);
+
+		let line = to_u16(&format!("{family}xy"));
+		let (out, width) = slice_with_width_impl(&line, 0, 3, true, DEFAULT_TAB_WIDTH);
+		assert_eq!(String::from_utf16_lossy(&out), format!("{family}x"));
+		assert_eq!(width, 3);
+		let lines = wrap_text_with_ansi_impl(&line, 2, DEFAULT_TAB_WIDTH);
+		assert_eq!(String::from_utf16_lossy(&lines[0]), family);
 	}
 }
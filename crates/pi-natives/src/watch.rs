@@ -0,0 +1,415 @@
+//! Filesystem watching for the scan cache.
+//!
+//! `watchRoot()` keeps cached scans under a directory fresh: every change
+//! below the root drops the affected [`fs_cache`] entries as soon as its event
+//! arrives, so cache-enabled `glob`/`grep`/`fd` calls never serve results
+//! older than the last change instead of relying on the TTL. Changes can also
+//! be streamed to a JS callback in debounced batches; under steady churn a
+//! batch is still delivered once its oldest change has waited `maxWaitMs`.
+//!
+//! Events come from the platform's native backend through `notify`
+//! (inotify, `FSEvents`, kqueue, or `ReadDirectoryChangesW`), falling back to
+//! polling every `intervalMs` when it cannot start. Backends that watch one
+//! directory at a time leave out `.git` and gitignored directories; the
+//! natively recursive ones (`FSEvents`, Windows) watch the whole tree and drop
+//! events under `.git`.
+
+use std::{
+	collections::HashSet,
+	path::{Path, PathBuf},
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+		mpsc::{self, Receiver, RecvTimeoutError},
+	},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant},
+};
+
+use napi::{
+	bindgen_prelude::*,
+	threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode},
+};
+use napi_derive::napi;
+use notify::{
+	Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher, WatcherKind,
+	event::{ModifyKind, RenameMode},
+};
+
+use crate::fs_cache;
+
+/// Default polling interval when the native backend cannot start.
+const DEFAULT_INTERVAL_MS: u32 = 500;
+/// Quiet period before a batch of changes is flushed.
+const DEFAULT_DEBOUNCE_MS: u32 = 50;
+/// Longest a change waits for a quiet period before its batch is flushed.
+const DEFAULT_MAX_WAIT_MS: u32 = 500;
+
+/// A change below a watched root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[napi(object)]
+pub struct FsChange {
+	/// Absolute path that changed.
+	pub path: String,
+	/// "create", "modify", "remove", or "rescan" when events were lost and
+	/// the whole root should be treated as changed.
+	#[napi(ts_type = "\"create\" | \"modify\" | \"remove\" | \"rescan\"")]
+	pub kind: String,
+}
+
+/// Options for `watchRoot()`.
+#[napi(object)]
+pub struct WatchRootOptions {
+	/// Polling interval when the native backend cannot start (default 500).
+	#[napi(js_name = "intervalMs")]
+	pub interval_ms: Option<u32>,
+	/// Quiet period before a batch of changes is delivered (default 50).
+	#[napi(js_name = "debounceMs")]
+	pub debounce_ms: Option<u32>,
+	/// Longest a change waits for a quiet period before its batch is
+	/// delivered anyway (default 500).
+	#[napi(js_name = "maxWaitMs")]
+	pub max_wait_ms: Option<u32>,
+}
+
+type ChangeCallback = ThreadsafeFunction<Vec<FsChange>>;
+
+/// Drops cached scans for each change right away and collects the changes
+/// for the callback, flushed after a quiet period or once the oldest has
+/// waited `max_wait`.
+struct Batcher {
+	pending:    Vec<FsChange>,
+	/// `(path, kind)` pairs already in `pending`.
+	seen:       HashSet<(String, &'static str)>,
+	/// When the oldest pending change arrived.
+	first:      Instant,
+	last_event: Instant,
+	debounce:   Duration,
+	max_wait:   Duration,
+	callback:   Option<ChangeCallback>,
+}
+
+impl Batcher {
+	fn new(debounce: Duration, max_wait: Duration, callback: Option<ChangeCallback>) -> Self {
+		let now = Instant::now();
+		Self {
+			pending: Vec::new(),
+			seen: HashSet::new(),
+			first: now,
+			last_event: now,
+			debounce,
+			max_wait,
+			callback,
+		}
+	}
+
+	fn push(&mut self, path: &Path, kind: &'static str) {
+		fs_cache::invalidate_path(path);
+		if self.callback.is_none() {
+			return;
+		}
+		let path = path.to_string_lossy().into_owned();
+		if self.seen.insert((path.clone(), kind)) {
+			if self.pending.is_empty() {
+				self.first = Instant::now();
+			}
+			self.pending.push(FsChange { path, kind: kind.to_string() });
+		}
+		self.last_event = Instant::now();
+	}
+
+	fn flush_if_due(&mut self) {
+		if self.pending.is_empty()
+			|| (self.last_event.elapsed() < self.debounce && self.first.elapsed() < self.max_wait)
+		{
+			return;
+		}
+		self.seen.clear();
+		let batch = std::mem::take(&mut self.pending);
+		if let Some(callback) = &self.callback {
+			callback.call(Ok(batch), ThreadsafeFunctionCallMode::NonBlocking);
+		}
+	}
+}
+
+/// Directories to watch under `root`: hidden ones included, `.git` and
+/// gitignored ones left out.
+fn watched_dirs(root: &Path) -> impl Iterator<Item = PathBuf> {
+	let mut builder = fs_cache::build_walker(root, &fs_cache::ScanPolicy::new(true, true));
+	builder.filter_entry(|entry| entry.file_name() != ".git");
+	builder
+		.build()
+		.filter_map(std::result::Result::ok)
+		.filter(|entry| entry.file_type().is_some_and(|kind| kind.is_dir()))
+		.map(ignore::DirEntry::into_path)
+}
+
+/// How often the watcher thread checks for stop requests and due batches.
+const TICK: Duration = Duration::from_millis(25);
+
+/// A `notify` watcher and the events it reports.
+struct Backend {
+	watcher: Box<dyn Watcher + Send>,
+	/// Whether directories are watched one at a time, which lets ignored ones
+	/// be left out; otherwise the root is watched recursively.
+	per_dir: bool,
+}
+
+impl Backend {
+	fn new(root: &Path, interval: Duration) -> Result<(Self, Receiver<notify::Result<Event>>)> {
+		let (sender, events) = mpsc::channel();
+		let config = notify::Config::default().with_poll_interval(interval);
+		let (watcher, kind): (Box<dyn Watcher + Send>, _) =
+			match RecommendedWatcher::new(sender.clone(), config) {
+				Ok(watcher) => (Box::new(watcher), RecommendedWatcher::kind()),
+				Err(_) => {
+					(
+						Box::new(PollWatcher::new(sender, config).map_err(|err| {
+							Error::from_reason(format!("Failed to start watcher: {err}"))
+						})?),
+						PollWatcher::kind(),
+					)
+				},
+			};
+		let per_dir =
+			!matches!(kind, WatcherKind::Fsevent | WatcherKind::ReadDirectoryChangesWatcher);
+		let mut backend = Self { watcher, per_dir };
+		backend
+			.watch_tree(root)
+			.map_err(|err| Error::from_reason(format!("Failed to watch {}: {err}", root.display())))?;
+		Ok((backend, events))
+	}
+
+	/// Watch `dir` and everything below it; only a failure on `dir` itself
+	/// is reported.
+	fn watch_tree(&mut self, dir: &Path) -> notify::Result<()> {
+		if !self.per_dir {
+			return self.watcher.watch(dir, RecursiveMode::Recursive);
+		}
+		for sub in watched_dirs(dir) {
+			if let Err(err) = self.watcher.watch(&sub, RecursiveMode::NonRecursive)
+				&& sub == dir
+			{
+				return Err(err);
+			}
+		}
+		Ok(())
+	}
+
+	fn run(
+		mut self,
+		root: &Path,
+		events: &Receiver<notify::Result<Event>>,
+		mut batcher: Batcher,
+		stop: &AtomicBool,
+	) {
+		while !stop.load(Ordering::Relaxed) {
+			match events.recv_timeout(TICK) {
+				Ok(Ok(event)) => self.handle(event, root, &mut batcher),
+				// The backend lost track of changes.
+				Ok(Err(_)) => batcher.push(root, "rescan"),
+				Err(RecvTimeoutError::Timeout) => {},
+				Err(RecvTimeoutError::Disconnected) => break,
+			}
+			batcher.flush_if_due();
+		}
+	}
+
+	fn handle(&mut self, event: Event, root: &Path, batcher: &mut Batcher) {
+		if event.need_rescan() {
+			batcher.push(root, "rescan");
+			return;
+		}
+		for (index, path) in event.paths.iter().enumerate() {
+			let kind = match event.kind {
+				EventKind::Access(_) => return,
+				EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => "create",
+				EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+					"remove"
+				},
+				// Renames within the tree report the old path, then the new one.
+				EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if index == 0 => "remove",
+				EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => "create",
+				_ => "modify",
+			};
+			let relative = path.strip_prefix(root).unwrap_or(path);
+			if relative.components().any(|part| part.as_os_str() == ".git") {
+				continue;
+			}
+			if kind == "create" && self.per_dir && path.is_dir() {
+				let _ = self.watch_tree(path);
+			}
+			batcher.push(path, kind);
+		}
+	}
+}
+
+struct WatcherInner {
+	stop:   Arc<AtomicBool>,
+	thread: Option<JoinHandle<()>>,
+}
+
+impl WatcherInner {
+	fn start(root: PathBuf, interval: Duration, batcher: Batcher) -> Result<Self> {
+		let stop = Arc::new(AtomicBool::new(false));
+		let (backend, events) = Backend::new(&root, interval)?;
+		let thread_stop = Arc::clone(&stop);
+		let thread = thread::Builder::new()
+			.name("pi-watch".to_string())
+			.spawn(move || backend.run(&root, &events, batcher, &thread_stop))
+			.map_err(|err| Error::from_reason(format!("Failed to start watcher: {err}")))?;
+		Ok(Self { stop, thread: Some(thread) })
+	}
+
+	fn stop(&mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+impl Drop for WatcherInner {
+	fn drop(&mut self) {
+		self.stop();
+	}
+}
+
+/// Handle for a running `watchRoot()` watcher.
+#[napi]
+pub struct FsWatcher {
+	root:  String,
+	inner: Option<WatcherInner>,
+}
+
+#[napi]
+impl FsWatcher {
+	/// Canonical root being watched.
+	#[napi(getter)]
+	pub fn root(&self) -> String {
+		self.root.clone()
+	}
+
+	/// Whether `close()` has been called.
+	#[napi(getter)]
+	pub const fn closed(&self) -> bool {
+		self.inner.is_none()
+	}
+
+	/// Stop watching. Pending changes are discarded; cached scans for the
+	/// root are dropped since nothing keeps them fresh anymore.
+	#[napi]
+	pub fn close(&mut self) {
+		if let Some(mut inner) = self.inner.take() {
+			inner.stop();
+			fs_cache::invalidate_path(Path::new(&self.root));
+		}
+	}
+}
+
+/// Watch `path` and drop cached filesystem scans as soon as anything below it
+/// changes.
+///
+/// `onChange` receives batches of changes whose scans were already dropped,
+/// once changes pause for `debounceMs` or the oldest has waited `maxWaitMs`.
+/// Call `close()` on the returned watcher to stop.
+///
+/// # Errors
+/// Fails when `path` is not a directory or the watch cannot be set up.
+#[napi(js_name = "watchRoot")]
+pub fn watch_root(
+	path: String,
+	options: Option<WatchRootOptions>,
+	#[napi(ts_arg_type = "((changes: FsChange[]) => void) | undefined | null")] on_change: Option<
+		ChangeCallback,
+	>,
+) -> Result<FsWatcher> {
+	let root = fs_cache::resolve_search_path(&path)?;
+	let interval = options
+		.as_ref()
+		.and_then(|options| options.interval_ms)
+		.unwrap_or(DEFAULT_INTERVAL_MS)
+		.max(1);
+	let debounce = options
+		.as_ref()
+		.and_then(|options| options.debounce_ms)
+		.unwrap_or(DEFAULT_DEBOUNCE_MS);
+	let max_wait = options
+		.as_ref()
+		.and_then(|options| options.max_wait_ms)
+		.unwrap_or(DEFAULT_MAX_WAIT_MS);
+	let batcher = Batcher::new(
+		Duration::from_millis(debounce.into()),
+		Duration::from_millis(max_wait.into()),
+		on_change,
+	);
+	let inner = WatcherInner::start(root.clone(), Duration::from_millis(interval.into()), batcher)?;
+	Ok(FsWatcher { root: root.to_string_lossy().into_owned(), inner: Some(inner) })
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		time::{SystemTime, UNIX_EPOCH},
+	};
+
+	use super::*;
+	use crate::task;
+
+	#[test]
+	fn drops_cached_scans_on_change() {
+		let unique = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.expect("system time should be after UNIX_EPOCH")
+			.as_nanos();
+		let root = std::env::temp_dir().join(format!("pi-watch-test-{unique}"));
+		fs::create_dir_all(root.join("sub")).expect("temp dir should be created");
+		let root = fs::canonicalize(&root).unwrap();
+		let policy = fs_cache::ScanPolicy::new(true, true);
+		let ct = task::CancelToken::default();
+
+		// A debounce longer than the test shows that invalidation does not wait
+		// for a quiet period.
+		let batcher = Batcher::new(Duration::from_secs(60), Duration::from_secs(60), None);
+		let mut watcher = WatcherInner::start(root.clone(), Duration::from_millis(20), batcher)
+			.expect("watcher should start");
+		let is_cached = || {
+			fs_cache::fs_cache_stats()
+				.roots
+				.iter()
+				.any(|cached| Path::new(&cached.root) == root.join("sub"))
+		};
+		fs_cache::force_rescan(&root.join("sub"), &policy, true, &ct).unwrap();
+		assert!(is_cached());
+
+		fs::write(root.join("sub").join("new.txt"), "x").unwrap();
+		let deadline = Instant::now() + Duration::from_secs(5);
+		while is_cached() && Instant::now() < deadline {
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert!(!is_cached(), "scan should be dropped after a change");
+
+		// Directories created after the watch started are watched too.
+		let fresh = root.join("fresh");
+		fs::create_dir(&fresh).unwrap();
+		thread::sleep(Duration::from_millis(100));
+		fs_cache::force_rescan(&fresh, &policy, true, &ct).unwrap();
+		let fresh_cached = || {
+			fs_cache::fs_cache_stats()
+				.roots
+				.iter()
+				.any(|cached| Path::new(&cached.root) == fresh)
+		};
+		assert!(fresh_cached());
+		fs::write(fresh.join("new.txt"), "x").unwrap();
+		let deadline = Instant::now() + Duration::from_secs(5);
+		while fresh_cached() && Instant::now() < deadline {
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert!(!fresh_cached(), "scan of a new directory should be dropped after a change");
+
+		watcher.stop();
+		let _ = fs::remove_dir_all(&root);
+	}
+}
@@ -0,0 +1,1345 @@
+//! ANSI-aware text layout on UTF-16 buffers.
+//!
+//! The width, wrapping, truncation, and slicing core behind the `pi-natives`
+//! text functions. It depends only on the Unicode tables, so it builds for
+//! `wasm32-unknown-unknown`; the `wasm` feature adds wasm-bindgen exports
+//! (`wasm`) that lay out text exactly like the addon.
+
+use std::{
+	cell::RefCell,
+	sync::{Arc, OnceLock},
+};
+
+use smallvec::{SmallVec, smallvec};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub const DEFAULT_TAB_WIDTH: usize = 3;
+const MIN_TAB_WIDTH: usize = 1;
+const MAX_TAB_WIDTH: usize = 16;
+pub const ESC: u16 = 0x1b;
+
+#[inline]
+pub const fn clamp_tab_width(tab_width: Option<u32>) -> usize {
+	let width = match tab_width {
+		Some(tab_width) => tab_width as usize,
+		None => DEFAULT_TAB_WIDTH,
+	};
+	if width < MIN_TAB_WIDTH {
+		MIN_TAB_WIDTH
+	} else if width > MAX_TAB_WIDTH {
+		MAX_TAB_WIDTH
+	} else {
+		width
+	}
+}
+
+// ============================================================================
+// ANSI State Tracking - Zero Allocation
+// ============================================================================
+
+const ATTR_BOLD: u16 = 1 << 0;
+const ATTR_DIM: u16 = 1 << 1;
+const ATTR_ITALIC: u16 = 1 << 2;
+const ATTR_UNDERLINE: u16 = 1 << 3;
+const ATTR_BLINK: u16 = 1 << 4;
+const ATTR_INVERSE: u16 = 1 << 6;
+const ATTR_HIDDEN: u16 = 1 << 7;
+const ATTR_STRIKE: u16 = 1 << 8;
+const ATTR_OVERLINE: u16 = 1 << 9;
+
+/// Underline style of SGR `4:n`; `4` alone is single.
+const UNDERLINE_SINGLE: u8 = 1;
+
+type ColorVal = u32;
+const COLOR_NONE: ColorVal = 0;
+
+/// OSC 8 with empty parameters and URI, which closes a hyperlink.
+const OSC8_CLOSE: &[u16] =
+	&[ESC, b']' as u16, b'8' as u16, b';' as u16, b';' as u16, ESC, b'\\' as u16];
+
+/// SGR attributes and hyperlink in effect at a point of a text.
+#[derive(Clone, Default)]
+pub struct AnsiState {
+	attrs:           u16,
+	/// Style of the underline while `ATTR_UNDERLINE` is set: 1 single,
+	/// 2 double, 3 curly, 4 dotted, 5 dashed.
+	underline_style: u8,
+	fg:              ColorVal,
+	bg:              ColorVal,
+	/// Underline color (SGR 58).
+	underline_color: ColorVal,
+	/// OSC 8 sequence that opened the current hyperlink, if one is open.
+	link:            Option<Arc<[u16]>>,
+}
+
+impl AnsiState {
+	#[inline]
+	pub const fn new() -> Self {
+		Self {
+			attrs:           0,
+			underline_style: 0,
+			fg:              COLOR_NONE,
+			bg:              COLOR_NONE,
+			underline_color: COLOR_NONE,
+			link:            None,
+		}
+	}
+
+	#[inline]
+	const fn is_empty(&self) -> bool {
+		self.sgr_is_empty() && self.link.is_none()
+	}
+
+	#[inline]
+	const fn sgr_is_empty(&self) -> bool {
+		self.attrs == 0
+			&& self.fg == COLOR_NONE
+			&& self.bg == COLOR_NONE
+			&& self.underline_color == COLOR_NONE
+	}
+
+	/// Reset SGR attributes; SGR 0 leaves an open hyperlink open.
+	#[inline]
+	const fn reset(&mut self) {
+		self.attrs = 0;
+		self.underline_style = 0;
+		self.fg = COLOR_NONE;
+		self.bg = COLOR_NONE;
+		self.underline_color = COLOR_NONE;
+	}
+
+	/// Track an SGR or OSC 8 sequence; other sequences leave the state as is.
+	fn apply_seq_u16(&mut self, seq: &[u16]) {
+		if is_sgr_u16(seq) {
+			self.apply_sgr_u16(&seq[2..seq.len() - 1]);
+		} else if let Some(opens) = osc8_opens(seq) {
+			self.link = opens.then(|| seq.into());
+		}
+	}
+
+	/// Apply SGR parameters, with `:` sub-parameters as in `4:3` or
+	/// `38:2::r:g:b`.
+	pub fn apply_sgr_u16(&mut self, params: &[u16]) {
+		if params.is_empty() {
+			self.reset();
+			return;
+		}
+
+		// Empty parameters are skipped rather than read as 0.
+		let mut groups = params
+			.split(|&u| u == b';' as u16)
+			.filter(|group| !group.is_empty());
+		while let Some(group) = groups.next() {
+			let mut subs = group.split(|&u| u == b':' as u16).map(parse_sgr_num_u16);
+			let code = subs.next().flatten().unwrap_or(0);
+			let has_subs = group.contains(&(b':' as u16));
+
+			match code {
+				0 => self.reset(),
+				1 => self.attrs |= ATTR_BOLD,
+				2 => self.attrs |= ATTR_DIM,
+				3 => self.attrs |= ATTR_ITALIC,
+				4 => match subs.next().flatten() {
+					Some(0) => self.attrs &= !ATTR_UNDERLINE,
+					style => {
+						self.attrs |= ATTR_UNDERLINE;
+						self.underline_style =
+							style.map_or(UNDERLINE_SINGLE, |style| u8::try_from(style).unwrap_or(u8::MAX));
+					},
+				},
+				5 => self.attrs |= ATTR_BLINK,
+				7 => self.attrs |= ATTR_INVERSE,
+				8 => self.attrs |= ATTR_HIDDEN,
+				9 => self.attrs |= ATTR_STRIKE,
+				53 => self.attrs |= ATTR_OVERLINE,
+
+				21 => self.attrs &= !ATTR_BOLD,
+				22 => self.attrs &= !(ATTR_BOLD | ATTR_DIM),
+				23 => self.attrs &= !ATTR_ITALIC,
+				24 => self.attrs &= !ATTR_UNDERLINE,
+				25 => self.attrs &= !ATTR_BLINK,
+				27 => self.attrs &= !ATTR_INVERSE,
+				28 => self.attrs &= !ATTR_HIDDEN,
+				29 => self.attrs &= !ATTR_STRIKE,
+				55 => self.attrs &= !ATTR_OVERLINE,
+
+				30..=37 => self.fg = (code - 29) as ColorVal,
+				39 => self.fg = COLOR_NONE,
+				40..=47 => self.bg = (code - 39) as ColorVal,
+				49 => self.bg = COLOR_NONE,
+				59 => self.underline_color = COLOR_NONE,
+				90..=97 => self.fg = (code - 81) as ColorVal,
+				100..=107 => self.bg = (code - 91) as ColorVal,
+
+				38 | 48 | 58 => {
+					let color = if has_subs {
+						let args: SmallVec<[Option<u32>; 6]> = subs.collect();
+						let (mode, rest) = args
+							.split_first()
+							.map_or((None, &[][..]), |(mode, rest)| (*mode, rest));
+						// `38:2:cs:r:g:b` has a color space id before the channels.
+						let rest = if mode == Some(2) && rest.len() > 3 {
+							&rest[1..]
+						} else {
+							rest
+						};
+						extended_color(mode, rest)
+					} else {
+						let mode = groups.next().and_then(parse_sgr_num_u16);
+						let count = match mode {
+							Some(5) => 1,
+							Some(2) => 3,
+							_ => 0,
+						};
+						let args: SmallVec<[Option<u32>; 3]> =
+							groups.by_ref().take(count).map(parse_sgr_num_u16).collect();
+						extended_color(mode, &args)
+					};
+					let Some(color) = color else {
+						continue;
+					};
+
+					match code {
+						38 => self.fg = color,
+						48 => self.bg = color,
+						_ => self.underline_color = color,
+					}
+				},
+
+				_ => {},
+			}
+		}
+	}
+
+	/// Write the sequences that re-establish this state after a reset.
+	pub fn write_restore_u16(&self, out: &mut Vec<u16>) {
+		if self.sgr_is_empty() {
+			return;
+		}
+
+		out.extend_from_slice(&[ESC, b'[' as u16]);
+		let mut first = true;
+
+		macro_rules! push_code {
+			($code:expr) => {{
+				if !first {
+					out.push(b';' as u16);
+				}
+				first = false;
+				write_u32_u16(out, $code);
+			}};
+		}
+
+		if self.attrs & ATTR_BOLD != 0 {
+			push_code!(1);
+		}
+		if self.attrs & ATTR_DIM != 0 {
+			push_code!(2);
+		}
+		if self.attrs & ATTR_ITALIC != 0 {
+			push_code!(3);
+		}
+		if self.attrs & ATTR_UNDERLINE != 0 {
+			push_code!(4);
+			if self.underline_style > UNDERLINE_SINGLE {
+				out.push(b':' as u16);
+				write_u32_u16(out, u32::from(self.underline_style));
+			}
+		}
+		if self.attrs & ATTR_BLINK != 0 {
+			push_code!(5);
+		}
+		if self.attrs & ATTR_INVERSE != 0 {
+			push_code!(7);
+		}
+		if self.attrs & ATTR_HIDDEN != 0 {
+			push_code!(8);
+		}
+		if self.attrs & ATTR_STRIKE != 0 {
+			push_code!(9);
+		}
+		if self.attrs & ATTR_OVERLINE != 0 {
+			push_code!(53);
+		}
+
+		write_color_u16(out, self.fg, 38, &mut first);
+		write_color_u16(out, self.bg, 48, &mut first);
+		write_color_u16(out, self.underline_color, 58, &mut first);
+
+		out.push(b'm' as u16);
+	}
+}
+
+#[inline]
+fn write_color_u16(out: &mut Vec<u16>, color: ColorVal, base: u32, first: &mut bool) {
+	if color == COLOR_NONE {
+		return;
+	}
+
+	if !*first {
+		out.push(b';' as u16);
+	}
+	*first = false;
+
+	if color < 0x100 {
+		let code = if color <= 8 { color + 29 } else { color + 81 };
+		let code = if base == 48 { code + 10 } else { code };
+		write_u32_u16(out, code);
+	} else if color < 0x1000000 {
+		write_u32_u16(out, base);
+		out.extend_from_slice(&[b';' as u16, b'5' as u16, b';' as u16]);
+		write_u32_u16(out, color & 0xff);
+	} else {
+		write_u32_u16(out, base);
+		out.extend_from_slice(&[b';' as u16, b'2' as u16, b';' as u16]);
+		write_u32_u16(out, (color >> 16) & 0xff);
+		out.push(b';' as u16);
+		write_u32_u16(out, (color >> 8) & 0xff);
+		out.push(b';' as u16);
+		write_u32_u16(out, color & 0xff);
+	}
+}
+
+/// Value of one SGR parameter or sub-parameter; `None` when empty.
+#[inline]
+fn parse_sgr_num_u16(param: &[u16]) -> Option<u32> {
+	if param.is_empty() {
+		return None;
+	}
+	Some(param.iter().fold(0u32, |val, &b| {
+		if (b'0' as u16..=b'9' as u16).contains(&b) {
+			val.saturating_mul(10)
+				.saturating_add(u32::from(b - b'0' as u16))
+		} else {
+			val
+		}
+	}))
+}
+
+/// Color of a `38`/`48`/`58` parameter from its mode (5 indexed, 2 RGB) and
+/// the values after it.
+fn extended_color(mode: Option<u32>, args: &[Option<u32>]) -> Option<ColorVal> {
+	let arg = |i: usize| args.get(i).copied().flatten().unwrap_or(0) & 0xff;
+	match mode? {
+		5 => Some(0x100 | arg(0)),
+		2 => Some(0x1000000 | (arg(0) << 16) | (arg(1) << 8) | arg(2)),
+		_ => None,
+	}
+}
+
+#[inline]
+fn write_u32_u16(out: &mut Vec<u16>, mut val: u32) {
+	if val == 0 {
+		out.push(b'0' as u16);
+		return;
+	}
+	let start = out.len();
+	while val > 0 {
+		out.push(b'0' as u16 + (val % 10) as u16);
+		val /= 10;
+	}
+	out[start..].reverse();
+}
+
+// ============================================================================
+// ANSI Sequence Detection - UTF-16
+// ============================================================================
+
+#[inline]
+pub fn ansi_seq_len_u16(data: &[u16], pos: usize) -> Option<usize> {
+	if pos >= data.len() || data[pos] != ESC {
+		return None;
+	}
+	if pos + 1 >= data.len() {
+		return None;
+	}
+
+	match data[pos + 1] {
+		0x5b => {
+			// '[' CSI
+			for (i, b) in data[pos + 2..].iter().enumerate() {
+				if (0x40..=0x7e).contains(b) {
+					return Some(i + 3);
+				}
+			}
+			None
+		},
+		0x5d => {
+			// ']' OSC
+			for (i, &b) in data[pos + 2..].iter().enumerate() {
+				if b == 0x07 {
+					return Some(i + 3);
+				}
+				if b == ESC && data.get(pos + 2 + i + 1) == Some(&0x5c) {
+					return Some(i + 4);
+				}
+			}
+			None
+		},
+		0x50 | 0x58 | 0x5e | 0x5f => {
+			// 'P' DCS, 'X' SOS, '^' PM, '_' APC (terminated by ST)
+			for (i, &b) in data[pos + 2..].iter().enumerate() {
+				if b == ESC && data.get(pos + 2 + i + 1) == Some(&0x5c) {
+					return Some(i + 4);
+				}
+			}
+			None
+		},
+		0x20..=0x2f => {
+			// ESC + intermediates + final byte
+			for (i, b) in data[pos + 2..].iter().enumerate() {
+				if (0x30..=0x7e).contains(b) {
+					return Some(i + 3);
+				}
+			}
+			None
+		},
+		0x40..=0x7e => Some(2),
+		_ => None,
+	}
+}
+
+#[inline]
+pub const fn is_sgr_u16(seq: &[u16]) -> bool {
+	seq.len() >= 3 && seq[1] == b'[' as u16 && *seq.last().unwrap() == b'm' as u16
+}
+
+/// For an OSC 8 sequence (`ESC ] 8 ; params ; URI ST`), whether it opens a
+/// hyperlink (non-empty URI) or closes one; `None` for other sequences.
+fn osc8_opens(seq: &[u16]) -> Option<bool> {
+	if !seq.starts_with(&[ESC, b']' as u16, b'8' as u16, b';' as u16]) {
+		return None;
+	}
+	let body = &seq[4..];
+	let uri_start = body.iter().position(|&u| u == b';' as u16)? + 1;
+	let terminator = if seq.last() == Some(&0x07) { 1 } else { 2 };
+	Some(body.len().saturating_sub(terminator) > uri_start)
+}
+
+// ============================================================================
+// Grapheme / Width
+// ============================================================================
+
+#[inline]
+pub const fn ascii_cell_width_u16(u: u16, tab_width: usize) -> usize {
+	let b = u as u8;
+	match b {
+		b'\t' => tab_width,
+		0x20..=0x7e => 1,
+		_ => 0,
+	}
+}
+
+/// How wide characters that terminals disagree on are measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WidthPolicy {
+	/// East Asian ambiguous-width characters take two cells.
+	pub ambiguous_wide: bool,
+	/// Emoji presentation sequences (a character and U+FE0F) take two cells.
+	pub emoji_wide:     bool,
+}
+
+impl WidthPolicy {
+	pub const DEFAULT: Self = Self { ambiguous_wide: false, emoji_wide: true };
+}
+
+static WIDTH_POLICY_SOURCE: OnceLock<fn() -> WidthPolicy> = OnceLock::new();
+
+/// Make `source` decide the width policy in effect, e.g. a per-call settings
+/// snapshot of the embedding addon. Only the first call has an effect.
+pub fn set_width_policy_source(source: fn() -> WidthPolicy) {
+	let _ = WIDTH_POLICY_SOURCE.set(source);
+}
+
+/// The width policy in effect: [`WidthPolicy::DEFAULT`] unless a source was
+/// installed with [`set_width_policy_source`].
+pub fn width_policy() -> WidthPolicy {
+	WIDTH_POLICY_SOURCE
+		.get()
+		.map_or(WidthPolicy::DEFAULT, |source| source())
+}
+
+#[inline]
+fn char_width(c: char, policy: WidthPolicy) -> usize {
+	if policy.ambiguous_wide {
+		UnicodeWidthChar::width_cjk(c)
+	} else {
+		UnicodeWidthChar::width(c)
+	}
+	.unwrap_or(0)
+}
+
+#[inline]
+fn grapheme_width_str(g: &str, tab_width: usize, policy: WidthPolicy) -> usize {
+	if g == "\t" {
+		return tab_width;
+	}
+	let mut it = g.chars();
+	let Some(c0) = it.next() else {
+		return 0;
+	};
+	if it.next().is_none() {
+		return char_width(c0, policy);
+	}
+	// Measure the sequence as its text presentation; ZWJ sequences stay whole.
+	if !policy.emoji_wide && g.contains('\u{FE0F}') && !g.contains('\u{200D}') {
+		return g
+			.chars()
+			.filter(|&c| c != '\u{FE0F}')
+			.map(|c| char_width(c, policy))
+			.sum();
+	}
+	if policy.ambiguous_wide {
+		UnicodeWidthStr::width_cjk(g)
+	} else {
+		UnicodeWidthStr::width(g)
+	}
+}
+
+thread_local! {
+  static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Iterate graphemes in a non-ASCII UTF-16 segment.
+///
+/// Callback returns `true` to continue, `false` to stop early.
+#[inline]
+pub fn for_each_grapheme_u16_slow<F>(segment: &[u16], tab_width: usize, mut f: F) -> bool
+where
+	F: FnMut(&[u16], usize) -> bool,
+{
+	if segment.is_empty() {
+		return true;
+	}
+
+	let policy = width_policy();
+	SCRATCH.with_borrow_mut(|scratch| {
+		scratch.clear();
+		scratch.reserve(segment.len());
+
+		for r in std::char::decode_utf16(segment.iter().copied()) {
+			scratch.push(r.unwrap_or('\u{FFFD}'));
+		}
+
+		let mut utf16_pos = 0usize;
+		for g in scratch.graphemes(true) {
+			let w = grapheme_width_str(g, tab_width, policy);
+
+			let g_u16_len: usize = g.chars().map(|c| c.len_utf16()).sum();
+			let u16_slice = &segment[utf16_pos..utf16_pos + g_u16_len];
+			utf16_pos += g_u16_len;
+
+			if !f(u16_slice, w) {
+				return false;
+			}
+		}
+
+		true
+	})
+}
+
+/// Visible width, with early-exit if width exceeds `limit`.
+fn visible_width_u16_up_to(data: &[u16], limit: usize, tab_width: usize) -> (usize, bool) {
+	let mut width = 0usize;
+	let mut i = 0usize;
+	let len = data.len();
+
+	while i < len {
+		if data[i] == ESC {
+			if let Some(seq_len) = ansi_seq_len_u16(data, i) {
+				i += seq_len;
+				continue;
+			}
+			i += 1;
+			continue;
+		}
+
+		let start = i;
+		let mut is_ascii = true;
+		while i < len && data[i] != ESC {
+			if data[i] > 0x7f {
+				is_ascii = false;
+			}
+			i += 1;
+		}
+		let seg = &data[start..i];
+
+		if is_ascii {
+			for &u in seg {
+				width += ascii_cell_width_u16(u, tab_width);
+				if width > limit {
+					return (width, true);
+				}
+			}
+		} else {
+			let ok = for_each_grapheme_u16_slow(seg, tab_width, |_, w| {
+				width += w;
+				width <= limit
+			});
+			if !ok {
+				return (width, true);
+			}
+		}
+	}
+
+	(width, width > limit)
+}
+
+pub fn visible_width_u16(data: &[u16], tab_width: usize) -> usize {
+	visible_width_u16_up_to(data, usize::MAX, tab_width).0
+}
+
+/// Display width of `text` in terminal cells, `tab_width` cells per tab.
+pub fn display_width(text: &str, tab_width: usize) -> usize {
+	let data: Vec<u16> = text.encode_utf16().collect();
+	visible_width_u16(&data, tab_width)
+}
+
+/// Byte length of the longest grapheme-aligned prefix of `text` that fits in
+/// `max_width` cells.
+pub fn width_prefix_len(text: &str, max_width: usize, tab_width: usize) -> usize {
+	let policy = width_policy();
+	let mut width = 0;
+	for (offset, grapheme) in text.grapheme_indices(true) {
+		width += grapheme_width_str(grapheme, tab_width, policy);
+		if width > max_width {
+			return offset;
+		}
+	}
+	text.len()
+}
+
+// ============================================================================
+// wrapTextWithAnsi
+// ============================================================================
+
+#[inline]
+fn write_active_codes(state: &AnsiState, out: &mut Vec<u16>) {
+	if !state.is_empty() {
+		state.write_restore_u16(out);
+		if let Some(link) = &state.link {
+			out.extend_from_slice(link);
+		}
+	}
+}
+
+#[inline]
+fn write_line_end_reset(state: &AnsiState, out: &mut Vec<u16>) {
+	// A hyperlink must not run into whatever follows the line.
+	if state.link.is_some() {
+		out.extend_from_slice(OSC8_CLOSE);
+	}
+	// Line decorations would otherwise run on through the padding.
+	let ends: SmallVec<[u32; 3]> = [(ATTR_UNDERLINE, 24), (ATTR_STRIKE, 29), (ATTR_OVERLINE, 55)]
+		.into_iter()
+		.filter(|&(attr, _)| state.attrs & attr != 0)
+		.map(|(_, code)| code)
+		.collect();
+	if ends.is_empty() {
+		return;
+	}
+
+	out.extend_from_slice(&[ESC, b'[' as u16]);
+	for (i, &code) in ends.iter().enumerate() {
+		if i > 0 {
+			out.push(b';' as u16);
+		}
+		write_u32_u16(out, code);
+	}
+	out.push(b'm' as u16);
+}
+
+fn update_state_from_text(data: &[u16], state: &mut AnsiState) {
+	let mut i = 0usize;
+	while i < data.len() {
+		if data[i] == ESC
+			&& let Some(seq_len) = ansi_seq_len_u16(data, i)
+		{
+			state.apply_seq_u16(&data[i..i + seq_len]);
+			i += seq_len;
+			continue;
+		}
+		i += 1;
+	}
+}
+
+/// Close a hyperlink left open at the end of `out`, so a cut never strands
+/// one.
+fn close_open_link(out: &mut Vec<u16>) {
+	let mut state = AnsiState::new();
+	update_state_from_text(out, &mut state);
+	if state.link.is_some() {
+		out.extend_from_slice(OSC8_CLOSE);
+	}
+}
+
+fn token_is_whitespace(token: &[u16]) -> bool {
+	let mut i = 0usize;
+	while i < token.len() {
+		if token[i] == ESC
+			&& let Some(seq_len) = ansi_seq_len_u16(token, i)
+		{
+			i += seq_len;
+			continue;
+		}
+		if token[i] != b' ' as u16 {
+			return false;
+		}
+		i += 1;
+	}
+	true
+}
+
+fn trim_end_spaces_in_place(line: &mut Vec<u16>) {
+	while let Some(&last) = line.last() {
+		if last == b' ' as u16 {
+			line.pop();
+		} else {
+			break;
+		}
+	}
+}
+
+fn split_into_tokens_with_ansi(line: &[u16]) -> SmallVec<[Vec<u16>; 4]> {
+	let mut tokens = SmallVec::<[Vec<u16>; 4]>::new();
+	let mut current = Vec::<u16>::new();
+	let mut pending_ansi = SmallVec::<[u16; 32]>::new();
+	let mut in_whitespace = false;
+	let mut i = 0usize;
+
+	while i < line.len() {
+		if line[i] == ESC
+			&& let Some(seq_len) = ansi_seq_len_u16(line, i)
+		{
+			pending_ansi.extend_from_slice(&line[i..i + seq_len]);
+			i += seq_len;
+			continue;
+		}
+
+		let ch = line[i];
+		let char_is_space = ch == b' ' as u16;
+		if char_is_space != in_whitespace && !current.is_empty() {
+			tokens.push(current);
+			current = Vec::new();
+		}
+
+		if !pending_ansi.is_empty() {
+			current.extend_from_slice(&pending_ansi);
+			pending_ansi.clear();
+		}
+
+		in_whitespace = char_is_space;
+		current.push(ch);
+		i += 1;
+	}
+
+	if !pending_ansi.is_empty() {
+		current.extend_from_slice(&pending_ansi);
+	}
+
+	if !current.is_empty() {
+		tokens.push(current);
+	}
+
+	tokens
+}
+
+fn break_long_word(
+	word: &[u16],
+	width: usize,
+	tab_width: usize,
+	state: &mut AnsiState,
+) -> SmallVec<[Vec<u16>; 4]> {
+	let mut lines = SmallVec::<[Vec<u16>; 4]>::new();
+	let mut current_line = Vec::<u16>::new();
+	write_active_codes(state, &mut current_line);
+	let mut current_width = 0usize;
+	let mut i = 0usize;
+
+	while i < word.len() {
+		if word[i] == ESC
+			&& let Some(seq_len) = ansi_seq_len_u16(word, i)
+		{
+			let seq = &word[i..i + seq_len];
+			current_line.extend_from_slice(seq);
+			state.apply_seq_u16(seq);
+			i += seq_len;
+			continue;
+		}
+
+		let start = i;
+		let mut is_ascii = true;
+		while i < word.len() && word[i] != ESC {
+			if word[i] > 0x7f {
+				is_ascii = false;
+			}
+			i += 1;
+		}
+		let seg = &word[start..i];
+
+		if is_ascii {
+			for &u in seg {
+				let gw = ascii_cell_width_u16(u, tab_width);
+				if current_width + gw > width {
+					write_line_end_reset(state, &mut current_line);
+					lines.push(current_line);
+					current_line = Vec::new();
+					write_active_codes(state, &mut current_line);
+					current_width = 0;
+				}
+				current_line.push(u);
+				current_width += gw;
+			}
+		} else {
+			let _ = for_each_grapheme_u16_slow(seg, tab_width, |gu16, gw| {
+				if current_width + gw > width {
+					write_line_end_reset(state, &mut current_line);
+					lines.push(std::mem::take(&mut current_line));
+					write_active_codes(state, &mut current_line);
+					current_width = 0;
+				}
+				current_line.extend_from_slice(gu16);
+				current_width += gw;
+				true
+			});
+		}
+	}
+
+	if !current_line.is_empty() {
+		lines.push(current_line);
+	}
+
+	lines
+}
+
+fn wrap_single_line(line: &[u16], width: usize, tab_width: usize) -> SmallVec<[Vec<u16>; 4]> {
+	if line.is_empty() {
+		return smallvec![Vec::new()];
+	}
+
+	if visible_width_u16(line, tab_width) <= width {
+		return smallvec![line.to_vec()];
+	}
+
+	let tokens = split_into_tokens_with_ansi(line);
+	let mut wrapped = SmallVec::<[Vec<u16>; 4]>::new();
+	let mut current_line = Vec::<u16>::new();
+	let mut current_width = 0usize;
+	let mut state = AnsiState::new();
+
+	for token in tokens {
+		let token_width = visible_width_u16(&token, tab_width);
+		let is_whitespace = token_is_whitespace(&token);
+
+		if token_width > width && !is_whitespace {
+			if !current_line.is_empty() {
+				write_line_end_reset(&state, &mut current_line);
+				wrapped.push(current_line);
+				current_line = Vec::new();
+				current_width = 0;
+			}
+
+			let mut broken = break_long_word(&token, width, tab_width, &mut state);
+			if let Some(last) = broken.pop() {
+				wrapped.extend(broken);
+				current_line = last;
+				current_width = visible_width_u16(&current_line, tab_width);
+			}
+			continue;
+		}
+
+		let total_needed = current_width + token_width;
+		if total_needed > width && current_width > 0 {
+			let mut line_to_wrap = current_line;
+			trim_end_spaces_in_place(&mut line_to_wrap);
+			write_line_end_reset(&state, &mut line_to_wrap);
+			wrapped.push(line_to_wrap);
+
+			current_line = Vec::new();
+			write_active_codes(&state, &mut current_line);
+			if is_whitespace {
+				current_width = 0;
+			} else {
+				current_line.extend_from_slice(&token);
+				current_width = token_width;
+			}
+		} else {
+			current_line.extend_from_slice(&token);
+			current_width += token_width;
+		}
+
+		update_state_from_text(&token, &mut state);
+	}
+
+	if !current_line.is_empty() {
+		wrapped.push(current_line);
+	}
+
+	for line in &mut wrapped {
+		trim_end_spaces_in_place(line);
+	}
+
+	if wrapped.is_empty() {
+		wrapped.push(Vec::new());
+	}
+
+	wrapped
+}
+
+/// Visit each `\n`-separated paragraph as a `start..end` range together with
+/// the SGR state active at its start.
+pub fn for_each_paragraph<F>(text: &[u16], mut f: F)
+where
+	F: FnMut(usize, usize, &AnsiState),
+{
+	let mut state = AnsiState::new();
+	let mut line_start = 0usize;
+
+	for i in 0..=text.len() {
+		if i == text.len() || text[i] == b'\n' as u16 {
+			f(line_start, i, &state);
+			update_state_from_text(&text[line_start..i], &mut state);
+			line_start = i + 1;
+		}
+	}
+}
+
+/// Wrap the paragraph at `start..end`, re-opening the SGR `state` inherited
+/// from earlier paragraphs.
+pub fn wrap_paragraph(
+	text: &[u16],
+	start: usize,
+	end: usize,
+	state: &AnsiState,
+	width: usize,
+	tab_width: usize,
+) -> SmallVec<[Vec<u16>; 4]> {
+	let mut line_with_prefix: Vec<u16> = Vec::new();
+	if start > 0 {
+		write_active_codes(state, &mut line_with_prefix);
+	}
+	line_with_prefix.extend_from_slice(&text[start..end]);
+	wrap_single_line(&line_with_prefix, width, tab_width)
+}
+
+/// Wrap each `\n`-separated paragraph, calling `on_paragraph` with the source
+/// paragraph and its wrapped lines.
+pub fn wrap_paragraphs<F>(text: &[u16], width: usize, tab_width: usize, mut on_paragraph: F)
+where
+	F: FnMut(&[u16], SmallVec<[Vec<u16>; 4]>),
+{
+	for_each_paragraph(text, |start, end, state| {
+		on_paragraph(&text[start..end], wrap_paragraph(text, start, end, state, width, tab_width));
+	});
+}
+
+pub fn wrap_text_with_ansi_impl(
+	text: &[u16],
+	width: usize,
+	tab_width: usize,
+) -> SmallVec<[Vec<u16>; 4]> {
+	if text.is_empty() {
+		return smallvec![Vec::new()];
+	}
+
+	let mut result = SmallVec::<[Vec<u16>; 4]>::new();
+	wrap_paragraphs(text, width, tab_width, |_, wrapped| result.extend(wrapped));
+
+	if result.is_empty() {
+		result.push(Vec::new());
+	}
+
+	result
+}
+
+// ============================================================================
+// truncateToWidth
+// ============================================================================
+
+/// Truncated `text`, or `None` when it fits and needs no padding.
+pub fn truncate_to_width_impl(
+	text: &[u16],
+	max_width: usize,
+	ellipsis_kind: u8,
+	pad: bool,
+	tab_width: usize,
+) -> Option<Vec<u16>> {
+	// Fast path: early-exit width check
+	let (text_w, exceeded) = visible_width_u16_up_to(text, max_width, tab_width);
+	if !exceeded {
+		if !pad {
+			return None;
+		}
+
+		if text_w < max_width {
+			let mut out = Vec::with_capacity(text.len() + (max_width - text_w));
+			out.extend_from_slice(text);
+			out.resize(out.len() + (max_width - text_w), b' ' as u16);
+			return Some(out);
+		}
+
+		// Exactly fits and padding requested: the original is still fine.
+		return None;
+	}
+
+	// Map ellipsis kind to UTF-16 data and width
+	const ELLIPSIS_UNICODE: &[u16] = &[0x2026]; // "…"
+	const ELLIPSIS_ASCII: &[u16] = &[0x2e, 0x2e, 0x2e]; // "..."
+	const ELLIPSIS_OMIT: &[u16] = &[];
+
+	let (ellipsis, ellipsis_w): (&[u16], usize) = match ellipsis_kind {
+		0 => (ELLIPSIS_UNICODE, 1),
+		1 => (ELLIPSIS_ASCII, 3),
+		2 => (ELLIPSIS_OMIT, 0),
+		_ => (ELLIPSIS_UNICODE, 1), // Default to Unicode for invalid values
+	};
+
+	let target_w = max_width.saturating_sub(ellipsis_w);
+
+	// If ellipsis alone doesn't fit, return ellipsis cut to max_width
+	if target_w == 0 {
+		let mut out = Vec::with_capacity(ellipsis.len().min(max_width * 2));
+		let mut w = 0usize;
+		let _ = for_each_grapheme_u16_slow(ellipsis, tab_width, |gu16, gw| {
+			if w + gw > max_width {
+				return false;
+			}
+			out.extend_from_slice(gu16);
+			w += gw;
+			true
+		});
+
+		if pad && w < max_width {
+			out.resize(out.len() + (max_width - w), b' ' as u16);
+		}
+		return Some(out);
+	}
+
+	// Main truncation
+	let mut out = Vec::with_capacity(text.len().min(max_width * 2) + ellipsis.len() + 8);
+	let mut w = 0usize;
+	let mut i = 0usize;
+	let text_len = text.len();
+
+	let mut saw_sgr = false;
+
+	while i < text_len {
+		if text[i] == ESC {
+			if let Some(seq_len) = ansi_seq_len_u16(text, i) {
+				let seq = &text[i..i + seq_len];
+				out.extend_from_slice(seq);
+				if is_sgr_u16(seq) {
+					saw_sgr = true;
+				}
+				i += seq_len;
+				continue;
+			}
+			out.push(ESC);
+			i += 1;
+			continue;
+		}
+
+		let start = i;
+		let mut is_ascii = true;
+		while i < text_len && text[i] != ESC {
+			if text[i] > 0x7f {
+				is_ascii = false;
+			}
+			i += 1;
+		}
+		let seg = &text[start..i];
+
+		if is_ascii {
+			for &u in seg {
+				let gw = ascii_cell_width_u16(u, tab_width);
+				if w + gw > target_w {
+					break;
+				}
+				out.push(u);
+				w += gw;
+			}
+			if w >= target_w {
+				break;
+			}
+		} else {
+			let keep_going = for_each_grapheme_u16_slow(seg, tab_width, |gu16, gw| {
+				if w + gw > target_w {
+					return false;
+				}
+				out.extend_from_slice(gu16);
+				w += gw;
+				true
+			});
+			if !keep_going {
+				break;
+			}
+		}
+	}
+
+	// Only reset if we actually copied SGR codes into the output.
+	if saw_sgr {
+		out.extend_from_slice(&[ESC, b'[' as u16, b'0' as u16, b'm' as u16]);
+	}
+	close_open_link(&mut out);
+	out.extend_from_slice(ellipsis);
+
+	if pad {
+		let out_w = w + ellipsis_w;
+		if out_w < max_width {
+			out.resize(out.len() + (max_width - out_w), b' ' as u16);
+		}
+	}
+
+	Some(out)
+}
+
+// ============================================================================
+// sliceWithWidth
+// ============================================================================
+
+pub fn slice_with_width_impl(
+	line: &[u16],
+	start_col: usize,
+	length: usize,
+	strict: bool,
+	tab_width: usize,
+) -> (Vec<u16>, usize) {
+	let end_col = start_col.saturating_add(length);
+
+	let mut out = Vec::with_capacity(length * 2);
+	let mut out_w = 0usize;
+
+	let mut current_col = 0usize;
+	let mut i = 0usize;
+	let line_len = line.len();
+
+	// Store pending ANSI ranges (pos, len) to avoid copying until needed
+	let mut pending_ansi: SmallVec<[(usize, usize); 4]> = SmallVec::new();
+
+	while i < line_len && current_col < end_col {
+		if line[i] == ESC {
+			if let Some(seq_len) = ansi_seq_len_u16(line, i) {
+				if current_col >= start_col {
+					out.extend_from_slice(&line[i..i + seq_len]);
+				} else {
+					pending_ansi.push((i, seq_len));
+				}
+				i += seq_len;
+				continue;
+			}
+			if current_col >= start_col {
+				out.push(ESC);
+			}
+			i += 1;
+			continue;
+		}
+
+		let start = i;
+		let mut is_ascii = true;
+		while i < line_len && line[i] != ESC {
+			if line[i] > 0x7f {
+				is_ascii = false;
+			}
+			i += 1;
+		}
+		let seg = &line[start..i];
+
+		if is_ascii {
+			for &u in seg {
+				if current_col >= end_col {
+					break;
+				}
+				let gw = ascii_cell_width_u16(u, tab_width);
+				let in_range = current_col >= start_col;
+				let fits = !strict || current_col + gw <= end_col;
+
+				if in_range && fits {
+					if !pending_ansi.is_empty() {
+						for &(p, l) in &pending_ansi {
+							out.extend_from_slice(&line[p..p + l]);
+						}
+						pending_ansi.clear();
+					}
+					out.push(u);
+					out_w += gw;
+				}
+				current_col += gw;
+			}
+		} else {
+			let _ = for_each_grapheme_u16_slow(seg, tab_width, |gu16, gw| {
+				if current_col >= end_col {
+					return false;
+				}
+
+				let in_range = current_col >= start_col;
+				let fits = !strict || current_col + gw <= end_col;
+
+				if in_range && fits {
+					if !pending_ansi.is_empty() {
+						for &(p, l) in &pending_ansi {
+							out.extend_from_slice(&line[p..p + l]);
+						}
+						pending_ansi.clear();
+					}
+					out.extend_from_slice(gu16);
+					out_w += gw;
+				}
+
+				current_col += gw;
+				current_col < end_col
+			});
+		}
+	}
+
+	// Include trailing ANSI sequences (e.g., reset codes) that immediately follow
+	while i < line.len() {
+		if line[i] == ESC
+			&& let Some(len) = ansi_seq_len_u16(line, i)
+		{
+			out.extend_from_slice(&line[i..i + len]);
+			i += len;
+			continue;
+		}
+		break;
+	}
+
+	close_open_link(&mut out);
+	(out, out_w)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn to_u16(s: &str) -> Vec<u16> {
+		s.encode_utf16().collect()
+	}
+
+	#[test]
+	fn test_visible_width() {
+		assert_eq!(visible_width_u16(&to_u16("hello"), DEFAULT_TAB_WIDTH), 5);
+		assert_eq!(visible_width_u16(&to_u16("\x1b[31mhello\x1b[0m"), DEFAULT_TAB_WIDTH), 5);
+		assert_eq!(visible_width_u16(&to_u16("\x1b[38;5;196mred\x1b[0m"), DEFAULT_TAB_WIDTH), 3);
+		assert_eq!(visible_width_u16(&to_u16("a\tb"), DEFAULT_TAB_WIDTH), 1 + DEFAULT_TAB_WIDTH + 1);
+	}
+
+	#[test]
+	fn test_ansi_detection() {
+		let data = to_u16("\x1b[31mred\x1b[0m");
+		assert_eq!(ansi_seq_len_u16(&data, 0), Some(5)); // \x1b[31m
+		assert_eq!(ansi_seq_len_u16(&data, 8), Some(4)); // \x1b[0m
+	}
+
+	#[test]
+	fn test_slice_basic() {
+		let data = to_u16("hello world");
+		let (out, width) = slice_with_width_impl(&data, 0, 5, false, DEFAULT_TAB_WIDTH);
+		assert_eq!(String::from_utf16_lossy(&out), "hello");
+		assert_eq!(width, 5);
+	}
+
+	#[test]
+	fn test_slice_with_ansi() {
+		let data = to_u16("\x1b[31mhello\x1b[0m world");
+		let (out, width) = slice_with_width_impl(&data, 0, 5, false, DEFAULT_TAB_WIDTH);
+		assert_eq!(String::from_utf16_lossy(&out), "\x1b[31mhello\x1b[0m");
+		assert_eq!(width, 5);
+	}
+
+	#[test]
+	fn test_ascii_fast_path() {
+		fn is_ascii(seg: &[u16]) -> bool {
+			seg.iter().all(|&u| u <= 0x7f)
+		}
+
+		let ascii = to_u16("hello world 12345");
+		assert!(is_ascii(&ascii));
+
+		let non_ascii = to_u16("hello 世界");
+		assert!(!is_ascii(&non_ascii));
+	}
+
+	#[test]
+	fn test_early_exit() {
+		let data = to_u16(&"a]b".repeat(1000));
+		let (w, exceeded) = visible_width_u16_up_to(&data, 10, DEFAULT_TAB_WIDTH);
+		assert!(exceeded);
+		assert!(w > 10);
+	}
+
+	#[test]
+	fn test_wrap_text_with_ansi_preserves_color() {
+		let data = to_u16("\x1b[38;2;156;163;176mhello world\x1b[0m");
+		let lines = wrap_text_with_ansi_impl(&data, 5, DEFAULT_TAB_WIDTH);
+		assert_eq!(lines.len(), 2);
+		let first = String::from_utf16_lossy(&lines[0]);
+		let second = String::from_utf16_lossy(&lines[1]);
+		assert!(first.starts_with("\x1b[38;2;156;163;176m"));
+		assert!(second.starts_with("\x1b[38;2;156;163;176m"));
+		assert!(second.contains("world"));
+	}
+
+	#[test]
+	fn test_hyperlink_reopened_across_wraps_and_closed_in_slices() {
+		let open = "\x1b]8;;https://example.com\x1b\\";
+		let close = "\x1b]8;;\x1b\\";
+		let data = to_u16(&format!("{open}hello world{close} done"));
+		let lines: Vec<String> = wrap_text_with_ansi_impl(&data, 5, DEFAULT_TAB_WIDTH)
+			.iter()
+			.map(|line| String::from_utf16_lossy(line))
+			.collect();
+		assert_eq!(lines[0], format!("{open}hello{close}"));
+		assert!(lines[1].starts_with(open));
+		assert!(lines[1].contains("world"));
+
+		let (out, _) = slice_with_width_impl(&data, 0, 3, false, DEFAULT_TAB_WIDTH);
+		assert_eq!(String::from_utf16_lossy(&out), format!("{open}hel{close}"));
+		let bel_close = to_u16("\x1b]8;;\x07");
+		assert_eq!(osc8_opens(&bel_close), Some(false));
+	}
+
+	#[test]
+	fn test_wrap_restores_extended_sgr() {
+		let data = to_u16("\x1b[4:3;58:2::255:0:0;53;2mhello world\x1b[0m");
+		let lines: Vec<String> = wrap_text_with_ansi_impl(&data, 5, DEFAULT_TAB_WIDTH)
+			.iter()
+			.map(|line| String::from_utf16_lossy(line))
+			.collect();
+		assert!(lines[0].ends_with("hello\x1b[24;55m"));
+		assert!(lines[1].starts_with("\x1b[2;4:3;53;58;2;255;0;0mworld"));
+
+		let mut state = AnsiState::new();
+		state.apply_sgr_u16(&to_u16("4:3;58;5;9;22;4:0;59"));
+		assert!(state.sgr_is_empty());
+		state.apply_sgr_u16(&to_u16("1;;38;2;1;2;3"));
+		let mut out = Vec::new();
+		state.write_restore_u16(&mut out);
+		assert_eq!(String::from_utf16_lossy(&out), "\x1b[1;38;2;1;2;3m");
+	}
+
+	#[test]
+	fn test_wrap_text_with_ansi_resets_strike_without_resetting_colors() {
+		let data =
+			to_u16("\x1b[38;5;196m\x1b[48;5;236m\x1b[9mstrikethrough content wraps\x1b[29m\x1b[0m");
+		let lines = wrap_text_with_ansi_impl(&data, 12, DEFAULT_TAB_WIDTH);
+		assert!(lines.len() > 1);
+
+		for line in &lines[..lines.len() - 1] {
+			let line_text = String::from_utf16_lossy(line);
+			if line_text.contains("\x1b[9m") {
+				assert!(line_text.ends_with("\x1b[29m"));
+				assert!(!line_text.ends_with("\x1b[0m"));
+			}
+		}
+
+		for line in &lines[1..] {
+			let line_text = String::from_utf16_lossy(line);
+			assert!(line_text.contains("38;5;196"));
+			assert!(line_text.contains("48;5;236"));
+		}
+	}
+}
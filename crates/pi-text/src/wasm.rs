@@ -0,0 +1,86 @@
+//! wasm-bindgen exports of the text layout functions (`wasm` feature).
+//!
+//! Each export converts to UTF-16 and calls the same implementation as the
+//! N-API function of the same name in `pi-natives`, so a browser build lays
+//! out text exactly like the addon.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+	clamp_tab_width, slice_with_width_impl, truncate_to_width_impl, visible_width_u16,
+	wrap_text_with_ansi_impl,
+};
+
+fn to_utf16(text: &str) -> Vec<u16> {
+	text.encode_utf16().collect()
+}
+
+/// Visible width of `text` in terminal cells, skipping ANSI escapes.
+#[wasm_bindgen(js_name = "visibleWidth")]
+#[must_use]
+pub fn visible_width(text: &str, tab_width: Option<u32>) -> u32 {
+	let width = visible_width_u16(&to_utf16(text), clamp_tab_width(tab_width));
+	u32::try_from(width).unwrap_or(u32::MAX)
+}
+
+/// Wrap text to a visible width, carrying active SGR codes across line breaks.
+#[wasm_bindgen(js_name = "wrapTextWithAnsi")]
+#[must_use]
+pub fn wrap_text_with_ansi(text: &str, width: u32, tab_width: Option<u32>) -> Vec<String> {
+	let tab_width = clamp_tab_width(tab_width);
+	wrap_text_with_ansi_impl(&to_utf16(text), width as usize, tab_width)
+		.iter()
+		.map(|line| String::from_utf16_lossy(line))
+		.collect()
+}
+
+/// Truncate text to a visible width, preserving ANSI codes.
+///
+/// `ellipsis_kind`: 0 = "…", 1 = "...", 2 = "" (omit).
+#[wasm_bindgen(js_name = "truncateToWidth")]
+#[must_use]
+pub fn truncate_to_width(
+	text: &str,
+	max_width: u32,
+	ellipsis_kind: u8,
+	pad: bool,
+	tab_width: Option<u32>,
+) -> String {
+	let tab_width = clamp_tab_width(tab_width);
+	truncate_to_width_impl(&to_utf16(text), max_width as usize, ellipsis_kind, pad, tab_width)
+		.map_or_else(|| text.to_string(), |out| String::from_utf16_lossy(&out))
+}
+
+/// A slice of visible columns and its width.
+#[wasm_bindgen]
+pub struct SliceResult {
+	/// Selected text, ANSI codes included.
+	#[wasm_bindgen(getter_with_clone)]
+	pub text:  String,
+	/// Visible width of the slice in terminal cells.
+	pub width: u32,
+}
+
+/// Slice a range of visible columns from a line.
+#[wasm_bindgen(js_name = "sliceWithWidth")]
+#[must_use]
+pub fn slice_with_width(
+	line: &str,
+	start_col: u32,
+	length: u32,
+	strict: bool,
+	tab_width: Option<u32>,
+) -> SliceResult {
+	let tab_width = clamp_tab_width(tab_width);
+	let (out, width) = slice_with_width_impl(
+		&to_utf16(line),
+		start_col as usize,
+		length as usize,
+		strict,
+		tab_width,
+	);
+	SliceResult {
+		text:  String::from_utf16_lossy(&out),
+		width: u32::try_from(width).unwrap_or(u32::MAX),
+	}
+}
@@ -5,21 +5,28 @@
 import { native } from "../native";
 import type { AstFindOptions, AstFindResult, AstReplaceOptions, AstReplaceResult } from "./types";
 
+export type { DiffHunk, FileDiff } from "../bindings";
 export type {
+	AstCacheStats,
+	AstFileMatches,
 	AstFindMatch,
 	AstFindOptions,
 	AstFindResult,
+	AstRelationalRule,
 	AstReplaceChange,
 	AstReplaceFileChange,
 	AstReplaceOptions,
 	AstReplaceResult,
+	AstRule,
 	AstStrictness,
 } from "./types";
 
 export async function astGrep(options: AstFindOptions): Promise<AstFindResult> {
-	return native.astGrep(options);
+	return native.astGrep({ ...options, rules: options.rules?.map(rule => JSON.stringify(rule)) });
 }
 
 export async function astEdit(options: AstReplaceOptions): Promise<AstReplaceResult> {
 	return native.astEdit(options);
 }
+
+export const { astCacheInvalidate, astCacheStats } = native;
@@ -2,21 +2,82 @@
  * Types for native ast-grep operations.
  */
 
-import type { Cancellable } from "../bindings";
+import type {
+	BlameInfo,
+	Cancellable,
+	ChangedScope,
+	ChangeSet,
+	FileDiff,
+	IgnoreSource,
+	TraceSpan,
+} from "../bindings";
+import type { ContextLine } from "../grep/types";
 
 export type AstStrictness = "cst" | "smart" | "ast" | "relaxed" | "signature";
 
+/** An ast-grep rule object; every key present must hold. */
+export interface AstRule {
+	/** Pattern source, or a pattern parsed in `context` with `selector` picking the node to match. */
+	pattern?: string | { context: string; selector?: string; strictness?: AstStrictness };
+	/** Tree-sitter node kind (e.g. "call_expression"). */
+	kind?: string;
+	/** Regex matched against the node text. */
+	regex?: string;
+	/** Some ancestor matches. */
+	inside?: AstRelationalRule;
+	/** Some descendant matches. */
+	has?: AstRelationalRule;
+	/** Some preceding sibling matches. */
+	follows?: AstRelationalRule;
+	/** Some following sibling matches. */
+	precedes?: AstRelationalRule;
+	/** All sub-rules match. */
+	all?: AstRule[];
+	/** Any sub-rule matches. */
+	any?: AstRule[];
+	/** The sub-rule does not match. */
+	not?: AstRule;
+}
+
+/** A rule for a related node, with how far to search for it. */
+export interface AstRelationalRule extends AstRule {
+	/** "neighbor" (default) checks only the parent, direct children, or adjacent sibling; "end" searches all the way; a rule stops at the first node it matches. */
+	stopBy?: "neighbor" | "end" | AstRule;
+	/** Only consider the node in this field of its parent (`inside` and `has` only). */
+	field?: string;
+}
+
 export interface AstFindOptions extends Cancellable {
 	patterns?: string[];
+	/** ast-grep rule objects for structure patterns cannot express, searched alongside `patterns`. */
+	rules?: AstRule[];
 	lang?: string;
 	path?: string;
 	glob?: string;
+	/** Match `glob` case-sensitively; by default, matching ignores case unless `glob` contains an uppercase letter. */
+	globCaseSensitive?: boolean;
+	/** Search only files changed in git. */
+	changedOnly?: ChangedScope;
+	ignoreSources?: IgnoreSource[];
+	ignoreFiles?: string[];
 	selector?: string;
 	strictness?: AstStrictness;
 	limit?: number;
 	offset?: number;
 	includeMeta?: boolean;
+	/** Source lines to include before and after each match. */
 	context?: number;
+	/**
+	 * Also search embedded code: `<script>`/`<style>` blocks in HTML, Vue, and Svelte files and fenced code blocks in
+	 * Markdown. With `lang`, only blocks in that language are searched.
+	 */
+	injections?: boolean;
+	/** Return matches grouped by file in `files`; `limit` and `offset` then count files instead of matches. */
+	groupByFile?: boolean;
+	/** Annotate each match with the last commit that touched its first line, one `git blame` per file. */
+	blame?: boolean;
+	/** Attach per-phase timings to the result as `trace`. */
+	trace?: boolean;
 }
 
 export interface AstFindMatch {
@@ -29,6 +90,14 @@ export interface AstFindMatch {
 	endLine: number;
 	endColumn: number;
 	metaVariables?: Record<string, string>;
+	/** Language of the embedded block the match is in (`injections` only); positions are in the host file. */
+	embeddedLang?: string;
+	/** Last commit that touched the match's first line (`blame: true` only). */
+	blame?: BlameInfo;
+	/** Lines before the match (`context` only). */
+	contextBefore?: ContextLine[];
+	/** Lines after the match (`context` only). */
+	contextAfter?: ContextLine[];
 }
 
 export interface AstFindResult {
@@ -38,6 +107,18 @@ export interface AstFindResult {
 	filesSearched: number;
 	limitReached: boolean;
 	parseErrors?: string[];
+	/** Matches grouped by file (`groupByFile` only; `matches` is then empty). */
+	files?: AstFileMatches[];
+	/** Per-phase timings, when requested with `trace`. */
+	trace?: TraceSpan;
+}
+
+/** Matches in one file (`groupByFile`). */
+export interface AstFileMatches {
+	path: string;
+	/** Matches in the file. */
+	count: number;
+	matches: AstFindMatch[];
 }
 
 export interface AstReplaceOptions extends Cancellable {
@@ -45,12 +126,18 @@ export interface AstReplaceOptions extends Cancellable {
 	lang?: string;
 	path?: string;
 	glob?: string;
+	/** Match `glob` case-sensitively; by default, matching ignores case unless `glob` contains an uppercase letter. */
+	globCaseSensitive?: boolean;
+	ignoreSources?: IgnoreSource[];
+	ignoreFiles?: string[];
 	selector?: string;
 	strictness?: AstStrictness;
 	dryRun?: boolean;
 	maxReplacements?: number;
 	maxFiles?: number;
 	failOnParseError?: boolean;
+	/** Return a unified diff per touched file, e.g. to review a dry run before applying it. */
+	returnDiff?: boolean;
 }
 
 export interface AstReplaceChange {
@@ -80,11 +167,39 @@ export interface AstReplaceResult {
 	applied: boolean;
 	limitReached: boolean;
 	parseErrors?: string[];
+	/** Unified diff per touched file, in path order (with `returnDiff`). */
+	diffs?: FileDiff[];
+	/** Previewed changes for `applyChangeSet()` (dry runs only). */
+	changeSet?: ChangeSet;
+}
+
+/** Snapshot of the parsed-tree cache shared by `astGrep` and `astEdit`. */
+export interface AstCacheStats {
+	/** Cached files. */
+	entries: number;
+	/** Source bytes held by cached files. */
+	sourceBytes: number;
+	/** Lookups served from the cache since startup. */
+	hits: number;
+	/** Lookups that read and parsed the file since startup. */
+	misses: number;
+	/** Maximum cached files (`AST_CACHE_MAX_ENTRIES`). */
+	maxEntries: number;
+	/** Maximum cached source bytes (`AST_CACHE_MAX_BYTES`). */
+	maxBytes: number;
 }
 
 declare module "../bindings" {
 	interface NativeBindings {
-		astGrep(options: AstFindOptions): Promise<AstFindResult>;
+		/** Rules are passed as JSON text. */
+		astGrep(options: Omit<AstFindOptions, "rules"> & { rules?: string[] }): Promise<AstFindResult>;
 		astEdit(options: AstReplaceOptions): Promise<AstReplaceResult>;
+		/**
+		 * Drop cached syntax trees for files under `path`, or all of them. Changed files are detected by modification
+		 * time and size, so this is only needed to release memory.
+		 */
+		astCacheInvalidate(path?: string): number;
+		/** Report what the parsed-tree cache holds and how often it was hit. */
+		astCacheStats(): AstCacheStats;
 	}
 }
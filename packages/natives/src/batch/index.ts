@@ -0,0 +1,9 @@
+/**
+ * Several grep, glob, and astFind searches over one tree in a single pass.
+ */
+
+import { native } from "../native";
+
+export type { BatchQueryOptions, BatchQueryResult, QueryResult, QuerySpec } from "./types";
+
+export const { batchQuery } = native;
@@ -0,0 +1,67 @@
+/**
+ * Types for running several searches over one tree in a single pass.
+ */
+
+import type { AstFindResult } from "../ast/types";
+import type { Cancellable } from "../bindings";
+import type { GlobResult } from "../glob/types";
+import type { GrepResult } from "../grep/types";
+
+/** One search of a `batchQuery()`. */
+export interface QuerySpec {
+	/** Kind of search. */
+	kind: "grep" | "glob" | "astFind";
+	/** Regex (grep) or glob pattern (glob). */
+	pattern?: string;
+	/** ast-grep patterns (astFind). */
+	patterns?: string[];
+	/** Language of astFind patterns; inferred from each file's extension by default. */
+	lang?: string;
+	/** Glob filter on the paths of searched files (grep, astFind). */
+	glob?: string;
+	/** Case-insensitive search (grep). */
+	ignoreCase?: boolean;
+	/** Let matches span lines (grep). */
+	multiline?: boolean;
+	/** Lines of context before and after each match (grep). */
+	context?: number;
+	/** Maximum matches to return (default: all for grep and glob, 50 for astFind). */
+	limit?: number;
+}
+
+/** Options for `batchQuery()`, shared by all of its queries. */
+export interface BatchQueryOptions extends Cancellable {
+	/** Include hidden files (default: false). */
+	hidden?: boolean;
+	/** Respect .gitignore files (default: true). */
+	gitignore?: boolean;
+	/** Enable shared filesystem scan cache (default: false). */
+	cache?: boolean;
+}
+
+/** Result of one query; only the field of its kind is set. */
+export interface QueryResult {
+	grep?: GrepResult;
+	glob?: GlobResult;
+	astFind?: AstFindResult;
+}
+
+/** Result of `batchQuery()`. */
+export interface BatchQueryResult {
+	/** Results in query order. */
+	results: QueryResult[];
+	/** Files read from disk, each at most once for all queries. */
+	filesRead: number;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Run several grep, glob, and astFind queries over `root` with one scan, reading each file at most once.
+		 * @param root Directory to search.
+		 * @param queries Searches to run; results come back in the same order.
+		 * @param options Scan and cancellation options shared by all queries.
+		 */
+		batchQuery(root: string, queries: QuerySpec[], options?: BatchQueryOptions): Promise<BatchQueryResult>;
+	}
+}
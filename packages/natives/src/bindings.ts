@@ -6,12 +6,157 @@
 /** Callback type for threadsafe functions from N-API. */
 export type TsFunc<T> = (error: Error | null, value: T) => void;
 
+/** Cancellation handle shared across multiple native calls. */
+export interface CancellationScope {
+	/** Abort every operation started with this scope. */
+	cancel(): void;
+	/** Whether `cancel()` has been called. */
+	readonly cancelled: boolean;
+}
+
 /** Options for cancellable operations. */
 export interface Cancellable {
 	/** Timeout in milliseconds for the operation. */
 	timeoutMs?: number;
 	/** Abort signal for cancelling the operation. */
 	signal?: AbortSignal;
+	/** Shared cancellation scope; cancelling it aborts the operation. */
+	scope?: CancellationScope;
+}
+
+/** A timed phase of a traced call (`trace: true`). */
+export interface TraceSpan {
+	/** Phase name (e.g. "scan", "filter", "match", "serialize"). */
+	name: string;
+	/** Start offset from the beginning of the call, in milliseconds. */
+	startMs: number;
+	/** Phase duration in milliseconds. */
+	durationMs: number;
+	/** Nested phases, in start order. */
+	children: TraceSpan[];
+}
+
+/** How streamed `onMatch` results are batched. */
+export interface StreamOptions {
+	/** Most items per batch (default: 64). */
+	maxBatch?: number;
+	/** Longest an item waits before its batch is sent, in milliseconds (default: 16). */
+	maxDelayMs?: number;
+}
+
+/** A batch of streamed results. Every stream ends with a `done` batch. */
+export interface StreamBatch<T> {
+	/** Batch number, counting from 0; a gap means a batch was dropped. */
+	seq: number;
+	matches: T[];
+	/** Whether this is the last batch of the call. */
+	done: boolean;
+	/** Items dropped so far because the JS queue refused their batch. */
+	dropped: number;
+}
+
+/** A hunk of a unified patch. */
+export interface DiffHunk {
+	/** First old line covered (1-based; the line before the hunk when it covers no old lines). */
+	oldStart: number;
+	/** Number of old lines covered. */
+	oldLines: number;
+	/** First new line covered, numbered like `oldStart`. */
+	newStart: number;
+	/** Number of new lines covered. */
+	newLines: number;
+	/** Hunk text: the `@@` header followed by ` `, `-`, and `+` lines. */
+	text: string;
+}
+
+/** Unified patch for one file. */
+export interface FileDiff {
+	/** File path, as shown in the `---`/`+++` headers. */
+	path: string;
+	/** Complete patch: `--- a/path`, `+++ b/path`, then every hunk. */
+	patch: string;
+	/** Hunks in file order. */
+	hunks: DiffHunk[];
+	/** Lines added. */
+	additions: number;
+	/** Lines removed. */
+	deletions: number;
+}
+
+/** One file in a change set. */
+export interface ChangeSetFile {
+	/** File path as reported by the writer (usually relative to its root). */
+	path: string;
+	/** Whether the change edits, creates, or deletes the file. */
+	status: "modified" | "created" | "deleted";
+	/** xxh3 hash (16 hex digits) of the content the change was computed from. */
+	beforeHash?: string;
+	/** xxh3 hash of the content after the change. */
+	afterHash?: string;
+	/** Unified diff of the change. */
+	diff: FileDiff;
+}
+
+/** A file a change set could not cover. */
+export interface ChangeSetConflict {
+	/** File path as reported by the writer. */
+	path: string;
+	/** Why the file was left out or could not be applied. */
+	reason: string;
+}
+
+/** Previewed file changes from a writer's dry run, applied later with `applyChangeSet()`. */
+export interface ChangeSet {
+	/** Id to pass to `applyChangeSet()` or `discardChangeSet()`. */
+	id: string;
+	/** Changed files, in path order. */
+	files: ChangeSetFile[];
+	/** Lines added across all files. */
+	additions: number;
+	/** Lines removed across all files. */
+	deletions: number;
+	/** Files left out of the set. */
+	conflicts: ChangeSetConflict[];
+}
+
+/** How a command ended. */
+export type ExitKind = "success" | "failure" | "killed" | "timeout" | "stopped";
+
+/** Structured exit status of a PTY or shell command. */
+export interface ExitInfo {
+	/** Exit code, when the command exited on its own. */
+	code?: number;
+	/** Terminating or stopping signal name (e.g. "SIGKILL"), when known. */
+	signal?: string;
+	/** Whether the command dumped core (Unix only). */
+	coreDumped: boolean;
+	/** Classification, consistent across Unix and Windows. */
+	kind: ExitKind;
+}
+
+/** Standard per-directory ignore sources honored by filesystem scans. */
+export type IgnoreSource = "gitignore" | "ignore" | "rgignore";
+
+/**
+ * Git changes a search can be restricted to: uncommitted work including untracked files ("working"), the index
+ * ("staged"), or everything changed since the branch forked from a ref ("branch:<ref>").
+ */
+export type ChangedScope = "working" | "staged" | `branch:${string}`;
+
+/** Last commit that touched a line, from `git blame`. */
+export interface BlameInfo {
+	/** Abbreviated commit hash. */
+	commit: string;
+	/** Author name. */
+	author: string;
+	/** Author email, without angle brackets. */
+	authorEmail: string;
+	/** Author time in milliseconds since the Unix epoch. */
+	time: number;
+	/** First line of the commit message. */
+	summary: string;
+	/** Whether the line has uncommitted changes. */
+	uncommitted: boolean;
 }
 
 /**
@@ -20,4 +165,6 @@ export interface Cancellable {
  */
 export interface NativeBindings {
 	cancelWork(id: number): void;
+	/** Optional cargo features compiled into the addon (e.g. "shell"). */
+	nativeFeatures(): string[];
 }
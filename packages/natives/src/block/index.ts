@@ -0,0 +1,9 @@
+/**
+ * Exact multi-line block search for "replace this block" edits.
+ */
+
+import { native } from "../native";
+
+export type { BlockMatch, FindBlockOptions } from "./types";
+
+export const { findBlock } = native;
@@ -0,0 +1,33 @@
+/**
+ * Types for exact block search.
+ */
+
+/** Options for `findBlock()`. */
+export interface FindBlockOptions {
+	/** Ignore leading whitespace on each line (default: false). */
+	ignoreIndentation?: boolean;
+	/** Collapse runs of spaces and tabs to one space and ignore trailing whitespace on each line (default: false). */
+	normalizeWhitespace?: boolean;
+}
+
+/** A run of lines matching the block. */
+export interface BlockMatch {
+	/** Byte offset (UTF-8) of the first matched line's start. */
+	start: number;
+	/** Byte offset (UTF-8) of the last matched line's end, before its line break. */
+	end: number;
+	/** First matched line (1-indexed). */
+	startLine: number;
+	/** Last matched line (1-indexed, inclusive). */
+	endLine: number;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Find every run of whole lines in `fileText` that matches `block`; line endings and a trailing line break on
+		 * `block` are ignored, and overlapping runs are all reported.
+		 */
+		findBlock(fileText: string, block: string, options?: FindBlockOptions): BlockMatch[];
+	}
+}
@@ -0,0 +1,10 @@
+/**
+ * Change sets previewed by dry runs of the mutating APIs (e.g. `astEdit`), applied atomically by id.
+ */
+
+import { native } from "../native";
+
+export type { ChangeSet, ChangeSetConflict, ChangeSetFile } from "../bindings";
+export type { ApplyChangeSetResult } from "./types";
+
+export const { applyChangeSet, discardChangeSet } = native;
@@ -0,0 +1,28 @@
+/**
+ * Types for applying previewed change sets.
+ */
+
+import type { ChangeSetConflict } from "../bindings";
+
+/** Outcome of `applyChangeSet()`. */
+export interface ApplyChangeSetResult {
+	/** Whether the files were written. */
+	applied: boolean;
+	/** Number of files written or deleted. */
+	filesWritten: number;
+	/** Files that changed since the preview; nothing is written when any exist, and the set stays pending. */
+	conflicts: ChangeSetConflict[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Write a previewed change set atomically, refusing it when any file changed since the preview.
+		 * @param id Id from a dry run's `changeSet`.
+		 * @throws When the id is unknown or expired, or read-only mode is on.
+		 */
+		applyChangeSet(id: string): Promise<ApplyChangeSetResult>;
+		/** Forget a previewed change set; returns whether it was pending. */
+		discardChangeSet(id: string): boolean;
+	}
+}
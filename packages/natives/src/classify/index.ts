@@ -0,0 +1,9 @@
+/**
+ * Source/test/fixture/config/docs classification shared by search filters and ranking.
+ */
+
+import { native } from "../native";
+
+export type { PathClass } from "./types";
+
+export const { classifyPath, classifyPaths } = native;
@@ -0,0 +1,21 @@
+/**
+ * Types for path classification.
+ */
+
+/** Role of a file in its project. */
+export type PathClass = "source" | "test" | "fixture" | "config" | "docs";
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Classify a path as source, test, fixture, config, or docs by ecosystem naming conventions.
+		 * @param path File path, ideally relative to the project root.
+		 */
+		classifyPath(path: string): PathClass;
+		/**
+		 * Classify many paths in one call.
+		 * @param paths File paths, ideally relative to the project root.
+		 */
+		classifyPaths(paths: string[]): PathClass[];
+	}
+}
@@ -0,0 +1,9 @@
+/**
+ * Per-extension comment syntax registry used by comment-aware native features.
+ */
+
+import { native } from "../native";
+
+export type { CommentSyntax, CommentSyntaxEntry } from "./types";
+
+export const { registerCommentSyntax, getCommentSyntax, listCommentSyntaxes } = native;
@@ -0,0 +1,37 @@
+/**
+ * Types for the per-extension comment syntax registry.
+ */
+
+/** Comment markers for one file type. */
+export interface CommentSyntax {
+	/** Line comment marker (e.g. "//"). */
+	line?: string;
+	/** Block comment opener (e.g. "/*"). */
+	blockStart?: string;
+	/** Block comment closer (e.g. "*\/"). */
+	blockEnd?: string;
+}
+
+/** A registry entry, as returned by `listCommentSyntaxes()`. */
+export interface CommentSyntaxEntry {
+	/** Normalized extension (lowercase, no leading dot). */
+	ext: string;
+	syntax: CommentSyntax;
+	/** Whether the entry was registered at runtime (vs. built in). */
+	custom: boolean;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Register (or override) comment markers for a file extension.
+		 * @param ext Extension, with or without leading dot.
+		 * @param syntax Line and/or block comment markers.
+		 */
+		registerCommentSyntax(ext: string, syntax: CommentSyntax): void;
+		/** Get the effective comment markers for an extension, if known. */
+		getCommentSyntax(ext: string): CommentSyntax | null;
+		/** List every known extension with its effective comment markers. */
+		listCommentSyntaxes(): CommentSyntaxEntry[];
+	}
+}
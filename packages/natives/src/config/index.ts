@@ -0,0 +1,9 @@
+/**
+ * Effective native configuration, for debugging, and the workspace root setting.
+ */
+
+import { native } from "../native";
+
+export type { EffectiveConfig } from "./types";
+
+export const { getEffectiveConfig, setWorkspaceRoot } = native;
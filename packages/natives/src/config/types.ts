@@ -0,0 +1,48 @@
+/**
+ * Types for the effective native configuration.
+ */
+
+import type { TextWidthPolicy } from "../text/types";
+import type { ConcurrencyPolicy } from "../work/types";
+
+/** Live settings, from `getEffectiveConfig()`. */
+export interface EffectiveConfig {
+	/** Whether read-only mode is enabled. */
+	readOnly: boolean;
+	/** Background work throttling policy. */
+	concurrency: Required<ConcurrencyPolicy>;
+	/** Cell widths of ambiguous characters and emoji. */
+	width: Required<TextWidthPolicy>;
+	/** Workspace root, from `setWorkspaceRoot()` or the current directory. */
+	workspaceRoot: string;
+	/** Root of scratch directories (`FS_SCRATCH_DIR`). */
+	scratchDir: string;
+	/** Directory of pending write journals (`FS_JOURNAL_DIR`). */
+	journalDir: string;
+	/** Filesystem scan lifetime in milliseconds (`FS_SCAN_CACHE_TTL_MS`). */
+	fsScanCacheTtlMs: number;
+	/** Age after which an empty cached scan is redone (`FS_SCAN_EMPTY_RECHECK_MS`). */
+	fsScanEmptyRecheckMs: number;
+	/** Maximum cached filesystem scans (`FS_SCAN_CACHE_MAX_ENTRIES`). */
+	fsScanCacheMaxEntries: number;
+	/** Maximum cached syntax trees (`AST_CACHE_MAX_ENTRIES`). */
+	astCacheMaxEntries: number;
+	/** Maximum source bytes of cached syntax trees (`AST_CACHE_MAX_BYTES`). */
+	astCacheMaxBytes: number;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Report the settings a native call started now would use. Each call snapshots read-only mode, the
+		 * concurrency and width policies, and the workspace root when it starts, so changing them does not affect calls already running.
+		 */
+		getEffectiveConfig(): EffectiveConfig;
+		/**
+		 * Set the workspace root that `removePaths`, `movePath`, `statPaths`, and the write journal default to; `null`
+		 * restores the current directory. Calls already running keep the root they started with.
+		 * @param path Existing directory.
+		 */
+		setWorkspaceRoot(path?: string | null): void;
+	}
+}
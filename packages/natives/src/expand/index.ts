@@ -0,0 +1,9 @@
+/**
+ * Shell-style expansion for user-supplied paths, matching the embedded shell's word expansion.
+ */
+
+import { native } from "../native";
+
+export type { ExpandPathOptions } from "./types";
+
+export const { expandPath } = native;
@@ -0,0 +1,24 @@
+/**
+ * Types for shell-style path expansion.
+ */
+
+/**
+ * Options for `expandPath()`.
+ */
+export interface ExpandPathOptions {
+	/** Variables to expand from; defaults to the process environment. */
+	env?: Record<string, string>;
+	/** Home directory for `~`; defaults to `HOME` (or the OS home directory). */
+	home?: string;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Expand `~`, `$VAR`, and `${VAR:-default}` in a path, including quote removal.
+		 * Supports the `-`, `:-`, `+`, and `:+` operators, but not the embedded shell's full expansion: no field splitting, globbing, or command substitution.
+		 * @throws On unterminated quotes and malformed or unsupported `${...}`.
+		 */
+		expandPath(input: string, options?: ExpandPathOptions): string;
+	}
+}
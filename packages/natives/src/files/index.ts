@@ -0,0 +1,16 @@
+/**
+ * Bounded, workspace-confined file removal and moves that honour read-only mode, and batched path checks.
+ */
+
+import { native } from "../native";
+
+export type {
+	MovePathOptions,
+	MovePathResult,
+	PathStat,
+	RemovePathsOptions,
+	RemovePathsResult,
+	StatPathsOptions,
+} from "./types";
+
+export const { removePaths, movePath, statPaths } = native;
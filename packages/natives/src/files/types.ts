@@ -0,0 +1,102 @@
+/**
+ * Types for bounded file removal and moves, and batched path checks.
+ */
+
+import type { FileType } from "../glob/types";
+
+/** Options for `removePaths()`. */
+export interface RemovePathsOptions {
+	/** Report what would be deleted without deleting it (default: false). */
+	dryRun?: boolean;
+	/** Fail before deleting anything when more files would go (default: 10000). */
+	maxFiles?: number;
+	/** Permit targets outside the workspace (default: false). */
+	allowOutsideWorkspace?: boolean;
+	/** Workspace root that targets must stay inside; relative paths resolve against it (default: `setWorkspaceRoot()`, else the current directory). */
+	workspace?: string;
+}
+
+/** What `removePaths()` deleted, or would delete in a dry run. */
+export interface RemovePathsResult {
+	/** Absolute paths of the removed targets. */
+	removed: string[];
+	/** Targets that did not exist. */
+	missing: string[];
+	/** Absolute path of every file (or symlink) removed, targets included. */
+	files: string[];
+	/** Number of directories removed. */
+	directories: number;
+	/** Total size of the removed files in bytes. */
+	bytes: number;
+	/** Whether this was a dry run. */
+	dryRun: boolean;
+}
+
+/** Options for `movePath()`. */
+export interface MovePathOptions {
+	/** Replace an existing file or symlink at the destination (default: false). Existing directories are never replaced. */
+	overwrite?: boolean;
+	/** Permit paths outside the workspace (default: false). */
+	allowOutsideWorkspace?: boolean;
+	/** Workspace root that both paths must stay inside; relative paths resolve against it (default: `setWorkspaceRoot()`, else the current directory). */
+	workspace?: string;
+}
+
+/** Outcome of `movePath()`. */
+export interface MovePathResult {
+	/** Absolute source path. */
+	from: string;
+	/** Absolute destination path. */
+	to: string;
+	/** Whether an existing destination was replaced. */
+	overwritten: boolean;
+}
+
+/** Options for `statPaths()`. */
+export interface StatPathsOptions {
+	/** Report symlinks as themselves instead of their targets (default: false). */
+	noFollow?: boolean;
+	/** Permit paths outside the workspace (default: false). */
+	allowOutsideWorkspace?: boolean;
+	/** Workspace root that paths must stay inside; relative paths resolve against it (default: `setWorkspaceRoot()`, else the current directory). */
+	workspace?: string;
+}
+
+/** Existence and metadata of one path, from `statPaths()`. */
+export interface PathStat {
+	/** The path as given. */
+	path: string;
+	/** Whether the path exists (and may be inspected). */
+	exists: boolean;
+	/** Entry type; `undefined` for missing paths and special files. */
+	fileType?: FileType;
+	/** Size in bytes, for files. */
+	size?: number;
+	/** Modification time in milliseconds since epoch. */
+	mtime?: number;
+	/** Why the path could not be inspected (outside the workspace, permission denied, ...); unset for paths that do not exist. */
+	error?: string;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Delete files and directory trees inside the workspace. Everything is counted first; nothing is deleted
+		 * when more than `maxFiles` files would go. Blocked by read-only mode unless `dryRun`.
+		 * @param paths Files or directories, relative to the workspace or absolute.
+		 */
+		removePaths(paths: string[], options?: RemovePathsOptions): Promise<RemovePathsResult>;
+		/**
+		 * Move or rename a file or directory inside the workspace, copying across devices. Blocked by read-only mode.
+		 * @param from Source path, relative to the workspace or absolute.
+		 * @param to Destination path; its parent directory must exist.
+		 */
+		movePath(from: string, to: string, options?: MovePathOptions): Promise<MovePathResult>;
+		/**
+		 * Check existence, type, size, and mtime of many paths in parallel, in input order. Paths outside the
+		 * workspace are reported as missing with an `error`.
+		 * @param paths Paths relative to the workspace or absolute.
+		 */
+		statPaths(paths: string[], options?: StatPathsOptions): Promise<PathStat[]>;
+	}
+}
@@ -0,0 +1,16 @@
+/**
+ * Git status, blame, revision diffs, and reads at a revision via the `git` executable.
+ */
+
+import { native } from "../native";
+
+export type {
+	ChangedFile,
+	ChangedFileStatus,
+	FileAtRev,
+	GitStatus,
+	GitStatusEntry,
+	ReadAtRevOptions,
+} from "./types";
+
+export const { changedFiles, gitBlameLine, gitStatus, readFileAtRev } = native;
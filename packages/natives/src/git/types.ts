@@ -0,0 +1,99 @@
+/**
+ * Types for git status, blame, revision diffs, and reads at a revision.
+ */
+
+import type { BlameInfo } from "../bindings";
+
+/** A changed path reported by `gitStatus()`. */
+export interface GitStatusEntry {
+	/** Path relative to the work tree root. */
+	path: string;
+	/** Source path of a rename or copy. */
+	originalPath?: string;
+	/** Index status code as `git status --short` prints it: "M", "T", "A", "D", "R", "C", "U", "?" (untracked), or " " (unchanged). */
+	index: string;
+	/** Work tree status code, with the same codes as `index`. */
+	workTree: string;
+}
+
+/** Work tree state from `gitStatus()`. */
+export interface GitStatus {
+	/** Canonical top-level directory of the work tree. */
+	root: string;
+	/** Checked-out branch; absent when `HEAD` is detached. */
+	branch?: string;
+	/** Upstream branch the current branch tracks. */
+	upstream?: string;
+	/** Commits on the branch that are not on its upstream. */
+	ahead: number;
+	/** Commits on the upstream that are not on the branch. */
+	behind: number;
+	/** Changed and untracked paths. */
+	entries: GitStatusEntry[];
+}
+
+/** How a file changed between two revisions. */
+export type ChangedFileStatus = "added" | "modified" | "deleted" | "renamed" | "copied" | "typechange";
+
+/** A file changed between two revisions, from `changedFiles()`. */
+export interface ChangedFile {
+	/** Absolute path under the work tree root. */
+	path: string;
+	/** How the file changed. */
+	status: ChangedFileStatus;
+	/** Absolute source path of a rename or copy. */
+	originalPath?: string;
+}
+
+/** Options for `readFileAtRev()`. */
+export interface ReadAtRevOptions {
+	/** Bytes of content returned before the rest is cut off (default: 4 MiB, as in `grep`; 0 disables the cap). */
+	maxFileSize?: number;
+}
+
+/** A file's content at a git revision, from `readFileAtRev()`. */
+export interface FileAtRev {
+	/** Path relative to the work tree root. */
+	path: string;
+	/** Whether the path is a file at the revision. */
+	exists: boolean;
+	/** Text content; absent when the file is missing or binary. */
+	content?: string;
+	/** Size of the file at the revision in bytes. */
+	size: number;
+	/** Whether the content has NUL bytes, which `grep` treats as binary. */
+	binary: boolean;
+	/** Whether the file is larger than `maxFileSize`, so `content` holds only its start. */
+	truncated: boolean;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Report the branch and changed paths of the work tree containing `root`.
+		 * @param root Directory inside a git work tree.
+		 */
+		gitStatus(root: string): Promise<GitStatus>;
+		/**
+		 * Blame one line of a file; resolves to `null` past the end of the file.
+		 * @param path File inside a git work tree.
+		 * @param line Line number (1-indexed).
+		 */
+		gitBlameLine(path: string, line: number): Promise<BlameInfo | null>;
+		/**
+		 * List files changed between two revisions.
+		 * @param base Base revision.
+		 * @param head Head revision (default: the work tree, untracked files included).
+		 * @param path Directory inside the work tree (default: the cwd).
+		 */
+		changedFiles(base: string, head?: string, path?: string): Promise<ChangedFile[]>;
+		/**
+		 * Read a file as it was at a git revision, without checking it out; resolves with `exists: false` when the
+		 * path is not a file at that revision.
+		 * @param path File path in the work tree (it may no longer exist there).
+		 * @param rev Revision, e.g. "main" or a commit hash.
+		 * @param options Size cap.
+		 */
+		readFileAtRev(path: string, rev: string, options?: ReadAtRevOptions): Promise<FileAtRev>;
+	}
+}
@@ -4,20 +4,44 @@
 
 import * as path from "node:path";
 import { native } from "../native";
-import type { GlobMatch, GlobOptions, GlobResult } from "./types";
+import type { GlobMatch, GlobMatchBatch, GlobOptions, GlobResult } from "./types";
 
-export type { GlobMatch, GlobOptions, GlobResult } from "./types";
+export type {
+	ExplainGlobOptions,
+	FsCacheRoot,
+	FsCacheStats,
+	GlobExplanation,
+	GlobMatch,
+	GlobMatchBatch,
+	GlobOptions,
+	GlobResult,
+} from "./types";
 export { FileType } from "./types";
 
 /**
  * Find files matching a glob pattern.
  * Respects .gitignore by default.
+ *
+ * Matches are streamed natively in batches (see `options.stream`); `onMatch` sees them one at a time, `onBatch`
+ * sees each batch with its sequence number and drop count.
  */
-export async function glob(options: GlobOptions, onMatch?: (match: GlobMatch) => void): Promise<GlobResult> {
-	const searchPath = path.resolve(options.path);
+export async function glob(
+	options: GlobOptions,
+	onMatch?: (match: GlobMatch) => void,
+	onBatch?: (batch: GlobMatchBatch) => void,
+): Promise<GlobResult> {
+	// Several roots are passed as given, so each match's `root` echoes the caller's spelling.
+	const searchPath = typeof options.path === "string" ? path.resolve(options.path) : options.path;
 	const pattern = options.pattern || "*";
 	// napi-rs ThreadsafeFunction passes (error, value) - skip callback on error
-	const cb = onMatch ? (err: Error | null, m: GlobMatch) => !err && onMatch(m) : undefined;
+	const cb =
+		onMatch || onBatch
+			? (err: Error | null, batch: GlobMatchBatch) => {
+					if (err) return;
+					if (onMatch) for (const m of batch.matches) onMatch(m);
+					onBatch?.(batch);
+				}
+			: undefined;
 
 	return native.glob(
 		{
@@ -32,6 +56,12 @@ export async function glob(options: GlobOptions, onMatch?: (match: GlobMatch) =>
 	);
 }
 
+/**
+ * Explain whether a path (relative to the search root) matches a glob pattern as `glob()` would normalize it,
+ * and which pattern component failed if it does not.
+ */
+export const { explainGlob } = native;
+
 /**
  * Invalidate the filesystem scan cache.
  *
@@ -41,3 +71,19 @@ export async function glob(options: GlobOptions, onMatch?: (match: GlobMatch) =>
 export function invalidateFsScanCache(path?: string): void {
 	native.invalidateFsScanCache(path);
 }
+
+/**
+ * Report what the filesystem scan cache currently holds: cached roots, their age, entry counts, and a memory
+ * estimate.
+ */
+export const { fsCacheStats } = native;
+
+/**
+ * Drop cached scans after an external change such as a build or `git checkout`.
+ *
+ * With a path, drops scans whose root contains it or lies under it; without one, drops every scan.
+ * Returns the number of scans dropped.
+ */
+export function fsCacheInvalidate(path?: string): number {
+	return native.fsCacheInvalidate(path);
+}
@@ -2,7 +2,7 @@
  * Types for native find API.
  */
 
-import type { Cancellable, TsFunc } from "../bindings";
+import type { Cancellable, ChangedScope, IgnoreSource, StreamBatch, StreamOptions, TraceSpan, TsFunc } from "../bindings";
 
 export const enum FileType {
 	/** A regular file. */
@@ -15,26 +15,50 @@ export const enum FileType {
 
 /** Options for discovering files and directories. */
 export interface GlobOptions extends Cancellable {
-	/** Glob pattern to match (e.g., `*.ts`). */
-	pattern: string;
-	/** Directory to search. */
-	path: string;
+	/** Glob pattern to match (e.g., `*.ts`), or several; entries match any pattern and none of the patterns starting with `!` (e.g., `["src/**\/*.ts", "!**\/*.test.ts"]`). */
+	pattern: string | string[];
+	/** Directory to search, or several searched as one; each match then reports its `root`, and entries under more than one root are reported once, under the first. */
+	path: string | string[];
+	/** Workspace package (name or relative path) to search instead of all of `path` (single `path` only); see `discoverPackages()`. Result paths are relative to the package. */
+	package?: string;
+	/** Match only files changed in git; directories never match. Fails outside a git work tree. */
+	changedOnly?: ChangedScope;
 	/** Filter by file type: "file", "dir", or "symlink". Symlinks match file/dir filters when their target type matches. */
 	fileType?: FileType;
 	/** Match simple patterns recursively by default (example: *.ts -> recursive match). Set false to keep patterns relative to the search root only. */
 	recursive?: boolean;
+	/** Match patterns case-sensitively; by default, matching ignores case unless a pattern contains an uppercase letter. */
+	caseSensitive?: boolean;
 	/** Include hidden files (default: false). */
 	hidden?: boolean;
 	/** Maximum number of results to return. */
 	maxResults?: number;
+	/** `cursor` of a previous result with the same options, to continue after its last match without filtering the entries before it again. Not supported with `sortByMtime` or `naturalSort`. */
+	cursor?: string;
 	/** Respect .gitignore files (default: true). */
 	gitignore?: boolean;
+	/** Standard ignore sources to apply; overrides `gitignore` when set (default: all three). */
+	ignoreSources?: IgnoreSource[];
+	/** Additional ignore file names to honor in each directory. */
+	ignoreFiles?: string[];
+	/** Deepest level to scan; direct children of the search root are at depth 1. */
+	maxDepth?: number;
+	/** Directories whose subtrees are never scanned: a bare name (`"build"`) matches at any depth, a name with `/` matches that path relative to the search root. */
+	excludeDirs?: string[];
 	/** Enable shared filesystem scan cache (default: false). */
 	cache?: boolean;
 	/** Sort results by mtime (most recent first) before applying limit. */
 	sortByMtime?: boolean;
+	/** Order results naturally by path (`file2` before `file10`, accents and case folded) before applying limit; breaks mtime ties with `sortByMtime`. */
+	naturalSort?: boolean;
 	/** Include node_modules entries even when pattern does not mention node_modules. */
 	includeNodeModules?: boolean;
+	/** Report each match's `mode`, `readonly`, and `symlinkTarget`, at the cost of one extra stat per match (default: false). */
+	withMetadata?: boolean;
+	/** Attach per-phase timings to the result as `trace`. */
+	trace?: boolean;
+	/** Batching of `onMatch` results. */
+	stream?: StreamOptions;
 }
 
 /** A single filesystem match. */
@@ -45,14 +69,91 @@ export interface GlobMatch {
 	fileType: FileType;
 	/** Modification time in milliseconds since epoch, if available. */
 	mtime?: number;
+	/** Size in bytes, for files. */
+	size?: number;
+	/** Search root `path` is relative to, as given in `path`; set only when searching several roots. */
+	root?: string;
+	/** Unix mode bits (`st_mode`, including the file type bits); set only with `withMetadata`, on Unix. */
+	mode?: number;
+	/** Whether the entry is read-only; set only with `withMetadata`. */
+	readonly?: boolean;
+	/** Target of a symlink, as stored in the link; set only with `withMetadata`. */
+	symlinkTarget?: string;
 }
 
+/** A batch of streamed `onMatch` results. */
+export type GlobMatchBatch = StreamBatch<GlobMatch>;
+
 /** Result of a find operation. */
 export interface GlobResult {
 	/** Matched filesystem entries. */
 	matches: GlobMatch[];
 	/** Number of matches returned after limits are applied. */
 	totalMatches: number;
+	/** Opaque position after the last match, set when `maxResults` stopped the scan before the end; pass it back as `cursor` for the next page. */
+	cursor?: string;
+	/** Per-phase timings, when requested with `trace`. */
+	trace?: TraceSpan;
+}
+
+/** Options for `explainGlob()`. */
+export interface ExplainGlobOptions {
+	/** Match simple patterns recursively, as `glob()` does (default: true). */
+	recursive?: boolean;
+	/** Match case-sensitively, as `glob()` does (default: only when the pattern contains an uppercase letter). */
+	caseSensitive?: boolean;
+}
+
+/** Whether a path matches a glob pattern, and why not. */
+export interface GlobExplanation {
+	/** Whether `glob()` would accept the path. */
+	matched: boolean;
+	/** Pattern after normalization, exactly as `glob()` compiles it. */
+	pattern: string;
+	/** Whether a leading `**` component was added because the pattern has no `/`. */
+	autoPrefixed: boolean;
+	/** Number of `}` appended to close unclosed `{` groups. */
+	closedBraces: number;
+	/** Index of the first `/`-separated pattern component that could not be matched; absent when the pattern matched or the path is too long. */
+	failedComponent?: number;
+	/** Text of the failed pattern component. */
+	failedPattern?: string;
+	/** Path component the failed pattern component was compared with, or the unmatched remainder when the pattern ended first. */
+	failedPath?: string;
+	/** Human-readable explanation. */
+	reason: string;
+}
+
+/** One cached filesystem scan. */
+export interface FsCacheRoot {
+	/** Absolute root of the scan. */
+	root: string;
+	/** Filesystem entries held for this scan. */
+	entries: number;
+	/** Milliseconds since the scan ran. */
+	ageMs: number;
+	/** Whether the scan includes hidden files. */
+	hidden: boolean;
+	/** Whether the scan honors `.gitignore`. */
+	gitignore: boolean;
+	/** Approximate heap bytes held by the entries. */
+	memoryBytes: number;
+}
+
+/** Snapshot of the filesystem scan cache. */
+export interface FsCacheStats {
+	/** Cached scans. */
+	scans: number;
+	/** Filesystem entries across all scans. */
+	entries: number;
+	/** Approximate heap bytes held by all scans. */
+	memoryBytes: number;
+	/** Scan lifetime in milliseconds (`FS_SCAN_CACHE_TTL_MS`). */
+	ttlMs: number;
+	/** Maximum cached scans (`FS_SCAN_CACHE_MAX_ENTRIES`). */
+	maxScans: number;
+	/** Cached scans, oldest first. */
+	roots: FsCacheRoot[];
 }
 
 declare module "../bindings" {
@@ -62,8 +163,20 @@ declare module "../bindings" {
 		 * @param options Search options that control globbing and filters.
 		 * @param onMatch Optional callback for streaming matches as they are found.
 		 */
-		glob(options: GlobOptions, onMatch?: TsFunc<GlobMatch>): Promise<GlobResult>;
+		glob(options: GlobOptions, onMatch?: TsFunc<GlobMatchBatch>): Promise<GlobResult>;
+		/**
+		 * Explain whether a path (relative to the search root) matches a glob, including the leading `**` auto-prefix
+		 * and brace closing that `glob()` applies, and which pattern component failed on a miss.
+		 */
+		explainGlob(pattern: string, path: string, options?: ExplainGlobOptions): GlobExplanation;
 		/** Invalidate the filesystem scan cache for the given path (or all caches if omitted). */
 		invalidateFsScanCache(path?: string): void;
+		/** Report what the filesystem scan cache currently holds. */
+		fsCacheStats(): FsCacheStats;
+		/**
+		 * Drop cached scans whose root contains `path` or lies under it (every scan if omitted).
+		 * @returns Number of scans dropped.
+		 */
+		fsCacheInvalidate(path?: string): number;
 	}
 }
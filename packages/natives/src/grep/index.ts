@@ -4,38 +4,104 @@
 
 import { native } from "../native";
 import type {
+	BlockLine,
 	ContextLine,
+	DelimitedMatch,
+	DelimitedSearchOptions,
+	DelimitedSearchResult,
+	DelimitedSource,
+	DirectoryCount,
+	DocumentSearchResult,
+	FileHeatmap,
 	FuzzyFindMatch,
 	FuzzyFindOptions,
 	FuzzyFindResult,
+	FuzzyFindWeights,
+	GrepBlock,
 	GrepMatch,
+	GrepMatchBatch,
 	GrepOptions,
 	GrepResult,
 	GrepSummary,
+	MatchColumn,
+	MatchSpan,
+	SearchDocument,
+	SearchDocumentsOptions,
+	SearchDocumentsResult,
 	SearchOptions,
 	SearchResult,
 } from "./types";
 
 export type {
+	BlockLine,
 	ContextLine,
+	DelimitedMatch,
+	DelimitedSearchOptions,
+	DelimitedSearchResult,
+	DelimitedSource,
+	DirectoryCount,
+	DocumentSearchResult,
+	FileHeatmap,
 	FuzzyFindMatch,
 	FuzzyFindOptions,
 	FuzzyFindResult,
+	FuzzyFindWeights,
+	GrepBlock,
 	GrepMatch,
+	GrepMatchBatch,
 	GrepOptions,
 	GrepResult,
 	GrepSummary,
+	MatchColumn,
+	MatchSpan,
+	SearchDocument,
+	SearchDocumentsOptions,
+	SearchDocumentsResult,
 	SearchOptions,
 	SearchResult,
 };
 
+// napi-rs ThreadsafeFunction passes (error, value) - skip callback on error
+function unbatch(
+	onMatch?: (match: GrepMatch) => void,
+	onBatch?: (batch: GrepMatchBatch) => void,
+): ((err: Error | null, batch: GrepMatchBatch) => void) | undefined {
+	if (!onMatch && !onBatch) return undefined;
+	return (err, batch) => {
+		if (err) return;
+		if (onMatch) for (const m of batch.matches) onMatch(m);
+		onBatch?.(batch);
+	};
+}
+
 /**
  * Search files for a regex pattern with optional streaming callback.
+ *
+ * Matches are streamed natively in batches (see `options.stream`); `onMatch` sees them one at a time, `onBatch`
+ * sees each batch with its sequence number and drop count.
+ */
+export async function grep(
+	options: GrepOptions,
+	onMatch?: (match: GrepMatch) => void,
+	onBatch?: (batch: GrepMatchBatch) => void,
+): Promise<GrepResult> {
+	return native.grep(options, unbatch(onMatch, onBatch));
+}
+
+export type { GrepSession } from "./types";
+export const { GrepSession } = native;
+
+/**
+ * Search files like `grep()`, reusing the session's results for files whose mtime and size are unchanged.
+ * Changing the pattern, its flags, context, output mode, or root starts the session over.
  */
-export async function grep(options: GrepOptions, onMatch?: (match: GrepMatch) => void): Promise<GrepResult> {
-	// napi-rs ThreadsafeFunction passes (error, value) - skip callback on error
-	const cb = onMatch ? (err: Error | null, m: GrepMatch) => !err && onMatch(m) : undefined;
-	return native.grep(options, cb);
+export async function grepIncremental(
+	session: InstanceType<typeof GrepSession>,
+	options: GrepOptions,
+	onMatch?: (match: GrepMatch) => void,
+	onBatch?: (batch: GrepMatchBatch) => void,
+): Promise<GrepResult> {
+	return native.grepIncremental(session, options, unbatch(onMatch, onBatch));
 }
 
 /**
@@ -48,6 +114,25 @@ export function searchContent(content: string | Uint8Array, options: SearchOptio
 	return native.search(content, options);
 }
 
+/**
+ * Search many in-memory documents (e.g. open editor buffers) in one native call, compiling the pattern once.
+ * `maxCount` and `offset` apply per document.
+ */
+export function searchDocuments(documents: SearchDocument[], options: SearchDocumentsOptions): SearchDocumentsResult {
+	return native.searchDocuments(documents, options);
+}
+
+/**
+ * Search selected columns of CSV/TSV data, parsing quoted fields natively.
+ * Returns one match per matching field with row/column coordinates.
+ */
+export async function searchDelimited(
+	source: DelimitedSource,
+	options: DelimitedSearchOptions,
+): Promise<DelimitedSearchResult> {
+	return native.searchDelimited(source, options);
+}
+
 /**
  * Quick check if content contains a pattern match.
  *
@@ -2,42 +2,101 @@
  * Types for grep/search operations.
  */
 
-import type { Cancellable, TsFunc } from "../bindings";
+import type {
+	BlameInfo,
+	Cancellable,
+	ChangedScope,
+	IgnoreSource,
+	StreamBatch,
+	StreamOptions,
+	TraceSpan,
+	TsFunc,
+} from "../bindings";
+
+/** Regex engine for content search. */
+export type RegexEngine = "default" | "pcre2";
 
 /** Options for searching files. */
 export interface GrepOptions extends Cancellable {
 	/** Regex pattern to search for */
 	pattern: string;
-	/** Directory or file to search */
-	path: string;
+	/** Directory or file to search, or several searched as one; each result then reports its `root`, and files under more than one root are searched once, under the first */
+	path: string | string[];
+	/** Workspace package (name or relative path) to search instead of all of `path` (single `path` only); see `discoverPackages()`. Result paths are relative to the package. */
+	package?: string;
+	/** Search only files changed in git; fails outside a git work tree. */
+	changedOnly?: ChangedScope;
 	/** Glob filter for filenames (e.g., "*.ts") */
 	glob?: string;
-	/** Filter by file type (e.g., "js", "py", "rust") */
+	/** Match `glob` case-sensitively; by default, matching ignores case unless `glob` contains an uppercase letter. */
+	globCaseSensitive?: boolean;
+	/** Filter by file type (e.g., "js", "py", "rust"), or by `classifyPath` class ("test", "!test" to exclude tests) */
 	type?: string;
 	/** Case-insensitive search */
 	ignoreCase?: boolean;
 	/** Enable multiline matching */
 	multiline?: boolean;
+	/** Regex engine; `"pcre2"` adds lookaround and backreferences (default falls back to it on compile error) */
+	engine?: RegexEngine;
 	/** Include hidden files (default: true) */
 	hidden?: boolean;
 	/** Respect .gitignore files (default: true) */
 	gitignore?: boolean;
+	/** Standard ignore sources to apply; overrides `gitignore` when set (default: all three). */
+	ignoreSources?: IgnoreSource[];
+	/** Additional ignore file names to honor in each directory (e.g. ".dockerignore") */
+	ignoreFiles?: string[];
 	/** Enable shared filesystem scan cache (default: false). */
 	cache?: boolean;
 	/** Maximum number of matches to return */
 	maxCount?: number;
-	/** Skip first N matches */
+	/** Skip first N matches; ignored with `cursor`, which already accounts for it */
 	offset?: number;
+	/** `cursor` of a previous result with the same options, to continue after its last match without searching the files before it again; resending the same `offset` does not skip again */
+	cursor?: string;
+	/** Order files naturally (`file2` before `file10`, accents and case folded) instead of by byte value */
+	naturalSort?: boolean;
 	/** Lines of context before matches */
 	contextBefore?: number;
 	/** Lines of context after matches */
 	contextAfter?: number;
 	/** Lines of context before/after matches (legacy) */
 	context?: number;
-	/** Truncate lines longer than this (characters) */
+	/** Truncate lines longer than this (bytes, or cells with `truncateByWidth`) */
 	maxColumns?: number;
-	/** Output mode */
-	mode?: "content" | "filesWithMatches" | "count";
+	/** Count `maxColumns` in display cells as the text module measures them (tabs `tabWidth` cells), instead of bytes */
+	truncateByWidth?: boolean;
+	/** Report where each match starts on its line as `columns`, counted in bytes, UTF-16 code units, and display cells (content mode only) */
+	columns?: boolean;
+	/** Cells per tab in display columns, as in text rendering (default: 3) */
+	tabWidth?: number;
+	/** Report each match's ranges within `line` in `spans`, as UTF-16 code unit offsets for slicing JS strings (content mode only) */
+	spans?: boolean;
+	/** Bytes read per file before the rest is skipped (default: 4 MiB; 0 disables the cap) */
+	maxFileSize?: number;
+	/** Search only the first files, in search order, whose sizes (capped at `maxFileSize`) add up to at most this many bytes; see `budgetExhausted`. */
+	maxTotalBytes?: number;
+	/** Search at most this many files, in search order; see `budgetExhausted`. */
+	maxFiles?: number;
+	/**
+	 * Output mode; `"filesWithoutMatch"` lists searched files with zero matches (paged by `offset`/`maxCount`),
+	 * `"countByDir"` aggregates per-directory totals into `directories`
+	 */
+	mode?: "content" | "filesWithMatches" | "filesWithoutMatch" | "count" | "countByDir";
+	/** Directory depth used to group counts in `"countByDir"` mode (default: 1) */
+	dirDepth?: number;
+	/** Annotate each match with the last commit that touched its line, one `git blame` per file (content mode only) */
+	blame?: boolean;
+	/** Report a match-density histogram for each file in the result as `heatmaps`, counted over the whole file regardless of `maxCount` */
+	heatmap?: boolean;
+	/** Histogram buckets per file with `heatmap` (default: 32, max 1024) */
+	heatmapBuckets?: number;
+	/** Group adjacent matches of a file with their context into `blocks`, so no line is listed twice; matches then carry no context (content mode only) */
+	blocks?: boolean;
+	/** Attach per-phase timings to the result as `trace` */
+	trace?: boolean;
+	/** Batching of `onMatch` results */
+	stream?: StreamOptions;
 }
 
 /** A context line returned around a match. */
@@ -52,6 +111,8 @@ export interface ContextLine {
 export interface GrepMatch {
 	/** File path for the match (relative for directory searches). */
 	path: string;
+	/** Search root `path` is relative to, as given in `path`; set only when searching several roots. */
+	root?: string;
 	/** 1-indexed line number (0 for count-only entries). */
 	lineNumber: number;
 	/** Matched line content (empty for count-only entries). */
@@ -64,6 +125,68 @@ export interface GrepMatch {
 	truncated?: boolean;
 	/** Per-file match count (count mode only). */
 	matchCount?: number;
+	/** Last commit that touched the line (`blame: true` only). */
+	blame?: BlameInfo;
+	/** Start of each match on the line (`columns: true` only). */
+	columns?: MatchColumn[];
+	/** Ranges of the match within `line`, as UTF-16 code unit offsets (`spans: true` only). */
+	spans?: MatchSpan[];
+}
+
+/** Where a match starts on its line, in each column unit (all 1-indexed). */
+export interface MatchColumn {
+	/** Byte column. */
+	byte: number;
+	/** UTF-16 code unit column, as JS string indices count. */
+	utf16: number;
+	/** Display cell column, with tabs expanded to `tabWidth`. */
+	display: number;
+}
+
+/** A batch of streamed `onMatch` results. */
+export type GrepMatchBatch = StreamBatch<GrepMatch>;
+
+/** Aggregated matches under one directory (`"countByDir"` mode). */
+export interface DirectoryCount {
+	/** Files with at least one match. */
+	files: number;
+	/** Total matches. */
+	matches: number;
+}
+
+/** Consecutive lines of one file covering adjacent matches and their context (`blocks: true`). */
+export interface GrepBlock {
+	/** File path, as in `matches`. */
+	path: string;
+	/** Search root of the file, as in `matches`. */
+	root?: string;
+	/** First line of the block (1-indexed). */
+	startLine: number;
+	/** Last line of the block (1-indexed, inclusive). */
+	endLine: number;
+	lines: BlockLine[];
+}
+
+/** A line of a `GrepBlock`. */
+export interface BlockLine {
+	lineNumber: number;
+	line: string;
+	/** Whether the line is part of a match rather than context. */
+	isMatch: boolean;
+}
+
+/** Where matches fall in one file, for a results minimap (`heatmap: true`). */
+export interface FileHeatmap {
+	/** File path, as in `matches`. */
+	path: string;
+	/** Search root of the file, as in `matches`. */
+	root?: string;
+	/** Lines in the file (in the searched part for files over `maxFileSize`). */
+	lines: number;
+	/** Matching lines counted into `buckets`. */
+	matches: number;
+	/** Matching lines in each equal slice of the file, first line to last. */
+	buckets: number[];
 }
 
 /** Summary stats for a grep run. */
@@ -76,12 +199,28 @@ export interface GrepSummary {
 	filesSearched: number;
 	/** Whether the limit/offset stopped the search early. */
 	limitReached?: boolean;
+	/** Files larger than `maxFileSize` that were only partially searched, prefixed with their root when searching several roots. */
+	truncatedFiles?: string[];
 }
 
 /** Full grep result including matches and summary counts. */
 export interface GrepResult extends GrepSummary {
-	/** Matches or per-file counts, depending on mode. */
+	/** Matches or per-file counts, depending on mode (empty in `"countByDir"` mode). */
 	matches: GrepMatch[];
+	/** Per-directory totals keyed by directory path (`"."` for the search root; prefixed with the root when searching several). */
+	directories?: Record<string, DirectoryCount>;
+	/** Files whose results were reused from a `GrepSession` (`grepIncremental` only). */
+	reusedFiles?: number;
+	/** Match density per file in `matches` (`heatmap: true` only). */
+	heatmaps?: FileHeatmap[];
+	/** Matches grouped with their context (`blocks: true` only). */
+	blocks?: GrepBlock[];
+	/** Opaque position after the last match returned, set when `maxCount` or a budget stopped the search before the end; pass it back as `cursor` for the next page. */
+	cursor?: string;
+	/** Budget that left files unsearched. */
+	budgetExhausted?: "maxFiles" | "maxTotalBytes";
+	/** Per-phase timings, when requested with `trace`. */
+	trace?: TraceSpan;
 }
 
 /** Options for searching in-memory content. */
@@ -92,6 +231,8 @@ export interface SearchOptions {
 	ignoreCase?: boolean;
 	/** Enable multiline matching */
 	multiline?: boolean;
+	/** Regex engine; `"pcre2"` adds lookaround and backreferences (default falls back to it on compile error) */
+	engine?: RegexEngine;
 	/** Maximum number of matches to return */
 	maxCount?: number;
 	/** Skip first N matches */
@@ -102,10 +243,24 @@ export interface SearchOptions {
 	contextAfter?: number;
 	/** Lines of context before/after matches (legacy) */
 	context?: number;
-	/** Truncate lines longer than this (characters) */
+	/** Truncate lines longer than this (bytes, or cells with `truncateByWidth`) */
 	maxColumns?: number;
+	/** Count `maxColumns` in display cells as the text module measures them (tabs 3 cells), instead of bytes */
+	truncateByWidth?: boolean;
 	/** Output mode */
 	mode?: "content" | "count";
+	/** First line to search (1-based, inclusive); line numbers in the result stay relative to the whole content */
+	lineStart?: number;
+	/** Last line to search (1-based, inclusive); context never reaches outside `lineStart`..`lineEnd` */
+	lineEnd?: number;
+}
+
+/** A matched range within a line (UTF-16 code unit offsets). */
+export interface MatchSpan {
+	/** Start offset (inclusive). */
+	start: number;
+	/** End offset (exclusive). */
+	end: number;
 }
 
 /** A single content match. */
@@ -120,6 +275,12 @@ export interface SearchMatch {
 	contextAfter?: ContextLine[];
 	/** Whether the line was truncated. */
 	truncated?: boolean;
+	/** Matched ranges within `line`, clipped to the visible part when truncated. */
+	spans: MatchSpan[];
+	/** Last line of a match spanning several lines (`multiline` only). */
+	endLineNumber?: number;
+	/** Full untruncated text from the start of the first match to the end of the last, for a match spanning several lines. */
+	text?: string;
 }
 
 /** Result of searching in-memory content. */
@@ -134,6 +295,44 @@ export interface SearchResult {
 	error?: string;
 }
 
+/** Options for `searchDocuments`; limits apply to each document on its own. */
+export interface SearchDocumentsOptions extends Omit<SearchOptions, "mode"> {
+	/** Output mode; `"filesWithMatches"` returns counts for matching documents only */
+	mode?: "content" | "count" | "filesWithMatches";
+}
+
+/** A named piece of content for `searchDocuments`. */
+export interface SearchDocument {
+	/** Caller-chosen identifier, echoed in the result. */
+	id: string;
+	/** Text to search. */
+	content: string;
+}
+
+/** Result of searching one document. */
+export interface DocumentSearchResult {
+	/** Identifier of the document. */
+	id: string;
+	/** Matches in the document (empty in count modes). */
+	matches: SearchMatch[];
+	/** Total number of matches in the document. */
+	matchCount: number;
+	/** Whether `maxCount` stopped the search of this document. */
+	limitReached: boolean;
+	/** Error message, if searching this document failed. */
+	error?: string;
+}
+
+/** Result of searching several documents. */
+export interface SearchDocumentsResult {
+	/** Per-document results, in input order. */
+	documents: DocumentSearchResult[];
+	/** Matches across all documents. */
+	totalMatches: number;
+	/** Error message, if the pattern failed to compile. */
+	error?: string;
+}
+
 /** Options for fuzzy file path search. */
 export interface FuzzyFindOptions extends Cancellable {
 	/** Fuzzy query to match against file paths (case-insensitive). */
@@ -144,10 +343,47 @@ export interface FuzzyFindOptions extends Cancellable {
 	hidden?: boolean;
 	/** Respect .gitignore (default: true). */
 	gitignore?: boolean;
+	/** Standard ignore sources to apply; overrides `gitignore` when set (default: all three). */
+	ignoreSources?: IgnoreSource[];
+	/** Additional ignore file names to honor in each directory. */
+	ignoreFiles?: string[];
 	/** Enable shared filesystem scan cache (default: false). */
 	cache?: boolean;
 	/** Maximum number of matches to return (default: 100). */
 	maxResults?: number;
+	/** Also look for the query in the contents of the best path candidates and boost files that contain it (default: false). */
+	searchContent?: boolean;
+	/** How many top-scoring files `searchContent` checks (default: 100). */
+	contentCandidates?: number;
+	/** Recently opened paths, most recent first (relative to `path` or absolute); matches get a boost that fades with position. */
+	recent?: string[];
+	/** Frecency weights by path (relative to `path` or absolute); matches get a boost that grows logarithmically with the weight. */
+	frecency?: Record<string, number>;
+	/** Ranking weights; omitted fields keep their defaults. */
+	weights?: FuzzyFindWeights;
+}
+
+/**
+ * Ranking weights for `fuzzyFind`. Each must be finite and not negative, and is clamped to 1000; `gapPenalty` is
+ * clamped to 16 (the score of one matched character).
+ */
+export interface FuzzyFindWeights {
+	/** Score of a file name equal to the query (default: 120). */
+	exact?: number;
+	/** Score of a file name starting with the query (default: 100). */
+	prefix?: number;
+	/** Score of a file name containing the query (default: 80). */
+	contains?: number;
+	/** Score of a path containing the query (default: 60). */
+	pathContains?: number;
+	/** Base score of a file name matching the query as a subsequence; up to 40 is added for match quality (default: 50). */
+	subsequence?: number;
+	/** Base score of a path matching the query as a subsequence (default: 30). */
+	pathSubsequence?: number;
+	/** Penalty for each gap between matched characters of a subsequence (default: 3). */
+	gapPenalty?: number;
+	/** Added to the score of directories (default: 10). */
+	directoryBonus?: number;
 }
 
 /** A single match in fuzzy find results. */
@@ -158,6 +394,10 @@ export interface FuzzyFindMatch {
 	isDirectory: boolean;
 	/** Match quality score (higher is better). */
 	score: number;
+	/** Whether the file contains the query (`searchContent` only). */
+	contentMatch?: boolean;
+	/** Offsets (UTF-16 code units) in `path` of the characters that matched the query, for highlighting; empty for an empty query. */
+	positions: number[];
 }
 
 /** Result of fuzzy file path search. */
@@ -168,14 +408,74 @@ export interface FuzzyFindResult {
 	totalMatches: number;
 }
 
+/** Input for delimited search: a file path or in-memory text. */
+export type DelimitedSource = { path: string; text?: undefined } | { text: string; path?: undefined };
+
+/** Options for CSV/TSV structured search. */
+export interface DelimitedSearchOptions extends Cancellable {
+	/** Regex pattern matched against each selected field. */
+	pattern: string;
+	/** Field delimiter (one character). Defaults to tab for `.tsv`/`.tab` paths and comma otherwise. */
+	delimiter?: string;
+	/** Columns to search, by zero-based index or header name (default: all). */
+	columns?: Array<number | string>;
+	/** Treat the first record as a header row (default: false). */
+	hasHeader?: boolean;
+	/** Case-insensitive search. */
+	ignoreCase?: boolean;
+	/** Maximum number of matching fields to return. */
+	maxCount?: number;
+}
+
+/** A matching field in delimited data. */
+export interface DelimitedMatch {
+	/** Zero-based data row index (the header row is not counted). */
+	row: number;
+	/** Zero-based column index. */
+	column: number;
+	/** Header name of the column, when `hasHeader` is set. */
+	columnName?: string;
+	/** 1-indexed line on which the record starts. */
+	lineNumber: number;
+	/** Unquoted field value. */
+	value: string;
+	/** Matched ranges within `value`. */
+	spans: MatchSpan[];
+}
+
+/** Result of a delimited search. */
+export interface DelimitedSearchResult {
+	matches: DelimitedMatch[];
+	/** Header fields, when `hasHeader` is set. */
+	header?: string[];
+	rowsSearched: number;
+	limitReached: boolean;
+}
+
+/** Per-file grep results remembered across `grepIncremental()` calls. */
+export interface GrepSession {
+	/** Number of files with remembered results. */
+	readonly fileCount: number;
+	/** Forget all remembered results. */
+	clear(): void;
+}
+
 declare module "../bindings" {
 	interface NativeBindings {
+		/** GrepSession class constructor. */
+		GrepSession: new () => GrepSession;
 		/** Fuzzy file path search for autocomplete. */
 		fuzzyFind(options: FuzzyFindOptions): Promise<FuzzyFindResult>;
 		/** Search files for a regex pattern. */
-		grep(options: GrepOptions, onMatch?: TsFunc<GrepMatch>): Promise<GrepResult>;
+		grep(options: GrepOptions, onMatch?: TsFunc<GrepMatchBatch>): Promise<GrepResult>;
+		/** Search files like `grep`, re-searching only files whose mtime or size changed since the session's last call. */
+		grepIncremental(session: GrepSession, options: GrepOptions, onMatch?: TsFunc<GrepMatchBatch>): Promise<GrepResult>;
 		/** Search in-memory content for a regex pattern. */
 		search(content: string | Uint8Array, options: SearchOptions): SearchResult;
+		/** Search several in-memory documents with one compiled pattern. */
+		searchDocuments(documents: SearchDocument[], options: SearchDocumentsOptions): SearchDocumentsResult;
+		/** Search selected columns of CSV/TSV data. */
+		searchDelimited(source: DelimitedSource, options: DelimitedSearchOptions): Promise<DelimitedSearchResult>;
 		/** Quick check if content matches a pattern. */
 		hasMatch(
 			content: string | Uint8Array,
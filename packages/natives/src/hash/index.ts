@@ -0,0 +1,17 @@
+/**
+ * Content hashing for files and directory trees, and duplicate file detection.
+ */
+
+import { native } from "../native";
+
+export type {
+	DuplicateFilesOptions,
+	DuplicateGroup,
+	FileHash,
+	HashAlgorithm,
+	HashFileOptions,
+	HashTreeOptions,
+	TreeHash,
+} from "./types";
+
+export const { hashFile, hashTree, duplicateFiles } = native;
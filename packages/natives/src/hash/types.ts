@@ -0,0 +1,71 @@
+/**
+ * Types for content hashing and duplicate detection.
+ */
+
+import type { Cancellable } from "../bindings";
+
+/** Digest algorithm: XXH3-128 (fast, default) or BLAKE3-256 (cryptographic). */
+export type HashAlgorithm = "xxh3" | "blake3";
+
+/** Options for `hashFile()`. */
+export interface HashFileOptions {
+	/** Digest algorithm (default: "xxh3"). */
+	algorithm?: HashAlgorithm;
+}
+
+/** Digest of one file. */
+export interface FileHash {
+	/** File path (relative to the root for tree hashes). */
+	path: string;
+	/** Lowercase hex digest. */
+	hash: string;
+	/** Bytes hashed. */
+	size: number;
+}
+
+/** Options for `hashTree()`. */
+export interface HashTreeOptions extends Cancellable {
+	/** Digest algorithm (default: "xxh3"). */
+	algorithm?: HashAlgorithm;
+	/** Glob filter for files (e.g., `*.ts`). */
+	glob?: string;
+	/** Include hidden files (default: true). */
+	hidden?: boolean;
+	/** Respect .gitignore and other ignore files (default: true). */
+	gitignore?: boolean;
+}
+
+/** Digest of a directory tree. */
+export interface TreeHash {
+	/** Digest over every file's relative path and digest, in path order. */
+	hash: string;
+	/** Per-file digests, sorted by path. */
+	files: FileHash[];
+}
+
+/** Options for `duplicateFiles()`. */
+export interface DuplicateFilesOptions extends HashTreeOptions {
+	/** Ignore files smaller than this many bytes (default: 1, skipping empty files). */
+	minSize?: number;
+}
+
+/** Files with identical content. */
+export interface DuplicateGroup {
+	/** Shared digest. */
+	hash: string;
+	/** Size of each file in bytes. */
+	size: number;
+	/** Relative paths, sorted. */
+	paths: string[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/** Hash a single file. */
+		hashFile(path: string, options?: HashFileOptions): Promise<FileHash>;
+		/** Hash every file under a directory, plus a digest of the whole tree (covers paths and contents). */
+		hashTree(path: string, options?: HashTreeOptions): Promise<TreeHash>;
+		/** Group files with identical content, largest wasted bytes first. */
+		duplicateFiles(path: string, options?: DuplicateFilesOptions): Promise<DuplicateGroup[]>;
+	}
+}
@@ -1,9 +1,9 @@
 /**
- * Syntax highlighting powered by native syntect bindings.
+ * Syntax highlighting powered by native syntect bindings, plus tree-sitter highlight spans.
  */
 
 import { native } from "../native";
 
-export type { HighlightColors } from "./types";
+export type { HighlightCategory, HighlightColors, HighlightSpan } from "./types";
 
-export const { highlightCode, supportsLanguage, getSupportedLanguages } = native;
+export const { highlightCode, supportsLanguage, getSupportedLanguages, highlight } = native;
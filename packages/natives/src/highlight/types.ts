@@ -31,6 +31,27 @@ export interface HighlightColors {
 	deleted?: string;
 }
 
+/** Highlight category of a span, matching the `highlightCode` color slots. */
+export type HighlightCategory =
+	| "comment"
+	| "keyword"
+	| "function"
+	| "variable"
+	| "string"
+	| "number"
+	| "type"
+	| "operator"
+	| "punctuation";
+
+/** A highlighted range of the input. */
+export interface HighlightSpan {
+	/** Start offset in UTF-16 code units (inclusive). */
+	start: number;
+	/** End offset in UTF-16 code units (exclusive). */
+	end: number;
+	category: HighlightCategory;
+}
+
 declare module "../bindings" {
 	interface NativeBindings {
 		/**
@@ -52,5 +73,12 @@ declare module "../bindings" {
 		 * @returns Syntect language names supported by the native highlighter.
 		 */
 		getSupportedLanguages(): string[];
+		/**
+		 * Compute syntax highlighting spans with tree-sitter, for renderers that apply their own styling.
+		 * @param content Source code to highlight.
+		 * @param lang Language name or extension (ast-grep languages only).
+		 * @returns Sorted, non-overlapping spans covering the highlighted text.
+		 */
+		highlight(content: string, lang: string): HighlightSpan[];
 	}
 }
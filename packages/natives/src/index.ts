@@ -1,15 +1,39 @@
 export * from "./appearance";
 export * from "./ast";
+export * from "./batch";
+export * from "./block";
+export * from "./changeset";
+export * from "./classify";
 export * from "./clipboard";
+export * from "./comments";
+export * from "./config";
+export * from "./expand";
+export * from "./files";
+export * from "./git";
 export * from "./glob";
 export * from "./grep";
+export * from "./hash";
 export * from "./highlight";
 export * from "./html";
 export * from "./image";
+export * from "./journal";
+export * from "./jsonc";
 export * from "./keys";
+export * from "./lines";
+export * from "./merge";
+export * from "./outline";
+export * from "./pack";
+export * from "./patch";
+export * from "./project";
 export * from "./projfs";
 export * from "./ps";
 export * from "./pty";
+export * from "./readonly";
+export * from "./scratch";
 export * from "./shell";
+export * from "./similar";
+export * from "./sort";
+export * from "./symbols";
 export * from "./text";
+export * from "./watch";
 export * from "./work";
@@ -0,0 +1,18 @@
+/**
+ * Crash recovery for journaled multi-file writes (e.g. `astEdit`).
+ */
+
+import { native } from "../native";
+import type { RecoverJournalOptions, RecoverJournalResult } from "./types";
+
+export type { RecoverJournalOptions, RecoverJournalResult } from "./types";
+
+/**
+ * Replay journals left behind by an interrupted multi-file write.
+ *
+ * @param options - Journal directory and replay direction
+ * @returns Number of journals and files recovered
+ */
+export async function recoverJournal(options?: RecoverJournalOptions): Promise<RecoverJournalResult> {
+	return native.recoverJournal(options);
+}
@@ -0,0 +1,31 @@
+/**
+ * Types for write-ahead journal recovery.
+ */
+
+/** Options for replaying leftover journals. */
+export interface RecoverJournalOptions {
+	/** Journal directory (default: `FS_JOURNAL_DIR` or the workspace's directory under the user state directory). */
+	dir?: string;
+	/** "forward" finishes interrupted writes (default); "backward" restores the original contents. */
+	direction?: "forward" | "backward";
+}
+
+/** Outcome of replaying leftover journals. */
+export interface RecoverJournalResult {
+	/** Number of journals replayed and removed. Journals of processes that are still running are skipped. */
+	journals: number;
+	/** Number of files written or deleted while replaying. */
+	filesRestored: number;
+	/** Journals that could not be replayed (left in place). */
+	errors?: string[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Replay journals left behind by an interrupted multi-file write.
+		 * @param options Journal directory and replay direction.
+		 */
+		recoverJournal(options?: RecoverJournalOptions): Promise<RecoverJournalResult>;
+	}
+}
@@ -0,0 +1,20 @@
+/**
+ * JSONC/JSON5 parsing and comment-preserving edits for tsconfig and settings files.
+ */
+
+import { native } from "../native";
+import type { JsoncPath } from "./types";
+
+export type { JsoncNode, JsoncPath } from "./types";
+
+export const { parseJsonc } = native;
+
+/**
+ * Set the value at `path` in JSONC/JSON5 text, or remove it when `value` is `undefined`.
+ *
+ * Only the affected span changes, so comments, key order, and formatting elsewhere survive. Missing object members
+ * along the path are created; inserted values follow the file's indentation.
+ */
+export function editJsonc(text: string, path: JsoncPath, value: unknown): string {
+	return native.editJsonc(text, path, value === undefined ? undefined : JSON.stringify(value));
+}
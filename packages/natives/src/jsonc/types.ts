@@ -0,0 +1,35 @@
+/**
+ * Types for JSONC/JSON5 parsing and editing.
+ */
+
+/** A node of a parsed JSONC document. Offsets are UTF-16 code units, so `text.slice(start, end)` is the node's source. */
+export interface JsoncNode {
+	/** Value type. */
+	kind: "object" | "array" | "string" | "number" | "boolean" | "null";
+	/** Start of the value. */
+	start: number;
+	/** End of the value (exclusive). */
+	end: number;
+	/** Member name, for object members. */
+	key?: string;
+	/** Start of the member name, including quotes. */
+	keyStart?: number;
+	/** End of the member name (exclusive). */
+	keyEnd?: number;
+	/** Decoded value of strings, numbers, and booleans. */
+	value?: string | number | boolean;
+	/** Members or elements, for objects and arrays. */
+	children?: JsoncNode[];
+}
+
+/** Path to a value: member names and array indices. */
+export type JsoncPath = Array<string | number>;
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/** Parse JSONC/JSON5 text into a tree of nodes with source spans; throws with the line and column of a syntax error or of nesting deeper than 512 levels. */
+		parseJsonc(text: string): JsoncNode;
+		/** Set (`value` as JSON text) or remove (`value` omitted) the value at `path`, splicing only the affected span. */
+		editJsonc(text: string, path: JsoncPath, value?: string): string;
+	}
+}
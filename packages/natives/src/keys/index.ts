@@ -2,8 +2,40 @@
  * Keyboard sequence utilities powered by native bindings.
  */
 
+import type { BracketedPasteParser, KeyBindingSet } from "@oh-my-pi/pi-natives";
 import { native } from "../native";
 
-export type { KeyEventType, ParsedKittyResult } from "./types";
+export type {
+	BracketedPasteParser,
+	InputEvent,
+	KeyBindingSet,
+	KeyEventType,
+	ParsedKittyResult,
+	PasteSegment,
+	SplitInputResult,
+} from "./types";
 
-export const { matchesKittySequence, parseKey, matchesLegacySequence, parseKittySequence, matchesKey } = native;
+export const {
+	matchesKittySequence,
+	parseKey,
+	matchesLegacySequence,
+	parseKittySequence,
+	matchesKey,
+	splitInputEvents,
+} = native;
+
+/**
+ * Create a stateful bracketed paste parser. Pastes (`ESC[200~` ... `ESC[201~`) split across reads are returned
+ * as one segment once complete; call `flush()` after an input timeout to release a held-back partial marker.
+ */
+export function createBracketedPasteParser(): BracketedPasteParser {
+	return new native.BracketedPasteParser();
+}
+
+/**
+ * Compile key ids (e.g. "ctrl+c", "super+k") into a set matched in one native call per input, instead of
+ * calling `matchesKey` once per binding. Throws when an id names no key.
+ */
+export function createKeyBindingSet(keyIds: string[]): KeyBindingSet {
+	return new native.KeyBindingSet(keyIds);
+}
@@ -23,19 +23,65 @@ export interface ParsedKittyResult {
 	shiftedKey?: number;
 	/** Optional base layout key codepoint from the sequence. */
 	baseLayoutKey?: number;
-	/** Modifier bitmask (shift/alt/ctrl), excluding lock bits. */
+	/** Modifier bitmask (shift/alt/ctrl/super/hyper/meta), excluding lock bits. */
 	modifier: number;
 	/** Optional event type from the sequence. */
 	eventType?: KeyEventType;
 }
 
+/** One event split from a terminal input buffer. */
+export interface InputEvent {
+	/** Event kind. */
+	kind: "key" | "paste" | "mouse" | "unknown";
+	/** Raw input of the event; for pastes, the pasted text without markers. */
+	data: string;
+	/** Normalized key id (as returned by `parseKey`) for recognized keys. */
+	key?: string;
+}
+
+/** Events split from a terminal input buffer. */
+export interface SplitInputResult {
+	/** Complete events, in input order. */
+	events: InputEvent[];
+	/** Trailing incomplete sequence or paste, to prepend to the next chunk. */
+	rest: string;
+}
+
+/** A run of ordinary input or a complete bracketed paste. */
+export interface PasteSegment {
+	/** Segment kind. */
+	kind: "input" | "paste";
+	/** Input text, or the pasted text without markers. */
+	data: string;
+}
+
+/** Incremental bracketed paste parser; pastes and markers split across reads are buffered until complete. */
+export interface BracketedPasteParser {
+	/** Whether a paste has started but not yet ended. */
+	readonly inPaste: boolean;
+	/** Feed the next read; returns the input runs and pastes it completes, in order. */
+	push(chunk: string): PasteSegment[];
+	/** Release held-back input (e.g. a lone ESC after an input timeout); an unfinished paste keeps buffering. */
+	flush(): PasteSegment[];
+}
+
+/** Key ids compiled once and matched against input together. */
+export interface KeyBindingSet {
+	/** Number of bindings in the set. */
+	readonly size: number;
+	/** Index of the first binding the input matches, or null. */
+	match(data: string, kittyProtocolActive: boolean): number | null;
+	/** Indices of every binding the input matches, in binding order. */
+	matchAll(data: string, kittyProtocolActive: boolean): number[];
+}
+
 declare module "../bindings" {
 	interface NativeBindings {
 		/**
 		 * Match Kitty protocol sequences for a codepoint and modifier mask.
 		 * @param data Raw terminal input data.
 		 * @param expectedCodepoint Codepoint to compare against the parsed sequence.
-		 * @param expectedModifier Modifier mask (shift/alt/ctrl).
+		 * @param expectedModifier Modifier mask (shift/alt/ctrl/super/hyper/meta).
 		 * @returns True when the sequence matches the expected codepoint and modifiers.
 		 */
 		matchesKittySequence(data: string, expectedCodepoint: number, expectedModifier: number): boolean;
@@ -64,12 +110,25 @@ declare module "../bindings" {
 		/**
 		 * Match input data against a key identifier string.
 		 * Supports: escape, tab, enter, backspace, delete, home, end, space,
-		 * arrows (up/down/left/right), ctrl+X, shift+X, alt+X, combined modifiers.
+		 * arrows (up/down/left/right), menu, printScreen, keypad operators,
+		 * ctrl+X, shift+X, alt+X, super+X, hyper+X, meta+X, combined modifiers.
 		 * @param data Raw terminal input data.
 		 * @param keyId Key identifier string to match (e.g. "ctrl+c").
 		 * @param kittyProtocolActive Whether Kitty disambiguation is enabled.
 		 * @returns True when the input matches the key identifier.
 		 */
 		matchesKey(data: string, keyId: string, kittyProtocolActive: boolean): boolean;
+		/**
+		 * Split a terminal input buffer into key, bracketed paste, mouse, and unknown events.
+		 * @param data Raw terminal input data.
+		 * @param kittyActive Whether Kitty disambiguation is enabled.
+		 * @param flush Emit a trailing incomplete sequence instead of returning it in `rest`.
+		 * @returns Complete events and the unconsumed remainder.
+		 */
+		splitInputEvents(data: string, kittyActive: boolean, flush?: boolean): SplitInputResult;
+		/** BracketedPasteParser class constructor. */
+		BracketedPasteParser: new () => BracketedPasteParser;
+		/** KeyBindingSet class constructor; throws on an id with no key. */
+		KeyBindingSet: new (keyIds: string[]) => KeyBindingSet;
 	}
 }
@@ -0,0 +1,26 @@
+/**
+ * Indexed line access for large files.
+ */
+
+import { native } from "../native";
+import type { LineIndex } from "./types";
+
+export type { LineIndex } from "./types";
+
+/**
+ * Build (or reuse) the line offset index for a file.
+ *
+ * The index is cached per path and rebuilt only when the file's mtime or size
+ * changes, so goto-line and previews never rescan unchanged files.
+ */
+export async function buildLineIndex(path: string): Promise<LineIndex> {
+	return native.buildLineIndex(path);
+}
+
+/**
+ * Read up to `count` lines starting at 1-indexed `start`, seeking via the
+ * cached line index.
+ */
+export async function readFileLines(path: string, start: number, count: number): Promise<string[]> {
+	return native.readFileLines(path, start, count);
+}
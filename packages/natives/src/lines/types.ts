@@ -0,0 +1,31 @@
+/**
+ * Types for the large-file line index.
+ */
+
+/** Line offset index for a file, as returned by `buildLineIndex()`. */
+export interface LineIndex {
+	/** Number of lines in the file. */
+	get lineCount(): number;
+	/** File size in bytes when the index was built. */
+	get byteLength(): number;
+	/** File modification time (ms since epoch) when the index was built. */
+	get mtimeMs(): number;
+	/** Byte offset where 1-indexed `line` starts, or `null` when out of range. */
+	lineOffset(line: number): number | null;
+	/** 1-indexed line containing byte `offset`, or `null` when out of range. */
+	lineAt(offset: number): number | null;
+	/** Read up to `count` lines starting at 1-indexed `start`, without terminators. */
+	readLines(start: number, count: number): Promise<string[]>;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Build (or reuse) the line offset index for a file.
+		 * Cached per path until the file's mtime or size changes.
+		 */
+		buildLineIndex(path: string): Promise<LineIndex>;
+		/** Read up to `count` lines starting at 1-indexed `start` using the cached line index. */
+		readFileLines(path: string, start: number, count: number): Promise<string[]>;
+	}
+}
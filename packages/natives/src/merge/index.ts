@@ -0,0 +1,9 @@
+/**
+ * Three-way text merges with conflict markers.
+ */
+
+import { native } from "../native";
+
+export type { Merge3Options, Merge3Result, MergeConflict } from "./types";
+
+export const { merge3 } = native;
@@ -0,0 +1,64 @@
+/**
+ * Types for three-way text merges.
+ */
+
+/** Options for `merge3()`. */
+export interface Merge3Options {
+	/** Label after `<<<<<<<` (default: "ours"). */
+	oursLabel?: string;
+	/** Label after `|||||||` in diff3 style (default: "base"). */
+	baseLabel?: string;
+	/** Label after `>>>>>>>` (default: "theirs"). */
+	theirsLabel?: string;
+	/** Include the base text of conflicts between `|||||||` and `=======` (default: false). */
+	diff3?: boolean;
+}
+
+/** A region both sides changed differently. */
+export interface MergeConflict {
+	/** Line of the `<<<<<<<` marker in the merged content (1-based). */
+	startLine: number;
+	/** Line of the `>>>>>>>` marker in the merged content (1-based). */
+	endLine: number;
+	/** First conflicting line of the base (1-based). */
+	baseStart: number;
+	/** Conflicting lines of the base. */
+	baseLines: number;
+	/** First conflicting line of ours (1-based). */
+	oursStart: number;
+	/** Conflicting lines of ours. */
+	oursLines: number;
+	/** First conflicting line of theirs (1-based). */
+	theirsStart: number;
+	/** Conflicting lines of theirs. */
+	theirsLines: number;
+	/** Base text of the region. */
+	base: string;
+	/** Our text of the region. */
+	ours: string;
+	/** Their text of the region. */
+	theirs: string;
+}
+
+/** Result of `merge3()`. */
+export interface Merge3Result {
+	/** Merged content, with conflict markers around unresolved regions. */
+	content: string;
+	/** Whether the merge had no conflicts. */
+	clean: boolean;
+	/** Conflicts in content order. */
+	conflicts: MergeConflict[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Merge two edits of the same text line by line, like `git merge-file`.
+		 * @param base Common ancestor.
+		 * @param ours One edited copy.
+		 * @param theirs The other edited copy.
+		 * @param options Conflict marker options.
+		 */
+		merge3(base: string, ours: string, theirs: string, options?: Merge3Options): Merge3Result;
+	}
+}
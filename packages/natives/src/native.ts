@@ -13,18 +13,42 @@ import type { NativeBindings } from "./bindings";
 import { embeddedAddon } from "./embedded-addon";
 import "./appearance/types";
 import "./ast/types";
+import "./batch/types";
+import "./block/types";
+import "./changeset/types";
+import "./classify/types";
 import "./clipboard/types";
+import "./comments/types";
+import "./config/types";
+import "./expand/types";
+import "./files/types";
+import "./git/types";
 import "./glob/types";
 import "./grep/types";
+import "./hash/types";
 import "./highlight/types";
 import "./html/types";
 import "./image/types";
+import "./journal/types";
+import "./jsonc/types";
 import "./keys/types";
+import "./lines/types";
+import "./merge/types";
+import "./outline/types";
+import "./pack/types";
+import "./patch/types";
+import "./project/types";
 import "./ps/types";
 import "./projfs/types";
 import "./pty/types";
+import "./readonly/types";
+import "./scratch/types";
 import "./shell/types";
+import "./similar/types";
+import "./sort/types";
+import "./symbols/types";
 import "./text/types";
+import "./watch/types";
 import "./work/types";
 
 export type { NativeBindings, TsFunc } from "./bindings";
@@ -236,10 +260,12 @@ function loadNative(): NativeBindings {
 }
 function validateNative(bindings: NativeBindings, source: string): void {
 	const missing: string[] = [];
-	const checkFn = (name: keyof NativeBindings) => {
+	const checkFn = (name: keyof NativeBindings): boolean => {
 		if (typeof bindings[name] !== "function") {
 			missing.push(name);
+			return false;
 		}
+		return true;
 	};
 	checkFn("copyToClipboard");
 	checkFn("readImageFromClipboard");
@@ -259,9 +285,12 @@ function validateNative(bindings: NativeBindings, source: string): void {
 	checkFn("sliceWithWidth");
 	checkFn("extractSegments");
 	checkFn("matchesKittySequence");
-	checkFn("executeShell");
+	// Addons built without the `shell` cargo feature omit the embedded shell.
+	if (!checkFn("nativeFeatures") || bindings.nativeFeatures().includes("shell")) {
+		checkFn("executeShell");
+		checkFn("Shell");
+	}
 	checkFn("PtySession");
-	checkFn("Shell");
 	checkFn("parseKey");
 	checkFn("matchesLegacySequence");
 	checkFn("parseKittySequence");
@@ -0,0 +1,9 @@
+/**
+ * Per-file structured outline built on the tree-sitter grammars.
+ */
+
+import { native } from "../native";
+
+export type { OutlineNode } from "./types";
+
+export const { outline } = native;
@@ -0,0 +1,34 @@
+/**
+ * Types for per-file structured outlines.
+ */
+
+import type { SymbolKind } from "../symbols/types";
+
+/** A declaration in a file outline. */
+export interface OutlineNode {
+	/** Declaration name. */
+	name: string;
+	/** Declaration kind, as reported by `symbolIndex()`. */
+	kind: SymbolKind;
+	/** First line of the declaration (1-indexed). */
+	startLine: number;
+	/** First column of the declaration (1-indexed). */
+	startColumn: number;
+	/** Last line of the declaration (1-indexed, inclusive). */
+	endLine: number;
+	/** Column after the declaration's last character (1-indexed). */
+	endColumn: number;
+	/** Nested declarations in source order; members of Rust `impl` blocks and Go methods sit under their type. */
+	children: OutlineNode[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Build a hierarchical outline of a source file's declarations, for a collapsible outline panel.
+		 * @param path Source file.
+		 * @param lang Language override (default: inferred from the extension).
+		 */
+		outline(path: string, lang?: string): Promise<OutlineNode[]>;
+	}
+}
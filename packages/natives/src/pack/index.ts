@@ -0,0 +1,9 @@
+/**
+ * Token-budgeted packing of grep/astFind results for prompts.
+ */
+
+import { native } from "../native";
+
+export type { PackDropped, PackInput, PackResult, PackResultsOptions } from "./types";
+
+export const { packResults, estimateTokens } = native;
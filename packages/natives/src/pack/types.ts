@@ -0,0 +1,65 @@
+/**
+ * Types for token-budgeted result packing.
+ */
+
+import type { ContextLine } from "../grep/types";
+
+/** A `grep` or `astFind` match to pack; either shape is accepted. */
+export interface PackInput {
+	/** File path. */
+	path: string;
+	/** Matched line number (`grep`). */
+	lineNumber?: number;
+	/** Matched line (`grep`). */
+	line?: string;
+	/** First line of the match (`astFind`). */
+	startLine?: number;
+	/** Matched text, possibly several lines (`astFind`). */
+	text?: string;
+	/** Context lines before the match (`grep`). */
+	contextBefore?: ContextLine[];
+	/** Context lines after the match (`grep`). */
+	contextAfter?: ContextLine[];
+}
+
+/** Options for `packResults()`. */
+export interface PackResultsOptions {
+	/** Token budget for the packed text. */
+	maxTokens: number;
+	/** Model family the budget is for (e.g. "claude", "gpt-4o"; default: Claude). */
+	model?: string;
+}
+
+/** A match left out of the packed text. */
+export interface PackDropped {
+	/** File path. */
+	path: string;
+	/** Matched line number. */
+	line: number;
+}
+
+/** Result of `packResults()`. */
+export interface PackResult {
+	/** Matches grouped by file: the path, then `N:line` for matched lines and `N-line` for context. */
+	text: string;
+	/** Estimated tokens used by `text`. */
+	tokens: number;
+	/** Matches included (whole or trimmed). */
+	included: number;
+	/** Matches included with context or trailing lines removed. */
+	trimmed: number;
+	/** Matches that did not fit, in input order. */
+	dropped: PackDropped[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Greedily pack search results into text that fits a token budget, trimming context before dropping matches.
+		 * Token counts are estimated without a tokenizer and err on the high side.
+		 */
+		packResults(results: PackInput[], options: PackResultsOptions): PackResult;
+		/** Estimate the token count of `text` for a model family (default: Claude). */
+		estimateTokens(text: string, model?: string): number;
+	}
+}
@@ -0,0 +1,9 @@
+/**
+ * Unified patch application with fuzzy context matching.
+ */
+
+import { native } from "../native";
+
+export type { ApplyPatchOptions, ApplyPatchResult, PatchHunkResult, PatchHunkStatus } from "./types";
+
+export const { applyPatch } = native;
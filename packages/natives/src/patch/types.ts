@@ -0,0 +1,53 @@
+/**
+ * Types for applying unified patches.
+ */
+
+/** Options for `applyPatch()`. */
+export interface ApplyPatchOptions {
+	/** Context lines that may be ignored at each end of a hunk when it does not match exactly (default: 2; 0 requires exact context). */
+	fuzz?: number;
+	/** Compare lines ignoring differences in whitespace (default: false). */
+	ignoreWhitespace?: boolean;
+	/** Apply the hunks that match even when others fail (default: false, in which case a failed hunk leaves the content unchanged). */
+	allowPartial?: boolean;
+}
+
+/** How a hunk applied. */
+export type PatchHunkStatus = "exact" | "offset" | "fuzz" | "failed";
+
+/** Outcome of one hunk. */
+export interface PatchHunkResult {
+	/** The hunk's `@@` header line. */
+	header: string;
+	/** "exact" (at the line the header names), "offset" (elsewhere), "fuzz" (with context lines ignored), or "failed". */
+	status: PatchHunkStatus;
+	/** Line of the original content where the hunk applied (1-based). */
+	line?: number;
+	/** Lines between where the header placed the hunk and where it applied. */
+	offset: number;
+	/** Context lines ignored at each end to apply the hunk. */
+	fuzz: number;
+}
+
+/** Result of `applyPatch()`. */
+export interface ApplyPatchResult {
+	/** Patched content; unchanged when a hunk failed and `allowPartial` is off. */
+	content: string;
+	/** Whether every hunk applied. */
+	applied: boolean;
+	/** Per-hunk outcome, in patch order. */
+	hunks: PatchHunkResult[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Apply a single-file unified diff to `content`, tolerating shifted hunks and mismatched context.
+		 * @param content Text to patch.
+		 * @param patch Unified diff (`diff -u`, `git diff`) touching one file.
+		 * @param options Fuzz and matching options.
+		 * @throws When the patch is malformed, has no hunks, or touches more than one file.
+		 */
+		applyPatch(content: string, patch: string, options?: ApplyPatchOptions): ApplyPatchResult;
+	}
+}
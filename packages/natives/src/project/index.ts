@@ -0,0 +1,17 @@
+/**
+ * Project root discovery with a shared marker table, monorepo package discovery, and directory profiles.
+ */
+
+import { native } from "../native";
+
+export type {
+	DirProfileOptions,
+	DirStats,
+	ExtensionStats,
+	FileSize,
+	FindProjectRootOptions,
+	ProjectRoot,
+	WorkspacePackage,
+} from "./types";
+
+export const { findProjectRoot, discoverPackages, dirProfile } = native;
@@ -0,0 +1,100 @@
+/**
+ * Types for project root discovery and directory profiles.
+ */
+
+import type { Cancellable } from "../bindings";
+
+/** Options for `findProjectRoot()`. */
+export interface FindProjectRootOptions {
+	/** Marker file or directory names to look for (default: VCS directories and common manifests such as `package.json` and `Cargo.toml`). */
+	markers?: string[];
+	/** Return the outermost matching directory below the version-control root instead of the nearest one (default: false). */
+	outermost?: boolean;
+}
+
+/** A discovered project root. */
+export interface ProjectRoot {
+	/** Absolute path of the project root. */
+	root: string;
+	/** Markers present in `root`. */
+	markers: string[];
+	/** Project kinds implied by those markers (e.g. "git", "node", "rust"). */
+	kinds: string[];
+	/** Nearest enclosing version-control root, if any. */
+	vcsRoot?: string;
+}
+
+/** A member package of a monorepo workspace. */
+export interface WorkspacePackage {
+	/** Package name from its manifest (directory name when unnamed). */
+	name: string;
+	/** Package directory relative to the workspace root ("." for the root). */
+	path: string;
+	/** Ecosystem of the manifest. */
+	kind: "node" | "rust" | "go";
+}
+
+/** Options for `dirProfile()`. */
+export interface DirProfileOptions extends Cancellable {
+	/** Deepest directory level to report; the root is level 0 (default: 1). Deeper files count toward their ancestor. */
+	depth?: number;
+	/** Largest files to list per directory (default: 5). */
+	largest?: number;
+	/** Include hidden files (default: false). */
+	hidden?: boolean;
+	/** Respect .gitignore files (default: true). */
+	gitignore?: boolean;
+}
+
+/** Files with one extension. */
+export interface ExtensionStats {
+	/** Lowercase extension without the dot ("" for none). */
+	extension: string;
+	/** Number of files. */
+	files: number;
+	/** Total size in bytes. */
+	bytes: number;
+}
+
+/** A file and its size. */
+export interface FileSize {
+	/** Path relative to the profiled root. */
+	path: string;
+	/** Size in bytes. */
+	size: number;
+}
+
+/** Totals for one directory, including everything below it. */
+export interface DirStats {
+	/** Directory relative to the profiled root ("." for the root). */
+	path: string;
+	/** Number of files. */
+	files: number;
+	/** Total size in bytes. */
+	bytes: number;
+	/** File counts by extension, most common first. */
+	extensions: ExtensionStats[];
+	/** Largest files, largest first. */
+	largest: FileSize[];
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Walk up from `startPath` to the nearest directory holding a project marker, never past the first
+		 * version-control root. Returns `null` when no marker is found.
+		 * @throws When `startPath` does not exist.
+		 */
+		findProjectRoot(startPath: string, options?: FindProjectRootOptions): ProjectRoot | null;
+		/**
+		 * List a monorepo's member packages from `package.json` workspaces, `pnpm-workspace.yaml`, Cargo `[workspace]`,
+		 * and `go.work`, sorted by path.
+		 */
+		discoverPackages(root: string): Promise<WorkspacePackage[]>;
+		/**
+		 * Profile a tree from the shared scan cache: file counts by extension, total sizes, and largest files for the
+		 * root and each directory down to `depth`, sorted by path. `node_modules` is skipped.
+		 */
+		dirProfile(root: string, options?: DirProfileOptions): Promise<DirStats[]>;
+	}
+}
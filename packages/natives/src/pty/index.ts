@@ -4,7 +4,15 @@
 
 import { native } from "../native";
 
-export type { PtyRunResult, PtySessionConstructor, PtyStartOptions } from "./types";
+export type { ExitInfo, ExitKind } from "../bindings";
+export type {
+	PtyOutput,
+	PtyOutputOverflow,
+	PtyReadOutputOptions,
+	PtyRunResult,
+	PtySessionConstructor,
+	PtyStartOptions,
+} from "./types";
 
 export const { PtySession } = native;
 export type PtySession = import("./types").PtySession;
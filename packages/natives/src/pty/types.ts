@@ -2,7 +2,7 @@
  * Types for PTY-backed interactive execution.
  */
 
-import type { Cancellable, TsFunc } from "../bindings";
+import type { Cancellable, ExitInfo, TsFunc } from "../bindings";
 
 /**
  * Options for starting a command in a pseudo-terminal session.
@@ -18,18 +18,58 @@ export interface PtyStartOptions extends Cancellable {
 	cols?: number;
 	/** PTY row count. */
 	rows?: number;
+	/** Bytes of recent output kept for `readOutput()` (default: 1 MiB; 0 disables the buffer). */
+	outputBufferBytes?: number;
+	/**
+	 * When the output buffer is full: "dropOldest" (default) evicts old lines;
+	 * "block" stops reading the PTY until `readOutput()` consumes lines, stalling the command.
+	 */
+	outputOverflow?: PtyOutputOverflow;
+}
+
+/** Policy for a full PTY output buffer. */
+export type PtyOutputOverflow = "dropOldest" | "block";
+
+/**
+ * Options for reading buffered PTY output.
+ */
+export interface PtyReadOutputOptions {
+	/** First line to return (0-based, counted from the start of the run). Defaults to the last `maxLines` lines. */
+	fromLine?: number;
+	/** Maximum number of lines to return (default: 200). */
+	maxLines?: number;
+}
+
+/**
+ * Lines read from a PTY session's output buffer.
+ */
+export interface PtyOutput {
+	/** Lines without terminators; the last may be an unterminated line. */
+	lines: string[];
+	/** Line number of `lines[0]` (0-based). */
+	firstLine: number;
+	/** Line number to pass as `fromLine` to continue reading. */
+	nextLine: number;
+	/** Lines produced so far, including evicted ones. */
+	totalLines: number;
+	/** Oldest line number still retained. */
+	oldestLine: number;
+	/** Bytes evicted from the buffer so far. */
+	droppedBytes: number;
 }
 
 /**
  * Result of a PTY command run.
  */
 export interface PtyRunResult {
-	/** Exit code of the command, if available. */
+	/** Exit code of the command, if available (`128 + N` when killed by signal N). */
 	exitCode?: number;
 	/** Whether the command was cancelled by abort signal or kill request. */
 	cancelled: boolean;
 	/** Whether the command timed out. */
 	timedOut: boolean;
+	/** Structured exit status: signal name, core-dump flag, and classification. */
+	exit: ExitInfo;
 }
 
 /** Stateful PTY session instance. */
@@ -42,6 +82,11 @@ export interface PtySession {
 	resize(cols: number, rows: number): void;
 	/** Force-kill active command. */
 	kill(): void;
+	/**
+	 * Read buffered output of the current or last run (by default the last 200 lines).
+	 * Returned lines count as consumed for the "block" overflow policy.
+	 */
+	readOutput(options?: PtyReadOutputOptions): PtyOutput;
 }
 
 /** Native PTY session constructor. */
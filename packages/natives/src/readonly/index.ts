@@ -0,0 +1,18 @@
+/**
+ * Process-wide read-only switch for the native layer.
+ *
+ * While enabled, mutating exports (e.g. `astEdit` without `dryRun`) reject
+ * with an error whose message starts with `READ_ONLY:`.
+ */
+
+import { native } from "../native";
+
+/** Error message prefix for operations blocked by read-only mode. */
+export const READ_ONLY_PREFIX = "READ_ONLY:";
+
+export const { setReadOnly, isReadOnly } = native;
+
+/** Whether `err` was raised because read-only mode blocked a write. */
+export function isReadOnlyError(err: unknown): boolean {
+	return err instanceof Error && err.message.startsWith(READ_ONLY_PREFIX);
+}
@@ -0,0 +1,17 @@
+/**
+ * Types for the native read-only switch.
+ */
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Enable or disable read-only mode for all mutating native exports.
+		 * @param enabled Whether writes should be blocked.
+		 */
+		setReadOnly(enabled: boolean): void;
+		/** Whether read-only mode is currently enabled. */
+		isReadOnly(): boolean;
+	}
+}
+
+export {};
@@ -0,0 +1,7 @@
+/**
+ * Tracked scratch directories, removed on exit and swept when orphaned.
+ */
+
+import { native } from "../native";
+
+export const { createScratchDir, cleanupScratch } = native;
@@ -0,0 +1,22 @@
+/**
+ * Types for scratch-directory management.
+ */
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Create an empty scratch directory under the shared scratch root (`FS_SCRATCH_DIR`, default
+		 * `<tmp>/pi-natives-scratch`) and return its absolute path. It is removed when this JS environment shuts down.
+		 * Throws a `READ_ONLY:` error in read-only mode.
+		 * @param prefix Name prefix; characters other than letters, digits, `-`, and `_` are dropped (default: "scratch").
+		 */
+		createScratchDir(prefix?: string): string;
+		/**
+		 * Remove scratch directories left behind by exited processes that were not modified in the last `olderThanMs`
+		 * (default: one day). Directories of running processes, this one included, are never removed. Throws a `READ_ONLY:`
+		 * error in read-only mode.
+		 * @returns Paths of the removed directories.
+		 */
+		cleanupScratch(olderThanMs?: number): Promise<string[]>;
+	}
+}
@@ -2,7 +2,7 @@
  * Types for shell execution via brush-core.
  */
 
-import type { Cancellable, TsFunc } from "../bindings";
+import type { Cancellable, ExitInfo, TsFunc } from "../bindings";
 
 /**
  * Configuration for a persistent brush-core shell session.
@@ -36,6 +36,8 @@ export interface ShellRunResult {
 	cancelled: boolean;
 	/** Whether the command timed out. */
 	timedOut: boolean;
+	/** Structured exit status; shell codes `128 + N` are reported as signal N. */
+	exit: ExitInfo;
 }
 
 /**
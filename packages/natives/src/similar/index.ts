@@ -0,0 +1,9 @@
+/**
+ * Near-duplicate code search ("has this logic been implemented elsewhere?").
+ */
+
+import { native } from "../native";
+
+export type { FindSimilarCodeOptions, SimilarCodeMatch, SimilarCodeResult } from "./types";
+
+export const { findSimilarCode } = native;
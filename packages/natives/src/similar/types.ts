@@ -0,0 +1,50 @@
+/**
+ * Types for near-duplicate code search.
+ */
+
+import type { Cancellable } from "../bindings";
+
+/** Options for `findSimilarCode()`. */
+export interface FindSimilarCodeOptions extends Cancellable {
+	/** Directory to search (default: current directory). */
+	root?: string;
+	/** Only search files of this language (e.g. "typescript"); by default every file with a supported language extension is searched. */
+	lang?: string;
+	/** Glob filter for file paths relative to `root`. */
+	glob?: string;
+	/** Lowest similarity to report, between 0 and 1 (default: 0.6). */
+	minSimilarity?: number;
+	/** Maximum number of matches to return (default: 20). */
+	limit?: number;
+}
+
+/** A region similar to the snippet. */
+export interface SimilarCodeMatch {
+	/** File path relative to `root`, using forward slashes. */
+	path: string;
+	/** First line of the region (1-indexed). */
+	startLine: number;
+	/** Last line of the region (1-indexed, inclusive). */
+	endLine: number;
+	/** Estimated Jaccard similarity of the region's token shingles to the snippet's, between 0 and 1. */
+	similarity: number;
+}
+
+/** Result of `findSimilarCode()`. */
+export interface SimilarCodeResult {
+	/** Matches, most similar first. */
+	matches: SimilarCodeMatch[];
+	/** Number of files searched. */
+	filesSearched: number;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Find code similar to a snippet using shingled-token MinHash; whitespace and formatting are ignored.
+		 * @param snippet Code to look for (at least four tokens).
+		 * @param options Search root, language, and similarity threshold.
+		 */
+		findSimilarCode(snippet: string, options?: FindSimilarCodeOptions): Promise<SimilarCodeResult>;
+	}
+}
@@ -0,0 +1,41 @@
+/**
+ * Sorting and de-duplication of large result lists off the JS thread.
+ */
+
+import { native } from "../native";
+import type { SortOptions, SortStructuredOptions } from "./types";
+
+export type { SortOptions, SortStructuredOptions };
+
+export const { sortColumns, sortLines } = native;
+
+function keyColumn<T>(items: readonly T[], key: keyof T): string[] | Float64Array {
+	const numeric = items.some(item => typeof item[key] === "number");
+	if (numeric) {
+		return Float64Array.from(items, item => (typeof item[key] === "number" ? (item[key] as number) : Number.NaN));
+	}
+	return items.map(item => String(item[key] ?? ""));
+}
+
+/**
+ * Sort and optionally de-duplicate a newline-delimited buffer, a string list, or objects by `keys`.
+ * The sort is stable; with `unique`, the first of each run of equal keys is kept.
+ */
+export function sortStructured(buffer: Uint8Array, options?: SortOptions): Promise<string[]>;
+export function sortStructured(items: readonly string[], options?: SortOptions): Promise<string[]>;
+export function sortStructured<T extends object>(items: readonly T[], options: SortStructuredOptions<T>): Promise<T[]>;
+export async function sortStructured<T extends object>(
+	input: Uint8Array | readonly (string | T)[],
+	options?: SortStructuredOptions<T>,
+): Promise<(string | T)[]> {
+	const { keys, ...sortOptions } = options ?? {};
+	if (input instanceof Uint8Array) {
+		return native.sortLines(input, sortOptions);
+	}
+	const columns =
+		keys && keys.length > 0
+			? keys.map(key => keyColumn(input as readonly T[], key))
+			: [input.map(item => String(item))];
+	const order = await native.sortColumns(columns, sortOptions);
+	return Array.from(order, index => input[index]);
+}
@@ -0,0 +1,34 @@
+/**
+ * Types for native sorting and de-duplication.
+ */
+
+/** Options for `sortColumns()` and `sortLines()`. */
+export interface SortOptions {
+	/** Drop rows whose keys all equal an earlier row's (default: false). */
+	unique?: boolean;
+	/** Compare strings with root-locale collation (case and Latin accents folded first) instead of code points (default: false). */
+	locale?: boolean;
+	/** Sort in descending order (default: false). */
+	descending?: boolean;
+}
+
+/** Options for `sortStructured()` on objects. */
+export interface SortStructuredOptions<T> extends SortOptions {
+	/** Fields to sort by, most significant first. Numeric fields compare as numbers; missing numbers sort last. */
+	keys?: Array<keyof T & string>;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Sort rows given as parallel key columns and return the kept row indices in order.
+		 * @param columns Key columns of equal length, most significant first.
+		 */
+		sortColumns(columns: Array<string[] | Float64Array>, options?: SortOptions): Promise<Uint32Array>;
+		/**
+		 * Sort the lines of a newline-delimited UTF-8 buffer.
+		 * @param buffer Lines separated by `\n` or `\r\n`; a trailing newline adds no empty line.
+		 */
+		sortLines(buffer: Uint8Array, options?: SortOptions): Promise<string[]>;
+	}
+}
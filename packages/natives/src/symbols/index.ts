@@ -0,0 +1,9 @@
+/**
+ * Symbol index ("goto symbol") and per-file syntax statistics built on the tree-sitter grammars.
+ */
+
+import { native } from "../native";
+
+export type { FileSyntaxStats, SymbolIndexOptions, SymbolIndexResult, SymbolInfo, SymbolKind } from "./types";
+
+export const { symbolIndex, fileSyntaxStats } = native;
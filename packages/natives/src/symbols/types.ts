@@ -0,0 +1,102 @@
+/**
+ * Types for the tree-sitter symbol index.
+ */
+
+import type { Cancellable } from "../bindings";
+
+/** Options for `symbolIndex()`. */
+export interface SymbolIndexOptions extends Cancellable {
+	/** Only index files of this language (e.g. "rust"); by default every file with a supported language extension is indexed. */
+	lang?: string;
+	/** Glob filter for file paths relative to the search root. */
+	glob?: string;
+	/** Only return symbols of these kinds (e.g. `["function", "class"]`). */
+	kinds?: SymbolKind[];
+	/** Fuzzy query matched against symbol names; results are ranked by score. */
+	query?: string;
+	/** Maximum number of symbols to return (default: 1000). */
+	limit?: number;
+}
+
+/** Kind of a declared symbol. */
+export type SymbolKind =
+	| "function"
+	| "method"
+	| "class"
+	| "struct"
+	| "enum"
+	| "interface"
+	| "trait"
+	| "type"
+	| "const"
+	| "variable"
+	| "module"
+	| "macro";
+
+/** A declared symbol. */
+export interface SymbolInfo {
+	/** Symbol name. */
+	name: string;
+	/** Declaration kind. */
+	kind: SymbolKind;
+	/** File path relative to the search root, using forward slashes. */
+	path: string;
+	/** Enclosing class, trait, impl target, or module, for members. */
+	container?: string;
+	/** First line of the declaration (1-indexed). */
+	startLine: number;
+	/** First column of the declaration (1-indexed). */
+	startColumn: number;
+	/** Last line of the declaration (1-indexed, inclusive). */
+	endLine: number;
+	/** Column after the declaration's last character (1-indexed). */
+	endColumn: number;
+	/** Fuzzy match score for `query` (higher is better). */
+	score?: number;
+}
+
+/** Result of `symbolIndex()`. */
+export interface SymbolIndexResult {
+	/** Symbols in path and line order, or by score with `query`. */
+	symbols: SymbolInfo[];
+	/** Symbols found before `limit` was applied. */
+	totalSymbols: number;
+	/** Number of files parsed. */
+	filesIndexed: number;
+}
+
+/** Syntax statistics of one file, from `fileSyntaxStats()`. */
+export interface FileSyntaxStats {
+	/** Language the file was parsed as, or its extension when only its comment syntax is known. */
+	lang: string;
+	/** Number of lines. */
+	lines: number;
+	/** Functions and methods, including functions bound to variables. */
+	functions: number;
+	/** Classes, structs, enums, interfaces, and traits. */
+	classes: number;
+	/** Imported modules: one per `import`/`use`/`#include`, or per spec in a Go import group. */
+	imports: number;
+	/** Comments containing TODO, FIXME, XXX, or HACK. */
+	todos: number;
+	/** Deepest nesting of blocks and bodies (0 for a file without any). */
+	maxDepth: number;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * List the functions, classes, types, and constants declared in a file or directory tree.
+		 * @param path File or directory to index.
+		 * @param options Language, glob, kind filter, and fuzzy query.
+		 */
+		symbolIndex(path: string, options?: SymbolIndexOptions): Promise<SymbolIndexResult>;
+		/**
+		 * Count a file's functions, classes, imports, TODO comments, and maximum block nesting in one parse.
+		 * Files without a grammar but with a known comment syntax (see `registerCommentSyntax()`) get line and TODO counts only.
+		 * @param path Source file.
+		 * @param lang Language override (default: inferred from the extension).
+		 */
+		fileSyntaxStats(path: string, lang?: string): Promise<FileSyntaxStats>;
+	}
+}
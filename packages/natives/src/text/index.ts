@@ -2,11 +2,48 @@
  * ANSI-aware text utilities powered by native bindings.
  */
 
-import { Ellipsis, type ExtractSegmentsResult, type SliceWithWidthResult } from "@oh-my-pi/pi-natives";
+import {
+	type BidiDirection,
+	type BidiLine,
+	type ColumnizeOptions,
+	type ColumnRange,
+	Ellipsis,
+	type ExtractSegmentsResult,
+	type FitSegmentsOptions,
+	type FitSegmentsResult,
+	type GraphemeCluster,
+	type HighlightMatchesOptions,
+	type SliceWithWidthResult,
+	type StripAnsiResult,
+	type StripAnsiStream,
+	type TextAlign,
+	type UnicodeVersionInfo,
+	type WrapWindowOptions,
+	type WrapWindowResult,
+} from "@oh-my-pi/pi-natives";
 import { getDefaultTabWidth } from "@oh-my-pi/pi-utils";
 import { native } from "../native";
 
-export type { ExtractSegmentsResult, SliceWithWidthResult } from "./types";
+export type {
+	BidiDirection,
+	BidiLine,
+	BidiRun,
+	ColumnizeOptions,
+	ColumnRange,
+	ExtractSegmentsResult,
+	FitSegmentsOptions,
+	FitSegmentsResult,
+	GraphemeCluster,
+	HighlightMatchesOptions,
+	SliceWithWidthResult,
+	StripAnsiResult,
+	StripAnsiStream,
+	TextAlign,
+	TextWidthPolicy,
+	UnicodeVersionInfo,
+	WrapWindowOptions,
+	WrapWindowResult,
+} from "./types";
 export { Ellipsis } from "./types";
 
 /**
@@ -30,12 +67,82 @@ export function truncateToWidth(
 	return native.truncateToWidth(text, maxWidth, ellipsis, pad, tabWidth);
 }
 
+/**
+ * Fit breadcrumb segments into a total width, separators included. The widest
+ * segments are middle-truncated first ("comp…nts"), never below `minPerSegment`.
+ * Widths are grapheme-aware (emoji count as two cells) and ignore ANSI codes.
+ *
+ * @param segments - Segments to fit, optionally containing ANSI escape codes
+ * @param totalWidth - Visible width available for the joined text
+ * @param options - Separator (default: "/"), minimum segment width, and tab width
+ * @returns Joined text, the fitted segments, and the resulting width
+ */
+export function fitSegments(
+	segments: string[],
+	totalWidth: number,
+	options: FitSegmentsOptions = {},
+): FitSegmentsResult {
+	return native.fitSegments(segments, totalWidth, { ...options, tabWidth: options.tabWidth ?? getDefaultTabWidth() });
+}
+
+/**
+ * Truncate many texts like {@link truncateToWidth} in a single native call.
+ *
+ * @param texts - Texts to truncate (may contain ANSI codes)
+ * @param maxWidth - Maximum visible width
+ * @param ellipsis - Ellipsis kind to append when truncating (default: Unicode "…")
+ * @param pad - If true, pad each result with spaces to exactly maxWidth (default: false)
+ * @returns Truncated texts, in input order
+ */
+export function truncateToWidthBatch(
+	texts: string[],
+	maxWidth: number,
+	ellipsis: Ellipsis = Ellipsis.Unicode,
+	pad = false,
+	tabWidth = getDefaultTabWidth(),
+): string[] {
+	return native.truncateToWidthBatch(texts, maxWidth, ellipsis, pad, tabWidth);
+}
+
+/**
+ * Pad or truncate text to exactly `width` visible cells. Text wider than `width` is truncated with "…".
+ * Widths are grapheme-aware and ignore ANSI codes.
+ *
+ * @param text - Input text, optionally containing ANSI escape codes
+ * @param width - Visible width of the result
+ * @param align - Side the text sits on; "center" puts the odd cell on the right (default: "left")
+ * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @returns Text occupying exactly `width` cells
+ */
+export function padToWidth(
+	text: string,
+	width: number,
+	align: TextAlign = "left",
+	tabWidth = getDefaultTabWidth(),
+): string {
+	return native.padToWidth(text, width, align, tabWidth);
+}
+
+/**
+ * Lay out table rows as lines, padding or truncating each cell to its column width. Missing cells are blank
+ * and cells past the last column width are dropped.
+ *
+ * @param rows - Rows of cells, optionally containing ANSI escape codes
+ * @param widths - Visible width of each column
+ * @param options - Column separator (default: " "), per-column alignment, and tab width
+ * @returns One line per row
+ */
+export function columnize(rows: string[][], widths: number[], options: ColumnizeOptions = {}): string[] {
+	return native.columnize(rows, widths, { ...options, tabWidth: options.tabWidth ?? getDefaultTabWidth() });
+}
+
 /**
  * Slice a range of visible columns from a line.
  * @param line - The line to slice
  * @param startCol - The starting column
  * @param length - The length of the slice
  * @param strict - Whether to strictly enforce the length
+ * @param tabStops - Advance tabs to the next tab stop instead of a fixed width (default: false)
  * @returns The sliced line
  */
 export function sliceWithWidth(
@@ -44,9 +151,10 @@ export function sliceWithWidth(
 	length: number,
 	strict = false,
 	tabWidth = getDefaultTabWidth(),
+	tabStops = false,
 ): SliceWithWidthResult {
 	if (length <= 0) return { text: "", width: 0 };
-	return native.sliceWithWidth(line, startCol, length, strict, tabWidth);
+	return native.sliceWithWidth(line, startCol, length, strict, tabWidth, tabStops);
 }
 
 /**
@@ -55,10 +163,99 @@ export function sliceWithWidth(
  * @param text - Input text, optionally containing ANSI escape codes
  * @param width - Maximum visible width per output line
  * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @param tabStops - Expand tabs to the next tab stop instead of a fixed width (default: false)
  * @returns Wrapped lines with ANSI state preserved across breaks
  */
-export function wrapTextWithAnsi(text: string, width: number, tabWidth = getDefaultTabWidth()): string[] {
-	return native.wrapTextWithAnsi(text, width, tabWidth);
+export function wrapTextWithAnsi(
+	text: string,
+	width: number,
+	tabWidth = getDefaultTabWidth(),
+	tabStops = false,
+): string[] {
+	return native.wrapTextWithAnsi(text, width, tabWidth, tabStops);
+}
+
+/**
+ * Wrap many texts like {@link wrapTextWithAnsi} in a single native call.
+ *
+ * @param texts - Input texts, optionally containing ANSI escape codes
+ * @param width - Maximum visible width per output line
+ * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @returns Wrapped lines of each text, in input order
+ */
+export function wrapTextWithAnsiBatch(texts: string[], width: number, tabWidth = getDefaultTabWidth()): string[][] {
+	return native.wrapTextWithAnsiBatch(texts, width, tabWidth);
+}
+
+/**
+ * Wrap text like {@link wrapTextWithAnsi} but return only the lines in a
+ * viewport window, plus the total wrapped line count.
+ *
+ * The line layout is cached natively by content hash, so scrolling a large
+ * transcript only re-wraps the paragraphs in view.
+ *
+ * @param text - Input text, optionally containing ANSI escape codes
+ * @param width - Maximum visible width per output line
+ * @param options - Window start/size and tab width (default: configured tab width)
+ * @returns Wrapped lines in the window with ANSI state preserved
+ */
+export function wrapWindow(text: string, width: number, options: WrapWindowOptions = {}): WrapWindowResult {
+	return native.wrapWindow(text, width, { ...options, tabWidth: options.tabWidth ?? getDefaultTabWidth() });
+}
+
+/**
+ * Wrap text like {@link wrapTextWithAnsi} and split each line into bidi
+ * (UAX #9) level runs so RTL text can be reordered per line.
+ *
+ * @param text - Input text, optionally containing ANSI escape codes
+ * @param width - Maximum visible width per output line
+ * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @param direction - Paragraph direction; "auto" detects it from the first strong character
+ * @returns Wrapped lines with paragraph direction and logical-order runs
+ */
+export function wrapTextWithBidi(
+	text: string,
+	width: number,
+	tabWidth = getDefaultTabWidth(),
+	direction: BidiDirection | "auto" = "auto",
+): BidiLine[] {
+	return native.wrapTextWithBidi(text, width, tabWidth, direction);
+}
+
+/**
+ * Highlight regex matches in an already-styled string.
+ *
+ * Matching runs on the visible text, so matches may span style changes. The
+ * styles active in the original string are restored after each highlight.
+ *
+ * @param text - Input text, optionally containing ANSI escape codes
+ * @param pattern - Regex pattern matched against the visible text
+ * @param options - Highlight SGR (default: inverse video) and case sensitivity
+ * @returns Text with highlight codes inserted around each match
+ */
+export function highlightMatches(text: string, pattern: string, options?: HighlightMatchesOptions): string {
+	return native.highlightMatches(text, pattern, options);
+}
+
+/**
+ * Highlight visible-column ranges in an already-styled line.
+ *
+ * A wide character is highlighted whole when any of its cells is in a range. The styles active in the original
+ * line are restored after each range, so nested styles are preserved.
+ *
+ * @param line - Input line, optionally containing ANSI escape codes
+ * @param ranges - Column ranges to highlight; may be unsorted or overlap
+ * @param sgr - Highlight SGR, a full sequence or its parameters (default: inverse video)
+ * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @returns Line with highlight codes inserted around each range
+ */
+export function highlightRanges(
+	line: string,
+	ranges: ColumnRange[],
+	sgr?: string,
+	tabWidth = getDefaultTabWidth(),
+): string {
+	return native.highlightRanges(line, ranges, sgr, tabWidth);
 }
 
 /**
@@ -66,10 +263,34 @@ export function wrapTextWithAnsi(text: string, width: number, tabWidth = getDefa
  *
  * @param text - Input text, optionally containing ANSI escape codes
  * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @param tabStops - Advance tabs to the next tab stop instead of a fixed width (default: false)
  * @returns Visible width in terminal cells
  */
-export function visibleWidth(text: string, tabWidth = getDefaultTabWidth()): number {
-	return native.visibleWidth(text, tabWidth);
+export function visibleWidth(text: string, tabWidth = getDefaultTabWidth(), tabStops = false): number {
+	return native.visibleWidth(text, tabWidth, tabStops);
+}
+
+/**
+ * Expand tabs to spaces, advancing each to the next tab stop like a terminal does. Columns count from the start
+ * of each line and ignore ANSI escape codes.
+ *
+ * @param text - Input text, optionally containing ANSI escape codes
+ * @param tabWidth - Distance between tab stops (default: configured tab width)
+ * @returns Text without tabs
+ */
+export function expandTabs(text: string, tabWidth = getDefaultTabWidth()): string {
+	return native.expandTabs(text, tabWidth);
+}
+
+/**
+ * Measure visible terminal width of many lines in a single native call.
+ *
+ * @param lines - Input lines, optionally containing ANSI escape codes
+ * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @returns Visible width of each line, in input order
+ */
+export function visibleWidthBatch(lines: string[], tabWidth = getDefaultTabWidth()): Uint32Array {
+	return native.visibleWidthBatch(lines, tabWidth);
 }
 
 /**
@@ -95,3 +316,46 @@ export function extractSegments(
 }
 
 export const { sanitizeText } = native;
+
+/**
+ * Strip ANSI escape sequences and map each remaining code unit back to its index in `text`, so hits found by
+ * searching the plain text can be highlighted in the styled string.
+ *
+ * @param text - Input text, optionally containing ANSI escape codes
+ * @returns Plain text and the original index of each of its code units
+ */
+export function stripAnsi(text: string): StripAnsiResult {
+	return native.stripAnsi(text);
+}
+
+/**
+ * Create a stateful ANSI stripper for streamed output (e.g. session logs). Escape sequences split across chunks
+ * are held back until complete, so no fragments reach the cleaned text; call `finish()` at the end of the stream.
+ */
+export function createStripAnsiStream(): StripAnsiStream {
+	return new native.StripAnsiStream();
+}
+
+/**
+ * Split text into grapheme clusters with their widths and emoji classification
+ * (ZWJ sequence, skin tone, flag). ANSI escape sequences are skipped; offsets
+ * index the original string.
+ *
+ * @param text - Input text, optionally containing ANSI escape codes
+ * @param tabWidth - Width used when measuring tab characters (default: configured tab width)
+ * @returns Grapheme clusters in order
+ */
+export function graphemeInfo(text: string, tabWidth = getDefaultTabWidth()): GraphemeCluster[] {
+	return native.graphemeInfo(text, tabWidth);
+}
+
+/** Unicode versions of the pinned segmentation and width tables used by all width functions. */
+export function unicodeVersion(): UnicodeVersionInfo {
+	return native.unicodeVersion();
+}
+
+/**
+ * Choose how every text function measures characters terminals disagree on: East Asian ambiguous-width
+ * characters (1 or 2 cells) and emoji presentation sequences (2 or 1). Match the terminal so columns align.
+ */
+export const { setWidthPolicy, getWidthPolicy } = native;
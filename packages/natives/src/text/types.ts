@@ -22,6 +22,150 @@ export interface ExtractSegmentsResult {
 	afterWidth: number;
 }
 
+/** Options for `wrapWindow`. */
+export interface WrapWindowOptions {
+	/** First wrapped line to return (0-indexed, default: 0). */
+	firstLine?: number;
+	/** Maximum number of wrapped lines to return (default: all remaining). */
+	lineCount?: number;
+	/** Width used when measuring tab characters. */
+	tabWidth?: number;
+}
+
+/** A window of wrapped lines. */
+export interface WrapWindowResult {
+	/** Wrapped lines in the requested window. */
+	lines: string[];
+	/** Index of `lines[0]` in the full wrapped output (clamped to `totalLines`). */
+	firstLine: number;
+	/** Wrapped line count of the whole text. */
+	totalLines: number;
+}
+
+/** Options for `fitSegments`. */
+export interface FitSegmentsOptions {
+	/** Text placed between segments (default: "/"). */
+	separator?: string;
+	/** Width no segment is truncated below, unless it is narrower already (default: 1). */
+	minPerSegment?: number;
+	/** Width used when measuring tab characters. */
+	tabWidth?: number;
+}
+
+/** Segments fitted to a total width. */
+export interface FitSegmentsResult {
+	/** Fitted segments joined by the separator. */
+	text: string;
+	/** Each segment after fitting, in input order. */
+	segments: string[];
+	/** Visible width of `text`. */
+	width: number;
+}
+
+/** Horizontal alignment for `padToWidth` and `columnize`. */
+export type TextAlign = "left" | "right" | "center";
+
+/** Options for `columnize`. */
+export interface ColumnizeOptions {
+	/** Text placed between columns (default: " "). */
+	separator?: string;
+	/** Alignment of each column (default: "left"). */
+	align?: TextAlign[];
+	/** Width used when measuring tab characters. */
+	tabWidth?: number;
+}
+
+/** A range of visible columns, `start` inclusive and `end` exclusive. */
+export interface ColumnRange {
+	/** First column of the range. */
+	start: number;
+	/** Column just past the range. */
+	end: number;
+}
+
+/** Options for `highlightMatches`. */
+export interface HighlightMatchesOptions {
+	/** Highlight SGR: a full sequence (`"\x1b[43m"`) or its parameters (`"1;33"`). Default: inverse video. */
+	style?: string;
+	/** Case-insensitive matching. */
+	ignoreCase?: boolean;
+}
+
+/** Bidi direction of a paragraph or run. */
+export type BidiDirection = "ltr" | "rtl";
+
+/** A run of characters sharing one resolved bidi embedding level. */
+export interface BidiRun {
+	/** Start offset in the line (UTF-16 code units, inclusive). */
+	start: number;
+	/** End offset in the line (UTF-16 code units, exclusive). */
+	end: number;
+	/** Resolved embedding level (odd levels are right-to-left). */
+	level: number;
+	/** Run direction. */
+	direction: BidiDirection;
+}
+
+/** A wrapped line with its bidi runs in logical order. */
+export interface BidiLine {
+	/** Line content with ANSI codes preserved. */
+	text: string;
+	/** Paragraph direction. */
+	direction: BidiDirection;
+	/** Level runs covering the whole line; ANSI codes belong to the run of the following character. */
+	runs: BidiRun[];
+}
+
+/** A grapheme cluster with its position, width, and emoji classification. */
+export interface GraphemeCluster {
+	/** Start offset (UTF-16 code units, inclusive). */
+	start: number;
+	/** End offset (UTF-16 code units, exclusive). */
+	end: number;
+	/** Visible width in terminal cells, as used by every width function. */
+	width: number;
+	/** Cluster renders as an emoji. */
+	emoji: boolean;
+	/** Cluster joins several emoji with ZERO WIDTH JOINER (e.g. families). */
+	zwjSequence: boolean;
+	/** Cluster carries a skin tone modifier. */
+	skinTone: boolean;
+	/** Cluster is a flag (regional indicator pair or tag sequence). */
+	flag: boolean;
+}
+
+/** Unicode versions of the pinned segmentation and width tables. */
+export interface UnicodeVersionInfo {
+	/** Grapheme cluster segmentation tables (UAX #29). */
+	segmentation: string;
+	/** East Asian width and emoji width tables. */
+	width: string;
+}
+
+/** Cells for characters terminals disagree on; omitted fields keep their value. */
+export interface TextWidthPolicy {
+	/** Cells for East Asian ambiguous-width characters such as `±`: 1 (default) or 2, as terminals in CJK locales draw them. */
+	ambiguous?: 1 | 2;
+	/** Cells for emoji presentation sequences such as `❤️` (a character followed by U+FE0F): 2 (default) or 1. */
+	emojiPresentation?: 1 | 2;
+}
+
+/** ANSI-stripped text with the original index of each of its code units. */
+export interface StripAnsiResult {
+	/** Text with escape sequences removed. */
+	text: string;
+	/** Original UTF-16 index of each code unit of `text`; `start..end` maps to `indices[start]..indices[end - 1] + 1`. */
+	indices: Uint32Array;
+}
+
+/** Incremental ANSI stripper for streamed output; sequences split across chunks are held back until complete. */
+export interface StripAnsiStream {
+	/** Strip ANSI/OSC sequences from the next chunk; a trailing partial sequence is completed by the following push. */
+	push(chunk: string): string;
+	/** End the stream, dropping any unterminated sequence; the stream can be reused afterwards. */
+	finish(): string;
+}
+
 /** Ellipsis strategy for truncation. */
 export const enum Ellipsis {
 	/** Use a single Unicode ellipsis character ("…"). */
@@ -42,17 +186,66 @@ declare module "../bindings" {
 		 * @param pad Whether to pad the output to `maxWidth`.
 		 */
 		truncateToWidth(text: string, maxWidth: number, ellipsisKind: number, pad: boolean, tabWidth?: number): string;
+		/**
+		 * Truncate many texts to a visible width in one call.
+		 * @param texts UTF-16 input texts with optional ANSI escapes.
+		 */
+		truncateToWidthBatch(
+			texts: string[],
+			maxWidth: number,
+			ellipsisKind: number,
+			pad: boolean,
+			tabWidth?: number,
+		): string[];
+		/**
+		 * Fit breadcrumb segments into a total width, middle-truncating the widest segments first.
+		 * @param segments UTF-16 segments with optional ANSI escapes.
+		 * @param totalWidth Width available for all segments and separators.
+		 */
+		fitSegments(segments: string[], totalWidth: number, options?: FitSegmentsOptions): FitSegmentsResult;
+		/** Pad or truncate text to exactly `width` visible cells. */
+		padToWidth(text: string, width: number, align?: TextAlign, tabWidth?: number): string;
+		/** Lay out rows as lines with each cell padded or truncated to its column width. */
+		columnize(rows: string[][], widths: number[], options?: ColumnizeOptions): string[];
 		/**
 		 * Sanitize text output: strip ANSI codes, remove binary garbage, normalize line endings.
 		 */
 		sanitizeText(text: string): string;
+		/** Strip ANSI escape sequences, mapping each remaining code unit to its original index. */
+		stripAnsi(text: string): StripAnsiResult;
+		/** StripAnsiStream class constructor. */
+		StripAnsiStream: new () => StripAnsiStream;
 
 		/**
 		 * Wrap text to a visible width, preserving ANSI codes across line breaks.
 		 * @param text UTF-16 input text with optional ANSI escapes.
 		 * @param width Maximum visible width per line.
 		 */
-		wrapTextWithAnsi(text: string, width: number, tabWidth?: number): string[];
+		wrapTextWithAnsi(text: string, width: number, tabWidth?: number, tabStops?: boolean): string[];
+		/**
+		 * Wrap many texts in one call, returning the wrapped lines of each.
+		 * @param texts UTF-16 input texts with optional ANSI escapes.
+		 * @param width Maximum visible width per line.
+		 */
+		wrapTextWithAnsiBatch(texts: string[], width: number, tabWidth?: number): string[][];
+		/**
+		 * Wrap text like `wrapTextWithAnsi` but return only a window of lines plus the total count.
+		 * @param text UTF-16 input text with optional ANSI escapes.
+		 * @param width Maximum visible width per line.
+		 */
+		wrapWindow(text: string, width: number, options?: WrapWindowOptions): WrapWindowResult;
+		/**
+		 * Wrap text like `wrapTextWithAnsi` and segment each line into bidi level runs.
+		 * @param text UTF-16 input text with optional ANSI escapes.
+		 * @param width Maximum visible width per line.
+		 * @param direction Paragraph direction; "auto" detects it from the first strong character.
+		 */
+		wrapTextWithBidi(
+			text: string,
+			width: number,
+			tabWidth?: number,
+			direction?: BidiDirection | "auto",
+		): BidiLine[];
 		/**
 		 * Slice a range of visible columns from a line.
 		 * @param line UTF-16 input line with optional ANSI escapes.
@@ -66,12 +259,43 @@ declare module "../bindings" {
 			length: number,
 			strict: boolean,
 			tabWidth?: number,
+			tabStops?: boolean,
 		): SliceWithWidthResult;
 		/**
 		 * Measure the visible width of text (excluding ANSI codes).
 		 * @param text UTF-16 input text with optional ANSI escapes.
 		 */
-		visibleWidth(text: string, tabWidth?: number): number;
+		/**
+		 * Wrap regex matches in an already-styled string with a highlight SGR, restoring the outer styles after each match.
+		 * @param text UTF-16 input text with optional ANSI escapes.
+		 * @param pattern Regex matched against the visible text.
+		 */
+		highlightMatches(text: string, pattern: string, options?: HighlightMatchesOptions): string;
+		/**
+		 * Wrap visible-column ranges of an already-styled line with a highlight SGR, restoring the outer styles after each range.
+		 * @param line UTF-16 input line with optional ANSI escapes.
+		 * @param ranges Column ranges to highlight, in any order.
+		 */
+		highlightRanges(line: string, ranges: ColumnRange[], sgr?: string, tabWidth?: number): string;
+		visibleWidth(text: string, tabWidth?: number, tabStops?: boolean): number;
+		/** Expand tabs to spaces up to the next tab stop, counting columns from the start of each line. */
+		expandTabs(text: string, tabWidth?: number): string;
+		/**
+		 * Measure the visible width of many lines in one call (excluding ANSI codes).
+		 * @param lines UTF-16 input lines with optional ANSI escapes.
+		 */
+		visibleWidthBatch(lines: string[], tabWidth?: number): Uint32Array;
+		/**
+		 * Split text into grapheme clusters with widths and emoji classification (ANSI codes skipped).
+		 * @param text UTF-16 input text with optional ANSI escapes.
+		 */
+		graphemeInfo(text: string, tabWidth?: number): GraphemeCluster[];
+		/** Unicode versions of the pinned segmentation and width tables. */
+		unicodeVersion(): UnicodeVersionInfo;
+		/** Set how every text function measures ambiguous-width characters and emoji presentation sequences. */
+		setWidthPolicy(policy: TextWidthPolicy): void;
+		/** Current width policy of the text functions. */
+		getWidthPolicy(): Required<TextWidthPolicy>;
 		/** Extract before/after segments around an overlay region.
 		 * @param line UTF-16 input line with optional ANSI escapes.
 		 * @param beforeEnd Column where the "before" segment ends.
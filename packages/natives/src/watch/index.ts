@@ -0,0 +1,24 @@
+/**
+ * Filesystem watching that keeps the shared scan cache fresh.
+ */
+
+import * as path from "node:path";
+import { native } from "../native";
+import type { FsChange, FsWatcher, WatchRootOptions } from "./types";
+
+export type { FsChange, FsWatcher, WatchRootOptions } from "./types";
+
+/**
+ * Watch a directory so cache-enabled `glob`, `grep`, and `fd` calls never see scans older than the last change.
+ *
+ * Directories excluded by `.gitignore` and `.git` itself are not watched. Call `close()` on the returned watcher
+ * to stop.
+ */
+export function watchRoot(
+	root: string,
+	options?: WatchRootOptions,
+	onChange?: (changes: FsChange[]) => void,
+): FsWatcher {
+	const cb = onChange ? (err: Error | null, changes: FsChange[]) => !err && onChange(changes) : undefined;
+	return native.watchRoot(path.resolve(root), options, cb);
+}
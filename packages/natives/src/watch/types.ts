@@ -0,0 +1,45 @@
+/**
+ * Types for filesystem watching.
+ */
+
+import type { TsFunc } from "../bindings";
+
+/** A change below a watched root. */
+export interface FsChange {
+	/** Absolute path that changed. */
+	path: string;
+	/** Kind of change; "rescan" means events were lost and the whole root should be treated as changed. */
+	kind: "create" | "modify" | "remove" | "rescan";
+}
+
+/** Options for `watchRoot()`. */
+export interface WatchRootOptions {
+	/** Polling interval in milliseconds when the native backend cannot start (default 500). */
+	intervalMs?: number;
+	/** Quiet period in milliseconds before a batch of changes is delivered (default 50). */
+	debounceMs?: number;
+	/** Longest a change waits in milliseconds for a quiet period before its batch is delivered anyway (default 500). */
+	maxWaitMs?: number;
+}
+
+/** Handle for a running watcher. */
+export interface FsWatcher {
+	/** Canonical root being watched. */
+	readonly root: string;
+	/** Whether `close()` has been called. */
+	readonly closed: boolean;
+	/** Stop watching and drop cached scans for the root. */
+	close(): void;
+}
+
+declare module "../bindings" {
+	interface NativeBindings {
+		/**
+		 * Watch a directory and drop cached filesystem scans under it as soon as anything changes.
+		 * @param path Directory to watch.
+		 * @param options Polling, debounce, and max-wait settings.
+		 * @param onChange Optional callback receiving batches of changes after their scans were dropped.
+		 */
+		watchRoot(path: string, options?: WatchRootOptions, onChange?: TsFunc<FsChange[]>): FsWatcher;
+	}
+}
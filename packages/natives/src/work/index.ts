@@ -7,5 +7,7 @@
 
 import { native } from "../native";
 
-export type { WorkProfile } from "./types";
-export const { getWorkProfile } = native;
+export type { CancellationScope, TraceSpan } from "../bindings";
+export type { ConcurrencyPolicy, ConcurrencyStats, WorkProfile } from "./types";
+export const { getWorkProfile, CancellationScope, setConcurrencyPolicy, getConcurrencyPolicy, getConcurrencyStats } =
+	native;
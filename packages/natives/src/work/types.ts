@@ -2,6 +2,8 @@
  * Types for work scheduling profiling.
  */
 
+import type { CancellationScope } from "../bindings";
+
 /**
  * Profiling results from work scheduling instrumentation.
  */
@@ -18,6 +20,34 @@ export interface WorkProfile {
 	sampleCount: number;
 }
 
+/**
+ * Tuning for background work throttling. Omitted fields keep their current value.
+ */
+export interface ConcurrencyPolicy {
+	/** Throttle background work at all (default: true). */
+	enabled?: boolean;
+	/** Background work pauses while more interactive calls than this are queued or running (default: 0). */
+	maxInteractive?: number;
+	/** Background work pauses while the 1-minute load average per core exceeds this (default: 0.9). */
+	maxLoad?: number;
+	/** Longest single pause; background work then runs for the same time before pausing again (default: 250). */
+	maxPauseMs?: number;
+}
+
+/**
+ * Snapshot of scheduler pressure.
+ */
+export interface ConcurrencyStats {
+	/** Interactive calls queued or running. */
+	interactive: number;
+	/** Background calls (index builds, warmups) queued or running. */
+	background: number;
+	/** Last sampled 1-minute load average per core (0 where unavailable). */
+	load: number;
+	/** Total time background work has spent paused, in milliseconds. */
+	throttledMs: number;
+}
+
 declare module "../bindings" {
 	interface NativeBindings {
 		/**
@@ -27,5 +57,17 @@ declare module "../bindings" {
 		 * Call this to retrieve recent activity.
 		 */
 		getWorkProfile(lastSeconds: number): WorkProfile;
+
+		/** Tune how background work yields to interactive calls and system load. */
+		setConcurrencyPolicy(policy: ConcurrencyPolicy): void;
+
+		/** Current background work throttling policy, with every field set. */
+		getConcurrencyPolicy(): Required<ConcurrencyPolicy>;
+
+		/** In-flight work counts and load as seen by the background throttle. */
+		getConcurrencyStats(): ConcurrencyStats;
+
+		/** CancellationScope class constructor for grouping cancellable calls. */
+		CancellationScope: new () => CancellationScope;
 	}
 }
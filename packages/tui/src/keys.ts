@@ -143,31 +143,42 @@ type SpecialKey =
 	| "f9"
 	| "f10"
 	| "f11"
-	| "f12";
+	| "f12"
+	| "menu"
+	| "printScreen";
 
 type BaseKey = Letter | Digit | SymbolKey | SpecialKey;
 
+/** ctrl/shift/alt prefixes, in any order. */
+type BasicModifiers =
+	| "ctrl"
+	| "shift"
+	| "alt"
+	| "ctrl+shift"
+	| "shift+ctrl"
+	| "ctrl+alt"
+	| "alt+ctrl"
+	| "shift+alt"
+	| "alt+shift"
+	| "ctrl+shift+alt"
+	| "ctrl+alt+shift"
+	| "shift+ctrl+alt"
+	| "shift+alt+ctrl"
+	| "alt+ctrl+shift"
+	| "alt+shift+ctrl";
+
+/** super/hyper/meta prefixes, in the order `parseKey` emits them after ctrl/shift/alt. */
+type ExtraModifiers = "super" | "hyper" | "meta" | "super+hyper" | "super+meta" | "hyper+meta" | "super+hyper+meta";
+
 /**
  * Union type of all valid key identifiers.
  * Provides autocomplete and catches typos at compile time.
  */
 export type KeyId =
 	| BaseKey
-	| `ctrl+${BaseKey}`
-	| `shift+${BaseKey}`
-	| `alt+${BaseKey}`
-	| `ctrl+shift+${BaseKey}`
-	| `shift+ctrl+${BaseKey}`
-	| `ctrl+alt+${BaseKey}`
-	| `alt+ctrl+${BaseKey}`
-	| `shift+alt+${BaseKey}`
-	| `alt+shift+${BaseKey}`
-	| `ctrl+shift+alt+${BaseKey}`
-	| `ctrl+alt+shift+${BaseKey}`
-	| `shift+ctrl+alt+${BaseKey}`
-	| `shift+alt+ctrl+${BaseKey}`
-	| `alt+ctrl+shift+${BaseKey}`
-	| `alt+shift+ctrl+${BaseKey}`;
+	| `${BasicModifiers}+${BaseKey}`
+	| `${ExtraModifiers}+${BaseKey}`
+	| `${BasicModifiers}+${ExtraModifiers}+${BaseKey}`;
 
 // =============================================================================
 // Kitty Protocol Parsing